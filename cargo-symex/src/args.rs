@@ -49,6 +49,118 @@ pub struct Args {
     #[clap(short, long)]
     pub function: Option<String>,
 
+    /// Name of a shim generated by `symex_lib::symex_shim!` to run.
+    #[clap(long, conflicts_with = "function")]
+    pub shim: Option<String>,
+
+    /// List the shims generated by `symex_lib::symex_shim!` instead of running.
+    #[clap(long)]
+    pub list_shims: bool,
+
+    /// List the `symex_lib` intrinsics found in the binary instead of running.
+    #[clap(long)]
+    pub list_intrinsics: bool,
+
+    /// Maximum size in bytes of a symbol, larger symbols are only symbolic in
+    /// their low bytes and the other bytes are assumed to be zero.
+    #[clap(long)]
+    pub max_symbol_size: Option<u64>,
+
+    /// Store memory in separately allocated regions that are copied on write
+    /// instead of in a single SMT array, faster for memory heavy firmware.
+    #[clap(long)]
+    pub region_memory: bool,
+
+    /// Name the locals of functions that are stored on the stack after their
+    /// function and variable in the reported memory and symbols, using the
+    /// debug information.
+    #[clap(long)]
+    pub stack_locals: bool,
+
+    /// Maximum number of instructions executed on a path, longer paths are
+    /// truncated.
+    #[clap(long)]
+    pub max_instructions: Option<usize>,
+
+    /// Maximum number of paths to explore, the remaining paths are truncated.
+    #[clap(long)]
+    pub max_paths: Option<usize>,
+
+    /// Maximum total time in seconds spent in the solver, the remaining paths
+    /// are truncated once it is used.
+    #[clap(long)]
+    pub max_solver_time: Option<u64>,
+
+    /// Maximum number of iterations of each loop, paths that iterate a loop
+    /// more often are truncated.
+    #[clap(long)]
+    pub loop_bound: Option<usize>,
+
+    /// Print a worst case execution time report of all paths after the run,
+    /// with the worst path and the cycles spent in each region.
+    #[clap(long)]
+    pub wcet: bool,
+
+    /// Print the results of all paths as JSON instead of the path results,
+    /// for consumption by other tools.
+    #[clap(long)]
+    pub json: bool,
+
+    /// Keep running and analyze the elf file again each time it changes,
+    /// printing how the results changed since the previous run.
+    #[clap(long)]
+    pub watch: bool,
+
+    /// Export the worst case execution time of the function as a task to a
+    /// schedulability analyzer after the run, as CSV or JSON by the extension
+    /// of the file and as a MAST model otherwise.
+    #[clap(long)]
+    pub export_task: Option<PathBuf>,
+
+    /// Period of the exported task in cycles.
+    #[clap(long, requires = "export_task")]
+    pub period: Option<u64>,
+
+    /// Deadline of the exported task in cycles, the period if not set.
+    #[clap(long, requires = "export_task")]
+    pub deadline: Option<u64>,
+
+    /// Priority of the exported task, higher values preempt lower values.
+    #[clap(long, requires = "export_task")]
+    pub priority: Option<u32>,
+
+    /// Print the preconditions inferred from the ranges of the symbolic inputs
+    /// of the paths after the run, as candidate assumptions.
+    #[clap(long)]
+    pub preconditions: bool,
+
+    /// Summarize the calls of the function with the given name and reuse the
+    /// summary at later calls that read the same values, can be given more
+    /// than once.
+    #[clap(long)]
+    pub summarize: Vec<String>,
+
+    /// Replace the remaining iterations of loops that copy memory one element
+    /// per iteration with a single copy of at most the given number of bytes.
+    #[clap(long)]
+    pub accelerate_copies: Option<u64>,
+
+    /// Print the concrete values of the symbolic inputs that reproduce each
+    /// path after the run, with their bytes in memory order.
+    #[clap(long)]
+    pub inputs: bool,
+
+    /// Write a `#[test]` that reproduces each path to the given file after the
+    /// run, with the values of the symbolic inputs of the path.
+    #[clap(long)]
+    pub generate_tests: Option<PathBuf>,
+
+    /// Call of the code under test in each generated test, `{function}` is
+    /// replaced by the analyzed function and `{inputs}` by the inputs of the
+    /// path.
+    #[clap(long, default_value = "{function}({inputs})")]
+    pub test_call: String,
+
     #[clap(subcommand)]
     pub subcommand: Option<Subcommands>,
 
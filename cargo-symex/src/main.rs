@@ -97,13 +97,93 @@ fn run_elf(args: Args) -> Result<()> {
             format!("{}/{}", target_dir.to_str().unwrap(), target_name)
         }
     };
-    let function_name = match args.function {
-        Some(function) => function,
-        None => "main".to_owned(),
+    if args.list_shims {
+        for shim in symex::run_elf::list_shims(&path)? {
+            println!("{shim}");
+        }
+        return Ok(());
+    }
+    if args.list_intrinsics {
+        print!("{}", symex::run_elf::list_intrinsics(&path)?);
+        return Ok(());
+    }
+
+    let function_name = match (args.function, args.shim) {
+        (Some(function), _) => function,
+        (None, Some(shim)) => format!("{}{shim}", symex::run_elf::SHIM_PREFIX),
+        (None, None) => "main".to_owned(),
     };
     debug!("Starting analasys on target: {path}, function: {function_name}");
 
-    symex::run_elf::run_elf(&path, &function_name, true)?;
+    let options = symex::run_elf::ElfOptions {
+        show_path_results: !args.json,
+        max_symbol_size: args.max_symbol_size,
+        memory_model: if args.region_memory {
+            symex::general_assembly::MemoryModel::Region
+        } else {
+            symex::general_assembly::MemoryModel::Array
+        },
+        stack_locals: args.stack_locals,
+        infer_preconditions: args.preconditions,
+        summarized: args.summarize,
+        accelerate_copies: args.accelerate_copies,
+        budget: symex::general_assembly::budget::ExecutionBudget {
+            max_instructions: args.max_instructions,
+            max_paths: args.max_paths,
+            max_solver_time: args.max_solver_time.map(std::time::Duration::from_secs),
+            loop_bound: args.loop_bound,
+            ..Default::default()
+        },
+    };
+    if args.watch {
+        let options = symex::run_elf::ElfOptions {
+            show_path_results: false,
+            ..options
+        };
+        let watcher = symex::watch::Watcher::new(&path, &function_name, options);
+        watcher.watch(std::time::Duration::from_millis(500), |delta| match delta {
+            Ok(delta) => println!("{delta}"),
+            Err(err) => eprintln!("{err}"),
+        });
+    }
+    let results = symex::run_elf::run_elf_with_options(&path, &function_name, &options)?;
+    if args.json {
+        let report = symex::json::RunReport::new(&function_name, &results);
+        println!("{}", report.to_json());
+    }
+    if args.wcet {
+        println!("{}", symex::wcet::WcetReport::new(&results));
+    }
+    if let Some(file) = args.export_task {
+        let report = symex::wcet::WcetReport::new(&results);
+        let task = symex::schedulability::Task {
+            period: args.period,
+            deadline: args.deadline,
+            priority: args.priority,
+            ..symex::schedulability::Task::new(&function_name, &report)
+        };
+        let tasks = symex::schedulability::TaskSet { tasks: vec![task] };
+        let format = symex::schedulability::ExportFormat::from_path(&file);
+        std::fs::write(&file, tasks.export(format))?;
+    }
+    if args.preconditions {
+        println!(
+            "{}",
+            symex::preconditions::PreconditionReport::new(&results)
+        );
+    }
+    if args.inputs {
+        for result in &results {
+            println!("Path {}:", result.path);
+            for input in result.concrete_inputs() {
+                println!("    {input}");
+            }
+        }
+    }
+    if let Some(file) = args.generate_tests {
+        let generator = symex::test_gen::TestGenerator::new(&function_name).call(&args.test_call);
+        std::fs::write(file, generator.generate(&results))?;
+    }
     Ok(())
 }
 
@@ -1,4 +1,4 @@
-use super::symbolic;
+use super::{ignore_path, symbolic};
 
 pub trait Any {
     fn any() -> Self;
@@ -36,6 +36,52 @@ blanket_impl!(i64);
 blanket_impl!(i128);
 blanket_impl!(isize);
 
+blanket_impl!(f32);
+blanket_impl!(f64);
+
+impl Any for char {
+    #[inline(always)]
+    fn any() -> Self {
+        match char::from_u32(u32::any()) {
+            Some(c) => c,
+            None => ignore_path(),
+        }
+    }
+}
+
+impl<T: Any> Any for Option<T> {
+    #[inline(always)]
+    fn any() -> Self {
+        match bool::any() {
+            true => Some(T::any()),
+            false => None,
+        }
+    }
+}
+
+impl<T: Any + Copy, const N: usize> Any for [T; N] {
+    #[inline(always)]
+    fn any() -> Self {
+        core::array::from_fn(|_| T::any())
+    }
+}
+
+macro_rules! tuple_impl {
+    ( $($name: ident),+ ) => {
+        impl<$($name: Any),+> Any for ($($name,)+) {
+            #[inline(always)]
+            fn any() -> Self {
+                ($($name::any(),)+)
+            }
+        }
+    };
+}
+
+tuple_impl!(A);
+tuple_impl!(A, B);
+tuple_impl!(A, B, C);
+tuple_impl!(A, B, C, D);
+
 fn internal_any<T: Any>() -> T {
     unsafe {
         let mut a = core::mem::MaybeUninit::uninit();
@@ -0,0 +1,101 @@
+//! Build script helpers that generate shims for library functions.
+//!
+//! Add `symex_lib` with the `build` feature as a build dependency and describe the functions to
+//! analyze in the build script of the binary or example that is analyzed:
+//!
+//! ```rust,no_run
+//! // build.rs
+//! use symex_lib::build::{write_shims, Shim};
+//!
+//! fn main() {
+//!     write_shims(&[Shim::new("add", "mylib::checksum::add")
+//!         .arg("a", "u32")
+//!         .arg("b", "u32")
+//!         .returns("u32")])
+//!     .unwrap();
+//! }
+//! ```
+//!
+//! The shims are then included with [`include_shims!`](crate::include_shims).
+extern crate std;
+
+use std::{
+    env,
+    fmt::Write,
+    fs, io,
+    path::PathBuf,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// A shim for a single library function, see [`symex_shim!`](crate::symex_shim).
+#[derive(Debug, Clone)]
+pub struct Shim {
+    name: String,
+    function: String,
+    args: Vec<(String, String)>,
+    ret: Option<String>,
+}
+
+impl Shim {
+    /// Creates a shim exported as `symex_shim_<name>` that calls `function`, which is a path to
+    /// the function such as `mylib::checksum::add`.
+    pub fn new(name: &str, function: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            function: function.to_string(),
+            args: Vec::new(),
+            ret: None,
+        }
+    }
+
+    /// Adds an argument of type `ty`.
+    pub fn arg(mut self, name: &str, ty: &str) -> Self {
+        self.args.push((name.to_string(), ty.to_string()));
+        self
+    }
+
+    /// Sets the return type.
+    pub fn returns(mut self, ty: &str) -> Self {
+        self.ret = Some(ty.to_string());
+        self
+    }
+}
+
+/// Generates the source code of `shims`.
+pub fn generate_shims(shims: &[Shim]) -> String {
+    let mut source = String::from("symex_lib::symex_shim! {\n");
+    for shim in shims {
+        let args: Vec<String> = shim
+            .args
+            .iter()
+            .map(|(name, ty)| std::format!("{name}: {ty}"))
+            .collect();
+        let ret = match &shim.ret {
+            Some(ty) => std::format!(" -> {ty}"),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            source,
+            "    fn {}({}){} => {};",
+            shim.name,
+            args.join(", "),
+            ret,
+            shim.function
+        );
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// Writes the source code of `shims` to `symex_shims.rs` in `OUT_DIR` and returns the path to
+/// the file.
+///
+/// Must be called from a build script.
+pub fn write_shims(shims: &[Shim]) -> io::Result<PathBuf> {
+    let out_dir = env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?;
+    let path = PathBuf::from(out_dir).join("symex_shims.rs");
+    fs::write(&path, generate_shims(shims))?;
+    Ok(path)
+}
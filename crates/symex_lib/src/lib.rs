@@ -1,5 +1,8 @@
 #![no_std]
 mod any;
+#[cfg(feature = "build")]
+pub mod build;
+mod shim;
 
 use core::mem::size_of;
 
@@ -28,11 +31,43 @@ pub use valid_derive::Validate;
 /// ```
 #[inline(never)]
 pub fn assume(condition: bool) {
-    let mut condition = condition;
-    if condition {
-        black_box(&mut condition);
-    } else {
-        suppress_path();
+    symex_assume(condition);
+}
+
+#[doc(hidden)]
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn symex_assume(mut condition: bool) {
+    black_box(&mut condition);
+}
+
+/// Assert the condition.
+///
+/// Every path where the condition can be false is reported as a failure, the paths where it is
+/// true continue. When not running under symex this panics if the condition is false.
+///
+/// # Example
+///
+/// ```rust
+/// # use symex_lib::{any, assert};
+/// fn foo() {
+///     let x: u8 = any();
+///     // Reported as a failure for x == 255.
+///     assert(x.checked_add(1).is_some());
+/// }
+/// ```
+#[inline(never)]
+pub fn assert(condition: bool) {
+    symex_assert(condition);
+}
+
+#[doc(hidden)]
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn symex_assert(mut condition: bool) {
+    black_box(&mut condition);
+    if !condition {
+        core::panic!("symex assertion failed")
     }
 }
 
@@ -66,6 +101,53 @@ pub fn suppress_path() {
     core::panic!()
 }
 
+/// Marks a location that no path may reach.
+///
+/// Any path that calls this function is reported as a failure.
+///
+/// # Example
+/// ```rust
+/// # use symex_lib::{symbolic, symex_unreachable};
+/// # fn foo() {
+/// #   let mut x: u8 = 0;
+/// #   symbolic(&mut x);
+/// if x > 200 && x < 100 {
+///     // Reported as a failure if any path gets here.
+///     symex_unreachable();
+/// }
+/// # }
+/// ```
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn symex_unreachable() {
+    let mut s: u8 = 0;
+    black_box(&mut s);
+}
+
+/// Marks a location that at least one path must reach.
+///
+/// An error is reported if the analysis finishes without any path calling
+/// this function.
+///
+/// # Example
+/// ```rust
+/// # use symex_lib::{symbolic, symex_must_reach};
+/// # fn foo() {
+/// #   let mut x: u8 = 0;
+/// #   symbolic(&mut x);
+/// if x == 42 {
+///     // The analysis fails if no path gets here.
+///     symex_must_reach();
+/// }
+/// # }
+/// ```
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn symex_must_reach() {
+    let mut s: u16 = 0;
+    black_box(&mut s);
+}
+
 #[inline(never)]
 pub fn start_cyclecount() {
     let mut s: i32 = 0;
@@ -156,11 +238,22 @@ pub fn ignore_path() -> ! {
     unsafe { core::hint::unreachable_unchecked() }
 }
 
+/// Version of the intrinsics in this crate.
+///
+/// The engine reads this to detect binaries built against a version of this crate with intrinsics
+/// it does not hook. It must be incremented whenever an intrinsic is added, renamed or changes
+/// signature.
+#[no_mangle]
+#[used]
+pub static SYMEX_INTRINSICS_VERSION: u32 = 2;
+
 /// Try and trick the optimizer.
 ///
 /// It is hard to create a "can be anything" value in pure rust, this function tries to trick the
 /// optimizer into not optimizing `value`.
 #[doc(hidden)]
 pub fn black_box<T>(value: &mut T) {
+    // Keeps the version in every binary that uses an intrinsic.
+    let _ = unsafe { core::ptr::read_volatile(&SYMEX_INTRINSICS_VERSION) };
     *value = unsafe { core::ptr::read_volatile(value as *mut T) }
 }
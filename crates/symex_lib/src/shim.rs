@@ -0,0 +1,54 @@
+/// Exposes library functions as ELF symbols that symex can use as entry functions.
+///
+/// Each shim is an `extern "C"` function exported as `symex_shim_<name>` that forwards its
+/// arguments to the library function. Run the analysis on a shim with
+/// `cargo symex --elf --shim <name>`.
+///
+/// The shims must be expanded in the binary or example that is analyzed, library crates are only
+/// linked in as far as they are used. The `build` module, enabled by the `build` feature, generates
+/// the shims from a build script.
+///
+/// # Example
+///
+/// ```rust
+/// mod checksum {
+///     pub fn add(a: u32, b: u32) -> u32 {
+///         a.wrapping_add(b)
+///     }
+/// }
+///
+/// symex_lib::symex_shim! {
+///     // Exported as `symex_shim_add`.
+///     fn add(a: u32, b: u32) -> u32 => checksum::add;
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! symex_shim {
+    ($(fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? => $function:path;)*) => {
+        $(
+            const _: () = {
+                #[allow(improper_ctypes_definitions)]
+                #[export_name = concat!("symex_shim_", stringify!($name))]
+                #[inline(never)]
+                extern "C" fn $name($($arg: $ty),*) $(-> $ret)? {
+                    $function($($arg),*)
+                }
+            };
+        )*
+    };
+}
+
+/// Includes the shims generated by `build::write_shims` in a build script.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// symex_lib::include_shims!();
+/// ```
+#[macro_export]
+macro_rules! include_shims {
+    () => {
+        include!(concat!(env!("OUT_DIR"), "/symex_shims.rs"));
+    };
+}
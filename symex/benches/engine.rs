@@ -0,0 +1,273 @@
+//! Baseline performance of the engine.
+//!
+//! Measures decoder throughput, executed instructions per second, the cost of
+//! forking paths, the memory models and the end to end time of analyzing
+//! firmware. Run with
+//!
+//! ```shell
+//! cargo bench -p symex
+//! ```
+//!
+//! Save a baseline with `-- --save-baseline <name>` before a change and compare
+//! against it with `-- --baseline <name>` after the change.
+//!
+//! The end to end benchmarks analyze the release builds of the
+//! `armv6-m-examples`, build them with `cargo build --release --examples` in
+//! that directory first. Another directory of elf files can be used by setting
+//! `SYMEX_BENCH_FIXTURES`, missing files are skipped.
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use symex::{
+    general_assembly::{
+        arch::{
+            arm::{v6::ArmV6M, v7::ArmV7EM},
+            riscv::rv32i::RiscV32I,
+            Arch,
+        },
+        project::{PCHook, Project},
+        state::GAState,
+        vm::VM,
+        Endianness,
+        MemoryModel,
+        WordSize,
+    },
+    memory::{Memory, SmtMap},
+    run_elf::run_elf,
+    smt::{DContext, DSolver},
+};
+
+/// Thumb instructions covering data processing, memory accesses, stack
+/// operations and branches.
+const THUMB: &[u16] = &[
+    0x2064, // movs r0, #100
+    0x3801, // subs r0, #1
+    0xD1FD, // bne #-6
+    0x0840, // lsrs r0, r0, #1
+    0xD2FF, // bcs #-2
+    0x1888, // adds r0, r1, r2
+    0x6848, // ldr r0, [r1, #4]
+    0x6048, // str r0, [r1, #4]
+    0xB510, // push {r4, lr}
+    0xBD10, // pop {r4, pc}
+];
+
+/// RV32I instructions covering data processing, memory accesses and branches.
+const RV32I: &[u32] = &[
+    0xFFF5_0513, // addi a0, a0, -1
+    0x00B5_2633, // slt a2, a0, a1
+    0x40B5_5533, // sra a0, a0, a1
+    0x0080_00EF, // jal ra, 8
+    0xFEB5_6CE3, // bltu a0, a1, -8
+    0x0041_2583, // lw a1, 4(sp)
+    0x00B1_2423, // sw a1, 8(sp)
+    0x0000_8067, // jalr x0, 0(ra)
+];
+
+/// Number of iterations of the counting loop in [`executor`].
+const ITERATIONS: u16 = 200;
+
+/// Number of words written by the benchmarks in [`memory`].
+const WORDS: u64 = 256;
+
+/// Elf files and functions analyzed by [`end_to_end`].
+const FIXTURES: &[(&str, &str)] = &[
+    ("get_sign", "get_sign"),
+    ("test_functions", "simple_loop"),
+    ("test_functions", "panic_test_core"),
+];
+
+fn context() -> DContext {
+    thread_local! {
+        static CONTEXT: DContext = DContext::new();
+    }
+    CONTEXT.with(DContext::clone)
+}
+
+/// Creates a project with `program` at address 0 that ends successfully when
+/// reaching the end of the program.
+fn project<A: Arch>(program: &[u16]) -> Arc<Project<A>> {
+    let code: Vec<u8> = program.iter().flat_map(|half| half.to_le_bytes()).collect();
+    let end = code.len() as u64;
+    let mut pc_hooks = HashMap::new();
+    pc_hooks.insert(end, PCHook::EndSuccess);
+    Arc::new(Project::manual_project(
+        code,
+        0,
+        end,
+        WordSize::Bit32,
+        Endianness::Little,
+        HashMap::new(),
+        pc_hooks,
+        HashMap::new(),
+        HashMap::new(),
+        HashMap::new(),
+        vec![],
+        HashMap::new(),
+        vec![],
+    ))
+}
+
+/// Creates a vm at the start of `project` with `R0` set to `r0`.
+fn vm(project: &Arc<Project<ArmV6M>>, r0: Option<u64>) -> VM<ArmV6M> {
+    let ctx = context();
+    let mut state = GAState::create_test_state(
+        project.clone(),
+        &ctx,
+        DSolver::new(&ctx),
+        0,
+        0x2000_1000,
+        ArmV6M {},
+    );
+    let r0 = match r0 {
+        Some(value) => ctx.from_u64(value, 32),
+        None => ctx.unconstrained(32, "r0"),
+    };
+    state.set_register("R0".to_owned(), r0).unwrap();
+    VM::new_with_state(project.clone(), state)
+}
+
+/// Runs all paths of `vm` to completion and returns the number of paths.
+fn explore(vm: &mut VM<ArmV6M>) -> usize {
+    let mut paths = 0;
+    while vm.run().unwrap().is_some() {
+        paths += 1;
+    }
+    paths
+}
+
+/// Translates the instructions in `code` one after another.
+fn decode_all<A: Arch>(architecture: &A, code: &[u8]) {
+    let mut offset = 0;
+    while offset < code.len() {
+        let instruction = architecture
+            .translate(&code[offset..], false)
+            .expect("Malformed instruction");
+        offset += instruction.instruction_size as usize / 8;
+    }
+}
+
+fn decoder(c: &mut Criterion) {
+    let thumb: Vec<u8> = THUMB.iter().flat_map(|half| half.to_le_bytes()).collect();
+    let rv32i: Vec<u8> = RV32I.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+    let mut group = c.benchmark_group("decoder");
+    group.throughput(Throughput::Elements(THUMB.len() as u64));
+    group.bench_function("armv6-m", |b| b.iter(|| decode_all(&ArmV6M {}, &thumb)));
+    group.bench_function("armv7-em", |b| b.iter(|| decode_all(&ArmV7EM {}, &thumb)));
+    group.throughput(Throughput::Elements(RV32I.len() as u64));
+    group.bench_function("rv32i", |b| {
+        b.iter(|| decode_all(&RiscV32I::default(), &rv32i))
+    });
+    group.finish();
+}
+
+fn executor(c: &mut Criterion) {
+    // movs r0, #ITERATIONS; loop: subs r0, #1; bne loop
+    let project = project::<ArmV6M>(&[0x2000 | ITERATIONS, 0x3801, 0xD1FD]);
+    let instructions = 1 + 2 * ITERATIONS as u64;
+
+    let mut group = c.benchmark_group("executor");
+    group.throughput(Throughput::Elements(instructions));
+    group.bench_function("counting loop", |b| {
+        b.iter_batched(
+            || vm(&project, Some(0)),
+            |mut vm| explore(&mut vm),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn fork(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fork");
+    for depth in [2, 4, 6] {
+        // Each `lsrs r0, r0, #1; bcs next` forks on the next bit of r0.
+        let program: Vec<u16> = (0..depth).flat_map(|_| [0x0840, 0xD2FF]).collect();
+        let project = project::<ArmV6M>(&program);
+        group.throughput(Throughput::Elements(1 << depth));
+        group.bench_function(BenchmarkId::new("paths", 1 << depth), |b| {
+            b.iter_batched(
+                || vm(&project, None),
+                |mut vm| explore(&mut vm),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Creates a memory in `model` with [`WORDS`] words written.
+fn written_memory(model: MemoryModel) -> Memory {
+    let ctx = context();
+    let mut memory = Memory::new(model, &[], &ctx, 32, Endianness::Little);
+    for word in 0..WORDS {
+        let addr = ctx.from_u64(0x2000_0000 + word * 4, 32);
+        memory.write(&addr, ctx.from_u64(word, 32)).unwrap();
+    }
+    memory
+}
+
+fn memory(c: &mut Criterion) {
+    let ctx = context();
+    let models = [
+        ("array", MemoryModel::Array),
+        ("region", MemoryModel::Region),
+    ];
+
+    let mut group = c.benchmark_group("memory");
+    group.throughput(Throughput::Elements(WORDS));
+    for (name, model) in models {
+        group.bench_function(BenchmarkId::new("write", name), |b| {
+            b.iter(|| written_memory(model))
+        });
+        let memory = written_memory(model);
+        group.bench_function(BenchmarkId::new("read", name), |b| {
+            b.iter(|| {
+                for word in 0..WORDS {
+                    let addr = ctx.from_u64(0x2000_0000 + word * 4, 32);
+                    memory.read(&addr, 32).unwrap().simplify();
+                }
+            })
+        });
+    }
+
+    // A forked path writes a single word.
+    group.throughput(Throughput::Elements(1));
+    for (name, model) in models {
+        let memory = written_memory(model);
+        let addr = ctx.from_u64(0x2000_0000, 32);
+        group.bench_function(BenchmarkId::new("fork", name), |b| {
+            b.iter(|| {
+                let mut fork = memory.clone();
+                fork.write(&addr, ctx.from_u64(1, 32)).unwrap();
+                fork
+            })
+        });
+    }
+    group.finish();
+}
+
+fn end_to_end(c: &mut Criterion) {
+    let fixtures = std::env::var_os("SYMEX_BENCH_FIXTURES")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| "../armv6-m-examples/target/thumbv6m-none-eabi/release/examples".into());
+
+    let mut group = c.benchmark_group("end to end");
+    group.sample_size(10);
+    for (file, function) in FIXTURES {
+        let path = fixtures.join(file);
+        if !path.exists() {
+            eprintln!("Skipping {function}, {} does not exist", path.display());
+            continue;
+        }
+        group.bench_function(format!("{file}/{function}"), |b| {
+            b.iter(|| run_elf(&path, function, false).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decoder, executor, fork, memory, end_to_end);
+criterion_main!(benches);
@@ -0,0 +1,154 @@
+//! Defines the parts of an architecture that are independent of the symbolic
+//! execution engine.
+//!
+//! An [`InstructionSet`] describes the machine code of an architecture, such
+//! as the size of its instructions and its registers, and [`ParseError`] is
+//! returned by decoders that translate machine code in to general assembly
+//! [`Operation`](crate::operation::Operation)s.
+
+use alloc::string::String;
+use core::{
+    fmt::{self, Display},
+    ops::Range,
+};
+
+use crate::jump_table::JumpTable;
+
+#[derive(Debug, Eq, PartialEq, PartialOrd, Clone)]
+/// Errors that occur while decoding an instruction.
+pub enum ParseError {
+    /// Input not long enough for an instruction.
+    InsufficientInput,
+
+    /// 32 bit instruction not long enough.
+    MalfromedInstruction,
+
+    /// Opcode not matching valid 32 bit instruction.
+    InvalidInstruction,
+
+    /// This instruction causes unpredictable behaviour.
+    Unpredictable,
+
+    /// Trying to access an invalid register.
+    InvalidRegister,
+
+    /// Invalid condition code used.
+    InvalidCondition,
+
+    /// A generic parsing error.
+    Generic(&'static str),
+
+    /// The instruction decoded correctly but can not be translated yet.
+    Unsupported(&'static str),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InsufficientInput => write!(f, "Insufficient input"),
+            Self::MalfromedInstruction => write!(f, "Tried to parse a malformed instruction."),
+            Self::InvalidInstruction => write!(f, "Instruction not supported in the parser."),
+            Self::Unpredictable => write!(f, "Instruction defined as unpredictable."),
+            Self::InvalidRegister => write!(f, "Parser encountered an invalid register."),
+            Self::InvalidCondition => write!(f, "Parser encountered an invalid condition."),
+            Self::Generic(msg) => write!(f, "{msg}"),
+            Self::Unsupported(instruction) => {
+                write!(f, "Instruction {instruction} is not supported.")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// Instruction set state of the processor, selected by the T bit of the
+/// execution program status register on ARM processors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ExecutionState {
+    /// Executing Thumb instructions, the T bit is set.
+    Thumb,
+
+    /// Executing ARM instructions, the T bit is clear.
+    Arm,
+}
+
+/// Describes the machine code and the registers of an architecture.
+///
+/// Only the size of the instructions is required, the remaining methods
+/// default to the architecture not describing the property.
+pub trait InstructionSet {
+    /// Returns the size in bits of the instruction at the start of `buff`
+    /// without translating it. Used to step over instructions that can not be
+    /// translated.
+    fn instruction_size(&self, buff: &[u8]) -> Option<u32>;
+
+    /// Returns the names of the registers defined by the architecture, `None`
+    /// if the architecture does not declare its registers.
+    fn registers(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Returns the names of the flags defined by the architecture, `None` if
+    /// the architecture defines flags dynamically.
+    fn flags(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Returns the role of `register` in the calling convention of the
+    /// architecture, such as holding an argument, `None` if it has no role or
+    /// the architecture does not describe its calling convention.
+    fn register_role(&self, _register: &str) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the registers a function returns its value in, least
+    /// significant first, `None` if the architecture does not describe its
+    /// calling convention.
+    fn return_registers(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Returns the registers the first arguments of a function are passed in,
+    /// in order, `None` if the architecture does not describe its calling
+    /// convention.
+    fn argument_registers(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Returns the registers a call may change, `None` if the architecture
+    /// does not describe its calling convention.
+    fn caller_saved_registers(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Returns the addresses of the system control space of the processor,
+    /// where the interrupt controller is mapped, `None` if the architecture
+    /// does not have one.
+    fn system_region(&self) -> Option<Range<u64>> {
+        None
+    }
+
+    /// Returns the name of the register with the dwarf register number
+    /// `number`, `None` if the number is not mapped.
+    fn dwarf_register(&self, _number: u16) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the name of the opcode of the instruction at the start of
+    /// `buff`, `None` if it can not be decoded or the architecture does not
+    /// name its opcodes.
+    fn mnemonic(&self, _buff: &[u8]) -> Option<String> {
+        None
+    }
+
+    /// Returns the jump table read by the instruction at the start of `buff`,
+    /// `None` if the instruction does not read one.
+    fn jump_table(&self, _buff: &[u8]) -> Option<JumpTable> {
+        None
+    }
+
+    /// Returns true if the processor can execute instructions in `state`.
+    fn supports_execution_state(&self, _state: ExecutionState) -> bool {
+        true
+    }
+}
@@ -0,0 +1,44 @@
+//! Describes the compiler generated jump tables read by an instruction.
+//!
+//! The symbolic execution engine recovers the bounds of the table and forks
+//! one path per entry.
+
+use alloc::string::String;
+
+/// A table branch decoded from an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpTable {
+    /// Register that holds the address of the table, `None` if the table
+    /// follows the instruction.
+    pub base: Option<String>,
+
+    /// Register that holds the index into the table.
+    pub index: String,
+
+    /// Number of bytes the index is multiplied with to get the offset of the
+    /// entry.
+    pub scale: u64,
+
+    /// Format of the entries.
+    pub entry: TableEntry,
+}
+
+/// Format of the entries of a [`JumpTable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableEntry {
+    /// Entries of the given number of bytes holding the number of halfwords to
+    /// branch forward from the end of the instruction, as in `TBB` and `TBH`.
+    Offset(u64),
+
+    /// Entries of the given number of bytes holding the address to branch to.
+    Address(u64),
+}
+
+impl TableEntry {
+    /// Size of an entry in bytes.
+    pub const fn size(&self) -> u64 {
+        match self {
+            Self::Offset(size) | Self::Address(size) => *size,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! Core definitions shared between [Symex](../../) and the crates around it.
+//!
+//! Re-exports the [general assembly](general_assembly) language and defines
+//! the parts of an architecture that do not depend on the symbolic execution
+//! engine, see [`InstructionSet`](arch::InstructionSet). The crate is `no_std`
+//! and only requires [`alloc`], so decoders and embedded side helper crates
+//! can share these types without pulling in the solver or the object file
+//! parsers.
+
+#![no_std]
+#![deny(warnings)]
+#![deny(clippy::all)]
+#![deny(missing_docs)]
+#![deny(rustdoc::all)]
+
+extern crate alloc;
+
+pub mod arch;
+pub mod jump_table;
+
+pub use general_assembly::{condition, hazard, operand, operation, prelude, shift, text, verify};
@@ -0,0 +1,441 @@
+//! Pipeline resources used by [`Operation`] sequences.
+//!
+//! The [`Hazards`] of an instruction describe the register and flag ports it
+//! reads and writes, whether it accesses memory and which functional unit it
+//! occupies. Timing models use them to find the stalls between dependent
+//! instructions without knowing the instruction set the operations were
+//! translated from.
+
+use alloc::{borrow::ToOwned, collections::BTreeSet, string::String};
+
+use crate::{condition::Condition, operand::Operand, operation::Operation};
+
+/// A register or flag that an instruction reads or writes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Port {
+    /// A register of the core.
+    Register(String),
+
+    /// A flag of the core.
+    Flag(String),
+}
+
+/// The functional unit that decides the latency of an instruction, ordered
+/// from the most to the least significant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HazardClass {
+    /// Writes the program counter.
+    Branch,
+
+    /// Reads from memory.
+    Load,
+
+    /// Writes to memory.
+    Store,
+
+    /// Divides.
+    Divide,
+
+    /// Multiplies.
+    Multiply,
+
+    /// Any other operation.
+    Alu,
+}
+
+/// The pipeline resources used by an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hazards {
+    /// Ports read by the instruction.
+    pub reads: BTreeSet<Port>,
+
+    /// Ports written by the instruction.
+    pub writes: BTreeSet<Port>,
+
+    /// True if the instruction reads from memory.
+    pub loads: bool,
+
+    /// True if the instruction writes to memory.
+    pub stores: bool,
+
+    /// The functional unit used by the instruction.
+    pub class: HazardClass,
+}
+
+impl Hazards {
+    /// Collects the resources used by the `operations` of an instruction.
+    ///
+    /// The program counter is the register `PC`, any operation that writes it
+    /// or jumps makes the instruction a branch. Locals are internal to the
+    /// instruction and are not ports.
+    pub fn of(operations: &[Operation]) -> Self {
+        let mut hazards = Self {
+            reads: BTreeSet::new(),
+            writes: BTreeSet::new(),
+            loads: false,
+            stores: false,
+            class: HazardClass::Alu,
+        };
+        let mut branch = false;
+        let mut divide = false;
+        let mut multiply = false;
+        for operation in operations {
+            hazards.operation(operation);
+            match operation {
+                Operation::ConditionalJump { .. } => branch = true,
+                Operation::SDiv { .. } | Operation::UDiv { .. } => divide = true,
+                Operation::Mul { .. } => multiply = true,
+                _ => {}
+            }
+        }
+        branch |= hazards.writes.contains(&Port::Register("PC".to_owned()));
+        hazards.class = if branch {
+            HazardClass::Branch
+        } else if hazards.loads {
+            HazardClass::Load
+        } else if hazards.stores {
+            HazardClass::Store
+        } else if divide {
+            HazardClass::Divide
+        } else if multiply {
+            HazardClass::Multiply
+        } else {
+            HazardClass::Alu
+        };
+        hazards
+    }
+
+    /// Checks if the instruction reads a port that `earlier` writes.
+    pub fn depends_on(&self, earlier: &Hazards) -> bool {
+        !self.reads.is_disjoint(&earlier.writes)
+    }
+
+    fn read(&mut self, operand: &Operand) {
+        match operand {
+            Operand::Register(name) => {
+                self.reads.insert(Port::Register(name.clone()));
+            }
+            Operand::Flag(name) => {
+                self.reads.insert(Port::Flag(name.clone()));
+            }
+            Operand::Address(..) | Operand::AddressInLocal(..) => self.loads = true,
+            Operand::AddressWithOffset { offset_reg, .. } => {
+                self.reads.insert(Port::Register(offset_reg.clone()));
+                self.loads = true;
+            }
+            Operand::Immediate(_) | Operand::Local(_) => {}
+        }
+    }
+
+    fn write(&mut self, operand: &Operand) {
+        match operand {
+            Operand::Register(name) => {
+                self.writes.insert(Port::Register(name.clone()));
+            }
+            Operand::Flag(name) => {
+                self.writes.insert(Port::Flag(name.clone()));
+            }
+            Operand::Address(..) | Operand::AddressInLocal(..) => self.stores = true,
+            Operand::AddressWithOffset { offset_reg, .. } => {
+                self.reads.insert(Port::Register(offset_reg.clone()));
+                self.stores = true;
+            }
+            Operand::Immediate(_) | Operand::Local(_) => {}
+        }
+    }
+
+    fn write_flag(&mut self, flag: &str) {
+        self.writes.insert(Port::Flag(flag.to_owned()));
+    }
+
+    fn condition(&mut self, condition: &Condition) {
+        let flags: &[&str] = match condition {
+            Condition::EQ | Condition::NE => &["Z"],
+            Condition::CS | Condition::CC => &["C"],
+            Condition::MI | Condition::PL => &["N"],
+            Condition::VS | Condition::VC => &["V"],
+            Condition::HI | Condition::LS => &["C", "Z"],
+            Condition::GE | Condition::LT => &["N", "V"],
+            Condition::GT | Condition::LE => &["N", "V", "Z"],
+            Condition::None => &[],
+        };
+        for flag in flags {
+            self.reads.insert(Port::Flag((*flag).to_owned()));
+        }
+    }
+
+    fn operation(&mut self, operation: &Operation) {
+        match operation {
+            Operation::Nop => {}
+            Operation::ConditionalExecution { conditions } => {
+                for condition in conditions {
+                    self.condition(condition);
+                }
+            }
+            Operation::Move {
+                destination,
+                source,
+            } => {
+                self.read(source);
+                self.write(destination);
+            }
+            Operation::Add {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Sub {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Mul {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::SDiv {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::UDiv {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::And {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Or {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Xor {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                self.read(operand1);
+                self.read(operand2);
+                self.write(destination);
+            }
+            Operation::Adc {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                self.read(operand1);
+                self.read(operand2);
+                self.reads.insert(Port::Flag("C".to_owned()));
+                self.write(destination);
+            }
+            Operation::Shift {
+                destination,
+                operand,
+                shift_n,
+                ..
+            }
+            | Operation::Sl {
+                destination,
+                operand,
+                shift: shift_n,
+            }
+            | Operation::Srl {
+                destination,
+                operand,
+                shift: shift_n,
+            }
+            | Operation::Sra {
+                destination,
+                operand,
+                shift: shift_n,
+            }
+            | Operation::Sror {
+                destination,
+                operand,
+                shift: shift_n,
+            } => {
+                self.read(operand);
+                self.read(shift_n);
+                self.write(destination);
+            }
+            Operation::BitFieldInsert {
+                destination,
+                operand,
+                ..
+            } => {
+                self.read(operand);
+                self.read(destination);
+                self.write(destination);
+            }
+            Operation::RotateRightExtend {
+                destination,
+                operand,
+            } => {
+                self.read(operand);
+                self.reads.insert(Port::Flag("C".to_owned()));
+                self.write(destination);
+            }
+            Operation::Not {
+                destination,
+                operand,
+            }
+            | Operation::ZeroExtend {
+                destination,
+                operand,
+                ..
+            }
+            | Operation::Resize {
+                destination,
+                operand,
+                ..
+            }
+            | Operation::BitFieldExtract {
+                destination,
+                operand,
+                ..
+            }
+            | Operation::SignedBitFieldExtract {
+                destination,
+                operand,
+                ..
+            }
+            | Operation::ReverseBits {
+                destination,
+                operand,
+            }
+            | Operation::CountOnes {
+                destination,
+                operand,
+            }
+            | Operation::CountZeroes {
+                destination,
+                operand,
+            }
+            | Operation::CountLeadingOnes {
+                destination,
+                operand,
+            }
+            | Operation::CountLeadingZeroes {
+                destination,
+                operand,
+            }
+            | Operation::SignExtend {
+                destination,
+                operand,
+                ..
+            } => {
+                self.read(operand);
+                self.write(destination);
+            }
+            Operation::ConditionalJump {
+                destination,
+                condition,
+            } => {
+                self.read(destination);
+                self.condition(condition);
+            }
+            Operation::SetNFlag(operand) => {
+                self.read(operand);
+                self.write_flag("N");
+            }
+            Operation::SetZFlag(operand) => {
+                self.read(operand);
+                self.write_flag("Z");
+            }
+            Operation::SetCFlagRor(operand) => {
+                self.read(operand);
+                self.write_flag("C");
+            }
+            Operation::SetCFlag {
+                operand1, operand2, ..
+            } => {
+                self.read(operand1);
+                self.read(operand2);
+                self.write_flag("C");
+            }
+            Operation::SetVFlag {
+                operand1, operand2, ..
+            } => {
+                self.read(operand1);
+                self.read(operand2);
+                self.write_flag("V");
+            }
+            Operation::SetCFlagShiftLeft { operand, shift }
+            | Operation::SetCFlagSrl { operand, shift }
+            | Operation::SetCFlagSra { operand, shift } => {
+                self.read(operand);
+                self.read(shift);
+                self.write_flag("C");
+            }
+            Operation::ForEach {
+                operands,
+                operations,
+            } => {
+                for operand in operands {
+                    self.read(operand);
+                }
+                for operation in operations {
+                    self.operation(operation);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{borrow::ToOwned, vec};
+
+    use super::{HazardClass, Hazards, Port};
+    use crate::{
+        condition::Condition,
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    #[test]
+    fn load_use() {
+        let load = Hazards::of(&[
+            Operation::Add {
+                destination: Operand::Local("address".to_owned()),
+                operand1: Operand::Register("R1".to_owned()),
+                operand2: Operand::Immediate(DataWord::Word32(4)),
+            },
+            Operation::Move {
+                destination: Operand::Register("R0".to_owned()),
+                source: Operand::AddressInLocal("address".to_owned(), 32),
+            },
+        ]);
+        assert_eq!(load.class, HazardClass::Load);
+        assert!(load.loads && !load.stores);
+        assert!(load.reads.contains(&Port::Register("R1".to_owned())));
+        assert!(load.writes.contains(&Port::Register("R0".to_owned())));
+
+        let add = Hazards::of(&[
+            Operation::Add {
+                destination: Operand::Register("R2".to_owned()),
+                operand1: Operand::Register("R0".to_owned()),
+                operand2: Operand::Register("R3".to_owned()),
+            },
+            Operation::SetZFlag(Operand::Register("R2".to_owned())),
+        ]);
+        assert_eq!(add.class, HazardClass::Alu);
+        assert!(add.depends_on(&load));
+        assert!(!load.depends_on(&add));
+
+        let branch = Hazards::of(&[Operation::ConditionalJump {
+            destination: Operand::Local("target".to_owned()),
+            condition: Condition::NE,
+        }]);
+        assert_eq!(branch.class, HazardClass::Branch);
+        assert!(branch.depends_on(&add));
+        assert_eq!(branch.reads.into_iter().collect::<vec::Vec<_>>(), vec![
+            Port::Flag("Z".to_owned())
+        ]);
+    }
+}
@@ -6,16 +6,26 @@
 //! [`Shift`](shift::Shift)s composed in to
 //! [`Operation`](operation::Operation)s. Which in turn can be composed in to
 //! meta instructions that describe more complex instructions.
+//!
+//! The crate is `no_std` and only requires [`alloc`], so decoders and
+//! embedded side helper crates can share these types without pulling in the
+//! solver.
 
+#![no_std]
 #![deny(warnings)]
 #![deny(clippy::all)]
 #![deny(missing_docs)]
 #![deny(rustdoc::all)]
 
+extern crate alloc;
+
 pub mod condition;
+pub mod hazard;
 pub mod operand;
 pub mod operation;
 pub mod shift;
+pub mod text;
+pub mod verify;
 
 /// Re-exports the main exports of this crate.
 pub mod prelude {
@@ -1,6 +1,8 @@
 //! Defines all types of operands that are valid in [Symex](../../../) General
 //! Assembly.
 
+use alloc::string::String;
+
 #[derive(Debug, Clone, Copy)]
 /// [Symex](../../../) representation for immediate fields.
 #[allow(missing_docs)]
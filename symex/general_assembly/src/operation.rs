@@ -1,6 +1,8 @@
 //! Defines all operations that are valid in [`Symex`](../../../) General
 //! Assembly language.
 
+use alloc::vec::Vec;
+
 use crate::{condition::Condition, operand::Operand, shift::Shift};
 
 /// Represents a single operation
@@ -228,6 +230,53 @@ pub enum Operation {
         stop_bit: u32,
     },
 
+    /// Extracts start_bit until stop_bit from the operand, right adjusts it and
+    /// sign extends it from stop_bit in to destination.
+    SignedBitFieldExtract {
+        /// Where to store the result.
+        destination: Operand,
+        /// Which value to extract bits from.
+        operand: Operand,
+        /// Where to start the extraction.
+        start_bit: u32,
+        /// Where to stop the extraction.
+        stop_bit: u32,
+    },
+
+    /// Replaces start_bit until stop_bit of destination with the least
+    /// significant bits of the operand, the other bits of destination are kept.
+    BitFieldInsert {
+        /// Where to insert the bits.
+        destination: Operand,
+        /// Which value to take the bits from.
+        operand: Operand,
+        /// First bit in destination to replace.
+        start_bit: u32,
+        /// Last bit in destination to replace.
+        stop_bit: u32,
+    },
+
+    /// Reverses the bit order of the operand.
+    #[allow(missing_docs)]
+    ReverseBits {
+        destination: Operand,
+        operand: Operand,
+    },
+
+    /// Rotate right through carry.
+    ///
+    /// ```ignore
+    /// destination = (C << (word_size - 1)) | (operand >> 1)
+    /// ```
+    ///
+    /// The carry flag is not updated, use [`Operation::SetCFlagSrl`] with a
+    /// shift of one before writing the result to update it.
+    #[allow(missing_docs)]
+    RotateRightExtend {
+        destination: Operand,
+        operand: Operand,
+    },
+
     /// Count the number of ones in the operand.
     #[allow(missing_docs)]
     CountOnes {
@@ -0,0 +1,972 @@
+//! A human readable textual format for [`Operation`] sequences.
+//!
+//! Every [`Operation`], [`Operand`], [`Condition`] and [`Shift`] implements
+//! [`Display`] in this format, and [`parse`] reads it back. This allows
+//! instruction semantics to be written as text fixtures and translations to be
+//! dumped while debugging a translator.
+//!
+//! # Syntax
+//!
+//! One operation is written per line as a mnemonic, optionally followed by
+//! `.`-separated modifiers, and a comma-separated list of arguments. The
+//! destination is always the first argument. Comments start with `//` and run
+//! to the end of the line.
+//!
+//! ```text
+//! // adds r1 + 1 and sets the carry flag
+//! mov $a, %R1
+//! add %R0, $a, 0x1:32
+//! setc $a, 0x1:32
+//! jump.eq mem32[0x20000000:32]
+//! foreach %R0, %R1 {
+//!     shift.lsl %R0, %R0, 0x2:32
+//! }
+//! ```
+//!
+//! Operands are written as:
+//!
+//! | Operand                        | Syntax                     |
+//! |--------------------------------|----------------------------|
+//! | [`Operand::Register`]          | `%R0`                      |
+//! | [`Operand::Local`]             | `$name`                    |
+//! | [`Operand::Flag`]              | `!Z`                       |
+//! | [`Operand::Immediate`]         | `0x2a:32` or `42:32`       |
+//! | [`Operand::Address`]           | `mem8[0x20000000:32]`      |
+//! | [`Operand::AddressInLocal`]    | `mem16[$name]`             |
+//! | [`Operand::AddressWithOffset`] | `mem32[0x4:32, %R1]`       |
+//!
+//! Immediates carry their width in bits after the colon. Plain numbers, such
+//! as the bit counts of `zext`, `sext`, `resize`, `bfx`, `sbfx` and `bfi`, are
+//! written without a width.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+use crate::{
+    condition::Condition,
+    operand::{DataWord, Operand},
+    operation::Operation,
+    shift::Shift,
+};
+
+/// An error encountered while parsing the textual format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextError {
+    /// The line, starting at 1, the error occurred on.
+    pub line: usize,
+    /// A description of the error.
+    pub message: String,
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Dumps `operations` in the textual format, one operation per line.
+pub fn dump(operations: &[Operation]) -> String {
+    let mut ret = String::new();
+    for operation in operations {
+        // Writing to a string can not fail.
+        let _ = writeln!(ret, "{operation}");
+    }
+    ret
+}
+
+/// Parses a sequence of operations written in the textual format.
+pub fn parse(input: &str) -> Result<Vec<Operation>, TextError> {
+    let mut parser = Parser { input, line: 1 };
+    parser.block(false)
+}
+
+impl Display for DataWord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Word64(v) => write!(f, "{v:#x}:64"),
+            Self::Word32(v) => write!(f, "{v:#x}:32"),
+            Self::Word16(v) => write!(f, "{v:#x}:16"),
+            Self::Word8(v) => write!(f, "{v:#x}:8"),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Register(name) => write!(f, "%{name}"),
+            Self::Local(name) => write!(f, "${name}"),
+            Self::Flag(name) => write!(f, "!{name}"),
+            Self::Immediate(value) => write!(f, "{value}"),
+            Self::Address(address, width) => write!(f, "mem{width}[{address}]"),
+            Self::AddressInLocal(name, width) => write!(f, "mem{width}[${name}]"),
+            Self::AddressWithOffset {
+                address,
+                offset_reg,
+                width,
+            } => write!(f, "mem{width}[{address}, %{offset_reg}]"),
+        }
+    }
+}
+
+impl Condition {
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::EQ => "eq",
+            Self::NE => "ne",
+            Self::CS => "cs",
+            Self::CC => "cc",
+            Self::MI => "mi",
+            Self::PL => "pl",
+            Self::VS => "vs",
+            Self::VC => "vc",
+            Self::HI => "hi",
+            Self::LS => "ls",
+            Self::GE => "ge",
+            Self::LT => "lt",
+            Self::GT => "gt",
+            Self::LE => "le",
+            Self::None => "al",
+        }
+    }
+
+    fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        Some(match mnemonic {
+            "eq" => Self::EQ,
+            "ne" => Self::NE,
+            "cs" => Self::CS,
+            "cc" => Self::CC,
+            "mi" => Self::MI,
+            "pl" => Self::PL,
+            "vs" => Self::VS,
+            "vc" => Self::VC,
+            "hi" => Self::HI,
+            "ls" => Self::LS,
+            "ge" => Self::GE,
+            "lt" => Self::LT,
+            "gt" => Self::GT,
+            "le" => Self::LE,
+            "al" => Self::None,
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.mnemonic())
+    }
+}
+
+impl Shift {
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Lsl => "lsl",
+            Self::Lsr => "lsr",
+            Self::Asr => "asr",
+            Self::Rrx => "rrx",
+            Self::Ror => "ror",
+        }
+    }
+
+    fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        Some(match mnemonic {
+            "lsl" => Self::Lsl,
+            "lsr" => Self::Lsr,
+            "asr" => Self::Asr,
+            "rrx" => Self::Rrx,
+            "ror" => Self::Ror,
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Shift {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.mnemonic())
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.write_indented(f, 0)
+    }
+}
+
+impl Operation {
+    fn write_indented(&self, f: &mut Formatter<'_>, depth: usize) -> FmtResult {
+        fn flags(sub: bool, carry: bool) -> &'static str {
+            match (sub, carry) {
+                (false, false) => "",
+                (true, false) => ".sub",
+                (false, true) => ".carry",
+                (true, true) => ".sub.carry",
+            }
+        }
+
+        for _ in 0..depth {
+            f.write_str("    ")?;
+        }
+        match self {
+            Self::Nop => write!(f, "nop"),
+            Self::Move {
+                destination,
+                source,
+            } => write!(f, "mov {destination}, {source}"),
+            Self::Add {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "add {destination}, {operand1}, {operand2}"),
+            Self::Adc {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "adc {destination}, {operand1}, {operand2}"),
+            Self::Sub {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "sub {destination}, {operand1}, {operand2}"),
+            Self::Mul {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "mul {destination}, {operand1}, {operand2}"),
+            Self::SDiv {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "sdiv {destination}, {operand1}, {operand2}"),
+            Self::UDiv {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "udiv {destination}, {operand1}, {operand2}"),
+            Self::And {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "and {destination}, {operand1}, {operand2}"),
+            Self::Or {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "or {destination}, {operand1}, {operand2}"),
+            Self::Xor {
+                destination,
+                operand1,
+                operand2,
+            } => write!(f, "xor {destination}, {operand1}, {operand2}"),
+            Self::Not {
+                destination,
+                operand,
+            } => write!(f, "not {destination}, {operand}"),
+            Self::Shift {
+                destination,
+                operand,
+                shift_n,
+                shift_t,
+            } => write!(f, "shift.{shift_t} {destination}, {operand}, {shift_n}"),
+            Self::Sl {
+                destination,
+                operand,
+                shift,
+            } => write!(f, "sl {destination}, {operand}, {shift}"),
+            Self::Srl {
+                destination,
+                operand,
+                shift,
+            } => write!(f, "srl {destination}, {operand}, {shift}"),
+            Self::Sra {
+                destination,
+                operand,
+                shift,
+            } => write!(f, "sra {destination}, {operand}, {shift}"),
+            Self::Sror {
+                destination,
+                operand,
+                shift,
+            } => write!(f, "sror {destination}, {operand}, {shift}"),
+            Self::ZeroExtend {
+                destination,
+                operand,
+                bits,
+                target_bits,
+            } => write!(f, "zext {destination}, {operand}, {bits}, {target_bits}"),
+            Self::BitFieldExtract {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => write!(f, "bfx {destination}, {operand}, {start_bit}, {stop_bit}"),
+            Self::SignedBitFieldExtract {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => write!(f, "sbfx {destination}, {operand}, {start_bit}, {stop_bit}"),
+            Self::BitFieldInsert {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => write!(f, "bfi {destination}, {operand}, {start_bit}, {stop_bit}"),
+            Self::ReverseBits {
+                destination,
+                operand,
+            } => write!(f, "rbit {destination}, {operand}"),
+            Self::RotateRightExtend {
+                destination,
+                operand,
+            } => write!(f, "rrx {destination}, {operand}"),
+            Self::CountOnes {
+                destination,
+                operand,
+            } => write!(f, "cnt1 {destination}, {operand}"),
+            Self::CountZeroes {
+                destination,
+                operand,
+            } => write!(f, "cnt0 {destination}, {operand}"),
+            Self::CountLeadingOnes {
+                destination,
+                operand,
+            } => write!(f, "clo {destination}, {operand}"),
+            Self::CountLeadingZeroes {
+                destination,
+                operand,
+            } => write!(f, "clz {destination}, {operand}"),
+            Self::SignExtend {
+                destination,
+                operand,
+                bits,
+            } => write!(f, "sext {destination}, {operand}, {bits}"),
+            Self::Resize {
+                destination,
+                operand,
+                bits,
+            } => write!(f, "resize {destination}, {operand}, {bits}"),
+            Self::ConditionalJump {
+                destination,
+                condition: Condition::None,
+            } => write!(f, "jump {destination}"),
+            Self::ConditionalJump {
+                destination,
+                condition,
+            } => write!(f, "jump.{condition} {destination}"),
+            Self::SetNFlag(operand) => write!(f, "setn {operand}"),
+            Self::SetZFlag(operand) => write!(f, "setz {operand}"),
+            Self::SetCFlag {
+                operand1,
+                operand2,
+                sub,
+                carry,
+            } => write!(f, "setc{} {operand1}, {operand2}", flags(*sub, *carry)),
+            Self::SetCFlagShiftLeft { operand, shift } => {
+                write!(f, "setcsl {operand}, {shift}")
+            }
+            Self::SetCFlagSrl { operand, shift } => write!(f, "setcsrl {operand}, {shift}"),
+            Self::SetCFlagSra { operand, shift } => write!(f, "setcsra {operand}, {shift}"),
+            Self::SetCFlagRor(operand) => write!(f, "setcror {operand}"),
+            Self::SetVFlag {
+                operand1,
+                operand2,
+                sub,
+                carry,
+            } => write!(f, "setv{} {operand1}, {operand2}", flags(*sub, *carry)),
+            Self::ForEach {
+                operands,
+                operations,
+            } => {
+                f.write_str("foreach")?;
+                for (idx, operand) in operands.iter().enumerate() {
+                    let separator = if idx == 0 { " " } else { ", " };
+                    write!(f, "{separator}{operand}")?;
+                }
+                f.write_str(" {\n")?;
+                for operation in operations {
+                    operation.write_indented(f, depth + 1)?;
+                    f.write_str("\n")?;
+                }
+                for _ in 0..depth {
+                    f.write_str("    ")?;
+                }
+                f.write_str("}")
+            }
+            Self::ConditionalExecution { conditions } => {
+                f.write_str("cexec")?;
+                for (idx, condition) in conditions.iter().enumerate() {
+                    let separator = if idx == 0 { " " } else { ", " };
+                    write!(f, "{separator}{condition}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Characters that end a name or a mnemonic.
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}')
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: impl ToString) -> Result<T, TextError> {
+        Err(TextError {
+            line: self.line,
+            message: message.to_string(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            if c == '\n' {
+                self.line += 1;
+            }
+            self.input = &self.input[c.len_utf8()..];
+        }
+    }
+
+    /// Skips spaces and comments on the current line.
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() && c != '\n' => self.bump(),
+                Some('/') if self.input.starts_with("//") => {
+                    let end = self.input.find('\n').unwrap_or(self.input.len());
+                    self.input = &self.input[end..];
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Skips spaces, comments and line breaks.
+    fn skip_lines(&mut self) {
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                Some('\n') => self.bump(),
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), TextError> {
+        self.skip_blank();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => self.error(format!("expected `{expected}`, found `{c}`")),
+            None => self.error(format!("expected `{expected}`, found end of input")),
+        }
+    }
+
+    fn word(&mut self) -> Result<&'a str, TextError> {
+        self.skip_blank();
+        let end = self.input.find(is_delimiter).unwrap_or(self.input.len());
+        if end == 0 {
+            return self.error("expected a name");
+        }
+        let (word, rest) = self.input.split_at(end);
+        self.input = rest;
+        Ok(word)
+    }
+
+    fn number(&mut self) -> Result<u64, TextError> {
+        self.skip_blank();
+        let end = self
+            .input
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(self.input.len());
+        let (literal, rest) = self.input.split_at(end);
+        let digits = literal.replace('_', "");
+        let value = match digits.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => digits.parse(),
+        };
+        match value {
+            Ok(value) => {
+                self.input = rest;
+                Ok(value)
+            }
+            Err(_) => self.error(format!("invalid number `{literal}`")),
+        }
+    }
+
+    fn bits(&mut self) -> Result<u32, TextError> {
+        let value = self.number()?;
+        match u32::try_from(value) {
+            Ok(value) => Ok(value),
+            Err(_) => self.error(format!("bit count {value} is out of range")),
+        }
+    }
+
+    fn immediate(&mut self) -> Result<DataWord, TextError> {
+        let value = self.number()?;
+        self.expect(':')?;
+        let width = self.number()?;
+        let word = match width {
+            64 => Some(DataWord::Word64(value)),
+            32 => u32::try_from(value).ok().map(DataWord::Word32),
+            16 => u16::try_from(value).ok().map(DataWord::Word16),
+            8 => u8::try_from(value).ok().map(DataWord::Word8),
+            _ => return self.error(format!("invalid immediate width {width}")),
+        };
+        match word {
+            Some(word) => Ok(word),
+            None => self.error(format!("{value:#x} does not fit in {width} bits")),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, TextError> {
+        self.skip_blank();
+        match self.peek() {
+            Some('%') => {
+                self.bump();
+                Ok(Operand::Register(self.word()?.to_string()))
+            }
+            Some('$') => {
+                self.bump();
+                Ok(Operand::Local(self.word()?.to_string()))
+            }
+            Some('!') => {
+                self.bump();
+                Ok(Operand::Flag(self.word()?.to_string()))
+            }
+            Some(c) if c.is_ascii_digit() => Ok(Operand::Immediate(self.immediate()?)),
+            Some('m') if self.input.starts_with("mem") => {
+                self.input = &self.input[3..];
+                let width = self.bits()?;
+                self.expect('[')?;
+                self.skip_blank();
+                let operand = if self.peek() == Some('$') {
+                    self.bump();
+                    Operand::AddressInLocal(self.word()?.to_string(), width)
+                } else {
+                    let address = self.immediate()?;
+                    self.skip_blank();
+                    if self.peek() == Some(',') {
+                        self.bump();
+                        self.expect('%')?;
+                        Operand::AddressWithOffset {
+                            address,
+                            offset_reg: self.word()?.to_string(),
+                            width,
+                        }
+                    } else {
+                        Operand::Address(address, width)
+                    }
+                };
+                self.expect(']')?;
+                Ok(operand)
+            }
+            Some(c) => self.error(format!("expected an operand, found `{c}`")),
+            None => self.error("expected an operand, found end of input"),
+        }
+    }
+
+    /// Parses a comma separated list of exactly `N` operands.
+    fn operands<const N: usize>(&mut self) -> Result<[Operand; N], TextError> {
+        let mut operands = Vec::with_capacity(N);
+        for idx in 0..N {
+            if idx != 0 {
+                self.expect(',')?;
+            }
+            operands.push(self.operand()?);
+        }
+        Ok(operands
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("exactly N operands were parsed")))
+    }
+
+    /// Parses `N` operands followed by `M` plain numbers.
+    fn operands_and_bits<const N: usize, const M: usize>(
+        &mut self,
+    ) -> Result<([Operand; N], [u32; M]), TextError> {
+        let operands = self.operands::<N>()?;
+        let mut bits = [0; M];
+        for value in bits.iter_mut() {
+            self.expect(',')?;
+            *value = self.bits()?;
+        }
+        Ok((operands, bits))
+    }
+
+    fn block(&mut self, nested: bool) -> Result<Vec<Operation>, TextError> {
+        let mut operations = Vec::new();
+        loop {
+            self.skip_lines();
+            match self.peek() {
+                None if nested => return self.error("unterminated `foreach` block"),
+                None => return Ok(operations),
+                Some('}') if nested => {
+                    self.bump();
+                    return Ok(operations);
+                }
+                Some('}') => return self.error("unmatched `}`"),
+                _ => operations.push(self.operation()?),
+            }
+        }
+    }
+
+    fn operation(&mut self) -> Result<Operation, TextError> {
+        let word = self.word()?;
+        let mut parts = word.split('.');
+        let mnemonic = parts.next().unwrap_or_default();
+        let modifiers: Vec<&str> = parts.collect();
+
+        let no_modifiers = |parser: &Self| match modifiers.first() {
+            Some(modifier) => parser.error(format!(
+                "`{mnemonic}` takes no modifier, found `{modifier}`"
+            )),
+            None => Ok(()),
+        };
+        let sub_carry = |parser: &Self| {
+            let (mut sub, mut carry) = (false, false);
+            for modifier in &modifiers {
+                match *modifier {
+                    "sub" => sub = true,
+                    "carry" => carry = true,
+                    _ => return parser.error(format!("unknown modifier `{modifier}`")),
+                }
+            }
+            Ok((sub, carry))
+        };
+
+        macro_rules! binary {
+            ($variant:ident) => {{
+                no_modifiers(self)?;
+                let [destination, operand1, operand2] = self.operands()?;
+                Operation::$variant {
+                    destination,
+                    operand1,
+                    operand2,
+                }
+            }};
+        }
+        macro_rules! unary {
+            ($variant:ident) => {{
+                no_modifiers(self)?;
+                let [destination, operand] = self.operands()?;
+                Operation::$variant {
+                    destination,
+                    operand,
+                }
+            }};
+        }
+        macro_rules! shift {
+            ($variant:ident) => {{
+                no_modifiers(self)?;
+                let [destination, operand, shift] = self.operands()?;
+                Operation::$variant {
+                    destination,
+                    operand,
+                    shift,
+                }
+            }};
+        }
+
+        let operation = match mnemonic {
+            "nop" => {
+                no_modifiers(self)?;
+                Operation::Nop
+            }
+            "mov" => {
+                no_modifiers(self)?;
+                let [destination, source] = self.operands()?;
+                Operation::Move {
+                    destination,
+                    source,
+                }
+            }
+            "add" => binary!(Add),
+            "adc" => binary!(Adc),
+            "sub" => binary!(Sub),
+            "mul" => binary!(Mul),
+            "sdiv" => binary!(SDiv),
+            "udiv" => binary!(UDiv),
+            "and" => binary!(And),
+            "or" => binary!(Or),
+            "xor" => binary!(Xor),
+            "not" => unary!(Not),
+            "shift" => {
+                let shift_t = match modifiers.as_slice() {
+                    [modifier] => match Shift::from_mnemonic(modifier) {
+                        Some(shift) => shift,
+                        None => return self.error(format!("unknown shift `{modifier}`")),
+                    },
+                    _ => return self.error("`shift` takes exactly one shift type"),
+                };
+                let [destination, operand, shift_n] = self.operands()?;
+                Operation::Shift {
+                    destination,
+                    operand,
+                    shift_n,
+                    shift_t,
+                }
+            }
+            "sl" => shift!(Sl),
+            "srl" => shift!(Srl),
+            "sra" => shift!(Sra),
+            "sror" => shift!(Sror),
+            "zext" => {
+                no_modifiers(self)?;
+                let ([destination, operand], [bits, target_bits]) = self.operands_and_bits()?;
+                Operation::ZeroExtend {
+                    destination,
+                    operand,
+                    bits,
+                    target_bits,
+                }
+            }
+            "bfx" => {
+                no_modifiers(self)?;
+                let ([destination, operand], [start_bit, stop_bit]) = self.operands_and_bits()?;
+                Operation::BitFieldExtract {
+                    destination,
+                    operand,
+                    start_bit,
+                    stop_bit,
+                }
+            }
+            "sbfx" => {
+                no_modifiers(self)?;
+                let ([destination, operand], [start_bit, stop_bit]) = self.operands_and_bits()?;
+                Operation::SignedBitFieldExtract {
+                    destination,
+                    operand,
+                    start_bit,
+                    stop_bit,
+                }
+            }
+            "bfi" => {
+                no_modifiers(self)?;
+                let ([destination, operand], [start_bit, stop_bit]) = self.operands_and_bits()?;
+                Operation::BitFieldInsert {
+                    destination,
+                    operand,
+                    start_bit,
+                    stop_bit,
+                }
+            }
+            "rbit" => unary!(ReverseBits),
+            "rrx" => unary!(RotateRightExtend),
+            "cnt1" => unary!(CountOnes),
+            "cnt0" => unary!(CountZeroes),
+            "clo" => unary!(CountLeadingOnes),
+            "clz" => unary!(CountLeadingZeroes),
+            "sext" => {
+                no_modifiers(self)?;
+                let ([destination, operand], [bits]) = self.operands_and_bits()?;
+                Operation::SignExtend {
+                    destination,
+                    operand,
+                    bits,
+                }
+            }
+            "resize" => {
+                no_modifiers(self)?;
+                let ([destination, operand], [bits]) = self.operands_and_bits()?;
+                Operation::Resize {
+                    destination,
+                    operand,
+                    bits,
+                }
+            }
+            "jump" => {
+                let condition = match modifiers.as_slice() {
+                    [] => Condition::None,
+                    [modifier] => match Condition::from_mnemonic(modifier) {
+                        Some(condition) => condition,
+                        None => return self.error(format!("unknown condition `{modifier}`")),
+                    },
+                    _ => return self.error("`jump` takes at most one condition"),
+                };
+                let [destination] = self.operands()?;
+                Operation::ConditionalJump {
+                    destination,
+                    condition,
+                }
+            }
+            "setn" => {
+                no_modifiers(self)?;
+                let [operand] = self.operands()?;
+                Operation::SetNFlag(operand)
+            }
+            "setz" => {
+                no_modifiers(self)?;
+                let [operand] = self.operands()?;
+                Operation::SetZFlag(operand)
+            }
+            "setc" => {
+                let (sub, carry) = sub_carry(self)?;
+                let [operand1, operand2] = self.operands()?;
+                Operation::SetCFlag {
+                    operand1,
+                    operand2,
+                    sub,
+                    carry,
+                }
+            }
+            "setv" => {
+                let (sub, carry) = sub_carry(self)?;
+                let [operand1, operand2] = self.operands()?;
+                Operation::SetVFlag {
+                    operand1,
+                    operand2,
+                    sub,
+                    carry,
+                }
+            }
+            "setcsl" => {
+                no_modifiers(self)?;
+                let [operand, shift] = self.operands()?;
+                Operation::SetCFlagShiftLeft { operand, shift }
+            }
+            "setcsrl" => {
+                no_modifiers(self)?;
+                let [operand, shift] = self.operands()?;
+                Operation::SetCFlagSrl { operand, shift }
+            }
+            "setcsra" => {
+                no_modifiers(self)?;
+                let [operand, shift] = self.operands()?;
+                Operation::SetCFlagSra { operand, shift }
+            }
+            "setcror" => {
+                no_modifiers(self)?;
+                let [operand] = self.operands()?;
+                Operation::SetCFlagRor(operand)
+            }
+            "foreach" => {
+                no_modifiers(self)?;
+                let mut operands = Vec::new();
+                self.skip_blank();
+                while self.peek() != Some('{') {
+                    if !operands.is_empty() {
+                        self.expect(',')?;
+                    }
+                    operands.push(self.operand()?);
+                    self.skip_blank();
+                }
+                self.bump();
+                let operations = self.block(true)?;
+                Operation::ForEach {
+                    operands,
+                    operations,
+                }
+            }
+            "cexec" => {
+                no_modifiers(self)?;
+                let mut conditions = Vec::new();
+                loop {
+                    self.skip_blank();
+                    match self.peek() {
+                        None | Some('\n') | Some('}') => break,
+                        _ => {}
+                    }
+                    if !conditions.is_empty() {
+                        self.expect(',')?;
+                    }
+                    let word = self.word()?;
+                    match Condition::from_mnemonic(word) {
+                        Some(condition) => conditions.push(condition),
+                        None => return self.error(format!("unknown condition `{word}`")),
+                    }
+                }
+                Operation::ConditionalExecution { conditions }
+            }
+            _ => return self.error(format!("unknown mnemonic `{mnemonic}`")),
+        };
+
+        // Every operation ends at a line break or the end of a block.
+        self.skip_blank();
+        match self.peek() {
+            None | Some('\n') | Some('}') => Ok(operation),
+            Some(c) => self.error(format!("expected end of line, found `{c}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::ToString;
+
+    use super::{dump, parse};
+    use crate::{
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    #[test]
+    fn round_trip() {
+        let text = "\
+nop
+mov $a, %R1
+add %R0, $a, 0x1:32
+setc.sub.carry $a, 0xff:8
+shift.lsl %R0, %R0, mem32[$a]
+zext %R0, mem8[0x20000000:32], 8, 32
+sbfx $a, %R1, 4, 11
+bfi %R0, $a, 8, 15
+rbit %R0, %R0
+rrx %R1, %R0
+jump.eq mem32[0x4:32, %SP&]
+foreach %R0, !Z {
+    not %R0, %R0
+    foreach %R1 {
+        nop
+    }
+}
+cexec eq, al
+";
+        let operations = parse(text).unwrap();
+        assert_eq!(operations.len(), 13);
+        assert_eq!(dump(&operations), text);
+    }
+
+    #[test]
+    fn parse_operands() {
+        let operations = parse("// comment\nadd %PC+, 42:16, $b // trailing\n").unwrap();
+        match &operations[..] {
+            [Operation::Add {
+                destination: Operand::Register(destination),
+                operand1: Operand::Immediate(DataWord::Word16(42)),
+                operand2: Operand::Local(operand2),
+            }] => {
+                assert_eq!(destination, "PC+");
+                assert_eq!(operand2, "b");
+            }
+            _ => panic!("unexpected operations {operations:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = parse("nop\nfoo %R0").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.to_string(), "line 2: unknown mnemonic `foo`");
+
+        let error = parse("mov %R0, 0x100:8").unwrap_err();
+        assert_eq!(error.message, "0x100 does not fit in 8 bits");
+
+        assert!(parse("foreach %R0 {\nnop\n").is_err());
+        assert!(parse("add %R0, %R1").is_err());
+    }
+}
@@ -0,0 +1,501 @@
+//! Verification of translated [`Operation`] sequences.
+//!
+//! The verifier catches translator bugs at IR construction time, before they
+//! surface as panics or sort errors deep inside the solver.
+
+use alloc::{collections::BTreeMap, string::String};
+use core::fmt::Display;
+
+use crate::{
+    condition::Condition,
+    operand::{DataWord, Operand},
+    operation::Operation,
+};
+
+/// Names of the program counter, `PC+` is the program counter as read and
+/// written by the ARM translations.
+const PROGRAM_COUNTER: [&str; 2] = ["PC", "PC+"];
+
+/// A malformed [`Operation`] sequence.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub enum VerifyError {
+    /// A local is read before it is assigned.
+    UndefinedLocal {
+        /// Index of the operation that reads the local.
+        index: usize,
+        /// Name of the local.
+        name: String,
+    },
+
+    /// The operands of an operation have different widths.
+    WidthMismatch {
+        /// Index of the offending operation.
+        index: usize,
+        /// Width of the first operand.
+        lhs: u32,
+        /// Width of the second operand.
+        rhs: u32,
+    },
+
+    /// The operation or one of its operands can not be executed.
+    Unsupported {
+        /// Index of the offending operation.
+        index: usize,
+        /// What is not supported.
+        what: &'static str,
+    },
+
+    /// The operation follows an unconditional jump or a write to the program
+    /// counter, which has to end the instruction.
+    Unreachable {
+        /// Index of the offending operation.
+        index: usize,
+    },
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UndefinedLocal { index, name } => {
+                write!(f, "operation {index} reads undefined local {name}")
+            }
+            Self::WidthMismatch { index, lhs, rhs } => {
+                write!(
+                    f,
+                    "operation {index} mixes operands of width {lhs} and {rhs}"
+                )
+            }
+            Self::Unsupported { index, what } => {
+                write!(f, "operation {index} uses unsupported {what}")
+            }
+            Self::Unreachable { index } => {
+                write!(f, "operation {index} follows the end of the instruction")
+            }
+        }
+    }
+}
+
+/// Verifies that `operations` is well formed.
+///
+/// Checks that every local is assigned before it is read, that the operands
+/// of arithmetic and logic operations have the same width where it is known,
+/// that no operation that can not be executed is used and that no operation
+/// follows an unconditional jump or a write to the program counter.
+///
+/// Registers and flags are sized by the executor, so their width is treated
+/// as unknown.
+pub fn verify(operations: &[Operation]) -> Result<(), VerifyError> {
+    let mut verifier = Verifier {
+        locals: BTreeMap::new(),
+        jumped: false,
+    };
+    for (index, operation) in operations.iter().enumerate() {
+        if verifier.jumped {
+            return Err(VerifyError::Unreachable { index });
+        }
+        verifier.operation(index, operation)?;
+    }
+    Ok(())
+}
+
+struct Verifier {
+    /// Defined locals and their width, if known.
+    locals: BTreeMap<String, Option<u32>>,
+    /// Set once the program counter is written unconditionally.
+    jumped: bool,
+}
+
+fn data_word_width(word: &DataWord) -> u32 {
+    match word {
+        DataWord::Word64(_) => 64,
+        DataWord::Word32(_) => 32,
+        DataWord::Word16(_) => 16,
+        DataWord::Word8(_) => 8,
+    }
+}
+
+impl Verifier {
+    /// Checks that `operand` can be read and returns its width, if known.
+    fn read(&self, index: usize, operand: &Operand) -> Result<Option<u32>, VerifyError> {
+        match operand {
+            Operand::Register(_) | Operand::Flag(_) => Ok(None),
+            Operand::Immediate(word) => Ok(Some(data_word_width(word))),
+            Operand::Address(_, width) => Ok(Some(*width)),
+            Operand::Local(name) => match self.locals.get(name) {
+                Some(width) => Ok(*width),
+                None => Err(VerifyError::UndefinedLocal {
+                    index,
+                    name: name.clone(),
+                }),
+            },
+            Operand::AddressInLocal(name, width) => {
+                self.read(index, &Operand::Local(name.clone()))?;
+                Ok(Some(*width))
+            }
+            Operand::AddressWithOffset { .. } => Err(VerifyError::Unsupported {
+                index,
+                what: "address with offset operand",
+            }),
+        }
+    }
+
+    /// Checks that `operand` can be written and records the width of locals.
+    fn write(
+        &mut self,
+        index: usize,
+        operand: &Operand,
+        width: Option<u32>,
+    ) -> Result<(), VerifyError> {
+        match operand {
+            Operand::Local(name) => {
+                self.locals.insert(name.clone(), width);
+                Ok(())
+            }
+            Operand::AddressInLocal(name, _) => {
+                self.read(index, &Operand::Local(name.clone()))?;
+                Ok(())
+            }
+            Operand::AddressWithOffset { .. } => Err(VerifyError::Unsupported {
+                index,
+                what: "address with offset operand",
+            }),
+            Operand::Register(name) if PROGRAM_COUNTER.contains(&name.as_str()) => {
+                self.jumped = true;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a binary operation and returns the width of the result, if
+    /// known.
+    fn binary(
+        &self,
+        index: usize,
+        operand1: &Operand,
+        operand2: &Operand,
+    ) -> Result<Option<u32>, VerifyError> {
+        match (self.read(index, operand1)?, self.read(index, operand2)?) {
+            (Some(lhs), Some(rhs)) if lhs != rhs => {
+                Err(VerifyError::WidthMismatch { index, lhs, rhs })
+            }
+            (Some(width), _) | (_, Some(width)) => Ok(Some(width)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn operation(&mut self, index: usize, operation: &Operation) -> Result<(), VerifyError> {
+        match operation {
+            Operation::Nop | Operation::ConditionalExecution { .. } => {}
+            Operation::Move {
+                destination,
+                source,
+            } => {
+                let width = self.read(index, source)?;
+                self.write(index, destination, width)?;
+            }
+            Operation::Add {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Adc {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Sub {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Mul {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::SDiv {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::UDiv {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::And {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Or {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Xor {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let width = self.binary(index, operand1, operand2)?;
+                self.write(index, destination, width)?;
+            }
+            Operation::Not {
+                destination,
+                operand,
+            } => {
+                let width = self.read(index, operand)?;
+                self.write(index, destination, width)?;
+            }
+            Operation::Shift {
+                destination,
+                operand,
+                shift_n,
+                ..
+            }
+            | Operation::Sl {
+                destination,
+                operand,
+                shift: shift_n,
+            }
+            | Operation::Srl {
+                destination,
+                operand,
+                shift: shift_n,
+            }
+            | Operation::Sra {
+                destination,
+                operand,
+                shift: shift_n,
+            }
+            | Operation::Sror {
+                destination,
+                operand,
+                shift: shift_n,
+            } => {
+                let width = self.read(index, operand)?;
+                self.read(index, shift_n)?;
+                self.write(index, destination, width)?;
+            }
+            Operation::ZeroExtend {
+                destination,
+                operand,
+                target_bits,
+                ..
+            } => {
+                self.read(index, operand)?;
+                self.write(index, destination, Some(*target_bits))?;
+            }
+            Operation::Resize {
+                destination,
+                operand,
+                bits,
+            } => {
+                self.read(index, operand)?;
+                self.write(index, destination, Some(*bits))?;
+            }
+            Operation::BitFieldInsert {
+                destination,
+                operand,
+                ..
+            } => {
+                self.read(index, operand)?;
+                let width = self.read(index, destination)?;
+                self.write(index, destination, width)?;
+            }
+            Operation::BitFieldExtract {
+                destination,
+                operand,
+                ..
+            }
+            | Operation::SignedBitFieldExtract {
+                destination,
+                operand,
+                ..
+            }
+            | Operation::ReverseBits {
+                destination,
+                operand,
+            }
+            | Operation::RotateRightExtend {
+                destination,
+                operand,
+            }
+            | Operation::CountOnes {
+                destination,
+                operand,
+            }
+            | Operation::CountZeroes {
+                destination,
+                operand,
+            }
+            | Operation::CountLeadingOnes {
+                destination,
+                operand,
+            }
+            | Operation::CountLeadingZeroes {
+                destination,
+                operand,
+            }
+            | Operation::SignExtend {
+                destination,
+                operand,
+                ..
+            } => {
+                self.read(index, operand)?;
+                self.write(index, destination, None)?;
+            }
+            Operation::ConditionalJump {
+                destination,
+                condition,
+            } => {
+                self.read(index, destination)?;
+                self.jumped = *condition == Condition::None;
+            }
+            Operation::SetNFlag(operand)
+            | Operation::SetZFlag(operand)
+            | Operation::SetCFlagRor(operand) => {
+                self.read(index, operand)?;
+            }
+            Operation::SetCFlag {
+                operand1, operand2, ..
+            }
+            | Operation::SetVFlag {
+                operand1, operand2, ..
+            } => {
+                self.binary(index, operand1, operand2)?;
+            }
+            Operation::SetCFlagShiftLeft { operand, shift }
+            | Operation::SetCFlagSrl { operand, shift }
+            | Operation::SetCFlagSra { operand, shift } => {
+                self.read(index, operand)?;
+                self.read(index, shift)?;
+            }
+            Operation::ForEach { .. } => {
+                return Err(VerifyError::Unsupported {
+                    index,
+                    what: "for each operation",
+                })
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{borrow::ToOwned, vec};
+
+    use super::{verify, VerifyError};
+    use crate::{
+        condition::Condition,
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    #[test]
+    fn undefined_local() {
+        let operations = vec![
+            Operation::Move {
+                destination: Operand::Local("a".to_owned()),
+                source: Operand::Register("R0".to_owned()),
+            },
+            Operation::Add {
+                destination: Operand::Register("R0".to_owned()),
+                operand1: Operand::Local("a".to_owned()),
+                operand2: Operand::Local("b".to_owned()),
+            },
+        ];
+        assert_eq!(
+            verify(&operations),
+            Err(VerifyError::UndefinedLocal {
+                index: 1,
+                name: "b".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn width_mismatch() {
+        let operations = vec![
+            Operation::Move {
+                destination: Operand::Local("a".to_owned()),
+                source: Operand::Immediate(DataWord::Word8(1)),
+            },
+            Operation::Add {
+                destination: Operand::Register("R0".to_owned()),
+                operand1: Operand::Local("a".to_owned()),
+                operand2: Operand::Immediate(DataWord::Word32(1)),
+            },
+        ];
+        assert_eq!(
+            verify(&operations),
+            Err(VerifyError::WidthMismatch {
+                index: 1,
+                lhs: 8,
+                rhs: 32
+            })
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let operations = vec![
+            Operation::ConditionalJump {
+                destination: Operand::Register("LR".to_owned()),
+                condition: Condition::None,
+            },
+            Operation::Move {
+                destination: Operand::Register("R0".to_owned()),
+                source: Operand::Register("R1".to_owned()),
+            },
+        ];
+        assert_eq!(
+            verify(&operations),
+            Err(VerifyError::Unreachable { index: 1 })
+        );
+
+        let operations = vec![
+            Operation::Move {
+                destination: Operand::Register("PC".to_owned()),
+                source: Operand::Register("LR".to_owned()),
+            },
+            Operation::SetZFlag(Operand::Register("PC".to_owned())),
+        ];
+        assert_eq!(
+            verify(&operations),
+            Err(VerifyError::Unreachable { index: 1 })
+        );
+
+        // Operations may follow a conditional jump.
+        let operations = vec![
+            Operation::ConditionalJump {
+                destination: Operand::Register("LR".to_owned()),
+                condition: Condition::EQ,
+            },
+            Operation::Move {
+                destination: Operand::Register("R0".to_owned()),
+                source: Operand::Register("R1".to_owned()),
+            },
+        ];
+        assert_eq!(verify(&operations), Ok(()));
+    }
+
+    #[test]
+    fn well_formed() {
+        let operations = vec![
+            Operation::Move {
+                destination: Operand::Local("a".to_owned()),
+                source: Operand::Register("R1".to_owned()),
+            },
+            Operation::Add {
+                destination: Operand::Register("R0".to_owned()),
+                operand1: Operand::Local("a".to_owned()),
+                operand2: Operand::Immediate(DataWord::Word32(1)),
+            },
+        ];
+        assert_eq!(verify(&operations), Ok(()));
+    }
+}
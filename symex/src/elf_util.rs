@@ -1,13 +1,35 @@
 //! Utility structures mostly related to passing information to runner and
 //! display to user.
 use core::fmt::{self, Write};
-use std::iter::Peekable;
+use std::{
+    collections::{BTreeSet, HashMap},
+    iter::{self, Peekable},
+};
 
 use colored::*;
 use indenter::indented;
+use serde::Serialize;
 
 use crate::{
-    general_assembly::{arch::Arch, state::GAState, GAError},
+    general_assembly::{
+        arch::Arch,
+        branch_predictor::PredictionStats,
+        budget::Truncation,
+        cache::CacheStats,
+        interrupts::{AtomicityViolation, TakenInterrupt},
+        mmio::{AccessKind, MmioAccess},
+        nvic::NvicBank,
+        path_data::PathData,
+        project::SourceLocation,
+        reentrancy::Reentry,
+        slice::Location,
+        stack_locals::StackLocal,
+        state::{FilteredCall, GAState, RegisterWrite, SymbolProvenance},
+        GAError,
+    },
+    json,
+    memory::SmtMap,
+    preconditions::{self, InputRange},
     smt::DExpr,
 };
 
@@ -18,7 +40,9 @@ use crate::{
 ///
 /// All input variables and variables used in `symbolic` calls will also have
 /// solutions available.
-#[derive(Debug)]
+///
+/// Serializes to the form described in [`json`](crate::json).
+#[derive(Debug, Serialize)]
 pub struct VisualPathResult {
     /// Which path this is.
     pub path: usize,
@@ -27,53 +51,226 @@ pub struct VisualPathResult {
     ///
     /// If the path failed the reason will be in the error. Otherwise there will
     /// be a value unless the analyzed function returned void.
+    #[serde(flatten)]
     pub result: PathStatus,
 
     /// Variables explicitly marked as symbolic.
+    #[serde(rename = "inputs", serialize_with = "json::concrete")]
     pub symbolics: Vec<Variable>,
 
+    /// Names of the variables marked as symbolic that the path condition
+    /// depends on.
+    pub constrained: Vec<String>,
+
+    /// Range of each variable marked as symbolic on the path, if
+    /// preconditions are inferred, see [`preconditions`](crate::preconditions).
+    pub input_ranges: Vec<InputRange>,
+
     /// All register values att the end of execution.
+    #[serde(rename = "registers", serialize_with = "json::concrete")]
     pub end_state: Vec<Variable>,
 
+    /// Roles of the registers in the calling convention of the architecture,
+    /// by register name.
+    #[serde(skip)]
+    pub register_roles: HashMap<String, &'static str>,
+
+    /// The value the analyzed function returned, decoded with the return type
+    /// declared in the debug data, if the path succeeded and the debug data
+    /// describes the function.
+    pub return_value: Option<String>,
+
     /// The number of instructions executed in the path
     pub instruction_count: usize,
 
     /// the maximum number of clock cycles the path can contain
     pub max_cycles: usize,
 
+    /// Energy used by the path in nJ, if an energy model is used.
+    pub energy: Option<f64>,
+
+    /// Hits and misses in the instruction cache, if it is modeled.
+    pub instruction_cache: Option<CacheStats>,
+
+    /// Hits and misses in the data cache, if it is modeled.
+    pub data_cache: Option<CacheStats>,
+
+    /// Predicted and mispredicted conditional branches, if the branch
+    /// predictor is modeled.
+    pub branch_predictions: Option<PredictionStats>,
+
     /// cycle counts at marked events
+    #[serde(serialize_with = "json::cycle_laps")]
     pub cycle_laps: Vec<(usize, String)>,
 
+    /// Names of the functions the path entered or jumped within, sorted.
+    /// Functions only reached by falling through from the previous function
+    /// are not included.
+    pub reached: Vec<String>,
+
     /// The initial stack pointer for this path.
+    #[serde(skip)]
     pub initial_sp: u64,
+
+    /// All memory written during the path, with concrete values.
+    pub memory_delta: Vec<MemoryDelta>,
+
+    /// The latest writes to the recorded registers, with concrete values.
+    #[serde(skip)]
+    pub register_history: Vec<RegisterWrite>,
+
+    /// Accesses to memory mapped peripherals in program order, with concrete
+    /// values.
+    #[serde(skip)]
+    pub mmio_log: Vec<MmioAccess>,
+
+    /// Backward slice from the failure, if the path failed and failing paths
+    /// are sliced.
+    #[serde(skip)]
+    pub slice: Option<FailureSlice>,
+
+    /// Where the symbols created on the path entered the program.
+    pub provenance: Vec<SymbolProvenance>,
+
+    /// Calls to functions that were filtered out of the analysis, these are
+    /// assumptions the result depends on.
+    #[serde(skip)]
+    pub filtered_calls: Vec<FilteredCall>,
+
+    /// Instructions where not every value of a symbolic address or branch
+    /// target was followed.
+    pub under_approximated: Vec<u64>,
+
+    /// Interrupts that preempted the path in order.
+    #[serde(skip)]
+    pub interrupts: Vec<TakenInterrupt>,
+
+    /// The read-modify-write sequence a handler wrote into, if the path
+    /// failed for it.
+    #[serde(skip)]
+    pub atomicity_violation: Option<AtomicityViolation>,
+
+    /// The non-reentrant function that was reentered, if the path failed for
+    /// it.
+    #[serde(skip)]
+    pub reentry: Option<Reentry>,
+
+    /// Interrupts enabled, pending and active in the NVIC at the end of the
+    /// path, if it is modeled.
+    #[serde(skip)]
+    pub nvic: Vec<(NvicBank, String)>,
+
+    /// Names of the modeled peripherals the path accessed and the states
+    /// they ended in.
+    #[serde(skip)]
+    pub peripherals: Vec<(String, String)>,
+
+    /// Data attached to the path by hooks.
+    #[serde(skip)]
+    pub path_data: PathData,
+
+    /// The path constraints as an SMT-LIB script.
+    pub constraints: String,
 }
 
-fn elf_get_values<'a, I>(vars: I, state: &GAState<impl Arch>) -> Result<Vec<Variable>, GAError>
+/// Decodes the value the function `state` started in returned from the
+/// solved registers in `end_state`, with the return type declared in the
+/// debug data. Returns `None` for functions that return nothing or return the
+/// value in memory.
+///
+/// The contents of returned slices are read from the memory of `state`, solved
+/// with the return registers fixed to their values in `end_state`.
+fn return_value(state: &GAState<impl Arch>, end_state: &[Variable]) -> Option<String> {
+    let return_type = state.project.get_return_type(state.entry)?;
+    if return_type.ty.size()? == 0 {
+        return None;
+    }
+    let mut registers = vec![];
+    for register in state.architecture.return_registers()? {
+        let value = end_state
+            .iter()
+            .find(|var| var.name.as_deref() == Some(*register))
+            .and_then(|var| var.value.get_constant())?;
+        registers.push((*register, value));
+    }
+    let endianness = state.project.get_endianness();
+    let word = state.project.get_word_size() as u64 / 8;
+    let values: Vec<u64> = registers.iter().map(|(_, value)| *value).collect();
+    let bytes = return_type.from_registers(&values, word, &endianness)?;
+
+    let memory = |address: u64, len: u64| {
+        if len == 0 {
+            return Some(vec![]);
+        }
+        state.constraints.push();
+        let contents = read_concrete(state, &registers, address, len);
+        state.constraints.pop();
+        contents
+    };
+    return_type
+        .ty
+        .render_with_memory(&bytes, &endianness, &memory)
+}
+
+/// Solves for the `len` bytes at `address` in the memory of `state`, with the
+/// `registers` fixed to their values.
+fn read_concrete(
+    state: &GAState<impl Arch>,
+    registers: &[(&str, u64)],
+    address: u64,
+    len: u64,
+) -> Option<Vec<u8>> {
+    for (register, value) in registers {
+        let register = state.registers.get(*register)?;
+        let value = state.ctx.from_u64(*value, register.len());
+        state.constraints.assert(&register.eq(&value));
+    }
+    let ptr_size = state.project.get_ptr_size();
+    let mut contents: Option<DExpr> = None;
+    for address in address..address.checked_add(len)? {
+        let byte = state
+            .memory
+            .read(&state.ctx.from_u64(address, ptr_size), 8)
+            .ok()?;
+        contents = Some(match contents {
+            Some(contents) => contents.concat(&byte),
+            None => byte,
+        });
+    }
+    let value = state.constraints.get_value(&contents?).ok()?;
+    Some(MemoryDelta::from_value(address, &value).bytes)
+}
+
+/// Replaces the values of `vars` with the next values of `model`.
+fn elf_get_values<'a, I>(vars: I, model: &mut impl Iterator<Item = DExpr>) -> Vec<Variable>
 where
     I: Iterator<Item = &'a Variable>,
 {
-    let mut results = Vec::new();
-    for var in vars {
-        let constant = state.constraints.get_value(&var.value)?;
-        let var = Variable {
+    vars.zip(model)
+        .map(|(var, value)| Variable {
             name: var.name.clone(),
-            value: constant,
+            value,
             ty: var.ty.clone(),
-        };
-        results.push(var);
-    }
-
-    Ok(results)
+        })
+        .collect()
 }
 
 impl VisualPathResult {
+    /// Returns the concrete values of the variables marked as symbolic that
+    /// reproduce the path, in the order they were marked.
+    pub fn concrete_inputs(&self) -> Vec<ConcreteInput> {
+        self.symbolics
+            .iter()
+            .map(|var| ConcreteInput::new(var.name.clone(), var.ty.clone(), &var.value))
+            .collect()
+    }
+
     /// Creates a result from a state.
     pub fn from_state(
         state: GAState<impl Arch>,
         path_num: usize,
         result: PathStatus,
     ) -> Result<Self, GAError> {
-        let symbolics = elf_get_values(state.marked_symbolic.iter(), &state)?;
         let registers: Vec<Variable> = state
             .registers
             .iter()
@@ -83,17 +280,149 @@ impl VisualPathResult {
                 ty: ExpressionType::Integer(state.project.get_word_size() as usize),
             })
             .collect();
-        let end_state = elf_get_values(registers.iter(), &state)?;
+        let (regions, resolved) = state.written_regions()?;
 
+        // Checked before the marked variables are replaced by their solutions.
+        let path_condition = state.constraints.path_condition();
+        let constrained = state
+            .marked_symbolic
+            .iter()
+            .filter(|var| var.value.shares_symbols(&path_condition))
+            .filter_map(|var| var.name.clone())
+            .collect();
+        let input_ranges = match state.project.get_infer_preconditions() {
+            true => preconditions::input_ranges(&state)?,
+            false => vec![],
+        };
+        let reached: BTreeSet<String> = iter::once(state.entry)
+            .chain(state.jump_targets.iter().copied())
+            .filter_map(|address| state.function_name(address))
+            .collect();
+
+        // Solve for everything that is reported in a single query, this also keeps
+        // the reported values consistent with each other.
+        let values: Vec<DExpr> = state
+            .marked_symbolic
+            .iter()
+            .chain(registers.iter())
+            .map(|var| var.value.clone())
+            .chain(
+                state
+                    .register_history
+                    .iter()
+                    .map(|write| write.value.clone()),
+            )
+            .chain(state.mmio_log.iter().map(|access| access.value.clone()))
+            .chain(regions.iter().map(|(_, value)| value.clone()))
+            .collect();
+        let constraints = state.constraints.to_smtlib();
+        state.constraints.push();
+        state.constraints.assert(&resolved);
+        let model = state.constraints.get_model(&values);
+        state.constraints.pop();
+        let mut model = model?.into_iter();
+        let symbolics = elf_get_values(state.marked_symbolic.iter(), &mut model);
+        let end_state = elf_get_values(registers.iter(), &mut model);
+        let register_roles = end_state
+            .iter()
+            .filter_map(|var| {
+                let name = var.name.as_ref()?;
+                Some((name.clone(), state.architecture.register_role(name)?))
+            })
+            .collect();
+        let return_value = match result {
+            PathStatus::Ok(None) => return_value(&state, &end_state),
+            _ => None,
+        };
+        let register_history = state
+            .register_history
+            .iter()
+            .zip(&mut model)
+            .map(|(write, value)| RegisterWrite {
+                value,
+                ..write.clone()
+            })
+            .collect();
+        let mmio_log = state
+            .mmio_log
+            .iter()
+            .zip(&mut model)
+            .map(|(access, value)| MmioAccess {
+                value,
+                ..access.clone()
+            })
+            .collect();
+        let memory_delta = regions
+            .iter()
+            .zip(model)
+            .map(|((address, _), value)| {
+                MemoryDelta::from_value(*address, &value).with_locals(&state)
+            })
+            .collect();
+        let slice = match &result {
+            PathStatus::Failed(_) => state.data_flow.slice().map(|slice| FailureSlice {
+                instructions: slice
+                    .instructions
+                    .into_iter()
+                    .map(|(instruction, pc)| SliceInstruction {
+                        instruction,
+                        pc,
+                        source: state.project.get_source_location(pc).cloned(),
+                    })
+                    .collect(),
+                inputs: slice.inputs,
+            }),
+            PathStatus::Ok(_) | PathStatus::Unknown(_) | PathStatus::Truncated(_) => None,
+        };
         Ok(VisualPathResult {
             path: path_num,
             result,
             symbolics,
+            constrained,
+            input_ranges,
             end_state,
+            register_roles,
+            return_value,
             instruction_count: state.get_instruction_count(),
             initial_sp: state.inital_sp,
             max_cycles: state.cycle_count,
+            energy: state.project.get_energy_model().map(|_| state.energy),
+            instruction_cache: state
+                .project
+                .get_instruction_cache()
+                .map(|_| state.instruction_cache.stats()),
+            data_cache: state
+                .project
+                .get_data_cache()
+                .map(|_| state.data_cache.stats()),
+            branch_predictions: state
+                .project
+                .get_branch_predictor()
+                .map(|_| state.branch_predictions.stats()),
             cycle_laps: state.cycle_laps.clone(),
+            reached: reached.into_iter().collect(),
+            memory_delta,
+            register_history,
+            mmio_log,
+            slice,
+            provenance: state.provenance.clone(),
+            filtered_calls: state.filtered_calls.clone(),
+            under_approximated: state.under_approximated.clone(),
+            interrupts: state.interrupts.taken.clone(),
+            atomicity_violation: state.interrupts.violation.clone(),
+            reentry: state.reentrancy.reentry.clone(),
+            nvic: state.interrupts.nvic.describe(),
+            peripherals: state
+                .peripherals
+                .states()
+                .into_iter()
+                .map(|(index, current)| {
+                    let model = &state.project.get_peripheral_models()[index];
+                    (model.name.clone(), model.states[current].clone())
+                })
+                .collect(),
+            path_data: state.path_data,
+            constraints,
         })
     }
 }
@@ -106,16 +435,30 @@ impl fmt::Display for VisualPathResult {
             self.path
         )?;
 
-        match &self.result {
-            PathStatus::Ok(None) => {
+        match (&self.result, &self.return_value) {
+            (PathStatus::Ok(None), Some(value)) => {
+                writeln!(f, "{}: returned {}", "Success".green(), value)?;
+            }
+            (PathStatus::Ok(None), None) => {
                 writeln!(f, "{}: returned void", "Success".green())?;
             }
-            PathStatus::Ok(Some(value)) => {
+            (PathStatus::Ok(Some(value)), _) => {
                 writeln!(f, "{}: returned {}", "Success".green(), value)?;
             }
-            PathStatus::Failed(err) => {
+            (PathStatus::Failed(err), _) => {
                 writeln!(f, "{}: {}", "Error".red(), err.error_message)?;
             }
+            (PathStatus::Unknown(query), _) => {
+                writeln!(
+                    f,
+                    "{}: the solver could not decide {}",
+                    "Unknown".yellow(),
+                    query
+                )?;
+            }
+            (PathStatus::Truncated(truncation), _) => {
+                writeln!(f, "{}: the path {}", "Truncated".yellow(), truncation)?;
+            }
         }
 
         if !self.symbolics.is_empty() {
@@ -142,18 +485,336 @@ impl fmt::Display for VisualPathResult {
             state.sort_by(|a, b| sort_respect_numbers(&a.0, &b.0));
 
             for (name, value) in state.iter() {
-                writeln!(indented(f), "{name}: {value}")?;
+                match self.register_roles.get(name) {
+                    Some(role) => writeln!(indented(f), "{name} ({role}): {value}")?,
+                    None => writeln!(indented(f), "{name}: {value}")?,
+                }
+            }
+        }
+
+        if !self.memory_delta.is_empty() {
+            writeln!(f, "\nMemory written:")?;
+            for delta in &self.memory_delta {
+                writeln!(indented(f), "{delta}")?;
+            }
+        }
+
+        if !self.register_history.is_empty() {
+            writeln!(f, "\nRegister writes:")?;
+            for write in &self.register_history {
+                let value = match write.value.get_constant() {
+                    Some(value) => format!("{value:#X}"),
+                    None => "symbolic".to_owned(),
+                };
+                writeln!(
+                    indented(f),
+                    "#{} {:#010X}: {} = {}",
+                    write.instruction,
+                    write.pc,
+                    write.register,
+                    value
+                )?;
+            }
+        }
+
+        if !self.mmio_log.is_empty() {
+            writeln!(f, "\nPeripheral accesses:")?;
+            for access in &self.mmio_log {
+                let value = match access.value.get_constant() {
+                    Some(value) => format!("{value:#X}"),
+                    None => "symbolic".to_owned(),
+                };
+                let kind = match access.kind {
+                    AccessKind::Read => "read",
+                    AccessKind::Write => "write",
+                };
+                writeln!(
+                    indented(f),
+                    "#{} {:#010X}: {} {:#010X} = {}",
+                    access.instruction,
+                    access.pc,
+                    kind,
+                    access.address,
+                    value
+                )?;
             }
         }
 
+        if !self.provenance.is_empty() {
+            writeln!(f, "\nSymbol origins:")?;
+            for symbol in &self.provenance {
+                writeln!(
+                    indented(f),
+                    "{}: {} after {:#010X}",
+                    symbol.name,
+                    symbol.origin,
+                    symbol.pc
+                )?;
+            }
+        }
+
+        if !self.filtered_calls.is_empty() {
+            writeln!(f, "\nAssumptions:")?;
+            for call in &self.filtered_calls {
+                let function = call.function.as_deref().unwrap_or("unknown function");
+                match call.return_address {
+                    Some(address) => writeln!(
+                        indented(f),
+                        "{function} {}, called from {address:#010X}",
+                        call.action
+                    )?,
+                    None => writeln!(indented(f), "{function} {}", call.action)?,
+                }
+            }
+        }
+
+        if !self.under_approximated.is_empty() {
+            writeln!(f, "\nUnder-approximated at:")?;
+            for pc in &self.under_approximated {
+                writeln!(indented(f), "{pc:#010X}")?;
+            }
+        }
+
+        if !self.interrupts.is_empty() {
+            writeln!(f, "\nInterrupts:")?;
+            for interrupt in &self.interrupts {
+                writeln!(
+                    indented(f),
+                    "{} before {:#010X}",
+                    interrupt.handler,
+                    interrupt.pc
+                )?;
+            }
+        }
+
+        if let Some(violation) = &self.atomicity_violation {
+            writeln!(f, "\nAtomicity violation:")?;
+            writeln!(
+                indented(f),
+                "{} at {:#010X} wrote {:#010X} between the load at {:#010X} and the store at {:#010X}",
+                violation.handler,
+                violation.write,
+                violation.address,
+                violation.load,
+                violation.store
+            )?;
+        }
+
+        if let Some(reentry) = &self.reentry {
+            let address = |address: Option<u64>| match address {
+                Some(address) => format!("{address:#010X}"),
+                None => "<unknown>".to_owned(),
+            };
+            writeln!(f, "\nReentered:")?;
+            writeln!(
+                indented(f),
+                "{} returning to {} before it returned to {}",
+                reentry.function,
+                address(reentry.second),
+                address(reentry.first)
+            )?;
+        }
+
+        if !self.nvic.is_empty() {
+            writeln!(f, "\nNVIC:")?;
+            for (bank, interrupts) in &self.nvic {
+                writeln!(indented(f), "{bank}: {interrupts}")?;
+            }
+        }
+
+        if !self.peripherals.is_empty() {
+            writeln!(f, "\nPeripherals:")?;
+            for (name, state) in &self.peripherals {
+                writeln!(indented(f), "{name}: {state}")?;
+            }
+        }
+
+        if !self.path_data.is_empty() {
+            writeln!(f, "\nPath data:")?;
+            for (name, value) in self.path_data.iter() {
+                writeln!(indented(f), "{name}: {value:?}")?;
+            }
+        }
+
+        if let Some(slice) = &self.slice {
+            writeln!(f, "\nSlice of the failure:")?;
+            write!(indented(f), "{slice}")?;
+        }
+
         writeln!(f, "Instructions executed: {}", self.instruction_count)?;
 
-        writeln!(f, "Max number of cycles: {}", self.max_cycles)?;
+        match self.result {
+            PathStatus::Truncated(_) => {
+                writeln!(f, "Max number of cycles: at least {}", self.max_cycles)?
+            }
+            _ => writeln!(f, "Max number of cycles: {}", self.max_cycles)?,
+        }
 
+        if let Some(energy) = self.energy {
+            writeln!(f, "Energy: {energy:.3} nJ")?;
+        }
+
+        if let Some(stats) = self.instruction_cache {
+            writeln!(f, "Instruction cache: {stats}")?;
+        }
+
+        if let Some(stats) = self.data_cache {
+            writeln!(f, "Data cache: {stats}")?;
+        }
+
+        if let Some(stats) = self.branch_predictions {
+            writeln!(f, "Branches: {stats}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A contiguous region of memory written during a path.
+///
+/// The bytes are concrete values taken from a model of the path constraints.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryDelta {
+    /// Address of the first byte in the region.
+    pub address: u64,
+
+    /// The bytes in the region, in ascending address order.
+    pub bytes: Vec<u8>,
+
+    /// Locals on the stack that start in the region, see
+    /// [`stack_locals`](crate::general_assembly::stack_locals).
+    pub locals: Vec<StackLocal>,
+}
+
+impl MemoryDelta {
+    /// Creates a region starting at `address` from a concrete value that holds
+    /// the byte at the lowest address as its most significant byte.
+    pub fn from_value(address: u64, value: &DExpr) -> Self {
+        let bits = value.to_binary_string();
+        let bytes = bits
+            .as_bytes()
+            .chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .fold(0u8, |acc, bit| (acc << 1) | (*bit == b'1') as u8)
+            })
+            .collect();
+        Self {
+            address,
+            bytes,
+            locals: vec![],
+        }
+    }
+
+    /// Names the locals on the stack that were written in the region.
+    pub fn with_locals<A: Arch>(mut self, state: &GAState<A>) -> Self {
+        self.locals = state.stack_locals_in(self.address, self.bytes.len() as u64);
+        self
+    }
+}
+
+impl fmt::Display for MemoryDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BYTES_PER_LINE: usize = 16;
+        for (n, line) in self.bytes.chunks(BYTES_PER_LINE).enumerate() {
+            let address = self.address + (n * BYTES_PER_LINE) as u64;
+            let line = line
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if n != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{address:#010x}: {line}")?;
+        }
+        for local in &self.locals {
+            let start = (local.address - self.address) as usize;
+            let end = (start + local.size.max(1) as usize).min(self.bytes.len());
+            let bytes = self.bytes[start..end]
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let name = match local.offset {
+                0 => local.name.clone(),
+                offset => format!("{}+{offset:#X}", local.name),
+            };
+            write!(f, "\n  {name} @ {:#010x}: {bytes}", local.address)?;
+        }
+        Ok(())
+    }
+}
+
+/// Backward slice from the failure of a path, see
+/// [`slice`](crate::general_assembly::slice).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureSlice {
+    /// The instructions in the slice, in execution order.
+    pub instructions: Vec<SliceInstruction>,
+
+    /// Locations that influenced the failure and were not written on the path.
+    pub inputs: Vec<Location>,
+}
+
+/// An instruction in a [`FailureSlice`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceInstruction {
+    /// Index of the instruction on the path.
+    pub instruction: usize,
+
+    /// Address of the instruction.
+    pub pc: u64,
+
+    /// Source location of the instruction, if available.
+    pub source: Option<SourceLocation>,
+}
+
+impl fmt::Display for FailureSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instruction in &self.instructions {
+            write!(f, "#{} {:#010X}", instruction.instruction, instruction.pc)?;
+            match &instruction.source {
+                Some(source) => writeln!(f, " at {source}")?,
+                None => writeln!(f)?,
+            }
+        }
+
+        // Adjacent bytes of memory are shown as a single range.
+        let mut inputs: Vec<String> = vec![];
+        let mut range: Option<(u64, u64)> = None;
+        for input in &self.inputs {
+            match (input, range) {
+                (Location::Memory(address), Some((start, end))) if *address == end + 1 => {
+                    range = Some((start, *address));
+                    continue;
+                }
+                (Location::Memory(address), _) => {
+                    if let Some((start, end)) = range.replace((*address, *address)) {
+                        inputs.push(memory_range(start, end));
+                    }
+                    continue;
+                }
+                _ => inputs.push(input.to_string()),
+            }
+        }
+        if let Some((start, end)) = range {
+            inputs.push(memory_range(start, end));
+        }
+        if !inputs.is_empty() {
+            writeln!(f, "Inputs: {}", inputs.join(", "))?;
+        }
         Ok(())
     }
 }
 
+fn memory_range(start: u64, end: u64) -> String {
+    match start == end {
+        true => format!("mem {start:#X}"),
+        false => format!("mem {start:#X}..={end:#X}"),
+    }
+}
+
 /// Status of the path.
 ///
 /// If the path succeeded the return value (if any) is contained in that
@@ -166,6 +827,14 @@ pub enum PathStatus {
 
     /// The path failed.
     Failed(ErrorReason),
+
+    /// The solver could not decide a query of the path, such as when it timed
+    /// out. Holds a description of the query.
+    Unknown(String),
+
+    /// The path exceeded the budget of the exploration and was ended before
+    /// it finished, so its cycle count is a lower bound.
+    Truncated(Truncation),
 }
 
 /// Detailed description of why a run failed.
@@ -220,9 +889,80 @@ impl fmt::Display for Variable {
     }
 }
 
+/// Concrete value of a variable marked as symbolic, taken from a model of the
+/// path constraints. Running the program with these values reproduces the
+/// path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConcreteInput {
+    /// `name` is the source name of the variable, if it exists.
+    pub name: Option<String>,
+
+    /// Simple representation of the variable.
+    pub ty: ExpressionType,
+
+    /// Size of the variable in bits.
+    pub bits: u32,
+
+    /// The bytes of the value in little endian order, as the variable is
+    /// stored in memory. The last byte is padded with zeroes if the size is not
+    /// a whole number of bytes.
+    pub bytes: Vec<u8>,
+}
+
+impl ConcreteInput {
+    /// Creates the input of the variable `name` from the concrete `value`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `value` is not a constant.
+    pub fn new(name: Option<String>, ty: ExpressionType, value: &DExpr) -> Self {
+        let raw = value.to_binary_string();
+        let padded = format!("{raw:0>width$}", width = raw.len().div_ceil(8) * 8);
+        let bytes = padded
+            .as_bytes()
+            .rchunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .fold(0u8, |acc, bit| (acc << 1) | (*bit == b'1') as u8)
+            })
+            .collect();
+        Self {
+            name,
+            ty,
+            bits: value.len(),
+            bytes,
+        }
+    }
+
+    /// Returns the value as an unsigned integer, if it fits in 128 bits.
+    pub fn as_u128(&self) -> Option<u128> {
+        (self.bytes.len() <= 16).then(|| {
+            self.bytes
+                .iter()
+                .rev()
+                .fold(0, |acc, byte| (acc << 8) | *byte as u128)
+        })
+    }
+}
+
+impl fmt::Display for ConcreteInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.name.as_deref().unwrap_or("_"))?;
+        if let Some(value) = self.as_u128() {
+            write!(f, "{value:#x} ")?;
+        }
+        let bytes: Vec<String> = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        write!(f, "[{}]", bytes.join(" "))
+    }
+}
+
 /// Type information for a an expression. This should be generic enough for all
 /// kinds of executor to support.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum ExpressionType {
     /// Integer value of a certain size in bits.
     Integer(usize),
@@ -474,7 +1214,27 @@ impl<I: Iterator<Item = char>> Iterator for Tokenizer<I> {
 }
 #[cfg(test)]
 mod tests {
-    use super::TypedVariable;
+    use std::sync::Arc;
+
+    use super::{
+        ConcreteInput,
+        ExpressionType,
+        MemoryDelta,
+        StackLocal,
+        TypedVariable,
+        VisualPathResult,
+    };
+    use crate::{
+        general_assembly::{
+            arch::arm::v6::ArmV6M,
+            executor::PathResult,
+            project::Project,
+            state::GAState,
+            vm::VM,
+            RunConfig,
+        },
+        smt::{DContext, Solutions},
+    };
 
     #[test]
     fn i64_works() {
@@ -490,4 +1250,98 @@ mod tests {
         let s = format!("{typed_variable}");
         assert_eq!(s, "0b1 (1-bit)");
     }
+
+    #[test]
+    fn concrete_input() {
+        let ctx = DContext::new();
+        let name = Some("len".to_owned());
+        let input = ConcreteInput::new(
+            name.clone(),
+            ExpressionType::Integer(32),
+            &ctx.from_u64(0x12c, 32),
+        );
+        assert_eq!(input.bytes, vec![0x2c, 0x01, 0x00, 0x00]);
+        assert_eq!(input.as_u128(), Some(0x12c));
+        assert_eq!(format!("{input}"), "len: 0x12c [2c 01 00 00]");
+
+        // The most significant byte is padded.
+        let input = ConcreteInput::new(name, ExpressionType::Integer(12), &ctx.from_u64(0xabc, 12));
+        assert_eq!((input.bits, input.bytes), (12, vec![0xbc, 0x0a]));
+    }
+
+    #[test]
+    fn memory_delta_display() {
+        let mut delta = MemoryDelta {
+            address: 0x2000_0000,
+            bytes: (0..18).collect(),
+            locals: vec![],
+        };
+        let s = format!("{delta}");
+        assert_eq!(
+            s,
+            "0x20000000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n0x20000010: 10 11"
+        );
+
+        delta.bytes.truncate(4);
+        delta.locals.push(StackLocal {
+            name: "app::parse::len".to_owned(),
+            address: 0x2000_0002,
+            size: 4,
+            offset: 0,
+        });
+        let s = format!("{delta}");
+        assert_eq!(
+            s,
+            "0x20000000: 00 01 02 03\n  app::parse::len @ 0x20000002: 02 03"
+        );
+    }
+
+    #[test]
+    fn memory_delta_of_symbolic_write() {
+        let project = Arc::new(Project::test_project());
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+
+        // The address is written to itself, so the value in the delta shows
+        // which address it was solved for.
+        let address = context.unconstrained(32, "address");
+        let first = address.eq(&context.from_u64(0x2000_0000, 32));
+        let second = address.eq(&context.from_u64(0x2000_0010, 32));
+        state.constraints.assert(&first.or(&second));
+        state
+            .write_word_to_memory(&address, address.clone())
+            .unwrap();
+
+        // The value of the region is only known once the address is.
+        let (regions, resolved) = state.written_regions().unwrap();
+        assert_eq!(regions.len(), 1);
+        let value = &regions[0].1;
+        let solutions = |state: &GAState<ArmV6M>| match state.constraints.get_values(value, 2) {
+            Ok(Solutions::Exactly(solutions)) => solutions.len(),
+            _ => usize::MAX,
+        };
+        assert_eq!(solutions(&state), usize::MAX);
+        state.constraints.push();
+        state.constraints.assert(&resolved);
+        assert_eq!(solutions(&state), 1);
+        state.constraints.pop();
+
+        let delta = state.memory_delta().unwrap();
+        assert_eq!(delta.len(), 1);
+        let bytes: [u8; 4] = delta[0].bytes.clone().try_into().unwrap();
+        assert_eq!(u32::from_le_bytes(bytes) as u64, delta[0].address);
+        assert_eq!(state.constraints.constraint_count(), 1);
+    }
+
+    #[test]
+    fn states_and_results_are_send() {
+        fn send<T: Send>() {}
+        fn sync<T: Sync>() {}
+        send::<GAState<ArmV6M>>();
+        send::<VM<ArmV6M>>();
+        send::<PathResult>();
+        send::<VisualPathResult>();
+        sync::<Project<ArmV6M>>();
+        sync::<RunConfig<ArmV6M>>();
+    }
 }
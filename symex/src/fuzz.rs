@@ -0,0 +1,85 @@
+//! Entry points for fuzzing the instruction decoders and the executor.
+//!
+//! Each entry point decodes the input bytes as an instruction for the given
+//! architecture and, if decoding succeeds, executes it on a trivial state.
+//! Errors are expected, panics are bugs.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    general_assembly::{
+        arch::{
+            arm::{v6::ArmV6M, v7::ArmV7EM},
+            Arch,
+        },
+        executor::GAExecutor,
+        project::Project,
+        state::GAState,
+        vm::VM,
+        Endianness,
+        WordSize,
+    },
+    smt::{DContext, DSolver},
+};
+
+thread_local! {
+    static CONTEXT: DContext = DContext::new();
+}
+
+/// Decode and execute `data` as an ARMv6-M instruction.
+pub fn armv6m(data: &[u8]) {
+    static PROJECT: OnceLock<Arc<Project<ArmV6M>>> = OnceLock::new();
+    let project = PROJECT.get_or_init(|| Arc::new(empty_project()));
+    run(project.clone(), ArmV6M {}, data);
+}
+
+/// Decode and execute `data` as an ARMv7E-M instruction.
+pub fn armv7em(data: &[u8]) {
+    static PROJECT: OnceLock<Arc<Project<ArmV7EM>>> = OnceLock::new();
+    let project = PROJECT.get_or_init(|| Arc::new(empty_project()));
+    run(project.clone(), ArmV7EM {}, data);
+}
+
+fn empty_project<A: Arch>() -> Project<A> {
+    Project::manual_project(
+        vec![],
+        0,
+        0,
+        WordSize::Bit32,
+        Endianness::Little,
+        HashMap::new(),
+        HashMap::new(),
+        HashMap::new(),
+        HashMap::new(),
+        HashMap::new(),
+        vec![],
+        HashMap::new(),
+        vec![],
+    )
+}
+
+fn run<A: Arch>(project: Arc<Project<A>>, architecture: A, data: &[u8]) {
+    let context = CONTEXT.with(DContext::clone);
+    let solver = DSolver::new(&context);
+    let state = GAState::create_test_state(
+        project.clone(),
+        &context,
+        solver,
+        0,
+        u32::MAX as u64,
+        architecture.clone(),
+    );
+
+    let instruction = match architecture.translate(data, false) {
+        Ok(instruction) => instruction,
+        Err(_) => return,
+    };
+
+    let mut vm = VM::new_with_state(project.clone(), state);
+    let state = vm.paths.get_path().unwrap().state;
+    let mut executor = GAExecutor::from_state(state, &mut vm, project);
+    let _ = executor.execute_instruction(&instruction);
+}
@@ -0,0 +1,533 @@
+//! Acceleration of counting loops.
+//!
+//! A loop header is detected when a path jumps backwards to it. The next
+//! iteration is observed and every register that changed by a constant step is
+//! taken to be an induction variable. The induction variables are then widened
+//! to `value + k * step` for a fresh iteration count `k`, which represents any
+//! number of iterations in a single path, so delay loops and other counting
+//! loops with a symbolic trip count no longer fork once per iteration.
+//!
+//! Loops that store to an address that advances with an induction variable,
+//! such as `memset`, are accelerated as well if the element is stored with the
+//! same value every iteration. The stores of the skipped iterations are
+//! represented by a [`LoopFill`] that reads of the filled elements return until
+//! they are overwritten. Loops with any other store are not accelerated.
+//!
+//! The widening is checked inductively: when the widened path returns to the
+//! header every induction variable must have advanced by exactly one step, all
+//! other registers must be unchanged and the iteration must have made the same
+//! stores as the observed iteration, one element further. Otherwise the
+//! widening is dropped, the path continues from the state before it and the
+//! loop is unrolled as usual. The paths that left the loop from the widened
+//! state are kept.
+//!
+//! The cycles of the accelerated iterations are not counted.
+
+use std::collections::{BTreeSet, HashMap};
+
+use tracing::debug;
+
+use super::{
+    arch::Arch,
+    executor::{GAExecutor, PathResult},
+    state::{GAState, SymbolOrigin},
+    Endianness,
+    Result,
+};
+use crate::{
+    elf_util::{ExpressionType, Variable},
+    smt::{DContext, DExpr},
+};
+
+/// Largest distance between the address of a store and the induction variable
+/// it is relative to.
+const MAX_STORE_OFFSET: i64 = 4096;
+
+/// A store made by an iteration of a loop.
+#[derive(Debug, Clone)]
+pub struct LoopStore {
+    address: u64,
+    bits: u32,
+    value: DExpr,
+}
+
+/// A store of every iteration to an induction variable plus a constant offset.
+#[derive(Debug, Clone)]
+pub struct StorePattern {
+    register: String,
+    offset: i64,
+    bits: u32,
+    value: DExpr,
+}
+
+/// The stores of the iterations skipped by the acceleration of a loop with a
+/// [`StorePattern`]: `count` elements of `bytes` bytes holding `value`, the
+/// first at `start` and each following element `step` bytes after the
+/// previous.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoopFill {
+    start: u64,
+    step: u64,
+    bytes: u64,
+    count: DExpr,
+    value: DExpr,
+    /// Bytes written since the fill, they no longer hold the filled value.
+    overwritten: BTreeSet<u64>,
+}
+
+impl LoopFill {
+    /// Records that the `bytes` bytes at `address` were written.
+    pub fn overwrite(&mut self, address: u64, bytes: u64) {
+        self.overwritten.extend(address..address + bytes);
+    }
+
+    /// Returns `value`, the byte read from `address`, with the filled value if
+    /// the address is in a filled element.
+    fn read_byte(
+        &self,
+        ctx: &DContext,
+        address: u64,
+        value: DExpr,
+        endianness: &Endianness,
+    ) -> DExpr {
+        if address < self.start || self.overwritten.contains(&address) {
+            return value;
+        }
+        let element = (address - self.start) / self.step;
+        let offset = (address - self.start) % self.step;
+        if offset >= self.bytes {
+            return value;
+        }
+        let byte = match endianness {
+            Endianness::Little => offset,
+            Endianness::Big => self.bytes - 1 - offset,
+        } as u32;
+        let filled = self.value.slice(8 * byte, 8 * byte + 7);
+        let in_fill = self.count.ugt(&ctx.from_u64(element, self.count.len()));
+        in_fill.ite(&filled, &value).simplify()
+    }
+}
+
+/// Progress of the acceleration of a loop on a path.
+#[derive(Debug, Clone)]
+pub enum LoopAcceleration<A: Arch> {
+    /// The header has been reached through a back edge, the next iteration is
+    /// observed.
+    Observed {
+        /// Registers at the header.
+        registers: HashMap<String, DExpr>,
+
+        /// Stores made since the header was reached.
+        stores: Vec<LoopStore>,
+    },
+
+    /// The induction variables have been widened.
+    Widened {
+        /// Registers at the header after the widening.
+        registers: HashMap<String, DExpr>,
+
+        /// Step of each induction variable.
+        steps: HashMap<String, DExpr>,
+
+        /// Stores every iteration makes.
+        patterns: Vec<StorePattern>,
+
+        /// Stores made since the widening.
+        stores: Vec<LoopStore>,
+
+        /// The state before the widening, the path continues from it if the
+        /// widening is not inductive.
+        fallback: Box<GAState<A>>,
+    },
+
+    /// The loop is not a counting loop.
+    Rejected,
+}
+
+impl<A: Arch> LoopAcceleration<A> {
+    /// Records that `bits` of `value` were stored at `address`.
+    pub fn record_store(&mut self, address: u64, bits: u32, value: &DExpr) {
+        match self {
+            Self::Observed { stores, .. } | Self::Widened { stores, .. } => {
+                stores.push(LoopStore {
+                    address,
+                    bits,
+                    value: value.clone(),
+                })
+            }
+            Self::Rejected => {}
+        }
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Accelerates the loop with the header at the program counter, if any.
+    ///
+    /// Returns a result if the path should end.
+    pub(super) fn accelerate_loop(&mut self) -> Result<Option<PathResult>> {
+        let pc = match self.state.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Ok(None),
+        };
+        let back_edge = self.state.get_has_jumped() && self.state.last_pc >= pc;
+        if !back_edge {
+            return Ok(None);
+        }
+
+        let registers = self.state.registers.clone();
+        match self.state.loop_accelerations.remove(&pc) {
+            None => {
+                self.state
+                    .loop_accelerations
+                    .insert(pc, LoopAcceleration::Observed {
+                        registers,
+                        stores: vec![],
+                    });
+            }
+            Some(LoopAcceleration::Observed {
+                registers: before,
+                stores,
+            }) => {
+                let acceleration = match induction_steps(&before, &registers)
+                    .and_then(|steps| Some((store_patterns(&before, &steps, &stores)?, steps)))
+                {
+                    Some((patterns, steps)) => self.widen(pc, steps, patterns)?,
+                    None => LoopAcceleration::Rejected,
+                };
+                self.state.loop_accelerations.insert(pc, acceleration);
+            }
+            Some(LoopAcceleration::Widened {
+                registers: before,
+                steps,
+                patterns,
+                stores,
+                fallback,
+            }) => {
+                if !self.is_inductive(&before, &registers, &steps, &patterns, &stores)? {
+                    debug!(
+                        "Acceleration of the loop at {:#X} is not inductive, unrolling it",
+                        pc
+                    );
+                    self.state = *fallback;
+                    return Ok(None);
+                }
+                debug!("Acceleration of the loop at {:#X} is inductive", pc);
+                return Ok(Some(PathResult::Suppress));
+            }
+            Some(LoopAcceleration::Rejected) => {
+                self.state
+                    .loop_accelerations
+                    .insert(pc, LoopAcceleration::Rejected);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns true if the widened iteration from `before` to `after` advanced
+    /// every induction variable by its step, left the other registers
+    /// unchanged and made the stores of `patterns` one element further.
+    fn is_inductive(
+        &self,
+        before: &HashMap<String, DExpr>,
+        after: &HashMap<String, DExpr>,
+        steps: &HashMap<String, DExpr>,
+        patterns: &[StorePattern],
+        stores: &[LoopStore],
+    ) -> Result<bool> {
+        if patterns.len() != stores.len() {
+            return Ok(false);
+        }
+        let ptr_size = self.project.get_ptr_size();
+        let mask = ((1u128 << ptr_size) - 1) as u64;
+        for (pattern, store) in patterns.iter().zip(stores) {
+            let Some(base) = before.get(&pattern.register) else {
+                return Ok(false);
+            };
+            let offset = self
+                .state
+                .ctx
+                .from_u64(pattern.offset as u64 & mask, ptr_size);
+            let expected = base.add(&offset);
+            let address = self.state.ctx.from_u64(store.address, ptr_size);
+            if pattern.bits != store.bits
+                || !self.state.constraints.must_be_equal(&expected, &address)?
+                || !self
+                    .state
+                    .constraints
+                    .must_be_equal(&pattern.value, &store.value)?
+            {
+                return Ok(false);
+            }
+        }
+        for (register, value) in after {
+            if register == "PC" {
+                continue;
+            }
+            let expected = match (before.get(register), steps.get(register)) {
+                (Some(before), Some(step)) => before.add(step),
+                (Some(before), None) => before.clone(),
+                (None, _) => continue,
+            };
+            if !self.state.constraints.must_be_equal(&expected, value)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Widens the induction variables of the loop with the header at `pc`.
+    fn widen(
+        &mut self,
+        pc: u64,
+        steps: HashMap<String, DExpr>,
+        patterns: Vec<StorePattern>,
+    ) -> Result<LoopAcceleration<A>> {
+        debug!("Accelerating the loop at {:#X}", pc);
+        let mut fallback = self.state.clone();
+        fallback
+            .loop_accelerations
+            .insert(pc, LoopAcceleration::Rejected);
+
+        let word_size = self.project.get_word_size();
+        let name = format!("loop_iterations_{pc:#X}");
+        let iterations = self.state.new_symbol(word_size, &name, SymbolOrigin::Havoc);
+        self.state.marked_symbolic.push(Variable {
+            name: Some(name),
+            value: iterations.clone(),
+            ty: ExpressionType::Integer(word_size as usize),
+        });
+        let address_space = 1u128 << self.project.get_ptr_size();
+        for pattern in &patterns {
+            let start = self.state.registers[&pattern.register]
+                .get_constant()
+                .expect("stores are relative to concrete registers")
+                .wrapping_add(pattern.offset as u64)
+                & (address_space - 1) as u64;
+            let step = steps[&pattern.register]
+                .get_constant()
+                .expect("steps are constant");
+            // The filled elements do not wrap around the address space.
+            let max = ((address_space - start as u128) / step as u128).min(u64::MAX as u128);
+            let max = self.state.ctx.from_u64(max as u64, word_size);
+            self.state.constraints.assert(&iterations.ulte(&max));
+            self.state.loop_fills.push(LoopFill {
+                start,
+                step,
+                bytes: (pattern.bits / 8) as u64,
+                count: iterations.clone(),
+                value: pattern.value.clone(),
+                overwritten: BTreeSet::new(),
+            });
+        }
+        for (register, step) in &steps {
+            if let Some(value) = self.state.registers.get(register) {
+                let widened = value.add(&iterations.mul(step)).simplify();
+                self.state.registers.insert(register.to_owned(), widened);
+            }
+        }
+        // The flags at the header depend on the iteration.
+        for flag in ["N", "Z", "C", "V"] {
+            let value = self.state.new_symbol(
+                1,
+                &format!("flags.{flag}_loop_{pc:#X}"),
+                SymbolOrigin::Havoc,
+            );
+            self.state.set_flag(flag.to_owned(), value)?;
+        }
+        Ok(LoopAcceleration::Widened {
+            registers: self.state.registers.clone(),
+            steps,
+            patterns,
+            stores: vec![],
+            fallback: Box::new(fallback),
+        })
+    }
+
+    /// Returns `value`, read from `address`, with the bytes in the fills of
+    /// the accelerated loops replaced by the filled values.
+    pub(super) fn read_loop_fills(&self, address: u64, bits: u32, value: DExpr) -> DExpr {
+        if self.state.loop_fills.is_empty() || !bits.is_multiple_of(8) {
+            return value;
+        }
+        let endianness = self.project.get_endianness();
+        let bytes = bits / 8;
+        let mut result: Option<DExpr> = None;
+        for n in 0..bytes {
+            let position = match &endianness {
+                Endianness::Little => n,
+                Endianness::Big => bytes - 1 - n,
+            };
+            let byte = address + n as u64;
+            let mut read = value.slice(8 * position, 8 * position + 7);
+            for fill in &self.state.loop_fills {
+                read = fill.read_byte(&self.state.ctx, byte, read, &endianness);
+            }
+            // Assemble the value with the most significant byte first.
+            result = Some(match (result, &endianness) {
+                (None, _) => read,
+                (Some(result), Endianness::Little) => read.concat(&result),
+                (Some(result), Endianness::Big) => result.concat(&read),
+            });
+        }
+        result.unwrap_or(value)
+    }
+}
+
+/// Returns the pattern of every store in `stores`, made by an iteration that
+/// started with `before`, `None` if a store does not advance with an
+/// induction variable.
+fn store_patterns(
+    before: &HashMap<String, DExpr>,
+    steps: &HashMap<String, DExpr>,
+    stores: &[LoopStore],
+) -> Option<Vec<StorePattern>> {
+    let mut registers: Vec<_> = steps.keys().collect();
+    registers.sort();
+    stores
+        .iter()
+        .map(|store| {
+            let register = registers.iter().find(|register| {
+                let (Some(base), Some(step)) = (
+                    before[**register].get_constant(),
+                    steps[**register].get_constant(),
+                ) else {
+                    return false;
+                };
+                let offset = store.address.wrapping_sub(base) as i64;
+                // Elements of different iterations do not overlap.
+                (-MAX_STORE_OFFSET..MAX_STORE_OFFSET).contains(&offset)
+                    && store.bits.is_multiple_of(8)
+                    && step >= (store.bits / 8) as u64
+                    && step < 1 << 31
+            })?;
+            let base = before[*register].get_constant()?;
+            Some(StorePattern {
+                register: (**register).to_owned(),
+                offset: store.address.wrapping_sub(base) as i64,
+                bits: store.bits,
+                value: store.value.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the step of every register that changed by a constant between
+/// `before` and `after`, `None` if a register changed by something else.
+fn induction_steps(
+    before: &HashMap<String, DExpr>,
+    after: &HashMap<String, DExpr>,
+) -> Option<HashMap<String, DExpr>> {
+    let mut steps = HashMap::new();
+    for (register, value) in after {
+        if register == "PC" || before.get(register) == Some(value) {
+            continue;
+        }
+        let step = value.sub(before.get(register)?).simplify();
+        match step.get_constant()? {
+            0 => {}
+            _ => {
+                steps.insert(register.to_owned(), step);
+            }
+        }
+    }
+    match steps.is_empty() {
+        true => None,
+        false => Some(steps),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::{GAExecutor, PathResult},
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    #[test]
+    fn test_accelerate_counting_loop() {
+        let project = Arc::new(Project::test_project());
+        let mut state = GAState::test_state(project.clone(), 0x10, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        state
+            .set_register("R0".to_owned(), context.from_u64(0, 32))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let one = context.from_u64(1, 32);
+
+        // back edge from 0x14 to the header at 0x10
+        let iterate = |executor: &mut GAExecutor<'_, ArmV6M>| {
+            let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+            executor
+                .state
+                .set_register("R0".to_owned(), r0.add(&one))
+                .unwrap();
+            executor.state.last_pc = 0x14;
+            executor.state.set_has_jumped();
+            executor.accelerate_loop().unwrap()
+        };
+
+        assert!(iterate(&mut executor).is_none());
+        assert!(iterate(&mut executor).is_none());
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert!(r0.get_constant().is_none());
+        assert!(matches!(iterate(&mut executor), Some(PathResult::Suppress)));
+    }
+
+    #[test]
+    fn test_accelerate_memset_loop() {
+        let project = Arc::new(Project::test_project());
+        let mut state = GAState::test_state(project.clone(), 0x10, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        state
+            .set_register("R0".to_owned(), context.from_u64(0x2000_0000, 32))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let one = context.from_u64(1, 32);
+
+        // stores a zero byte at R0 and increments it, back edge from 0x14 to
+        // the header at 0x10
+        let iterate = |executor: &mut GAExecutor<'_, ArmV6M>, address: u64, bits: u32| {
+            executor
+                .set_memory(context.from_u64(0, bits), address, bits)
+                .unwrap();
+            let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+            executor
+                .state
+                .set_register("R0".to_owned(), r0.add(&one))
+                .unwrap();
+            executor.state.last_pc = 0x14;
+            executor.state.set_has_jumped();
+            executor.accelerate_loop().unwrap()
+        };
+
+        assert!(iterate(&mut executor, 0x2000_0000, 8).is_none());
+        assert!(iterate(&mut executor, 0x2000_0001, 8).is_none());
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert!(r0.get_constant().is_none());
+
+        // the elements of the skipped iterations are filled
+        let stored = executor.get_memory(0x2000_0001, 8).unwrap();
+        assert_eq!(stored.get_constant(), Some(0));
+        let filled = executor.get_memory(0x2000_0005, 8).unwrap();
+        assert!(filled.get_constant().is_none());
+
+        // an iteration with another store drops the widening and unrolls the
+        // loop
+        assert!(iterate(&mut executor, 0x3000_0000, 32).is_none());
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0.get_constant(), Some(0x2000_0002));
+        assert!(executor.state.loop_fills.is_empty());
+        assert!(iterate(&mut executor, 0x2000_0002, 8).is_none());
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0.get_constant(), Some(0x2000_0003));
+    }
+}
@@ -9,13 +9,20 @@
 pub mod arm;
 /// Defines discovery behaviour for the architectures.
 pub mod discover;
+pub mod riscv;
 use std::fmt::{Debug, Display};
 
 use arm::{v6::ArmV6M, v7::ArmV7EM};
+use general_assembly::verify::VerifyError;
 use object::File;
+use riscv::rv32i::RiscV32I;
+pub use symex_core::arch::{ExecutionState, InstructionSet, ParseError};
 use thiserror::Error;
 
-use crate::general_assembly::{instruction::Instruction, state::GAState, RunConfig};
+use crate::{
+    general_assembly::{instruction::Instruction, state::GAState, GAError, RunConfig},
+    smt::DExpr,
+};
 
 /// Enumerates all of the discoverable machine code formats.
 ///
@@ -26,6 +33,7 @@ use crate::general_assembly::{instruction::Instruction, state::GAState, RunConfi
 pub enum SupportedArchitechture {
     ArmV7EM(ArmV7EM),
     ArmV6M(ArmV6M),
+    RiscV32I(RiscV32I),
 }
 
 #[derive(Debug, Eq, PartialEq, PartialOrd, Clone, Error)]
@@ -55,37 +63,10 @@ pub enum ArchError {
     /// Thrown when something goes wrong during instruction parsing.
     #[error("Error occurred while parsing.")]
     ParsingError(#[from] ParseError),
-}
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Clone, Error)]
-pub enum ParseError {
-    /// Input not long enough for an instruction.
-    #[error("Insufficient input")]
-    InsufficientInput,
-
-    /// 32 bit instruction not long enough.
-    #[error("Tried to parse a malformed instruction.")]
-    MalfromedInstruction,
-
-    /// Opcode not matching valid 32 bit instruction.
-    #[error("Instruction not supported in the parser.")]
-    InvalidInstruction,
-
-    /// This instruction causes unpredictable behaviour.
-    #[error("Instruction defined as unpredictable.")]
-    Unpredictable,
-
-    /// Trying to access an invalid register.
-    #[error("Parser encountered an invalid register.")]
-    InvalidRegister,
-
-    /// Invalid condition code used.
-    #[error("Parser encountered an invalid condition.")]
-    InvalidCondition,
-
-    /// A generic parsing error.
-    #[error("Parser encountered some unspecified error.")]
-    Generic(&'static str),
+    /// Thrown in debug builds when a translated instruction is malformed.
+    #[error("Translated instruction is malformed: {0}")]
+    InvalidTranslation(VerifyError),
 }
 
 pub enum Architecture {
@@ -96,15 +77,65 @@ pub enum Architecture {
 /// A generic architecture
 ///
 /// Denotes that the implementer can be treated as an architecture in this
-/// crate.
-pub trait Arch: Debug + Display + Clone + Sized + 'static {
+/// crate. Architectures are shared between threads through the [`Project`].
+///
+/// The engine independent parts of the architecture are described by the
+/// [`InstructionSet`] it extends. Reads of registers outside of
+/// [`InstructionSet::registers`] before they are written are handled according
+/// to the [`UnknownRegisterPolicy`](super::UnknownRegisterPolicy), accesses to
+/// flags outside of [`InstructionSet::flags`] fail with
+/// [`GAError::UnknownFlag`](super::GAError::UnknownFlag) and
+/// [`InstructionSet::jump_table`] is used to bound symbolic table indices, see
+/// [`jump_table`](super::jump_table).
+///
+/// [`Project`]: super::project::Project
+pub trait Arch: InstructionSet + Debug + Display + Clone + Send + Sync + Sized + 'static {
     /// Converts a slice of bytes to an [`Instruction`]
-    fn translate(&self, buff: &[u8], state: &GAState<Self>)
-        -> Result<Instruction<Self>, ArchError>;
+    ///
+    /// `in_conditional_block` indicates that the instruction is executed
+    /// inside of a conditional block, such as an IT block.
+    fn translate(
+        &self,
+        buff: &[u8],
+        in_conditional_block: bool,
+    ) -> Result<Instruction<Self>, ArchError>;
 
     /// Adds the architecture specific hooks to the [`RunConfig`]
     fn add_hooks(&self, cfg: &mut RunConfig<Self>);
 
+    /// Returns the register that holds the value of `register` in `state`,
+    /// such as the banked register that another register aliases. Registers
+    /// are read and written through it.
+    fn register_alias(&self, _state: &GAState<Self>, register: String) -> String {
+        register
+    }
+
+    /// Returns a condition on `value` that decides the effects of writing it
+    /// to `register` in `state`, `None` if the effects do not depend on the
+    /// value. The path forks on the condition before the write if it can be
+    /// both true and false.
+    fn register_write_condition(
+        &self,
+        _state: &GAState<Self>,
+        _register: &str,
+        _value: &DExpr,
+    ) -> Option<DExpr> {
+        None
+    }
+
+    /// Applies the effects of writing `value` to `register` in `state`, such
+    /// as switching the banked register that another register aliases, and
+    /// returns the value to write. The returned value must be equal to `value`
+    /// on the path.
+    fn write_register(
+        &self,
+        _state: &mut GAState<Self>,
+        _register: &str,
+        value: DExpr,
+    ) -> Result<DExpr, GAError> {
+        Ok(value)
+    }
+
     /// Returns an instance of self if the file is defined for this
     /// specific architecture.
     fn discover(file: &File<'_>) -> Result<Option<Self>, ArchError>;
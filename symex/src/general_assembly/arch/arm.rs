@@ -2,9 +2,113 @@
 pub mod v6;
 pub mod v7;
 
+use std::{fmt::Debug, ops::Range};
+
 use object::ObjectSection;
 
-use super::ArchError;
+use super::{Arch, ArchError, ExecutionState};
+use crate::{
+    general_assembly::{
+        project::{MemoryHookAddress, MemoryReadHook, RegisterWriteHook},
+        state::{GAState, SymbolOrigin},
+        RunConfig,
+        TimeSource,
+    },
+    smt::DExpr,
+};
+
+/// Address of the DWT cycle count register.
+const DWT_CYCCNT: u64 = 0xE000_1004;
+
+/// Address of the SysTick reload value register.
+const SYST_RVR: u64 = 0xE000_E014;
+
+/// Address of the SysTick current value register.
+const SYST_CVR: u64 = 0xE000_E018;
+
+/// Registers of the Armv6-M and Armv7-M profiles, `LastAddr` holds the
+/// address tagged by the exclusive monitor.
+const REGISTERS: &[&str] = &[
+    "R0",
+    "R1",
+    "R2",
+    "R3",
+    "R4",
+    "R5",
+    "R6",
+    "R7",
+    "R8",
+    "R9",
+    "R10",
+    "R11",
+    "R12",
+    "SP",
+    "LR",
+    "PC",
+    "MSP",
+    "PSP",
+    "PRIMASK",
+    "BASEPRI",
+    "FAULTMASK",
+    "CONTROL",
+    "APSR",
+    "IAPSR",
+    "EAPSR",
+    "XPSR",
+    "IPSR",
+    "EPSR",
+    "IEPSR",
+    "LastAddr",
+];
+
+/// Pseudo register that interworking branches write their target address to
+/// before clearing bit 0 of it, bit 0 of the target selects the instruction set
+/// state as in `BXWritePC` and `LoadWritePC` of the ARM pseudocode.
+const THUMB_BIT: &str = "EPSR.T";
+
+/// Condition flags of the application program status register.
+const FLAGS: &[&str] = &["N", "Z", "C", "V", "Q"];
+
+/// Registers that hold values of up to eight bytes returned by a function in
+/// the procedure call standard (AAPCS), larger values are returned in memory.
+const RETURN_REGISTERS: &[&str] = &["R0", "R1"];
+
+/// Registers that hold the first four arguments of a function in the procedure
+/// call standard (AAPCS), further arguments are passed on the stack.
+const ARGUMENT_REGISTERS: &[&str] = &["R0", "R1", "R2", "R3"];
+
+/// Registers that a call may change in the procedure call standard (AAPCS).
+const CALLER_SAVED_REGISTERS: &[&str] = &["R0", "R1", "R2", "R3", "R12", "LR"];
+
+/// Addresses of the system control space of the M-profile processors, which
+/// holds the NVIC.
+const SYSTEM_REGION: Range<u64> = 0xE000_0000..0x1_0000_0000;
+
+/// Returns the role of `register` in the procedure call standard (AAPCS).
+fn aapcs_role(register: &str) -> Option<&'static str> {
+    Some(match register {
+        "R0" => "return value, argument 1",
+        "R1" => "return value, argument 2",
+        "R2" => "argument 3",
+        "R3" => "argument 4",
+        "R4" | "R5" | "R6" | "R7" | "R8" | "R10" | "R11" => "callee-saved",
+        "R9" => "platform register, callee-saved",
+        "R12" => "intra-procedure-call scratch",
+        "SP" => "stack pointer",
+        "LR" => "link register",
+        "PC" => "program counter",
+        _ => return None,
+    })
+}
+
+/// Returns the name of the core register with the dwarf register number
+/// `number`, R0 to R15 are numbered 0 to 15.
+fn dwarf_register(number: u16) -> Option<&'static str> {
+    match number {
+        0..=15 => Some(REGISTERS[number as usize]),
+        _ => None,
+    }
+}
 
 #[non_exhaustive]
 #[allow(dead_code)]
@@ -40,3 +144,186 @@ fn arm_isa<'a, T: ObjectSection<'a>>(section: &T) -> Result<ArmIsa, ArchError> {
         _ => Err(ArchError::UnsuportedArchitechture),
     }
 }
+
+/// Returns the size in bits of the Thumb instruction starting at `buff`.
+fn thumb_instruction_size(buff: &[u8]) -> Option<u32> {
+    let halfword = u16::from_le_bytes([*buff.first()?, *buff.get(1)?]);
+    Some(match halfword >> 11 {
+        0b11101..=0b11111 => 32,
+        _ => 16,
+    })
+}
+
+/// Returns the name of the variant of a decoded operation, which is the first
+/// identifier of its debug representation.
+fn debug_mnemonic<T: Debug>(operation: &T) -> String {
+    let debug = format!("{operation:?}");
+    debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Models a read of a time source that counts up and currently holds `now`.
+///
+/// The value is either `now` or a fresh symbol in `[now, now + bound]`
+/// depending on the [`TimeSource`] in use.
+fn time_source_value<A: Arch>(state: &mut GAState<A>, now: u32, name: &str, address: u64) -> DExpr {
+    match state.project.get_time_source() {
+        Some(TimeSource::Bounded(bound)) => {
+            let value = state.new_symbol(32, name, SymbolOrigin::MemoryRead(address));
+            let low = state.ctx.from_u64(now as u64, 32);
+            let high = state.ctx.from_u64(now.saturating_add(bound) as u64, 32);
+            state.constraints.assert(&value.ugte(&low));
+            state.constraints.assert(&value.ulte(&high));
+            value
+        }
+        _ => state.ctx.from_u64(now as u64, 32),
+    }
+}
+
+/// Adds memory read hooks for the Cortex-M time sources if a [`TimeSource`]
+/// is configured.
+///
+/// Reads of `DWT.CYCCNT` return the cycle count of the path. Reads of
+/// `SYST_CVR` return the value of the down counter assuming that it was
+/// started at the beginning of the path.
+fn add_time_source_hooks<A: Arch>(cfg: &mut RunConfig<A>) {
+    if cfg.time_source.is_none() {
+        return;
+    }
+
+    let read_cyccnt: MemoryReadHook<A> = |state, _addr| {
+        let now = state.cycle_count as u32;
+        Ok(time_source_value(state, now, "DWT.CYCCNT", DWT_CYCCNT))
+    };
+
+    let read_syst_cvr: MemoryReadHook<A> = |state, _addr| {
+        let rvr = state.ctx.from_u64(SYST_RVR, 32);
+        let reload = state
+            .read_word_from_memory(&rvr)?
+            .get_constant()
+            .unwrap_or(0x00FF_FFFF)
+            & 0x00FF_FFFF;
+        let elapsed = state.cycle_count as u64 % (reload + 1);
+
+        // Model the elapsed ticks as the up counting time source and convert it
+        // to the down counting register.
+        let elapsed = time_source_value(state, elapsed as u32, "SYST_CVR", SYST_CVR);
+        let reload = state.ctx.from_u64(reload, 32);
+        Ok(reload.sub(&elapsed))
+    };
+
+    cfg.memory_read_hooks.extend([
+        (MemoryHookAddress::Single(DWT_CYCCNT), read_cyccnt),
+        (MemoryHookAddress::Single(SYST_CVR), read_syst_cvr),
+    ]);
+}
+
+/// Adds the register write hook that tracks the instruction set state, see
+/// [`THUMB_BIT`].
+///
+/// A target with bit 0 clear switches to ARM state, which M-profile processors
+/// do not support. The path then fails on the next instruction fetch, as the
+/// processor would raise an INVSTATE usage fault, instead of executing the
+/// target as Thumb code.
+fn add_execution_state_hooks<A: Arch>(cfg: &mut RunConfig<A>) {
+    let write_thumb_bit: RegisterWriteHook<A> = |state, target| {
+        let thumb = target.slice(0, 0);
+        let execution_state = match state
+            .constraints
+            .must_be_equal(&thumb, &state.ctx.from_u64(1, 1))?
+        {
+            true => ExecutionState::Thumb,
+            false => ExecutionState::Arm,
+        };
+        state.set_execution_state(execution_state);
+        Ok(())
+    };
+
+    cfg.register_write_hooks
+        .push((THUMB_BIT.to_owned(), write_thumb_bit));
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use general_assembly::{
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    use super::{v6::ArmV6M, DWT_CYCCNT, SYST_CVR, SYST_RVR};
+    use crate::{
+        general_assembly::{
+            executor::GAExecutor,
+            project::Project,
+            state::GAState,
+            vm::VM,
+            TimeSource,
+        },
+        smt::DExpr,
+    };
+
+    fn setup_test_vm(time_source: TimeSource) -> VM<ArmV6M> {
+        let mut project = Project::test_project();
+        project.set_time_source(Some(time_source));
+        project.add_hooks(&ArmV6M {});
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, 0x2000_1000, ArmV6M {});
+        state.cycle_count = 100;
+        VM::new_with_state(project, state)
+    }
+
+    /// Loads the word at `address` in to R0 through the executor.
+    fn load(executor: &mut GAExecutor<'_, ArmV6M>, address: u64) -> DExpr {
+        let operation = Operation::Move {
+            destination: Operand::Register("R0".to_owned()),
+            source: Operand::Address(DataWord::Word32(address as u32), 32),
+        };
+        executor
+            .execute_operation(&operation, &mut HashMap::new())
+            .unwrap();
+        executor.state.get_register("R0".to_owned()).unwrap()
+    }
+
+    #[test]
+    fn test_cycle_count_time_source() {
+        let mut vm = setup_test_vm(TimeSource::CycleCount);
+        let project = vm.project.clone();
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+
+        assert_eq!(load(&mut executor, DWT_CYCCNT).get_constant(), Some(100));
+
+        // SysTick counts down from the reload value.
+        let rvr = executor.state.ctx.from_u64(SYST_RVR, 32);
+        let reload = executor.state.ctx.from_u64(39, 32);
+        executor.state.write_word_to_memory(&rvr, reload).unwrap();
+        assert_eq!(load(&mut executor, SYST_CVR).get_constant(), Some(19));
+    }
+
+    #[test]
+    fn test_bounded_time_source() {
+        let mut vm = setup_test_vm(TimeSource::Bounded(10));
+        let project = vm.project.clone();
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+
+        let cyccnt = load(&mut executor, DWT_CYCCNT);
+        let can_read = |value| {
+            let value = executor.state.ctx.from_u64(value, 32);
+            executor
+                .state
+                .constraints
+                .can_equal(&cyccnt, &value)
+                .unwrap()
+        };
+        assert!(!can_read(99));
+        assert!(can_read(100));
+        assert!(can_read(110));
+        assert!(!can_read(111));
+    }
+}
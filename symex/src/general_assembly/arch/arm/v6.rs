@@ -3,23 +3,35 @@
 pub mod decoder;
 pub mod timing;
 
-use std::fmt::Display;
+use std::{fmt::Display, ops::Range};
 
 use armv6_m_instruction_parser::Error;
 use object::{File, Object};
-use regex::Regex;
 use tracing::trace;
 
-use super::{arm_isa, ArmIsa};
+use super::{
+    add_execution_state_hooks,
+    add_time_source_hooks,
+    arm_isa,
+    debug_mnemonic,
+    thumb_instruction_size,
+    ArmIsa,
+};
 use crate::{
     elf_util::{ExpressionType, Variable},
     general_assembly::{
-        arch::{Arch, ArchError, ParseError},
+        arch::{Arch, ArchError, ExecutionState, InstructionSet, ParseError},
+        context,
         instruction::Instruction,
+        jump_table::{JumpTable, TableEntry},
         project::{MemoryHookAddress, MemoryReadHook, PCHook, RegisterReadHook, RegisterWriteHook},
-        state::GAState,
+        state::{GAState, SymbolOrigin},
+        GAError,
         RunConfig,
     },
+    intrinsics,
+    memory::SmtMap,
+    smt::DExpr,
 };
 
 /// Type level denotation for the
@@ -27,6 +39,70 @@ use crate::{
 #[derive(Clone, Copy, Debug)]
 pub struct ArmV6M {}
 
+impl InstructionSet for ArmV6M {
+    fn instruction_size(&self, buff: &[u8]) -> Option<u32> {
+        thumb_instruction_size(buff)
+    }
+
+    fn registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::REGISTERS)
+    }
+
+    fn flags(&self) -> Option<&'static [&'static str]> {
+        Some(super::FLAGS)
+    }
+
+    fn register_role(&self, register: &str) -> Option<&'static str> {
+        super::aapcs_role(register)
+    }
+
+    fn return_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::RETURN_REGISTERS)
+    }
+
+    fn argument_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::ARGUMENT_REGISTERS)
+    }
+
+    fn caller_saved_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::CALLER_SAVED_REGISTERS)
+    }
+
+    fn system_region(&self) -> Option<Range<u64>> {
+        Some(super::SYSTEM_REGION)
+    }
+
+    fn dwarf_register(&self, number: u16) -> Option<&'static str> {
+        super::dwarf_register(number)
+    }
+
+    fn supports_execution_state(&self, state: ExecutionState) -> bool {
+        // M-profile processors only execute Thumb instructions.
+        state == ExecutionState::Thumb
+    }
+
+    fn mnemonic(&self, buff: &[u8]) -> Option<String> {
+        let instruction = armv6_m_instruction_parser::parse(buff).ok()?;
+        Some(debug_mnemonic(&instruction.operation))
+    }
+
+    fn jump_table(&self, buff: &[u8]) -> Option<JumpTable> {
+        let instruction = armv6_m_instruction_parser::parse(buff).ok()?;
+        match instruction.operation {
+            // adr rn, table; ldr rt, [rn, rm] with rm holding the index times four
+            armv6_m_instruction_parser::instructons::Operation::LDRReg { m, n, .. } => {
+                Some(JumpTable {
+                    base: Some(format!("{n:?}")),
+                    index: format!("{m:?}"),
+                    scale: 1,
+                    entry: TableEntry::Address(4),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
 impl Arch for ArmV6M {
     fn add_hooks(&self, cfg: &mut RunConfig<Self>) {
         let symbolic_sized = |state: &mut GAState<Self>| {
@@ -38,7 +114,7 @@ impl Arch for ArmV6M {
                 size
             );
             let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
-            let symb_value = state.ctx.unconstrained(size as u32, &name);
+            let symb_value = state.new_symbol(size as u32, &name, SymbolOrigin::Intrinsic);
             state.marked_symbolic.push(Variable {
                 name: Some(name),
                 value: symb_value.clone(),
@@ -52,7 +128,7 @@ impl Arch for ArmV6M {
         };
 
         cfg.pc_hooks.push((
-            Regex::new(r"^symbolic_size<.+>$").unwrap(),
+            intrinsics::hook_pattern("symbolic_size"),
             PCHook::Intrinsic(symbolic_sized),
         ));
 
@@ -75,18 +151,46 @@ impl Arch for ArmV6M {
         };
         cfg.memory_read_hooks
             .push((MemoryHookAddress::Single(0x4000c008), read_reset_done));
+
+        add_time_source_hooks(cfg);
+        add_execution_state_hooks(cfg);
     }
 
     fn translate(
         &self,
         buff: &[u8],
-        _state: &GAState<Self>,
+        _in_conditional_block: bool,
     ) -> Result<Instruction<Self>, ArchError> {
         let ret = armv6_m_instruction_parser::parse(buff).map_err(map_err)?;
-        let to_exec = Self::expand(ret);
+        let to_exec = Self::expand(ret)?;
         Ok(to_exec)
     }
 
+    fn register_alias(&self, state: &GAState<Self>, register: String) -> String {
+        context::stack_alias(state, register)
+    }
+
+    fn register_write_condition(
+        &self,
+        state: &GAState<Self>,
+        register: &str,
+        value: &DExpr,
+    ) -> Option<DExpr> {
+        (register == "CONTROL").then(|| context::selects_process_stack(state, value))
+    }
+
+    fn write_register(
+        &self,
+        state: &mut GAState<Self>,
+        register: &str,
+        value: DExpr,
+    ) -> Result<DExpr, GAError> {
+        match register {
+            "CONTROL" => context::write_control(state, value),
+            _ => Ok(value),
+        }
+    }
+
     fn discover(file: &File<'_>) -> Result<Option<Self>, ArchError> {
         let f = match file {
             File::Elf32(f) => Ok(f),
@@ -13,12 +13,16 @@ use general_assembly::{
 };
 
 use super::ArmV6M;
-use crate::general_assembly::instruction::Instruction as GAInstruction;
+use crate::general_assembly::{arch::ParseError, instruction::Instruction as GAInstruction};
 
 impl ArmV6M {
-    pub(super) fn expand(instr: Instruction) -> GAInstruction<ArmV6M> {
+    /// Expands the instruction in to general assembly operations.
+    ///
+    /// Returns an error for instructions that are not supported, this never
+    /// panics.
+    pub(super) fn expand(instr: Instruction) -> Result<GAInstruction<ArmV6M>, ParseError> {
         let operations = match &instr.operation {
-            Operation::UDF { .. } => todo!(),
+            Operation::UDF { .. } => return Err(ParseError::InvalidInstruction),
             Operation::ADCReg { m, n, d } => {
                 let dest = arm_register_to_ga_operand(d);
                 let mreg = arm_register_to_ga_operand(m);
@@ -78,6 +82,19 @@ impl ArmV6M {
                     },
                 ]
             }
+            // `BranchWritePC` without setting the flags, it ends the instruction.
+            Operation::ADDReg { m, n, d } if *d == Register::PC => vec![
+                GAOperation::Add {
+                    destination: Operand::Local("result".to_owned()),
+                    operand1: arm_register_to_ga_operand(n),
+                    operand2: arm_register_to_ga_operand(m),
+                },
+                GAOperation::And {
+                    destination: arm_register_to_ga_operand(d),
+                    operand1: Operand::Local("result".to_owned()),
+                    operand2: Operand::Immediate(DataWord::Word32(!0b1)),
+                },
+            ],
             Operation::ADDReg { m, n, d } => {
                 let dest = arm_register_to_ga_operand(d);
                 let mreg = arm_register_to_ga_operand(m);
@@ -236,9 +253,10 @@ impl ArmV6M {
                     destination: Operand::Local("PC".to_owned()),
                     source: Operand::Register("PC".to_owned()),
                 },
-                GAOperation::Move {
+                GAOperation::Or {
                     destination: Operand::Register("LR".to_owned()),
-                    source: Operand::Local("PC".to_owned()),
+                    operand1: Operand::Local("PC".to_owned()),
+                    operand2: Operand::Immediate(DataWord::Word32(1)),
                 },
                 GAOperation::Add {
                     destination: Operand::Local("newPC".to_owned()),
@@ -250,24 +268,22 @@ impl ArmV6M {
                     source: Operand::Local("newPC".to_owned()),
                 },
             ],
-            Operation::BLXReg { m } => vec![
-                GAOperation::Move {
-                    destination: arm_register_to_ga_operand(&Register::LR),
-                    source: Operand::Register("PC".to_owned()),
-                },
-                GAOperation::Move {
-                    destination: Operand::Register("PC".to_owned()),
-                    source: arm_register_to_ga_operand(m),
-                },
-            ],
-            Operation::BX { m } => {
-                let reg = arm_register_to_ga_operand(m);
-                let destination = Operand::Register("PC".to_owned());
-                vec![GAOperation::Move {
-                    destination,
-                    source: reg,
-                }]
+            Operation::BLXReg { m } => {
+                let mut operations = vec![
+                    GAOperation::Move {
+                        destination: Operand::Local("target".to_owned()),
+                        source: arm_register_to_ga_operand(m),
+                    },
+                    GAOperation::Or {
+                        destination: arm_register_to_ga_operand(&Register::LR),
+                        operand1: Operand::Register("PC".to_owned()),
+                        operand2: Operand::Immediate(DataWord::Word32(1)),
+                    },
+                ];
+                operations.extend(interworking_branch(Operand::Local("target".to_owned())));
+                operations
             }
+            Operation::BX { m } => interworking_branch(arm_register_to_ga_operand(m)),
             Operation::CMNReg { m, n } => {
                 let m = arm_register_to_ga_operand(m);
                 let n = arm_register_to_ga_operand(n);
@@ -343,14 +359,16 @@ impl ArmV6M {
                     },
                 ]
             }
-            Operation::CPS { im: _ } => {
-                // change processor state do nothig for now but should probably be modeled
-                // in armv6-m it is only used to enable disable interrupts
-                vec![]
+            Operation::CPS { im } => {
+                // in armv6-m it is only used to enable disable interrupts, CPSID sets PRIMASK
+                vec![GAOperation::Move {
+                    destination: Operand::Register("PRIMASK".to_owned()),
+                    source: Operand::Immediate(DataWord::Word32(*im as u32)),
+                }]
             }
             Operation::CPY => {
                 // this is not a real instruction is equvelatn to mov
-                unreachable!()
+                return Err(ParseError::Unsupported("CPY"));
             }
             Operation::DMB { option: _ } => {
                 // data barier do nothig as data barier is not modeled yet
@@ -712,11 +730,21 @@ impl ArmV6M {
                     destination: Operand::Local("Address".to_owned()),
                     source: Operand::Register("SP".to_owned()),
                 });
+                let mut pops_pc = false;
                 for reg in reg_list {
+                    let value = Operand::AddressInLocal("Address".to_owned(), 32);
+                    // The branch to a popped PC is the last operation.
+                    let destination = match *reg == Register::PC {
+                        true => {
+                            pops_pc = true;
+                            Operand::Local("target".to_owned())
+                        }
+                        false => arm_register_to_ga_operand(reg),
+                    };
                     // write register to memory
                     operations.push(GAOperation::Move {
-                        source: Operand::AddressInLocal("Address".to_owned(), 32),
-                        destination: arm_register_to_ga_operand(reg),
+                        source: value,
+                        destination,
                     });
                     // update address
                     operations.push(GAOperation::Add {
@@ -731,6 +759,9 @@ impl ArmV6M {
                     operand1: Operand::Register("SP".to_owned()),
                     operand2: Operand::Immediate(DataWord::Word32((4 * reg_list.len()) as u32)),
                 });
+                if pops_pc {
+                    operations.extend(interworking_branch(Operand::Local("target".to_owned())));
+                }
 
                 operations
             }
@@ -1371,9 +1402,9 @@ impl ArmV6M {
                 bits: 16,
                 target_bits: 32,
             }],
-            Operation::WFE => todo!(),
-            Operation::WFI => todo!(),
-            Operation::YIELD => todo!(),
+            Operation::WFE => return Err(ParseError::Unsupported("WFE")),
+            Operation::WFI => return Err(ParseError::Unsupported("WFI")),
+            Operation::YIELD => return Err(ParseError::Unsupported("YIELD")),
         };
 
         let instruction_width = match instr.width {
@@ -1383,16 +1414,32 @@ impl ArmV6M {
 
         let max_cycle_count = super::timing::cycle_count_m0plus_core(&instr.operation);
 
-        GAInstruction {
+        Ok(GAInstruction {
             instruction_size: instruction_width,
             operations,
             max_cycle: max_cycle_count,
             // TODO! Add in checks for whether or not this is true
             memory_access: false,
-        }
+        })
     }
 }
 
+/// Branches to `target` and selects the instruction set state from bit 0 of
+/// the target, `BXWritePC` in the ARM pseudocode.
+fn interworking_branch(target: Operand) -> Vec<GAOperation> {
+    vec![
+        GAOperation::Move {
+            destination: Operand::Register("EPSR.T".to_owned()),
+            source: target.clone(),
+        },
+        GAOperation::And {
+            destination: Operand::Register("PC".to_owned()),
+            operand1: target,
+            operand2: Operand::Immediate(DataWord::Word32(!0b1)),
+        },
+    ]
+}
+
 fn arm_register_to_ga_operand(reg: &Register) -> Operand {
     Operand::Register(match reg {
         Register::R0 => "R0".to_owned(),
@@ -1,22 +1,34 @@
-use std::fmt::Display;
+use std::{fmt::Display, ops::Range};
 
 use decoder::Convert;
 use disarmv7::prelude::{Operation as V7Operation, *};
 use general_assembly::operation::Operation;
 use object::{File, Object};
-use regex::Regex;
 use tracing::trace;
 
-use super::{arm_isa, ArmIsa};
+use super::{
+    add_execution_state_hooks,
+    add_time_source_hooks,
+    arm_isa,
+    debug_mnemonic,
+    thumb_instruction_size,
+    ArmIsa,
+};
 use crate::{
     elf_util::{ExpressionType, Variable},
     general_assembly::{
-        arch::{Arch, ArchError, ParseError},
+        arch::{Arch, ArchError, ExecutionState, InstructionSet, ParseError},
+        context,
         instruction::Instruction,
+        jump_table::{JumpTable, TableEntry},
         project::{MemoryHookAddress, MemoryReadHook, PCHook, RegisterReadHook, RegisterWriteHook},
         run_config::RunConfig,
-        state::GAState,
+        state::{GAState, SymbolOrigin},
+        GAError,
     },
+    intrinsics,
+    memory::SmtMap,
+    smt::DExpr,
 };
 
 #[rustfmt::skip]
@@ -30,13 +42,91 @@ pub mod timing;
 #[derive(Debug, Default, Clone)]
 pub struct ArmV7EM {}
 
+impl InstructionSet for ArmV7EM {
+    fn instruction_size(&self, buff: &[u8]) -> Option<u32> {
+        thumb_instruction_size(buff)
+    }
+
+    fn registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::REGISTERS)
+    }
+
+    fn flags(&self) -> Option<&'static [&'static str]> {
+        Some(super::FLAGS)
+    }
+
+    fn register_role(&self, register: &str) -> Option<&'static str> {
+        super::aapcs_role(register)
+    }
+
+    fn return_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::RETURN_REGISTERS)
+    }
+
+    fn argument_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::ARGUMENT_REGISTERS)
+    }
+
+    fn caller_saved_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::CALLER_SAVED_REGISTERS)
+    }
+
+    fn system_region(&self) -> Option<Range<u64>> {
+        Some(super::SYSTEM_REGION)
+    }
+
+    fn dwarf_register(&self, number: u16) -> Option<&'static str> {
+        super::dwarf_register(number)
+    }
+
+    fn supports_execution_state(&self, state: ExecutionState) -> bool {
+        // M-profile processors only execute Thumb instructions.
+        state == ExecutionState::Thumb
+    }
+
+    fn mnemonic(&self, buff: &[u8]) -> Option<String> {
+        let mut buff: disarmv7::buffer::PeekableBuffer<u8, _> = buff.iter().cloned().into();
+        let (_, operation) = V7Operation::parse(&mut buff).ok()?;
+        Some(debug_mnemonic(&operation))
+    }
+
+    fn jump_table(&self, buff: &[u8]) -> Option<JumpTable> {
+        let mut buff: disarmv7::buffer::PeekableBuffer<u8, _> = buff.iter().cloned().into();
+        let (_, operation) = V7Operation::parse(&mut buff).ok()?;
+        match operation {
+            V7Operation::Tb(tb) => {
+                let size = match tb.is_tbh.unwrap_or(false) {
+                    true => 2,
+                    false => 1,
+                };
+                Some(JumpTable {
+                    base: match tb.rn {
+                        Register::PC => None,
+                        rn => Some(format!("{rn:?}")),
+                    },
+                    index: format!("{:?}", tb.rm),
+                    scale: size,
+                    entry: TableEntry::Offset(size),
+                })
+            }
+            V7Operation::LdrRegister(ldr) => Some(JumpTable {
+                base: Some(format!("{:?}", ldr.rn)),
+                index: format!("{:?}", ldr.rm),
+                scale: 1 << ldr.shift.map_or(0, |shift| shift.shift_n),
+                entry: TableEntry::Address(4),
+            }),
+            _ => None,
+        }
+    }
+}
+
 impl Arch for ArmV7EM {
     fn add_hooks(&self, cfg: &mut RunConfig<Self>) {
         let symbolic_sized = |state: &mut GAState<Self>| {
             let value_ptr = state.get_register("R0".to_owned())?;
             let size = state.get_register("R1".to_owned())?.get_constant().unwrap() * 8;
             let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
-            let symb_value = state.ctx.unconstrained(size as u32, &name);
+            let symb_value = state.new_symbol(size as u32, &name, SymbolOrigin::Intrinsic);
             state.marked_symbolic.push(Variable {
                 name: Some(name),
                 value: symb_value.clone(),
@@ -50,7 +140,7 @@ impl Arch for ArmV7EM {
         };
 
         cfg.pc_hooks.push((
-            Regex::new(r"^symbolic_size<.+>$").unwrap(),
+            intrinsics::hook_pattern("symbolic_size"),
             PCHook::Intrinsic(symbolic_sized),
         ));
         // §B1.4 Specifies that R[15] => Addr(Current instruction) + 4
@@ -101,19 +191,23 @@ impl Arch for ArmV7EM {
         };
         cfg.memory_read_hooks
             .push((MemoryHookAddress::Single(0x4000c008), read_reset_done));
+
+        add_time_source_hooks(cfg);
+        add_execution_state_hooks(cfg);
     }
 
     fn translate(
         &self,
         buff: &[u8],
-        state: &GAState<Self>,
+        in_conditional_block: bool,
     ) -> Result<Instruction<Self>, ArchError> {
         let mut buff: disarmv7::buffer::PeekableBuffer<u8, _> = buff.iter().cloned().into();
 
-        let instr = V7Operation::parse(&mut buff).map_err(|e| ArchError::ParsingError(e.into()))?;
+        let instr =
+            V7Operation::parse(&mut buff).map_err(|e| ArchError::ParsingError(parse_error(e)))?;
         trace!("Running {:?}", instr.1);
         let timing = Self::cycle_count_m4_core(&instr.1);
-        let ops: Vec<Operation> = instr.clone().convert(state.get_in_conditional_block());
+        let ops: Vec<Operation> = instr.clone().convert(in_conditional_block)?;
 
         Ok(Instruction {
             instruction_size: instr.0 as u32,
@@ -123,6 +217,31 @@ impl Arch for ArmV7EM {
         })
     }
 
+    fn register_alias(&self, state: &GAState<Self>, register: String) -> String {
+        context::stack_alias(state, register)
+    }
+
+    fn register_write_condition(
+        &self,
+        state: &GAState<Self>,
+        register: &str,
+        value: &DExpr,
+    ) -> Option<DExpr> {
+        (register == "CONTROL").then(|| context::selects_process_stack(state, value))
+    }
+
+    fn write_register(
+        &self,
+        state: &mut GAState<Self>,
+        register: &str,
+        value: DExpr,
+    ) -> Result<DExpr, GAError> {
+        match register {
+            "CONTROL" => context::write_control(state, value),
+            _ => Ok(value),
+        }
+    }
+
     fn discover(file: &File<'_>) -> Result<Option<Self>, ArchError> {
         let f = match file {
             File::Elf32(f) => Ok(f),
@@ -146,35 +265,28 @@ impl Display for ArmV7EM {
     }
 }
 
-impl From<disarmv7::ParseError> for ParseError {
-    fn from(value: disarmv7::ParseError) -> Self {
-        match value {
-            disarmv7::ParseError::Undefined => ParseError::InvalidInstruction,
-            disarmv7::ParseError::ArchError(aerr) => match aerr {
-                disarmv7::prelude::arch::ArchError::InvalidCondition => {
-                    ParseError::InvalidCondition
-                }
-                disarmv7::prelude::arch::ArchError::InvalidRegister(_) => {
-                    ParseError::InvalidRegister
-                }
-                disarmv7::prelude::arch::ArchError::InvalidField(_) => {
-                    ParseError::MalfromedInstruction
-                }
-            },
-            disarmv7::ParseError::Unpredictable => ParseError::Unpredictable,
-            disarmv7::ParseError::Invalid16Bit(_) | disarmv7::ParseError::Invalid32Bit(_) => {
-                ParseError::InvalidInstruction
-            }
-            disarmv7::ParseError::InvalidField(_) => ParseError::MalfromedInstruction,
-            disarmv7::ParseError::Incomplete32Bit => ParseError::InsufficientInput,
-            disarmv7::ParseError::InternalError(info) => ParseError::Generic(info),
-            disarmv7::ParseError::IncompleteParser => {
-                ParseError::Generic("Encountered instruction that is not yet supported.")
-            }
-            disarmv7::ParseError::InvalidCondition => ParseError::InvalidCondition,
-            disarmv7::ParseError::IncompleteProgram => ParseError::InsufficientInput,
-            disarmv7::ParseError::InvalidRegister(_) => ParseError::InvalidRegister,
-            disarmv7::ParseError::PartiallyParsed(error, _) => (*error).into(),
+/// Converts an error from the decoder to a [`ParseError`].
+fn parse_error(value: disarmv7::ParseError) -> ParseError {
+    match value {
+        disarmv7::ParseError::Undefined => ParseError::InvalidInstruction,
+        disarmv7::ParseError::ArchError(aerr) => match aerr {
+            disarmv7::prelude::arch::ArchError::InvalidCondition => ParseError::InvalidCondition,
+            disarmv7::prelude::arch::ArchError::InvalidRegister(_) => ParseError::InvalidRegister,
+            disarmv7::prelude::arch::ArchError::InvalidField(_) => ParseError::MalfromedInstruction,
+        },
+        disarmv7::ParseError::Unpredictable => ParseError::Unpredictable,
+        disarmv7::ParseError::Invalid16Bit(_) | disarmv7::ParseError::Invalid32Bit(_) => {
+            ParseError::InvalidInstruction
+        }
+        disarmv7::ParseError::InvalidField(_) => ParseError::MalfromedInstruction,
+        disarmv7::ParseError::Incomplete32Bit => ParseError::InsufficientInput,
+        disarmv7::ParseError::InternalError(info) => ParseError::Generic(info),
+        disarmv7::ParseError::IncompleteParser => {
+            ParseError::Generic("Encountered instruction that is not yet supported.")
         }
+        disarmv7::ParseError::InvalidCondition => ParseError::InvalidCondition,
+        disarmv7::ParseError::IncompleteProgram => ParseError::InsufficientInput,
+        disarmv7::ParseError::InvalidRegister(_) => ParseError::InvalidRegister,
+        disarmv7::ParseError::PartiallyParsed(error, _) => parse_error(*error),
     }
 }
@@ -238,13 +238,13 @@ fn eq_trampoline(lhs: &V6Instruction, rhs: &(usize, V7Operation)) -> bool {
             m.equal(&ldrsh.rm) && n.equal(&ldrsh.rn) && t.equal(&ldrsh.rt)
         }
         (V6Operation::LSLImm { imm, m, d }, V7Operation::LslImmediate(lsl)) => {
-            (*imm == lsl.imm.into()) && m.equal(&lsl.rm) && d.equal(&lsl.rd)
+            (*imm == u32::from(lsl.imm)) && m.equal(&lsl.rm) && d.equal(&lsl.rd)
         }
         (V6Operation::LSLReg { m, dn }, V7Operation::LslRegister(lsl)) => {
             m.equal(&lsl.rm) && dn.equal(&lsl.rd) && dn.equal(&lsl.rn)
         }
         (V6Operation::LSRImm { imm, m, d }, V7Operation::LsrImmediate(lsr)) => {
-            (*imm == lsr.imm.into()) && m.equal(&lsr.rm) && d.equal(&lsr.rd)
+            (*imm == u32::from(lsr.imm)) && m.equal(&lsr.rm) && d.equal(&lsr.rd)
         }
         (V6Operation::LSRReg { m, dn }, V7Operation::LsrRegister(lsr)) => {
             m.equal(&lsr.rm) && dn.equal(&lsr.rd) && dn.equal(&lsr.rn)
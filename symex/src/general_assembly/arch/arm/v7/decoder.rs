@@ -8,6 +8,8 @@ use general_assembly::{
 use paste::paste;
 use transpiler::pseudo;
 
+use crate::general_assembly::arch::ParseError;
+
 use disarmv7::prelude::{
     Register,
     Shift,
@@ -50,8 +52,8 @@ macro_rules! shift {
                 GAShift::Lsl => Operation::SetCFlagShiftLeft { operand: $reg_flag.clone(), shift: shift_n.clone() },
                 GAShift::Asr => Operation::SetCFlagSra { operand: $reg_flag.clone(), shift: shift_n.clone() },
                 GAShift::Lsr => Operation::SetCFlagSrl { operand: $reg_flag.clone(), shift: shift_n.clone() },
-                GAShift::Rrx => todo!(),
-                GAShift::Ror => todo!()
+                GAShift::Rrx => return Err(ParseError::Unsupported("carry out of RRX shift")),
+                GAShift::Ror => return Err(ParseError::Unsupported("carry out of ROR shift"))
             });)?
 
        }
@@ -77,8 +79,8 @@ macro_rules! shift_imm {
                 GAShift::Lsl => Operation::SetCFlagShiftLeft { operand: $reg_flag.clone(), shift: shift_n.clone() },
                 GAShift::Asr => Operation::SetCFlagSra { operand: $reg_flag.clone(), shift: shift_n.clone() },
                 GAShift::Lsr => Operation::SetCFlagSrl { operand: $reg_flag.clone(), shift: shift_n.clone() },
-                GAShift::Rrx => todo!(),
-                GAShift::Ror => todo!()
+                GAShift::Rrx => return Err(ParseError::Unsupported("carry out of RRX shift")),
+                GAShift::Ror => return Err(ParseError::Unsupported("carry out of ROR shift"))
             });)?
             $ret.push(
                 Operation::Shift {
@@ -103,12 +105,16 @@ macro_rules! local {
 /// Simply forces the least significant bit to zero.
 const REMOVE_LAST_BIT_MASK:u32 = !0b1;
 pub trait Convert {
-    fn convert(self,in_it_block:bool) -> Vec<Operation>;
+    /// Converts the instruction in to general assembly operations.
+    ///
+    /// Returns an error for instructions that are not supported or that are
+    /// unpredictable, this never panics.
+    fn convert(self,in_it_block:bool) -> Result<Vec<Operation>, ParseError>;
 }
 
 impl Convert for (usize, V7Operation) {
-    fn convert(self,in_it_block:bool) -> Vec<Operation> {
-        'outer_block: {
+    fn convert(self,in_it_block:bool) -> Result<Vec<Operation>, ParseError> {
+        Ok('outer_block: {
             match self.1 {
                 V7Operation::AdcImmediate(adc) => {
                     // Ensure that all fields are used
@@ -409,6 +415,9 @@ impl Convert for (usize, V7Operation) {
                 }
                 V7Operation::Bfc(bfc) => {
                     consume!((rd,lsb,msb) from bfc);
+                    if msb < lsb {
+                        return Err(ParseError::Unpredictable);
+                    }
                     let rd = rd.local_into();
                     let mask = !mask_dyn(lsb, msb);
                     vec![
@@ -421,16 +430,18 @@ impl Convert for (usize, V7Operation) {
                 }
                 V7Operation::Bfi(bfi) => {
                     consume!((rd,rn,lsb,msb) from bfi);
+                    if msb < lsb {
+                        return Err(ParseError::Unpredictable);
+                    }
                     let (rd, rn) = (rd.local_into(), rn.local_into());
-                    let diff = msb - lsb;
-                    let nmask = (!(mask_dyn(lsb,msb) << lsb)).local_into();
-                    assert!(msb >= lsb, "would be unpredictable");
-                    pseudo!([
-                        // Assume happy case here
-                        rd = rd & nmask;
-                        let intermediate = rn<diff:0> << lsb.local_into();
-                        rd |= intermediate;
-                    ])
+                    vec![
+                        Operation::BitFieldInsert {
+                            destination: rd,
+                            operand: rn,
+                            start_bit: lsb,
+                            stop_bit: msb
+                        }
+                    ]
                 }
                 V7Operation::BicImmediate(bic) => {
                     consume!((s.unwrap_or(false),rd,rn,imm,carry) from bic);
@@ -511,7 +522,7 @@ impl Convert for (usize, V7Operation) {
 
                         Register("LR") = next_instr_addr & REMOVE_LAST_BIT_MASK.local_into();
                         Register("LR") |= 1.local_into();
-                        Register("EPSR") = Register("EPSR") | (1 << 27).local_into();
+                        Register("EPSR.T") = target;
                         target = target & REMOVE_LAST_BIT_MASK.local_into();
                         Register("PC+") = target;
                     ])
@@ -521,6 +532,7 @@ impl Convert for (usize, V7Operation) {
                     let rm = bx.rm.local_into();
                     pseudo!([
                         let next_addr = rm;
+                        Register("EPSR.T") = next_addr;
                         next_addr = next_addr & REMOVE_LAST_BIT_MASK.local_into();
                         Register("PC+") = next_addr;
                     ])
@@ -546,7 +558,7 @@ impl Convert for (usize, V7Operation) {
                         Flag("Z") = old_z;
                     ])
                 }
-                V7Operation::Clrex(_) => todo!("This should not be needed for now"),
+                V7Operation::Clrex(_) => return Err(ParseError::Unsupported("Clrex")),
                 V7Operation::Clz(clz) => {
                     vec![Operation::CountLeadingZeroes{
                         destination: clz.rd.local_into(),
@@ -607,7 +619,9 @@ impl Convert for (usize, V7Operation) {
                 }
                 V7Operation::Cps(cps) => {
                     consume!((enable,disable,affect_pri,affect_fault) from cps);
-                    assert!(enable != disable);
+                    if enable == disable {
+                        return Err(ParseError::Unpredictable);
+                    }
                     let mut ret = Vec::with_capacity(1);
                     if enable {
                         if affect_pri {
@@ -634,7 +648,7 @@ impl Convert for (usize, V7Operation) {
                         if affect_pri {
                             // force lsb to 1
                             ret.push(
-                                Operation::And { 
+                                Operation::Or { 
                                     destination: SpecialRegister::PRIMASK.local_into(), 
                                     operand1: SpecialRegister::PRIMASK.local_into(), 
                                     operand2: ((0b1u32).local_into()) 
@@ -644,7 +658,7 @@ impl Convert for (usize, V7Operation) {
                         if affect_fault {
                             // force lsb to 1
                             ret.push(
-                                Operation::And {
+                                Operation::Or {
                                     destination: SpecialRegister::FAULTMASK.local_into(),
                                     operand1: SpecialRegister::FAULTMASK.local_into(),
                                     operand2: ((0b1u32).local_into()) 
@@ -757,14 +771,15 @@ impl Convert for (usize, V7Operation) {
                             address += 4.local_into();
                         }
 
+                        if (w) {
+                            rn += (4*bc).local_into();
+                        }
                         if (contained) {
                             let target = LocalAddress(address,4);
+                            Register("EPSR.T") = target;
                             target = target & REMOVE_LAST_BIT_MASK.local_into();
                             Jump(target);
                         }
-                        if (w) {
-                            rn += (4*bc).local_into();
-                        }
                     ])
                 }
                 V7Operation::Ldmdb(ldmdb) => {
@@ -798,14 +813,15 @@ impl Convert for (usize, V7Operation) {
                             address += 4.local_into();
                         }
 
+                        if (w) {
+                            rn -= (4*bc).local_into();
+                        }
                         if (contained) {
                             let target = LocalAddress(address,4);
+                            Register("EPSR.T") = target;
                             target = target & REMOVE_LAST_BIT_MASK.local_into();
                             Jump(target);
                         }
-                        if (w) {
-                            rn -= (4*bc).local_into();
-                        }
                     ])
                 }
                 V7Operation::LdrImmediate(ldr) => {
@@ -833,6 +849,7 @@ impl Convert for (usize, V7Operation) {
                         }
 
                         if (is_pc) {
+                            Register("EPSR.T") = data;
                             data = data & REMOVE_LAST_BIT_MASK.local_into();
                             Jump(data);
                         }
@@ -862,6 +879,7 @@ impl Convert for (usize, V7Operation) {
 
                         let data = LocalAddress(address,32);
                         if (rt == Register::PC){
+                            Register("EPSR.T") = data;
                             data = data & REMOVE_LAST_BIT_MASK.local_into();
                             Jump(data);
                         }
@@ -903,6 +921,7 @@ impl Convert for (usize, V7Operation) {
                        }
 
                        if (rt_old == Register::PC){
+                           Register("EPSR.T") = data;
                            data = data & REMOVE_LAST_BIT_MASK.local_into();
                            Jump(data);
                        }
@@ -1034,9 +1053,9 @@ impl Convert for (usize, V7Operation) {
                         rt2 = LocalAddress(address,32);
                     ])
                 }
-                V7Operation::Ldrex(_) => todo!("Hardware semaphores"),
-                V7Operation::Ldrexb(_) => todo!("Hardware semaphores"),
-                V7Operation::Ldrexh(_) => todo!("Hardware semaphores"),
+                V7Operation::Ldrex(_) => return Err(ParseError::Unsupported("Ldrex")),
+                V7Operation::Ldrexb(_) => return Err(ParseError::Unsupported("Ldrexb")),
+                V7Operation::Ldrexh(_) => return Err(ParseError::Unsupported("Ldrexh")),
                 V7Operation::LdrhImmediate(ldrh) => {
                     consume!((
                             rt.local_into(),
@@ -1638,21 +1657,21 @@ impl Convert for (usize, V7Operation) {
                                 // TODO! Add in priv checks
                                 primask = primask & REMOVE_LAST_BIT_MASK.local_into();
                                 let intermediate = rn<0:0>;
-                                apsr |= intermediate;
+                                primask |= intermediate;
                             }
                             if (((sysm>>3) & 0b11111) == 2 && (sysm&0b111 == 1)) {
                                 // TODO! Add in priv checks
-                                basepri = primask<31:8> << 8.local_into();
+                                basepri = basepri<31:8> << 8.local_into();
                                 let intermediate = rn<7:0>;
                                 basepri |= intermediate;
                             }
                             if (((sysm>>3) & 0b11111) == 2 && (sysm&0b111 == 2)) {
                                 // TODO! Add in priv checks
-                                basepri = primask<31:8> << 8.local_into();
+                                basepri = basepri<31:8> << 8.local_into();
                                 let intermediate = rn<7:0>;
                                 basepri |= intermediate;
                             }
-                            if (((sysm>>3) & 0b11111) == 2 && (sysm&0b111 == 2)) {
+                            if (((sysm>>3) & 0b11111) == 2 && (sysm&0b111 == 3)) {
                                 // TODO! Add om priv and priority checks here
                                 faultmask = faultmask & REMOVE_LAST_BIT_MASK.local_into();
                                 let intermediate = rn<0:0>;
@@ -1849,12 +1868,12 @@ impl Convert for (usize, V7Operation) {
                     // NOTE:
                     // This should be logged in the ARMv7 struct so we can know that the address
                     // was preloaded in the cycle estimates.
-                    todo!(" We need some speciality pre load instruction here")
+                    return Err(ParseError::Unsupported("PldImmediate"))
                 }
-                V7Operation::PldLiteral(_) => todo!(" We need some speciality pre load instruction here"),
-                V7Operation::PldRegister(_) => todo!(" We need some speciality pre load instruction here"),
-                V7Operation::PliImmediate(_) => todo!(" We need some speciality pre load instruction here"),
-                V7Operation::PliRegister(_) => todo!(" We need some speciality pre load instruction here"),
+                V7Operation::PldLiteral(_) => return Err(ParseError::Unsupported("PldLiteral")),
+                V7Operation::PldRegister(_) => return Err(ParseError::Unsupported("PldRegister")),
+                V7Operation::PliImmediate(_) => return Err(ParseError::Unsupported("PliImmediate")),
+                V7Operation::PliRegister(_) => return Err(ParseError::Unsupported("PliRegister")),
                 V7Operation::Pop(pop) => {
                     consume!((registers) from pop);
 
@@ -1878,6 +1897,7 @@ impl Convert for (usize, V7Operation) {
                         }
                         if (jump) {
                             address = LocalAddress(address,32);
+                            Register("EPSR.T") = address;
                             address = address & REMOVE_LAST_BIT_MASK.local_into();
                             Jump(address);
                         }
@@ -1885,8 +1905,11 @@ impl Convert for (usize, V7Operation) {
                 }
                 V7Operation::Push(push) => {
                     consume!((registers) from push);
-                    assert!(!registers.registers.contains(&Register::SP));
-                    assert!(!registers.registers.contains(&Register::PC));
+                    if registers.registers.contains(&Register::SP)
+                        || registers.registers.contains(&Register::PC)
+                    {
+                        return Err(ParseError::Unpredictable);
+                    }
 
                     let n = registers.registers.len() as u32;
                     pseudo!([
@@ -1900,52 +1923,26 @@ impl Convert for (usize, V7Operation) {
                         Register("SP&") -= (4*n).local_into();
                     ])
                 }
-                V7Operation::Qadd(_) => todo!("Need to figure out how to do saturating operations"),
-                V7Operation::Qadd16(_) => todo!("Need to figure out how to do saturating operations"),
-                V7Operation::Qadd8(_) => todo!("Need to figure out how to do saturating operations"),
-                V7Operation::Qasx(_) => todo!("Need to figure out how to do saturating operations"),
-                V7Operation::Qdadd(_) => todo!("Need to figure out how to do saturating operations"),
-                V7Operation::Qdsub(_) => todo!("Need to figure out how to do saturating operations"),
-                V7Operation::Qsax(_) => todo!("Need to figure out how to do saturating operations"),
+                V7Operation::Qadd(_) => return Err(ParseError::Unsupported("Qadd")),
+                V7Operation::Qadd16(_) => return Err(ParseError::Unsupported("Qadd16")),
+                V7Operation::Qadd8(_) => return Err(ParseError::Unsupported("Qadd8")),
+                V7Operation::Qasx(_) => return Err(ParseError::Unsupported("Qasx")),
+                V7Operation::Qdadd(_) => return Err(ParseError::Unsupported("Qdadd")),
+                V7Operation::Qdsub(_) => return Err(ParseError::Unsupported("Qdsub")),
+                V7Operation::Qsax(_) => return Err(ParseError::Unsupported("Qsax")),
                 V7Operation::Qsub(_) => {
-                    todo!("Need to add in the flags APSR.Q");
+                    return Err(ParseError::Unsupported("Qsub"));
                 }
-                V7Operation::Qsub16(_) => todo!("Need to figure out how to do saturating operations"),
-                V7Operation::Qsub8(_) => todo!("Need to figure out how to do saturating operations"),
+                V7Operation::Qsub16(_) => return Err(ParseError::Unsupported("Qsub16")),
+                V7Operation::Qsub8(_) => return Err(ParseError::Unsupported("Qsub8")),
                 V7Operation::Rbit(rbit) => {
                     consume!((rd.local_into(),rm.local_into()) from rbit);
-                    let mut ret = vec![];
-                    local!(intermediate);
-                    let zero = 0.local_into();
-                    for i in 0..31u32 {
-                        let mask = (1 << i).local_into();
-                        let shift = 31 - (i as i32) * 2i32;
-                        match shift > 0 {
-                            true => {
-                                let shift = (shift as u32).local_into();
-                                pseudo!(
-                                    ret.extend[
-                                    intermediate = zero;
-                                    intermediate = rm & mask;
-                                    intermediate =  intermediate << shift;
-                                    rd = rd|intermediate;
-                                    ]
-                                    );
-                            }
-                            false => {
-                                let shift = (-shift as u32).local_into();
-                                pseudo!(
-                                    ret.extend[
-                                    intermediate = zero;
-                                    intermediate = rm & mask;
-                                    intermediate =  intermediate >> shift;
-                                    rd = rd|intermediate;
-                                    ]
-                                    );
-                            }
+                    vec![
+                        Operation::ReverseBits {
+                            destination: rd,
+                            operand: rm
                         }
-                    }
-                    ret
+                    ]
                 }
                 V7Operation::Rev(rev) => {
                     consume!((rd.local_into(),rm.local_into()) from rev);
@@ -2068,34 +2065,29 @@ impl Convert for (usize, V7Operation) {
                 }
                 V7Operation::Rrx(rrx) => {
                     consume!((s,rd.local_into(), rm.local_into()) from rrx);
-                    // Let's fulhacka
-                    let mask = (u32::MAX >> 1).local_into();
-                    let lsb_mask = (1).local_into();
-                    local!(lsb, result, msb);
-                    let carry = Operand::Flag("C".to_owned());
-                    let mut ret = Vec::with_capacity(10);
-                    pseudo!(
-                        ret.extend[
-                        lsb = rm & lsb_mask;
-                        result = rm >> 1.local_into();
-                        msb = carry << 31.local_into();
-                        // Clear the bit first
-                        result = result & mask;
-                        result = result | msb;
-                        rd = result;
-                        ]
-                        );
+                    local!(result);
+                    let mut ret = vec![
+                        Operation::RotateRightExtend {
+                            destination: result.clone(),
+                            operand: rm.clone()
+                        }
+                    ];
 
                     if let Some(true) = s {
                         ret.extend([
                             Operation::SetNFlag(result.clone()),
                             Operation::SetZFlag(result.clone()),
-                            Operation::Move {
-                                destination: carry,
-                                source: lsb
+                            // The bit shifted out becomes the carry.
+                            Operation::SetCFlagSrl {
+                                operand: rm,
+                                shift: 1.local_into()
                             }
                         ]);
                     }
+                    ret.push(Operation::Move {
+                        destination: rd,
+                        source: result
+                    });
                     ret
                 }
                 V7Operation::RsbImmediate(rsb) => {
@@ -2274,19 +2266,14 @@ impl Convert for (usize, V7Operation) {
                 }
                 V7Operation::Sbfx(sbfx) => {
                     consume!((rd.local_into(), rn.local_into(), lsb, width) from sbfx);
-                    let mut ret = vec![];
-
-                    let msb = lsb + (width - 1);
-                    let mask = ((1 << (msb - lsb)) - 1) << lsb;
-
-                    pseudo!(
-                        ret.extend[
-                        let intermediate = rn & mask.local_into();
-                        intermediate = intermediate >> lsb.local_into();
-                        rd = SignExtend(intermediate,width);
-                        ]
-                        );
-                    ret
+                    vec![
+                        Operation::SignedBitFieldExtract {
+                            destination: rd,
+                            operand: rn,
+                            start_bit: lsb,
+                            stop_bit: lsb + (width - 1)
+                        }
+                    ]
                 }
                 V7Operation::Sdiv(sdiv) => {
                     consume!((
@@ -2299,7 +2286,7 @@ impl Convert for (usize, V7Operation) {
                             rd = result;
                     ])
                 }
-                V7Operation::Sel(_) => todo!("SIMD"),
+                V7Operation::Sel(_) => return Err(ParseError::Unsupported("Sel")),
                 V7Operation::Sev(_) => vec![],// todo!("Modelling"),
                 V7Operation::Shadd16(shadd) => {
                     consume!((
@@ -2420,29 +2407,29 @@ impl Convert for (usize, V7Operation) {
                             rd = rd | intemediate_result;
                     ])
                 }
-                V7Operation::Smla(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smlad(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smlal(_) => todo!("Need to revisit SInt"),
-                V7Operation::SmlalSelective(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smlald(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smlaw(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smlsd(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smlsld(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smmla(_) => todo!("Need to revisit SInt"),
+                V7Operation::Smla(_) => return Err(ParseError::Unsupported("Smla")),
+                V7Operation::Smlad(_) => return Err(ParseError::Unsupported("Smlad")),
+                V7Operation::Smlal(_) => return Err(ParseError::Unsupported("Smlal")),
+                V7Operation::SmlalSelective(_) => return Err(ParseError::Unsupported("SmlalSelective")),
+                V7Operation::Smlald(_) => return Err(ParseError::Unsupported("Smlald")),
+                V7Operation::Smlaw(_) => return Err(ParseError::Unsupported("Smlaw")),
+                V7Operation::Smlsd(_) => return Err(ParseError::Unsupported("Smlsd")),
+                V7Operation::Smlsld(_) => return Err(ParseError::Unsupported("Smlsld")),
+                V7Operation::Smmla(_) => return Err(ParseError::Unsupported("Smmla")),
                 V7Operation::Smmls(_) => {
-                    todo!()
-                }
-                V7Operation::Smmul(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smuad(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smul(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smull(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smulw(_) => todo!("Need to revisit SInt"),
-                V7Operation::Smusd(_) => todo!("Need to revisit SInt"),
-                V7Operation::Ssat(_) => todo!("Need to revisit SInt"),
-                V7Operation::Ssat16(_) => todo!("Need to revisit SInt"),
-                V7Operation::Ssax(_) => todo!("Need to revisit SInt"),
-                V7Operation::Ssub16(_) => todo!("Need to revisit SInt"),
-                V7Operation::Ssub8(_) => todo!("Need to revisit SInt"),
+                    return Err(ParseError::Unsupported("Smmls"))
+                }
+                V7Operation::Smmul(_) => return Err(ParseError::Unsupported("Smmul")),
+                V7Operation::Smuad(_) => return Err(ParseError::Unsupported("Smuad")),
+                V7Operation::Smul(_) => return Err(ParseError::Unsupported("Smul")),
+                V7Operation::Smull(_) => return Err(ParseError::Unsupported("Smull")),
+                V7Operation::Smulw(_) => return Err(ParseError::Unsupported("Smulw")),
+                V7Operation::Smusd(_) => return Err(ParseError::Unsupported("Smusd")),
+                V7Operation::Ssat(_) => return Err(ParseError::Unsupported("Ssat")),
+                V7Operation::Ssat16(_) => return Err(ParseError::Unsupported("Ssat16")),
+                V7Operation::Ssax(_) => return Err(ParseError::Unsupported("Ssax")),
+                V7Operation::Ssub16(_) => return Err(ParseError::Unsupported("Ssub16")),
+                V7Operation::Ssub8(_) => return Err(ParseError::Unsupported("Ssub8")),
                 V7Operation::Stm(stm) => {
                     consume!(
                         (
@@ -2721,10 +2708,8 @@ impl Convert for (usize, V7Operation) {
                             shift
                             ) from strh);
                     let shift_n = match shift {
-                        Some(shift) => {
-                            assert!(shift.shift_t == Shift::Lsl);
-                            shift.shift_n as u32
-                        }
+                        Some(shift) if shift.shift_t == Shift::Lsl => shift.shift_n as u32,
+                        Some(_) => return Err(ParseError::InvalidInstruction),
                         None => 0,
                     }
                     .local_into();
@@ -3321,17 +3306,17 @@ impl Convert for (usize, V7Operation) {
                         rdlo = result<31:0:u64>;
                     ])
                 }
-                V7Operation::Uqadd16(_) => todo!("TODO! Look in to saturating operators"),
-                V7Operation::Uqadd8(_) => todo!("TODO! Look in to saturating operators"),
-                V7Operation::Uqasx(_) => todo!("TODO! Look in to saturating"),
-                V7Operation::Uqsax(_) => todo!("TODO! ^"),
-                V7Operation::Uqsub16(_) => todo!("TODO! ^"),
-                V7Operation::Uqsub8(_) => todo!("TODO! ^"),
-                V7Operation::Uqsad8(_) => todo!("TODO! ^"),
-                V7Operation::Usada8(_) => todo!("TODO! ^"),
-                V7Operation::Usad8(_) => todo!("TODO! Look in to why ABS is needed here"),
-                V7Operation::Usat(_) => todo!("TODO! Look in to why ABS is needed here"),
-                V7Operation::Usat16(_) => todo!("TODO! Look in to SAT"),
+                V7Operation::Uqadd16(_) => return Err(ParseError::Unsupported("Uqadd16")),
+                V7Operation::Uqadd8(_) => return Err(ParseError::Unsupported("Uqadd8")),
+                V7Operation::Uqasx(_) => return Err(ParseError::Unsupported("Uqasx")),
+                V7Operation::Uqsax(_) => return Err(ParseError::Unsupported("Uqsax")),
+                V7Operation::Uqsub16(_) => return Err(ParseError::Unsupported("Uqsub16")),
+                V7Operation::Uqsub8(_) => return Err(ParseError::Unsupported("Uqsub8")),
+                V7Operation::Uqsad8(_) => return Err(ParseError::Unsupported("Uqsad8")),
+                V7Operation::Usada8(_) => return Err(ParseError::Unsupported("Usada8")),
+                V7Operation::Usad8(_) => return Err(ParseError::Unsupported("Usad8")),
+                V7Operation::Usat(_) => return Err(ParseError::Unsupported("Usat")),
+                V7Operation::Usat16(_) => return Err(ParseError::Unsupported("Usat16")),
                 V7Operation::Usax(usax) => {
                     let (rn, rd, rm) = (usax.rn.local_into(), usax.rd.local_into(), usax.rm.local_into());
                     let rd = rd.unwrap_or(rn.clone());
@@ -3358,7 +3343,7 @@ impl Convert for (usize, V7Operation) {
                     ])
                 }
                 V7Operation::Usub8(_) => {
-                    todo!("SIMD needs more work");
+                    return Err(ParseError::Unsupported("Usub8"));
                 }
                 V7Operation::Uxtab(uxtab) => {
                     let (
@@ -3431,17 +3416,17 @@ impl Convert for (usize, V7Operation) {
                 //Here we have to assume intant return.
                 V7Operation::Yield(_) => vec![],//todo!("This requires extensive system modelling"),
                 // I think that we should simply write Any here. i.e. they are noops.
-                V7Operation::Svc(_) => todo!(),
-                V7Operation::Stc(_) => todo!(),
-                V7Operation::Mcr(_) => todo!(),
-                V7Operation::Mrc(_) => todo!(),
-                V7Operation::Mrrc(_) => todo!(),
-                V7Operation::Mcrr(_) => todo!(),
-                V7Operation::Cdp(_) => todo!(),
-                V7Operation::LdcLiteral(_) => todo!(),
-                V7Operation::LdcImmediate(_) => todo!(),
+                V7Operation::Svc(_) => return Err(ParseError::Unsupported("Svc")),
+                V7Operation::Stc(_) => return Err(ParseError::Unsupported("Stc")),
+                V7Operation::Mcr(_) => return Err(ParseError::Unsupported("Mcr")),
+                V7Operation::Mrc(_) => return Err(ParseError::Unsupported("Mrc")),
+                V7Operation::Mrrc(_) => return Err(ParseError::Unsupported("Mrrc")),
+                V7Operation::Mcrr(_) => return Err(ParseError::Unsupported("Mcrr")),
+                V7Operation::Cdp(_) => return Err(ParseError::Unsupported("Cdp")),
+                V7Operation::LdcLiteral(_) => return Err(ParseError::Unsupported("LdcLiteral")),
+                V7Operation::LdcImmediate(_) => return Err(ParseError::Unsupported("LdcImmediate")),
             }
-        }
+        })
     }
 }
 
@@ -3567,6 +3552,7 @@ impl Into<Operand> for u32 {
         Operand::Immediate(DataWord::Word32(self))
     }
 }
+/// Returns a mask of the bits `start` to `end`, inclusive.
 fn mask_dyn(start: u32, end: u32) -> u32 {
-    (1 << (end - start + 1)) - 1
+    (u32::MAX >> (31 - (end - start))) << start
 }
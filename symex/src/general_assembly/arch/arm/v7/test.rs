@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use disarmv7::prelude::{operation::*, *};
 use general_assembly::{
@@ -7,18 +7,13 @@ use general_assembly::{
 };
 
 use super::ArmV7EM;
-use crate::{
-    general_assembly::{
-        arch::arm::v7::decoder::Convert,
-        executor::GAExecutor,
-        instruction::{CycleCount, Instruction},
-        project::Project,
-        state::GAState,
-        vm::VM,
-        Endianness,
-        WordSize,
-    },
-    smt::{DContext, DSolver},
+use crate::general_assembly::{
+    arch::{arm::v7::decoder::Convert, ParseError},
+    executor::GAExecutor,
+    instruction::{CycleCount, Instruction},
+    project::Project,
+    state::GAState,
+    vm::VM,
 };
 
 macro_rules! get_operand {
@@ -154,37 +149,19 @@ macro_rules! initiate {
 
 fn setup_test_vm() -> VM<ArmV7EM> {
     // create an empty project
-    let mut project = Box::new(Project::manual_project(
-        vec![],
-        0,
-        0,
-        WordSize::Bit32,
-        Endianness::Little,
-        HashMap::new(),
-        HashMap::new(),
-        HashMap::new(),
-        HashMap::new(),
-        HashMap::new(),
-        vec![],
-        HashMap::new(),
-        vec![],
-    ));
-    let mut arch = ArmV7EM::default();
-    project.add_hooks(&mut arch);
-
-    let project = Box::leak(project);
-    let context = Box::new(DContext::new());
-    let context = Box::leak(context);
-    let solver = DSolver::new(context);
-    let state = GAState::create_test_state(project, context, solver, 0, u32::MAX as u64, arch);
-    let vm = VM::new_with_state(project, state);
-    vm
+    let mut project = Project::test_project();
+    let arch = ArmV7EM::default();
+    project.add_hooks(&arch);
+
+    let project = Arc::new(project);
+    let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, arch);
+    VM::new_with_state(project, state)
 }
 
 #[test]
 fn test_adc_no_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -205,7 +182,7 @@ fn test_adc_no_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -223,7 +200,7 @@ fn test_adc_no_set_flag() {
 #[test]
 fn test_adc_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -244,7 +221,7 @@ fn test_adc_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -275,7 +252,7 @@ fn test_adc_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -310,7 +287,7 @@ fn test_adc_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(true),
+        operations: (16, instruction).convert(true).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -345,7 +322,7 @@ fn test_adc_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(true),
+        operations: (16, instruction).convert(true).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -365,7 +342,7 @@ fn test_adc_set_flag() {
 #[test]
 fn test_adc_imm_no_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -383,7 +360,7 @@ fn test_adc_imm_no_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -400,7 +377,7 @@ fn test_adc_imm_no_set_flag() {
 #[test]
 fn test_adc_immediate_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -420,7 +397,7 @@ fn test_adc_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -449,7 +426,7 @@ fn test_adc_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -469,7 +446,7 @@ fn test_adc_immediate_set_flag() {
 #[test]
 fn test_add_no_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -490,7 +467,7 @@ fn test_add_no_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -508,7 +485,7 @@ fn test_add_no_set_flag() {
 #[test]
 fn test_add_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -529,7 +506,7 @@ fn test_add_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -560,7 +537,7 @@ fn test_add_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -595,7 +572,7 @@ fn test_add_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(true),
+        operations: (16, instruction).convert(true).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -630,7 +607,7 @@ fn test_add_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(true),
+        operations: (16, instruction).convert(true).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -650,7 +627,7 @@ fn test_add_set_flag() {
 #[test]
 fn test_add_imm_no_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -668,7 +645,7 @@ fn test_add_imm_no_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -686,7 +663,7 @@ fn test_add_imm_no_set_flag() {
 #[test]
 fn test_add_immediate_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -706,7 +683,7 @@ fn test_add_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -735,7 +712,7 @@ fn test_add_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -755,7 +732,7 @@ fn test_add_immediate_set_flag() {
 #[test]
 fn test_add_sp_immediate() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -773,7 +750,7 @@ fn test_add_sp_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -801,7 +778,7 @@ fn test_add_sp_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -829,7 +806,7 @@ fn test_add_sp_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -847,7 +824,7 @@ fn test_add_sp_immediate() {
 #[test]
 fn test_add_sp_reg() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -866,7 +843,7 @@ fn test_add_sp_reg() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -895,7 +872,7 @@ fn test_add_sp_reg() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -927,7 +904,7 @@ fn test_add_sp_reg() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -945,7 +922,7 @@ fn test_add_sp_reg() {
 #[test]
 fn test_adr() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -962,7 +939,7 @@ fn test_adr() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -988,7 +965,7 @@ fn test_adr() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1014,7 +991,7 @@ fn test_adr() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1031,7 +1008,7 @@ fn test_adr() {
 #[test]
 fn test_and_no_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1055,7 +1032,7 @@ fn test_and_no_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1073,7 +1050,7 @@ fn test_and_no_set_flag() {
 #[test]
 fn test_and_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1097,7 +1074,7 @@ fn test_and_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1131,7 +1108,7 @@ fn test_and_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1169,7 +1146,7 @@ fn test_and_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(true),
+        operations: (16, instruction).convert(true).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1207,7 +1184,7 @@ fn test_and_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1245,7 +1222,7 @@ fn test_and_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1265,7 +1242,7 @@ fn test_and_set_flag() {
 #[test]
 fn test_and_imm_no_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1283,7 +1260,7 @@ fn test_and_imm_no_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1301,7 +1278,7 @@ fn test_and_imm_no_set_flag() {
 #[test]
 fn test_and_immediate_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1320,7 +1297,7 @@ fn test_and_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1350,7 +1327,7 @@ fn test_and_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1389,7 +1366,7 @@ fn test_and_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1419,7 +1396,7 @@ fn test_and_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1439,7 +1416,7 @@ fn test_and_immediate_set_flag() {
 #[test]
 fn test_asr_immediate() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1457,7 +1434,7 @@ fn test_asr_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1475,7 +1452,7 @@ fn test_asr_immediate() {
 #[test]
 fn test_asr_immediate_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1495,7 +1472,7 @@ fn test_asr_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1526,7 +1503,7 @@ fn test_asr_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1557,7 +1534,7 @@ fn test_asr_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(true),
+        operations: (16, instruction).convert(true).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1588,7 +1565,7 @@ fn test_asr_immediate_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1607,7 +1584,7 @@ fn test_asr_immediate_set_flag() {
 #[test]
 fn test_asr() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1626,7 +1603,7 @@ fn test_asr() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1644,7 +1621,7 @@ fn test_asr() {
 #[test]
 fn test_asr_set_flag() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1665,7 +1642,7 @@ fn test_asr_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1697,7 +1674,7 @@ fn test_asr_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1729,7 +1706,7 @@ fn test_asr_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(true),
+        operations: (16, instruction).convert(true).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1761,7 +1738,7 @@ fn test_asr_set_flag() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1780,7 +1757,7 @@ fn test_asr_set_flag() {
 #[test]
 fn test_b() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1798,7 +1775,7 @@ fn test_b() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1815,7 +1792,7 @@ fn test_b() {
 #[test]
 fn test_b_conditional() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1833,7 +1810,7 @@ fn test_b_conditional() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1860,7 +1837,7 @@ fn test_b_conditional() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1887,7 +1864,7 @@ fn test_b_conditional() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1914,7 +1891,7 @@ fn test_b_conditional() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1931,7 +1908,7 @@ fn test_b_conditional() {
 #[test]
 fn test_bx() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1946,7 +1923,7 @@ fn test_bx() {
     let instruction: Operation = Bx::builder().set_rm(Register::LR).complete().into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1963,7 +1940,7 @@ fn test_bx() {
 #[test]
 fn test_bfc() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -1982,7 +1959,7 @@ fn test_bfc() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -1997,29 +1974,60 @@ fn test_bfc() {
 }
 
 #[test]
-fn test_bfi() {
+fn test_bfc_shifted() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
     initiate!(executor {
         register PC = 0;
-        register R1 = 0x80010003;
-        register R2 = 12;
-        flag C = true
+        register R1 = 0xffffffff
     });
 
-    let instruction: Operation = Bfi::builder()
+    let instruction: Operation = Bfc::builder()
+        .set_rd(Register::R1)
+        .set_lsb(4)
+        .set_msb(7)
+        .complete()
+        .into();
+
+    let instruction = Instruction {
+        operations: (16, instruction).convert(false).unwrap(),
+        memory_access: false,
+        instruction_size: 16,
+        max_cycle: CycleCount::Value(0),
+    };
+    executor
+        .execute_instruction(&instruction)
+        .expect("Malformed instruction");
+
+    test!(executor {
+        register R1 == 0xffffff0f
+    });
+}
+
+#[test]
+fn test_bfc_full_width() {
+    let mut vm = setup_test_vm();
+    let project = vm.project.clone();
+
+    let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+    initiate!(executor {
+        register PC = 0;
+        register R1 = 0xffffffff
+    });
+
+    let instruction: Operation = Bfc::builder()
         .set_rd(Register::R1)
         .set_lsb(0)
-        .set_msb(4)
-        .set_rn(Register::R2)
+        .set_msb(31)
         .complete()
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2029,15 +2037,14 @@ fn test_bfi() {
         .expect("Malformed instruction");
 
     test!(executor {
-        register R1 == 0x8001000c
+        register R1 == 0
     });
 }
 
 #[test]
-#[should_panic]
-fn test_bfi_panic() {
+fn test_bfi() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2050,14 +2057,14 @@ fn test_bfi_panic() {
 
     let instruction: Operation = Bfi::builder()
         .set_rd(Register::R1)
-        .set_lsb(4)
-        .set_msb(0)
+        .set_lsb(0)
+        .set_msb(4)
         .set_rn(Register::R2)
         .complete()
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2071,10 +2078,78 @@ fn test_bfi_panic() {
     });
 }
 
+#[test]
+fn test_bfi_unpredictable() {
+    let instruction: Operation = Bfi::builder()
+        .set_rd(Register::R1)
+        .set_lsb(4)
+        .set_msb(0)
+        .set_rn(Register::R2)
+        .complete()
+        .into();
+
+    assert!(matches!(
+        (16, instruction).convert(false),
+        Err(ParseError::Unpredictable)
+    ));
+}
+
+#[test]
+fn test_cps_unpredictable() {
+    let instruction: Operation = Cps::builder()
+        .set_enable(true)
+        .set_disable(true)
+        .set_affect_pri(true)
+        .set_affect_fault(false)
+        .complete()
+        .into();
+
+    assert!(matches!(
+        (16, instruction).convert(false),
+        Err(ParseError::Unpredictable)
+    ));
+}
+
+#[test]
+fn test_push_unpredictable() {
+    for register in [Register::SP, Register::PC] {
+        let instruction: Operation = Push::builder()
+            .set_registers(RegisterList {
+                registers: vec![Register::R4, register],
+            })
+            .complete()
+            .into();
+
+        assert!(matches!(
+            (32, instruction).convert(false),
+            Err(ParseError::Unpredictable)
+        ));
+    }
+}
+
+#[test]
+fn test_strh_register_invalid_shift() {
+    let instruction: Operation = StrhRegister::builder()
+        .set_rt(Register::R1)
+        .set_rn(Register::R2)
+        .set_rm(Register::R3)
+        .set_shift(Some(ImmShift {
+            shift_t: Shift::Asr,
+            shift_n: 1,
+        }))
+        .complete()
+        .into();
+
+    assert!(matches!(
+        (32, instruction).convert(false),
+        Err(ParseError::InvalidInstruction)
+    ));
+}
+
 #[test]
 fn test_bic_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2095,7 +2170,7 @@ fn test_bic_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2113,7 +2188,7 @@ fn test_bic_imm() {
 #[test]
 fn test_bic_imm_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2134,7 +2209,7 @@ fn test_bic_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2165,7 +2240,7 @@ fn test_bic_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2184,7 +2259,7 @@ fn test_bic_imm_set_flags() {
 #[test]
 fn test_bic_reg() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2205,7 +2280,7 @@ fn test_bic_reg() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2223,7 +2298,7 @@ fn test_bic_reg() {
 #[test]
 fn test_bic_reg_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2247,7 +2322,7 @@ fn test_bic_reg_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2281,7 +2356,7 @@ fn test_bic_reg_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2300,7 +2375,7 @@ fn test_bic_reg_set_flags() {
 #[test]
 fn test_bl() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2335,7 +2410,7 @@ fn test_bl() {
     let instruction: Operation = Bl::builder().set_imm(0x4).complete().into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2387,7 +2462,7 @@ fn test_bl() {
 #[test]
 fn test_cmp_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2406,7 +2481,7 @@ fn test_cmp_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2437,7 +2512,7 @@ fn test_cmp_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2457,7 +2532,7 @@ fn test_cmp_imm() {
 #[test]
 fn test_ldr_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2479,7 +2554,7 @@ fn test_ldr_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2511,7 +2586,7 @@ fn test_ldr_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2543,7 +2618,7 @@ fn test_ldr_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2575,7 +2650,7 @@ fn test_ldr_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2606,7 +2681,7 @@ fn test_ldr_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2625,7 +2700,7 @@ fn test_ldr_imm() {
 #[test]
 fn test_ldr_literal() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2643,7 +2718,7 @@ fn test_ldr_literal() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2661,7 +2736,7 @@ fn test_ldr_literal() {
 #[test]
 fn test_ldr_register() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2682,7 +2757,7 @@ fn test_ldr_register() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2714,7 +2789,7 @@ fn test_ldr_register() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2749,7 +2824,7 @@ fn test_ldr_register() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2767,7 +2842,7 @@ fn test_ldr_register() {
 #[test]
 fn test_ldrh_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2789,7 +2864,7 @@ fn test_ldrh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2820,7 +2895,7 @@ fn test_ldrh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2851,7 +2926,7 @@ fn test_ldrh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2882,7 +2957,7 @@ fn test_ldrh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2912,7 +2987,7 @@ fn test_ldrh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2930,7 +3005,7 @@ fn test_ldrh_imm() {
 #[test]
 fn test_ldrb_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -2952,7 +3027,7 @@ fn test_ldrb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2984,7 +3059,7 @@ fn test_ldrb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3015,7 +3090,7 @@ fn test_ldrb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3046,7 +3121,7 @@ fn test_ldrb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3077,7 +3152,7 @@ fn test_ldrb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3107,7 +3182,7 @@ fn test_ldrb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3125,7 +3200,7 @@ fn test_ldrb_imm() {
 #[test]
 fn test_lsl_immediate() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3145,7 +3220,7 @@ fn test_lsl_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3178,7 +3253,7 @@ fn test_lsl_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3198,7 +3273,7 @@ fn test_lsl_immediate() {
 #[test]
 fn test_lsr_immediate() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3218,7 +3293,7 @@ fn test_lsr_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3251,7 +3326,7 @@ fn test_lsr_immediate() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3271,7 +3346,7 @@ fn test_lsr_immediate() {
 #[test]
 fn test_mov_imm_no_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3291,7 +3366,7 @@ fn test_mov_imm_no_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3311,7 +3386,7 @@ fn test_mov_imm_no_set_flags() {
 #[test]
 fn test_mov_imm_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3331,7 +3406,7 @@ fn test_mov_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3363,7 +3438,7 @@ fn test_mov_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3383,7 +3458,7 @@ fn test_mov_imm_set_flags() {
 #[test]
 fn test_mov_reg_no_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3403,7 +3478,7 @@ fn test_mov_reg_no_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3423,7 +3498,7 @@ fn test_mov_reg_no_set_flags() {
 #[test]
 fn test_mov_reg_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3443,7 +3518,7 @@ fn test_mov_reg_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3475,7 +3550,7 @@ fn test_mov_reg_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3494,7 +3569,7 @@ fn test_mov_reg_set_flags() {
 #[test]
 fn test_mul() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3515,7 +3590,7 @@ fn test_mul() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3548,7 +3623,7 @@ fn test_mul() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3568,7 +3643,7 @@ fn test_mul() {
 #[test]
 fn test_pop() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3595,7 +3670,7 @@ fn test_pop() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3616,7 +3691,7 @@ fn test_pop() {
 #[test]
 fn test_push() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3641,7 +3716,7 @@ fn test_push() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3662,7 +3737,7 @@ fn test_push() {
 #[test]
 fn test_rsb() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3683,7 +3758,7 @@ fn test_rsb() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3714,7 +3789,7 @@ fn test_rsb() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3731,7 +3806,7 @@ fn test_rsb() {
 #[test]
 fn test_strb_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3752,7 +3827,7 @@ fn test_strb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3784,7 +3859,7 @@ fn test_strb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3816,7 +3891,7 @@ fn test_strb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3848,7 +3923,7 @@ fn test_strb_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3867,7 +3942,7 @@ fn test_strb_imm() {
 #[test]
 fn test_strh_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -3891,7 +3966,7 @@ fn test_strh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3926,7 +4001,7 @@ fn test_strh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3961,7 +4036,7 @@ fn test_strh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -3996,7 +4071,7 @@ fn test_strh_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4015,7 +4090,7 @@ fn test_strh_imm() {
 #[test]
 fn test_str_imm() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4039,7 +4114,7 @@ fn test_str_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4073,7 +4148,7 @@ fn test_str_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4107,7 +4182,7 @@ fn test_str_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4141,7 +4216,7 @@ fn test_str_imm() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4159,7 +4234,7 @@ fn test_str_imm() {
 #[test]
 fn test_sub_imm_no_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4181,7 +4256,7 @@ fn test_sub_imm_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4215,7 +4290,7 @@ fn test_sub_imm_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4250,7 +4325,7 @@ fn test_sub_imm_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4271,7 +4346,7 @@ fn test_sub_imm_no_flags() {
 #[test]
 fn test_sub_imm_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4293,7 +4368,7 @@ fn test_sub_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4328,7 +4403,7 @@ fn test_sub_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4349,7 +4424,7 @@ fn test_sub_imm_set_flags() {
 #[test]
 fn test_sub_reg_no_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4373,7 +4448,7 @@ fn test_sub_reg_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4410,7 +4485,7 @@ fn test_sub_reg_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4450,7 +4525,7 @@ fn test_sub_reg_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4471,7 +4546,7 @@ fn test_sub_reg_no_flags() {
 #[test]
 fn test_sub_reg_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4495,7 +4570,7 @@ fn test_sub_reg_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4532,7 +4607,7 @@ fn test_sub_reg_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4553,7 +4628,7 @@ fn test_sub_reg_set_flags() {
 #[test]
 fn test_sub_sp_imm_no_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4574,7 +4649,7 @@ fn test_sub_sp_imm_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4608,7 +4683,7 @@ fn test_sub_sp_imm_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4642,7 +4717,7 @@ fn test_sub_sp_imm_no_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4663,7 +4738,7 @@ fn test_sub_sp_imm_no_flags() {
 #[test]
 fn test_sub_sp_imm_set_flags() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4684,7 +4759,7 @@ fn test_sub_sp_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4718,7 +4793,7 @@ fn test_sub_sp_imm_set_flags() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4739,7 +4814,7 @@ fn test_sub_sp_imm_set_flags() {
 #[test]
 fn test_sub_uxth() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4760,7 +4835,7 @@ fn test_sub_uxth() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4794,7 +4869,7 @@ fn test_sub_uxth() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4815,7 +4890,7 @@ fn test_sub_uxth() {
 #[test]
 fn test_tb() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4839,7 +4914,7 @@ fn test_tb() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4869,7 +4944,7 @@ fn test_tb() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4886,7 +4961,7 @@ fn test_tb() {
 #[test]
 fn test_bfi_2() {
     let mut vm = setup_test_vm();
-    let project = vm.project;
+    let project = vm.project.clone();
 
     let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
@@ -4911,7 +4986,7 @@ fn test_bfi_2() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -4944,7 +5019,7 @@ fn test_bfi_2() {
         .into();
 
     let instruction = Instruction {
-        operations: (16, instruction).convert(false),
+        operations: (16, instruction).convert(false).unwrap(),
         memory_access: false,
         instruction_size: 16,
         max_cycle: CycleCount::Value(0),
@@ -2,6 +2,7 @@ use object::{Architecture, File, Object};
 
 use super::{
     arm::{v6::ArmV6M, v7::ArmV7EM},
+    riscv::rv32i::RiscV32I,
     Arch,
     ArchError,
     SupportedArchitechture,
@@ -12,10 +13,6 @@ impl SupportedArchitechture {
     pub fn discover(obj_file: &File<'_>) -> Result<Self, ArchError> {
         let architecture = obj_file.architecture();
 
-        // Exception here as we will extend this in the future.
-        //
-        // TODO: Remove this allow when risc-v is done.
-        #[allow(clippy::single_match)]
         match architecture {
             Architecture::Arm => {
                 // Run the paths with architecture specific data.
@@ -28,6 +25,11 @@ impl SupportedArchitechture {
                     return Ok(Self::ArmV6M(v6));
                 }
             }
+            Architecture::Riscv32 => {
+                if let Some(rv32i) = RiscV32I::discover(obj_file)? {
+                    return Ok(Self::RiscV32I(rv32i));
+                }
+            }
             _ => {}
         }
         Err(ArchError::UnsuportedArchitechture)
@@ -0,0 +1,101 @@
+//! Defines the supported RISC-V architectures
+//!
+//! The integer registers are named `X1` to `X31` after their index, except for
+//! the return address `x1` and the stack pointer `x2` which are named `LR` and
+//! `SP` as the engine expects. `x0` is hard wired to zero and is never stored.
+pub mod rv32i;
+
+/// Integer registers of the RV32I base instruction set.
+const REGISTERS: &[&str] = &[
+    "LR", "SP", "X3", "X4", "X5", "X6", "X7", "X8", "X9", "X10", "X11", "X12", "X13", "X14", "X15",
+    "X16", "X17", "X18", "X19", "X20", "X21", "X22", "X23", "X24", "X25", "X26", "X27", "X28",
+    "X29", "X30", "X31", "PC",
+];
+
+/// RISC-V has no condition flags, the comparisons of branches and set less than
+/// instructions compute these flags as the ARM comparisons do to reuse the
+/// general assembly conditions. They are not visible to the program.
+const FLAGS: &[&str] = &["N", "Z", "C", "V"];
+
+/// Registers that hold values of up to eight bytes returned by a function in
+/// the ILP32 calling convention, larger values are returned in memory.
+const RETURN_REGISTERS: &[&str] = &["X10", "X11"];
+
+/// Registers that hold the first eight arguments of a function in the ILP32
+/// calling convention, further arguments are passed on the stack.
+const ARGUMENT_REGISTERS: &[&str] = &["X10", "X11", "X12", "X13", "X14", "X15", "X16", "X17"];
+
+/// Registers that a call may change in the ILP32 calling convention, the
+/// return address and the temporary and argument registers.
+const CALLER_SAVED_REGISTERS: &[&str] = &[
+    "LR", "X5", "X6", "X7", "X10", "X11", "X12", "X13", "X14", "X15", "X16", "X17", "X28", "X29",
+    "X30", "X31",
+];
+
+/// Returns the name of the integer register with the dwarf register number
+/// `number`, x0 to x31 are numbered 0 to 31. The zero register is not mapped.
+fn dwarf_register(number: u16) -> Option<&'static str> {
+    match number {
+        1..=31 => Some(REGISTERS[number as usize - 1]),
+        _ => None,
+    }
+}
+
+/// Returns the ABI name and role of `register` in the ILP32 calling convention.
+fn ilp32_role(register: &str) -> Option<&'static str> {
+    Some(match register {
+        "LR" => "ra, return address",
+        "SP" => "sp, stack pointer",
+        "X3" => "gp, global pointer",
+        "X4" => "tp, thread pointer",
+        "X5" => "t0, temporary",
+        "X6" => "t1, temporary",
+        "X7" => "t2, temporary",
+        "X8" => "s0, frame pointer, callee-saved",
+        "X9" => "s1, callee-saved",
+        "X10" => "a0, return value, argument 1",
+        "X11" => "a1, return value, argument 2",
+        "X12" => "a2, argument 3",
+        "X13" => "a3, argument 4",
+        "X14" => "a4, argument 5",
+        "X15" => "a5, argument 6",
+        "X16" => "a6, argument 7",
+        "X17" => "a7, argument 8",
+        "X18" => "s2, callee-saved",
+        "X19" => "s3, callee-saved",
+        "X20" => "s4, callee-saved",
+        "X21" => "s5, callee-saved",
+        "X22" => "s6, callee-saved",
+        "X23" => "s7, callee-saved",
+        "X24" => "s8, callee-saved",
+        "X25" => "s9, callee-saved",
+        "X26" => "s10, callee-saved",
+        "X27" => "s11, callee-saved",
+        "X28" => "t3, temporary",
+        "X29" => "t4, temporary",
+        "X30" => "t5, temporary",
+        "X31" => "t6, temporary",
+        "PC" => "program counter",
+        _ => return None,
+    })
+}
+
+/// Returns the name of integer register `x{index}`, see the module
+/// documentation.
+fn register_name(index: u8) -> String {
+    match index {
+        1 => "LR".to_owned(),
+        2 => "SP".to_owned(),
+        n => format!("X{n}"),
+    }
+}
+
+/// Returns the size in bits of the instruction at the start of `buff`, encoded
+/// in the low bits of its first half word.
+fn instruction_size(buff: &[u8]) -> Option<u32> {
+    let first = buff.first()?;
+    match first & 0b11 {
+        0b11 => Some(32),
+        _ => Some(16),
+    }
+}
@@ -0,0 +1,131 @@
+//! Defines RV32IMC hooks, instruction translation and timings.
+
+pub mod compressed;
+pub mod decoder;
+pub mod parser;
+#[cfg(test)]
+pub mod test;
+pub mod timing;
+
+use std::fmt::Display;
+
+use object::{Architecture, File, Object};
+use tracing::trace;
+
+use crate::{
+    elf_util::{ExpressionType, Variable},
+    general_assembly::{
+        arch::{Arch, ArchError, InstructionSet, ParseError},
+        instruction::Instruction,
+        project::PCHook,
+        state::{GAState, SymbolOrigin},
+        RunConfig,
+    },
+    intrinsics,
+    memory::SmtMap,
+};
+
+/// Type level denotation for the
+/// [RV32I](https://riscv.org/technical/specifications/) base integer
+/// instruction set with the M extension for multiplication and division and
+/// the C extension for compressed instructions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RiscV32I {}
+
+impl InstructionSet for RiscV32I {
+    fn instruction_size(&self, buff: &[u8]) -> Option<u32> {
+        super::instruction_size(buff)
+    }
+
+    fn registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::REGISTERS)
+    }
+
+    fn flags(&self) -> Option<&'static [&'static str]> {
+        Some(super::FLAGS)
+    }
+
+    fn register_role(&self, register: &str) -> Option<&'static str> {
+        super::ilp32_role(register)
+    }
+
+    fn return_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::RETURN_REGISTERS)
+    }
+
+    fn argument_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::ARGUMENT_REGISTERS)
+    }
+
+    fn caller_saved_registers(&self) -> Option<&'static [&'static str]> {
+        Some(super::CALLER_SAVED_REGISTERS)
+    }
+
+    fn dwarf_register(&self, number: u16) -> Option<&'static str> {
+        super::dwarf_register(number)
+    }
+
+    fn mnemonic(&self, buff: &[u8]) -> Option<String> {
+        let operation = parser::parse(buff).ok()?;
+        Some(operation.mnemonic().to_owned())
+    }
+}
+
+impl Arch for RiscV32I {
+    fn add_hooks(&self, cfg: &mut RunConfig<Self>) {
+        let symbolic_sized = |state: &mut GAState<Self>| {
+            let value_ptr = state.get_register("X10".to_owned())?;
+            let size = state
+                .get_register("X11".to_owned())?
+                .get_constant()
+                .unwrap()
+                * 8;
+            trace!(
+                "trying to create symbolic: addr: {:?}, size: {}",
+                value_ptr,
+                size
+            );
+            let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
+            let symb_value = state.new_symbol(size as u32, &name, SymbolOrigin::Intrinsic);
+            state.marked_symbolic.push(Variable {
+                name: Some(name),
+                value: symb_value.clone(),
+                ty: ExpressionType::Integer(size as usize),
+            });
+            state.memory.write(&value_ptr, symb_value)?;
+
+            let lr = state.get_register("LR".to_owned())?;
+            state.set_register("PC".to_owned(), lr)?;
+            Ok(())
+        };
+
+        cfg.pc_hooks.push((
+            intrinsics::hook_pattern("symbolic_size"),
+            PCHook::Intrinsic(symbolic_sized),
+        ));
+    }
+
+    fn translate(
+        &self,
+        buff: &[u8],
+        _in_conditional_block: bool,
+    ) -> Result<Instruction<Self>, ArchError> {
+        let operation = parser::parse(buff)?;
+        let size = super::instruction_size(buff).ok_or(ParseError::InsufficientInput)?;
+        Ok(Self::expand(operation, size)?)
+    }
+
+    fn discover(file: &File<'_>) -> Result<Option<Self>, ArchError> {
+        match file {
+            File::Elf32(_) if file.architecture() == Architecture::Riscv32 => Ok(Some(RiscV32I {})),
+            File::Elf32(_) => Ok(None),
+            _ => Err(ArchError::IncorrectFileType),
+        }
+    }
+}
+
+impl Display for RiscV32I {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RV32IMC")
+    }
+}
@@ -0,0 +1,232 @@
+//! Decodes the 16 bit encodings of the C extension for RV32.
+//!
+//! Every compressed instruction is decoded to the [`Operation`] of the 32 bit
+//! instruction it expands to. The compressed floating point loads and stores
+//! are not supported.
+
+use super::parser::{bits, sign_extend, AluOperation, BranchCondition, Operation};
+use crate::general_assembly::arch::ParseError;
+
+/// Returns the index of the register `x8` to `x15` encoded in the three bits
+/// starting at bit `low` of `half`.
+fn compact_register(half: u32, low: u32) -> u8 {
+    8 + bits(half, low + 2, low) as u8
+}
+
+/// Returns the shift amount of the shift `half`, amounts of 32 and above are
+/// reserved on RV32.
+fn shift_amount(half: u32) -> Result<i32, ParseError> {
+    match bits(half, 12, 12) {
+        0 => Ok(bits(half, 6, 2) as i32),
+        _ => Err(ParseError::InvalidInstruction),
+    }
+}
+
+/// `rd = rs1 + imm`.
+fn add_immediate(rd: u8, rs1: u8, imm: i32) -> Operation {
+    Operation::OpImm {
+        operation: AluOperation::Add,
+        rd,
+        rs1,
+        imm,
+    }
+}
+
+/// Decodes the compressed instruction `half`.
+pub fn parse(half: u16) -> Result<Operation, ParseError> {
+    let half = half as u32;
+    // The all zero half word is defined to be illegal.
+    if half == 0 {
+        return Err(ParseError::InvalidInstruction);
+    }
+
+    let rd = bits(half, 11, 7) as u8;
+    let rs2 = bits(half, 6, 2) as u8;
+    let rd_compact = compact_register(half, 2);
+    let rs1_compact = compact_register(half, 7);
+    let imm = sign_extend(bits(half, 12, 12) << 5 | bits(half, 6, 2), 6);
+    let word_offset =
+        (bits(half, 12, 10) << 3 | bits(half, 6, 6) << 2 | bits(half, 5, 5) << 6) as i32;
+    let jump_offset = sign_extend(
+        bits(half, 12, 12) << 11
+            | bits(half, 11, 11) << 4
+            | bits(half, 10, 9) << 8
+            | bits(half, 8, 8) << 10
+            | bits(half, 7, 7) << 6
+            | bits(half, 6, 6) << 7
+            | bits(half, 5, 3) << 1
+            | bits(half, 2, 2) << 5,
+        12,
+    );
+    let branch_offset = sign_extend(
+        bits(half, 12, 12) << 8
+            | bits(half, 11, 10) << 3
+            | bits(half, 6, 5) << 6
+            | bits(half, 4, 3) << 1
+            | bits(half, 2, 2) << 5,
+        9,
+    );
+
+    Ok(match (bits(half, 1, 0), bits(half, 15, 13)) {
+        // c.addi4spn
+        (0b00, 0b000) => {
+            let imm = bits(half, 12, 11) << 4
+                | bits(half, 10, 7) << 6
+                | bits(half, 6, 6) << 2
+                | bits(half, 5, 5) << 3;
+            if imm == 0 {
+                return Err(ParseError::InvalidInstruction);
+            }
+            add_immediate(rd_compact, 2, imm as i32)
+        }
+        // c.lw
+        (0b00, 0b010) => Operation::Load {
+            bits: 32,
+            signed: true,
+            rd: rd_compact,
+            rs1: rs1_compact,
+            offset: word_offset,
+        },
+        // c.sw
+        (0b00, 0b110) => Operation::Store {
+            bits: 32,
+            rs1: rs1_compact,
+            rs2: rd_compact,
+            offset: word_offset,
+        },
+        // c.addi and c.nop
+        (0b01, 0b000) => add_immediate(rd, rd, imm),
+        // c.jal
+        (0b01, 0b001) => Operation::Jal {
+            rd: 1,
+            offset: jump_offset,
+        },
+        // c.li
+        (0b01, 0b010) => add_immediate(rd, 0, imm),
+        // c.addi16sp
+        (0b01, 0b011) if rd == 2 => {
+            let imm = sign_extend(
+                bits(half, 12, 12) << 9
+                    | bits(half, 6, 6) << 4
+                    | bits(half, 5, 5) << 6
+                    | bits(half, 4, 3) << 7
+                    | bits(half, 2, 2) << 5,
+                10,
+            );
+            if imm == 0 {
+                return Err(ParseError::InvalidInstruction);
+            }
+            add_immediate(2, 2, imm)
+        }
+        // c.lui
+        (0b01, 0b011) if imm != 0 => Operation::Lui {
+            rd,
+            imm: (imm << 12) as u32,
+        },
+        (0b01, 0b100) => match (bits(half, 11, 10), bits(half, 12, 12), bits(half, 6, 5)) {
+            (0b00, ..) => Operation::OpImm {
+                operation: AluOperation::Srl,
+                rd: rs1_compact,
+                rs1: rs1_compact,
+                imm: shift_amount(half)?,
+            },
+            (0b01, ..) => Operation::OpImm {
+                operation: AluOperation::Sra,
+                rd: rs1_compact,
+                rs1: rs1_compact,
+                imm: shift_amount(half)?,
+            },
+            (0b10, ..) => Operation::OpImm {
+                operation: AluOperation::And,
+                rd: rs1_compact,
+                rs1: rs1_compact,
+                imm,
+            },
+            (0b11, 0, funct2) => Operation::Op {
+                operation: match funct2 {
+                    0b00 => AluOperation::Sub,
+                    0b01 => AluOperation::Xor,
+                    0b10 => AluOperation::Or,
+                    _ => AluOperation::And,
+                },
+                rd: rs1_compact,
+                rs1: rs1_compact,
+                rs2: rd_compact,
+            },
+            _ => return Err(ParseError::InvalidInstruction),
+        },
+        // c.j
+        (0b01, 0b101) => Operation::Jal {
+            rd: 0,
+            offset: jump_offset,
+        },
+        // c.beqz and c.bnez
+        (0b01, funct3 @ (0b110 | 0b111)) => Operation::Branch {
+            condition: match funct3 {
+                0b110 => BranchCondition::Eq,
+                _ => BranchCondition::Ne,
+            },
+            rs1: rs1_compact,
+            rs2: 0,
+            offset: branch_offset,
+        },
+        // c.slli
+        (0b10, 0b000) => Operation::OpImm {
+            operation: AluOperation::Sll,
+            rd,
+            rs1: rd,
+            imm: shift_amount(half)?,
+        },
+        // c.lwsp
+        (0b10, 0b010) if rd != 0 => Operation::Load {
+            bits: 32,
+            signed: true,
+            rd,
+            rs1: 2,
+            offset: (bits(half, 12, 12) << 5 | bits(half, 6, 4) << 2 | bits(half, 3, 2) << 6)
+                as i32,
+        },
+        (0b10, 0b100) => match (bits(half, 12, 12), rd, rs2) {
+            (0, 0, 0) => return Err(ParseError::InvalidInstruction),
+            // c.ebreak
+            (_, 0, 0) => Operation::Ebreak,
+            // c.jr
+            (0, _, 0) => Operation::Jalr {
+                rd: 0,
+                rs1: rd,
+                offset: 0,
+            },
+            // c.mv
+            (0, ..) => Operation::Op {
+                operation: AluOperation::Add,
+                rd,
+                rs1: 0,
+                rs2,
+            },
+            // c.jalr
+            (_, _, 0) => Operation::Jalr {
+                rd: 1,
+                rs1: rd,
+                offset: 0,
+            },
+            // c.add
+            _ => Operation::Op {
+                operation: AluOperation::Add,
+                rd,
+                rs1: rd,
+                rs2,
+            },
+        },
+        // c.swsp
+        (0b10, 0b110) => Operation::Store {
+            bits: 32,
+            rs1: 2,
+            rs2,
+            offset: (bits(half, 12, 9) << 2 | bits(half, 8, 7) << 6) as i32,
+        },
+        (0b00 | 0b10, 0b001 | 0b011 | 0b101 | 0b111) => {
+            return Err(ParseError::Unsupported("compressed floating point"))
+        }
+        _ => return Err(ParseError::InvalidInstruction),
+    })
+}
@@ -0,0 +1,493 @@
+//! Defines translation rules from RV32IMC [`Operation`]s to general assembly
+//! [`Operation`](GAOperation)s.
+
+use general_assembly::{
+    condition::Condition,
+    operand::{DataWord, Operand},
+    operation::Operation as GAOperation,
+};
+
+use super::{
+    parser::{AluOperation, BranchCondition, MulDivOperation, Operation},
+    timing::cycle_count,
+    RiscV32I,
+};
+use crate::general_assembly::{
+    arch::{riscv::register_name, ParseError},
+    instruction::Instruction as GAInstruction,
+};
+
+impl RiscV32I {
+    /// Expands the instruction of `size` bits in to general assembly
+    /// operations.
+    ///
+    /// Returns an error for instructions that are not supported, this never
+    /// panics.
+    pub(super) fn expand(
+        operation: Operation,
+        size: u32,
+    ) -> Result<GAInstruction<RiscV32I>, ParseError> {
+        // The executor advances PC before the operations run, so PC holds the
+        // address of the next instruction.
+        let pc = Operand::Register("PC".to_owned());
+        let bytes = size / 8;
+        let operations = match operation {
+            Operation::Lui { rd, imm } => vec![GAOperation::Move {
+                destination: write(rd),
+                source: immediate(imm),
+            }],
+            Operation::Auipc { rd, imm } => vec![GAOperation::Add {
+                destination: write(rd),
+                operand1: pc,
+                operand2: immediate(imm.wrapping_sub(bytes)),
+            }],
+            Operation::Jal { rd, offset } => {
+                let target = Operand::Local("target".to_owned());
+                vec![
+                    GAOperation::Add {
+                        destination: target.clone(),
+                        operand1: pc.clone(),
+                        operand2: immediate((offset as u32).wrapping_sub(bytes)),
+                    },
+                    GAOperation::Move {
+                        destination: write(rd),
+                        source: pc,
+                    },
+                    GAOperation::ConditionalJump {
+                        destination: target,
+                        condition: Condition::None,
+                    },
+                ]
+            }
+            Operation::Jalr { rd, rs1, offset } => {
+                let target = Operand::Local("target".to_owned());
+                vec![
+                    GAOperation::Add {
+                        destination: target.clone(),
+                        operand1: read(rs1),
+                        operand2: immediate(offset as u32),
+                    },
+                    GAOperation::And {
+                        destination: target.clone(),
+                        operand1: target.clone(),
+                        operand2: immediate(!0b1),
+                    },
+                    GAOperation::Move {
+                        destination: write(rd),
+                        source: pc,
+                    },
+                    GAOperation::ConditionalJump {
+                        destination: target,
+                        condition: Condition::None,
+                    },
+                ]
+            }
+            Operation::Branch {
+                condition,
+                rs1,
+                rs2,
+                offset,
+            } => {
+                let target = Operand::Local("target".to_owned());
+                let mut operations = vec![GAOperation::Add {
+                    destination: target.clone(),
+                    operand1: pc,
+                    operand2: immediate((offset as u32).wrapping_sub(bytes)),
+                }];
+                operations.extend(compare(read(rs1), read(rs2)));
+                operations.push(GAOperation::ConditionalJump {
+                    destination: target,
+                    condition: match condition {
+                        BranchCondition::Eq => Condition::EQ,
+                        BranchCondition::Ne => Condition::NE,
+                        BranchCondition::Lt => Condition::LT,
+                        BranchCondition::Ge => Condition::GE,
+                        BranchCondition::Ltu => Condition::CC,
+                        BranchCondition::Geu => Condition::CS,
+                    },
+                });
+                operations
+            }
+            Operation::Load {
+                bits,
+                signed,
+                rd,
+                rs1,
+                offset,
+            } => {
+                let mut operations = vec![
+                    GAOperation::Add {
+                        destination: Operand::Local("addr".to_owned()),
+                        operand1: read(rs1),
+                        operand2: immediate(offset as u32),
+                    },
+                    GAOperation::Move {
+                        destination: write(rd),
+                        source: Operand::AddressInLocal("addr".to_owned(), bits),
+                    },
+                ];
+                match (bits, signed) {
+                    (32, _) => {}
+                    (_, true) => operations.push(GAOperation::SignExtend {
+                        destination: write(rd),
+                        operand: write(rd),
+                        bits,
+                    }),
+                    (_, false) => operations.push(GAOperation::ZeroExtend {
+                        destination: write(rd),
+                        operand: write(rd),
+                        bits,
+                        target_bits: 32,
+                    }),
+                }
+                operations
+            }
+            Operation::Store {
+                bits,
+                rs1,
+                rs2,
+                offset,
+            } => vec![
+                GAOperation::Add {
+                    destination: Operand::Local("addr".to_owned()),
+                    operand1: read(rs1),
+                    operand2: immediate(offset as u32),
+                },
+                GAOperation::Move {
+                    destination: Operand::AddressInLocal("addr".to_owned(), bits),
+                    source: read(rs2),
+                },
+            ],
+            Operation::OpImm {
+                operation,
+                rd,
+                rs1,
+                imm,
+            } => alu(operation, write(rd), read(rs1), immediate(imm as u32)),
+            Operation::Op {
+                operation,
+                rd,
+                rs1,
+                rs2,
+            } => {
+                let shift = Operand::Local("shift".to_owned());
+                match operation {
+                    // Register shifts only use the low five bits of rs2.
+                    AluOperation::Sll | AluOperation::Srl | AluOperation::Sra => {
+                        let mut operations = vec![GAOperation::And {
+                            destination: shift.clone(),
+                            operand1: read(rs2),
+                            operand2: immediate(0b11111),
+                        }];
+                        operations.extend(alu(operation, write(rd), read(rs1), shift));
+                        operations
+                    }
+                    _ => alu(operation, write(rd), read(rs1), read(rs2)),
+                }
+            }
+            Operation::MulDiv {
+                operation,
+                rd,
+                rs1,
+                rs2,
+            } => mul_div(operation, write(rd), read(rs1), read(rs2)),
+            Operation::Fence | Operation::Ebreak => vec![GAOperation::Nop],
+            Operation::Ecall => return Err(ParseError::Unsupported("ECALL")),
+        };
+
+        Ok(GAInstruction {
+            instruction_size: size,
+            operations,
+            max_cycle: cycle_count(&operation),
+            memory_access: matches!(operation, Operation::Load { .. } | Operation::Store { .. }),
+        })
+    }
+}
+
+/// Operand reading register `x{index}`, `x0` always reads zero.
+fn read(index: u8) -> Operand {
+    match index {
+        0 => immediate(0),
+        n => Operand::Register(register_name(n)),
+    }
+}
+
+/// Operand writing register `x{index}`, writes to `x0` are discarded.
+fn write(index: u8) -> Operand {
+    match index {
+        0 => Operand::Local("discard".to_owned()),
+        n => Operand::Register(register_name(n)),
+    }
+}
+
+fn immediate(value: u32) -> Operand {
+    Operand::Immediate(DataWord::Word32(value))
+}
+
+/// Sets the flags as an ARM `CMP operand1, operand2` would, see
+/// [`FLAGS`](super::super::FLAGS).
+fn compare(operand1: Operand, operand2: Operand) -> Vec<GAOperation> {
+    let result = Operand::Local("result".to_owned());
+    vec![
+        GAOperation::Sub {
+            destination: result.clone(),
+            operand1: operand1.clone(),
+            operand2: operand2.clone(),
+        },
+        GAOperation::SetNFlag(result.clone()),
+        GAOperation::SetZFlag(result),
+        GAOperation::SetCFlag {
+            operand1: operand1.clone(),
+            operand2: operand2.clone(),
+            sub: true,
+            carry: false,
+        },
+        GAOperation::SetVFlag {
+            operand1,
+            operand2,
+            sub: true,
+            carry: false,
+        },
+    ]
+}
+
+fn alu(
+    operation: AluOperation,
+    destination: Operand,
+    operand1: Operand,
+    operand2: Operand,
+) -> Vec<GAOperation> {
+    match operation {
+        AluOperation::Add => vec![GAOperation::Add {
+            destination,
+            operand1,
+            operand2,
+        }],
+        AluOperation::Sub => vec![GAOperation::Sub {
+            destination,
+            operand1,
+            operand2,
+        }],
+        AluOperation::Xor => vec![GAOperation::Xor {
+            destination,
+            operand1,
+            operand2,
+        }],
+        AluOperation::Or => vec![GAOperation::Or {
+            destination,
+            operand1,
+            operand2,
+        }],
+        AluOperation::And => vec![GAOperation::And {
+            destination,
+            operand1,
+            operand2,
+        }],
+        AluOperation::Sll => vec![GAOperation::Sl {
+            destination,
+            operand: operand1,
+            shift: operand2,
+        }],
+        AluOperation::Srl => vec![GAOperation::Srl {
+            destination,
+            operand: operand1,
+            shift: operand2,
+        }],
+        AluOperation::Sra => vec![GAOperation::Sra {
+            destination,
+            operand: operand1,
+            shift: operand2,
+        }],
+        // Signed less than, N != V after the comparison.
+        AluOperation::Slt => {
+            let mut operations = compare(operand1, operand2);
+            operations.push(GAOperation::Xor {
+                destination,
+                operand1: Operand::Flag("N".to_owned()),
+                operand2: Operand::Flag("V".to_owned()),
+            });
+            operations
+        }
+        // Unsigned less than, the subtraction borrowed and cleared C.
+        AluOperation::Sltu => {
+            let mut operations = compare(operand1, operand2);
+            operations.push(GAOperation::Xor {
+                destination,
+                operand1: Operand::Flag("C".to_owned()),
+                operand2: immediate(1),
+            });
+            operations
+        }
+    }
+}
+
+/// Translates a multiplication or division of the M extension. The result is
+/// computed in locals before it is written, as `destination` may be one of the
+/// operands.
+fn mul_div(
+    operation: MulDivOperation,
+    destination: Operand,
+    operand1: Operand,
+    operand2: Operand,
+) -> Vec<GAOperation> {
+    let high = Operand::Local("high".to_owned());
+    let quotient = Operand::Local("quotient".to_owned());
+    let product = Operand::Local("product".to_owned());
+    match operation {
+        MulDivOperation::Mul => vec![GAOperation::Mul {
+            destination,
+            operand1,
+            operand2,
+        }],
+        MulDivOperation::Mulh | MulDivOperation::Mulhsu | MulDivOperation::Mulhu => {
+            let wide1 = Operand::Local("wide1".to_owned());
+            let wide2 = Operand::Local("wide2".to_owned());
+            let mut operations = vec![
+                GAOperation::ZeroExtend {
+                    destination: wide1.clone(),
+                    operand: operand1.clone(),
+                    bits: 32,
+                    target_bits: 64,
+                },
+                GAOperation::ZeroExtend {
+                    destination: wide2.clone(),
+                    operand: operand2.clone(),
+                    bits: 32,
+                    target_bits: 64,
+                },
+                GAOperation::Mul {
+                    destination: wide1.clone(),
+                    operand1: wide1.clone(),
+                    operand2: wide2,
+                },
+                GAOperation::Srl {
+                    destination: wide1.clone(),
+                    operand: wide1.clone(),
+                    shift: Operand::Immediate(DataWord::Word64(32)),
+                },
+                GAOperation::Resize {
+                    destination: high.clone(),
+                    operand: wide1,
+                    bits: 32,
+                },
+            ];
+            // The upper half of a signed product is that of the unsigned
+            // product minus the other operand for each negative operand.
+            if operation != MulDivOperation::Mulhu {
+                operations.extend(subtract_if_negative(
+                    high.clone(),
+                    operand1.clone(),
+                    operand2.clone(),
+                ));
+            }
+            if operation == MulDivOperation::Mulh {
+                operations.extend(subtract_if_negative(high.clone(), operand2, operand1));
+            }
+            operations.push(GAOperation::Move {
+                destination,
+                source: high,
+            });
+            operations
+        }
+        // Unsigned division by zero results in all ones, as RISC-V defines it.
+        MulDivOperation::Divu => vec![GAOperation::UDiv {
+            destination,
+            operand1,
+            operand2,
+        }],
+        // Signed division by zero results in one for a negative dividend and
+        // in minus one otherwise, RISC-V defines it as minus one. Two is
+        // subtracted for a negative dividend and a zero divisor.
+        MulDivOperation::Div => {
+            let zero_divisor = Operand::Local("zero_divisor".to_owned());
+            let mut operations = vec![GAOperation::SDiv {
+                destination: quotient.clone(),
+                operand1: operand1.clone(),
+                operand2: operand2.clone(),
+            }];
+            operations.extend(compare(operand2, immediate(0)));
+            operations.extend([
+                GAOperation::Move {
+                    destination: zero_divisor.clone(),
+                    source: Operand::Flag("Z".to_owned()),
+                },
+                GAOperation::Srl {
+                    destination: product.clone(),
+                    operand: operand1,
+                    shift: immediate(31),
+                },
+                GAOperation::And {
+                    destination: product.clone(),
+                    operand1: product.clone(),
+                    operand2: zero_divisor,
+                },
+                GAOperation::Sl {
+                    destination: product.clone(),
+                    operand: product.clone(),
+                    shift: immediate(1),
+                },
+                GAOperation::Sub {
+                    destination,
+                    operand1: quotient,
+                    operand2: product,
+                },
+            ]);
+            operations
+        }
+        // The remainder of the truncating division, which is the dividend for
+        // a zero divisor and zero on signed overflow as RISC-V defines it.
+        MulDivOperation::Rem | MulDivOperation::Remu => {
+            let divide = match operation {
+                MulDivOperation::Rem => GAOperation::SDiv {
+                    destination: quotient.clone(),
+                    operand1: operand1.clone(),
+                    operand2: operand2.clone(),
+                },
+                _ => GAOperation::UDiv {
+                    destination: quotient.clone(),
+                    operand1: operand1.clone(),
+                    operand2: operand2.clone(),
+                },
+            };
+            vec![
+                divide,
+                GAOperation::Mul {
+                    destination: product.clone(),
+                    operand1: quotient,
+                    operand2,
+                },
+                GAOperation::Sub {
+                    destination,
+                    operand1,
+                    operand2: product,
+                },
+            ]
+        }
+    }
+}
+
+/// Subtracts `subtrahend` from `destination` if `operand` is negative.
+fn subtract_if_negative(
+    destination: Operand,
+    operand: Operand,
+    subtrahend: Operand,
+) -> Vec<GAOperation> {
+    let mask = Operand::Local("mask".to_owned());
+    vec![
+        GAOperation::Sra {
+            destination: mask.clone(),
+            operand,
+            shift: immediate(31),
+        },
+        GAOperation::And {
+            destination: mask.clone(),
+            operand1: mask.clone(),
+            operand2: subtrahend,
+        },
+        GAOperation::Sub {
+            destination: destination.clone(),
+            operand1: destination,
+            operand2: mask,
+        },
+    ]
+}
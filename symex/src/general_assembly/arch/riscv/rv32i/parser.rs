@@ -0,0 +1,358 @@
+//! Decodes the 32 bit encodings of the RV32I base integer instruction set and
+//! the M extension, the 16 bit encodings of the C extension are decoded by
+//! [`compressed`](super::compressed).
+
+use super::compressed;
+use crate::general_assembly::arch::ParseError;
+
+/// Operation of an arithmetic or logic instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AluOperation {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+}
+
+/// Operation of a multiply or divide instruction of the M extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MulDivOperation {
+    Mul,
+    Mulh,
+    Mulhsu,
+    Mulhu,
+    Div,
+    Divu,
+    Rem,
+    Remu,
+}
+
+/// Comparison of a conditional branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchCondition {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+/// A decoded RV32IMC instruction, registers are the indices of `x0` to `x31`.
+/// A compressed instruction is decoded to the instruction it expands to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// `rd = imm`, the immediate holds the upper 20 bits.
+    Lui { rd: u8, imm: u32 },
+
+    /// `rd = pc + imm`, the immediate holds the upper 20 bits.
+    Auipc { rd: u8, imm: u32 },
+
+    /// Jumps to `pc + offset` and links the next address in `rd`.
+    Jal { rd: u8, offset: i32 },
+
+    /// Jumps to `rs1 + offset` with bit 0 cleared and links the next address
+    /// in `rd`.
+    Jalr { rd: u8, rs1: u8, offset: i32 },
+
+    /// Jumps to `pc + offset` if `rs1` and `rs2` satisfy the condition.
+    Branch {
+        condition: BranchCondition,
+        rs1: u8,
+        rs2: u8,
+        offset: i32,
+    },
+
+    /// Loads `bits` bits from `rs1 + offset` to `rd`, sign extended if
+    /// `signed` is set.
+    Load {
+        bits: u32,
+        signed: bool,
+        rd: u8,
+        rs1: u8,
+        offset: i32,
+    },
+
+    /// Stores the low `bits` bits of `rs2` to `rs1 + offset`.
+    Store {
+        bits: u32,
+        rs1: u8,
+        rs2: u8,
+        offset: i32,
+    },
+
+    /// `rd = rs1 op imm`.
+    OpImm {
+        operation: AluOperation,
+        rd: u8,
+        rs1: u8,
+        imm: i32,
+    },
+
+    /// `rd = rs1 op rs2`.
+    Op {
+        operation: AluOperation,
+        rd: u8,
+        rs1: u8,
+        rs2: u8,
+    },
+
+    /// `rd = rs1 op rs2` of the M extension.
+    MulDiv {
+        operation: MulDivOperation,
+        rd: u8,
+        rs1: u8,
+        rs2: u8,
+    },
+
+    /// Orders memory accesses, `FENCE` and `FENCE.I`.
+    Fence,
+
+    /// Environment call.
+    Ecall,
+
+    /// Breakpoint.
+    Ebreak,
+}
+
+impl Operation {
+    /// Get the mnemonic of the instruction.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Operation::Lui { .. } => "LUI",
+            Operation::Auipc { .. } => "AUIPC",
+            Operation::Jal { .. } => "JAL",
+            Operation::Jalr { .. } => "JALR",
+            Operation::Branch { condition, .. } => match condition {
+                BranchCondition::Eq => "BEQ",
+                BranchCondition::Ne => "BNE",
+                BranchCondition::Lt => "BLT",
+                BranchCondition::Ge => "BGE",
+                BranchCondition::Ltu => "BLTU",
+                BranchCondition::Geu => "BGEU",
+            },
+            Operation::Load { bits, signed, .. } => match (bits, signed) {
+                (8, true) => "LB",
+                (16, true) => "LH",
+                (8, false) => "LBU",
+                (16, false) => "LHU",
+                _ => "LW",
+            },
+            Operation::Store { bits, .. } => match bits {
+                8 => "SB",
+                16 => "SH",
+                _ => "SW",
+            },
+            Operation::OpImm { operation, .. } => match operation {
+                AluOperation::Add | AluOperation::Sub => "ADDI",
+                AluOperation::Sll => "SLLI",
+                AluOperation::Slt => "SLTI",
+                AluOperation::Sltu => "SLTIU",
+                AluOperation::Xor => "XORI",
+                AluOperation::Srl => "SRLI",
+                AluOperation::Sra => "SRAI",
+                AluOperation::Or => "ORI",
+                AluOperation::And => "ANDI",
+            },
+            Operation::Op { operation, .. } => match operation {
+                AluOperation::Add => "ADD",
+                AluOperation::Sub => "SUB",
+                AluOperation::Sll => "SLL",
+                AluOperation::Slt => "SLT",
+                AluOperation::Sltu => "SLTU",
+                AluOperation::Xor => "XOR",
+                AluOperation::Srl => "SRL",
+                AluOperation::Sra => "SRA",
+                AluOperation::Or => "OR",
+                AluOperation::And => "AND",
+            },
+            Operation::MulDiv { operation, .. } => match operation {
+                MulDivOperation::Mul => "MUL",
+                MulDivOperation::Mulh => "MULH",
+                MulDivOperation::Mulhsu => "MULHSU",
+                MulDivOperation::Mulhu => "MULHU",
+                MulDivOperation::Div => "DIV",
+                MulDivOperation::Divu => "DIVU",
+                MulDivOperation::Rem => "REM",
+                MulDivOperation::Remu => "REMU",
+            },
+            Operation::Fence => "FENCE",
+            Operation::Ecall => "ECALL",
+            Operation::Ebreak => "EBREAK",
+        }
+    }
+}
+
+/// Sign extends the low `bits` bits of `value`.
+pub(super) fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+/// Extracts bits `high` down to `low` of `word`.
+pub(super) fn bits(word: u32, high: u32, low: u32) -> u32 {
+    (word >> low) & ((1 << (high - low + 1)) - 1)
+}
+
+/// Decodes the instruction at the start of `buff`, which is stored little
+/// endian as all RISC-V instructions.
+///
+/// The instructions of extensions other than M and C are not supported.
+pub fn parse(buff: &[u8]) -> Result<Operation, ParseError> {
+    let half = buff.get(..2).ok_or(ParseError::InsufficientInput)?;
+    if half[0] & 0b11 != 0b11 {
+        return compressed::parse(u16::from_le_bytes([half[0], half[1]]));
+    }
+    let bytes = buff.get(..4).ok_or(ParseError::InsufficientInput)?;
+    let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+    let rd = bits(word, 11, 7) as u8;
+    let rs1 = bits(word, 19, 15) as u8;
+    let rs2 = bits(word, 24, 20) as u8;
+    let funct3 = bits(word, 14, 12);
+    let funct7 = bits(word, 31, 25);
+    let i_imm = sign_extend(bits(word, 31, 20), 12);
+    let s_imm = sign_extend(bits(word, 31, 25) << 5 | bits(word, 11, 7), 12);
+    let b_imm = sign_extend(
+        bits(word, 31, 31) << 12
+            | bits(word, 7, 7) << 11
+            | bits(word, 30, 25) << 5
+            | bits(word, 11, 8) << 1,
+        13,
+    );
+    let j_imm = sign_extend(
+        bits(word, 31, 31) << 20
+            | bits(word, 19, 12) << 12
+            | bits(word, 20, 20) << 11
+            | bits(word, 30, 21) << 1,
+        21,
+    );
+
+    Ok(match bits(word, 6, 0) {
+        0b0110111 => Operation::Lui {
+            rd,
+            imm: word & 0xFFFF_F000,
+        },
+        0b0010111 => Operation::Auipc {
+            rd,
+            imm: word & 0xFFFF_F000,
+        },
+        0b1101111 => Operation::Jal { rd, offset: j_imm },
+        0b1100111 if funct3 == 0 => Operation::Jalr {
+            rd,
+            rs1,
+            offset: i_imm,
+        },
+        0b1100011 => Operation::Branch {
+            condition: match funct3 {
+                0b000 => BranchCondition::Eq,
+                0b001 => BranchCondition::Ne,
+                0b100 => BranchCondition::Lt,
+                0b101 => BranchCondition::Ge,
+                0b110 => BranchCondition::Ltu,
+                0b111 => BranchCondition::Geu,
+                _ => return Err(ParseError::InvalidInstruction),
+            },
+            rs1,
+            rs2,
+            offset: b_imm,
+        },
+        0b0000011 => {
+            let (bits, signed) = match funct3 {
+                0b000 => (8, true),
+                0b001 => (16, true),
+                0b010 => (32, true),
+                0b100 => (8, false),
+                0b101 => (16, false),
+                _ => return Err(ParseError::InvalidInstruction),
+            };
+            Operation::Load {
+                bits,
+                signed,
+                rd,
+                rs1,
+                offset: i_imm,
+            }
+        }
+        0b0100011 => Operation::Store {
+            bits: match funct3 {
+                0b000 => 8,
+                0b001 => 16,
+                0b010 => 32,
+                _ => return Err(ParseError::InvalidInstruction),
+            },
+            rs1,
+            rs2,
+            offset: s_imm,
+        },
+        0b0010011 => {
+            let (operation, imm) = match (funct3, funct7) {
+                (0b000, _) => (AluOperation::Add, i_imm),
+                (0b010, _) => (AluOperation::Slt, i_imm),
+                (0b011, _) => (AluOperation::Sltu, i_imm),
+                (0b100, _) => (AluOperation::Xor, i_imm),
+                (0b110, _) => (AluOperation::Or, i_imm),
+                (0b111, _) => (AluOperation::And, i_imm),
+                (0b001, 0b0000000) => (AluOperation::Sll, rs2 as i32),
+                (0b101, 0b0000000) => (AluOperation::Srl, rs2 as i32),
+                (0b101, 0b0100000) => (AluOperation::Sra, rs2 as i32),
+                _ => return Err(ParseError::InvalidInstruction),
+            };
+            Operation::OpImm {
+                operation,
+                rd,
+                rs1,
+                imm,
+            }
+        }
+        0b0110011 if funct7 == 0b0000001 => Operation::MulDiv {
+            operation: match funct3 {
+                0b000 => MulDivOperation::Mul,
+                0b001 => MulDivOperation::Mulh,
+                0b010 => MulDivOperation::Mulhsu,
+                0b011 => MulDivOperation::Mulhu,
+                0b100 => MulDivOperation::Div,
+                0b101 => MulDivOperation::Divu,
+                0b110 => MulDivOperation::Rem,
+                _ => MulDivOperation::Remu,
+            },
+            rd,
+            rs1,
+            rs2,
+        },
+        0b0110011 => {
+            let operation = match (funct3, funct7) {
+                (0b000, 0b0000000) => AluOperation::Add,
+                (0b000, 0b0100000) => AluOperation::Sub,
+                (0b001, 0b0000000) => AluOperation::Sll,
+                (0b010, 0b0000000) => AluOperation::Slt,
+                (0b011, 0b0000000) => AluOperation::Sltu,
+                (0b100, 0b0000000) => AluOperation::Xor,
+                (0b101, 0b0000000) => AluOperation::Srl,
+                (0b101, 0b0100000) => AluOperation::Sra,
+                (0b110, 0b0000000) => AluOperation::Or,
+                (0b111, 0b0000000) => AluOperation::And,
+                _ => return Err(ParseError::InvalidInstruction),
+            };
+            Operation::Op {
+                operation,
+                rd,
+                rs1,
+                rs2,
+            }
+        }
+        0b0001111 => Operation::Fence,
+        0b1110011 => match word {
+            0x0000_0073 => Operation::Ecall,
+            0x0010_0073 => Operation::Ebreak,
+            _ => return Err(ParseError::Unsupported("CSR")),
+        },
+        _ => return Err(ParseError::InvalidInstruction),
+    })
+}
@@ -0,0 +1,917 @@
+use std::sync::Arc;
+
+use super::{
+    parser::{parse, AluOperation, BranchCondition, MulDivOperation, Operation},
+    RiscV32I,
+};
+use crate::general_assembly::{
+    arch::{Arch, InstructionSet, ParseError},
+    executor::{GAExecutor, PathResult},
+    project::{PCHook, Project},
+    state::GAState,
+    vm::VM,
+    GAError,
+};
+
+fn setup_test_vm() -> VM<RiscV32I> {
+    // create an empty project
+    let mut project = Project::test_project();
+    let arch = RiscV32I::default();
+    project.add_hooks(&arch);
+
+    let project = Arc::new(project);
+    let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, arch);
+    VM::new_with_state(project, state)
+}
+
+fn set(executor: &mut GAExecutor<'_, RiscV32I>, register: &str, value: u64) {
+    let value = executor.state.ctx.from_u64(value, 32);
+    executor
+        .state
+        .set_register(register.to_owned(), value)
+        .unwrap();
+}
+
+fn get(executor: &mut GAExecutor<'_, RiscV32I>, register: &str) -> u64 {
+    executor
+        .state
+        .get_register(register.to_owned())
+        .unwrap()
+        .get_constant()
+        .unwrap()
+}
+
+/// Translates and executes the instruction encoded as `word`.
+fn execute(executor: &mut GAExecutor<'_, RiscV32I>, word: u32) {
+    let instruction = RiscV32I::default()
+        .translate(&word.to_le_bytes(), false)
+        .expect("Malformed instruction");
+    executor
+        .execute_instruction(&instruction)
+        .expect("Malformed instruction");
+}
+
+#[test]
+fn test_parse() {
+    let decode = |word: u32| parse(&word.to_le_bytes());
+
+    // addi a0, a0, -1
+    assert_eq!(
+        decode(0xFFF5_0513),
+        Ok(Operation::OpImm {
+            operation: AluOperation::Add,
+            rd: 10,
+            rs1: 10,
+            imm: -1
+        })
+    );
+    // jal ra, 8
+    assert_eq!(decode(0x0080_00EF), Ok(Operation::Jal { rd: 1, offset: 8 }));
+    // bltu a0, a1, -8
+    assert_eq!(
+        decode(0xFEB5_6CE3),
+        Ok(Operation::Branch {
+            condition: BranchCondition::Ltu,
+            rs1: 10,
+            rs2: 11,
+            offset: -8
+        })
+    );
+    // lw a1, 4(sp)
+    assert_eq!(
+        decode(0x0041_2583),
+        Ok(Operation::Load {
+            bits: 32,
+            signed: true,
+            rd: 11,
+            rs1: 2,
+            offset: 4
+        })
+    );
+    // sw a1, 8(sp)
+    assert_eq!(
+        decode(0x00B1_2423),
+        Ok(Operation::Store {
+            bits: 32,
+            rs1: 2,
+            rs2: 11,
+            offset: 8
+        })
+    );
+    // sra a0, a0, a1
+    assert_eq!(
+        decode(0x40B5_5533),
+        Ok(Operation::Op {
+            operation: AluOperation::Sra,
+            rd: 10,
+            rs1: 10,
+            rs2: 11
+        })
+    );
+    // lui a0, 0x12345
+    assert_eq!(
+        decode(0x1234_5537),
+        Ok(Operation::Lui {
+            rd: 10,
+            imm: 0x1234_5000
+        })
+    );
+    // mulhsu a0, a0, a1
+    assert_eq!(
+        decode(0x02B5_2533),
+        Ok(Operation::MulDiv {
+            operation: MulDivOperation::Mulhsu,
+            rd: 10,
+            rs1: 10,
+            rs2: 11
+        })
+    );
+    // csrr a0, mcycle
+    assert_eq!(decode(0xB000_2573), Err(ParseError::Unsupported("CSR")));
+    assert_eq!(
+        RiscV32I::default().instruction_size(&[0x01, 0x45]),
+        Some(16)
+    );
+}
+
+#[test]
+fn test_parse_immediates() {
+    let decode = |word: u32| parse(&word.to_le_bytes());
+    let branch = |condition, rs1, rs2, offset| {
+        Ok(Operation::Branch {
+            condition,
+            rs1,
+            rs2,
+            offset,
+        })
+    };
+    let store = |bits, offset| {
+        Ok(Operation::Store {
+            bits,
+            rs1: 2,
+            rs2: 11,
+            offset,
+        })
+    };
+
+    // B immediates, the sign is bit 31 and bit 11 of the offset is bit 7.
+    assert_eq!(decode(0x7E00_0FE3), branch(BranchCondition::Eq, 0, 0, 4094));
+    assert_eq!(
+        decode(0x8000_0063),
+        branch(BranchCondition::Eq, 0, 0, -4096)
+    );
+    assert_eq!(
+        decode(0x00B5_10E3),
+        branch(BranchCondition::Ne, 10, 11, 2048)
+    );
+    assert_eq!(decode(0xFEB5_5FE3), branch(BranchCondition::Ge, 10, 11, -2));
+
+    // J immediates, bit 11 of the offset is bit 20.
+    assert_eq!(
+        decode(0x7FFF_F06F),
+        Ok(Operation::Jal {
+            rd: 0,
+            offset: 1_048_574
+        })
+    );
+    assert_eq!(
+        decode(0x8000_00EF),
+        Ok(Operation::Jal {
+            rd: 1,
+            offset: -1_048_576
+        })
+    );
+    assert_eq!(
+        decode(0x0010_006F),
+        Ok(Operation::Jal {
+            rd: 0,
+            offset: 2048
+        })
+    );
+    assert_eq!(
+        decode(0x0000_106F),
+        Ok(Operation::Jal {
+            rd: 0,
+            offset: 4096
+        })
+    );
+    assert_eq!(
+        decode(0xFFFF_F0EF),
+        Ok(Operation::Jal { rd: 1, offset: -2 })
+    );
+
+    // S immediates are split at bit 5.
+    assert_eq!(decode(0xFEB1_2FA3), store(32, -1));
+    assert_eq!(decode(0x7EB1_2FA3), store(32, 2047));
+    assert_eq!(decode(0x80B1_0023), store(8, -2048));
+    assert_eq!(decode(0x00B1_1FA3), store(16, 31));
+
+    // I immediates.
+    let add_immediate = |imm| {
+        Ok(Operation::OpImm {
+            operation: AluOperation::Add,
+            rd: 10,
+            rs1: 0,
+            imm,
+        })
+    };
+    assert_eq!(decode(0x8000_0513), add_immediate(-2048));
+    assert_eq!(decode(0x7FF0_0513), add_immediate(2047));
+    assert_eq!(
+        decode(0xFFF0_8067),
+        Ok(Operation::Jalr {
+            rd: 0,
+            rs1: 1,
+            offset: -1
+        })
+    );
+
+    // U immediates hold the upper bits as they are.
+    assert_eq!(
+        decode(0x8000_0537),
+        Ok(Operation::Lui {
+            rd: 10,
+            imm: 0x8000_0000
+        })
+    );
+    assert_eq!(
+        decode(0xFFFF_F517),
+        Ok(Operation::Auipc {
+            rd: 10,
+            imm: 0xFFFF_F000
+        })
+    );
+}
+
+#[test]
+fn test_parse_invalid() {
+    let decode = |word: u32| parse(&word.to_le_bytes());
+
+    // LOAD-FP, custom-0, AMO and the 48 bit and longer encodings.
+    for word in [
+        0x0000_2007,
+        0x0000_000B,
+        0x0000_202F,
+        0x0000_001F,
+        0x0000_007F,
+    ] {
+        assert_eq!(
+            decode(word),
+            Err(ParseError::InvalidInstruction),
+            "{word:#X}"
+        );
+    }
+    // jalr with funct3 1, a branch with funct3 2, lwu and sd of RV64, slli
+    // with a shift amount of 32 and add with an unknown funct7.
+    for word in [
+        0x0000_9067,
+        0x00B5_2063,
+        0x0081_6503,
+        0x00B1_3423,
+        0x0205_1513,
+        0x10B5_0533,
+    ] {
+        assert_eq!(
+            decode(word),
+            Err(ParseError::InvalidInstruction),
+            "{word:#X}"
+        );
+    }
+
+    // Truncated instructions.
+    assert_eq!(parse(&[0x13]), Err(ParseError::InsufficientInput));
+    assert_eq!(parse(&[0x13, 0x05]), Err(ParseError::InsufficientInput));
+
+    // Invalid instructions are not translated.
+    assert!(RiscV32I::default()
+        .translate(&0x0000_000Bu32.to_le_bytes(), false)
+        .is_err());
+    assert!(RiscV32I::default()
+        .translate(&0x8000u16.to_le_bytes(), false)
+        .is_err());
+}
+
+#[test]
+fn test_parse_compressed() {
+    let decode = |half: u16| parse(&half.to_le_bytes());
+    let add_immediate = |rd, rs1, imm| {
+        Ok(Operation::OpImm {
+            operation: AluOperation::Add,
+            rd,
+            rs1,
+            imm,
+        })
+    };
+
+    // c.li a0, 0
+    assert_eq!(decode(0x4501), add_immediate(10, 0, 0));
+    // c.addi sp, -16
+    assert_eq!(decode(0x1141), add_immediate(2, 2, -16));
+    // c.addi16sp sp, -48
+    assert_eq!(decode(0x7179), add_immediate(2, 2, -48));
+    // c.addi4spn a0, sp, 8
+    assert_eq!(decode(0x0028), add_immediate(10, 2, 8));
+    // c.lui a0, 1
+    assert_eq!(
+        decode(0x6505),
+        Ok(Operation::Lui {
+            rd: 10,
+            imm: 0x1000
+        })
+    );
+    // c.swsp ra, 12(sp)
+    assert_eq!(
+        decode(0xC606),
+        Ok(Operation::Store {
+            bits: 32,
+            rs1: 2,
+            rs2: 1,
+            offset: 12
+        })
+    );
+    // c.lwsp ra, 12(sp)
+    assert_eq!(
+        decode(0x40B2),
+        Ok(Operation::Load {
+            bits: 32,
+            signed: true,
+            rd: 1,
+            rs1: 2,
+            offset: 12
+        })
+    );
+    // c.lw a0, 0(a1)
+    assert_eq!(
+        decode(0x4188),
+        Ok(Operation::Load {
+            bits: 32,
+            signed: true,
+            rd: 10,
+            rs1: 11,
+            offset: 0
+        })
+    );
+    // c.sw a0, 4(a1)
+    assert_eq!(
+        decode(0xC1C8),
+        Ok(Operation::Store {
+            bits: 32,
+            rs1: 11,
+            rs2: 10,
+            offset: 4
+        })
+    );
+    // c.srli a0, 1
+    assert_eq!(
+        decode(0x8105),
+        Ok(Operation::OpImm {
+            operation: AluOperation::Srl,
+            rd: 10,
+            rs1: 10,
+            imm: 1
+        })
+    );
+    // c.andi a0, 15
+    assert_eq!(
+        decode(0x893D),
+        Ok(Operation::OpImm {
+            operation: AluOperation::And,
+            rd: 10,
+            rs1: 10,
+            imm: 15
+        })
+    );
+    // c.sub a0, a1
+    assert_eq!(
+        decode(0x8D0D),
+        Ok(Operation::Op {
+            operation: AluOperation::Sub,
+            rd: 10,
+            rs1: 10,
+            rs2: 11
+        })
+    );
+    // c.mv a0, a1
+    assert_eq!(
+        decode(0x852E),
+        Ok(Operation::Op {
+            operation: AluOperation::Add,
+            rd: 10,
+            rs1: 0,
+            rs2: 11
+        })
+    );
+    // c.jr ra and c.jalr a5
+    assert_eq!(
+        decode(0x8082),
+        Ok(Operation::Jalr {
+            rd: 0,
+            rs1: 1,
+            offset: 0
+        })
+    );
+    assert_eq!(
+        decode(0x9782),
+        Ok(Operation::Jalr {
+            rd: 1,
+            rs1: 15,
+            offset: 0
+        })
+    );
+    // c.j 0 and c.j -4
+    assert_eq!(decode(0xA001), Ok(Operation::Jal { rd: 0, offset: 0 }));
+    assert_eq!(decode(0xBFF5), Ok(Operation::Jal { rd: 0, offset: -4 }));
+    // c.beqz a0, 8
+    assert_eq!(
+        decode(0xC501),
+        Ok(Operation::Branch {
+            condition: BranchCondition::Eq,
+            rs1: 10,
+            rs2: 0,
+            offset: 8
+        })
+    );
+    // c.ebreak
+    assert_eq!(decode(0x9002), Ok(Operation::Ebreak));
+
+    // The all zero instruction, c.slli a0, 34 and c.fldsp
+    assert_eq!(decode(0x0000), Err(ParseError::InvalidInstruction));
+    assert_eq!(decode(0x150A), Err(ParseError::InvalidInstruction));
+    assert_eq!(
+        decode(0x2002),
+        Err(ParseError::Unsupported("compressed floating point"))
+    );
+}
+
+#[test]
+fn test_parse_compressed_quadrants() {
+    let decode = |half: u16| parse(&half.to_le_bytes());
+    let invalid = Err(ParseError::InvalidInstruction);
+    let add_immediate = |rd, rs1, imm| {
+        Ok(Operation::OpImm {
+            operation: AluOperation::Add,
+            rd,
+            rs1,
+            imm,
+        })
+    };
+
+    // Quadrant 0: c.addi4spn a0, sp, 1020 and c.lw a0, 124(a1). The
+    // c.addi4spn with a zero immediate and the encodings with funct3 4 are
+    // reserved.
+    assert_eq!(decode(0x1FE8), add_immediate(10, 2, 1020));
+    assert_eq!(
+        decode(0x5DE8),
+        Ok(Operation::Load {
+            bits: 32,
+            signed: true,
+            rd: 10,
+            rs1: 11,
+            offset: 124
+        })
+    );
+    assert_eq!(decode(0x0004), invalid);
+    assert_eq!(decode(0x8000), invalid);
+
+    // Quadrant 1: c.nop, c.addi a0, -32, c.li a0, 31, c.addi16sp sp, -512
+    // and 496 and c.lui a0, 0xFFFE0.
+    assert_eq!(decode(0x0001), add_immediate(0, 0, 0));
+    assert_eq!(decode(0x1501), add_immediate(10, 10, -32));
+    assert_eq!(decode(0x457D), add_immediate(10, 0, 31));
+    assert_eq!(decode(0x7101), add_immediate(2, 2, -512));
+    assert_eq!(decode(0x617D), add_immediate(2, 2, 496));
+    assert_eq!(
+        decode(0x7501),
+        Ok(Operation::Lui {
+            rd: 10,
+            imm: 0xFFFE_0000
+        })
+    );
+    // c.srai a0, 1 and c.andi a0, -1.
+    assert_eq!(
+        decode(0x8505),
+        Ok(Operation::OpImm {
+            operation: AluOperation::Sra,
+            rd: 10,
+            rs1: 10,
+            imm: 1
+        })
+    );
+    assert_eq!(
+        decode(0x997D),
+        Ok(Operation::OpImm {
+            operation: AluOperation::And,
+            rd: 10,
+            rs1: 10,
+            imm: -1
+        })
+    );
+    // The jump offsets are sign extended from bit 12, c.jal -2048, c.jal 2
+    // and c.j 2046.
+    assert_eq!(
+        decode(0x3001),
+        Ok(Operation::Jal {
+            rd: 1,
+            offset: -2048
+        })
+    );
+    assert_eq!(decode(0x2009), Ok(Operation::Jal { rd: 1, offset: 2 }));
+    assert_eq!(
+        decode(0xAFFD),
+        Ok(Operation::Jal {
+            rd: 0,
+            offset: 2046
+        })
+    );
+    // c.beqz a0, -256, c.bnez s0, 254 and c.bnez a5, -2.
+    let branch = |condition, rs1, offset| {
+        Ok(Operation::Branch {
+            condition,
+            rs1,
+            rs2: 0,
+            offset,
+        })
+    };
+    assert_eq!(decode(0xD101), branch(BranchCondition::Eq, 10, -256));
+    assert_eq!(decode(0xEC7D), branch(BranchCondition::Ne, 8, 254));
+    assert_eq!(decode(0xFFFD), branch(BranchCondition::Ne, 15, -2));
+    // c.addi16sp and c.lui with a zero immediate are reserved, as are the
+    // shifts by 32 or more and c.subw of RV64.
+    assert_eq!(decode(0x6101), invalid);
+    assert_eq!(decode(0x6501), invalid);
+    assert_eq!(decode(0x9105), invalid);
+    assert_eq!(decode(0x9505), invalid);
+    assert_eq!(decode(0x9D0D), invalid);
+
+    // Quadrant 2: c.slli a0, 1, c.lwsp ra, 252(sp), c.swsp ra, 252(sp) and
+    // c.add a0, a1.
+    assert_eq!(
+        decode(0x0506),
+        Ok(Operation::OpImm {
+            operation: AluOperation::Sll,
+            rd: 10,
+            rs1: 10,
+            imm: 1
+        })
+    );
+    assert_eq!(
+        decode(0x50FE),
+        Ok(Operation::Load {
+            bits: 32,
+            signed: true,
+            rd: 1,
+            rs1: 2,
+            offset: 252
+        })
+    );
+    assert_eq!(
+        decode(0xDF86),
+        Ok(Operation::Store {
+            bits: 32,
+            rs1: 2,
+            rs2: 1,
+            offset: 252
+        })
+    );
+    assert_eq!(
+        decode(0x952E),
+        Ok(Operation::Op {
+            operation: AluOperation::Add,
+            rd: 10,
+            rs1: 10,
+            rs2: 11
+        })
+    );
+    // c.lwsp to x0 and c.jr x0 are reserved.
+    assert_eq!(decode(0x4002), invalid);
+    assert_eq!(decode(0x8002), invalid);
+
+    // Quadrant 3 holds the 32 bit instructions, two bytes are not enough.
+    assert_eq!(decode(0x0513), Err(ParseError::InsufficientInput));
+}
+
+#[test]
+fn test_alu() {
+    let mut vm = setup_test_vm();
+    let project = vm.project.clone();
+    let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+    // addi a0, a0, -1
+    set(&mut executor, "X10", 0);
+    execute(&mut executor, 0xFFF5_0513);
+    assert_eq!(get(&mut executor, "X10"), 0xFFFF_FFFF);
+
+    // slt a2, a0, a1 and sltu a2, a0, a1 with a0 = -1 and a1 = 1
+    set(&mut executor, "X11", 1);
+    execute(&mut executor, 0x00B5_2633);
+    assert_eq!(get(&mut executor, "X12"), 1);
+    execute(&mut executor, 0x00B5_3633);
+    assert_eq!(get(&mut executor, "X12"), 0);
+
+    // sra a0, a0, a1 with a0 = -1 shifts in ones
+    execute(&mut executor, 0x40B5_5533);
+    assert_eq!(get(&mut executor, "X10"), 0xFFFF_FFFF);
+
+    // sll a0, a0, a1 only uses the low five bits of a1
+    set(&mut executor, "X10", 1);
+    set(&mut executor, "X11", 33);
+    execute(&mut executor, 0x00B5_1533);
+    assert_eq!(get(&mut executor, "X10"), 2);
+
+    // addi x0, a0, 1 leaves x0 zero, add a0, x0, x0
+    execute(&mut executor, 0x0015_0013);
+    execute(&mut executor, 0x0000_0533);
+    assert_eq!(get(&mut executor, "X10"), 0);
+
+    // auipc a0, 1 at 0x11C
+    execute(&mut executor, 0x0000_1517);
+    assert_eq!(get(&mut executor, "X10"), 0x111C);
+}
+
+#[test]
+fn test_jumps() {
+    let mut vm = setup_test_vm();
+    let project = vm.project.clone();
+    let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+    // jal ra, 8 links the next instruction
+    execute(&mut executor, 0x0080_00EF);
+    assert_eq!(get(&mut executor, "PC"), 0x108);
+    assert_eq!(get(&mut executor, "LR"), 0x104);
+
+    // bltu a0, a1, -8 is taken for 0 < 1
+    set(&mut executor, "X10", 0);
+    set(&mut executor, "X11", 1);
+    execute(&mut executor, 0xFEB5_6CE3);
+    assert_eq!(get(&mut executor, "PC"), 0x100);
+
+    // and falls through for 1 < 1
+    set(&mut executor, "X10", 1);
+    execute(&mut executor, 0xFEB5_6CE3);
+    assert_eq!(get(&mut executor, "PC"), 0x104);
+
+    // jalr x0, 0(ra) clears bit 0 of the target
+    set(&mut executor, "LR", 0x201);
+    execute(&mut executor, 0x0000_8067);
+    assert_eq!(get(&mut executor, "PC"), 0x200);
+}
+
+#[test]
+fn test_load_store() {
+    let mut vm = setup_test_vm();
+    let project = vm.project.clone();
+    let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+    // sw a1, 8(sp)
+    set(&mut executor, "SP", 0x1000);
+    set(&mut executor, "X11", 0x1234_5680);
+    execute(&mut executor, 0x00B1_2423);
+
+    // lw a2, 8(sp)
+    execute(&mut executor, 0x0081_2603);
+    assert_eq!(get(&mut executor, "X12"), 0x1234_5680);
+
+    // lb a0, 8(sp) sign extends and lbu a0, 8(sp) zero extends
+    execute(&mut executor, 0x0081_0503);
+    assert_eq!(get(&mut executor, "X10"), 0xFFFF_FF80);
+    execute(&mut executor, 0x0081_4503);
+    assert_eq!(get(&mut executor, "X10"), 0x80);
+}
+
+/// Returns the results of the M extension instructions writing a2 with a0 and
+/// a1 as operands, in the order mul, mulh, mulhsu, mulhu, div, divu, rem and
+/// remu.
+fn mul_div(executor: &mut GAExecutor<'_, RiscV32I>, a0: u64, a1: u64) -> Vec<u64> {
+    set(executor, "X10", a0);
+    set(executor, "X11", a1);
+    (0..8)
+        .map(|funct3| {
+            execute(executor, 0x02B5_0633 | funct3 << 12);
+            get(executor, "X12")
+        })
+        .collect()
+}
+
+#[test]
+fn test_mul_div() {
+    let mut vm = setup_test_vm();
+    let project = vm.project.clone();
+    let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+    assert_eq!(mul_div(&mut executor, 0xFFFF_FFFE, 3), [
+        0xFFFF_FFFA,
+        0xFFFF_FFFF,
+        0xFFFF_FFFF,
+        2,
+        0,
+        0x5555_5554,
+        0xFFFF_FFFE,
+        2
+    ]);
+    assert_eq!(mul_div(&mut executor, 0xFFFF_FFFE, 0xFFFF_FFFD), [
+        6,
+        0,
+        0xFFFF_FFFE,
+        0xFFFF_FFFB,
+        0,
+        1,
+        0xFFFF_FFFE,
+        1
+    ]);
+
+    // Division by zero results in all ones and the remainder is the dividend.
+    assert_eq!(mul_div(&mut executor, 0xFFFF_FFFE, 0)[4..], [
+        0xFFFF_FFFF,
+        0xFFFF_FFFF,
+        0xFFFF_FFFE,
+        0xFFFF_FFFE
+    ]);
+    assert_eq!(mul_div(&mut executor, 5, 0)[4..], [
+        0xFFFF_FFFF,
+        0xFFFF_FFFF,
+        5,
+        5
+    ]);
+
+    // Signed overflow results in the dividend and a zero remainder.
+    assert_eq!(mul_div(&mut executor, 0x8000_0000, 0xFFFF_FFFF)[4..], [
+        0x8000_0000,
+        0,
+        0,
+        0x8000_0000
+    ]);
+
+    // mul a0, a0, a1 reads its operands before it writes a0
+    set(&mut executor, "X10", 6);
+    set(&mut executor, "X11", 7);
+    execute(&mut executor, 0x02B5_0533);
+    assert_eq!(get(&mut executor, "X10"), 42);
+}
+
+#[test]
+fn test_mul_div_corner_cases() {
+    let mut vm = setup_test_vm();
+    let project = vm.project.clone();
+    let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+    // The high words of the products of the extremes, the operands of mulhsu
+    // are signed times unsigned.
+    assert_eq!(mul_div(&mut executor, 0x8000_0000, 0x8000_0000)[..4], [
+        0,
+        0x4000_0000,
+        0xC000_0000,
+        0x4000_0000
+    ]);
+    assert_eq!(mul_div(&mut executor, 0xFFFF_FFFF, 0xFFFF_FFFF)[..4], [
+        1,
+        0,
+        0xFFFF_FFFF,
+        0xFFFF_FFFE
+    ]);
+
+    // The minimum divided by minus one overflows as signed values only.
+    assert_eq!(mul_div(&mut executor, 0x8000_0000, 0xFFFF_FFFF), [
+        0x8000_0000,
+        0,
+        0x8000_0000,
+        0x7FFF_FFFF,
+        0x8000_0000,
+        0,
+        0,
+        0x8000_0000
+    ]);
+
+    // Signed division rounds towards zero and the remainder has the sign of
+    // the dividend.
+    assert_eq!(mul_div(&mut executor, 0xFFFF_FFF9, 2)[4..], [
+        0xFFFF_FFFD,
+        0x7FFF_FFFC,
+        0xFFFF_FFFF,
+        1
+    ]);
+    assert_eq!(mul_div(&mut executor, 7, 0xFFFF_FFFE)[4..], [
+        0xFFFF_FFFD,
+        0,
+        1,
+        7
+    ]);
+
+    // Division of the minimum by zero.
+    assert_eq!(mul_div(&mut executor, 0x8000_0000, 0)[4..], [
+        0xFFFF_FFFF,
+        0xFFFF_FFFF,
+        0x8000_0000,
+        0x8000_0000
+    ]);
+
+    // div a2, a0, x0 and rem a2, a0, x0 divide by the zero register.
+    set(&mut executor, "X10", 9);
+    execute(&mut executor, 0x0205_4633);
+    assert_eq!(get(&mut executor, "X12"), 0xFFFF_FFFF);
+    execute(&mut executor, 0x0205_6633);
+    assert_eq!(get(&mut executor, "X12"), 9);
+}
+
+#[test]
+fn test_compressed() {
+    let mut vm = setup_test_vm();
+    let project = vm.project.clone();
+    let mut executor = GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+    // c.jal 8 at 0x100 links the next instruction two bytes later
+    execute(&mut executor, 0x2021);
+    assert_eq!(get(&mut executor, "PC"), 0x108);
+    assert_eq!(get(&mut executor, "LR"), 0x102);
+
+    // c.j -4 at 0x108
+    execute(&mut executor, 0xBFF5);
+    assert_eq!(get(&mut executor, "PC"), 0x104);
+
+    // c.addi sp, -16; c.swsp ra, 12(sp); c.lw a0, 12(a1)
+    set(&mut executor, "SP", 0x1010);
+    execute(&mut executor, 0x1141);
+    assert_eq!(get(&mut executor, "SP"), 0x1000);
+    execute(&mut executor, 0xC606);
+    set(&mut executor, "X11", 0x1000);
+    execute(&mut executor, 0x45C8);
+    assert_eq!(get(&mut executor, "X10"), 0x102);
+
+    // c.beqz a0, 8 at 0x10A falls through and is taken for zero
+    execute(&mut executor, 0xC501);
+    assert_eq!(get(&mut executor, "PC"), 0x10C);
+    set(&mut executor, "X10", 0);
+    execute(&mut executor, 0xC501);
+    assert_eq!(get(&mut executor, "PC"), 0x114);
+
+    // c.jr ra
+    execute(&mut executor, 0x8082);
+    assert_eq!(get(&mut executor, "PC"), 0x102);
+}
+
+/// Creates a VM that follows `trace` through `beq a0, zero, 8` at 0x100, the
+/// paths end at 0x10C.
+fn setup_trace_vm(trace: Vec<u64>, a0: Option<u64>) -> VM<RiscV32I> {
+    let program: Vec<u8> = [0x0005_0463u32, 0x0000_0013, 0x0000_0013]
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect();
+    let mut project = Project::test_project().with_program(program, 0x100, 0x10C);
+    let arch = RiscV32I::default();
+    project.add_hooks(&arch);
+    project.add_pc_hook(0x10C, PCHook::EndSuccess);
+    project.set_trace(trace);
+
+    let project = Arc::new(project);
+    let mut state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, arch);
+    let context = state.ctx.clone();
+    let a0 = match a0 {
+        Some(value) => context.from_u64(value, 32),
+        None => context.unconstrained(32, "a0"),
+    };
+    state.set_register("X10".to_owned(), a0).unwrap();
+    state.trace_position = Some(0);
+    VM::new_with_state(project, state)
+}
+
+#[test]
+fn test_follow_trace() {
+    let mut vm = setup_trace_vm(vec![0x108], None);
+
+    // The first path takes the branch as recorded.
+    let (result, mut state) = vm.run().unwrap().unwrap();
+    assert!(matches!(result, PathResult::Success(_)));
+    assert_eq!(state.trace_position, None);
+    let a0 = state.get_register("X10".to_owned()).unwrap();
+    let zero = state.ctx.from_u64(0, 32);
+    assert!(state.constraints.must_be_equal(&a0, &zero).unwrap());
+
+    // The divergence point is explored afterwards.
+    let (result, mut state) = vm.run().unwrap().unwrap();
+    assert!(matches!(result, PathResult::Success(_)));
+    let a0 = state.get_register("X10".to_owned()).unwrap();
+    assert!(!state.constraints.can_equal(&a0, &zero).unwrap());
+    assert!(vm.run().unwrap().is_none());
+}
+
+#[test]
+fn test_trace_diverged() {
+    // The branch is always taken but the trace continues elsewhere.
+    let mut vm = setup_trace_vm(vec![0x10C], Some(0));
+    assert!(matches!(
+        vm.run(),
+        Err(GAError::TraceDiverged {
+            expected: 0x10C,
+            found: 0x108
+        })
+    ));
+
+    // The branch is never taken but the trace continues at its target.
+    let mut vm = setup_trace_vm(vec![0x108], Some(1));
+    assert!(matches!(
+        vm.run(),
+        Err(GAError::TraceDiverged {
+            expected: 0x108,
+            found: 0x104
+        })
+    ));
+}
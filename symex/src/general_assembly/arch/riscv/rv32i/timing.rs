@@ -0,0 +1,50 @@
+//! Provides cycle counting for the RV32IMC instruction set.
+//!
+//! Models a single issue in-order core with a classic five stage pipeline that
+//! predicts all branches as not taken and has single cycle access to memory.
+//! Compressed instructions take as many cycles as the instructions they expand
+//! to. Multiplications are pipelined and divisions use an iterative divider
+//! that computes one bit of the quotient per cycle, which takes 34 cycles.
+
+use super::{
+    parser::{MulDivOperation, Operation},
+    RiscV32I,
+};
+use crate::general_assembly::{instruction::CycleCount, state::GAState};
+
+pub(crate) fn cycle_count(operation: &Operation) -> CycleCount<RiscV32I> {
+    match operation {
+        Operation::Lui { .. }
+        | Operation::Auipc { .. }
+        | Operation::OpImm { .. }
+        | Operation::Op { .. }
+        | Operation::Store { .. }
+        | Operation::Fence
+        | Operation::Ecall
+        | Operation::Ebreak => CycleCount::Value(1),
+        Operation::MulDiv { operation, .. } => match operation {
+            MulDivOperation::Mul
+            | MulDivOperation::Mulh
+            | MulDivOperation::Mulhsu
+            | MulDivOperation::Mulhu => CycleCount::Value(1),
+            MulDivOperation::Div
+            | MulDivOperation::Divu
+            | MulDivOperation::Rem
+            | MulDivOperation::Remu => CycleCount::Value(34),
+        },
+        // The loaded value is available one cycle later.
+        Operation::Load { .. } => CycleCount::Value(2),
+        // Jumps flush the instructions fetched after them.
+        Operation::Jal { .. } | Operation::Jalr { .. } => CycleCount::Value(3),
+        Operation::Branch { .. } => {
+            let max_cycle: fn(state: &GAState<RiscV32I>) -> usize = |state| {
+                if state.get_has_jumped() {
+                    3
+                } else {
+                    1
+                }
+            };
+            CycleCount::Function(max_cycle)
+        }
+    }
+}
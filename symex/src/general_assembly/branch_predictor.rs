@@ -0,0 +1,166 @@
+//! Model of the branch predictor of the processor.
+//!
+//! The cycle counts of the conditional branches assume that the processor
+//! does not predict branches, so every taken branch refills the pipeline. On
+//! cores with a [`branch_predictor`](super::RunConfig::branch_predictor) the
+//! cycles of each conditional branch are instead decided by whether the
+//! direction of the branch was predicted.
+
+use std::{collections::HashMap, fmt::Display};
+
+use general_assembly::{condition::Condition, operation::Operation};
+use serde::Serialize;
+
+use super::{arch::Arch, instruction::Instruction};
+
+/// How the direction of the branches is predicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredictorKind {
+    /// Every branch is predicted to be taken.
+    AlwaysTaken,
+
+    /// Every branch is predicted not to be taken.
+    NeverTaken,
+
+    /// A table of two bit saturating counters indexed by the address of the
+    /// branch, each counter starts as weakly not taken.
+    Bimodal {
+        /// Number of counters in the table.
+        entries: u64,
+    },
+}
+
+/// The branch predictor of a core and the cycles of the predicted branches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchPredictor {
+    /// How the branches are predicted.
+    pub kind: PredictorKind,
+
+    /// Cycles of a taken branch that was predicted to be taken.
+    pub taken_cycles: usize,
+
+    /// Cycles of a branch that was not taken and predicted not to be taken.
+    pub not_taken_cycles: usize,
+
+    /// Cycles of a branch in the direction that was not predicted.
+    pub mispredicted_cycles: usize,
+}
+
+impl BranchPredictor {
+    /// Creates a predictor where correctly predicted branches take one cycle
+    /// and mispredicted branches take `mispredicted_cycles` cycles.
+    pub fn new(kind: PredictorKind, mispredicted_cycles: usize) -> Self {
+        Self {
+            kind,
+            taken_cycles: 1,
+            not_taken_cycles: 1,
+            mispredicted_cycles,
+        }
+    }
+
+    /// Get the cycles of a branch in the direction `taken` that was
+    /// `predicted`.
+    pub fn cycles(&self, taken: bool, predicted: bool) -> usize {
+        match (predicted, taken) {
+            (false, _) => self.mispredicted_cycles,
+            (true, true) => self.taken_cycles,
+            (true, false) => self.not_taken_cycles,
+        }
+    }
+}
+
+/// Number of branches that were predicted and mispredicted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PredictionStats {
+    pub predicted: usize,
+    pub mispredicted: usize,
+}
+
+impl Display for PredictionStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} predicted, {} mispredicted",
+            self.predicted, self.mispredicted
+        )
+    }
+}
+
+/// History of the branch predictor on a path.
+#[derive(Debug, Clone, Default)]
+pub struct PredictorState {
+    /// Counters of the bimodal predictor by index, a counter of two or more
+    /// predicts taken.
+    counters: HashMap<u64, u8>,
+
+    stats: PredictionStats,
+}
+
+impl PredictorState {
+    /// Get the number of predicted and mispredicted branches on the path.
+    pub fn stats(&self) -> PredictionStats {
+        self.stats
+    }
+
+    /// Predicts the branch at `pc`, trains the predictor with the direction
+    /// `taken` and returns if the branch was predicted.
+    pub(crate) fn update(&mut self, predictor: &BranchPredictor, pc: u64, taken: bool) -> bool {
+        let predicted = match predictor.kind {
+            PredictorKind::AlwaysTaken => taken,
+            PredictorKind::NeverTaken => !taken,
+            PredictorKind::Bimodal { entries } => {
+                let counter = self.counters.entry((pc >> 1) % entries).or_insert(1);
+                let predicted = (*counter >= 2) == taken;
+                *counter = match taken {
+                    true => (*counter + 1).min(3),
+                    false => counter.saturating_sub(1),
+                };
+                predicted
+            }
+        };
+        match predicted {
+            true => self.stats.predicted += 1,
+            false => self.stats.mispredicted += 1,
+        }
+        predicted
+    }
+}
+
+/// Checks if `instruction` is a conditional branch.
+pub(crate) fn is_conditional_branch<A: Arch>(instruction: &Instruction<A>) -> bool {
+    instruction.operations.iter().any(|operation| {
+        matches!(operation, Operation::ConditionalJump { condition, .. } if *condition != Condition::None)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BranchPredictor, PredictionStats, PredictorKind, PredictorState};
+
+    #[test]
+    fn test_bimodal_predictor() {
+        let predictor = BranchPredictor::new(PredictorKind::Bimodal { entries: 16 }, 4);
+        let mut state = PredictorState::default();
+
+        // A loop branch that is taken three times and then falls through.
+        let predicted: Vec<_> = [true, true, true, false]
+            .into_iter()
+            .map(|taken| state.update(&predictor, 0x100, taken))
+            .collect();
+        assert_eq!(predicted, vec![false, true, true, false]);
+        // Another branch has its own counter.
+        assert!(state.update(&predictor, 0x102, false));
+        assert_eq!(state.stats(), PredictionStats {
+            predicted: 3,
+            mispredicted: 2
+        });
+
+        assert_eq!(predictor.cycles(true, true), 1);
+        assert_eq!(predictor.cycles(false, false), 4);
+
+        let predictor = BranchPredictor::new(PredictorKind::NeverTaken, 4);
+        let mut state = PredictorState::default();
+        assert!(!state.update(&predictor, 0x100, true));
+        assert!(state.update(&predictor, 0x100, false));
+    }
+}
@@ -0,0 +1,264 @@
+//! Budget of the exploration.
+//!
+//! An [`ExecutionBudget`] bounds the number of instructions of each path, the
+//! number of explored paths, the total time spent in the solver and the number
+//! of iterations of each loop. A loop header is detected when a path jumps
+//! backwards to it, as for the acceleration of loops (see
+//! [`acceleration`](super::acceleration)).
+//!
+//! A path that exceeds the budget ends with
+//! [`PathResult::Truncated`](super::executor::PathResult::Truncated) instead of
+//! running forever. Once the number of paths or the solver time is exhausted
+//! every remaining path is truncated when it is resumed. The cycle counts of a
+//! run with truncated paths are lower bounds of the worst case, as the
+//! truncated paths would have executed more instructions.
+
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use serde::Serialize;
+use tracing::debug;
+
+use super::{
+    arch::Arch,
+    executor::{GAExecutor, PathResult},
+    Result,
+};
+
+/// Limits of the exploration, `None` does not limit the exploration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionBudget {
+    /// Maximum number of instructions executed on a path.
+    pub max_instructions: Option<usize>,
+
+    /// Maximum number of paths to explore, paths forked after the limit is
+    /// reached are truncated.
+    pub max_paths: Option<usize>,
+
+    /// Maximum total time spent in the solver during the run.
+    pub max_solver_time: Option<Duration>,
+
+    /// Maximum number of times a path may jump back to the header of any loop
+    /// that is not bounded in `loop_bounds`.
+    pub loop_bound: Option<usize>,
+
+    /// Maximum number of times a path may jump back to the header of a loop,
+    /// by address of the header.
+    pub loop_bounds: BTreeMap<u64, usize>,
+}
+
+impl ExecutionBudget {
+    /// Creates a budget that does not limit the exploration.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_instructions: None,
+            max_paths: None,
+            max_solver_time: None,
+            loop_bound: None,
+            loop_bounds: BTreeMap::new(),
+        }
+    }
+
+    /// Bounds the loop with the header at `header` to `bound` iterations.
+    pub fn with_loop_bound(mut self, header: u64, bound: usize) -> Self {
+        self.loop_bounds.insert(header & !0b1, bound);
+        self
+    }
+
+    /// Get the bound of the loop with the header at `header`, if any.
+    pub fn loop_bound_at(&self, header: u64) -> Option<usize> {
+        self.loop_bounds.get(&header).copied().or(self.loop_bound)
+    }
+
+    /// Checks if any loop is bounded.
+    fn bounds_loops(&self) -> bool {
+        self.loop_bound.is_some() || !self.loop_bounds.is_empty()
+    }
+
+    /// Checks if the budget limits the exploration.
+    pub fn is_limited(&self) -> bool {
+        self.max_instructions.is_some()
+            || self.max_paths.is_some()
+            || self.max_solver_time.is_some()
+            || self.bounds_loops()
+    }
+}
+
+/// The part of the [`ExecutionBudget`] that a truncated path exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Truncation {
+    /// The path executed the maximum number of instructions.
+    Instructions(usize),
+
+    /// The maximum number of paths was explored before the path.
+    Paths(usize),
+
+    /// The solver was used for the maximum time.
+    SolverTime(Duration),
+
+    /// The path jumped back to the header of a loop more than the bound of
+    /// the loop.
+    LoopBound {
+        /// Address of the header of the loop.
+        header: u64,
+
+        /// Bound of the loop.
+        bound: usize,
+    },
+}
+
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instructions(max) => write!(f, "executed the maximum of {max} instructions"),
+            Self::Paths(max) => write!(f, "explored the maximum of {max} paths"),
+            Self::SolverTime(max) => write!(f, "used the solver for the maximum of {max:?}"),
+            Self::LoopBound { header, bound } => write!(
+                f,
+                "exceeded the bound of {bound} iterations of the loop at {header:#X}"
+            ),
+        }
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Checks the budget of the path before the instruction at the program
+    /// counter is executed.
+    ///
+    /// Returns a result if the path should end.
+    pub(super) fn check_budget(&mut self) -> Result<Option<PathResult>> {
+        let project = self.project.clone();
+        let budget = project.get_budget();
+        if !budget.is_limited() {
+            return Ok(None);
+        }
+
+        let truncation = self.exceeded_budget(budget)?;
+        if let Some(truncation) = truncation {
+            debug!(
+                "Truncating the path at {:#X}, {}",
+                self.state.last_pc, truncation
+            );
+        }
+        Ok(truncation.map(PathResult::Truncated))
+    }
+
+    /// Get the part of `budget` the path has exceeded, if any.
+    fn exceeded_budget(&mut self, budget: &ExecutionBudget) -> Result<Option<Truncation>> {
+        if let Some(max) = budget.max_instructions {
+            if self.state.get_instruction_count() >= max {
+                return Ok(Some(Truncation::Instructions(max)));
+            }
+        }
+        if let Some(max) = budget.max_solver_time {
+            if self.state.ctx.solver_time() >= max {
+                return Ok(Some(Truncation::SolverTime(max)));
+            }
+        }
+        if !budget.bounds_loops() {
+            return Ok(None);
+        }
+
+        let pc = match self.state.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Ok(None),
+        };
+        let back_edge = self.state.get_has_jumped() && self.state.last_pc >= pc;
+        let bound = match budget.loop_bound_at(pc) {
+            Some(bound) if back_edge => bound,
+            _ => return Ok(None),
+        };
+        let iterations = self.state.loop_iterations.entry(pc).or_default();
+        *iterations += 1;
+        match *iterations > bound {
+            true => Ok(Some(Truncation::LoopBound { header: pc, bound })),
+            false => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{ExecutionBudget, Truncation};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::{GAExecutor, PathResult},
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    fn setup_test_vm(budget: ExecutionBudget) -> VM<ArmV6M> {
+        let mut project = Project::test_project();
+        project.set_budget(budget);
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        VM::new_with_state(project, state)
+    }
+
+    #[test]
+    fn test_path_budget() {
+        let budget = ExecutionBudget {
+            max_instructions: Some(8),
+            loop_bound: Some(4),
+            ..ExecutionBudget::unlimited()
+        }
+        .with_loop_bound(0x11, 2);
+        let mut vm = setup_test_vm(budget);
+        let project = vm.project.clone();
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut jump = |from: u64, to: u64| {
+            let ctx = &executor.state.ctx;
+            executor.state.last_pc = from;
+            executor
+                .state
+                .set_register("PC".to_owned(), ctx.from_u64(to, 32))
+                .unwrap();
+            executor.state.set_has_jumped();
+            executor.check_budget().unwrap()
+        };
+
+        // Only jumps back to the header count as iterations.
+        assert!(jump(0x8, 0x10).is_none());
+        assert!(jump(0x14, 0x10).is_none());
+        assert!(jump(0x14, 0x10).is_none());
+        assert!(matches!(
+            jump(0x14, 0x10),
+            Some(PathResult::Truncated(Truncation::LoopBound {
+                header: 0x10,
+                bound: 2
+            }))
+        ));
+        // Other loops use the default bound.
+        for _ in 0..4 {
+            assert!(jump(0x24, 0x20).is_none());
+        }
+        assert!(jump(0x24, 0x20).is_some());
+
+        for _ in 0..8 {
+            executor.state.increment_instruction_count();
+        }
+        assert!(matches!(
+            executor.check_budget().unwrap(),
+            Some(PathResult::Truncated(Truncation::Instructions(8)))
+        ));
+    }
+
+    #[test]
+    fn test_max_paths() {
+        let mut vm = setup_test_vm(ExecutionBudget {
+            max_paths: Some(1),
+            ..ExecutionBudget::unlimited()
+        });
+        vm.explored_paths = 1;
+        let (result, _) = vm.run().unwrap().unwrap();
+        assert!(matches!(
+            result,
+            PathResult::Truncated(Truncation::Paths(1))
+        ));
+        assert_eq!(vm.truncated, 1);
+        assert!(vm.run().unwrap().is_none());
+    }
+}
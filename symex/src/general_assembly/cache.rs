@@ -0,0 +1,176 @@
+//! Model of the instruction and data caches of the processor.
+//!
+//! The cycle counts of the instructions assume that every access hits in the
+//! caches. When an [`instruction_cache`](super::RunConfig::instruction_cache)
+//! or a [`data_cache`](super::RunConfig::data_cache) is configured every
+//! instruction fetch or data access is looked up in a model of that cache and
+//! each miss adds the miss penalty to the cycles of the instruction. Accesses
+//! to the [`mmio_regions`](super::RunConfig::mmio_regions) bypass the data
+//! cache.
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// Size and organization of a set associative cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheGeometry {
+    /// Number of bytes in a cache line.
+    pub line_size: u64,
+
+    /// Number of sets.
+    pub sets: u64,
+
+    /// Number of lines in each set.
+    pub ways: usize,
+}
+
+impl CacheGeometry {
+    /// Creates the geometry of a cache of `sets` sets of `ways` lines of
+    /// `line_size` bytes.
+    pub fn new(line_size: u64, sets: u64, ways: usize) -> Self {
+        assert!(
+            line_size > 0 && sets > 0 && ways > 0,
+            "a cache has at least one line"
+        );
+        Self {
+            line_size,
+            sets,
+            ways,
+        }
+    }
+}
+
+/// How the accesses are looked up in the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Simulate a cache with least recently used replacement.
+    Simulated(CacheGeometry),
+
+    /// Every access misses, a safe upper bound when the contents of the cache
+    /// are not known.
+    AlwaysMiss,
+}
+
+/// A cache of the processor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// How the accesses are looked up.
+    pub mode: CacheMode,
+
+    /// Number of cycles added to an instruction for each miss.
+    pub miss_penalty: usize,
+}
+
+impl CacheConfig {
+    /// Creates a simulated cache with `geometry`.
+    pub fn new(geometry: CacheGeometry, miss_penalty: usize) -> Self {
+        Self {
+            mode: CacheMode::Simulated(geometry),
+            miss_penalty,
+        }
+    }
+
+    /// Creates a cache where every access misses.
+    pub fn always_miss(miss_penalty: usize) -> Self {
+        Self {
+            mode: CacheMode::AlwaysMiss,
+            miss_penalty,
+        }
+    }
+}
+
+/// Number of hits and misses in a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Display for CacheStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} hits, {} misses", self.hits, self.misses)
+    }
+}
+
+/// Contents of a cache on a path.
+#[derive(Debug, Clone, Default)]
+pub struct Cache {
+    /// Tags of the lines in each set, most recently used last.
+    sets: Vec<Vec<u64>>,
+
+    stats: CacheStats,
+}
+
+impl Cache {
+    /// Get the number of hits and misses on the path.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Looks up the `bytes` bytes at `address` in the cache, loads the lines
+    /// that miss and returns the number of misses.
+    pub(crate) fn access(&mut self, config: &CacheConfig, address: u64, bytes: u64) -> usize {
+        let geometry = match config.mode {
+            CacheMode::Simulated(geometry) => geometry,
+            CacheMode::AlwaysMiss => {
+                self.stats.misses += 1;
+                return 1;
+            }
+        };
+        self.sets.resize(geometry.sets as usize, vec![]);
+
+        let first = address / geometry.line_size;
+        let last = (address + bytes.max(1) - 1) / geometry.line_size;
+        let mut misses = 0;
+        for line in first..=last {
+            let tag = line / geometry.sets;
+            let set = &mut self.sets[(line % geometry.sets) as usize];
+            match set.iter().position(|cached| *cached == tag) {
+                Some(index) => {
+                    set.remove(index);
+                    self.stats.hits += 1;
+                }
+                None => {
+                    if set.len() == geometry.ways {
+                        set.remove(0);
+                    }
+                    misses += 1;
+                }
+            }
+            set.push(tag);
+        }
+        self.stats.misses += misses;
+        misses
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cache, CacheConfig, CacheGeometry, CacheStats};
+
+    #[test]
+    fn test_cache() {
+        // Two sets of two lines of 32 bytes.
+        let config = CacheConfig::new(CacheGeometry::new(32, 2, 2), 10);
+        let mut cache = Cache::default();
+
+        assert_eq!(cache.access(&config, 0x100, 4), 1);
+        assert_eq!(cache.access(&config, 0x104, 4), 0);
+        // Spans two lines, the second one misses.
+        assert_eq!(cache.access(&config, 0x11E, 4), 1);
+        // Fills the first set and evicts the least recently used line.
+        assert_eq!(cache.access(&config, 0x140, 4), 1);
+        assert_eq!(cache.access(&config, 0x100, 4), 0);
+        assert_eq!(cache.access(&config, 0x180, 4), 1);
+        assert_eq!(cache.access(&config, 0x140, 4), 1);
+        assert_eq!(cache.access(&config, 0x100, 4), 1);
+        assert_eq!(cache.stats(), CacheStats { hits: 3, misses: 6 });
+
+        let mut cache = Cache::default();
+        let config = CacheConfig::always_miss(10);
+        assert_eq!(cache.access(&config, 0x100, 4), 1);
+        assert_eq!(cache.access(&config, 0x100, 4), 1);
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 2 });
+    }
+}
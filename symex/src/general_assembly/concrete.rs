@@ -0,0 +1,1080 @@
+//! Concrete execution of general assembly without the SMT solver.
+//!
+//! [`ConcreteState`] executes the same [`Instruction`]s as the symbolic
+//! executor, decoded by the same architectures, but uses plain integer
+//! arithmetic and a byte addressed memory. This makes it a fast baseline
+//! emulator for replaying inputs and for differential testing of the
+//! symbolic executor.
+//!
+//! Registers and memory that have not been written read as zero instead of
+//! being unconstrained. Hooks operate on a [`GAState`](super::state::GAState)
+//! and can therefore not be run, except for [`PCHook::EndSuccess`],
+//! [`PCHook::EndFailure`], [`PCHook::Suppress`], [`PCHook::Assume`],
+//! [`PCHook::Assert`] and [`PCHook::Filter`]. A false assumption suppresses the
+//! path and skipped functions return zero. Cycles are only counted for
+//! instructions with a constant cycle count.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use general_assembly::{
+    condition::Condition,
+    operand::{DataWord, Operand, RawDataWord},
+    operation::Operation,
+    shift::Shift,
+};
+use tracing::{debug, trace};
+
+use super::{
+    arch::Arch,
+    instruction::{CycleCount, Instruction},
+    mmio::AccessKind,
+    project::{FilterAction, PCHook, Project, ProjectError},
+    Endianness,
+    GAError,
+    Result,
+};
+
+/// The result of a concrete execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcreteResult {
+    /// Execution reached a successful end.
+    Success,
+
+    /// Execution reached a failure.
+    Failure(&'static str),
+
+    /// Execution reached a suppressed path.
+    Suppress,
+}
+
+/// A concrete value of a specific width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Value {
+    value: u64,
+    bits: u32,
+}
+
+fn mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn sign_extend(value: u64, bits: u32) -> i64 {
+    if bits >= 64 {
+        return value as i64;
+    }
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+/// Checks if the `CONTROL` value `control` selects the process stack.
+fn uses_process_stack(control: u64) -> bool {
+    control & 0b10 != 0
+}
+
+impl Value {
+    fn new(value: u64, bits: u32) -> Self {
+        Self {
+            value: value & mask(bits),
+            bits,
+        }
+    }
+
+    fn from_data_word(data: DataWord) -> Self {
+        match data {
+            DataWord::Word64(v) => Self::new(v, 64),
+            DataWord::Word32(v) => Self::new(v as u64, 32),
+            DataWord::Word16(v) => Self::new(v as u64, 16),
+            DataWord::Word8(v) => Self::new(v as u64, 8),
+        }
+    }
+
+    fn signed(&self) -> i64 {
+        sign_extend(self.value, self.bits)
+    }
+
+    fn bit(&self, n: u32) -> bool {
+        n < self.bits && (self.value >> n) & 1 == 1
+    }
+
+    fn with(&self, value: u64) -> Self {
+        Self::new(value, self.bits)
+    }
+
+    fn sll(&self, shift: u64) -> Self {
+        match u32::try_from(shift) {
+            Ok(shift) if shift < self.bits => self.with(self.value << shift),
+            _ => self.with(0),
+        }
+    }
+
+    fn srl(&self, shift: u64) -> Self {
+        match u32::try_from(shift) {
+            Ok(shift) if shift < self.bits => self.with(self.value >> shift),
+            _ => self.with(0),
+        }
+    }
+
+    fn sra(&self, shift: u64) -> Self {
+        let shift = shift.min(63) as u32;
+        self.with((self.signed() >> shift) as u64)
+    }
+
+    fn ror(&self, shift: u64) -> Self {
+        let shift = (shift % self.bits as u64) as u32;
+        if shift == 0 {
+            return *self;
+        }
+        self.with((self.value >> shift) | (self.value << (self.bits - shift)))
+    }
+}
+
+/// The result of adding two values and a carry.
+struct AddWithCarryResult {
+    carry_out: bool,
+    overflow: bool,
+    result: u64,
+}
+
+fn add_with_carry(op1: Value, op2: Value, carry_in: bool, bits: u32) -> AddWithCarryResult {
+    let unsigned = op1.value as u128 + op2.value as u128 + carry_in as u128;
+    let signed = sign_extend(op1.value, bits) as i128
+        + sign_extend(op2.value, bits) as i128
+        + carry_in as i128;
+    let result = unsigned as u64 & mask(bits);
+    AddWithCarryResult {
+        carry_out: unsigned >> bits != 0,
+        overflow: sign_extend(result, bits) as i128 != signed,
+        result,
+    }
+}
+
+/// A memory access of a concrete execution, recorded after
+/// [`ConcreteState::record_accesses`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcreteAccess {
+    /// Direction of the access.
+    pub kind: AccessKind,
+
+    /// Address of the first accessed byte.
+    pub address: u64,
+
+    /// Number of accessed bits.
+    pub bits: u32,
+}
+
+/// Concrete execution state.
+#[derive(Debug, Clone)]
+pub struct ConcreteState<A: Arch> {
+    pub project: Arc<Project<A>>,
+    pub architecture: A,
+    /// Number of executed instructions.
+    pub instruction_count: usize,
+    /// Number of cycles of the executed instructions.
+    pub cycle_count: usize,
+    /// Address of the last executed instruction.
+    pub last_pc: u64,
+    registers: HashMap<String, u64>,
+    flags: HashMap<String, bool>,
+    memory: HashMap<u64, u8>,
+    instruction_conditions: VecDeque<Condition>,
+    accesses: Option<Vec<ConcreteAccess>>,
+}
+
+impl<A: Arch> ConcreteState<A> {
+    /// Create a new state that starts execution at `function` and ends when
+    /// it returns to `end_address`.
+    pub fn new(
+        project: Arc<Project<A>>,
+        function: &str,
+        end_address: u64,
+        architecture: A,
+    ) -> Result<Self> {
+        let pc_reg = project.get_entry_address(function)?;
+        debug!("Found function at addr: {:#X}.", pc_reg);
+
+        let sp_reg = match project.get_symbol_address("_stack_start") {
+            Some(a) => Ok(a),
+            None => Err(ProjectError::UnableToParseElf(
+                "start of stack not found".to_owned(),
+            )),
+        }?;
+        debug!("Found stack start at addr: {:#X}.", sp_reg);
+
+        let mut state = Self::create_test_state(project, pc_reg, sp_reg, architecture);
+        // set the link register to detect when returning from the function
+        state.set_register("LR", end_address);
+        Ok(state)
+    }
+
+    /// Create a state used for testing.
+    pub fn create_test_state(
+        project: Arc<Project<A>>,
+        start_pc: u64,
+        start_stack: u64,
+        architecture: A,
+    ) -> Self {
+        let mut registers = HashMap::new();
+        registers.insert("PC".to_owned(), start_pc);
+        registers.insert("SP".to_owned(), start_stack);
+
+        let flags = ["N", "Z", "C", "V"]
+            .into_iter()
+            .map(|flag| (flag.to_owned(), false))
+            .collect();
+
+        ConcreteState {
+            project,
+            architecture,
+            instruction_count: 0,
+            cycle_count: 0,
+            last_pc: start_pc,
+            registers,
+            flags,
+            memory: HashMap::new(),
+            instruction_conditions: VecDeque::new(),
+            accesses: None,
+        }
+    }
+
+    /// Create a state that continues from the concrete `registers` and
+    /// `flags` of a symbolic path, used to run a part of the path concretely.
+    /// `SP` holds the stack pointer in use, as in the symbolic state.
+    pub(crate) fn from_values(
+        project: Arc<Project<A>>,
+        architecture: A,
+        registers: HashMap<String, u64>,
+        flags: HashMap<String, bool>,
+    ) -> Self {
+        let pc = registers.get("PC").copied().unwrap_or(0);
+        ConcreteState {
+            project,
+            architecture,
+            instruction_count: 0,
+            cycle_count: 0,
+            last_pc: pc,
+            registers,
+            flags,
+            memory: HashMap::new(),
+            instruction_conditions: VecDeque::new(),
+            accesses: None,
+        }
+    }
+
+    /// Starts recording the memory accesses of the executed instructions.
+    pub(crate) fn record_accesses(&mut self) {
+        self.accesses = Some(vec![]);
+    }
+
+    /// Takes the memory accesses recorded since the last call.
+    pub(crate) fn take_accesses(&mut self) -> Vec<ConcreteAccess> {
+        self.accesses
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Records an access if accesses are recorded.
+    fn log_access(&mut self, kind: AccessKind, address: u64, bits: u32) {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.push(ConcreteAccess {
+                kind,
+                address,
+                bits,
+            });
+        }
+    }
+
+    /// Get the value stored in a register, registers that have not been
+    /// written read as zero.
+    pub fn get_register(&self, register: &str) -> u64 {
+        let register = self.stack_alias(register);
+        self.registers.get(register).copied().unwrap_or(0)
+    }
+
+    /// Set the value of a register.
+    pub fn set_register(&mut self, register: &str, value: u64) {
+        let value = value & mask(self.project.get_word_size());
+        trace!("Setting register {} to {:#X}", register, value);
+        let register = self.stack_alias(register);
+        if register == "CONTROL" && uses_process_stack(value) != self.uses_process_stack() {
+            let (from, to) = match uses_process_stack(value) {
+                true => ("MSP", "PSP"),
+                false => ("PSP", "MSP"),
+            };
+            let banked = self.registers.remove(to).unwrap_or(0);
+            let sp = self.get_register("SP");
+            self.registers.insert(from.to_owned(), sp);
+            self.registers.insert("SP".to_owned(), banked);
+        }
+        self.registers.insert(register.to_owned(), value);
+    }
+
+    /// Checks if `SP` aliases the process stack pointer, the interpreter only
+    /// runs in thread mode.
+    fn uses_process_stack(&self) -> bool {
+        uses_process_stack(self.registers.get("CONTROL").copied().unwrap_or(0))
+    }
+
+    /// Resolves the banked stack pointer in use to `SP`, which holds its value.
+    fn stack_alias<'a>(&self, register: &'a str) -> &'a str {
+        match (register, self.uses_process_stack()) {
+            ("PSP", true) | ("MSP", false) => "SP",
+            _ => register,
+        }
+    }
+
+    /// Get the value of a flag.
+    pub fn get_flag(&self, flag: &str) -> bool {
+        self.flags.get(flag).copied().unwrap_or(false)
+    }
+
+    /// Set the value of a flag.
+    pub fn set_flag(&mut self, flag: &str, value: bool) {
+        trace!("flag {} set to {}", flag, value);
+        self.flags.insert(flag.to_owned(), value);
+    }
+
+    /// Read `bits` bits from memory at `address`.
+    pub fn read_memory(&self, address: u64, bits: u32) -> Result<u64> {
+        let mut bytes = Vec::with_capacity(bits as usize / 8);
+        for offset in 0..(bits as u64).div_ceil(8) {
+            let address = address.wrapping_add(offset);
+            let byte = if self.project.address_in_range(address) {
+                self.project.get_byte(address)?
+            } else {
+                self.memory.get(&address).copied().unwrap_or(0)
+            };
+            bytes.push(byte);
+        }
+        if let Endianness::Little = self.project.get_endianness() {
+            bytes.reverse();
+        }
+        let value = bytes
+            .into_iter()
+            .fold(0u64, |acc, byte| (acc << 8) | byte as u64);
+        Ok(value & mask(bits))
+    }
+
+    /// Write the `bits` least significant bits of `value` to memory at
+    /// `address`.
+    pub fn write_memory(&mut self, address: u64, value: u64, bits: u32) -> Result<()> {
+        let len = (bits as u64).div_ceil(8);
+        if (0..len).any(|offset| self.project.address_in_range(address.wrapping_add(offset))) {
+            return Err(GAError::WritingToStaticMemoryProhibited);
+        }
+        for offset in 0..len {
+            let byte = match self.project.get_endianness() {
+                Endianness::Little => value >> (8 * offset),
+                Endianness::Big => value >> (8 * (len - 1 - offset)),
+            };
+            self.memory.insert(address.wrapping_add(offset), byte as u8);
+        }
+        Ok(())
+    }
+
+    /// Checks if a condition holds for the current flag values.
+    fn condition_holds(&self, condition: &Condition) -> bool {
+        let n = self.get_flag("N");
+        let z = self.get_flag("Z");
+        let c = self.get_flag("C");
+        let v = self.get_flag("V");
+        match condition {
+            Condition::EQ => z,
+            Condition::NE => !z,
+            Condition::CS => c,
+            Condition::CC => !c,
+            Condition::MI => n,
+            Condition::PL => !n,
+            Condition::VS => v,
+            Condition::VC => !v,
+            Condition::HI => c && !z,
+            Condition::LS => !c || z,
+            Condition::GE => n == v,
+            Condition::LT => n != v,
+            Condition::GT => !z && n == v,
+            Condition::LE => z || n != v,
+            Condition::None => true,
+        }
+    }
+
+    /// Run until the execution ends.
+    pub fn run(&mut self) -> Result<ConcreteResult> {
+        loop {
+            if let Some(result) = self.step()? {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Execute the next instruction, returns a result if the execution ended.
+    pub fn step(&mut self) -> Result<Option<ConcreteResult>> {
+        let pc = self.get_register("PC") & !0b1;
+        if let Some(hook) = self.project.get_pc_hook(pc) {
+            return match hook {
+                PCHook::Continue => {
+                    debug!("Continuing");
+                    let lr = self.get_register("LR");
+                    self.set_register("PC", lr);
+                    Ok(None)
+                }
+                PCHook::EndSuccess => Ok(Some(ConcreteResult::Success)),
+                PCHook::EndFailure(reason) => Ok(Some(ConcreteResult::Failure(reason))),
+                PCHook::Suppress => Ok(Some(ConcreteResult::Suppress)),
+                PCHook::Assume | PCHook::Assert(_) if self.get_register("R0") & 0xFF != 0 => {
+                    let lr = self.get_register("LR");
+                    self.set_register("PC", lr);
+                    Ok(None)
+                }
+                PCHook::Assume => Ok(Some(ConcreteResult::Suppress)),
+                PCHook::Assert(reason) => Ok(Some(ConcreteResult::Failure(reason))),
+                PCHook::Filter(FilterAction::Skip) => {
+                    self.set_register("R0", 0);
+                    let lr = self.get_register("LR");
+                    self.set_register("PC", lr);
+                    Ok(None)
+                }
+                PCHook::Filter(FilterAction::Succeed) => Ok(Some(ConcreteResult::Success)),
+                PCHook::Intrinsic(_) => {
+                    Err(GAError::UnsupportedInConcreteExecution("intrinsic pc hook"))
+                }
+            };
+        }
+
+        let in_conditional_block = !self.instruction_conditions.is_empty();
+        let instruction = match self.project.get_raw_word(pc)? {
+            RawDataWord::Word64(d) => self.architecture.translate(&d, in_conditional_block),
+            RawDataWord::Word32(d) => self.architecture.translate(&d, in_conditional_block),
+            RawDataWord::Word16(d) => self.architecture.translate(&d, in_conditional_block),
+            RawDataWord::Word8(d) => self.architecture.translate(&d, in_conditional_block),
+        }?;
+        self.execute_instruction(&instruction)?;
+        Ok(None)
+    }
+
+    /// Execute a single instruction.
+    pub fn execute_instruction(&mut self, i: &Instruction<A>) -> Result<()> {
+        let pc = self.get_register("PC");
+        self.last_pc = pc;
+
+        // Always increment pc before executing the operations
+        self.set_register("PC", pc + (i.instruction_size / 8) as u64);
+        self.instruction_count += 1;
+        if let CycleCount::Value(cycles) = i.max_cycle {
+            self.cycle_count += cycles;
+        }
+
+        let should_run = match self.instruction_conditions.pop_front() {
+            Some(condition) => self.condition_holds(&condition),
+            None => true,
+        };
+
+        if should_run {
+            let mut local = HashMap::new();
+            for operation in &i.operations {
+                self.execute_operation(operation, &mut local)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the value of an operand.
+    fn get_operand_value(
+        &mut self,
+        operand: &Operand,
+        local: &HashMap<String, Value>,
+    ) -> Result<Value> {
+        let word_size = self.project.get_word_size();
+        Ok(match operand {
+            Operand::Register(name) => Value::new(self.get_register(name), word_size),
+            Operand::Immediate(v) => Value::from_data_word(*v),
+            Operand::Address(address, width) => {
+                let address: u64 = (*address).into();
+                self.log_access(AccessKind::Read, address, *width);
+                Value::new(self.read_memory(address, *width)?, *width)
+            }
+            Operand::AddressInLocal(local_name, width) => {
+                let address = self
+                    .get_operand_value(&Operand::Local(local_name.to_owned()), local)?
+                    .value;
+                self.log_access(AccessKind::Read, address, *width);
+                Value::new(self.read_memory(address, *width)?, *width)
+            }
+            Operand::AddressWithOffset { .. } => {
+                return Err(GAError::UnsupportedInConcreteExecution(
+                    "address with offset operand",
+                ))
+            }
+            Operand::Local(k) => match local.get(k) {
+                Some(value) => *value,
+                None => {
+                    return Err(GAError::UnsupportedInConcreteExecution(
+                        "read of undefined local",
+                    ))
+                }
+            },
+            Operand::Flag(f) => Value::new(self.get_flag(f) as u64, word_size),
+        })
+    }
+
+    /// Set what the operand represents to `value`.
+    fn set_operand_value(
+        &mut self,
+        operand: &Operand,
+        value: Value,
+        local: &mut HashMap<String, Value>,
+    ) -> Result<()> {
+        match operand {
+            Operand::Register(name) => self.set_register(name, value.value),
+            Operand::Immediate(_) => {
+                return Err(GAError::UnsupportedInConcreteExecution(
+                    "write to immediate",
+                ))
+            }
+            Operand::Address(address, width) => {
+                self.log_access(AccessKind::Write, (*address).into(), *width);
+                self.write_memory((*address).into(), value.value, *width)?
+            }
+            Operand::AddressInLocal(local_name, width) => {
+                let address = self
+                    .get_operand_value(&Operand::Local(local_name.to_owned()), local)?
+                    .value;
+                self.log_access(AccessKind::Write, address, *width);
+                self.write_memory(address, value.value, *width)?;
+            }
+            Operand::AddressWithOffset { .. } => {
+                return Err(GAError::UnsupportedInConcreteExecution(
+                    "address with offset operand",
+                ))
+            }
+            Operand::Local(k) => {
+                local.insert(k.to_owned(), value);
+            }
+            Operand::Flag(f) => self.set_flag(f, value.value & 1 == 1),
+        }
+        Ok(())
+    }
+
+    /// Execute a single operation.
+    fn execute_operation(
+        &mut self,
+        operation: &Operation,
+        local: &mut HashMap<String, Value>,
+    ) -> Result<()> {
+        trace!("Executing operation: {}", operation);
+        let word_size = self.project.get_word_size();
+        match operation {
+            Operation::Nop => (),
+            Operation::Move {
+                destination,
+                source,
+            } => {
+                let value = self.get_operand_value(source, local)?;
+                self.set_operand_value(destination, value, local)?;
+            }
+            Operation::Add {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Sub {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Mul {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::UDiv {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::SDiv {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::And {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Or {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Xor {
+                destination,
+                operand1,
+                operand2,
+            }
+            | Operation::Adc {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let result = match operation {
+                    Operation::Add { .. } => op1.value.wrapping_add(op2.value),
+                    Operation::Sub { .. } => op1.value.wrapping_sub(op2.value),
+                    Operation::Mul { .. } => op1.value.wrapping_mul(op2.value),
+                    // Division by zero follows the SMT-LIB semantics used by the symbolic
+                    // executor.
+                    Operation::UDiv { .. } => match op2.value {
+                        0 => u64::MAX,
+                        divisor => op1.value / divisor,
+                    },
+                    Operation::SDiv { .. } => match op2.signed() {
+                        0 if op1.signed() < 0 => 1,
+                        0 => u64::MAX,
+                        divisor => op1.signed().wrapping_div(divisor) as u64,
+                    },
+                    Operation::And { .. } => op1.value & op2.value,
+                    Operation::Or { .. } => op1.value | op2.value,
+                    Operation::Xor { .. } => op1.value ^ op2.value,
+                    Operation::Adc { .. } => {
+                        add_with_carry(op1, op2, self.get_flag("C"), op1.bits).result
+                    }
+                    _ => unreachable!(),
+                };
+                self.set_operand_value(destination, op1.with(result), local)?;
+            }
+            Operation::Not {
+                destination,
+                operand,
+            } => {
+                let op = self.get_operand_value(operand, local)?;
+                self.set_operand_value(destination, op.with(!op.value), local)?;
+            }
+            Operation::Shift {
+                destination,
+                operand,
+                shift_n,
+                shift_t,
+            } => {
+                let value = self.get_operand_value(operand, local)?;
+                let shift_amount = self.get_operand_value(shift_n, local)?.value;
+                let result = match shift_t {
+                    Shift::Lsl => value.sll(shift_amount),
+                    Shift::Lsr => value.srl(shift_amount),
+                    Shift::Asr => value.sra(shift_amount),
+                    Shift::Rrx => {
+                        let carry = (self.get_flag("C") as u64) << (value.bits - 1);
+                        value.with(value.srl(1).value | carry)
+                    }
+                    Shift::Ror => value.ror(shift_amount),
+                };
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::Sl {
+                destination,
+                operand,
+                shift,
+            }
+            | Operation::Srl {
+                destination,
+                operand,
+                shift,
+            }
+            | Operation::Sra {
+                destination,
+                operand,
+                shift,
+            }
+            | Operation::Sror {
+                destination,
+                operand,
+                shift,
+            } => {
+                let value = self.get_operand_value(operand, local)?;
+                let shift_amount = self.get_operand_value(shift, local)?.value;
+                let result = match operation {
+                    Operation::Sl { .. } => value.sll(shift_amount),
+                    Operation::Srl { .. } => value.srl(shift_amount),
+                    Operation::Sra { .. } => value.sra(shift_amount),
+                    Operation::Sror { .. } => value.ror(shift_amount),
+                    _ => unreachable!(),
+                };
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::ConditionalJump {
+                destination,
+                condition,
+            } => {
+                let destination = self.get_operand_value(destination, local)?;
+                if self.condition_holds(condition) {
+                    self.set_register("PC", destination.value);
+                }
+            }
+            Operation::ConditionalExecution { conditions } => {
+                self.instruction_conditions
+                    .extend(conditions.iter().copied());
+            }
+            Operation::SetNFlag(operand) => {
+                let value = self.get_operand_value(operand, local)?;
+                self.set_flag("N", value.value >> (word_size - 1) & 1 == 1);
+            }
+            Operation::SetZFlag(operand) => {
+                let value = self.get_operand_value(operand, local)?;
+                self.set_flag("Z", value.value & mask(word_size) == 0);
+            }
+            Operation::SetCFlag {
+                operand1,
+                operand2,
+                sub,
+                carry,
+            }
+            | Operation::SetVFlag {
+                operand1,
+                operand2,
+                sub,
+                carry,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let result = match (sub, carry) {
+                    (true, true) => {
+                        let negated = op2.with(!op2.value);
+                        let c2 = negated.value == mask(word_size);
+                        let result = add_with_carry(
+                            op1,
+                            negated.with(negated.value.wrapping_add(1)),
+                            self.get_flag("C"),
+                            word_size,
+                        );
+                        AddWithCarryResult {
+                            carry_out: result.carry_out || c2,
+                            ..result
+                        }
+                    }
+                    (true, false) => add_with_carry(op1, op2.with(!op2.value), true, word_size),
+                    (false, true) => add_with_carry(op1, op2, self.get_flag("C"), word_size),
+                    (false, false) => add_with_carry(op1, op2, false, word_size),
+                };
+                match operation {
+                    Operation::SetCFlag { .. } => self.set_flag("C", result.carry_out),
+                    _ => self.set_flag("V", result.overflow),
+                }
+            }
+            Operation::ForEach { .. } => {
+                return Err(GAError::UnsupportedInConcreteExecution(
+                    "for each operation",
+                ))
+            }
+            Operation::ZeroExtend {
+                destination,
+                operand,
+                bits,
+                target_bits,
+            } => {
+                let op = self.get_operand_value(operand, local)?;
+                let result = Value::new(op.value & mask(*bits), *target_bits);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::SignExtend {
+                destination,
+                operand,
+                bits,
+            } => {
+                let op = self.get_operand_value(operand, local)?;
+                let result = Value::new(sign_extend(op.value, *bits) as u64, word_size);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::Resize {
+                destination,
+                operand,
+                bits,
+            } => {
+                let op = self.get_operand_value(operand, local)?;
+                self.set_operand_value(destination, Value::new(op.value, *bits), local)?;
+            }
+            Operation::SetCFlagShiftLeft { operand, shift } => {
+                let op = self.get_operand_value(operand, local)?.value;
+                let shift = self.get_operand_value(shift, local)?.value;
+                let carry = match u32::try_from(shift) {
+                    Ok(shift) if shift <= word_size => (op as u128) << shift >> word_size & 1 == 1,
+                    _ => false,
+                };
+                self.set_flag("C", carry);
+            }
+            Operation::SetCFlagSrl { operand, shift } => {
+                let op = self.get_operand_value(operand, local)?;
+                let shift = self.get_operand_value(shift, local)?.value;
+                let carry = shift != 0 && shift <= word_size as u64 && op.bit(shift as u32 - 1);
+                self.set_flag("C", carry);
+            }
+            Operation::SetCFlagSra { operand, shift } => {
+                let op = self.get_operand_value(operand, local)?;
+                let shift = self.get_operand_value(shift, local)?.value;
+                let carry = match shift {
+                    0 => false,
+                    shift if shift <= word_size as u64 => op.bit(shift as u32 - 1),
+                    _ => op.bit(word_size - 1),
+                };
+                self.set_flag("C", carry);
+            }
+            Operation::SetCFlagRor(operand) => {
+                // this is right for armv6-m but may be wrong for other architectures
+                let result = self.get_operand_value(operand, local)?;
+                self.set_flag("C", result.bit(word_size - 1));
+            }
+            Operation::CountOnes {
+                destination,
+                operand,
+            }
+            | Operation::CountZeroes {
+                destination,
+                operand,
+            }
+            | Operation::CountLeadingOnes {
+                destination,
+                operand,
+            }
+            | Operation::CountLeadingZeroes {
+                destination,
+                operand,
+            } => {
+                let op = self.get_operand_value(operand, local)?.value & mask(word_size);
+                // Left align the word so that leading bits can be counted.
+                let aligned = op << (64 - word_size);
+                let result = match operation {
+                    Operation::CountOnes { .. } => op.count_ones(),
+                    Operation::CountZeroes { .. } => word_size - op.count_ones(),
+                    Operation::CountLeadingOnes { .. } => aligned.leading_ones().min(word_size),
+                    Operation::CountLeadingZeroes { .. } => aligned.leading_zeros().min(word_size),
+                    _ => unreachable!(),
+                };
+                self.set_operand_value(destination, Value::new(result as u64, word_size), local)?;
+            }
+            Operation::BitFieldExtract {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => {
+                assert!(
+                    start_bit <= stop_bit,
+                    "Tried to extract from {start_bit} until {stop_bit}"
+                );
+                let op = self.get_operand_value(operand, local)?;
+                let result = (op.value >> start_bit) & mask(stop_bit - start_bit + 1);
+                self.set_operand_value(destination, Value::new(result, word_size), local)?;
+            }
+            Operation::SignedBitFieldExtract {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => {
+                assert!(
+                    start_bit <= stop_bit,
+                    "Tried to extract from {start_bit} until {stop_bit}"
+                );
+                let op = self.get_operand_value(operand, local)?;
+                let field = op.value >> start_bit;
+                let result = sign_extend(field, stop_bit - start_bit + 1) as u64;
+                self.set_operand_value(destination, Value::new(result, word_size), local)?;
+            }
+            Operation::BitFieldInsert {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => {
+                assert!(
+                    start_bit <= stop_bit,
+                    "Tried to insert from {start_bit} until {stop_bit}"
+                );
+                let op = self.get_operand_value(operand, local)?;
+                let current = self.get_operand_value(destination, local)?;
+                let field = mask(stop_bit - start_bit + 1) << start_bit;
+                let result = (current.value & !field) | ((op.value << start_bit) & field);
+                self.set_operand_value(destination, current.with(result), local)?;
+            }
+            Operation::ReverseBits {
+                destination,
+                operand,
+            } => {
+                let op = self.get_operand_value(operand, local)?;
+                let result = op.value.reverse_bits() >> (64 - word_size);
+                self.set_operand_value(destination, Value::new(result, word_size), local)?;
+            }
+            Operation::RotateRightExtend {
+                destination,
+                operand,
+            } => {
+                let op = self.get_operand_value(operand, local)?;
+                let carry = self.get_flag("C") as u64;
+                let result = (carry << (word_size - 1)) | ((op.value & mask(word_size)) >> 1);
+                self.set_operand_value(destination, Value::new(result, word_size), local)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use general_assembly::{
+        condition::Condition,
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    use super::ConcreteState;
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        instruction::{CycleCount, Instruction},
+        project::Project,
+    };
+
+    fn setup_test_state() -> ConcreteState<ArmV6M> {
+        let project = Arc::new(Project::test_project());
+        ConcreteState::create_test_state(project, 0x100, 0x2000_1000, ArmV6M {})
+    }
+
+    fn instruction(operations: Vec<Operation>) -> Instruction<ArmV6M> {
+        Instruction {
+            instruction_size: 16,
+            operations,
+            max_cycle: CycleCount::Value(1),
+            memory_access: false,
+        }
+    }
+
+    #[test]
+    fn test_add_sets_flags() {
+        let mut state = setup_test_state();
+        state.set_register("R1", 0xffff_ffff);
+        let r0 = Operand::Register("R0".to_owned());
+        let r1 = Operand::Register("R1".to_owned());
+        let one = Operand::Immediate(DataWord::Word32(1));
+        state
+            .execute_instruction(&instruction(vec![
+                Operation::Add {
+                    destination: r0.clone(),
+                    operand1: r1.clone(),
+                    operand2: one.clone(),
+                },
+                Operation::SetZFlag(r0.clone()),
+                Operation::SetCFlag {
+                    operand1: r1.clone(),
+                    operand2: one.clone(),
+                    sub: false,
+                    carry: false,
+                },
+                Operation::SetVFlag {
+                    operand1: r1,
+                    operand2: one,
+                    sub: false,
+                    carry: false,
+                },
+            ]))
+            .unwrap();
+
+        assert_eq!(state.get_register("R0"), 0);
+        assert_eq!(state.get_register("PC"), 0x102);
+        assert!(state.get_flag("Z"));
+        assert!(state.get_flag("C"));
+        assert!(!state.get_flag("V"));
+        assert_eq!(state.instruction_count, 1);
+        assert_eq!(state.cycle_count, 1);
+    }
+
+    #[test]
+    fn test_memory_and_jump() {
+        let mut state = setup_test_state();
+        state.set_register("R1", 0x2000_0000);
+        state.set_register("R2", 0x1234_5678);
+        state
+            .execute_instruction(&instruction(vec![
+                Operation::Move {
+                    destination: Operand::Local("addr".to_owned()),
+                    source: Operand::Register("R1".to_owned()),
+                },
+                Operation::Move {
+                    destination: Operand::AddressInLocal("addr".to_owned(), 32),
+                    source: Operand::Register("R2".to_owned()),
+                },
+                Operation::Move {
+                    destination: Operand::Register("R3".to_owned()),
+                    source: Operand::Address(DataWord::Word32(0x2000_0001), 8),
+                },
+                Operation::ConditionalJump {
+                    destination: Operand::Immediate(DataWord::Word32(0x200)),
+                    condition: Condition::None,
+                },
+            ]))
+            .unwrap();
+
+        assert_eq!(state.read_memory(0x2000_0000, 32).unwrap(), 0x1234_5678);
+        assert_eq!(state.get_register("R3"), 0x56);
+        assert_eq!(state.get_register("PC"), 0x200);
+    }
+
+    #[test]
+    fn test_banked_stack_pointers() {
+        let mut state = setup_test_state();
+        state.set_register("PSP", 0x2000_0800);
+        state.set_register("CONTROL", 0b10);
+        assert_eq!(state.get_register("SP"), 0x2000_0800);
+        state.set_register("SP", 0x2000_07F0);
+        assert_eq!(state.get_register("PSP"), 0x2000_07F0);
+        assert_eq!(state.get_register("MSP"), 0x2000_1000);
+        state.set_register("CONTROL", 0);
+        assert_eq!(state.get_register("SP"), 0x2000_1000);
+        assert_eq!(state.get_register("PSP"), 0x2000_07F0);
+    }
+
+    #[test]
+    fn test_bit_field_operations() {
+        let mut state = setup_test_state();
+        state.set_register("R0", 0x0000_0F50);
+        state.set_register("R1", 0xFFFF_FFFF);
+        state.set_flag("C", true);
+        let r0 = Operand::Register("R0".to_owned());
+        let register = |name: &str| Operand::Register(name.to_owned());
+        state
+            .execute_instruction(&instruction(vec![
+                Operation::SignedBitFieldExtract {
+                    destination: register("R2"),
+                    operand: r0.clone(),
+                    start_bit: 4,
+                    stop_bit: 11,
+                },
+                Operation::BitFieldInsert {
+                    destination: register("R1"),
+                    operand: r0.clone(),
+                    start_bit: 8,
+                    stop_bit: 15,
+                },
+                Operation::ReverseBits {
+                    destination: register("R3"),
+                    operand: r0.clone(),
+                },
+                Operation::RotateRightExtend {
+                    destination: register("R4"),
+                    operand: r0,
+                },
+            ]))
+            .unwrap();
+
+        assert_eq!(state.get_register("R2"), 0xFFFF_FFF5);
+        assert_eq!(state.get_register("R1"), 0xFFFF_50FF);
+        assert_eq!(state.get_register("R3"), 0x0AF0_0000);
+        assert_eq!(state.get_register("R4"), 0x8000_07A8);
+    }
+}
@@ -0,0 +1,368 @@
+//! Exception entry and return of the M-profile processors.
+//!
+//! On exception entry the processor stacks `R0`-`R3`, `R12`, `LR`, the return
+//! address and `xPSR` on the stack the preempted code runs on, the process
+//! stack `PSP` if `CONTROL.SPSEL` is set in thread mode and the main stack
+//! `MSP` otherwise, and the handler runs on the main stack with an
+//! `EXC_RETURN` value in `LR`. Branching to the `EXC_RETURN` value unstacks the
+//! frame from the stack it selects.
+//!
+//! The frame is unstacked from memory, so a handler such as `PendSV` can switch
+//! contexts by saving the remaining registers below the frame of one task,
+//! loading those of another task and pointing `PSP` at the frame of the other
+//! task before it returns.
+//!
+//! `SP` is an alias of the banked stack pointer in use, `MSP` in handler mode
+//! and the one `CONTROL.SPSEL` selects in thread mode. The value of the stack
+//! in use is kept in `SP` and the value of the other one in its banked
+//! register, so that switching stacks moves the values between them. Writes to
+//! `CONTROL` in thread mode switch stacks like the processor does. A path that
+//! writes a symbolic `CONTROL` forks on `CONTROL.SPSEL` and the bit is kept
+//! concrete in the written value.
+
+use super::{
+    arch::Arch,
+    state::{GAState, SymbolOrigin},
+    GAError,
+    Result,
+};
+use crate::smt::DExpr;
+
+/// `EXC_RETURN` that returns to thread mode on the main stack.
+pub const EXC_RETURN_MSP: u64 = 0xFFFF_FFF9;
+
+/// `EXC_RETURN` that returns to thread mode on the process stack.
+pub const EXC_RETURN_PSP: u64 = 0xFFFF_FFFD;
+
+/// Size of the context the processor stacks on exception entry.
+pub const EXCEPTION_FRAME_SIZE: u64 = 0x20;
+
+/// Registers in the order they are stacked, followed by `xPSR`.
+const STACKED: [&str; 7] = ["R0", "R1", "R2", "R3", "R12", "LR", "PC"];
+
+/// Flags held by `xPSR` and their bits.
+const XPSR_FLAGS: [(&str, u64); 4] = [("N", 31), ("Z", 30), ("C", 29), ("V", 28)];
+
+/// Bit of the stacked `xPSR` that is set if the frame was aligned to eight
+/// bytes by an extra word of padding.
+const XPSR_REALIGNED: u64 = 9;
+
+/// Bit of the thumb state in `xPSR`.
+const XPSR_THUMB: u64 = 24;
+
+/// Bit of `CONTROL` that selects the process stack in thread mode.
+const CONTROL_SPSEL: u64 = 0b10;
+
+/// Index of [`CONTROL_SPSEL`] in `CONTROL`.
+const CONTROL_SPSEL_BIT: u32 = 1;
+
+/// Checks if `value` is an `EXC_RETURN` value.
+pub fn is_exception_return(value: u64) -> bool {
+    value & 0xFFFF_FFF0 == 0xFFFF_FFF0
+}
+
+/// Checks if the thread code of `state` runs on the process stack, a
+/// `CONTROL` register that has not been written or where `CONTROL.SPSEL` is
+/// not concrete selects the main stack.
+pub fn uses_process_stack<A: Arch>(state: &GAState<A>) -> bool {
+    state
+        .registers
+        .get("CONTROL")
+        .and_then(|control| {
+            control
+                .slice(CONTROL_SPSEL_BIT, CONTROL_SPSEL_BIT)
+                .simplify()
+                .get_constant()
+        })
+        .is_some_and(|spsel| spsel != 0)
+}
+
+/// Returns `control` with `CONTROL.SPSEL` set if `process` is true and clear
+/// otherwise, the other bits are kept as they are.
+fn with_stack_selection<A: Arch>(state: &GAState<A>, control: &DExpr, process: bool) -> DExpr {
+    let spsel = state.ctx.from_bool(process);
+    control.replace_part(CONTROL_SPSEL_BIT, spsel).simplify()
+}
+
+/// Get the name of the banked stack pointer that `SP` aliases in `state`.
+pub fn active_stack<A: Arch>(state: &GAState<A>) -> &'static str {
+    match !state.interrupts.in_handler() && uses_process_stack(state) {
+        true => "PSP",
+        false => "MSP",
+    }
+}
+
+/// Stores the value of `SP` in the banked register `from` and loads `SP` from
+/// the banked register `to`.
+pub(crate) fn switch_stack<A: Arch>(state: &mut GAState<A>, from: &str, to: &str) -> Result<()> {
+    if from == to {
+        return Ok(());
+    }
+    let sp = state.get_register("SP".to_owned())?;
+    let banked = match state.registers.remove(to) {
+        Some(banked) => banked,
+        // The main stack pointer is loaded from the vector table on reset.
+        None if to == "MSP" => state
+            .ctx
+            .from_u64(state.inital_sp, state.project.get_ptr_size()),
+        None => state.new_symbol(
+            state.project.get_word_size(),
+            to,
+            SymbolOrigin::RegisterInit,
+        ),
+    };
+    state.registers.insert(from.to_owned(), sp);
+    state.set_register("SP".to_owned(), banked)
+}
+
+/// Resolves the banked stack pointer in use to `SP`, which holds its value.
+pub(crate) fn stack_alias<A: Arch>(state: &GAState<A>, register: String) -> String {
+    match register.as_str() {
+        "MSP" | "PSP" if register == active_stack(state) => "SP".to_owned(),
+        _ => register,
+    }
+}
+
+/// Get the condition that `control` selects the process stack, paths fork on
+/// it before `CONTROL` is written.
+pub(crate) fn selects_process_stack<A: Arch>(state: &GAState<A>, control: &DExpr) -> DExpr {
+    let ptr_size = state.project.get_ptr_size();
+    control
+        .and(&state.ctx.from_u64(CONTROL_SPSEL, ptr_size))
+        .ne(&state.ctx.zero(ptr_size))
+        .simplify()
+}
+
+/// Switches stacks if writing `control` to `CONTROL` changes the stack that
+/// `SP` aliases, `CONTROL.SPSEL` is ignored in handler mode. Returns `control`
+/// with `CONTROL.SPSEL` set to the value it must have on the path.
+///
+/// Fails if `CONTROL.SPSEL` can be both set and clear.
+pub(crate) fn write_control<A: Arch>(state: &mut GAState<A>, control: DExpr) -> Result<DExpr> {
+    let selects = selects_process_stack(state, &control);
+    let process = match selects.get_constant() {
+        Some(selects) => selects != 0,
+        None if !state.constraints.is_sat_with_constraint(&selects.not())? => true,
+        None if !state.constraints.is_sat_with_constraint(&selects)? => false,
+        None => return Err(GAError::SymbolicStackSelection),
+    };
+    let control = with_stack_selection(state, &control, process);
+    if !state.interrupts.in_handler() {
+        let to = match process {
+            true => "PSP",
+            false => "MSP",
+        };
+        switch_stack(state, active_stack(state), to)?;
+    }
+    Ok(control)
+}
+
+/// Stacks the context of the thread code of `state` as on exception entry and
+/// switches to the main stack. Returns the `EXC_RETURN` value that returns to
+/// the stacked context.
+pub(crate) fn stack_context<A: Arch>(state: &mut GAState<A>) -> Result<u64> {
+    let ctx = state.ctx.clone();
+    let ptr_size = state.project.get_ptr_size();
+    let word = |value: u64| ctx.from_u64(value, ptr_size);
+    let process = uses_process_stack(state);
+
+    let sp = state.get_register("SP".to_owned())?;
+    let frame = sp
+        .sub(&word(EXCEPTION_FRAME_SIZE))
+        .and(&word(!0b111))
+        .simplify();
+    // The padding is zero or four bytes, which is the realign bit shifted
+    // down. The flags are concatenated to keep the other bits concrete.
+    let padding = sp.sub(&frame.add(&word(EXCEPTION_FRAME_SIZE)));
+    let mut flags = state.get_flag("N".to_owned())?;
+    for (flag, _) in &XPSR_FLAGS[1..] {
+        flags = flags.concat(&state.get_flag((*flag).to_owned())?);
+    }
+    let xpsr = flags.concat(
+        &padding
+            .sll(&word(XPSR_REALIGNED - 2))
+            .or(&word(1 << XPSR_THUMB))
+            .slice(0, 27),
+    );
+    let mut values = vec![];
+    for register in STACKED {
+        values.push(state.get_register(register.to_owned())?);
+    }
+    values.push(xpsr.simplify());
+    for (offset, value) in values.into_iter().enumerate() {
+        let address = frame.add(&word(offset as u64 * 4)).simplify();
+        state.write_word_to_memory(&address, value)?;
+    }
+
+    state.set_register("SP".to_owned(), frame)?;
+    match process {
+        true => {
+            switch_stack(state, "PSP", "MSP")?;
+            Ok(EXC_RETURN_PSP)
+        }
+        false => Ok(EXC_RETURN_MSP),
+    }
+}
+
+/// Unstacks the context that `exc_return` returns to as on exception return
+/// and switches to the stack of that context.
+pub(crate) fn unstack_context<A: Arch>(state: &mut GAState<A>, exc_return: u64) -> Result<()> {
+    let ctx = state.ctx.clone();
+    let ptr_size = state.project.get_ptr_size();
+    let word = |value: u64| ctx.from_u64(value, ptr_size);
+    let process = exc_return & 0b100 != 0;
+
+    let bank = match process {
+        true => "PSP",
+        false => "MSP",
+    };
+    switch_stack(state, "MSP", bank)?;
+    let frame = state.get_register("SP".to_owned())?;
+    let mut values = vec![];
+    for offset in 0..STACKED.len() as u64 + 1 {
+        let address = frame.add(&word(offset * 4)).simplify();
+        values.push(state.read_word_from_memory(&address)?);
+    }
+    let xpsr = values.pop().expect("xPSR is stacked");
+    for (register, value) in STACKED.iter().zip(values) {
+        state.set_register((*register).to_owned(), value)?;
+    }
+    for (flag, bit) in XPSR_FLAGS {
+        let bit = bit as u32;
+        state.set_flag(flag.to_owned(), xpsr.slice(bit, bit))?;
+    }
+
+    let bit = XPSR_REALIGNED as u32;
+    let padding = xpsr.slice(bit, bit).resize_unsigned(ptr_size).sll(&word(2));
+    let sp = frame
+        .add(&word(EXCEPTION_FRAME_SIZE))
+        .add(&padding)
+        .simplify();
+    // The stacks were switched above, so `CONTROL` is updated in place.
+    let previous = match state.registers.get("CONTROL") {
+        Some(control) => control.clone(),
+        None => word(0),
+    };
+    let control = with_stack_selection(state, &previous, process);
+    state.registers.insert("CONTROL".to_owned(), control);
+    state.set_register("SP".to_owned(), sp)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use general_assembly::{operand::Operand, operation::Operation};
+
+    use super::{EXC_RETURN_PSP, XPSR_THUMB};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        instruction::{CycleCount, Instruction},
+        interrupts::{enter_handler, Interrupt},
+        project::{PCHook, Project},
+        state::GAState,
+        vm::VM,
+        GAError,
+    };
+
+    #[test]
+    fn test_context_switch() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("pendsv".to_owned(), 0x200)]));
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("pendsv"));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let word = |value| context.from_u64(value, 32);
+        let set = |executor: &mut GAExecutor<'_, ArmV6M>, register: &str, value| {
+            executor
+                .state
+                .set_register(register.to_owned(), word(value))
+                .unwrap();
+        };
+        let get = |executor: &mut GAExecutor<'_, ArmV6M>, register: &str| {
+            executor
+                .state
+                .get_register(register.to_owned())
+                .unwrap()
+                .get_constant()
+        };
+
+        // A task switches to the process stack and is preempted.
+        set(&mut executor, "CONTROL", 0b10);
+        assert_eq!(get(&mut executor, "MSP"), Some(0x2000_1000));
+        set(&mut executor, "SP", 0x2000_0804);
+        assert_eq!(get(&mut executor, "PSP"), Some(0x2000_0804));
+        set(&mut executor, "R0", 1);
+        enter_handler(&mut executor.state, 0, true).unwrap();
+        assert_eq!(get(&mut executor, "LR"), Some(EXC_RETURN_PSP));
+        assert_eq!(get(&mut executor, "SP"), Some(0x2000_1000));
+        assert_eq!(get(&mut executor, "PSP"), Some(0x2000_07E0));
+
+        // The handler points the process stack at the frame of another task.
+        for (offset, value) in [(0, 2), (0x18, 0x300), (0x1c, 1 << XPSR_THUMB)] {
+            let address = word(0x2000_0F00 + offset);
+            executor
+                .state
+                .write_word_to_memory(&address, word(value))
+                .unwrap();
+        }
+        set(&mut executor, "PSP", 0x2000_0F00);
+        set(&mut executor, "PC", EXC_RETURN_PSP);
+        assert!(executor.return_from_interrupt().unwrap().is_none());
+        assert_eq!(get(&mut executor, "R0"), Some(2));
+        assert_eq!(get(&mut executor, "PC"), Some(0x300));
+        assert_eq!(get(&mut executor, "SP"), Some(0x2000_0F20));
+        assert_eq!(get(&mut executor, "PSP"), Some(0x2000_0F20));
+        assert_eq!(get(&mut executor, "MSP"), Some(0x2000_1000));
+
+        // The other task switches back to the main stack.
+        set(&mut executor, "CONTROL", 0);
+        assert_eq!(get(&mut executor, "SP"), Some(0x2000_1000));
+        assert_eq!(get(&mut executor, "PSP"), Some(0x2000_0F20));
+    }
+
+    #[test]
+    fn test_symbolic_control() {
+        let project = Project::<ArmV6M>::test_project();
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let control = context.unconstrained(32, "control");
+
+        // A write outside of an instruction can not fork on the stack in use.
+        let result = state.set_register("CONTROL".to_owned(), control.clone());
+        assert!(matches!(result, Err(GAError::SymbolicStackSelection)));
+
+        state.set_register("R0".to_owned(), control).unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let msr = Instruction {
+            instruction_size: 32,
+            operations: vec![Operation::Move {
+                destination: Operand::Register("CONTROL".to_owned()),
+                source: Operand::Register("R0".to_owned()),
+            }],
+            max_cycle: CycleCount::Value(4),
+            memory_access: false,
+        };
+        executor.execute_instruction(&msr).unwrap();
+
+        // The path continues on the process stack and the forked path writes
+        // CONTROL again with CONTROL.SPSEL clear.
+        let control = executor.state.get_register("CONTROL".to_owned()).unwrap();
+        assert_eq!(control.slice(1, 1).simplify().get_constant(), Some(1));
+        let msp = executor.state.get_register("MSP".to_owned()).unwrap();
+        assert_eq!(msp.get_constant(), Some(0x2000_1000));
+        assert!(executor.state.continue_in_instruction.is_none());
+        let forked = vm.paths.get_path().unwrap().state;
+        let resumed = forked.continue_in_instruction.unwrap();
+        assert_eq!(resumed.index, 0);
+    }
+}
@@ -0,0 +1,629 @@
+//! Acceleration of loops that copy memory.
+//!
+//! Loops that copy memory one element per iteration, such as `memcpy`,
+//! `memmove` or the initialization of `.data`, are detected dynamically,
+//! whether or not they are a call of a known function. When a path jumps
+//! backwards to a loop header the next iteration is observed. The loop is a
+//! copy loop if the iteration loads a single element and stores it, and the
+//! loaded element is only read to be stored, so that the control flow of the
+//! loop does not depend on the copied data. The registers and flags the
+//! iteration reads before writing them have to be concrete, which makes the
+//! trip count concrete.
+//!
+//! The remaining iterations are then run on the
+//! [`ConcreteState`](super::concrete::ConcreteState) emulator, which gives the
+//! addresses of each copied element without the solver. Each iteration that
+//! executes the same instructions as the observed iteration and returns to the
+//! header is replaced and the registers and flags are set to their values
+//! after the last replaced iteration. The iteration that leaves the loop is
+//! executed as usual. If the replaced elements are contiguous and no element
+//! is loaded after an earlier iteration stored over it, they are copied with
+//! a single load and store of the whole array. Otherwise each element is
+//! copied in the order of the loop, so that overlapping copies behave as the
+//! loop does.
+//!
+//! At most [`RunConfig::accelerate_copies`](super::RunConfig::accelerate_copies)
+//! bytes are copied at once, the loop is observed again after a partial copy.
+//! Elements in hooked, modeled or memory mapped peripheral memory end the
+//! copy. The cycles, instructions and energy of a replaced iteration are those
+//! of the observed iteration. Copies are not accelerated when interrupts,
+//! caches or a branch predictor are modeled, as the cost of an iteration
+//! then depends on the state.
+
+use std::collections::{HashMap, HashSet};
+
+use tracing::debug;
+
+use super::{
+    arch::Arch,
+    concrete::ConcreteState,
+    executor::GAExecutor,
+    mmio::AccessKind,
+    slice::Location,
+    Endianness,
+    Result,
+};
+use crate::smt::DExpr;
+
+/// Largest number of instructions in the body of a copy loop.
+const MAX_BODY_INSTRUCTIONS: usize = 32;
+
+/// An event of an observed iteration.
+#[derive(Debug, Clone)]
+enum CopyEvent {
+    /// A register or flag was read.
+    Read(Location),
+
+    /// A register or flag was written.
+    Write(Location, DExpr),
+
+    /// An element was loaded from memory.
+    Load { bits: u32, value: DExpr },
+
+    /// An element was stored to memory.
+    Store { bits: u32, value: DExpr },
+}
+
+/// An iteration of a loop that is observed.
+#[derive(Debug, Clone)]
+struct Observation {
+    /// Address of the loop header.
+    header: u64,
+
+    /// Addresses of the executed instructions.
+    pcs: Vec<u64>,
+
+    /// Accesses of the iteration in program order.
+    events: Vec<CopyEvent>,
+
+    /// Cycle count when the iteration started.
+    cycles: usize,
+
+    /// Instruction count when the iteration started.
+    instructions: usize,
+
+    /// Energy when the iteration started.
+    energy: f64,
+
+    /// Number of path constraints when the iteration started.
+    constraints: usize,
+}
+
+/// How an iteration of a copy loop copies its element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CopyPattern {
+    /// Size of an element in bits.
+    bits: u32,
+
+    /// Register the element is loaded into.
+    temporary: String,
+
+    /// Set if the element is sign extended into the register.
+    signed: bool,
+}
+
+impl Observation {
+    /// Returns how the iteration copies its element, `None` if it is not an
+    /// iteration of a copy loop.
+    fn copy_pattern(&self, word_size: u32) -> Option<CopyPattern> {
+        let (mut load, mut store) = (None, None);
+        for (index, event) in self.events.iter().enumerate() {
+            match event {
+                CopyEvent::Load { bits, value } if load.is_none() => {
+                    load = Some((index, *bits, value))
+                }
+                CopyEvent::Store { bits, value } if store.is_none() => {
+                    store = Some((index, *bits, value))
+                }
+                CopyEvent::Load { .. } | CopyEvent::Store { .. } => return None,
+                _ => {}
+            }
+        }
+        let ((load, bits, element), (store, stored_bits, stored)) = (load?, store?);
+        if load > store || bits != stored_bits || bits > word_size {
+            return None;
+        }
+        if stored.resize_unsigned(bits).simplify() != element.clone().simplify() {
+            return None;
+        }
+
+        // The element is loaded into a register that is only read to store it.
+        let extended = |signed: bool| {
+            let value = match signed {
+                true => element.sign_ext(word_size),
+                false => element.resize_unsigned(word_size),
+            };
+            value.simplify()
+        };
+        let (write, temporary, signed) = self.events[load..store].iter().enumerate().find_map(
+            |(offset, event)| match event {
+                CopyEvent::Write(Location::Register(register), value) => [false, true]
+                    .into_iter()
+                    .find(|signed| value.clone().simplify() == extended(*signed))
+                    .map(|signed| (load + offset, register.clone(), signed)),
+                _ => None,
+            },
+        )?;
+        let location = Location::Register(temporary.clone());
+        let writes = self
+            .events
+            .iter()
+            .filter(|event| matches!(event, CopyEvent::Write(written, _) if *written == location))
+            .count();
+        let reads: Vec<usize> = (0..self.events.len())
+            .filter(
+                |index| matches!(&self.events[*index], CopyEvent::Read(read) if *read == location),
+            )
+            .collect();
+        match (writes, reads.as_slice()) {
+            (1, [read]) if write < *read && *read < store => Some(CopyPattern {
+                bits,
+                temporary,
+                signed,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the registers and flags the iteration reads before it writes
+    /// them.
+    fn inputs(&self) -> HashSet<&Location> {
+        let mut written = HashSet::new();
+        let mut inputs = HashSet::new();
+        for event in &self.events {
+            match event {
+                CopyEvent::Read(location) if !written.contains(location) => {
+                    inputs.insert(location);
+                }
+                CopyEvent::Write(location, _) => {
+                    written.insert(location);
+                }
+                _ => {}
+            }
+        }
+        inputs
+    }
+
+    /// Returns the registers and flags the iteration writes.
+    fn outputs(&self) -> Vec<&Location> {
+        let mut outputs = vec![];
+        for event in &self.events {
+            if let CopyEvent::Write(location, _) = event {
+                if !outputs.contains(&location) {
+                    outputs.push(location);
+                }
+            }
+        }
+        outputs
+    }
+}
+
+/// Detection of copy loops on a path.
+#[derive(Debug, Clone, Default)]
+pub struct CopyDetector {
+    /// The iteration that is observed.
+    observation: Option<Observation>,
+
+    /// Headers of the loops that are not copy loops.
+    rejected: HashSet<u64>,
+}
+
+impl CopyDetector {
+    /// Checks if an iteration is observed.
+    pub fn is_observing(&self) -> bool {
+        self.observation.is_some()
+    }
+
+    /// Records a read of a register or flag.
+    pub fn read(&mut self, location: Location) {
+        self.push(CopyEvent::Read(location));
+    }
+
+    /// Records a write of `value` to a register or flag.
+    pub fn write(&mut self, location: Location, value: &DExpr) {
+        self.push(CopyEvent::Write(location, value.clone()));
+    }
+
+    /// Records a load of `value` from memory.
+    pub fn load(&mut self, bits: u32, value: &DExpr) {
+        self.push(CopyEvent::Load {
+            bits,
+            value: value.clone(),
+        });
+    }
+
+    /// Records a store of `value` to memory.
+    pub fn store(&mut self, bits: u32, value: &DExpr) {
+        self.push(CopyEvent::Store {
+            bits,
+            value: value.clone(),
+        });
+    }
+
+    fn push(&mut self, event: CopyEvent) {
+        if let Some(observation) = &mut self.observation {
+            observation.events.push(event);
+        }
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Checks if copy loops are accelerated on this path.
+    fn copies_enabled(&self) -> bool {
+        self.project.get_accelerate_copies().is_some()
+            && self.project.get_interrupts().is_empty()
+            && self.project.get_instruction_cache().is_none()
+            && self.project.get_data_cache().is_none()
+            && self.project.get_branch_predictor().is_none()
+    }
+
+    /// Observes the iterations of loops before the next instruction, and
+    /// replaces the remaining iterations of a copy loop when the path returns
+    /// to its header.
+    pub(super) fn accelerate_copy(&mut self) -> Result<()> {
+        if !self.copies_enabled() {
+            return Ok(());
+        }
+        let pc = match self.state.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Ok(()),
+        };
+        let back_edge = self.state.get_has_jumped() && self.state.last_pc >= pc;
+        if !back_edge {
+            if let Some(observation) = &mut self.state.copy_loops.observation {
+                observation.pcs.push(pc);
+                if observation.pcs.len() > MAX_BODY_INSTRUCTIONS {
+                    self.state.copy_loops.observation = None;
+                }
+            }
+            return Ok(());
+        }
+
+        match self.state.copy_loops.observation.take() {
+            Some(observation)
+                if observation.header == pc && !self.replace_iterations(&observation)? =>
+            {
+                self.state.copy_loops.rejected.insert(pc);
+                return Ok(());
+            }
+            _ => {}
+        }
+        self.observe_iteration(pc);
+        Ok(())
+    }
+
+    /// Starts to observe the iteration of the loop with the header at `pc`.
+    fn observe_iteration(&mut self, pc: u64) {
+        if self.state.copy_loops.rejected.contains(&pc) || self.state.get_in_conditional_block() {
+            return;
+        }
+        self.state.copy_loops.observation = Some(Observation {
+            header: pc,
+            pcs: vec![pc],
+            events: vec![],
+            cycles: self.state.cycle_count,
+            instructions: self.state.get_instruction_count(),
+            energy: self.state.energy,
+            constraints: self.state.constraints.constraint_count(),
+        });
+    }
+
+    /// Replaces the remaining iterations of the loop of `observation` if it is
+    /// a copy loop. Returns false if it is not.
+    fn replace_iterations(&mut self, observation: &Observation) -> Result<bool> {
+        let word_size = self.project.get_word_size();
+        let pattern = match observation.copy_pattern(word_size) {
+            Some(pattern)
+                if observation.constraints == self.state.constraints.constraint_count() =>
+            {
+                pattern
+            }
+            _ => return Ok(false),
+        };
+        let Some(mut emulator) = self.copy_emulator(observation) else {
+            return Ok(false);
+        };
+
+        let budget = self.project.get_accelerate_copies().unwrap_or(0);
+        let element_bytes = (pattern.bits as u64).div_ceil(8);
+        let max_elements = (budget / element_bytes) as usize;
+        let outputs: Vec<Location> = observation
+            .outputs()
+            .into_iter()
+            .filter(|location| **location != Location::Register(pattern.temporary.clone()))
+            .cloned()
+            .collect();
+        let mut elements = vec![];
+        let mut values = vec![];
+        emulator.record_accesses();
+        'iterations: while elements.len() < max_elements {
+            let mut pcs = vec![];
+            loop {
+                let pc = emulator.get_register("PC") & !0b1;
+                if pc == observation.header && !pcs.is_empty() {
+                    break;
+                }
+                if pcs.len() == observation.pcs.len() || !matches!(emulator.step(), Ok(None)) {
+                    break 'iterations;
+                }
+                pcs.push(pc);
+            }
+            let element = match emulator.take_accesses()[..] {
+                [load, store]
+                    if pcs == observation.pcs
+                        && load.kind == AccessKind::Read
+                        && store.kind == AccessKind::Write
+                        && load.bits == pattern.bits
+                        && store.bits == pattern.bits
+                        && self.is_plain_memory(load.address, pattern.bits, false)
+                        && self.is_plain_memory(store.address, pattern.bits, true) =>
+                {
+                    (load.address, store.address)
+                }
+                _ => break,
+            };
+            elements.push(element);
+            values = outputs
+                .iter()
+                .map(|location| match location {
+                    Location::Register(register) => emulator.get_register(register),
+                    Location::Flag(flag) => emulator.get_flag(flag) as u64,
+                    Location::Memory(_) => unreachable!("memory is not a register or flag"),
+                })
+                .collect();
+        }
+        if elements.is_empty() {
+            return Ok(true);
+        }
+
+        debug!(
+            "Replacing {} iterations of the copy loop at {:#X}",
+            elements.len(),
+            observation.header
+        );
+        let energy = self.state.energy;
+        let mut element = None;
+        match contiguous_copy(&elements, element_bytes).filter(|_| pattern.bits.is_multiple_of(8)) {
+            Some((source, destination)) => {
+                let bits = pattern.bits * elements.len() as u32;
+                let value = self.get_memory(source, bits)?;
+                self.set_memory(value.clone(), destination, bits)?;
+                let (last, _) = elements[elements.len() - 1];
+                let offset = (last - source) as u32 * 8;
+                let low = match self.project.get_endianness() {
+                    Endianness::Little => offset,
+                    Endianness::Big => bits - offset - pattern.bits,
+                };
+                element = Some(value.slice(low, low + pattern.bits - 1));
+            }
+            None => {
+                for (source, destination) in &elements {
+                    let value = self.get_memory(*source, pattern.bits)?;
+                    self.set_memory(value.clone(), *destination, pattern.bits)?;
+                    element = Some(value);
+                }
+            }
+        }
+        for (location, value) in outputs.into_iter().zip(values) {
+            match location {
+                Location::Register(register) => {
+                    let value = self.state.ctx.from_u64(value, word_size);
+                    self.state.set_register(register, value)?;
+                }
+                Location::Flag(flag) => {
+                    let value = self.state.ctx.from_bool(value != 0);
+                    self.state.set_flag(flag, value)?;
+                }
+                Location::Memory(_) => unreachable!("memory is not a register or flag"),
+            }
+        }
+        if let Some(element) = element {
+            let value = match pattern.signed {
+                true => element.sign_ext(word_size),
+                false => element.resize_unsigned(word_size),
+            };
+            self.state.set_register(pattern.temporary.clone(), value)?;
+        }
+
+        let iterations = elements.len();
+        self.state.cycle_count += iterations * (self.state.cycle_count - observation.cycles);
+        self.state.skip_instructions(
+            iterations * (self.state.get_instruction_count() - observation.instructions),
+        );
+        self.state.energy = energy + iterations as f64 * (energy - observation.energy);
+        self.vm.copied_bytes += iterations as u64 * element_bytes;
+        Ok(true)
+    }
+
+    /// Creates an emulator that continues from the registers and flags of the
+    /// path, `None` if an input of the iteration in `observation` is not
+    /// concrete.
+    fn copy_emulator(&self, observation: &Observation) -> Option<ConcreteState<A>> {
+        for input in observation.inputs() {
+            let value = match input {
+                Location::Register(register) => self.state.registers.get(register),
+                Location::Flag(flag) => self.state.get_flags().get(flag),
+                Location::Memory(_) => continue,
+            };
+            value.and_then(DExpr::get_constant)?;
+        }
+        let constants = |values: &HashMap<String, DExpr>| -> HashMap<String, u64> {
+            values
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.get_constant()?)))
+                .collect()
+        };
+        let mut registers = constants(&self.state.registers);
+        registers.insert("PC".to_owned(), observation.header);
+        let flags = constants(self.state.get_flags())
+            .into_iter()
+            .map(|(flag, value)| (flag, value != 0))
+            .collect();
+        Some(ConcreteState::from_values(
+            self.project.clone(),
+            self.state.architecture.clone(),
+            registers,
+            flags,
+        ))
+    }
+
+    /// Checks if the `bits` bits at `address` are memory without hooks or
+    /// models, that can be written if `write` is set.
+    fn is_plain_memory(&self, address: u64, bits: u32, write: bool) -> bool {
+        (address..address + (bits as u64).div_ceil(8)).all(|address| {
+            let hooked = match write {
+                true => self.project.get_memory_write_hook(address).is_some(),
+                false => self.project.get_memory_read_hook(address).is_some(),
+            };
+            let static_memory = self.project.address_in_range(address)
+                && !self.project.address_in_backed_region(address);
+            let modeled = self.project.address_in_mmio_region(address)
+                || self.project.get_peripheral_model(address).is_some()
+                || (self.project.get_nvic() && self.in_system_region(address));
+            !(hooked || modeled || (write && static_memory))
+        })
+    }
+}
+
+/// Returns the lowest source and destination address of `elements`, the
+/// source and destination of each copied element of `bytes` bytes in the
+/// order of the loop, if a single copy of all of them copies the same values.
+/// The elements must be contiguous and in the same direction in both, and no
+/// element may be loaded after an earlier element was stored over it.
+fn contiguous_copy(elements: &[(u64, u64)], bytes: u64) -> Option<(u64, u64)> {
+    let (first, last) = (*elements.first()?, *elements.last()?);
+    let descending = last.0 < first.0;
+    let contiguous = elements.windows(2).all(|pair| {
+        let ((source, destination), (next_source, next_destination)) = (pair[0], pair[1]);
+        match descending {
+            false => next_source == source + bytes && next_destination == destination + bytes,
+            true => next_source + bytes == source && next_destination + bytes == destination,
+        }
+    });
+    if !contiguous {
+        return None;
+    }
+    let (source, destination) = match descending {
+        false => first,
+        true => last,
+    };
+    let length = bytes * elements.len() as u64;
+    let disjoint = source + length <= destination || destination + length <= source;
+    // An overlapping copy only stores over elements it has already loaded if
+    // it moves towards the direction it copies in.
+    let ordered = match descending {
+        false => destination <= source,
+        true => destination >= source,
+    };
+    (disjoint || ordered).then_some((source, destination))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{contiguous_copy, CopyEvent, CopyPattern, Observation};
+    use crate::{general_assembly::slice::Location, smt::DContext};
+
+    fn register(name: &str) -> Location {
+        Location::Register(name.to_owned())
+    }
+
+    /// The events of `ldrb r3, [r1]; strb r3, [r0]; adds r1, #1; adds r0, #1;
+    /// subs r2, #1; bne`, with `check` reading the element once more.
+    fn copy_iteration(context: &DContext, check: bool) -> Observation {
+        let element = context.from_u64(0x42, 8);
+        let word = |value: u64| context.from_u64(value, 32);
+        let mut events = vec![
+            CopyEvent::Read(register("R1")),
+            CopyEvent::Load {
+                bits: 8,
+                value: element.clone(),
+            },
+            CopyEvent::Write(register("R3"), element.resize_unsigned(32)),
+        ];
+        if check {
+            events.push(CopyEvent::Read(register("R3")));
+        }
+        events.extend([
+            CopyEvent::Read(register("R3")),
+            CopyEvent::Read(register("R0")),
+            CopyEvent::Store {
+                bits: 8,
+                value: element,
+            },
+            CopyEvent::Read(register("R1")),
+            CopyEvent::Write(register("R1"), word(0x2000_0001)),
+            CopyEvent::Read(register("R0")),
+            CopyEvent::Write(register("R0"), word(0x2000_0101)),
+            CopyEvent::Read(register("R2")),
+            CopyEvent::Write(register("R2"), word(15)),
+            CopyEvent::Write(Location::Flag("Z".to_owned()), context.from_bool(false)),
+            CopyEvent::Read(Location::Flag("Z".to_owned())),
+        ]);
+        Observation {
+            header: 0x100,
+            pcs: vec![0x100, 0x102, 0x104, 0x106, 0x108, 0x10A],
+            events,
+            cycles: 0,
+            instructions: 0,
+            energy: 0.0,
+            constraints: 0,
+        }
+    }
+
+    #[test]
+    fn test_copy_pattern() {
+        let context = DContext::new();
+        let observation = copy_iteration(&context, false);
+        assert_eq!(
+            observation.copy_pattern(32),
+            Some(CopyPattern {
+                bits: 8,
+                temporary: "R3".to_owned(),
+                signed: false,
+            })
+        );
+        let mut inputs: Vec<_> = observation.inputs().into_iter().cloned().collect();
+        inputs.sort();
+        assert_eq!(inputs, [register("R0"), register("R1"), register("R2")]);
+        assert_eq!(observation.outputs(), [
+            &register("R3"),
+            &register("R1"),
+            &register("R0"),
+            &register("R2"),
+            &Location::Flag("Z".to_owned()),
+        ]);
+
+        // The loop depends on the copied data, as in `strcpy`.
+        let observation = copy_iteration(&context, true);
+        assert_eq!(observation.copy_pattern(32), None);
+    }
+
+    #[test]
+    fn test_contiguous_copy() {
+        let forward = |source: u64, destination: u64| {
+            (0..4)
+                .map(|n| (source + 4 * n, destination + 4 * n))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            contiguous_copy(&forward(0x100, 0x200), 4),
+            Some((0x100, 0x200))
+        );
+
+        // A forward copy that overlaps its source is only copied at once if it
+        // moves the elements down, as `memmove` does.
+        assert_eq!(
+            contiguous_copy(&forward(0x104, 0x100), 4),
+            Some((0x104, 0x100))
+        );
+        assert_eq!(contiguous_copy(&forward(0x100, 0x104), 4), None);
+
+        // A backward copy, as `memmove` makes to move the elements up.
+        let backward: Vec<_> = forward(0x100, 0x104).into_iter().rev().collect();
+        assert_eq!(contiguous_copy(&backward, 4), Some((0x100, 0x104)));
+
+        // Elements that are not contiguous are copied one at a time.
+        let strided = [(0x100, 0x200), (0x108, 0x204)];
+        assert_eq!(contiguous_copy(&strided, 4), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Co-simulation of the executor with an external model.
+//!
+//! A [`CoSimulator`] is notified of every instruction the executor retires,
+//! which allows streaming the execution to a board or an external simulator
+//! and comparing the timing model of symex against hardware traces.
+
+use std::{io::Write, sync::Mutex};
+
+use tracing::warn;
+
+/// An instruction retired by the executor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetiredInstruction {
+    /// Number of instructions retired on the path before this one.
+    pub index: usize,
+
+    /// Address of the instruction.
+    pub pc: u64,
+
+    /// Number of cycles the instruction took.
+    pub cycles: usize,
+}
+
+/// An external model that is run in lock step with the executor.
+///
+/// The methods take `&self` as the co-simulator is shared through the
+/// [`Project`](super::project::Project), implementors that keep state need
+/// interior mutability. All paths are reported to the same co-simulator, use
+/// a concrete entry state to follow a single path.
+pub trait CoSimulator: Send + Sync {
+    /// Called for every instruction retired by the executor.
+    ///
+    /// Returns the instruction the external model retired at the same index,
+    /// if known. [`CoSimulator::diverged`] is called if it differs.
+    fn retire(&self, retired: &RetiredInstruction) -> Option<RetiredInstruction>;
+
+    /// Called when the external model retired a different instruction or took a
+    /// different number of cycles than the executor.
+    fn diverged(&self, symex: &RetiredInstruction, external: &RetiredInstruction) {
+        warn!(
+            "Co-simulation diverged at instruction {}: symex retired {:#X} in {} cycles, external model retired {:#X} in {} cycles",
+            symex.index, symex.pc, symex.cycles, external.pc, external.cycles
+        );
+    }
+}
+
+/// Streams every retired instruction to a writer, such as a socket connected
+/// to a co-simulator.
+///
+/// Each instruction is written on its own line as `index pc cycles`, with the
+/// address in hexadecimal.
+pub struct TraceStream<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> TraceStream<W> {
+    /// Creates a new [`TraceStream`] writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Consumes the stream and returns the writer.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<W: Write + Send> CoSimulator for TraceStream<W> {
+    fn retire(&self, retired: &RetiredInstruction) -> Option<RetiredInstruction> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(
+            writer,
+            "{} {:#x} {}",
+            retired.index, retired.pc, retired.cycles
+        ) {
+            warn!("Unable to stream retired instruction: {}", e);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CoSimulator, RetiredInstruction, TraceStream};
+
+    #[test]
+    fn test_trace_stream() {
+        let stream = TraceStream::new(Vec::new());
+        for (index, pc) in [0x100, 0x102].into_iter().enumerate() {
+            let retired = RetiredInstruction {
+                index,
+                pc,
+                cycles: 1,
+            };
+            assert_eq!(stream.retire(&retired), None);
+        }
+        let trace = String::from_utf8(stream.into_inner()).unwrap();
+        assert_eq!(trace, "0 0x100 1\n1 0x102 1\n");
+    }
+}
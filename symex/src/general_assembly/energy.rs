@@ -0,0 +1,267 @@
+//! Estimation of the energy used by each path.
+//!
+//! The [`energy_model`](super::RunConfig::energy_model) is consulted for every
+//! executed instruction, every data memory access and every access to a memory
+//! mapped peripheral in [`mmio_regions`](super::RunConfig::mmio_regions). The
+//! energy is accumulated alongside the cycles of the path and reported with
+//! the path result.
+
+use general_assembly::{operand::Operand, operation::Operation};
+
+use super::{arch::Arch, instruction::Instruction};
+
+/// Coarse class of an instruction used to estimate its energy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstructionClass {
+    /// Arithmetic, logic and moves between registers.
+    Alu,
+
+    /// Multiplications.
+    Multiply,
+
+    /// Divisions.
+    Divide,
+
+    /// Loads and stores.
+    Memory,
+
+    /// Instructions that may write the program counter.
+    Branch,
+
+    /// Instructions without any effect.
+    Nop,
+}
+
+impl InstructionClass {
+    /// Classifies `instruction` by its operations.
+    pub fn of<A: Arch>(instruction: &Instruction<A>) -> Self {
+        let operations = &instruction.operations;
+        let writes_pc = |operation: &Operation| match operation {
+            Operation::ConditionalJump { .. } => true,
+            Operation::Move {
+                destination: Operand::Register(register),
+                ..
+            } => register == "PC",
+            _ => false,
+        };
+        if operations.iter().any(writes_pc) {
+            Self::Branch
+        } else if instruction.memory_access {
+            Self::Memory
+        } else if operations
+            .iter()
+            .any(|operation| matches!(operation, Operation::SDiv { .. } | Operation::UDiv { .. }))
+        {
+            Self::Divide
+        } else if operations
+            .iter()
+            .any(|operation| matches!(operation, Operation::Mul { .. }))
+        {
+            Self::Multiply
+        } else if operations
+            .iter()
+            .all(|operation| matches!(operation, Operation::Nop))
+        {
+            Self::Nop
+        } else {
+            Self::Alu
+        }
+    }
+}
+
+/// Energy used by the parts of the execution, in nJ.
+///
+/// The methods take `&self` as the model is shared through the
+/// [`Project`](super::project::Project).
+pub trait EnergyModel: Send + Sync {
+    /// Energy of an instruction of `class` that took `cycles` cycles.
+    fn instruction(&self, class: InstructionClass, cycles: usize) -> f64;
+
+    /// Energy of a data memory access of `bits` bits at `address`.
+    fn memory_access(&self, _address: u64, _bits: u32) -> f64 {
+        0.0
+    }
+
+    /// Energy of an access to the peripheral register at `address`, in
+    /// addition to the energy of the memory access.
+    fn peripheral_access(&self, _address: u64) -> f64 {
+        0.0
+    }
+}
+
+/// Energy model with a fixed energy per cycle for each instruction class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassEnergyModel {
+    /// Energy per cycle of [`InstructionClass::Alu`] instructions.
+    pub alu: f64,
+
+    /// Energy per cycle of [`InstructionClass::Multiply`] instructions.
+    pub multiply: f64,
+
+    /// Energy per cycle of [`InstructionClass::Divide`] instructions.
+    pub divide: f64,
+
+    /// Energy per cycle of [`InstructionClass::Memory`] instructions.
+    pub memory: f64,
+
+    /// Energy per cycle of [`InstructionClass::Branch`] instructions.
+    pub branch: f64,
+
+    /// Energy per cycle of [`InstructionClass::Nop`] instructions.
+    pub nop: f64,
+
+    /// Energy per byte accessed in memory.
+    pub memory_byte: f64,
+
+    /// Energy per access to a peripheral register.
+    pub peripheral: f64,
+}
+
+impl ClassEnergyModel {
+    /// Creates a model where every cycle uses `energy` and accesses use no
+    /// additional energy.
+    pub fn uniform(energy: f64) -> Self {
+        Self {
+            alu: energy,
+            multiply: energy,
+            divide: energy,
+            memory: energy,
+            branch: energy,
+            nop: energy,
+            memory_byte: 0.0,
+            peripheral: 0.0,
+        }
+    }
+}
+
+impl EnergyModel for ClassEnergyModel {
+    fn instruction(&self, class: InstructionClass, cycles: usize) -> f64 {
+        let energy = match class {
+            InstructionClass::Alu => self.alu,
+            InstructionClass::Multiply => self.multiply,
+            InstructionClass::Divide => self.divide,
+            InstructionClass::Memory => self.memory,
+            InstructionClass::Branch => self.branch,
+            InstructionClass::Nop => self.nop,
+        };
+        energy * cycles as f64
+    }
+
+    fn memory_access(&self, _address: u64, bits: u32) -> f64 {
+        self.memory_byte * bits.div_ceil(8) as f64
+    }
+
+    fn peripheral_access(&self, _address: u64) -> f64 {
+        self.peripheral
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use general_assembly::{
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    use super::{ClassEnergyModel, EnergyModel, InstructionClass};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        instruction::{CycleCount, Instruction},
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    fn instruction(operations: Vec<Operation>, memory_access: bool) -> Instruction<ArmV6M> {
+        Instruction {
+            instruction_size: 16,
+            operations,
+            max_cycle: CycleCount::Value(1),
+            memory_access,
+        }
+    }
+
+    #[test]
+    fn test_instruction_class() {
+        let r0 = Operand::Register("R0".to_owned());
+        let r1 = Operand::Register("R1".to_owned());
+        let branch = instruction(
+            vec![Operation::Move {
+                destination: Operand::Register("PC".to_owned()),
+                source: r0.clone(),
+            }],
+            false,
+        );
+        let load = instruction(
+            vec![Operation::Move {
+                destination: r0.clone(),
+                source: Operand::AddressInLocal("address".to_owned(), 32),
+            }],
+            true,
+        );
+        let multiply = instruction(
+            vec![Operation::Mul {
+                destination: r0.clone(),
+                operand1: r0.clone(),
+                operand2: r1.clone(),
+            }],
+            false,
+        );
+        let add = instruction(
+            vec![Operation::Add {
+                destination: r0.clone(),
+                operand1: r0,
+                operand2: r1,
+            }],
+            false,
+        );
+        assert_eq!(InstructionClass::of(&branch), InstructionClass::Branch);
+        assert_eq!(InstructionClass::of(&load), InstructionClass::Memory);
+        assert_eq!(InstructionClass::of(&multiply), InstructionClass::Multiply);
+        assert_eq!(InstructionClass::of(&add), InstructionClass::Alu);
+        assert_eq!(
+            InstructionClass::of(&instruction(vec![Operation::Nop], false)),
+            InstructionClass::Nop
+        );
+
+        let model = ClassEnergyModel {
+            multiply: 3.0,
+            ..ClassEnergyModel::uniform(1.0)
+        };
+        assert_eq!(model.instruction(InstructionClass::Multiply, 2), 6.0);
+        assert_eq!(model.instruction(InstructionClass::Alu, 2), 2.0);
+    }
+
+    #[test]
+    fn test_access_energy() {
+        let mut project = Project::test_project();
+        project.add_mmio_region(0x4000_0000..0x4000_1000);
+        project.set_energy_model(Some(Arc::new(ClassEnergyModel {
+            memory_byte: 0.5,
+            peripheral: 10.0,
+            ..ClassEnergyModel::uniform(1.0)
+        })));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+        let write = |address: u64, bits: u32| Operation::Move {
+            destination: Operand::Address(DataWord::Word32(address as u32), bits),
+            source: Operand::Immediate(DataWord::Word32(1)),
+        };
+
+        executor
+            .execute_operation(&write(0x2000_0000, 32), &mut local)
+            .unwrap();
+        assert_eq!(executor.state.energy, 2.0);
+        executor
+            .execute_operation(&write(0x4000_0000, 16), &mut local)
+            .unwrap();
+        assert_eq!(executor.state.energy, 13.0);
+    }
+}
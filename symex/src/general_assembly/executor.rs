@@ -1,31 +1,58 @@
 //! General assembly executor
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
 
 use general_assembly::{
-    operand::{DataWord, Operand},
+    condition::Condition,
+    hazard::{Hazards, Port},
+    operand::{DataWord, Operand, RawDataWord},
     operation::Operation,
     shift::Shift,
 };
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use super::{
-    arch::Arch,
-    instruction::Instruction,
-    project::Project,
-    state::{ContinueInsideInstruction, GAState},
+    arch::{Arch, ArchError, ParseError},
+    branch_predictor::is_conditional_branch,
+    budget::Truncation,
+    cache::CacheConfig,
+    cosim::RetiredInstruction,
+    energy::InstructionClass,
+    instruction::{CycleCount, Instruction},
+    interrupts::ATOMICITY_VIOLATION,
+    mmio::{AccessKind, MmioAccess},
+    profile::Semantics,
+    project::{FilterAction, Project},
+    side_effects::{EffectFrame, FunctionEffects},
+    slice::Location,
+    state::{ContinueInsideInstruction, FilteredCall, GAState, LoopHavoc, SymbolOrigin},
+    temporal::PropertyMonitor,
     vm::VM,
+    watchdog::WATCHDOG_EXPIRED,
+    Endianness,
+    GAError,
     Result,
+    UnknownMemoryPolicy,
+    UnpredictablePolicy,
 };
 use crate::{
     general_assembly::{path_selection::Path, state::HookOrInstruction},
-    smt::{smt_boolector::BoolectorSolverContext, DExpr, SolverError},
+    memory::SmtMap,
+    smt::{DContext, DExpr, Solutions, SolverError},
 };
 
+/// Largest number of values of a symbolic address that are followed when the
+/// number of forks is not limited, and the least number of values the forks
+/// are prioritized among when it is.
+const MAX_VALUES: usize = 255;
+
 pub struct GAExecutor<'vm, A: Arch> {
     pub vm: &'vm mut VM<A>,
     pub state: GAState<A>,
-    pub project: &'static Project<A>,
+    pub project: Arc<Project<A>>,
     //current_instruction: Option<Instruction>,
     current_operation_index: usize,
 }
@@ -35,6 +62,12 @@ pub enum PathResult {
     Failure(&'static str),
     AssumptionUnsat,
     Suppress,
+    /// The solver could not decide a query of the path in time, holds a
+    /// description of the query.
+    Unknown(String),
+    /// The path exceeded the budget of the exploration and was ended before
+    /// it finished, see [`ExecutionBudget`](super::budget::ExecutionBudget).
+    Truncated(Truncation),
 }
 
 struct AddWithCarryResult {
@@ -45,7 +78,7 @@ struct AddWithCarryResult {
 
 impl<'vm, A: Arch> GAExecutor<'vm, A> {
     /// Construct a executor from a state.
-    pub fn from_state(state: GAState<A>, vm: &'vm mut VM<A>, project: &'static Project<A>) -> Self {
+    pub fn from_state(state: GAState<A>, vm: &'vm mut VM<A>, project: Arc<Project<A>>) -> Self {
         Self {
             vm,
             state,
@@ -65,11 +98,73 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
         }
 
         loop {
-            let instruction = match self.state.get_next_instruction()? {
+            self.resolve_symbolic_pc()?;
+            if self.update_timeline() {
+                return Ok(PathResult::Suppress);
+            }
+            if self.project.get_deduplicate_states()
+                && self.state.get_has_jumped()
+                && !self.vm.visit_state(self.state.key())
+            {
+                debug!("Pruning path that reached an explored state");
+                self.vm.duplicates += 1;
+                return Ok(PathResult::Suppress);
+            }
+            if self.is_subsumed()? {
+                debug!("Pruning path with a state implied by an explored state");
+                self.vm.subsumed += 1;
+                return Ok(PathResult::Suppress);
+            }
+            if !self.project.get_interrupts().is_empty() {
+                if let Some(result) = self.return_from_interrupt()? {
+                    return Ok(result);
+                }
+                self.inject_interrupts()?;
+            }
+            if let Some(result) = self.check_reentrancy()? {
+                return Ok(result);
+            }
+            if self.apply_summaries()? {
+                continue;
+            }
+            let next = match self.state.get_next_instruction() {
+                Err(GAError::ArchError(ArchError::ParsingError(ParseError::Unpredictable))) => {
+                    match self.handle_unpredictable()? {
+                        Some(result) => return Ok(result),
+                        None => continue,
+                    }
+                }
+                Err(GAError::UnsupportedExecutionState(execution_state, pc)) => {
+                    warn!(
+                        "Branch to {:#X} switched to {:?} state, which the processor does not support",
+                        pc, execution_state
+                    );
+                    return Ok(PathResult::Failure(
+                        "switched to an unsupported instruction set state",
+                    ));
+                }
+                Err(error @ GAError::ArchError(ArchError::ParsingError(_))) => {
+                    let pc = self.state.get_register("PC".to_owned())?.get_constant();
+                    if let Some(pc) = pc {
+                        self.record_semantics(pc & !0b1, Semantics::Unsupported);
+                    }
+                    return Err(error);
+                }
+                next => next?,
+            };
+            let mut instruction = match next {
                 HookOrInstruction::Instruction(v) => v,
                 HookOrInstruction::PcHook(hook) => match hook {
                     crate::general_assembly::project::PCHook::Continue => {
                         debug!("Continuing");
+                        if self.project.get_side_effects_file().is_some() {
+                            // Returning immediately is a stub without side effects.
+                            if let Some(function) = self.state.get_current_function_name()? {
+                                self.vm
+                                    .stub_effects
+                                    .record(&function, &FunctionEffects::default());
+                            }
+                        }
                         let lr = self.state.get_register("LR".to_owned()).unwrap();
                         self.state.set_register("PC".to_owned(), lr)?;
                         continue;
@@ -77,20 +172,52 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                     crate::general_assembly::project::PCHook::EndSuccess => {
                         debug!("Symbolic execution ended successfully");
                         self.state.increment_cycle_count();
+                        self.state.record_region_laps();
                         return Ok(PathResult::Success(None));
                     }
                     crate::general_assembly::project::PCHook::EndFailure(reason) => {
                         debug!("Symbolic execution ended unsuccessfully");
                         let data = *reason;
                         self.state.increment_cycle_count();
+                        self.state.record_region_laps();
                         return Ok(PathResult::Failure(data));
                     }
                     crate::general_assembly::project::PCHook::Suppress => {
                         self.state.increment_cycle_count();
                         return Ok(PathResult::Suppress);
                     }
+                    crate::general_assembly::project::PCHook::Assume => {
+                        if let Some(result) = self.assume_argument()? {
+                            return Ok(result);
+                        }
+                        continue;
+                    }
+                    crate::general_assembly::project::PCHook::Assert(reason) => {
+                        let reason = *reason;
+                        if let Some(result) = self.assert_argument(reason)? {
+                            return Ok(result);
+                        }
+                        continue;
+                    }
+                    crate::general_assembly::project::PCHook::Filter(action) => {
+                        if let Some(result) = self.filter_call(*action)? {
+                            return Ok(result);
+                        }
+                        continue;
+                    }
                     crate::general_assembly::project::PCHook::Intrinsic(f) => {
-                        f(&mut self.state)?;
+                        let f = *f;
+                        if self.project.get_side_effects_file().is_some() {
+                            let registers = self.state.registers.clone();
+                            let memory = self.state.memory.written_addresses().clone();
+                            let function = self.state.get_current_function_name()?;
+                            self.run_intrinsic(f)?;
+                            if let Some(function) = function {
+                                self.record_stub_effects(&function, &registers, &memory)?;
+                            }
+                        } else {
+                            self.run_intrinsic(f)?;
+                        }
 
                         // set last instruction to empty to no count instruction twice
                         self.state.last_instruction = None;
@@ -99,26 +226,710 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 },
             };
 
+            if let Some(result) = self.check_budget()? {
+                return Ok(result);
+            }
+            if self.vm.ranges.is_some() {
+                self.assume_ranges()?;
+            }
+            if let Some(result) = self.apply_memory_havocs()? {
+                return Ok(result);
+            }
+            if let Some(result) = self.apply_loop_invariant()? {
+                return Ok(result);
+            }
+            if self.project.get_accelerate_loops() {
+                if let Some(result) = self.accelerate_loop()? {
+                    return Ok(result);
+                }
+            }
+            self.accelerate_copy()?;
+
             // Add cycles to cycle count
             self.state.increment_cycle_count();
+            self.state.record_region_laps();
+            self.state.advance_trace()?;
+            if self.watchdog_expired() {
+                return Ok(PathResult::Failure(WATCHDOG_EXPIRED));
+            }
 
+            let max_call_depth = self.project.get_max_call_depth();
+            if max_call_depth.is_some() {
+                self.state.pop_returned_call();
+            }
+            let track_effects = self.project.get_side_effects_file().is_some();
+            if track_effects {
+                if let Some(frame) = self.state.pop_returned_effect_frame() {
+                    self.record_side_effects(frame)?;
+                }
+            }
+            let stack_locals = self.project.get_stack_locals();
+            if stack_locals {
+                self.state.pop_returned_stack_frame();
+            }
+            let track_calls = max_call_depth.is_some() || track_effects || stack_locals;
+
+            let stall = self.pipeline_stall(&instruction);
+            let misses = self.cache_misses();
+            self.fetch_through_cache(&instruction);
             trace!("executing instruction: {:?}", instruction);
             self.execute_instruction(&instruction)?;
+            let fall_through = self.state.last_pc + (instruction.instruction_size / 8) as u64;
+            if let Some(pc) = self.state.get_register("PC".to_owned())?.get_constant() {
+                if pc != fall_through {
+                    self.state.jump_targets.insert(pc & !0b1);
+                }
+            }
+            if let Some(reason) = self.resolve_peripherals()? {
+                debug!("Peripheral model violated: {}", reason);
+                return Ok(PathResult::Failure(reason));
+            }
+            if let Some(violation) = &self.state.interrupts.violation {
+                debug!("Atomicity violated: {:?}", violation);
+                return Ok(PathResult::Failure(ATOMICITY_VIOLATION));
+            }
+
+            if !self.project.get_properties().is_empty() {
+                let pc = self.state.last_pc;
+                if let Some(property) = PropertyMonitor::step(&mut self.state, pc)? {
+                    debug!("Temporal property violated: {}", property);
+                    return Ok(PathResult::Failure(property));
+                }
+            }
+
+            if track_calls {
+                if let Some(return_address) = self.detect_call(&instruction)? {
+                    let followed = match max_call_depth {
+                        Some(max_call_depth) => self.track_call(return_address, max_call_depth)?,
+                        None => true,
+                    };
+                    if followed && track_effects {
+                        self.push_effect_frame(return_address)?;
+                    }
+                    if followed && stack_locals {
+                        self.state.push_stack_frame(return_address);
+                    }
+                }
+            }
+
+            self.predict_branch(&mut instruction);
+            let cycles = match instruction.max_cycle {
+                CycleCount::Value(v) => v,
+                CycleCount::Function(f) => f(&self.state),
+            } + stall
+                + self.cache_stall(misses);
+            self.vm.histogram.record(self.state.last_pc, cycles);
+            if let Some(model) = self.project.get_energy_model() {
+                self.state.energy += model.instruction(InstructionClass::of(&instruction), cycles);
+            }
+            let stubbed = instruction
+                .operations
+                .iter()
+                .all(|operation| matches!(operation, Operation::Nop));
+            let semantics = match stubbed {
+                true => Semantics::Stubbed,
+                false => Semantics::Modeled,
+            };
+            self.record_semantics(self.state.last_pc & !0b1, semantics);
+
+            if let Some(co_simulator) = self.project.get_co_simulator() {
+                let retired = RetiredInstruction {
+                    index: self.state.get_instruction_count() - 1,
+                    pc: self.state.last_pc,
+                    cycles,
+                };
+                if let Some(external) = co_simulator.retire(&retired) {
+                    if external != retired {
+                        co_simulator.diverged(&retired, &external);
+                    }
+                }
+            }
 
             self.state.set_last_instruction(instruction);
         }
     }
 
+    /// Takes the snapshots and records the number of constraints of the
+    /// timeline of the path, returns true if the
+    /// path is replayed and has reached the instruction that is reconstructed,
+    /// see [`time_travel`](super::time_travel).
+    fn update_timeline(&mut self) -> bool {
+        let index = self.state.get_instruction_count();
+        let count = self.state.constraints.constraint_count();
+        let Some(timeline) = &mut self.state.timeline else {
+            return false;
+        };
+        if timeline.stops_at(index) {
+            return true;
+        }
+        timeline.record_constraints(index, count);
+        if timeline.needs_snapshot(index, self.project.get_snapshot_interval()) {
+            let mut timeline = self.state.timeline.take().expect("the path has a timeline");
+            timeline.add_snapshot(self.state.clone());
+            self.state.timeline = Some(timeline);
+        }
+        false
+    }
+
+    /// Checks if the state at a branch target is implied by a state explored
+    /// by another path, with the same values, no fewer cycles and a path
+    /// condition that holds whenever this one does. At most
+    /// [`subsumption_budget`](super::RunConfig::subsumption_budget) of the
+    /// most recent candidates are checked, a state that is not subsumed is
+    /// recorded as explored.
+    fn is_subsumed(&mut self) -> Result<bool> {
+        let Some(budget) = self.project.get_subsumption_budget() else {
+            return Ok(false);
+        };
+        if !self.state.get_has_jumped() {
+            return Ok(false);
+        }
+        let values = self.state.values();
+        let cycles = self.state.cycle_count;
+        let mut queries = 0;
+        for (condition, explored_cycles) in self.vm.explored_states(&values).iter().rev() {
+            if cycles > *explored_cycles {
+                continue;
+            }
+            if queries == budget {
+                break;
+            }
+            queries += 1;
+            if !self
+                .state
+                .constraints
+                .is_sat_with_constraint(&condition.not())?
+            {
+                return Ok(true);
+            }
+        }
+        let condition = self.state.constraints.path_condition();
+        self.vm.record_explored(values, condition, cycles);
+        Ok(false)
+    }
+
+    /// Get the boolean argument of the hooked function, true if it is non zero.
+    fn bool_argument(&mut self) -> Result<DExpr> {
+        let register = self.state.argument_register(0);
+        let argument = self.state.get_register(register)?.slice(0, 7);
+        Ok(argument.ne(&self.state.ctx.zero(8)))
+    }
+
+    /// Returns from the hooked function.
+    fn return_from_hook(&mut self) -> Result<()> {
+        let lr = self.state.get_register("LR".to_owned())?;
+        self.state.set_register("PC".to_owned(), lr)?;
+        self.state.last_instruction = None;
+        Ok(())
+    }
+
+    /// Constrains the boolean argument of the hooked function to be true, see
+    /// [`PCHook::Assume`](super::project::PCHook::Assume).
+    ///
+    /// Returns a result if the path should end.
+    fn assume_argument(&mut self) -> Result<Option<PathResult>> {
+        let condition = self.bool_argument()?;
+        self.state.constraints.assert(&condition);
+        if !self.state.constraints.is_sat()? {
+            return Ok(Some(PathResult::AssumptionUnsat));
+        }
+        self.return_from_hook()?;
+        Ok(None)
+    }
+
+    /// Checks that the boolean argument of the hooked function is true, see
+    /// [`PCHook::Assert`](super::project::PCHook::Assert).
+    ///
+    /// If the argument can be both true and false the path is forked, the
+    /// forked path reaches the hook again and fails.
+    ///
+    /// Returns a result if the path should end.
+    fn assert_argument(&mut self, reason: &'static str) -> Result<Option<PathResult>> {
+        let condition = self.bool_argument()?;
+        let can_hold = self.state.constraints.is_sat_with_constraint(&condition)?;
+        if !can_hold {
+            debug!("Assertion failed: {}", reason);
+            self.state.increment_cycle_count();
+            self.state.record_region_laps();
+            return Ok(Some(PathResult::Failure(reason)));
+        }
+        if self
+            .state
+            .constraints
+            .is_sat_with_constraint(&condition.not())?
+        {
+            self.fork(condition.not())?;
+        }
+        self.state.constraints.assert(&condition);
+        self.return_from_hook()?;
+        Ok(None)
+    }
+
+    /// Handles a call to a function that is filtered out of the analysis, see
+    /// [`FunctionFilter`](super::project::FunctionFilter).
+    ///
+    /// Returns a result if the path should end.
+    fn filter_call(&mut self, action: FilterAction) -> Result<Option<PathResult>> {
+        let function = self.state.get_current_function_name()?;
+        let return_address = self.state.get_register("LR".to_owned())?.get_constant();
+        debug!("Call to {:?} is {}", function, action);
+        self.state.filtered_calls.push(FilteredCall {
+            function: function.clone(),
+            action,
+            return_address,
+        });
+        match action {
+            FilterAction::Skip => {
+                let name = format!(
+                    "{}_filtered_return_{}",
+                    function.as_deref().unwrap_or("function"),
+                    self.state.get_instruction_count()
+                );
+                let word_size = self.project.get_word_size();
+                let value = self
+                    .state
+                    .new_symbol(word_size, &name, SymbolOrigin::StubReturn);
+                let register = self.state.return_register();
+                self.state.set_register(register, value)?;
+                self.return_from_hook()?;
+                Ok(None)
+            }
+            FilterAction::Succeed => {
+                self.state.increment_cycle_count();
+                self.state.record_region_laps();
+                Ok(Some(PathResult::Success(None)))
+            }
+        }
+    }
+
+    /// Records how the semantics of the instruction at `pc` were handled, see
+    /// [`SemanticsCoverage`](super::profile::SemanticsCoverage).
+    fn record_semantics(&mut self, pc: u64, semantics: Semantics) {
+        let project = self.project.clone();
+        let architecture = &self.state.architecture;
+        self.vm
+            .coverage
+            .record(pc, semantics, || match project.get_raw_word(pc).ok()? {
+                RawDataWord::Word64(d) => architecture.mnemonic(&d),
+                RawDataWord::Word32(d) => architecture.mnemonic(&d),
+                RawDataWord::Word16(d) => architecture.mnemonic(&d),
+                RawDataWord::Word8(d) => architecture.mnemonic(&d),
+            });
+    }
+
+    /// Havocs the memory regions declared for the program counter, if any, see
+    /// [`MemoryHavoc`](super::project::MemoryHavoc).
+    ///
+    /// Returns a result if the path should end.
+    fn apply_memory_havocs(&mut self) -> Result<Option<PathResult>> {
+        let pc = match self.state.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Ok(None),
+        };
+        let project = self.project.clone();
+        for havoc in project.get_memory_havocs(pc) {
+            debug!(
+                "Havocing {} bytes at {:#X} before {:#X}",
+                havoc.size, havoc.start, pc
+            );
+            let visit = self.state.get_instruction_count();
+            for address in havoc.start..havoc.start + havoc.size {
+                let name = self.state.memory_symbol_name(address);
+                let value =
+                    self.state
+                        .new_symbol(8, &format!("{name}_havoc_{visit}"), SymbolOrigin::Havoc);
+                self.set_memory(value, address, 8)?;
+            }
+            if let Some(assumption) = havoc.assumption {
+                let holds = assumption(&mut self.state)?;
+                self.state.constraints.assert(&holds);
+                if !self.state.constraints.is_sat()? {
+                    return Ok(Some(PathResult::AssumptionUnsat));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Verifies the invariant of the loop with the header at the program
+    /// counter, if any, see [`LoopInvariant`](super::project::LoopInvariant).
+    ///
+    /// Returns a result if the path should end.
+    fn apply_loop_invariant(&mut self) -> Result<Option<PathResult>> {
+        let pc = match self.state.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Ok(None),
+        };
+        let project = self.project.clone();
+        let invariant = match project.get_loop_invariant(pc) {
+            Some(invariant) => invariant,
+            None => return Ok(None),
+        };
+
+        let holds = (invariant.invariant)(&mut self.state)?;
+        let violated = self
+            .state
+            .constraints
+            .is_sat_with_constraint(&holds.not())?;
+        if let Some(havoced) = self.state.loop_havocs.remove(&pc) {
+            if violated {
+                return Ok(Some(PathResult::Failure("loop invariant is not inductive")));
+            }
+            // The havoc is only sound if the loop does not modify any other
+            // registers or memory.
+            if havoced.wrote_other_memory {
+                return Ok(Some(PathResult::Failure(
+                    "loop modifies memory that is not havoced",
+                )));
+            }
+            for (register, value) in &self.state.registers {
+                if register == "PC" || invariant.registers.contains(register) {
+                    continue;
+                }
+                if let Some(before) = havoced.registers.get(register) {
+                    if !self.state.constraints.must_be_equal(before, value)? {
+                        return Ok(Some(PathResult::Failure(
+                            "loop modifies a register that is not havoced",
+                        )));
+                    }
+                }
+            }
+            debug!("Loop invariant at {:#X} is inductive", pc);
+            return Ok(Some(PathResult::Suppress));
+        }
+        if violated {
+            return Ok(Some(PathResult::Failure(
+                "loop invariant does not hold on entry",
+            )));
+        }
+
+        debug!("Havocing loop at {:#X}", pc);
+        let word_size = self.project.get_word_size();
+        for register in &invariant.registers {
+            let value = self.state.new_symbol(
+                word_size,
+                &format!("{register}_loop_{pc:#X}"),
+                SymbolOrigin::Havoc,
+            );
+            self.state.registers.insert(register.to_owned(), value);
+        }
+        for (start, len) in &invariant.memory {
+            for address in *start..*start + *len {
+                let value = self.state.new_symbol(
+                    8,
+                    &format!("mem_{address:#X}_loop_{pc:#X}"),
+                    SymbolOrigin::Havoc,
+                );
+                self.set_memory(value, address, 8)?;
+            }
+        }
+        // The flags at the header depend on the iteration.
+        for flag in ["N", "Z", "C", "V"] {
+            let value = self.state.new_symbol(
+                1,
+                &format!("flags.{flag}_loop_{pc:#X}"),
+                SymbolOrigin::Havoc,
+            );
+            self.state.set_flag(flag.to_owned(), value)?;
+        }
+        let holds = (invariant.invariant)(&mut self.state)?;
+        self.state.constraints.assert(&holds);
+        self.state.loop_havocs.insert(pc, LoopHavoc {
+            registers: self.state.registers.clone(),
+            wrote_other_memory: false,
+        });
+        Ok(None)
+    }
+
+    /// Handles an instruction that is defined as unpredictable according to the
+    /// configured [`UnpredictablePolicy`].
+    ///
+    /// The instruction is not decoded, so its destination is not known and
+    /// [`UnpredictablePolicy::Havoc`] makes every register except the program
+    /// counter and stack pointer and every flag unconstrained.
+    ///
+    /// Returns a result if the path should end.
+    fn handle_unpredictable(&mut self) -> Result<Option<PathResult>> {
+        let policy = self.project.get_unpredictable_policy();
+        let pc = match self.state.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Err(GAError::SymbolicProgramCounter),
+        };
+        let semantics = match policy {
+            UnpredictablePolicy::Nop => Semantics::Stubbed,
+            UnpredictablePolicy::Havoc => Semantics::Havoced,
+            UnpredictablePolicy::FailPath => Semantics::Unsupported,
+        };
+        self.record_semantics(pc, semantics);
+        if policy == UnpredictablePolicy::FailPath {
+            return Ok(Some(PathResult::Failure("unpredictable instruction")));
+        }
+
+        let ptr_size = self.project.get_ptr_size();
+        let size = match self.project.get_raw_word(pc)? {
+            RawDataWord::Word64(d) => self.state.architecture.instruction_size(&d),
+            RawDataWord::Word32(d) => self.state.architecture.instruction_size(&d),
+            RawDataWord::Word16(d) => self.state.architecture.instruction_size(&d),
+            RawDataWord::Word8(d) => self.state.architecture.instruction_size(&d),
+        };
+        let size = match size {
+            Some(size) => size,
+            None => return Ok(Some(PathResult::Failure("unpredictable instruction"))),
+        };
+        warn!(
+            "Unpredictable instruction at {:#X}, applying {:?}",
+            pc, policy
+        );
+
+        if policy == UnpredictablePolicy::Havoc {
+            let word_size = self.project.get_word_size();
+            let registers: Vec<String> = self
+                .state
+                .registers
+                .keys()
+                .filter(|register| *register != "PC" && *register != "SP")
+                .cloned()
+                .collect();
+            for register in registers {
+                let value = self.state.new_symbol(
+                    word_size,
+                    &format!("havoc_{register}_{pc:#X}"),
+                    SymbolOrigin::Havoc,
+                );
+                self.state.registers.insert(register, value);
+            }
+            for flag in self.state.architecture.flags().unwrap_or_default() {
+                let value =
+                    self.state
+                        .new_symbol(1, &format!("havoc_{flag}_{pc:#X}"), SymbolOrigin::Havoc);
+                self.state.set_flag((*flag).to_owned(), value)?;
+            }
+        }
+
+        let next_pc = self.state.ctx.from_u64(pc + (size / 8) as u64, ptr_size);
+        self.state.set_register("PC".to_owned(), next_pc)?;
+        self.state.last_instruction = None;
+        Ok(None)
+    }
+
+    /// Detects if the last executed instruction was a call and returns the
+    /// return address of the call.
+    ///
+    /// A call is an instruction that jumps and writes the address of the next
+    /// instruction to the link register, as a branch with link does. The link
+    /// register may already hold the address, such as when a loop calls a
+    /// function from the same call site.
+    fn detect_call(&mut self, instruction: &Instruction<A>) -> Result<Option<u64>> {
+        if !self.state.get_has_jumped() {
+            return Ok(None);
+        }
+        let link = Port::Register("LR".to_owned());
+        if !Hazards::of(&instruction.operations).writes.contains(&link) {
+            return Ok(None);
+        }
+
+        let return_address = self.state.last_pc + (instruction.instruction_size / 8) as u64;
+        let lr = self.state.get_register("LR".to_owned())?;
+        let is_call = match lr.get_constant() {
+            Some(lr_value) => lr_value & !0b1 == return_address,
+            None => false,
+        };
+        Ok(match is_call {
+            true => Some(return_address),
+            false => None,
+        })
+    }
+
+    /// Keeps track of the call depth.
+    ///
+    /// Calls beyond `max_call_depth` are stubbed by returning immediately with
+    /// an unconstrained return value. The other registers the calling
+    /// convention lets a call change and the flags are unconstrained as well.
+    /// Returns false if the call was stubbed.
+    fn track_call(&mut self, return_address: u64, max_call_depth: usize) -> Result<bool> {
+        if self.state.call_stack.len() < max_call_depth {
+            self.state.call_stack.push(return_address);
+            return Ok(true);
+        }
+
+        debug!(
+            "Call depth {} reached, stubbing call at {:#X}",
+            max_call_depth, self.state.last_pc
+        );
+        let callee = self.state.get_register("PC".to_owned())?.get_constant();
+        let callee = callee
+            .and_then(|callee| self.state.function_name(callee))
+            .unwrap_or_else(|| "stub".to_owned());
+        let call = self.state.last_pc;
+        let word_size = self.project.get_word_size();
+        let lr = self.state.get_register("LR".to_owned())?;
+        let return_register = self.state.return_register();
+        let caller_saved = self
+            .state
+            .architecture
+            .caller_saved_registers()
+            .unwrap_or_default();
+        for register in caller_saved {
+            if *register == "LR" || *register == return_register {
+                continue;
+            }
+            let value = self.state.new_symbol(
+                word_size,
+                &format!("{callee}_{register}_{call:#X}"),
+                SymbolOrigin::StubReturn,
+            );
+            self.state.set_register((*register).to_owned(), value)?;
+        }
+        for flag in self.state.architecture.flags().unwrap_or_default() {
+            let value = self.state.new_symbol(
+                1,
+                &format!("{callee}_{flag}_{call:#X}"),
+                SymbolOrigin::StubReturn,
+            );
+            self.state.set_flag((*flag).to_owned(), value)?;
+        }
+        let return_value = self.state.new_symbol(
+            word_size,
+            &format!("{callee}_return_{call:#X}"),
+            SymbolOrigin::StubReturn,
+        );
+        self.state.set_register(return_register, return_value)?;
+        self.state.set_register("PC".to_owned(), lr)?;
+        Ok(false)
+    }
+
+    /// Starts recording the side effects of the function that was just
+    /// called.
+    fn push_effect_frame(&mut self, return_address: u64) -> Result<()> {
+        if let Some(function) = self.state.get_current_function_name()? {
+            self.state.effect_frames.push(EffectFrame {
+                function,
+                return_address,
+                registers: self.state.registers.clone(),
+                memory: BTreeSet::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the registers, other than the program counter, that may hold a
+    /// different value than in `before`.
+    fn changed_registers(&mut self, before: &HashMap<String, DExpr>) -> Result<BTreeSet<String>> {
+        let mut changed = BTreeSet::new();
+        for (register, value) in &self.state.registers {
+            if register == "PC" {
+                continue;
+            }
+            // Registers that are first read after `before` start out as a symbol
+            // named after the register.
+            let old = before.get(register).or_else(|| {
+                self.state
+                    .marked_symbolic
+                    .iter()
+                    .find(|variable| variable.name.as_deref() == Some(register.as_str()))
+                    .map(|variable| &variable.value)
+            });
+            let is_changed = match old {
+                Some(old) => !self.state.constraints.must_be_equal(old, value)?,
+                None => true,
+            };
+            if is_changed {
+                changed.insert(register.to_owned());
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Records the side effects of a function that returned.
+    fn record_side_effects(&mut self, frame: EffectFrame) -> Result<()> {
+        let effects = FunctionEffects {
+            registers: self.changed_registers(&frame.registers)?,
+            memory: frame.memory,
+        };
+        trace!(
+            "{} returned with side effects {:?}",
+            frame.function,
+            effects
+        );
+        // The side effects of a call are side effects of the caller as well.
+        if let Some(caller) = self.state.effect_frames.last_mut() {
+            caller.memory.extend(effects.memory.iter().copied());
+        }
+        self.vm.side_effects.record(&frame.function, &effects);
+        Ok(())
+    }
+
+    /// Records the side effects of a stub that replaced `function`.
+    ///
+    /// Only bytes written through a concrete address that were not written
+    /// before the stub ran are known to be written by the stub.
+    fn record_stub_effects(
+        &mut self,
+        function: &str,
+        registers: &HashMap<String, DExpr>,
+        memory: &BTreeSet<u64>,
+    ) -> Result<()> {
+        let effects = FunctionEffects {
+            registers: self.changed_registers(registers)?,
+            memory: self
+                .state
+                .memory
+                .written_addresses()
+                .difference(memory)
+                .copied()
+                .collect(),
+        };
+        if let Some(caller) = self.state.effect_frames.last_mut() {
+            caller.memory.extend(effects.memory.iter().copied());
+        }
+        self.vm.stub_effects.record(function, &effects);
+        Ok(())
+    }
+
     // Fork execution. Will create a new path with `constraint`.
-    fn fork(&mut self, constraint: DExpr) -> Result<()> {
+    pub(super) fn fork(&mut self, constraint: DExpr) -> Result<()> {
         trace!("Save backtracking path: constraint={:?}", constraint);
-        let forked_state = self.state.clone();
+        let mut forked_state = self.state.clone();
+        // Only the current path follows the recorded trace, the forked path is a
+        // divergence point.
+        forked_state.trace_position = None;
         let path = Path::new(forked_state, Some(constraint));
 
         self.vm.paths.save_path(path);
         Ok(())
     }
 
+    /// Forks the path if `condition` can be both true and false. The current
+    /// path continues with `condition` true and the forked path executes the
+    /// current operation again with it false.
+    fn fork_on_condition(
+        &mut self,
+        condition: DExpr,
+        local: &HashMap<String, DExpr>,
+    ) -> Result<()> {
+        let Some(instruction) = self.state.current_instruction.clone() else {
+            return Ok(());
+        };
+        if condition.get_constant().is_some()
+            || !self.state.constraints.is_sat_with_constraint(&condition)?
+            || !self
+                .state
+                .constraints
+                .is_sat_with_constraint(&condition.not())?
+        {
+            return Ok(());
+        }
+        self.state.continue_in_instruction = Some(ContinueInsideInstruction {
+            instruction,
+            index: self.current_operation_index,
+            local: local.to_owned(),
+        });
+        self.fork(condition.not())?;
+        self.state.continue_in_instruction = None;
+        self.state.constraints.assert(&condition);
+        Ok(())
+    }
+
     /// Creates smt expression from a dataword.
     fn get_dexpr_from_dataword(&mut self, data: DataWord) -> DExpr {
         match data {
@@ -131,11 +942,181 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
 
     /// Retrieves a smt expression representing value stored at `address` in
     /// memory.
-    fn get_memory(&mut self, address: u64, bits: u32) -> Result<DExpr> {
+    pub(crate) fn get_memory(&mut self, address: u64, bits: u32) -> Result<DExpr> {
+        let modeled = match self.access_nvic(AccessKind::Read, address, bits, None)? {
+            Some(value) => Some(value),
+            None => self.access_peripheral(AccessKind::Read, address, bits, None)?,
+        };
+        let value = match modeled {
+            Some(value) => {
+                self.state.summaries.invalidate();
+                value
+            }
+            None if self.project.get_memory_read_hook(address).is_some() => {
+                self.read_memory(address, bits)?
+            }
+            None => {
+                let value = self.read_memory(address, bits)?;
+                self.read_loop_fills(address, bits, value)
+            }
+        };
+        if self.state.summaries.is_recording() {
+            self.record_summary_read(address, bits);
+        }
+        if self.state.copy_loops.is_observing() {
+            self.state.copy_loops.load(bits, &value);
+        }
+        self.record_mmio(AccessKind::Read, address, bits, &value);
+        self.record_interrupt_access(address, bits, false)?;
+        self.check_resource_access(address, bits, false);
+        self.add_access_energy(address, bits);
+        self.access_data_cache(address, bits);
+        Ok(value)
+    }
+
+    /// Checks if the watchdog has expired on this path, see
+    /// [`watchdog`](super::watchdog).
+    fn watchdog_expired(&self) -> bool {
+        let expired = self
+            .project
+            .get_watchdog()
+            .is_some_and(|watchdog| watchdog.expired(&self.state));
+        if expired {
+            debug!("The watchdog expired at cycle {}", self.state.cycle_count);
+        }
+        expired
+    }
+
+    /// Get the reason a path that ends successfully fails anyway, a temporal
+    /// property that is not satisfied or an expired watchdog.
+    pub(crate) fn unsatisfied_at_end(&self) -> Option<&'static str> {
+        if let Some(property) = self.state.properties.unsatisfied(&self.project) {
+            debug!("Temporal property not satisfied at the end: {}", property);
+            return Some(property);
+        }
+        self.watchdog_expired().then_some(WATCHDOG_EXPIRED)
+    }
+
+    /// Records an access to a memory mapped peripheral, see
+    /// [`mmio`](super::mmio).
+    fn record_mmio(&mut self, kind: AccessKind, address: u64, bits: u32, value: &DExpr) {
+        if self.project.address_in_mmio_region(address) {
+            self.state.mmio_log.push(MmioAccess {
+                kind,
+                address,
+                bits,
+                value: value.clone(),
+                pc: self.state.last_pc,
+                instruction: self.state.get_instruction_count(),
+            });
+        }
+    }
+
+    /// Adds the energy of a data memory access, see [`energy`](super::energy).
+    fn add_access_energy(&mut self, address: u64, bits: u32) {
+        if let Some(model) = self.project.get_energy_model() {
+            self.state.energy += model.memory_access(address, bits);
+            if self.project.address_in_mmio_region(address) {
+                self.state.energy += model.peripheral_access(address);
+            }
+        }
+    }
+
+    /// Replaces the cycles of a conditional branch with the cycles decided by
+    /// the branch predictor, see [`branch_predictor`](super::branch_predictor).
+    fn predict_branch(&mut self, instruction: &mut Instruction<A>) {
+        if let Some(predictor) = self.project.get_branch_predictor() {
+            if is_conditional_branch(instruction) {
+                let taken = self.state.get_has_jumped();
+                let predicted = self.state.branch_predictions.update(
+                    predictor,
+                    self.state.last_pc & !0b1,
+                    taken,
+                );
+                instruction.max_cycle = CycleCount::Value(predictor.cycles(taken, predicted));
+            }
+        }
+    }
+
+    /// Adds the cycles `instruction` waits for the result of the previous
+    /// instruction to the cycle count, see [`pipeline`](super::pipeline).
+    fn pipeline_stall(&mut self, instruction: &Instruction<A>) -> usize {
+        let stall = match (self.project.get_pipeline(), &self.state.last_instruction) {
+            (Some(pipeline), Some(last)) => pipeline.stall(&last.hazards(), &instruction.hazards()),
+            _ => 0,
+        };
+        if self.state.count_cycles {
+            self.state.cycle_count += stall;
+        }
+        stall
+    }
+
+    /// Get the number of misses in the instruction and data caches on this
+    /// path.
+    fn cache_misses(&self) -> (usize, usize) {
+        (
+            self.state.instruction_cache.stats().misses,
+            self.state.data_cache.stats().misses,
+        )
+    }
+
+    /// Looks up the fetch of `instruction` in the instruction cache, see
+    /// [`cache`](super::cache).
+    fn fetch_through_cache(&mut self, instruction: &Instruction<A>) {
+        if let Some(config) = self.project.get_instruction_cache() {
+            let bytes = (instruction.instruction_size as u64).div_ceil(8);
+            self.state
+                .instruction_cache
+                .access(config, self.state.last_pc & !0b1, bytes);
+        }
+    }
+
+    /// Looks up a data memory access in the data cache, accesses to
+    /// peripherals are not cached.
+    fn access_data_cache(&mut self, address: u64, bits: u32) {
+        if let Some(config) = self.project.get_data_cache() {
+            if !self.project.address_in_mmio_region(address) {
+                self.state
+                    .data_cache
+                    .access(config, address, (bits as u64).div_ceil(8));
+            }
+        }
+    }
+
+    /// Adds the penalty of the cache misses since `misses` was taken with
+    /// [`Self::cache_misses`] to the cycle count, returns the added cycles.
+    fn cache_stall(&mut self, (instruction, data): (usize, usize)) -> usize {
+        let (instruction_now, data_now) = self.cache_misses();
+        let penalty = |config: Option<&CacheConfig>| config.map_or(0, |config| config.miss_penalty);
+        let stall = (instruction_now - instruction) * penalty(self.project.get_instruction_cache())
+            + (data_now - data) * penalty(self.project.get_data_cache());
+        if self.state.count_cycles {
+            self.state.cycle_count += stall;
+        }
+        stall
+    }
+
+    /// Reads the value stored at `address` from the hooks, the program or the
+    /// symbolic memory.
+    fn read_memory(&mut self, address: u64, bits: u32) -> Result<DExpr> {
         trace!("Getting memory addr: {:?}", address);
+        if self.state.data_flow.is_recording() {
+            for byte in address..address + (bits as u64).div_ceil(8) {
+                self.state.data_flow.read(Location::Memory(byte));
+            }
+        }
         // check for hook and return early
         if let Some(hook) = self.project.get_memory_read_hook(address) {
-            return hook(&mut self.state, address);
+            self.state.summaries.invalidate();
+            let project = self.project.clone();
+            return self.state.run_hook(
+                || project.get_memory_read_hook_handle(address),
+                |state| hook(state, address),
+            );
+        }
+
+        if self.project.address_in_backed_region(address) {
+            return self.get_backed_memory(address, bits);
         }
 
         if self.project.address_in_range(address) {
@@ -155,6 +1136,10 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 todo!()
             }
         } else {
+            let policy = self.project.get_unknown_memory_policy(address);
+            if policy != UnknownMemoryPolicy::Unconstrained {
+                return self.get_initialized_memory(address, bits, policy);
+            }
             let symbolic_address = self
                 .state
                 .ctx
@@ -164,15 +1149,165 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
         }
     }
 
+    /// Retrieves the value stored at `address`, the bytes that have not been
+    /// written on this path get their value from `policy`.
+    fn get_initialized_memory(
+        &mut self,
+        address: u64,
+        bits: u32,
+        policy: UnknownMemoryPolicy,
+    ) -> Result<DExpr> {
+        let mut initial = vec![];
+        for byte_address in address..address + (bits as u64).div_ceil(8) {
+            let written = self
+                .state
+                .memory
+                .written_addresses()
+                .contains(&byte_address);
+            initial.push(match policy {
+                _ if written => None,
+                UnknownMemoryPolicy::Unconstrained => None,
+                UnknownMemoryPolicy::Zero => Some(self.state.ctx.zero(8)),
+                UnknownMemoryPolicy::Fill(byte) => Some(self.state.ctx.from_u64(byte as u64, 8)),
+                UnknownMemoryPolicy::Fresh => {
+                    let name = self.state.memory_symbol_name(byte_address);
+                    Some(
+                        self.state
+                            .new_symbol(8, &name, SymbolOrigin::MemoryRead(byte_address)),
+                    )
+                }
+                UnknownMemoryPolicy::Error if self.state.memory.symbolic_writes().is_empty() => {
+                    return Err(GAError::UninitializedMemory(byte_address));
+                }
+                UnknownMemoryPolicy::Error => None,
+            });
+        }
+        Ok(self
+            .state
+            .memory
+            .read_or_initial(address, &initial)
+            .resize_unsigned(bits)
+            .simplify())
+    }
+
+    /// Retrieves the value stored at `address` in a backed region, bytes
+    /// written on this path are read from the symbolic overlay.
+    fn get_backed_memory(&mut self, address: u64, bits: u32) -> Result<DExpr> {
+        let ptr_size = self.project.get_ptr_size();
+        let addresses = (0..(bits as u64).div_ceil(8)).map(|offset| address + offset);
+        let mut value: Option<DExpr> = None;
+        for byte_address in addresses {
+            let byte = match self.project.get_backed_byte(byte_address) {
+                Some(byte) if !self.state.backed_overlay.contains(&byte_address) => {
+                    self.state.ctx.from_u64(byte as u64, 8)
+                }
+                _ => {
+                    let symbolic_address = self.state.ctx.from_u64(byte_address, ptr_size);
+                    self.state.memory.read(&symbolic_address, 8)?
+                }
+            };
+            value = Some(match (value, self.project.get_endianness()) {
+                (None, _) => byte,
+                (Some(value), Endianness::Little) => byte.concat(&value),
+                (Some(value), Endianness::Big) => value.concat(&byte),
+            });
+        }
+        Ok(value
+            .expect("memory accesses are at least one byte")
+            .resize_unsigned(bits)
+            .simplify())
+    }
+
     /// Sets the memory at `address` to `data`.
-    fn set_memory(&mut self, data: DExpr, address: u64, bits: u32) -> Result<()> {
+    pub(crate) fn set_memory(&mut self, data: DExpr, address: u64, bits: u32) -> Result<()> {
         trace!("Setting memory addr: {:?}", address);
+        self.record_mmio(AccessKind::Write, address, bits, &data);
+        self.record_interrupt_access(address, bits, true)?;
+        self.check_resource_access(address, bits, true);
+        self.add_access_energy(address, bits);
+        self.access_data_cache(address, bits);
+        if !self.project.get_properties().is_empty() {
+            self.state.properties.record_write(address, bits);
+        }
+        if let Some(watchdog) = self.project.get_watchdog() {
+            if watchdog.is_refresh(&self.state, address, &data)? {
+                self.state.watchdog_refresh = self.state.cycle_count;
+            }
+        }
+        if self.state.data_flow.is_recording() {
+            for byte in address..address + (bits as u64).div_ceil(8) {
+                self.state.data_flow.write(Location::Memory(byte));
+            }
+        }
+        if let Some(frame) = self.state.effect_frames.last_mut() {
+            frame
+                .memory
+                .extend(address..address + (bits as u64).div_ceil(8));
+        }
+        if self.state.summaries.is_recording() {
+            for byte in address..address + (bits as u64).div_ceil(8) {
+                self.state.summaries.write(Location::Memory(byte));
+            }
+        }
+        if self.state.copy_loops.is_observing() {
+            self.state.copy_loops.store(bits, &data);
+        }
+        if self.project.get_stack_locals() {
+            self.state
+                .name_stack_locals(address, (bits as u64).div_ceil(8));
+        }
+        for acceleration in self.state.loop_accelerations.values_mut() {
+            acceleration.record_store(address, bits, &data);
+        }
+        for fill in self.state.loop_fills.iter_mut() {
+            fill.overwrite(address, (bits as u64).div_ceil(8));
+        }
+        let end = address + (bits as u64).div_ceil(8);
+        for (header, havoc) in self.state.loop_havocs.iter_mut() {
+            let havoced = self
+                .project
+                .get_loop_invariant(*header)
+                .is_some_and(|invariant| {
+                    invariant
+                        .memory
+                        .iter()
+                        .any(|(start, len)| address >= *start && end <= start + len)
+                });
+            if !havoced {
+                havoc.wrote_other_memory = true;
+            }
+        }
+
+        if self
+            .access_nvic(AccessKind::Write, address, bits, Some(&data))?
+            .is_some()
+            || self
+                .access_peripheral(AccessKind::Write, address, bits, Some(&data))?
+                .is_some()
+        {
+            self.state.summaries.invalidate();
+            return Ok(());
+        }
+
         // check for hook and return early
         if let Some(hook) = self.project.get_memory_write_hook(address) {
-            return hook(&mut self.state, address, data, bits);
+            self.state.summaries.invalidate();
+            let project = self.project.clone();
+            return self.state.run_hook(
+                || project.get_memory_write_hook_handle(address),
+                |state| hook(state, address, data, bits),
+            );
         }
 
-        if self.project.address_in_range(address) {
+        if self.project.address_in_backed_region(address) {
+            // Writes go to the symbolic overlay of this path.
+            for offset in 0..(bits as u64).div_ceil(8) {
+                self.state.backed_overlay.insert(address + offset);
+            }
+        }
+
+        if self.project.address_in_range(address) && !self.project.address_in_backed_region(address)
+        {
             Err(super::GAError::WritingToStaticMemoryProhibited)
         } else {
             let symbolic_address = self
@@ -213,11 +1348,8 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 self.get_memory(address, *width)
             }
             Operand::Flag(f) => {
-                let value = self.state.get_flag(f.clone());
-                match value {
-                    Some(value) => Ok(value.resize_unsigned(self.project.get_word_size())),
-                    None => todo!(),
-                }
+                let value = self.state.get_flag(f.clone())?;
+                Ok(value.resize_unsigned(self.project.get_word_size()))
             }
         };
 
@@ -234,6 +1366,16 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
         match operand {
             Operand::Register(v) => {
                 trace!("Setting register {} to {:?}", v, value);
+                let value = match v == "PC" {
+                    true => self.resolve_target(value)?,
+                    false => value,
+                };
+                let architecture = self.state.architecture.clone();
+                if let Some(condition) =
+                    architecture.register_write_condition(&self.state, v, &value)
+                {
+                    self.fork_on_condition(condition, local)?;
+                }
                 self.state.set_register(v.to_owned(), value)?
             }
             Operand::Immediate(_) => panic!(), // not prohibited change to error later
@@ -261,8 +1403,107 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 //
                 // Might be a good thing to throw an error here if the value is not 0 or 1.
                 self.state
-                    .set_flag(f.clone(), value.resize_unsigned(1).simplify());
+                    .set_flag(f.clone(), value.resize_unsigned(1).simplify())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the values `expr` can have to fork a path for each of them.
+    ///
+    /// If there are more than [`get_max_forks`](Project::get_max_forks)
+    /// values, the values are prioritized and the rest are dropped, branch
+    /// `targets` at the start of a symbol first and then the lowest values.
+    /// The instruction is then recorded as under-approximated on the path.
+    fn possible_values(&mut self, expr: &DExpr, targets: bool) -> Result<Vec<DExpr>> {
+        let bound = self
+            .project
+            .get_max_forks()
+            .map_or(MAX_VALUES, |max_forks| max_forks.max(MAX_VALUES));
+        let (mut values, more) = match self.state.constraints.get_values(expr, bound)? {
+            Solutions::Exactly(values) => (values, false),
+            Solutions::AtLeast(_) if self.bound_jump_table()? => {
+                match self.state.constraints.get_values(expr, bound)? {
+                    Solutions::Exactly(values) => (values, false),
+                    Solutions::AtLeast(values) => (values, true),
+                }
+            }
+            Solutions::AtLeast(values) => (values, true),
+        };
+        let max_forks = match self.project.get_max_forks() {
+            Some(max_forks) => max_forks,
+            None if more => return Err(SolverError::TooManySolutions.into()),
+            None => return Ok(values),
+        };
+        if more || values.len() > max_forks {
+            warn!(
+                "Too many possible values at {:#X}, following {} of them",
+                self.state.last_pc, max_forks
+            );
+            if self.state.under_approximated.last() != Some(&self.state.last_pc) {
+                self.state.under_approximated.push(self.state.last_pc);
+            }
+            let project = self.project.clone();
+            let starts_symbol = |value: u64| !project.get_symbol_aliases(value & !0b1).is_empty();
+            values.sort_by_key(|value| {
+                let value = value.get_constant().unwrap_or(u64::MAX);
+                (!(targets && starts_symbol(value)), value)
+            });
+            values.truncate(max_forks);
+        }
+        Ok(values)
+    }
+
+    /// Returns [`GAError::TraceDiverged`] if the path follows a trace that
+    /// continues at `destination` but the branch can not be taken.
+    fn check_trace_fall_through(&mut self, destination: &DExpr) -> Result<()> {
+        let Some(expected) = self.state.get_next_trace_address() else {
+            return Ok(());
+        };
+        let found = self
+            .state
+            .get_register("PC".to_owned())?
+            .get_constant()
+            .unwrap_or(self.state.last_pc)
+            & !0b1;
+        let destination = destination.get_constant().map(|dest| dest & !0b1);
+        if destination == Some(expected) && found != expected {
+            return Err(GAError::TraceDiverged { expected, found });
+        }
+        Ok(())
+    }
+
+    /// Resolves a symbolic branch target, forks a path for every other
+    /// possible target.
+    fn resolve_target(&mut self, target: DExpr) -> Result<DExpr> {
+        if target.get_constant().is_some() {
+            return Ok(target);
+        }
+        let targets = self.possible_values(&target, true)?;
+        let (first, rest) = targets.split_first().ok_or(SolverError::Unsat)?;
+        for value in rest {
+            let mut forked_state = self.state.clone();
+            forked_state.set_register("PC".to_owned(), value.clone())?;
+            if let Some(address) = value.get_constant() {
+                forked_state.jump_targets.insert(address & !0b1);
             }
+            forked_state.trace_position = None;
+            let path = Path::new(forked_state, Some(target.eq(value)));
+            self.vm.paths.save_path(path);
+        }
+        self.state.constraints.assert(&target.eq(first));
+        Ok(first.clone())
+    }
+
+    /// Resolves a symbolic value written to PC outside of the operations that
+    /// resolve their targets, such as by a hook or a register write hook.
+    fn resolve_symbolic_pc(&mut self) -> Result<()> {
+        if let Some(target) = self.state.take_symbolic_pc() {
+            let target = self.resolve_target(target)?;
+            if let Some(address) = target.get_constant() {
+                self.state.jump_targets.insert(address & !0b1);
+            }
+            self.state.set_register("PC".to_owned(), target)?;
         }
         Ok(())
     }
@@ -271,13 +1512,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
         match &address.get_constant() {
             Some(addr) => Ok(*addr),
             None => {
-                // find all possible addresses
-                let addresses = self.state.constraints.get_values(&address, 255)?;
-
-                let addresses = match addresses {
-                    crate::smt::Solutions::Exactly(a) => Ok(a),
-                    crate::smt::Solutions::AtLeast(_) => Err(SolverError::TooManySolutions),
-                }?;
+                let addresses = self.possible_values(&address, false)?;
 
                 if addresses.len() == 1 {
                     return Ok(addresses[0].get_constant().unwrap());
@@ -360,6 +1595,10 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
         // increment instruction count before execution
         // so that forked path count this instruction
         self.state.increment_instruction_count();
+        if self.project.get_slice_failures() {
+            let instruction = self.state.get_instruction_count();
+            self.state.data_flow.begin(self.state.last_pc, instruction);
+        }
 
         self.state.current_instruction = Some(i.to_owned());
 
@@ -390,6 +1629,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 self.execute_operation(operation, &mut local)?;
             }
         }
+        self.state.data_flow.end();
 
         Ok(())
     }
@@ -401,7 +1641,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
         operation: &Operation,
         local: &mut HashMap<String, DExpr>,
     ) -> Result<()> {
-        trace!("Executing operation: {:?}", operation);
+        trace!("Executing operation: {}", operation);
         match operation {
             Operation::Nop => (), // nop so do nothing
             Operation::Move {
@@ -520,8 +1760,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                         ret.or(&self
                             .state
                             // Set the carry bit right above the last bit
-                            .get_flag("C".to_owned())
-                            .unwrap()
+                            .get_flag("C".to_owned())?
                             .sll(&shift_amount.add(&self.state.ctx.from_u64(1, 32))))
                     }
                     Shift::Ror => {
@@ -587,13 +1826,21 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 let dest_value = self.get_operand_value(destination, local)?;
                 let c = self.state.get_expr(condition)?.simplify();
                 trace!("conditional expr: {:?}", c);
+                let is_branch = *condition != Condition::None;
 
                 // if constant just jump
                 if let Some(constant_c) = c.get_constant_bool() {
+                    if is_branch {
+                        self.vm
+                            .branches
+                            .record(self.state.last_pc, constant_c, !constant_c);
+                    }
                     if constant_c {
                         self.state.set_has_jumped();
-                        let destination = dest_value;
+                        let destination = self.resolve_target(dest_value)?;
                         self.state.set_register("PC".to_owned(), destination)?;
+                    } else {
+                        self.check_trace_fall_through(&dest_value)?;
                     }
                     return Ok(());
                 }
@@ -605,6 +1852,11 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                     true_possible,
                     false_possible
                 );
+                if is_branch {
+                    self.vm
+                        .branches
+                        .record(self.state.last_pc, true_possible, false_possible);
+                }
 
                 let destination: DExpr = match (true_possible, false_possible) {
                     (true, true) => {
@@ -629,19 +1881,42 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                                 local: local.to_owned(),
                             });
                         }
-                        self.fork(c.not())?;
-                        self.state.constraints.assert(&c);
-                        self.state.set_has_jumped();
-                        Ok(dest_value)
+
+                        // Follow the direction recorded in the trace, if any.
+                        let follow_jump = match self.state.get_next_trace_address() {
+                            Some(expected) => {
+                                dest_value.get_constant().map(|dest| dest & !0b1) == Some(expected)
+                            }
+                            None => true,
+                        };
+                        if follow_jump {
+                            self.fork(c.not())?;
+                            self.state.constraints.assert(&c);
+                            self.state.set_has_jumped();
+                            Ok(dest_value)
+                        } else {
+                            // Save the jump as the divergence point.
+                            let fall_through = self.state.get_register("PC".to_owned())?;
+                            self.state.set_register("PC".to_owned(), dest_value)?;
+                            self.state.set_has_jumped();
+                            self.fork(c.clone())?;
+                            self.state.reset_has_jumped();
+                            self.state.constraints.assert(&c.not());
+                            Ok(fall_through)
+                        }
                     }
                     (true, false) => {
                         self.state.set_has_jumped();
                         Ok(dest_value)
                     }
-                    (false, true) => Ok(self.state.get_register("PC".to_owned())?), /* safe to assume PC exist */
+                    (false, true) => {
+                        self.check_trace_fall_through(&dest_value)?;
+                        Ok(self.state.get_register("PC".to_owned())?) /* safe to assume PC exist */
+                    }
                     (false, false) => Err(SolverError::Unsat),
                 }?;
 
+                let destination = self.resolve_target(destination)?;
                 self.state.set_register("PC".to_owned(), destination)?;
             }
             Operation::ConditionalExecution { conditions } => {
@@ -654,12 +1929,12 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                     .ctx
                     .from_u64((self.project.get_word_size() - 1) as u64, 32);
                 let result = value.srl(&shift).resize_unsigned(1);
-                self.state.set_flag("N".to_owned(), result);
+                self.state.set_flag("N".to_owned(), result)?;
             }
             Operation::SetZFlag(operand) => {
                 let value = self.get_operand_value(operand, local)?;
                 let result = value.eq(&self.state.ctx.zero(self.project.get_word_size()));
-                self.state.set_flag("Z".to_owned(), result);
+                self.state.set_flag("Z".to_owned(), result)?;
             }
             Operation::SetCFlag {
                 operand1,
@@ -675,7 +1950,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                     (true, true) => {
                         // I do not now if this part is used in any ISA but it is here for
                         // completeness.
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                        let carry_in = self.state.get_flag("C".to_owned())?;
                         let op2 = op2.not();
 
                         // Check for carry on twos complement of op2
@@ -697,14 +1972,14 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                         add_with_carry(&lhs, &rhs, &one, self.project.get_word_size()).carry_out
                     }
                     (false, true) => {
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                        let carry_in = self.state.get_flag("C".to_owned())?;
                         add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size())
                             .carry_out
                     }
                     (false, false) => op1.uaddo(&op2),
                 };
 
-                self.state.set_flag("C".to_owned(), result);
+                self.state.set_flag("C".to_owned(), result)?;
             }
             Operation::SetVFlag {
                 operand1,
@@ -719,7 +1994,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 let result = match (sub, carry) {
                     (true, true) => {
                         // slightly wrong at op2 = 0
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                        let carry_in = self.state.get_flag("C".to_owned())?;
                         let op2 = op2.not().add(&one);
                         add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size()).overflow
                     }
@@ -728,13 +2003,13 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                             .overflow
                     }
                     (false, true) => {
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                        let carry_in = self.state.get_flag("C".to_owned())?;
                         add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size()).overflow
                     }
                     (false, false) => op1.saddo(&op2),
                 };
 
-                self.state.set_flag("V".to_owned(), result);
+                self.state.set_flag("V".to_owned(), result)?;
             }
             Operation::ForEach {
                 operands: _,
@@ -781,8 +2056,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 let op2 = self.get_operand_value(operand2, local)?;
                 let carry = self
                     .state
-                    .get_flag("C".to_owned())
-                    .unwrap()
+                    .get_flag("C".to_owned())?
                     .zero_ext(self.project.get_word_size());
                 let result =
                     add_with_carry(&op1, &op2, &carry, self.project.get_word_size()).result;
@@ -803,7 +2077,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                         self.project.get_word_size() + 1,
                     ))
                     .resize_unsigned(1);
-                self.state.set_flag("C".to_owned(), carry);
+                self.state.set_flag("C".to_owned(), carry)?;
             }
             Operation::SetCFlagSrl { operand, shift } => {
                 let op = self
@@ -815,7 +2089,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                     .zero_ext(1 + self.project.get_word_size());
                 let result = op.srl(&shift);
                 let carry = result.resize_unsigned(1);
-                self.state.set_flag("C".to_owned(), carry);
+                self.state.set_flag("C".to_owned(), carry)?;
             }
             Operation::SetCFlagSra { operand, shift } => {
                 let op = self
@@ -827,7 +2101,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                     .zero_ext(1 + self.project.get_word_size());
                 let result = op.sra(&shift);
                 let carry = result.resize_unsigned(1);
-                self.state.set_flag("C".to_owned(), carry);
+                self.state.set_flag("C".to_owned(), carry)?;
             }
             Operation::SetCFlagRor(operand) => {
                 // this is right for armv6-m but may be wrong for other architectures
@@ -838,14 +2112,14 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 );
                 // result = srl(op, shift) OR sll(op, word_size - shift)
                 let c = result.srl(&word_size_minus_one).resize_unsigned(1);
-                self.state.set_flag("C".to_owned(), c);
+                self.state.set_flag("C".to_owned(), c)?;
             }
             Operation::CountOnes {
                 destination,
                 operand,
             } => {
                 let operand = self.get_operand_value(operand, local)?;
-                let result = count_ones(&operand, self.state.ctx, self.project.get_word_size());
+                let result = count_ones(&operand, &self.state.ctx, self.project.get_word_size());
                 self.set_operand_value(destination, result, local)?;
             }
             Operation::CountZeroes {
@@ -853,7 +2127,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                 operand,
             } => {
                 let operand = self.get_operand_value(operand, local)?;
-                let result = count_zeroes(&operand, self.state.ctx, self.project.get_word_size());
+                let result = count_zeroes(&operand, &self.state.ctx, self.project.get_word_size());
                 self.set_operand_value(destination, result, local)?;
             }
             Operation::CountLeadingOnes {
@@ -862,7 +2136,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
             } => {
                 let operand = self.get_operand_value(operand, local)?;
                 let result =
-                    count_leading_ones(&operand, self.state.ctx, self.project.get_word_size());
+                    count_leading_ones(&operand, &self.state.ctx, self.project.get_word_size());
                 self.set_operand_value(destination, result, local)?;
             }
             Operation::CountLeadingZeroes {
@@ -871,7 +2145,7 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
             } => {
                 let operand = self.get_operand_value(operand, local)?;
                 let result =
-                    count_leading_zeroes(&operand, self.state.ctx, self.project.get_word_size());
+                    count_leading_zeroes(&operand, &self.state.ctx, self.project.get_word_size());
                 self.set_operand_value(destination, result, local)?;
             }
             Operation::BitFieldExtract {
@@ -903,12 +2177,80 @@ impl<'vm, A: Arch> GAExecutor<'vm, A> {
                     .simplify();
                 self.set_operand_value(destination, operand, local)?;
             }
+            Operation::SignedBitFieldExtract {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => {
+                assert!(
+                    start_bit <= stop_bit,
+                    "Tried to extract from {start_bit} until {stop_bit}"
+                );
+                let word_size = self.project.get_word_size();
+                let operand = self.get_operand_value(operand, local)?;
+                let result = operand
+                    .resize_unsigned(word_size)
+                    .slice(*start_bit, *stop_bit)
+                    .sign_ext(word_size)
+                    .simplify();
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::BitFieldInsert {
+                destination,
+                operand,
+                start_bit,
+                stop_bit,
+            } => {
+                assert!(
+                    start_bit <= stop_bit,
+                    "Tried to insert from {start_bit} until {stop_bit}"
+                );
+                let word_size = self.project.get_word_size();
+                let field = self
+                    .get_operand_value(operand, local)?
+                    .resize_unsigned(word_size)
+                    .slice(0, *stop_bit - *start_bit);
+                let result = self
+                    .get_operand_value(destination, local)?
+                    .resize_unsigned(word_size)
+                    .replace_part(*start_bit, field)
+                    .simplify();
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::ReverseBits {
+                destination,
+                operand,
+            } => {
+                let word_size = self.project.get_word_size();
+                let operand = self.get_operand_value(operand, local)?;
+                let operand = operand.resize_unsigned(word_size);
+                // The least significant bit ends up as the most significant bit.
+                let result = (1..word_size)
+                    .fold(operand.slice(0, 0), |result, bit| {
+                        result.concat(&operand.slice(bit, bit))
+                    })
+                    .simplify();
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::RotateRightExtend {
+                destination,
+                operand,
+            } => {
+                let word_size = self.project.get_word_size();
+                let operand = self.get_operand_value(operand, local)?;
+                let carry = self.state.get_flag("C".to_owned())?.resize_unsigned(1);
+                let result = carry
+                    .concat(&operand.resize_unsigned(word_size).slice(1, word_size - 1))
+                    .simplify();
+                self.set_operand_value(destination, result, local)?;
+            }
         }
         Ok(())
     }
 }
 
-fn count_ones(input: &DExpr, ctx: &BoolectorSolverContext, word_size: u32) -> DExpr {
+fn count_ones(input: &DExpr, ctx: &DContext, word_size: u32) -> DExpr {
     let mut count = ctx.from_u64(0, word_size);
     let mask = ctx.from_u64(1, word_size);
     for n in 0..word_size {
@@ -919,7 +2261,7 @@ fn count_ones(input: &DExpr, ctx: &BoolectorSolverContext, word_size: u32) -> DE
     count
 }
 
-fn count_zeroes(input: &DExpr, ctx: &BoolectorSolverContext, word_size: u32) -> DExpr {
+fn count_zeroes(input: &DExpr, ctx: &DContext, word_size: u32) -> DExpr {
     let input = input.not();
     let mut count = ctx.from_u64(0, word_size);
     let mask = ctx.from_u64(1, word_size);
@@ -931,7 +2273,7 @@ fn count_zeroes(input: &DExpr, ctx: &BoolectorSolverContext, word_size: u32) ->
     count
 }
 
-fn count_leading_ones(input: &DExpr, ctx: &BoolectorSolverContext, word_size: u32) -> DExpr {
+fn count_leading_ones(input: &DExpr, ctx: &DContext, word_size: u32) -> DExpr {
     let mut count = ctx.from_u64(0, word_size);
     let mut stop_count_mask = ctx.from_u64(1, word_size);
     let mask = ctx.from_u64(1, word_size);
@@ -944,7 +2286,7 @@ fn count_leading_ones(input: &DExpr, ctx: &BoolectorSolverContext, word_size: u3
     count
 }
 
-fn count_leading_zeroes(input: &DExpr, ctx: &BoolectorSolverContext, word_size: u32) -> DExpr {
+fn count_leading_zeroes(input: &DExpr, ctx: &DContext, word_size: u32) -> DExpr {
     let input = input.not();
     let mut count = ctx.from_u64(0, word_size);
     let mut stop_count_mask = ctx.from_u64(1, word_size);
@@ -981,26 +2323,48 @@ fn add_with_carry(
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashMap;
+    use std::{collections::HashMap, sync::Arc};
 
     use general_assembly::{
         condition::Condition,
         operand::{DataWord, Operand},
         operation::Operation,
     };
+    use regex::Regex;
 
     use super::{count_leading_ones, count_ones, count_zeroes};
     use crate::{
         general_assembly::{
-            arch::arm::v6::ArmV6M,
-            executor::{add_with_carry, count_leading_zeroes, GAExecutor},
+            arch::{arm::v6::ArmV6M, Arch, ExecutionState},
+            executor::{add_with_carry, count_leading_zeroes, GAExecutor, PathResult},
             instruction::{CycleCount, Instruction},
-            project::Project,
-            state::GAState,
+            path_selection::Path,
+            project::{
+                BackedRegion,
+                CallSite,
+                FilterAction,
+                HookHandle,
+                LoopInvariant,
+                MemoryHavoc,
+                PCHook,
+                Project,
+                ProjectError,
+                RegionMetaData,
+            },
+            state::{GAState, HookOrInstruction, SymbolOrigin},
+            temporal::{Event, Formula, PropertyMonitor, TemporalProperty},
             vm::VM,
             Endianness,
+            Entry,
+            GAError,
+            RunConfig,
+            UnknownMemoryPolicy,
+            UnknownMemoryRegion,
+            UnknownRegisterPolicy,
+            UnpredictablePolicy,
             WordSize,
         },
+        memory::SmtMap,
         smt::{DContext, DSolver},
     };
 
@@ -1079,6 +2443,71 @@ mod test {
         assert_eq!(result.get_constant().unwrap(), 3);
     }
 
+    #[test]
+    fn test_bit_field_operations() {
+        let mut vm = setup_test_vm();
+        let project = vm.project.clone();
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+        let r0 = Operand::Register("R0".to_owned());
+        let r1 = Operand::Register("R1".to_owned());
+        let mut run = |operation: Operation, executor: &mut GAExecutor<'_, ArmV6M>| {
+            executor.execute_operation(&operation, &mut local).unwrap();
+            executor
+                .get_operand_value(&r1, &local)
+                .unwrap()
+                .get_constant()
+                .unwrap()
+        };
+
+        executor
+            .set_operand_value(
+                &r0,
+                executor.state.ctx.from_u64(0x0000_0F50, 32),
+                &mut HashMap::new(),
+            )
+            .unwrap();
+        let sbfx = Operation::SignedBitFieldExtract {
+            destination: r1.clone(),
+            operand: r0.clone(),
+            start_bit: 4,
+            stop_bit: 11,
+        };
+        assert_eq!(run(sbfx, &mut executor), 0xFFFF_FFF5);
+
+        executor
+            .set_operand_value(
+                &r1,
+                executor.state.ctx.from_u64(0xFFFF_FFFF, 32),
+                &mut HashMap::new(),
+            )
+            .unwrap();
+        let bfi = Operation::BitFieldInsert {
+            destination: r1.clone(),
+            operand: r0.clone(),
+            start_bit: 8,
+            stop_bit: 15,
+        };
+        assert_eq!(run(bfi, &mut executor), 0xFFFF_50FF);
+
+        let rbit = Operation::ReverseBits {
+            destination: r1.clone(),
+            operand: r0.clone(),
+        };
+        assert_eq!(run(rbit, &mut executor), 0x0AF0_0000);
+
+        executor
+            .state
+            .set_flag("C".to_owned(), executor.state.ctx.from_bool(true))
+            .unwrap();
+        let rrx = Operation::RotateRightExtend {
+            destination: r1.clone(),
+            operand: r0.clone(),
+        };
+        assert_eq!(run(rrx, &mut executor), 0x8000_07A8);
+    }
+
     #[test]
     fn test_add_with_carry() {
         let ctx = DContext::new();
@@ -1118,62 +2547,129 @@ mod test {
         assert!(result.carry_out.get_constant_bool().unwrap());
         assert!(!result.overflow.get_constant_bool().unwrap());
 
-        // signed overflow
-        let result = add_with_carry(&smax, &num16, &zero_bool, 32);
-        assert_eq!(result.result.get_constant().unwrap(), 2147483663);
-        assert!(!result.carry_out.get_constant_bool().unwrap());
-        assert!(result.overflow.get_constant_bool().unwrap());
+        // signed overflow
+        let result = add_with_carry(&smax, &num16, &zero_bool, 32);
+        assert_eq!(result.result.get_constant().unwrap(), 2147483663);
+        assert!(!result.carry_out.get_constant_bool().unwrap());
+        assert!(result.overflow.get_constant_bool().unwrap());
+
+        // signed underflow
+        let result = add_with_carry(&smin, &num16.not(), &one_bool, 32);
+        assert_eq!(result.result.get_constant().unwrap(), 2147483632);
+        assert!(result.carry_out.get_constant_bool().unwrap());
+        assert!(result.overflow.get_constant_bool().unwrap());
+
+        // zero add
+        let result = add_with_carry(&num16, &zero, &zero_bool, 32);
+        assert_eq!(result.result.get_constant().unwrap(), 16);
+        assert!(!result.carry_out.get_constant_bool().unwrap());
+        assert!(!result.overflow.get_constant_bool().unwrap());
+
+        // zero sub
+        let result = add_with_carry(&num16, &zero.not(), &one_bool, 32);
+        assert_eq!(result.result.get_constant().unwrap(), 16);
+        assert!(result.carry_out.get_constant_bool().unwrap());
+        assert!(!result.overflow.get_constant_bool().unwrap());
+    }
+
+    fn setup_test_vm() -> VM<ArmV6M> {
+        // create an empty project
+        let project = Arc::new(Project::test_project());
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        VM::new_with_state(project, state)
+    }
+
+    #[test]
+    fn test_backed_region() {
+        let mut project = Project::test_project();
+        project.add_backed_region(BackedRegion::new(0x6000_0000, vec![1, 2, 3, 4]));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+        let operand_r0 = Operand::Register("R0".to_owned());
+        let word = Operand::Address(DataWord::Word32(0x6000_0000), 32);
+
+        // read from the buffer
+        let operation = Operation::Move {
+            destination: operand_r0.clone(),
+            source: word.clone(),
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0.get_constant().unwrap(), 0x0403_0201);
+
+        // overwrite one byte and read the word again
+        let operation = Operation::Move {
+            destination: Operand::Address(DataWord::Word32(0x6000_0001), 8),
+            source: Operand::Immediate(DataWord::Word8(0xff)),
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+        let operation = Operation::Move {
+            destination: operand_r0.clone(),
+            source: word,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0.get_constant().unwrap(), 0x0403_ff01);
+    }
+
+    #[test]
+    fn test_unknown_memory_policy() {
+        let mut project = Project::test_project();
+        let regions = [
+            (0x0800_0000, UnknownMemoryPolicy::Fill(0xff)),
+            (0x2000_0000, UnknownMemoryPolicy::Zero),
+            (0x4000_0000, UnknownMemoryPolicy::Fresh),
+            (0x5000_0000, UnknownMemoryPolicy::Error),
+        ];
+        for (start, policy) in regions {
+            project.add_unknown_memory_region(UnknownMemoryRegion::new(
+                start,
+                start + 0x100,
+                policy,
+            ));
+        }
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
 
-        // signed underflow
-        let result = add_with_carry(&smin, &num16.not(), &one_bool, 32);
-        assert_eq!(result.result.get_constant().unwrap(), 2147483632);
-        assert!(result.carry_out.get_constant_bool().unwrap());
-        assert!(result.overflow.get_constant_bool().unwrap());
+        let flash = executor.get_memory(0x0800_0000, 32).unwrap();
+        assert_eq!(flash.get_constant(), Some(0xffff_ffff));
 
-        // zero add
-        let result = add_with_carry(&num16, &zero, &zero_bool, 32);
-        assert_eq!(result.result.get_constant().unwrap(), 16);
-        assert!(!result.carry_out.get_constant_bool().unwrap());
-        assert!(!result.overflow.get_constant_bool().unwrap());
+        // Written bytes are read from memory.
+        let value = context.from_u64(0x12, 8);
+        executor.set_memory(value, 0x2000_0001, 8).unwrap();
+        let ram = executor.get_memory(0x2000_0000, 16).unwrap();
+        assert_eq!(ram.get_constant(), Some(0x1200));
 
-        // zero sub
-        let result = add_with_carry(&num16, &zero.not(), &one_bool, 32);
-        assert_eq!(result.result.get_constant().unwrap(), 16);
-        assert!(result.carry_out.get_constant_bool().unwrap());
-        assert!(!result.overflow.get_constant_bool().unwrap());
-    }
+        let first = executor.get_memory(0x4000_0000, 8).unwrap();
+        let second = executor.get_memory(0x4000_0000, 8).unwrap();
+        assert_ne!(first, second);
+        let reads = executor
+            .state
+            .provenance
+            .iter()
+            .filter(|symbol| symbol.origin == SymbolOrigin::MemoryRead(0x4000_0000));
+        assert_eq!(reads.count(), 2);
 
-    fn setup_test_vm() -> VM<ArmV6M> {
-        // create an empty project
-        let project = Box::new(Project::manual_project(
-            vec![],
-            0,
-            0,
-            WordSize::Bit32,
-            Endianness::Little,
-            HashMap::new(),
-            HashMap::new(),
-            HashMap::new(),
-            HashMap::new(),
-            HashMap::new(),
-            vec![],
-            HashMap::new(),
-            vec![],
-        ));
-        let project = Box::leak(project);
-        let context = Box::new(DContext::new());
-        let context = Box::leak(context);
-        let solver = DSolver::new(context);
-        let state =
-            GAState::create_test_state(project, context, solver, 0, u32::MAX as u64, ArmV6M {});
-        let vm = VM::new_with_state(project, state);
-        vm
+        assert_eq!(
+            executor.get_memory(0x5000_0000, 32).err(),
+            Some(GAError::UninitializedMemory(0x5000_0000))
+        );
+        let outside = executor.get_memory(0x6000_0000, 32).unwrap();
+        assert_eq!(outside.get_constant(), None);
     }
 
     #[test]
     fn test_move() {
         let mut vm = setup_test_vm();
-        let project = vm.project;
+        let project = vm.project.clone();
         let mut executor =
             GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
         let mut local = HashMap::new();
@@ -1246,7 +2742,7 @@ mod test {
     #[test]
     fn test_add() {
         let mut vm = setup_test_vm();
-        let project = vm.project;
+        let project = vm.project.clone();
         let mut executor =
             GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
         let mut local = HashMap::new();
@@ -1321,7 +2817,7 @@ mod test {
     #[test]
     fn test_adc() {
         let mut vm = setup_test_vm();
-        let project = vm.project;
+        let project = vm.project.clone();
         let mut executor =
             GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
         let mut local = HashMap::new();
@@ -1335,7 +2831,10 @@ mod test {
         let false_dexpr = executor.state.ctx.from_bool(false);
 
         // test normal add
-        executor.state.set_flag("C".to_owned(), false_dexpr.clone());
+        executor
+            .state
+            .set_flag("C".to_owned(), false_dexpr.clone())
+            .unwrap();
         let operation = Operation::Adc {
             destination: r0.clone(),
             operand1: imm_42.clone(),
@@ -1352,7 +2851,10 @@ mod test {
         assert_eq!(result, 54);
 
         // test add with overflow
-        executor.state.set_flag("C".to_owned(), false_dexpr.clone());
+        executor
+            .state
+            .set_flag("C".to_owned(), false_dexpr.clone())
+            .unwrap();
         let operation = Operation::Adc {
             destination: r0.clone(),
             operand1: imm_umax.clone(),
@@ -1369,7 +2871,10 @@ mod test {
         assert_eq!(result, 11);
 
         // test add with carry in
-        executor.state.set_flag("C".to_owned(), true_dexpr.clone());
+        executor
+            .state
+            .set_flag("C".to_owned(), true_dexpr.clone())
+            .unwrap();
         let operation = Operation::Adc {
             destination: r0.clone(),
             operand1: imm_42.clone(),
@@ -1389,7 +2894,7 @@ mod test {
     #[test]
     fn test_sub() {
         let mut vm = setup_test_vm();
-        let project = vm.project;
+        let project = vm.project.clone();
         let mut executor =
             GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
         let mut local = HashMap::new();
@@ -1464,7 +2969,7 @@ mod test {
     #[test]
     fn test_mul() {
         let mut vm = setup_test_vm();
-        let project = vm.project;
+        let project = vm.project.clone();
         let mut executor =
             GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
         let mut local = HashMap::new();
@@ -1539,7 +3044,7 @@ mod test {
     #[test]
     fn test_set_v_flag() {
         let mut vm = setup_test_vm();
-        let project = vm.project;
+        let project = vm.project.clone();
         let mut executor =
             GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
         let mut local = HashMap::new();
@@ -1604,7 +3109,7 @@ mod test {
     #[test]
     fn test_conditional_execution() {
         let mut vm = setup_test_vm();
-        let project = vm.project;
+        let project = vm.project.clone();
         let mut executor =
             GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
         let imm_0 = Operand::Immediate(DataWord::Word32(0));
@@ -1659,4 +3164,852 @@ mod test {
             .unwrap();
         assert_eq!(r0_value, 1);
     }
+
+    #[test]
+    fn test_loop_invariant() {
+        let mut project = Project::test_project();
+        project.add_loop_invariant(LoopInvariant {
+            header: 0,
+            invariant: |state| {
+                let r0 = state.get_register("R0".to_owned())?;
+                Ok(r0.ulte(&state.ctx.from_u64(10, 32)))
+            },
+            registers: vec!["R0".to_owned()],
+            memory: vec![],
+        });
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        state
+            .set_register("R0".to_owned(), context.from_u64(0, 32))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+        // the invariant holds on entry, R0 is havoced
+        assert!(executor.apply_loop_invariant().unwrap().is_none());
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert!(r0.get_constant().is_none());
+
+        // an iteration that increments R0 while it is below 10 keeps the invariant
+        let ten = context.from_u64(10, 32);
+        executor.state.constraints.assert(&r0.ult(&ten));
+        executor
+            .state
+            .set_register("R0".to_owned(), r0.add(&context.from_u64(1, 32)))
+            .unwrap();
+        assert!(matches!(
+            executor.apply_loop_invariant().unwrap(),
+            Some(PathResult::Suppress)
+        ));
+    }
+
+    #[test]
+    fn test_loop_invariant_memory() {
+        let mut project = Project::test_project();
+        project.add_loop_invariant(LoopInvariant {
+            header: 0,
+            invariant: |state| Ok(state.ctx.from_bool(true)),
+            registers: vec![],
+            memory: vec![(0x2000_0000, 4)],
+        });
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let context = executor.state.ctx.clone();
+
+        // the flags are havoced with the loop
+        assert!(executor.apply_loop_invariant().unwrap().is_none());
+        let z = executor.state.get_flag("Z".to_owned()).unwrap();
+        assert!(z.get_constant().is_none());
+
+        // writes to the havoced memory are allowed
+        executor
+            .set_memory(context.from_u64(1, 32), 0x2000_0000, 32)
+            .unwrap();
+        assert!(matches!(
+            executor.apply_loop_invariant().unwrap(),
+            Some(PathResult::Suppress)
+        ));
+
+        // writes to other memory fail the inductive step
+        assert!(executor.apply_loop_invariant().unwrap().is_none());
+        executor
+            .set_memory(context.from_u64(1, 8), 0x2000_0004, 8)
+            .unwrap();
+        assert!(matches!(
+            executor.apply_loop_invariant().unwrap(),
+            Some(PathResult::Failure(
+                "loop modifies memory that is not havoced"
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_memory_havoc() {
+        let mut project = Project::test_project();
+        project.add_memory_havoc(MemoryHavoc {
+            pc: 0,
+            start: 0x2000_0000,
+            size: 4,
+            assumption: Some(|state| {
+                let address = state.ctx.from_u64(0x2000_0000, 32);
+                let word = state.read_word_from_memory(&address)?;
+                Ok(word.ult(&state.ctx.from_u64(16, 32)))
+            }),
+        });
+        project.add_memory_havoc(MemoryHavoc {
+            pc: 4,
+            start: 0x2000_0000,
+            size: 4,
+            assumption: Some(|state| Ok(state.ctx.from_bool(false))),
+        });
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        state
+            .memory
+            .write(&context.from_u64(0x2000_0000, 32), context.from_u64(0, 32))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+        // the buffer is replaced by fresh symbols
+        assert!(executor.apply_memory_havocs().unwrap().is_none());
+        let address = context.from_u64(0x2000_0000, 32);
+        let word = executor.state.read_word_from_memory(&address).unwrap();
+        assert!(word.get_constant().is_none());
+
+        // an assumption that can not hold ends the path
+        executor
+            .state
+            .set_register("PC".to_owned(), context.from_u64(4, 32))
+            .unwrap();
+        assert!(matches!(
+            executor.apply_memory_havocs().unwrap(),
+            Some(PathResult::AssumptionUnsat)
+        ));
+    }
+
+    #[test]
+    fn test_call_site_hook() {
+        let mut project = Project::test_project();
+        project.add_pc_hook(0x10, PCHook::Continue);
+        project.add_call_site_hook(
+            0x10,
+            CallSite::ReturnAddress(0x100),
+            PCHook::EndFailure("called from init"),
+        );
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x10, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+
+        state
+            .set_register("LR".to_owned(), context.from_u64(0x101, 32))
+            .unwrap();
+        assert!(matches!(
+            state.get_next_instruction().unwrap(),
+            HookOrInstruction::PcHook(PCHook::EndFailure("called from init"))
+        ));
+
+        // other call sites use the PC hook
+        state
+            .set_register("LR".to_owned(), context.from_u64(0x201, 32))
+            .unwrap();
+        assert!(matches!(
+            state.get_next_instruction().unwrap(),
+            HookOrInstruction::PcHook(PCHook::Continue)
+        ));
+    }
+
+    #[test]
+    fn test_hook_removal_and_scopes() {
+        let mut project = Project::<ArmV6M>::test_project();
+        let continue_hook = project.add_pc_hook(0x10, PCHook::Continue);
+        let handle = project.add_call_site_hook(
+            0x10,
+            CallSite::ReturnAddress(0x100),
+            PCHook::EndFailure("called from init"),
+        );
+        assert!(project.get_call_site_hook(0x10, 0x100).is_some());
+        assert!(project.remove_hook(&handle));
+        assert!(!project.remove_hook(&handle));
+        assert!(project.get_call_site_hook(0x10, 0x100).is_none());
+
+        project.with_hooks(|project| {
+            let handle = project.add_pc_hook(0x20, PCHook::Suppress);
+            assert!(project.get_pc_hook(0x20).is_some());
+            assert!(project.remove_hook(&handle));
+            project.remove_hook(&continue_hook);
+            project.add_pc_hook(0x30, PCHook::EndSuccess);
+        });
+        assert!(project.get_pc_hook(0x10).is_some());
+        assert!(project.get_pc_hook(0x30).is_none());
+
+        // A replaced hook runs again when the hook that replaced it is
+        // removed, and removing it does not remove the later hook.
+        let end = project.add_pc_hook(0x10, PCHook::EndSuccess);
+        assert!(matches!(
+            project.get_pc_hook(0x10),
+            Some(PCHook::EndSuccess)
+        ));
+        assert_eq!(project.get_pc_hook_handle(0x10, None), Some(end.clone()));
+        assert!(project.remove_hook(&continue_hook));
+        assert!(matches!(
+            project.get_pc_hook(0x10),
+            Some(PCHook::EndSuccess)
+        ));
+        let suppress = project.add_pc_hook(0x10, PCHook::Suppress);
+        assert!(project.remove_hook(&suppress));
+        assert!(matches!(
+            project.get_pc_hook(0x10),
+            Some(PCHook::EndSuccess)
+        ));
+        assert!(project.remove_hook(&end));
+        assert!(project.get_pc_hook(0x10).is_none());
+        assert_eq!(project.get_pc_hook_handle(0x10, None), None);
+    }
+
+    #[test]
+    fn test_hook_by_name() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.add_symbol("handler", 0x41, 0);
+        let handle = project.add_pc_hook_exact("handler", PCHook::Suppress);
+        assert!(matches!(handle, Ok(HookHandle::Pc { pc: 0x40, .. })));
+        assert!(project.get_pc_hook(0x40).is_some());
+        assert_eq!(
+            project.add_pc_hook_exact("missing", PCHook::Suppress),
+            Err(ProjectError::FunctionNotFound("missing".to_owned()))
+        );
+
+        // Without debug data there are no functions to match.
+        let pattern = Regex::new("^handler$").unwrap();
+        assert_eq!(project.add_pc_hook_regex(&pattern, PCHook::Suppress), 0);
+    }
+
+    #[test]
+    fn test_switch_to_arm_state() {
+        let mut cfg = RunConfig::default();
+        ArmV6M {}.add_hooks(&mut cfg);
+        let project = Project::<ArmV6M>::manual_project(
+            vec![],
+            0,
+            0,
+            WordSize::Bit32,
+            Endianness::Little,
+            HashMap::new(),
+            HashMap::from([(0x200, PCHook::EndSuccess)]),
+            HashMap::new(),
+            cfg.register_write_hooks.into_iter().collect(),
+            HashMap::new(),
+            vec![],
+            HashMap::new(),
+            vec![],
+        );
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        assert_eq!(state.get_execution_state(), ExecutionState::Thumb);
+
+        // An interworking branch to an even address clears the T bit.
+        state
+            .set_register("EPSR.T".to_owned(), context.from_u64(0x100, 32))
+            .unwrap();
+        state
+            .set_register("PC".to_owned(), context.from_u64(0x100, 32))
+            .unwrap();
+        assert_eq!(state.get_execution_state(), ExecutionState::Arm);
+        assert!(matches!(
+            state.get_next_instruction(),
+            Err(GAError::UnsupportedExecutionState(
+                ExecutionState::Arm,
+                0x100
+            ))
+        ));
+
+        // Hooks are not executed as instructions.
+        state
+            .set_register("PC".to_owned(), context.from_u64(0x200, 32))
+            .unwrap();
+        assert!(matches!(
+            state.get_next_instruction(),
+            Ok(HookOrInstruction::PcHook(PCHook::EndSuccess))
+        ));
+
+        state
+            .set_register("EPSR.T".to_owned(), context.from_u64(0x101, 32))
+            .unwrap();
+        assert_eq!(state.get_execution_state(), ExecutionState::Thumb);
+    }
+
+    #[test]
+    fn test_register_history() {
+        let mut project = Project::test_project();
+        project.record_registers(vec!["R0".to_owned()], 2);
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+
+        for value in 1..=3 {
+            state
+                .set_register("R0".to_owned(), context.from_u64(value, 32))
+                .unwrap();
+        }
+        state
+            .set_register("R1".to_owned(), context.from_u64(4, 32))
+            .unwrap();
+
+        let history: Vec<_> = state
+            .register_history
+            .iter()
+            .map(|write| (write.register.as_str(), write.value.get_constant()))
+            .collect();
+        assert_eq!(history, vec![("R0", Some(2)), ("R0", Some(3))]);
+    }
+
+    #[test]
+    fn test_symbol_provenance() {
+        let project = Arc::new(Project::test_project());
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        state.last_pc = 0x40;
+
+        state.get_register("R4".to_owned()).unwrap();
+        state.new_symbol(8, "any0", SymbolOrigin::Intrinsic);
+
+        let provenance: Vec<_> = state
+            .provenance
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.origin.clone(), symbol.pc))
+            .collect();
+        assert_eq!(provenance, vec![
+            ("R4", SymbolOrigin::RegisterInit, 0x40),
+            ("any0", SymbolOrigin::Intrinsic, 0x40),
+        ]);
+    }
+
+    #[test]
+    fn test_max_symbol_size() {
+        let mut project = Project::test_project();
+        project.set_max_symbol_size(Some(2));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+
+        let small = state.new_symbol(16, "small", SymbolOrigin::Intrinsic);
+        assert_eq!((small.len(), small.get_constant()), (16, None));
+        // Only the low two bytes of the buffer are symbolic.
+        let buffer = state.new_symbol(64, "buffer", SymbolOrigin::Intrinsic);
+        assert_eq!(buffer.len(), 64);
+        assert_eq!(buffer.slice(0, 15).get_constant(), None);
+        assert_eq!(buffer.slice(16, 63).get_constant(), Some(0));
+    }
+
+    #[test]
+    fn test_unknown_register_policy() {
+        let mut project = Project::test_project();
+        project.set_unknown_register_policy(UnknownRegisterPolicy::Error);
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+
+        assert!(state.get_register("R4".to_owned()).is_ok());
+        assert_eq!(
+            state.get_register("R44".to_owned()),
+            Err(GAError::UnknownRegister("R44".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_flag() {
+        let project = Arc::new(Project::test_project());
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+
+        // the saturation flag is defined but never set
+        assert!(state.get_flag("Q".to_owned()).is_ok());
+        assert_eq!(
+            state.set_flag("CY".to_owned(), context.from_bool(true)),
+            Err(GAError::UnknownFlag("CY".to_owned()))
+        );
+        assert_eq!(
+            state.get_flag("CY".to_owned()),
+            Err(GAError::UnknownFlag("CY".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_assume_and_assert_hooks() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.add_pc_hook(0x10, PCHook::Assume);
+        project.add_pc_hook(0x20, PCHook::Assert("assertion failed"));
+        project.add_pc_hook(0x100, PCHook::EndSuccess);
+        let project = Arc::new(project);
+
+        let run = |pc: u64, argument: u64| {
+            let mut state = GAState::test_state(project.clone(), pc, u32::MAX as u64, ArmV6M {});
+            let context = state.ctx.clone();
+            state
+                .set_register("LR".to_owned(), context.from_u64(0x100, 32))
+                .unwrap();
+            // only the low byte holds the bool
+            state
+                .set_register("R0".to_owned(), context.from_u64(argument, 32))
+                .unwrap();
+            let mut vm = VM::new_with_state(project.clone(), state);
+            vm.run().unwrap().unwrap().0
+        };
+
+        assert!(matches!(run(0x10, 1), PathResult::Success(None)));
+        assert!(matches!(run(0x10, 0x100), PathResult::AssumptionUnsat));
+        assert!(matches!(run(0x20, 1), PathResult::Success(None)));
+        assert!(matches!(
+            run(0x20, 0),
+            PathResult::Failure("assertion failed")
+        ));
+    }
+
+    #[test]
+    fn test_function_filters() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.add_pc_hook(0x10, PCHook::Filter(FilterAction::Skip));
+        project.add_pc_hook(0x20, PCHook::Filter(FilterAction::Succeed));
+        project.add_pc_hook(0x100, PCHook::EndSuccess);
+        let project = Arc::new(project);
+
+        let run = |pc: u64| {
+            let mut state = GAState::test_state(project.clone(), pc, u32::MAX as u64, ArmV6M {});
+            let context = state.ctx.clone();
+            state
+                .set_register("LR".to_owned(), context.from_u64(0x100, 32))
+                .unwrap();
+            state
+                .set_register("R0".to_owned(), context.from_u64(7, 32))
+                .unwrap();
+            let mut vm = VM::new_with_state(project.clone(), state);
+            vm.run().unwrap().unwrap()
+        };
+
+        // skipped functions return an unconstrained value
+        let (result, mut state) = run(0x10);
+        assert!(matches!(result, PathResult::Success(None)));
+        assert_eq!(state.filtered_calls.len(), 1);
+        assert_eq!(state.filtered_calls[0].action, FilterAction::Skip);
+        assert_eq!(state.filtered_calls[0].return_address, Some(0x100));
+        let r0 = state.get_register("R0".to_owned()).unwrap();
+        assert!(r0.get_constant().is_none());
+
+        let (result, state) = run(0x20);
+        assert!(matches!(result, PathResult::Success(None)));
+        assert_eq!(state.filtered_calls.len(), 1);
+        assert_eq!(state.filtered_calls[0].action, FilterAction::Succeed);
+    }
+
+    #[test]
+    fn test_max_forks() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.set_max_forks(Some(2));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        let target = context.unconstrained(32, "target");
+        let possible = [0x300, 0x100, 0x200]
+            .map(|value| target.eq(&context.from_u64(value, 32)))
+            .into_iter()
+            .reduce(|any, next| any.or(&next))
+            .unwrap();
+        state.constraints.assert(&possible);
+        state.set_register("R0".to_owned(), target).unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+        let branch = Operation::Move {
+            destination: Operand::Register("PC".to_owned()),
+            source: Operand::Register("R0".to_owned()),
+        };
+        executor
+            .execute_operation(&branch, &mut HashMap::new())
+            .unwrap();
+
+        // The lowest targets are followed and the third one is dropped.
+        let pc = executor.state.get_register("PC".to_owned()).unwrap();
+        assert_eq!(pc.get_constant(), Some(0x100));
+        assert_eq!(executor.state.under_approximated, vec![0]);
+        let mut forked = vm.paths.get_path().unwrap().state;
+        let pc = forked.get_register("PC".to_owned()).unwrap();
+        assert_eq!(pc.get_constant(), Some(0x200));
+        assert!(vm.paths.get_path().is_none());
+    }
+
+    #[test]
+    fn test_symbolic_pc_write() {
+        let project = Arc::new(Project::<ArmV6M>::test_project());
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+
+        // A hook returns to a function pointer that is one of two functions.
+        let target = context.unconstrained(32, "target");
+        let possible = target
+            .eq(&context.from_u64(0x100, 32))
+            .or(&target.eq(&context.from_u64(0x200, 32)));
+        executor.state.constraints.assert(&possible);
+        executor
+            .state
+            .set_register("PC".to_owned(), target)
+            .unwrap();
+        executor.resolve_symbolic_pc().unwrap();
+
+        // The path continues at one of the targets and the other is forked, in
+        // the order the solver finds them.
+        let pc = executor.state.get_register("PC".to_owned()).unwrap();
+        let first = pc.get_constant().unwrap();
+        assert!(executor.state.take_symbolic_pc().is_none());
+        let mut forked = vm.paths.get_path().unwrap().state;
+        let pc = forked.get_register("PC".to_owned()).unwrap();
+        let second = pc.get_constant().unwrap();
+        assert!(forked.take_symbolic_pc().is_none());
+        assert!(vm.paths.get_path().is_none());
+        let mut targets = [first, second];
+        targets.sort_unstable();
+        assert_eq!(targets, [0x100, 0x200]);
+    }
+
+    #[test]
+    fn test_deduplicate_states() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.add_pc_hook(0x100, PCHook::EndSuccess);
+        project.set_deduplicate_states(true);
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x100, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        state.set_has_jumped();
+        let mut changed = state.clone();
+        changed
+            .set_register("R0".to_owned(), context.from_u64(1, 32))
+            .unwrap();
+        assert_ne!(state.key(), changed.key());
+
+        // The per path state of the models and monitors is part of the key.
+        let mut refreshed = state.clone();
+        refreshed.watchdog_refresh = 10;
+        assert_ne!(state.key(), refreshed.key());
+        let mut called = state.clone();
+        called.call_stack.push(0x200);
+        assert_ne!(state.key(), called.key());
+        let mut allocated = state.clone();
+        allocated.heap_break = Some(0x2000_0000);
+        assert_ne!(state.key(), allocated.key());
+
+        // Distinct symbols with the same name are different values.
+        let mut first = state.clone();
+        first
+            .set_register("R0".to_owned(), context.unconstrained(32, "x"))
+            .unwrap();
+        let mut second = state.clone();
+        second
+            .set_register("R0".to_owned(), context.unconstrained(32, "x"))
+            .unwrap();
+        assert_eq!(first.key(), first.clone().key());
+        assert_ne!(first.key(), second.key());
+        assert_ne!(first.values(), second.values());
+
+        let mut vm = VM::new_with_state(project, state.clone());
+        vm.paths.save_path(Path::new(state, None));
+        vm.paths.save_path(Path::new(changed, None));
+
+        let mut results = vec![];
+        while let Some((result, _)) = vm.run().unwrap() {
+            results.push(result);
+        }
+        assert!(matches!(results[..], [
+            PathResult::Success(None),
+            PathResult::Success(None),
+            PathResult::Suppress
+        ]));
+        assert_eq!(vm.duplicates, 1);
+    }
+
+    #[test]
+    fn test_subsumption() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.set_subsumption_budget(Some(4));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x100, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        let x = context.unconstrained(32, "x");
+        state.set_register("R0".to_owned(), x.clone()).unwrap();
+        state.set_has_jumped();
+        let mut vm = VM::new_with_state(project.clone(), state.clone());
+
+        let mut subsumed = |bound: u64, cycles: usize| {
+            // Each state is a separate path with constraints of its own.
+            let mut state = state.clone();
+            let count = state.constraints.constraint_count();
+            state.constraints = state.constraints.detach(count);
+            state
+                .constraints
+                .assert(&x.ult(&context.from_u64(bound, 32)));
+            state.cycle_count = cycles;
+            GAExecutor::from_state(state, &mut vm, project.clone())
+                .is_subsumed()
+                .unwrap()
+        };
+        assert!(!subsumed(10, 5));
+        // Implied by the explored state.
+        assert!(subsumed(5, 5));
+        // Takes more cycles than the explored state.
+        assert!(!subsumed(5, 6));
+        // Reaches values the explored states did not.
+        assert!(!subsumed(20, 0));
+        assert!(subsumed(15, 0));
+        assert_eq!(vm.subsumed, 0);
+    }
+
+    #[test]
+    fn test_subsumption_per_path_state() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.set_subsumption_budget(Some(4));
+        project.add_property(TemporalProperty::new(
+            "reaches the end",
+            Formula::Eventually(Event::PcReached(0x100)),
+        ));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x100, u32::MAX as u64, ArmV6M {});
+        state.set_has_jumped();
+        let mut vm = VM::new_with_state(project.clone(), state.clone());
+        let mut subsumed = |state: GAState<ArmV6M>| {
+            GAExecutor::from_state(state, &mut vm, project.clone())
+                .is_subsumed()
+                .unwrap()
+        };
+        assert!(!subsumed(state.clone()));
+
+        // Only the watchdog was refreshed.
+        let mut refreshed = state.clone();
+        refreshed.watchdog_refresh = 10;
+        assert!(!subsumed(refreshed));
+
+        // Only the temporal property is satisfied.
+        let mut satisfied = state.clone();
+        assert_eq!(PropertyMonitor::step(&mut satisfied, 0x100), Ok(None));
+        assert!(satisfied.properties.unsatisfied(&project).is_none());
+        assert!(!subsumed(satisfied));
+
+        assert!(subsumed(state));
+    }
+
+    #[test]
+    fn test_repeated_call() {
+        let mut vm = setup_test_vm();
+        let project = vm.project.clone();
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let word = |value| Operand::Immediate(DataWord::Word32(value));
+        let jump = Operation::ConditionalJump {
+            destination: word(0x200),
+            condition: Condition::None,
+        };
+        let call = Instruction {
+            instruction_size: 32,
+            operations: vec![
+                Operation::Move {
+                    destination: Operand::Register("LR".to_owned()),
+                    source: word(0x105),
+                },
+                jump.clone(),
+            ],
+            max_cycle: CycleCount::Value(1),
+            memory_access: false,
+        };
+        let branch = Instruction {
+            operations: vec![jump],
+            ..call.clone()
+        };
+        let mut execute = |instruction: &Instruction<ArmV6M>| {
+            let pc = executor.state.ctx.from_u64(0x100, 32);
+            executor.state.set_register("PC".to_owned(), pc).unwrap();
+            executor.execute_instruction(instruction).unwrap();
+            executor.detect_call(instruction).unwrap()
+        };
+
+        assert_eq!(execute(&call), Some(0x104));
+        // A branch is not a call even if the link register holds the address
+        // of the next instruction.
+        assert_eq!(execute(&branch), None);
+        // A second call from the same call site.
+        assert_eq!(execute(&call), Some(0x104));
+    }
+
+    #[test]
+    fn test_region_laps() {
+        let mut project = Project::test_project();
+        project.add_region(RegionMetaData {
+            name: "loop".to_owned(),
+            start: 0x100,
+            end: 0x108,
+        });
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut step = |pc: u64, cycles: usize| {
+            let pc = context.from_u64(pc, 32);
+            state.set_register("PC".to_owned(), pc).unwrap();
+            state.cycle_count = cycles;
+            state.record_region_laps();
+            state.cycle_laps.clone()
+        };
+
+        assert!(step(0xFC, 1).is_empty());
+        // Thumb addresses delimit the region with bit 0 set.
+        assert_eq!(step(0x101, 3), vec![(3, "loop".to_owned())]);
+        assert_eq!(step(0x104, 5).len(), 1);
+        assert_eq!(step(0x108, 10), vec![
+            (3, "loop".to_owned()),
+            (10, "loop".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn test_call_depth_stub() {
+        let project = Arc::new(Project::<ArmV6M>::test_project());
+        let mut state = GAState::test_state(project.clone(), 0x200, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        for register in ["R0", "R1", "R2", "R3", "R4", "R12"] {
+            state
+                .set_register(register.to_owned(), context.from_u64(1, 32))
+                .unwrap();
+        }
+        state
+            .set_register("LR".to_owned(), context.from_u64(0x11, 32))
+            .unwrap();
+        state
+            .set_flag("Z".to_owned(), context.from_bool(true))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state.clone());
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        assert!(executor.track_call(0x10, 1).unwrap());
+        assert_eq!(executor.state.call_stack, vec![0x10]);
+
+        // The call beyond the depth returns and may change the caller saved
+        // registers and the flags.
+        assert!(!executor.track_call(0x10, 1).unwrap());
+        let mut value = |register: &str| {
+            executor
+                .state
+                .get_register(register.to_owned())
+                .unwrap()
+                .get_constant()
+        };
+        assert_eq!(value("PC"), Some(0x11));
+        assert_eq!(value("R4"), Some(1));
+        for register in ["R0", "R1", "R2", "R3", "R12"] {
+            assert_eq!(value(register), None);
+        }
+        let z = executor.state.get_flag("Z".to_owned()).unwrap();
+        assert_eq!(z.get_constant(), None);
+    }
+
+    #[test]
+    fn test_entry() {
+        // The vector table holds the initial stack pointer and the reset
+        // handler.
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_program(vec![0x00, 0x10, 0x00, 0x20, 0x41, 0x00, 0x00, 0x00], 0, 8)
+            .with_symbols(HashMap::from([
+                ("__vector_table".to_owned(), 0),
+                ("main".to_owned(), 0x81),
+                ("f".to_owned(), 0x101),
+            ]));
+        assert_eq!(project.get_entry_address("f"), Ok(0x101));
+        assert_eq!(
+            project.get_entry_address("g"),
+            Err(GAError::EntryFunctionNotFound("g".to_owned()))
+        );
+        project.set_entry(Some(Entry::Main));
+        assert_eq!(project.get_entry_address("f"), Ok(0x81));
+        project.set_entry(Some(Entry::Reset));
+        assert_eq!(project.get_entry_address("f"), Ok(0x41));
+
+        // No vector table.
+        let mut project = Project::<ArmV6M>::test_project();
+        project.set_entry(Some(Entry::Reset));
+        assert!(project.get_entry_address("f").is_err());
+    }
+
+    #[test]
+    fn test_unpredictable_policy() {
+        let run = |policy, pc: u64| {
+            // A 16 bit instruction followed by a 32 bit instruction.
+            let mut project = Project::test_project().with_program(
+                vec![0x00, 0xbf, 0x00, 0xbf, 0x00, 0xf0, 0x00, 0x80],
+                0,
+                8,
+            );
+            project.set_unpredictable_policy(policy);
+            let project = Arc::new(project);
+            let mut state = GAState::test_state(project.clone(), pc, 0x2000_1000, ArmV6M {});
+            let context = state.ctx.clone();
+            state
+                .set_register("R0".to_owned(), context.from_u64(1, 32))
+                .unwrap();
+            state
+                .set_flag("Z".to_owned(), context.from_bool(true))
+                .unwrap();
+            let mut vm = VM::new_with_state(project.clone(), state);
+            let state = vm.paths.get_path().unwrap().state;
+            let mut executor = GAExecutor::from_state(state, &mut vm, project);
+            let failure = match executor.handle_unpredictable().unwrap() {
+                Some(PathResult::Failure(reason)) => Some(reason),
+                Some(_) => panic!("the path ends without failing"),
+                None => None,
+            };
+            let pc = executor.state.get_register("PC".to_owned()).unwrap();
+            let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+            let z = executor.state.get_flag("Z".to_owned()).unwrap();
+            (
+                failure,
+                pc.get_constant().unwrap(),
+                r0.get_constant(),
+                z.get_constant(),
+            )
+        };
+
+        let failure = Some("unpredictable instruction");
+        assert_eq!(
+            run(UnpredictablePolicy::FailPath, 0),
+            (failure, 0, Some(1), Some(1))
+        );
+
+        // The instruction is stepped over.
+        assert_eq!(
+            run(UnpredictablePolicy::Nop, 0),
+            (None, 2, Some(1), Some(1))
+        );
+        assert_eq!(
+            run(UnpredictablePolicy::Nop, 4),
+            (None, 8, Some(1), Some(1))
+        );
+
+        // The registers and flags may hold any value after the instruction.
+        assert_eq!(run(UnpredictablePolicy::Havoc, 4), (None, 8, None, None));
+
+        // The address of the instruction is not known.
+        let mut project = Project::<ArmV6M>::test_project();
+        project.set_unpredictable_policy(UnpredictablePolicy::Havoc);
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, 0x2000_1000, ArmV6M {});
+        let pc = state.ctx.unconstrained(32, "pc");
+        state.set_register("PC".to_owned(), pc).unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state.clone());
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        assert!(matches!(
+            executor.handle_unpredictable(),
+            Err(GAError::SymbolicProgramCounter)
+        ));
+    }
 }
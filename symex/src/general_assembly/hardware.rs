@@ -0,0 +1,469 @@
+//! Memory mapped peripherals described by their registers.
+//!
+//! A [`HardwareModel`] declares the peripherals of a device, each an address
+//! range with registers at offsets from its base. A register has a reset
+//! value, or an unknown value after reset, and describes what reads and writes
+//! of it do: reads may return the stored value, a fresh symbol or clear the
+//! register, writes may store the value, be ignored or clear or set the written
+//! bits. Accesses may also set and clear bits of other registers of the
+//! peripheral, such as a status flag that is set when a data register is
+//! written.
+//!
+//! The whole model is registered at once with
+//! [`RunConfig::add_hardware_model`](super::run_config::RunConfig::add_hardware_model)
+//! or [`Project::add_hardware_model`](super::project::Project::add_hardware_model),
+//! which add a memory read and a memory write hook for the address range of
+//! each peripheral. The values of the registers are kept in the memory of the
+//! path, so they show up in the written memory of the path results. Addresses
+//! of a peripheral that are not in any register are read and written like
+//! plain memory.
+//!
+//! ```
+//! # use symex::general_assembly::hardware::*;
+//! // A transmitter whose status register reports the written byte as pending.
+//! let uart = MappedPeripheral {
+//!     name: "UART".to_owned(),
+//!     base: 0x4000_4000,
+//!     size: 0x400,
+//!     registers: vec![
+//!         MappedRegister {
+//!             on_write: vec![SideEffect::set(0x4, 0b1)],
+//!             ..MappedRegister::new("DR", 0x0, 4)
+//!         },
+//!         MappedRegister {
+//!             write: WriteAction::OneToClear,
+//!             ..MappedRegister::new("SR", 0x4, 4)
+//!         },
+//!         MappedRegister {
+//!             read: ReadAction::Symbolic,
+//!             write: WriteAction::Ignore,
+//!             ..MappedRegister::new("RX", 0x8, 4)
+//!         },
+//!     ],
+//! };
+//! let model = HardwareModel {
+//!     peripherals: vec![uart],
+//! };
+//! ```
+
+use tracing::debug;
+
+use super::{
+    arch::Arch,
+    state::{GAState, SymbolOrigin},
+    Result,
+};
+use crate::{memory::SmtMap, smt::DExpr};
+
+/// The memory mapped peripherals of a device.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HardwareModel {
+    pub peripherals: Vec<MappedPeripheral>,
+}
+
+/// A memory mapped peripheral and its registers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedPeripheral {
+    /// Name of the peripheral.
+    pub name: String,
+
+    /// Address of the first byte of the peripheral.
+    pub base: u64,
+
+    /// Number of bytes of the address range of the peripheral.
+    pub size: u64,
+
+    pub registers: Vec<MappedRegister>,
+}
+
+impl MappedPeripheral {
+    /// Checks if `address` is inside of the peripheral.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.base && address - self.base < self.size
+    }
+
+    /// Get the register that contains `address`, if any.
+    pub fn register_at(&self, address: u64) -> Option<&MappedRegister> {
+        let offset = address.checked_sub(self.base)?;
+        self.registers
+            .iter()
+            .find(|register| offset >= register.offset && offset - register.offset < register.size)
+    }
+
+    /// Get the register at `offset` from the base.
+    fn register_at_offset(&self, offset: u64) -> Option<&MappedRegister> {
+        self.registers
+            .iter()
+            .find(|register| register.offset == offset)
+    }
+}
+
+/// A register of a [`MappedPeripheral`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedRegister {
+    /// Name of the register.
+    pub name: String,
+
+    /// Offset of the register from the base of the peripheral.
+    pub offset: u64,
+
+    /// Size of the register in bytes, at most 8.
+    pub size: u64,
+
+    /// Value of the register after reset, `None` if the value is unknown and
+    /// a fresh symbol is used.
+    pub reset: Option<u64>,
+
+    /// What a read of the register returns.
+    pub read: ReadAction,
+
+    /// What a write of the register does.
+    pub write: WriteAction,
+
+    /// Changes to other registers after the register is read.
+    pub on_read: Vec<SideEffect>,
+
+    /// Changes to other registers after the register is written.
+    pub on_write: Vec<SideEffect>,
+}
+
+impl MappedRegister {
+    /// Creates a register of `size` bytes at `offset` that resets to zero and
+    /// is read and written like memory.
+    pub fn new(name: &str, offset: u64, size: u64) -> Self {
+        Self {
+            name: name.to_owned(),
+            offset,
+            size,
+            reset: Some(0),
+            read: ReadAction::Value,
+            write: WriteAction::Store,
+            on_read: vec![],
+            on_write: vec![],
+        }
+    }
+
+    fn bits(&self) -> u32 {
+        self.size as u32 * 8
+    }
+}
+
+/// What a read of a [`MappedRegister`] returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadAction {
+    /// Returns the value of the register.
+    Value,
+
+    /// Returns a fresh symbol on every read, for registers the hardware
+    /// changes such as received data.
+    Symbolic,
+
+    /// Returns the value of the register and then clears it.
+    Clear,
+}
+
+/// What a write of a [`MappedRegister`] does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteAction {
+    /// Stores the written value.
+    Store,
+
+    /// Ignores the written value, for read only registers.
+    Ignore,
+
+    /// Clears the bits that are written as one.
+    OneToClear,
+
+    /// Sets the bits that are written as one.
+    OneToSet,
+}
+
+/// Sets and clears bits of a register of the same peripheral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SideEffect {
+    /// Offset of the changed register from the base of the peripheral.
+    pub register: u64,
+
+    /// Bits that are set.
+    pub set: u64,
+
+    /// Bits that are cleared, before `set` is applied.
+    pub clear: u64,
+}
+
+impl SideEffect {
+    /// Sets the bits of `mask` in the register at `register`.
+    pub const fn set(register: u64, mask: u64) -> Self {
+        Self {
+            register,
+            set: mask,
+            clear: 0,
+        }
+    }
+
+    /// Clears the bits of `mask` in the register at `register`.
+    pub const fn clear(register: u64, mask: u64) -> Self {
+        Self {
+            register,
+            set: 0,
+            clear: mask,
+        }
+    }
+}
+
+/// Mask of the low `bits` bits.
+fn mask(bits: u32) -> u64 {
+    match bits {
+        64.. => u64::MAX,
+        bits => (1 << bits) - 1,
+    }
+}
+
+/// Memory read hook of the peripherals of a [`HardwareModel`].
+pub fn read_mapped_register<A: Arch>(state: &mut GAState<A>, address: u64) -> Result<DExpr> {
+    let project = state.project.clone();
+    let word_size = project.get_word_size();
+    let Some(peripheral) = project.get_mapped_peripheral(address) else {
+        return read_memory(state, address, word_size);
+    };
+    let Some(register) = peripheral.register_at(address) else {
+        return read_memory(state, address, word_size);
+    };
+    let start = peripheral.base + register.offset;
+    let bits = register.bits();
+    let value = match register.read {
+        ReadAction::Value => register_value(state, peripheral, register)?,
+        ReadAction::Symbolic => {
+            let name = state.memory_symbol_name(start);
+            state.new_symbol(bits, &name, SymbolOrigin::MemoryRead(start))
+        }
+        ReadAction::Clear => {
+            let value = register_value(state, peripheral, register)?;
+            let zero = state.ctx.zero(bits);
+            write_memory(state, start, zero)?;
+            value
+        }
+    };
+    for effect in &register.on_read {
+        apply(state, peripheral, effect)?;
+    }
+
+    // A read of the later bytes of a register starts at the byte.
+    let shift = (address - start) as u32 * 8;
+    Ok(value
+        .srl(&state.ctx.from_u64(shift as u64, bits))
+        .resize_unsigned(bits - shift))
+}
+
+/// Memory write hook of the peripherals of a [`HardwareModel`].
+pub fn write_mapped_register<A: Arch>(
+    state: &mut GAState<A>,
+    address: u64,
+    value: DExpr,
+    bits: u32,
+) -> Result<()> {
+    let project = state.project.clone();
+    let Some(peripheral) = project.get_mapped_peripheral(address) else {
+        return write_memory(state, address, value.resize_unsigned(bits));
+    };
+    let Some(register) = peripheral.register_at(address) else {
+        return write_memory(state, address, value.resize_unsigned(bits));
+    };
+    let start = peripheral.base + register.offset;
+    let width = register.bits();
+    let shift = (address - start) as u32 * 8;
+    let ctx = state.ctx.clone();
+
+    // The written bits in the position of the register.
+    let written_mask = (mask(bits) << shift) & mask(width);
+    let written = value
+        .resize_unsigned(bits)
+        .resize_unsigned(width)
+        .sll(&ctx.from_u64(shift as u64, width))
+        .and(&ctx.from_u64(written_mask, width));
+    let new = match register.write {
+        WriteAction::Ignore => None,
+        WriteAction::Store if written_mask == mask(width) => Some(written),
+        WriteAction::Store => {
+            let old = register_value(state, peripheral, register)?;
+            Some(old.and(&ctx.from_u64(!written_mask, width)).or(&written))
+        }
+        WriteAction::OneToClear => {
+            let old = register_value(state, peripheral, register)?;
+            Some(old.and(&written.not()))
+        }
+        WriteAction::OneToSet => {
+            let old = register_value(state, peripheral, register)?;
+            Some(old.or(&written))
+        }
+    };
+    if let Some(new) = new {
+        write_memory(state, start, new.simplify())?;
+    }
+    for effect in &register.on_write {
+        apply(state, peripheral, effect)?;
+    }
+    Ok(())
+}
+
+/// Get the value of `register`, the reset value until it is first written.
+fn register_value<A: Arch>(
+    state: &mut GAState<A>,
+    peripheral: &MappedPeripheral,
+    register: &MappedRegister,
+) -> Result<DExpr> {
+    let start = peripheral.base + register.offset;
+    let written = (start..start + register.size)
+        .any(|address| state.memory.written_addresses().contains(&address));
+    if written {
+        return read_memory(state, start, register.bits());
+    }
+    match register.reset {
+        Some(reset) => Ok(state
+            .ctx
+            .from_u64(reset & mask(register.bits()), register.bits())),
+        None => {
+            // The unknown reset value is the same on every read.
+            let name = state.memory_symbol_name(start);
+            let value = state.new_symbol(register.bits(), &name, SymbolOrigin::MemoryRead(start));
+            write_memory(state, start, value.clone())?;
+            Ok(value)
+        }
+    }
+}
+
+/// Applies `effect` to its register in `peripheral`.
+fn apply<A: Arch>(
+    state: &mut GAState<A>,
+    peripheral: &MappedPeripheral,
+    effect: &SideEffect,
+) -> Result<()> {
+    let Some(register) = peripheral.register_at_offset(effect.register) else {
+        debug!(
+            "{} has no register at {:#X}, ignoring the side effect",
+            peripheral.name, effect.register
+        );
+        return Ok(());
+    };
+    let bits = register.bits();
+    let value = register_value(state, peripheral, register)?
+        .and(&state.ctx.from_u64(!effect.clear & mask(bits), bits))
+        .or(&state.ctx.from_u64(effect.set & mask(bits), bits))
+        .simplify();
+    write_memory(state, peripheral.base + register.offset, value)
+}
+
+fn read_memory<A: Arch>(state: &mut GAState<A>, address: u64, bits: u32) -> Result<DExpr> {
+    let address = state.ctx.from_u64(address, state.project.get_ptr_size());
+    Ok(state.memory.read(&address, bits)?)
+}
+
+fn write_memory<A: Arch>(state: &mut GAState<A>, address: u64, value: DExpr) -> Result<()> {
+    let address = state.ctx.from_u64(address, state.project.get_ptr_size());
+    Ok(state.memory.write(&address, value)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{
+        HardwareModel,
+        MappedPeripheral,
+        MappedRegister,
+        ReadAction,
+        SideEffect,
+        WriteAction,
+    };
+    use crate::general_assembly::{arch::arm::v6::ArmV6M, project::Project, state::GAState};
+
+    fn setup_test_state() -> GAState<ArmV6M> {
+        let mut project = Project::test_project();
+        let uart = MappedPeripheral {
+            name: "UART".to_owned(),
+            base: 0x4000_4000,
+            size: 0x100,
+            registers: vec![
+                MappedRegister {
+                    on_write: vec![SideEffect::set(0x4, 0b1)],
+                    ..MappedRegister::new("DR", 0x0, 4)
+                },
+                MappedRegister {
+                    reset: Some(0x80),
+                    write: WriteAction::OneToClear,
+                    on_read: vec![SideEffect::clear(0x4, 0x80)],
+                    ..MappedRegister::new("SR", 0x4, 4)
+                },
+                MappedRegister {
+                    read: ReadAction::Symbolic,
+                    write: WriteAction::Ignore,
+                    ..MappedRegister::new("RX", 0x8, 4)
+                },
+                MappedRegister {
+                    read: ReadAction::Clear,
+                    write: WriteAction::OneToSet,
+                    ..MappedRegister::new("EV", 0xc, 2)
+                },
+                MappedRegister {
+                    reset: None,
+                    ..MappedRegister::new("ID", 0x10, 4)
+                },
+            ],
+        };
+        let handles = project.add_hardware_model(HardwareModel {
+            peripherals: vec![uart],
+        });
+        assert_eq!(handles.len(), 2);
+        let project = Arc::new(project);
+        GAState::test_state(project, 0, 0x2000_1000, ArmV6M {})
+    }
+
+    fn read(state: &mut GAState<ArmV6M>, address: u64) -> Option<u64> {
+        let hook = state.project.get_memory_read_hook(address).unwrap();
+        hook(state, address).unwrap().simplify().get_constant()
+    }
+
+    fn write(state: &mut GAState<ArmV6M>, address: u64, value: u64, bits: u32) {
+        let hook = state.project.get_memory_write_hook(address).unwrap();
+        let value = state.ctx.from_u64(value, bits);
+        hook(state, address, value, bits).unwrap();
+    }
+
+    #[test]
+    fn test_hardware_model() {
+        let mut state = setup_test_state();
+        assert!(state.project.get_memory_read_hook(0x4000_4100).is_none());
+
+        // The status register resets to 0x80, which is cleared by reading it,
+        // and writing the data register sets its lowest bit.
+        assert_eq!(read(&mut state, 0x4000_4004), Some(0x80));
+        assert_eq!(read(&mut state, 0x4000_4004), Some(0));
+        write(&mut state, 0x4000_4000, 0x41, 32);
+        assert_eq!(read(&mut state, 0x4000_4000), Some(0x41));
+        assert_eq!(read(&mut state, 0x4000_4004), Some(0b1));
+        write(&mut state, 0x4000_4004, 0b1, 32);
+        assert_eq!(read(&mut state, 0x4000_4004), Some(0));
+
+        // Byte writes only change the written byte.
+        write(&mut state, 0x4000_4001, 0x12, 8);
+        assert_eq!(read(&mut state, 0x4000_4000), Some(0x1241));
+        assert_eq!(read(&mut state, 0x4000_4001), Some(0x12));
+
+        // Received data is a fresh symbol on every read, writes are ignored.
+        write(&mut state, 0x4000_4008, 0x5, 32);
+        assert_eq!(read(&mut state, 0x4000_4008), None);
+        let provenance: Vec<_> = state.provenance.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(provenance, vec!["UART.RX"]);
+
+        // Events are set by writing ones and cleared by reading them.
+        write(&mut state, 0x4000_400c, 0b01, 16);
+        write(&mut state, 0x4000_400c, 0b10, 16);
+        assert_eq!(read(&mut state, 0x4000_400c), Some(0b11));
+        assert_eq!(read(&mut state, 0x4000_400c), Some(0));
+
+        // An unknown reset value is the same symbol on every read.
+        assert_eq!(read(&mut state, 0x4000_4010), None);
+        assert_eq!(read(&mut state, 0x4000_4010), None);
+        assert_eq!(state.provenance.len(), 2);
+    }
+}
@@ -1,6 +1,6 @@
 //! Describes a general assembly instruction.
 
-use general_assembly::operation::Operation;
+use general_assembly::{hazard::Hazards, operation::Operation};
 
 use super::{arch::Arch, state::GAState};
 
@@ -33,3 +33,11 @@ pub struct Instruction<A: Arch> {
     /// memory or not.
     pub memory_access: bool,
 }
+
+impl<A: Arch> Instruction<A> {
+    /// Get the pipeline resources used by the instruction, see
+    /// [`Hazards`].
+    pub fn hazards(&self) -> Hazards {
+        Hazards::of(&self.operations)
+    }
+}
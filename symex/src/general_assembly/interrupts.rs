@@ -0,0 +1,807 @@
+//! Injection of interrupts into the explored function.
+//!
+//! Each of the [`interrupts`](super::RunConfig::interrupts) may preempt the
+//! explored function between two instructions. The path is forked and the fork
+//! stacks the context, runs the handler and unstacks the context when the
+//! handler returns to the `EXC_RETURN` value in `LR`, as the processor does,
+//! see [`context`](super::context). Handlers do not preempt each other.
+//!
+//! Injecting every interrupt before every instruction makes the number of paths
+//! explode. With
+//! [`partial_order_reduction`](super::RunConfig::partial_order_reduction) the
+//! memory footprint of each handler is found by exploring the handler on its
+//! own, and an interrupt is only injected at the start and after the
+//! instructions whose accesses conflict with the footprint of its handler. All
+//! other instructions commute with the handler, so injecting it anywhere
+//! between two conflicting instructions reaches the same state. Accesses of a
+//! handler to its own stack frame are not part of its footprint. The footprint
+//! is found from the initial state, once a path writes a byte the handler
+//! reads, such as a pointer it follows, the footprint no longer holds and the
+//! interrupt is injected after every instruction of the path.
+//!
+//! An interrupt is not injected while `PRIMASK` is set or `BASEPRI` masks its
+//! [`priority`](Interrupt::priority), it is pending until it is unmasked.
+//!
+//! With [`atomicity_violations`](super::RunConfig::atomicity_violations) a
+//! path fails when a handler writes an address between a load of the thread
+//! code from it and the next store of the thread code to it, as the store
+//! overwrites the value of the handler with one computed from a stale value.
+//! The interrupts taken on the failing path are the interleaving that loses
+//! the write.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use tracing::debug;
+
+use super::{
+    arch::Arch,
+    context::{
+        is_exception_return,
+        stack_context,
+        unstack_context,
+        EXCEPTION_FRAME_SIZE,
+        EXC_RETURN_MSP,
+    },
+    executor::{GAExecutor, PathResult},
+    nvic::{NvicBank, NvicState, SystemException},
+    path_selection::Path,
+    races::{HandlerAccess, ThreadAccess},
+    state::GAState,
+    GAError,
+    Result,
+};
+
+/// Reported as the reason of the failure of a path where a handler writes an
+/// address between a load and a store of the thread code.
+pub const ATOMICITY_VIOLATION: &str = "read-modify-write interleaved by an interrupt";
+
+/// An interrupt that may preempt the explored function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interrupt {
+    /// Name of the handler function.
+    pub handler: String,
+
+    /// Number of times the interrupt may preempt a path.
+    pub max_activations: usize,
+
+    /// Priority of the interrupt, lower values are more urgent.
+    pub priority: u8,
+
+    /// Number of the interrupt in the NVIC, `None` if it is not controlled by
+    /// the [`nvic`](super::nvic) model.
+    pub irq: Option<u32>,
+
+    /// The system exception the handler belongs to, it may be pended through
+    /// `ICSR` when the [`nvic`](super::nvic) is modeled.
+    pub exception: Option<SystemException>,
+}
+
+impl Interrupt {
+    /// Creates an interrupt that runs `handler` at most once per path.
+    pub fn new(handler: &str) -> Self {
+        Self {
+            handler: handler.to_owned(),
+            max_activations: 1,
+            priority: 0,
+            irq: None,
+            exception: None,
+        }
+    }
+
+    /// Allows the interrupt to preempt a path `max_activations` times.
+    pub fn with_max_activations(self, max_activations: usize) -> Self {
+        Self {
+            max_activations,
+            ..self
+        }
+    }
+
+    /// Sets the priority of the interrupt.
+    pub fn with_priority(self, priority: u8) -> Self {
+        Self { priority, ..self }
+    }
+
+    /// Sets the number of the interrupt in the NVIC.
+    pub fn with_irq(self, irq: u32) -> Self {
+        Self {
+            irq: Some(irq),
+            ..self
+        }
+    }
+
+    /// Makes the handler the handler of the system exception `exception`.
+    pub fn with_exception(self, exception: SystemException) -> Self {
+        Self {
+            exception: Some(exception),
+            ..self
+        }
+    }
+}
+
+/// The interrupt masking registers of a path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InterruptMask {
+    /// True if `PRIMASK` masks all interrupts.
+    pub primask: bool,
+
+    /// Interrupts with a priority value of at least `BASEPRI` are masked, zero
+    /// masks none.
+    pub basepri: u64,
+}
+
+impl InterruptMask {
+    /// Get the mask of `state`, registers that are not concrete mask nothing.
+    pub fn of<A: Arch>(state: &GAState<A>) -> Self {
+        let register = |name: &str| {
+            state
+                .registers
+                .get(name)
+                .and_then(|value| value.get_constant())
+                .unwrap_or(0)
+        };
+        Self {
+            primask: register("PRIMASK") & 0b1 != 0,
+            basepri: register("BASEPRI") & 0xff,
+        }
+    }
+
+    /// Checks if an interrupt of priority `priority` is masked.
+    pub fn masks(&self, priority: u8) -> bool {
+        self.primask || (self.basepri != 0 && priority as u64 >= self.basepri)
+    }
+}
+
+/// Bytes of memory read and written by a sequence of instructions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Footprint {
+    pub reads: BTreeSet<u64>,
+    pub writes: BTreeSet<u64>,
+}
+
+impl Footprint {
+    /// Records an access of `bytes` bytes at `address`.
+    pub fn record(&mut self, address: u64, bytes: u64, write: bool) {
+        let accessed = match write {
+            true => &mut self.writes,
+            false => &mut self.reads,
+        };
+        accessed.extend(address..address + bytes);
+    }
+
+    /// Adds the accesses of `other`.
+    pub fn merge(&mut self, other: &Footprint) {
+        self.reads.extend(&other.reads);
+        self.writes.extend(&other.writes);
+    }
+
+    /// Checks if the accesses do not commute with the accesses of `other`, one
+    /// of them writes a byte the other one accesses.
+    pub fn conflicts_with(&self, other: &Footprint) -> bool {
+        !self.writes.is_disjoint(&other.writes)
+            || !self.writes.is_disjoint(&other.reads)
+            || !self.reads.is_disjoint(&other.writes)
+    }
+}
+
+/// An interrupt that preempted a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TakenInterrupt {
+    /// Name of the handler.
+    pub handler: String,
+
+    /// Address of the instruction the interrupt preempted.
+    pub pc: u64,
+}
+
+/// A read-modify-write sequence of the thread code that a handler wrote into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtomicityViolation {
+    /// Address of the byte the handler wrote.
+    pub address: u64,
+
+    /// Address of the load of the thread code.
+    pub load: u64,
+
+    /// Address of the store of the thread code.
+    pub store: u64,
+
+    /// Name of the handler.
+    pub handler: String,
+
+    /// Address of the store of the handler.
+    pub write: u64,
+}
+
+/// The handler that runs on a path.
+#[derive(Debug, Clone)]
+struct ActiveHandler {
+    /// Index of the interrupt.
+    index: usize,
+
+    /// Stack pointer the handler was entered with.
+    entry_sp: u64,
+
+    /// False if the handler is explored on its own and has no context to
+    /// return to.
+    preempted: bool,
+
+    /// Accesses of the handler outside of its stack frame.
+    accesses: Footprint,
+}
+
+/// Interrupts on a path.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptState {
+    /// Number of times each interrupt preempted the path, by index.
+    activations: Vec<usize>,
+
+    /// The handler that runs, if any.
+    active: Option<ActiveHandler>,
+
+    /// Set when the interrupts have been injected at the start of the path.
+    started: bool,
+
+    /// Accesses since the interrupts were last considered.
+    last_accesses: Footprint,
+
+    /// Interrupts that were masked when they were last considered.
+    masked: BTreeSet<usize>,
+
+    /// Interrupts whose handler reads a byte the path wrote, their footprint
+    /// may differ from the one found from the initial state.
+    stale: BTreeSet<usize>,
+
+    /// Address of the last load of the thread code from each byte it has not
+    /// stored to since, if atomicity violations are detected.
+    loads: BTreeMap<u64, u64>,
+
+    /// Bytes in `loads` that a handler wrote since, with the index of the
+    /// interrupt and the address of the store of the handler.
+    overwritten: BTreeMap<u64, (usize, u64)>,
+
+    /// The first atomicity violation on the path.
+    pub violation: Option<AtomicityViolation>,
+
+    /// The NVIC bits of the path, see [`nvic`](super::nvic).
+    pub nvic: NvicState,
+
+    /// Interrupts that preempted the path in order.
+    pub taken: Vec<TakenInterrupt>,
+}
+
+impl InterruptState {
+    /// Get the number of times each interrupt preempted the path, by index.
+    pub fn activations(&self) -> &[usize] {
+        &self.activations
+    }
+
+    /// Checks if a handler runs on the path.
+    pub fn in_handler(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Get the index of the interrupt whose handler runs, if any.
+    pub fn handler_index(&self) -> Option<usize> {
+        self.active.as_ref().map(|active| active.index)
+    }
+
+    /// Get the accesses of the running handler outside of its stack frame.
+    pub fn handler_footprint(&self) -> Option<&Footprint> {
+        self.active.as_ref().map(|active| &active.accesses)
+    }
+
+    /// Tracks an access of the thread code of `bytes` bytes at `address` by
+    /// the instruction at `pc`, recording a violation if it stores to a byte
+    /// a handler wrote since the thread code loaded it.
+    fn check_atomicity(
+        &mut self,
+        interrupts: &[Interrupt],
+        address: u64,
+        bytes: u64,
+        pc: u64,
+        write: bool,
+    ) {
+        for byte in address..address + bytes {
+            if !write {
+                // A new load reads the value of the handler.
+                self.loads.insert(byte, pc);
+                self.overwritten.remove(&byte);
+                continue;
+            }
+            let load = self.loads.remove(&byte);
+            let overwritten = self.overwritten.remove(&byte);
+            if let (Some(load), Some((index, handler_pc)), None) =
+                (load, overwritten, &self.violation)
+            {
+                self.violation = Some(AtomicityViolation {
+                    address: byte,
+                    load,
+                    store: pc,
+                    handler: interrupts[index].handler.clone(),
+                    write: handler_pc,
+                });
+            }
+        }
+    }
+}
+
+/// Enters the handler of the interrupt at `index` on the path of `state`,
+/// saving the context of the preempted function if `preempt` is set.
+pub(crate) fn enter_handler<A: Arch>(
+    state: &mut GAState<A>,
+    index: usize,
+    preempt: bool,
+) -> Result<()> {
+    let project = state.project.clone();
+    let interrupt = &project.get_interrupts()[index];
+    let handler = project
+        .get_symbol_address(&interrupt.handler)
+        .ok_or_else(|| GAError::EntryFunctionNotFound(interrupt.handler.clone()))?;
+    let pc = state.get_register("PC".to_owned())?.get_constant();
+
+    let ptr_size = state.project.get_ptr_size();
+    let symbolic_sp = || GAError::SymbolicStackPointer(interrupt.handler.clone());
+    let exc_return = match preempt {
+        true => stack_context(state)?,
+        false => {
+            let sp = state
+                .get_register("SP".to_owned())?
+                .get_constant()
+                .ok_or_else(symbolic_sp)?;
+            let frame = sp.saturating_sub(EXCEPTION_FRAME_SIZE) & !0b111;
+            state.set_register("SP".to_owned(), state.ctx.from_u64(frame, ptr_size))?;
+            EXC_RETURN_MSP
+        }
+    };
+    // The stack frame of the handler is only known for a concrete stack
+    // pointer.
+    let entry_sp = state
+        .get_register("SP".to_owned())?
+        .get_constant()
+        .ok_or_else(symbolic_sp)?;
+    state.set_register("LR".to_owned(), state.ctx.from_u64(exc_return, ptr_size))?;
+    state.set_register("PC".to_owned(), state.ctx.from_u64(handler, ptr_size))?;
+    state.last_instruction = None;
+
+    let interrupts = &mut state.interrupts;
+    if interrupts.activations.len() <= index {
+        interrupts.activations.resize(index + 1, 0);
+    }
+    interrupts.activations[index] += 1;
+    interrupts.taken.push(TakenInterrupt {
+        handler: interrupt.handler.clone(),
+        pc: pc.unwrap_or(state.last_pc),
+    });
+    if let (true, Some(irq)) = (project.get_nvic(), interrupt.irq) {
+        interrupts
+            .nvic
+            .set_bit(&state.ctx, NvicBank::Pending, irq, false);
+        interrupts
+            .nvic
+            .set_bit(&state.ctx, NvicBank::Active, irq, true);
+    }
+    if let Some(exception) = interrupt.exception {
+        interrupts.nvic.set_system_pending(exception, false);
+    }
+    interrupts.active = Some(ActiveHandler {
+        index,
+        entry_sp,
+        preempted: preempt,
+        accesses: Footprint::default(),
+    });
+    Ok(())
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Unstacks the preempted context when the running handler returns.
+    ///
+    /// Returns a result if the path should end, as a handler that is explored
+    /// on its own has no context to return to.
+    pub(super) fn return_from_interrupt(&mut self) -> Result<Option<PathResult>> {
+        if !self.state.interrupts.in_handler() {
+            return Ok(None);
+        }
+        let pc = self.state.get_register("PC".to_owned())?.get_constant();
+        let Some(exc_return) = pc.filter(|pc| is_exception_return(*pc)) else {
+            return Ok(None);
+        };
+        let active = self
+            .state
+            .interrupts
+            .active
+            .take()
+            .expect("a handler is running");
+        let irq = self.project.get_interrupts()[active.index].irq;
+        if let (true, Some(irq)) = (self.project.get_nvic(), irq) {
+            let nvic = &mut self.state.interrupts.nvic;
+            nvic.set_bit(&self.state.ctx, NvicBank::Active, irq, false);
+        }
+        if !active.preempted {
+            self.state.interrupts.active = Some(active);
+            return Ok(Some(PathResult::Success(None)));
+        }
+        debug!("Returning from the interrupt handler");
+        unstack_context(&mut self.state, exc_return)?;
+        self.state.last_instruction = None;
+        // The handler is a step of the interleaving like any instruction.
+        self.state.interrupts.last_accesses = active.accesses;
+        Ok(None)
+    }
+
+    /// Forks the path into the handlers of the interrupts that may preempt it
+    /// before the next instruction.
+    pub(super) fn inject_interrupts(&mut self) -> Result<()> {
+        if self.state.interrupts.in_handler() {
+            return Ok(());
+        }
+        let started = std::mem::replace(&mut self.state.interrupts.started, true);
+        let last_accesses = std::mem::take(&mut self.state.interrupts.last_accesses);
+        let reduce = self.project.get_partial_order_reduction();
+        let mask = InterruptMask::of(&self.state);
+        if let Some(index) = self.pended_interrupt(&mask) {
+            debug!("Taking the interrupt pended by the firmware");
+            return enter_handler(&mut self.state, index, true);
+        }
+        let ctx = &self.state.ctx;
+        for (index, interrupt) in self.project.get_interrupts().iter().enumerate() {
+            let activations = self
+                .state
+                .interrupts
+                .activations
+                .get(index)
+                .copied()
+                .unwrap_or(0);
+            if activations >= interrupt.max_activations {
+                continue;
+            }
+            // PendSV is only taken when the firmware pends it.
+            if self.project.get_nvic() && interrupt.exception == Some(SystemException::PendSV) {
+                continue;
+            }
+            // The interrupt is injected where it may be enabled in the NVIC.
+            let enabled = match (self.project.get_nvic(), interrupt.irq) {
+                (true, Some(irq)) => {
+                    let bit = self.state.interrupts.nvic.bit(ctx, NvicBank::Enabled, irq);
+                    let enabled = bit.eq(&ctx.from_u64(1, 1)).simplify();
+                    match enabled.get_constant_bool() {
+                        Some(true) => None,
+                        _ => Some(enabled),
+                    }
+                }
+                _ => None,
+            };
+            let disabled = match &enabled {
+                Some(enabled) if enabled.get_constant_bool() == Some(false) => true,
+                Some(enabled) => !self.state.constraints.is_sat_with_constraint(enabled)?,
+                None => false,
+            };
+            if mask.masks(interrupt.priority) || disabled {
+                self.state.interrupts.masked.insert(index);
+                continue;
+            }
+            // A pending interrupt is taken as soon as it is unmasked.
+            let unmasked = self.state.interrupts.masked.remove(&index);
+            let footprint = self.vm.interrupt_footprints.get(index);
+            let rewritten = footprint
+                .is_some_and(|footprint| !last_accesses.writes.is_disjoint(&footprint.reads));
+            if rewritten {
+                self.state.interrupts.stale.insert(index);
+            }
+            let commutes = !self.state.interrupts.stale.contains(&index)
+                && footprint.is_some_and(|footprint| !last_accesses.conflicts_with(footprint));
+            if reduce && started && commutes && !unmasked {
+                self.vm.pruned_interleavings += 1;
+                continue;
+            }
+            let mut preempted = self.state.clone();
+            preempted.trace_position = None;
+            enter_handler(&mut preempted, index, true)?;
+            self.vm.paths.save_path(Path::new(preempted, enabled));
+        }
+        Ok(())
+    }
+
+    /// Get the index of an interrupt that the firmware pended and that is
+    /// enabled and not masked, it is taken before the next instruction.
+    fn pended_interrupt(&self, mask: &InterruptMask) -> Option<usize> {
+        if !self.project.get_nvic() {
+            return None;
+        }
+        let nvic = &self.state.interrupts.nvic;
+        let ctx = &self.state.ctx;
+        self.project.get_interrupts().iter().position(|interrupt| {
+            let pending = match (interrupt.irq, interrupt.exception) {
+                (_, Some(exception)) => nvic.is_system_pending(exception),
+                (Some(irq), None) => {
+                    let set = |bank| nvic.bit(ctx, bank, irq).get_constant() == Some(1);
+                    set(NvicBank::Pending) && set(NvicBank::Enabled)
+                }
+                (None, None) => false,
+            };
+            pending && !mask.masks(interrupt.priority)
+        })
+    }
+
+    /// Records a memory access for the partial order reduction of the
+    /// interrupts and the detection of data races.
+    pub(super) fn record_interrupt_access(
+        &mut self,
+        address: u64,
+        bits: u32,
+        write: bool,
+    ) -> Result<()> {
+        if self.project.get_interrupts().is_empty() {
+            return Ok(());
+        }
+        let bytes = (bits as u64).div_ceil(8);
+        let sp = self.state.get_register("SP".to_owned())?.get_constant();
+        let pc = self.state.last_pc;
+        let mask = InterruptMask::of(&self.state);
+        let interrupts = &mut self.state.interrupts;
+        match &mut interrupts.active {
+            Some(active) => {
+                let in_frame = sp.is_some_and(|sp| (sp..active.entry_sp).contains(&address));
+                if !in_frame {
+                    active.accesses.record(address, bytes, write);
+                    if write {
+                        for byte in address..address + bytes {
+                            if interrupts.loads.contains_key(&byte) {
+                                interrupts.overwritten.insert(byte, (active.index, pc));
+                            }
+                        }
+                    }
+                    self.vm.races.record_handler(address, bytes, HandlerAccess {
+                        pc,
+                        write,
+                        interrupt: active.index,
+                    });
+                }
+            }
+            None => {
+                interrupts.last_accesses.record(address, bytes, write);
+                if self.project.get_atomicity_violations() {
+                    interrupts.check_atomicity(
+                        self.project.get_interrupts(),
+                        address,
+                        bytes,
+                        pc,
+                        write,
+                    );
+                }
+                let in_stack = sp.is_some_and(|sp| (sp..self.state.inital_sp).contains(&address));
+                if !in_stack {
+                    self.vm
+                        .races
+                        .record_thread(address, bytes, ThreadAccess { pc, write, mask });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::{enter_handler, Footprint, Interrupt, InterruptMask};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        context::EXC_RETURN_MSP,
+        executor::{GAExecutor, PathResult},
+        project::{PCHook, Project},
+        state::GAState,
+        vm::VM,
+        GAError,
+    };
+
+    #[test]
+    fn test_footprint_conflicts() {
+        let mut handler = Footprint::default();
+        handler.record(0x2000_0000, 4, true);
+        handler.record(0x2000_0010, 1, false);
+
+        let mut read = Footprint::default();
+        read.record(0x2000_0002, 2, false);
+        assert!(read.conflicts_with(&handler));
+        assert!(handler.conflicts_with(&read));
+
+        let mut reads = Footprint::default();
+        reads.record(0x2000_0010, 4, false);
+        reads.record(0x2000_0004, 4, false);
+        assert!(!reads.conflicts_with(&handler));
+
+        reads.record(0x2000_0010, 1, true);
+        assert!(reads.conflicts_with(&handler));
+    }
+
+    #[test]
+    fn test_inject_interrupts() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("handler".to_owned(), 0x200)]));
+        project.add_pc_hook(0x100, PCHook::EndSuccess);
+        // The handler returns right away.
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("handler").with_max_activations(2));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        assert_eq!(vm.interrupt_footprints, vec![Footprint::default()]);
+        vm.interrupt_footprints[0].record(0x2000_0000, 4, true);
+
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project.clone());
+        // Always injected at the start.
+        executor.inject_interrupts().unwrap();
+        // Commutes with the handler.
+        executor
+            .record_interrupt_access(0x2000_0100, 32, false)
+            .unwrap();
+        executor.inject_interrupts().unwrap();
+        // Reads what the handler writes.
+        executor
+            .record_interrupt_access(0x2000_0000, 32, false)
+            .unwrap();
+        executor.inject_interrupts().unwrap();
+        assert_eq!(executor.vm.pruned_interleavings, 1);
+
+        let mut preempted = vm.paths.get_path().unwrap().state;
+        assert!(vm.paths.get_path().is_some());
+        assert!(vm.paths.get_path().is_none());
+        assert!(preempted.interrupts.in_handler());
+        assert_eq!(preempted.interrupts.taken[0].pc, 0x100);
+        let lr = preempted.get_register("LR".to_owned()).unwrap();
+        assert_eq!(lr.get_constant(), Some(EXC_RETURN_MSP));
+        let sp = preempted.get_register("SP".to_owned()).unwrap();
+        assert_eq!(sp.get_constant(), Some(0x2000_0FE0));
+
+        // The handler returns to the preempted function with its context.
+        let mut executor = GAExecutor::from_state(preempted, &mut vm, project);
+        let result = executor.resume_execution().unwrap();
+        assert!(matches!(result, PathResult::Success(None)));
+        assert!(!executor.state.interrupts.in_handler());
+        assert_eq!(executor.state.interrupts.activations(), &[1]);
+        let sp = executor.state.get_register("SP".to_owned()).unwrap();
+        assert_eq!(sp.get_constant(), Some(0x2000_1000));
+    }
+
+    #[test]
+    fn test_stale_footprint() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("handler".to_owned(), 0x200)]));
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("handler"));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        // The handler writes through a pointer stored at 0x2000_0000.
+        vm.interrupt_footprints[0].record(0x2000_0000, 4, false);
+        vm.interrupt_footprints[0].record(0x2000_0100, 4, true);
+
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        executor.inject_interrupts().unwrap();
+        executor
+            .record_interrupt_access(0x2000_0200, 32, true)
+            .unwrap();
+        executor.inject_interrupts().unwrap();
+        assert_eq!(executor.vm.pruned_interleavings, 1);
+
+        // The pointer is rewritten, the handler may now write anywhere.
+        executor
+            .record_interrupt_access(0x2000_0000, 32, true)
+            .unwrap();
+        executor.inject_interrupts().unwrap();
+        executor
+            .record_interrupt_access(0x2000_0200, 32, true)
+            .unwrap();
+        executor.inject_interrupts().unwrap();
+        assert_eq!(executor.vm.pruned_interleavings, 1);
+        for _ in 0..3 {
+            assert!(vm.paths.get_path().is_some());
+        }
+        assert!(vm.paths.get_path().is_none());
+    }
+
+    #[test]
+    fn test_symbolic_handler_stack() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("handler".to_owned(), 0x200)]));
+        project.add_interrupt(Interrupt::new("handler"));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        state
+            .set_register("SP".to_owned(), context.unconstrained(32, "sp"))
+            .unwrap();
+
+        for preempt in [false, true] {
+            let result = enter_handler(&mut state.clone(), 0, preempt);
+            assert!(
+                matches!(result, Err(GAError::SymbolicStackPointer(handler)) if handler == "handler")
+            );
+        }
+    }
+
+    #[test]
+    fn test_masked_interrupts() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("handler".to_owned(), 0x200)]));
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("handler").with_priority(0x40));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut state = vm.paths.get_path().unwrap().state;
+        assert!(!InterruptMask::of(&state).masks(0));
+
+        state
+            .set_register("BASEPRI".to_owned(), context.from_u64(0x40, 32))
+            .unwrap();
+        let mask = InterruptMask::of(&state);
+        assert!(mask.masks(0x40) && mask.masks(0x80) && !mask.masks(0x20));
+
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        executor.inject_interrupts().unwrap();
+        executor
+            .state
+            .set_register("BASEPRI".to_owned(), context.from_u64(0, 32))
+            .unwrap();
+        // Taken when unmasked even though nothing conflicts with the handler.
+        executor.inject_interrupts().unwrap();
+        executor.inject_interrupts().unwrap();
+        assert_eq!(executor.vm.pruned_interleavings, 1);
+        assert!(vm.paths.get_path().is_some());
+        assert!(vm.paths.get_path().is_none());
+    }
+
+    #[test]
+    fn test_atomicity_violation() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("handler".to_owned(), 0x200)]));
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("handler").with_max_activations(2));
+        project.set_atomicity_violations(true);
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let access = |executor: &mut GAExecutor<'_, ArmV6M>, pc, write| {
+            executor.state.last_pc = pc;
+            executor
+                .record_interrupt_access(0x2000_0000, 32, write)
+                .unwrap();
+        };
+        let interrupt = |executor: &mut GAExecutor<'_, ArmV6M>| {
+            enter_handler(&mut executor.state, 0, true).unwrap();
+            access(executor, 0x200, true);
+            let ret = context.from_u64(EXC_RETURN_MSP, 32);
+            executor.state.set_register("PC".to_owned(), ret).unwrap();
+            executor.return_from_interrupt().unwrap();
+        };
+
+        // The handler writes before the value is loaded again.
+        access(&mut executor, 0x100, false);
+        interrupt(&mut executor);
+        access(&mut executor, 0x104, false);
+        access(&mut executor, 0x108, true);
+        assert!(executor.state.interrupts.violation.is_none());
+
+        // The handler writes between the load and the store.
+        access(&mut executor, 0x10c, false);
+        interrupt(&mut executor);
+        access(&mut executor, 0x110, true);
+        let violation = executor.state.interrupts.violation.clone().unwrap();
+        assert_eq!(
+            (violation.address, violation.load, violation.store),
+            (0x2000_0000, 0x10c, 0x110)
+        );
+        assert_eq!(
+            (violation.handler.as_str(), violation.write),
+            ("handler", 0x200)
+        );
+    }
+}
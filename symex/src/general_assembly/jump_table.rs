@@ -0,0 +1,167 @@
+//! Modeling of compiler generated jump tables.
+//!
+//! A switch statement is often compiled to a table branch, `TBB` and `TBH` on
+//! ARMv7-M, or to a load of the target address from a table in flash followed
+//! by a branch to it. When the index is symbolic and not bounded by the path
+//! constraints the table read has too many possible addresses to resolve.
+//!
+//! The architecture identifies such instructions, see
+//! [`InstructionSet::jump_table`](super::arch::InstructionSet::jump_table). The
+//! bounds of the table are then recovered from the table in flash and the index
+//! is assumed to be within them, after which the table read forks one path per
+//! entry as any other read through a symbolic address.
+
+use general_assembly::operand::RawDataWord;
+pub use symex_core::jump_table::{JumpTable, TableEntry};
+use tracing::{debug, warn};
+
+use super::{arch::Arch, executor::GAExecutor, project::Project, Endianness, Result};
+
+/// Largest number of entries recovered for a table, tables are assumed to end
+/// after this many entries.
+pub const MAX_ENTRIES: u64 = 255;
+
+/// Recovers the branch targets of the table at `base` from program memory.
+///
+/// `origin` is the address of the instruction following the table branch. A
+/// table of offsets ends where the first of its targets starts or at the first
+/// entry that does not branch past itself, such as the padding of a `TBB`
+/// table. A table of addresses ends at the first entry that is not a thumb
+/// address in program memory.
+pub fn recover_targets<A: Arch>(
+    project: &Project<A>,
+    base: u64,
+    entry: TableEntry,
+    origin: u64,
+) -> Vec<u64> {
+    let mut targets = vec![];
+    let mut end = u64::MAX;
+    for n in 0..MAX_ENTRIES {
+        let address = base + n * entry.size();
+        if address >= end {
+            break;
+        }
+        let value = match read_entry(project, address, entry.size()) {
+            Some(value) => value,
+            None => break,
+        };
+        let target = match entry {
+            TableEntry::Offset(_) => origin + 2 * value,
+            TableEntry::Address(_) if value & 0b1 == 1 => value & !0b1,
+            TableEntry::Address(_) => break,
+        };
+        if !project.address_in_range(target) {
+            break;
+        }
+        if let TableEntry::Offset(_) = entry {
+            if target <= address {
+                break;
+            }
+            end = end.min(target);
+        }
+        targets.push(target);
+    }
+    targets
+}
+
+/// Reads an unsigned entry of `size` bytes from program memory.
+fn read_entry<A: Arch>(project: &Project<A>, address: u64, size: u64) -> Option<u64> {
+    let mut value = 0;
+    for n in 0..size {
+        let byte = project.get_byte(address + n).ok()? as u64;
+        value = match project.get_endianness() {
+            Endianness::Little => value | byte << (8 * n),
+            Endianness::Big => value << 8 | byte,
+        };
+    }
+    Some(value)
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Bounds the index of the jump table read by the current instruction, if
+    /// it is one.
+    ///
+    /// Returns true if the index was bounded.
+    pub(super) fn bound_jump_table(&mut self) -> Result<bool> {
+        let pc = self.state.last_pc & !0b1;
+        let architecture = &self.state.architecture;
+        let decode = |buff: &[u8]| {
+            (
+                architecture.jump_table(buff),
+                architecture.instruction_size(buff),
+            )
+        };
+        let (table, size) = match self.project.get_raw_word(pc)? {
+            RawDataWord::Word64(d) => decode(&d),
+            RawDataWord::Word32(d) => decode(&d),
+            RawDataWord::Word16(d) => decode(&d),
+            RawDataWord::Word8(d) => decode(&d),
+        };
+        let (table, size) = match (table, size) {
+            (Some(table), Some(size)) => (table, size as u64 / 8),
+            _ => return Ok(false),
+        };
+        let origin = pc + size;
+        let base = match &table.base {
+            Some(register) => match self.state.get_register(register.to_owned())?.get_constant() {
+                Some(base) => base,
+                None => return Ok(false),
+            },
+            None => origin,
+        };
+        if !self.project.address_in_range(base) || self.project.address_in_backed_region(base) {
+            return Ok(false);
+        }
+
+        let targets = recover_targets(&self.project, base, table.entry, origin);
+        if targets.is_empty() || table.scale == 0 || table.entry.size() % table.scale != 0 {
+            return Ok(false);
+        }
+        warn!(
+            "Index {} of the jump table at {:#X} is not bounded, assuming it selects one of the {} entries",
+            table.index,
+            pc,
+            targets.len()
+        );
+        debug!("Targets of the jump table at {:#X}: {:#X?}", pc, targets);
+
+        let index = self.state.get_register(table.index.to_owned())?;
+        let word_size = self.project.get_word_size();
+        let step = table.entry.size() / table.scale;
+        let mut in_table = self.state.ctx.from_bool(false);
+        for n in 0..targets.len() as u64 {
+            let value = self.state.ctx.from_u64(n * step, word_size);
+            in_table = in_table.or(&index.eq(&value));
+        }
+        self.state.constraints.assert(&in_table);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{recover_targets, TableEntry};
+    use crate::general_assembly::{arch::arm::v6::ArmV6M, project::Project};
+
+    #[test]
+    fn test_recover_targets() {
+        // tbb [pc, r0] at 0x0 followed by a padded table of three entries
+        let mut memory = vec![0; 0x20];
+        memory[4..8].copy_from_slice(&[2, 3, 4, 0]);
+        let project = Project::<ArmV6M>::test_project().with_program(memory, 0, 0x20);
+        assert_eq!(
+            recover_targets(&project, 4, TableEntry::Offset(1), 4),
+            vec![8, 10, 12]
+        );
+
+        // table of addresses, ends at the first entry that is not a thumb address
+        let mut memory = vec![0; 0x20];
+        memory[0..12].copy_from_slice(&[0x11, 0, 0, 0, 0x19, 0, 0, 0, 0x18, 0, 0, 0]);
+        let project = Project::<ArmV6M>::test_project().with_program(memory, 0, 0x20);
+        assert_eq!(
+            recover_targets(&project, 0, TableEntry::Address(4), 0),
+            vec![0x10, 0x18]
+        );
+    }
+}
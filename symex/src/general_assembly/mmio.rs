@@ -0,0 +1,219 @@
+//! Ordered log of the accesses to memory mapped peripherals.
+//!
+//! Accesses to the address ranges in
+//! [`mmio_regions`](super::RunConfig::mmio_regions) are recorded in program
+//! order for each path, see [`GAState::mmio_log`]. Device programming
+//! sequences are checked against the log with [`MmioPattern`]s, such as that
+//! the enable bit of a control register is set before the data register is
+//! written.
+
+use super::{arch::Arch, state::GAState, Result};
+use crate::smt::DExpr;
+
+/// Direction of an access to a peripheral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A recorded access to a memory mapped peripheral.
+#[derive(Debug, Clone)]
+pub struct MmioAccess {
+    /// Direction of the access.
+    pub kind: AccessKind,
+
+    /// Address of the first accessed byte.
+    pub address: u64,
+
+    /// Number of accessed bits.
+    pub bits: u32,
+
+    /// The read or written value.
+    pub value: DExpr,
+
+    /// Address of the instruction that made the access.
+    pub pc: u64,
+
+    /// Index of the instruction that made the access on the path.
+    pub instruction: usize,
+}
+
+/// Describes the accesses of interest in the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MmioPattern {
+    /// Direction of the access, `None` matches both.
+    kind: Option<AccessKind>,
+
+    /// Address of the access.
+    address: u64,
+
+    /// Bits of the value that are compared, zero matches any value.
+    mask: u64,
+
+    /// Expected value of the compared bits.
+    value: u64,
+}
+
+impl MmioPattern {
+    /// Matches reads of `address`.
+    pub fn read(address: u64) -> Self {
+        Self::new(Some(AccessKind::Read), address)
+    }
+
+    /// Matches writes to `address`.
+    pub fn write(address: u64) -> Self {
+        Self::new(Some(AccessKind::Write), address)
+    }
+
+    /// Matches reads of and writes to `address`.
+    pub fn access(address: u64) -> Self {
+        Self::new(None, address)
+    }
+
+    fn new(kind: Option<AccessKind>, address: u64) -> Self {
+        Self {
+            kind,
+            address,
+            mask: 0,
+            value: 0,
+        }
+    }
+
+    /// Only matches accesses where the bits in `mask` of the value equal
+    /// those bits of `value`.
+    pub fn with_bits(self, mask: u64, value: u64) -> Self {
+        Self {
+            mask,
+            value: value & mask,
+            ..self
+        }
+    }
+
+    /// Checks if `access` matches the pattern. A symbolic value matches if the
+    /// compared bits must have the expected value, or with `may` if they can
+    /// have it.
+    fn matches<A: Arch>(&self, state: &GAState<A>, access: &MmioAccess, may: bool) -> Result<bool> {
+        if self.kind.is_some_and(|kind| kind != access.kind) || self.address != access.address {
+            return Ok(false);
+        }
+        if self.mask == 0 {
+            return Ok(true);
+        }
+        let mask = state.ctx.from_u64(self.mask, access.bits);
+        let expected = state.ctx.from_u64(self.value, access.bits);
+        let bits = access.value.and(&mask).simplify();
+        Ok(match may {
+            true => state.constraints.can_equal(&bits, &expected)?,
+            false => state.constraints.must_be_equal(&bits, &expected)?,
+        })
+    }
+}
+
+/// Get the index in the log of `state` of the first access that matches
+/// `pattern`.
+pub fn position<A: Arch>(state: &GAState<A>, pattern: &MmioPattern) -> Result<Option<usize>> {
+    for (index, access) in state.mmio_log.iter().enumerate() {
+        if pattern.matches(state, access, false)? {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+/// Checks that every access in the log of `state` that matches `then` comes
+/// after an access that matches `first`.
+///
+/// An access with a symbolic value counts for `then` if it can match and for
+/// `first` only if it must match, so a sequence that is not guaranteed is
+/// reported as a violation.
+pub fn happens_before<A: Arch>(
+    state: &GAState<A>,
+    first: &MmioPattern,
+    then: &MmioPattern,
+) -> Result<bool> {
+    for access in &state.mmio_log {
+        if first.matches(state, access, false)? {
+            return Ok(true);
+        }
+        if then.matches(state, access, true)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use general_assembly::{
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    use super::{happens_before, position, AccessKind, MmioPattern};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    const CR: u64 = 0x4000_0000;
+    const DR: u64 = 0x4000_0004;
+
+    #[test]
+    fn test_mmio_log() {
+        let mut project = Project::test_project();
+        project.add_mmio_region(0x4000_0000..0x4000_1000);
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let write = |address: u64, value: u32| Operation::Move {
+            destination: Operand::Address(DataWord::Word32(address as u32), 32),
+            source: Operand::Immediate(DataWord::Word32(value)),
+        };
+        // Not a peripheral, not recorded.
+        executor
+            .execute_operation(&write(0x2000_0000, 1), &mut local)
+            .unwrap();
+        executor
+            .execute_operation(&write(CR, 0b10), &mut local)
+            .unwrap();
+        executor
+            .execute_operation(&write(DR, 0x55), &mut local)
+            .unwrap();
+        executor
+            .execute_operation(&write(CR, 0b11), &mut local)
+            .unwrap();
+        let read = Operation::Move {
+            destination: Operand::Register("R0".to_owned()),
+            source: Operand::Address(DataWord::Word32(DR as u32), 32),
+        };
+        executor.execute_operation(&read, &mut local).unwrap();
+
+        let state = &executor.state;
+        let kinds: Vec<_> = state.mmio_log.iter().map(|access| access.kind).collect();
+        assert_eq!(kinds, vec![
+            AccessKind::Write,
+            AccessKind::Write,
+            AccessKind::Write,
+            AccessKind::Read
+        ]);
+        assert_eq!(state.mmio_log[1].value.get_constant(), Some(0x55));
+
+        let enabled = MmioPattern::write(CR).with_bits(0b1, 0b1);
+        assert_eq!(position(state, &enabled).unwrap(), Some(2));
+        assert_eq!(position(state, &MmioPattern::read(DR)).unwrap(), Some(3));
+        // The data register is written before the peripheral is enabled.
+        assert!(!happens_before(state, &enabled, &MmioPattern::write(DR)).unwrap());
+        assert!(happens_before(state, &enabled, &MmioPattern::read(DR)).unwrap());
+        assert!(happens_before(state, &MmioPattern::access(CR), &MmioPattern::access(DR)).unwrap());
+    }
+}
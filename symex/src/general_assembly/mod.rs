@@ -1,16 +1,48 @@
 use self::project::ProjectError;
 use crate::{memory::MemoryError, smt::SolverError};
 
+pub mod acceleration;
 pub mod arch;
+pub mod branch_predictor;
+pub mod budget;
+pub mod cache;
+pub mod concrete;
+pub mod context;
+pub mod copies;
+pub mod cosim;
+pub mod energy;
 pub mod executor;
+pub mod hardware;
 pub mod instruction;
+pub mod interrupts;
+pub mod jump_table;
+pub mod mmio;
+pub mod naming;
+pub mod nvic;
+pub mod path_data;
 pub mod path_selection;
+pub mod peripheral_model;
+pub mod pipeline;
+pub mod profile;
 pub mod project;
+pub mod races;
+pub mod range_analysis;
+pub mod reentrancy;
+pub mod resources;
 pub mod run_config;
+pub mod sandbox;
+pub mod side_effects;
+pub mod slice;
+pub mod stack_locals;
 pub mod state;
+pub mod summaries;
+pub mod temporal;
+pub mod time_travel;
+pub mod trace_import;
 pub mod vm;
+pub mod watchdog;
 
-use arch::ArchError;
+use arch::{ArchError, ExecutionState};
 pub use run_config::*;
 
 pub type Result<T> = std::result::Result<T, GAError>;
@@ -34,6 +66,42 @@ pub enum GAError {
 
     #[error("Architecture error.")]
     ArchError(#[from] ArchError),
+
+    #[error("Register {0} is not defined by the architecture.")]
+    UnknownRegister(String),
+
+    #[error("Flag {0} is not defined by the architecture.")]
+    UnknownFlag(String),
+
+    #[error("Memory at {0:#X} is read before it is written.")]
+    UninitializedMemory(u64),
+
+    #[error("No path reached symex_must_reach.")]
+    MustReachNotReached,
+
+    #[error("Not supported in concrete execution: {0}.")]
+    UnsupportedInConcreteExecution(&'static str),
+
+    #[error("Branched to {1:#X} in {0:?} state, which the architecture does not support.")]
+    UnsupportedExecutionState(ExecutionState, u64),
+
+    #[error("The state before instruction {0} can not be reconstructed.")]
+    StateNotRecorded(usize),
+
+    #[error("{0}")]
+    HookPanicked(Box<sandbox::HookPanic>),
+
+    #[error("CONTROL.SPSEL can be both set and clear, the stack in use is not known.")]
+    SymbolicStackSelection,
+
+    #[error("The path left the recorded trace at {found:#X}, it continues at {expected:#X}.")]
+    TraceDiverged { expected: u64, found: u64 },
+
+    #[error("The stack pointer is symbolic when entering the interrupt handler {0}.")]
+    SymbolicStackPointer(String),
+
+    #[error("The program counter is symbolic at an unpredictable instruction.")]
+    SymbolicProgramCounter,
 }
 
 #[derive(Debug, Clone, Copy)]
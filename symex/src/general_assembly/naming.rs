@@ -0,0 +1,94 @@
+//! Names of the symbols that are created implicitly.
+//!
+//! Symbols that the analyzed program does not create itself, such as the
+//! initial value of a register or a read from uninitialized memory, are named
+//! after where they come from so that models and constraints read like the
+//! source program. A register is qualified by the function it is first read
+//! in, as `R0@app::parse`, and an address by the peripheral model, the register
+//! of a [`hardware`](super::hardware) model, the local on the stack (see
+//! [`stack_locals`](super::stack_locals)) or the variable of the symbol table
+//! that contains it, as `UART.0x4`, `UART.SR`, `app::parse::len` or
+//! `app::BUFFER+0x8`. Names fall back to the register or the address.
+
+use rustc_demangle::demangle;
+
+use super::{arch::Arch, state::GAState};
+
+/// Demangles `symbol` without the hash of Rust symbols.
+fn readable(symbol: &str) -> String {
+    format!("{:#}", demangle(symbol))
+}
+
+impl<A: Arch> GAState<A> {
+    /// Get the name of the function that contains `address`, if the symbol
+    /// table has one that covers it.
+    pub fn function_name(&self, address: u64) -> Option<String> {
+        self.project
+            .get_symbol_containing(address & !0b1)
+            .map(|(symbol, _)| readable(symbol))
+    }
+
+    /// Get the name of the initial value of `register`, which is read at the
+    /// last executed instruction.
+    pub fn register_symbol_name(&self, register: &str) -> String {
+        match self.function_name(self.last_pc) {
+            Some(function) => format!("{register}@{function}"),
+            None => register.to_owned(),
+        }
+    }
+
+    /// Get the name of a value read from `address`.
+    pub fn memory_symbol_name(&self, address: u64) -> String {
+        if let Some((_, model)) = self.project.get_peripheral_model(address) {
+            return format!("{}.{:#X}", model.name, address - model.base);
+        }
+        if let Some(peripheral) = self.project.get_mapped_peripheral(address) {
+            return match peripheral.register_at(address) {
+                Some(register) if peripheral.base + register.offset == address => {
+                    format!("{}.{}", peripheral.name, register.name)
+                }
+                Some(register) => format!(
+                    "{}.{}+{:#X}",
+                    peripheral.name,
+                    register.name,
+                    address - peripheral.base - register.offset
+                ),
+                None => format!("{}.{:#X}", peripheral.name, address - peripheral.base),
+            };
+        }
+        if self.project.get_stack_locals() {
+            if let Some(name) = self.stack_local_name(address) {
+                return name;
+            }
+        }
+        match self.project.get_symbol_containing(address) {
+            Some((symbol, 0)) => readable(symbol),
+            Some((symbol, offset)) => format!("{}+{offset:#X}", readable(symbol)),
+            None => format!("memory.{address:#X}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::general_assembly::{arch::arm::v6::ArmV6M, project::Project, state::GAState};
+
+    #[test]
+    fn test_symbol_names() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.add_symbol("_ZN3app5parse17h0123456789abcdefE", 0x101, 0x40);
+        project.add_symbol("_ZN3app6BUFFER17h0123456789abcdefE", 0x2000_0000, 0x10);
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+
+        state.last_pc = 0x110;
+        assert_eq!(state.register_symbol_name("R0"), "R0@app::parse");
+        state.last_pc = 0x200;
+        assert_eq!(state.register_symbol_name("R0"), "R0");
+        assert_eq!(state.memory_symbol_name(0x2000_0000), "app::BUFFER");
+        assert_eq!(state.memory_symbol_name(0x2000_0008), "app::BUFFER+0x8");
+        assert_eq!(state.memory_symbol_name(0x2000_0010), "memory.0x20000010");
+    }
+}
@@ -0,0 +1,370 @@
+//! Model of the enable, pending and active registers of the NVIC.
+//!
+//! With the [`nvic`](super::RunConfig::nvic) model the set-enable,
+//! clear-enable, set-pending, clear-pending and active bit registers of the
+//! interrupt controller hold symbolic words per path instead of reading as
+//! unconstrained values. An [`Interrupt`](super::interrupts::Interrupt) with an
+//! [`irq`](super::interrupts::Interrupt::irq) number is only injected while it
+//! may be enabled, an interrupt the firmware pends while it is enabled and
+//! unmasked is taken before the next instruction, and entering a handler clears
+//! its pending bit and sets its active bit until it returns.
+//!
+//! The `PendSV` and `SysTick` system exceptions are pended and cleared through
+//! the interrupt control and state register, an
+//! [`exception`](super::interrupts::Interrupt::exception) pended by the
+//! firmware is taken like a pended interrupt. This is how kernels request a
+//! context switch.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use tracing::warn;
+
+use super::{arch::Arch, executor::GAExecutor, mmio::AccessKind, Result};
+use crate::smt::{DContext, DExpr};
+
+/// Address of the first interrupt set-enable register.
+pub const ISER: u64 = 0xE000_E100;
+
+/// Address of the first interrupt clear-enable register.
+pub const ICER: u64 = 0xE000_E180;
+
+/// Address of the first interrupt set-pending register.
+pub const ISPR: u64 = 0xE000_E200;
+
+/// Address of the first interrupt clear-pending register.
+pub const ICPR: u64 = 0xE000_E280;
+
+/// Address of the first interrupt active bit register.
+pub const IABR: u64 = 0xE000_E300;
+
+/// Address of the interrupt control and state register.
+pub const ICSR: u64 = 0xE000_ED04;
+
+/// Size of each bank of registers, 16 words of 32 interrupts.
+const BANK_SIZE: u64 = 0x40;
+
+/// A system exception that is pended through `ICSR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SystemException {
+    /// The pendable service call used for context switches.
+    PendSV,
+
+    /// The system timer.
+    SysTick,
+}
+
+impl SystemException {
+    /// Bits of `ICSR` that pend and clear the exception.
+    const fn icsr_bits(self) -> (u64, u64) {
+        match self {
+            Self::PendSV => (28, 27),
+            Self::SysTick => (26, 25),
+        }
+    }
+}
+
+impl std::fmt::Display for SystemException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PendSV => write!(f, "PendSV"),
+            Self::SysTick => write!(f, "SysTick"),
+        }
+    }
+}
+
+/// A bit of each interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NvicBank {
+    /// The interrupt is enabled.
+    Enabled,
+
+    /// The interrupt is pending.
+    Pending,
+
+    /// The handler of the interrupt runs.
+    Active,
+}
+
+impl std::fmt::Display for NvicBank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Enabled => write!(f, "enabled"),
+            Self::Pending => write!(f, "pending"),
+            Self::Active => write!(f, "active"),
+        }
+    }
+}
+
+/// Decodes the register at `address` into the bank it accesses, whether a
+/// write sets or clears bits, `None` if it is read only, and the index of the
+/// word.
+fn decode(address: u64) -> Option<(NvicBank, Option<bool>, u64)> {
+    let (bank, set, base) = match address {
+        ISER..ICER => (NvicBank::Enabled, Some(true), ISER),
+        ICER..ISPR => (NvicBank::Enabled, Some(false), ICER),
+        ISPR..ICPR => (NvicBank::Pending, Some(true), ISPR),
+        ICPR..IABR => (NvicBank::Pending, Some(false), ICPR),
+        _ if (IABR..IABR + BANK_SIZE).contains(&address) => (NvicBank::Active, None, IABR),
+        _ => return None,
+    };
+    Some((bank, set, (address - base) / 4))
+}
+
+/// The NVIC bits of a path, by bank and word. Words that are not stored are
+/// zero as after reset.
+#[derive(Debug, Clone, Default)]
+pub struct NvicState {
+    words: BTreeMap<(NvicBank, u64), DExpr>,
+
+    /// System exceptions pended through `ICSR`.
+    system_pending: BTreeSet<SystemException>,
+}
+
+impl NvicState {
+    /// Get the word `index` of `bank`.
+    fn word(&self, ctx: &DContext, bank: NvicBank, index: u64) -> DExpr {
+        self.words
+            .get(&(bank, index))
+            .cloned()
+            .unwrap_or_else(|| ctx.zero(32))
+    }
+
+    /// Get the bit of `irq` in `bank` as a one bit expression.
+    pub fn bit(&self, ctx: &DContext, bank: NvicBank, irq: u32) -> DExpr {
+        let bit = irq % 32;
+        self.word(ctx, bank, irq as u64 / 32).slice(bit, bit)
+    }
+
+    /// Sets or clears the bit of `irq` in `bank`.
+    pub fn set_bit(&mut self, ctx: &DContext, bank: NvicBank, irq: u32, set: bool) {
+        let index = irq as u64 / 32;
+        let mask = ctx.from_u64(1 << (irq % 32), 32);
+        let word = self.word(ctx, bank, index);
+        let word = match set {
+            true => word.or(&mask),
+            false => word.and(&mask.not()),
+        };
+        self.words.insert((bank, index), word.simplify());
+    }
+
+    /// Checks if the system exception `exception` is pending.
+    pub fn is_system_pending(&self, exception: SystemException) -> bool {
+        self.system_pending.contains(&exception)
+    }
+
+    /// Pends or clears the system exception `exception`.
+    pub fn set_system_pending(&mut self, exception: SystemException, pending: bool) {
+        match pending {
+            true => self.system_pending.insert(exception),
+            false => self.system_pending.remove(&exception),
+        };
+    }
+
+    /// Get the interrupts whose bit is set in each bank, symbolic words are
+    /// shown as expressions.
+    pub fn describe(&self) -> Vec<(NvicBank, String)> {
+        let mut banks: BTreeMap<NvicBank, Vec<String>> = BTreeMap::new();
+        banks.insert(
+            NvicBank::Pending,
+            self.system_pending
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        for ((bank, index), word) in &self.words {
+            let bits = banks.entry(*bank).or_default();
+            match word.get_constant() {
+                Some(word) => bits.extend(
+                    (0..32)
+                        .filter(|bit| word >> bit & 1 == 1)
+                        .map(|bit| format!("IRQ {}", index * 32 + bit)),
+                ),
+                None => bits.push(format!("word {index}: {word:?}")),
+            }
+        }
+        banks
+            .into_iter()
+            .filter(|(_, bits)| !bits.is_empty())
+            .map(|(bank, bits)| (bank, bits.join(", ")))
+            .collect()
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Checks if `address` is in the system region of the architecture, where
+    /// the NVIC is mapped.
+    pub(super) fn in_system_region(&self, address: u64) -> bool {
+        self.state
+            .architecture
+            .system_region()
+            .is_some_and(|region| region.contains(&address))
+    }
+
+    /// Handles an access to the NVIC registers, returns the value of the
+    /// access if `address` is one of them and the NVIC is modeled.
+    pub(super) fn access_nvic(
+        &mut self,
+        kind: AccessKind,
+        address: u64,
+        bits: u32,
+        written: Option<&DExpr>,
+    ) -> Result<Option<DExpr>> {
+        if !self.project.get_nvic() || !self.in_system_region(address) {
+            return Ok(None);
+        }
+        if address == ICSR && bits == 32 {
+            return Ok(Some(self.access_icsr(kind, written)));
+        }
+        let Some((bank, set, index)) = decode(address) else {
+            return Ok(None);
+        };
+        let ctx = &self.state.ctx;
+        let shift = ctx.from_u64((address & 0b11) * 8, 32);
+        let nvic = &mut self.state.interrupts.nvic;
+        let word = nvic.word(ctx, bank, index);
+        match (kind, written, set) {
+            (AccessKind::Write, Some(written), Some(set)) => {
+                let bits = written.resize_unsigned(32).sll(&shift);
+                let word = match set {
+                    true => word.or(&bits),
+                    false => word.and(&bits.not()),
+                };
+                nvic.words.insert((bank, index), word.simplify());
+                Ok(Some(written.clone()))
+            }
+            // The active bits are read only.
+            (AccessKind::Write, Some(written), None) => Ok(Some(written.clone())),
+            _ => Ok(Some(word.srl(&shift).resize_unsigned(bits).simplify())),
+        }
+    }
+
+    /// Handles an access to `ICSR`, only the pending bits of the system
+    /// exceptions are modeled.
+    fn access_icsr(&mut self, kind: AccessKind, written: Option<&DExpr>) -> DExpr {
+        let exceptions = [SystemException::PendSV, SystemException::SysTick];
+        let nvic = &mut self.state.interrupts.nvic;
+        match (kind, written) {
+            (AccessKind::Write, Some(written)) => {
+                let Some(value) = written.get_constant() else {
+                    warn!("Ignoring a symbolic write to ICSR");
+                    return written.clone();
+                };
+                for exception in exceptions {
+                    let (set, clear) = exception.icsr_bits();
+                    if value >> clear & 1 == 1 {
+                        nvic.set_system_pending(exception, false);
+                    }
+                    if value >> set & 1 == 1 {
+                        nvic.set_system_pending(exception, true);
+                    }
+                }
+                written.clone()
+            }
+            _ => {
+                let value = exceptions
+                    .into_iter()
+                    .filter(|exception| nvic.is_system_pending(*exception))
+                    .fold(0, |value, exception| value | 1 << exception.icsr_bits().0);
+                self.state.ctx.from_u64(value, 32)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::{NvicBank, SystemException, ICER, ICSR, ISER, ISPR};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        context::EXC_RETURN_MSP,
+        executor::GAExecutor,
+        interrupts::Interrupt,
+        mmio::AccessKind,
+        project::{PCHook, Project},
+        state::GAState,
+        vm::VM,
+    };
+
+    #[test]
+    fn test_nvic() {
+        let mut project = Project::<ArmV6M>::test_project().with_symbols(HashMap::from([
+            ("uart".to_owned(), 0x200),
+            ("timer".to_owned(), 0x300),
+            ("pendsv".to_owned(), 0x400),
+        ]));
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_pc_hook(0x300, PCHook::Continue);
+        project.add_pc_hook(0x400, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("uart").with_irq(33));
+        project.add_interrupt(Interrupt::new("timer").with_irq(2));
+        project.add_interrupt(Interrupt::new("pendsv").with_exception(SystemException::PendSV));
+        project.set_nvic(true);
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let write = |executor: &mut GAExecutor<'_, ArmV6M>, address, value| {
+            let value = context.from_u64(value, 32);
+            let access = executor.access_nvic(AccessKind::Write, address, 32, Some(&value));
+            assert!(access.unwrap().is_some());
+        };
+
+        // Nothing is enabled after reset.
+        executor.inject_interrupts().unwrap();
+        assert!(executor.vm.paths.get_path().is_none());
+
+        // Enables both and disables the timer again.
+        write(&mut executor, ISER, 0b100);
+        write(&mut executor, ISER + 4, 0b10);
+        write(&mut executor, ICER, 0b100);
+        let read = executor
+            .access_nvic(AccessKind::Read, ISER + 4, 8, None)
+            .unwrap();
+        assert_eq!(read.unwrap().get_constant(), Some(0b10));
+        let enabled = &executor.state.interrupts.nvic;
+        assert_eq!(enabled.describe(), vec![(
+            NvicBank::Enabled,
+            "IRQ 33".to_owned()
+        )]);
+        executor.inject_interrupts().unwrap();
+        let uart = executor.vm.paths.get_path().unwrap().state;
+        assert_eq!(uart.interrupts.taken[0].handler, "uart");
+        assert!(executor.vm.paths.get_path().is_none());
+
+        // Pending an enabled interrupt takes it right away.
+        write(&mut executor, ISER, 0b100);
+        write(&mut executor, ISPR, 0b100);
+        executor.inject_interrupts().unwrap();
+        assert!(executor.vm.paths.get_path().is_none());
+        assert!(executor.state.interrupts.in_handler());
+        let nvic = &executor.state.interrupts.nvic;
+        let bit = |bank| nvic.bit(&context, bank, 2).get_constant();
+        assert_eq!(
+            (bit(NvicBank::Pending), bit(NvicBank::Active)),
+            (Some(0), Some(1))
+        );
+
+        let ret = context.from_u64(EXC_RETURN_MSP, 32);
+        executor.state.set_register("PC".to_owned(), ret).unwrap();
+        executor.return_from_interrupt().unwrap();
+        let nvic = &executor.state.interrupts.nvic;
+        assert_eq!(
+            nvic.bit(&context, NvicBank::Active, 2).get_constant(),
+            Some(0)
+        );
+
+        // The firmware requests a context switch.
+        write(&mut executor, ICSR, 1 << 28);
+        let read = executor.access_nvic(AccessKind::Read, ICSR, 32, None);
+        assert_eq!(read.unwrap().unwrap().get_constant(), Some(1 << 28));
+        executor.inject_interrupts().unwrap();
+        let taken = executor.state.interrupts.taken.last().unwrap();
+        assert_eq!(taken.handler, "pendsv");
+        let nvic = &executor.state.interrupts.nvic;
+        assert!(!nvic.is_system_pending(SystemException::PendSV));
+    }
+}
@@ -0,0 +1,158 @@
+//! Typed data attached to a path by hooks.
+//!
+//! Analyses that are built from hooks, such as collecting the writes to the
+//! stack pointer or tracking the state of a protocol, keep their results in the
+//! [`PathData`] of the state. The data is moved to the result of the path when
+//! it ends, see [`VisualPathResult`](crate::elf_util::VisualPathResult), so it
+//! is reported together with the standard results of the path.
+//!
+//! Each path holds at most one value of each type. Forked paths share their
+//! values until one of them modifies a value.
+
+use std::{
+    any::{type_name, Any, TypeId},
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+/// A value that can be attached to a path.
+///
+/// Implemented for every type that can be printed with [`Debug`] and shared
+/// between threads.
+pub trait Payload: Any + Debug + Send + Sync {
+    /// Get the value as [`Any`] to downcast it.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Get the value as [`Any`] to downcast it for modification.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any + Debug + Send + Sync> Payload for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The data attached to a path, at most one value of each type.
+#[derive(Clone, Default)]
+pub struct PathData {
+    /// Type, type name and value of each entry in insertion order.
+    entries: Vec<(TypeId, &'static str, Arc<dyn Payload>)>,
+}
+
+impl PathData {
+    /// Creates an empty set of data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if no data is attached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Attaches `value`, replacing the value of the same type if there is one.
+    pub fn insert<T: Payload>(&mut self, value: T) {
+        let value = Arc::new(value);
+        match self.position::<T>() {
+            Some(index) => self.entries[index].2 = value,
+            None => self
+                .entries
+                .push((TypeId::of::<T>(), type_name::<T>(), value)),
+        }
+    }
+
+    /// Get the value of type `T`.
+    pub fn get<T: Payload>(&self) -> Option<&T> {
+        let index = self.position::<T>()?;
+        (*self.entries[index].2).as_any().downcast_ref()
+    }
+
+    /// Get the value of type `T` for modification.
+    ///
+    /// A value that is shared with a forked path is copied first.
+    pub fn get_mut<T: Payload + Clone>(&mut self) -> Option<&mut T> {
+        let index = self.position::<T>()?;
+        let entry = &mut self.entries[index].2;
+        if Arc::get_mut(entry).is_none() {
+            let value: T = (**entry).as_any().downcast_ref::<T>()?.clone();
+            *entry = Arc::new(value);
+        }
+        let value: &mut dyn Payload = Arc::get_mut(entry)?;
+        value.as_any_mut().downcast_mut()
+    }
+
+    /// Get the value of type `T` for modification, attaching the default value
+    /// if there is none.
+    pub fn get_or_default<T: Payload + Clone + Default>(&mut self) -> &mut T {
+        if self.position::<T>().is_none() {
+            self.insert(T::default());
+        }
+        self.get_mut()
+            .expect("a value of the type was just inserted")
+    }
+
+    /// Iterates over the type names and values of the data in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &dyn Payload)> {
+        self.entries
+            .iter()
+            .map(|(_, name, value)| (*name, value.as_ref()))
+    }
+
+    fn position<T: Payload>(&self) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(id, _, _)| *id == TypeId::of::<T>())
+    }
+}
+
+impl Debug for PathData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PathData;
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct SpWrites(Vec<u64>);
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Protocol {
+        Idle,
+        Connected,
+    }
+
+    #[test]
+    fn test_path_data() {
+        let mut data = PathData::new();
+        assert!(data.is_empty());
+        assert_eq!(data.get::<SpWrites>(), None);
+
+        data.get_or_default::<SpWrites>().0.push(0x2000_0000);
+        data.insert(Protocol::Idle);
+
+        // Forked paths do not see each others modifications.
+        let mut fork = data.clone();
+        fork.get_or_default::<SpWrites>().0.push(0x2000_0010);
+        fork.insert(Protocol::Connected);
+
+        assert_eq!(data.get::<SpWrites>(), Some(&SpWrites(vec![0x2000_0000])));
+        assert_eq!(data.get::<Protocol>(), Some(&Protocol::Idle));
+        assert_eq!(
+            fork.get::<SpWrites>(),
+            Some(&SpWrites(vec![0x2000_0000, 0x2000_0010]))
+        );
+        assert_eq!(fork.get::<Protocol>(), Some(&Protocol::Connected));
+
+        let names: Vec<_> = fork.iter().map(|(name, _)| name).collect();
+        assert!(names[0].ends_with("SpWrites"));
+        assert!(names[1].ends_with("Protocol"));
+    }
+}
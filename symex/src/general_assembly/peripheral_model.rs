@@ -0,0 +1,517 @@
+//! Abstract models of peripherals as nondeterministic automata.
+//!
+//! A [`PeripheralModel`] describes the allowed behavior of a memory mapped
+//! peripheral as a small state machine. Each transition is taken by a read or
+//! a write of a register of the peripheral, reads return the value of the
+//! transition or a fresh symbol and writes may be restricted to a value. When
+//! several transitions are enabled by an access the path is forked for each of
+//! them after the instruction, and an access that no transition allows fails
+//! the path. Accesses to a modeled peripheral do not reach memory.
+//!
+//! The models are loaded with [`parse_peripheral_models`] from a line based
+//! text format. The first state of a peripheral is its initial state, each
+//! transition is `read` or `write`, the state it leaves, the offset of the
+//! register, the state it enters and optionally the value:
+//!
+//! ```text
+//! # A transmitter that is busy after a byte is written.
+//! peripheral uart 0x40004000 0x400
+//! state idle
+//! state busy
+//! write idle 0x4 busy
+//! read idle 0x0 idle 0x1
+//! read busy 0x0 busy 0x0
+//! read busy 0x0 idle 0x1
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+use tracing::debug;
+
+use super::{
+    arch::Arch,
+    executor::GAExecutor,
+    mmio::AccessKind,
+    path_selection::Path,
+    state::SymbolOrigin,
+    Result,
+};
+use crate::smt::DExpr;
+
+/// Reported as the reason of the failure of a path that accesses a modeled
+/// peripheral in a way its model does not allow.
+pub const PERIPHERAL_VIOLATION: &str = "peripheral access not allowed by its model";
+
+/// Reported as the reason of the failure of a path where an instruction
+/// accesses a peripheral again after a nondeterministic transition.
+pub const PERIPHERAL_UNRESOLVED: &str =
+    "peripheral accessed again before its nondeterministic transition was resolved";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PeripheralModelError {
+    #[error("Malformed line {0} in peripheral models.")]
+    MalformedLine(usize),
+
+    #[error("Line {0} of the peripheral models does not belong to a peripheral.")]
+    MissingPeripheral(usize),
+
+    #[error("Unknown state {state:?} on line {line} of the peripheral models.")]
+    UnknownState { line: usize, state: String },
+
+    #[error("Peripheral {0} has no states.")]
+    NoStates(String),
+}
+
+/// A transition of a [`PeripheralModel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    /// Index of the state the transition leaves.
+    pub from: usize,
+
+    /// Direction of the access that takes the transition.
+    pub kind: AccessKind,
+
+    /// Offset of the accessed register from the base of the peripheral.
+    pub offset: u64,
+
+    /// Index of the state the transition enters.
+    pub to: usize,
+
+    /// Value a read returns or a write has to write, `None` for a fresh
+    /// symbol or any written value.
+    pub value: Option<u64>,
+}
+
+/// The allowed behavior of a memory mapped peripheral.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeripheralModel {
+    /// Name of the peripheral.
+    pub name: String,
+
+    /// Address of the first register.
+    pub base: u64,
+
+    /// Number of bytes of the address range of the peripheral.
+    pub size: u64,
+
+    /// Names of the states, the first state is the initial state.
+    pub states: Vec<String>,
+
+    pub transitions: Vec<Transition>,
+}
+
+impl PeripheralModel {
+    /// Checks if `address` is a register of the peripheral.
+    pub fn contains(&self, address: u64) -> bool {
+        (self.base..self.base + self.size).contains(&address)
+    }
+
+    /// Get the transitions from `state` taken by a `kind` access of
+    /// `address`.
+    pub fn enabled(&self, state: usize, kind: AccessKind, address: u64) -> Vec<Transition> {
+        self.transitions
+            .iter()
+            .filter(|transition| {
+                transition.from == state
+                    && transition.kind == kind
+                    && self.base + transition.offset == address
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// Parses peripheral models in the text format of
+/// [`peripheral_model`](self).
+///
+/// Lines starting with `#` are comments, numbers are hexadecimal with a `0x`
+/// prefix.
+pub fn parse_peripheral_models(
+    input: &str,
+) -> std::result::Result<Vec<PeripheralModel>, PeripheralModelError> {
+    let mut models: Vec<PeripheralModel> = vec![];
+    let hex = |text: &str| {
+        text.strip_prefix("0x")
+            .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+    };
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let malformed = PeripheralModelError::MalformedLine(line_number);
+
+        if let ["peripheral", name, base, size] = words.as_slice() {
+            let (Some(base), Some(size)) = (hex(base), hex(size)) else {
+                return Err(malformed);
+            };
+            if let Some(model) = models.last().filter(|model| model.states.is_empty()) {
+                return Err(PeripheralModelError::NoStates(model.name.clone()));
+            }
+            models.push(PeripheralModel {
+                name: (*name).to_owned(),
+                base,
+                size,
+                states: vec![],
+                transitions: vec![],
+            });
+            continue;
+        }
+        let model = models
+            .last_mut()
+            .ok_or(PeripheralModelError::MissingPeripheral(line_number))?;
+        let state = |name: &str| {
+            model
+                .states
+                .iter()
+                .position(|state| state == name)
+                .ok_or_else(|| PeripheralModelError::UnknownState {
+                    line: line_number,
+                    state: name.to_owned(),
+                })
+        };
+        match words.as_slice() {
+            ["state", name] => model.states.push((*name).to_owned()),
+            [kind @ ("read" | "write"), from, offset, to, value @ ..] if value.len() <= 1 => {
+                let transition = Transition {
+                    from: state(from)?,
+                    kind: match *kind {
+                        "read" => AccessKind::Read,
+                        _ => AccessKind::Write,
+                    },
+                    offset: hex(offset).ok_or(malformed.clone())?,
+                    to: state(to)?,
+                    value: match value.first() {
+                        Some(value) => Some(hex(value).ok_or(malformed)?),
+                        None => None,
+                    },
+                };
+                model.transitions.push(transition);
+            }
+            _ => return Err(malformed),
+        }
+    }
+    if let Some(model) = models.last().filter(|model| model.states.is_empty()) {
+        return Err(PeripheralModelError::NoStates(model.name.clone()));
+    }
+    Ok(models)
+}
+
+/// A nondeterministic choice between transitions that is resolved after the
+/// instruction.
+#[derive(Debug, Clone)]
+struct PendingChoice {
+    /// Index of the peripheral.
+    peripheral: usize,
+
+    /// Symbol that selects the transition.
+    choice: DExpr,
+
+    /// States entered by the transitions, by choice.
+    targets: Vec<usize>,
+}
+
+/// States of the modeled peripherals on a path.
+#[derive(Debug, Clone, Default)]
+pub struct PeripheralStates {
+    /// Current state of each peripheral by index, the peripherals that have
+    /// not been accessed are in their initial state.
+    states: HashMap<usize, usize>,
+
+    /// Choices of the current instruction.
+    pending: Vec<PendingChoice>,
+
+    /// Reason the path fails, set by an access the model does not allow.
+    violation: Option<&'static str>,
+}
+
+impl PeripheralStates {
+    /// Get the current state of the peripheral at `index`.
+    pub fn state(&self, index: usize) -> usize {
+        self.states.get(&index).copied().unwrap_or(0)
+    }
+
+    /// Get the states of the accessed peripherals by index.
+    pub fn states(&self) -> BTreeMap<usize, usize> {
+        self.states.iter().map(|(k, v)| (*k, *v)).collect()
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Takes the transition of a modeled peripheral for a `kind` access of
+    /// `bits` bits at `address`, `written` is the written value.
+    ///
+    /// Returns the read value, or the written value for writes, and `None`
+    /// if `address` is not in a modeled peripheral.
+    pub(super) fn access_peripheral(
+        &mut self,
+        kind: AccessKind,
+        address: u64,
+        bits: u32,
+        written: Option<&DExpr>,
+    ) -> Result<Option<DExpr>> {
+        let Some((index, model)) = self.project.get_peripheral_model(address) else {
+            return Ok(None);
+        };
+        let ctx = self.state.ctx.clone();
+        let symbol = |state: &mut super::state::GAState<A>| {
+            let name = state.memory_symbol_name(address);
+            state.new_symbol(bits, &name, SymbolOrigin::MemoryRead(address))
+        };
+        let peripherals = &mut self.state.peripherals;
+        if peripherals.violation.is_some() {
+            return Ok(Some(
+                written.cloned().unwrap_or_else(|| symbol(&mut self.state)),
+            ));
+        }
+        if peripherals
+            .pending
+            .iter()
+            .any(|pending| pending.peripheral == index)
+        {
+            peripherals.violation = Some(PERIPHERAL_UNRESOLVED);
+            return Ok(Some(
+                written.cloned().unwrap_or_else(|| symbol(&mut self.state)),
+            ));
+        }
+
+        let enabled = model.enabled(peripherals.state(index), kind, address);
+        // A write has to be allowed by a transition for every written value.
+        let allowed = match written {
+            Some(written) => enabled
+                .iter()
+                .map(|transition| match transition.value {
+                    Some(value) => written.eq(&ctx.from_u64(value, written.len())),
+                    None => ctx.from_bool(true),
+                })
+                .reduce(|any, allowed| any.or(&allowed)),
+            None => (!enabled.is_empty()).then(|| ctx.from_bool(true)),
+        };
+        let violated = match &allowed {
+            Some(allowed) => self
+                .state
+                .constraints
+                .is_sat_with_constraint(&allowed.not())?,
+            None => true,
+        };
+        if violated {
+            debug!(
+                "{:?} of {:#X} is not allowed by the model of {}",
+                kind, address, model.name
+            );
+            self.state.peripherals.violation = Some(PERIPHERAL_VIOLATION);
+            return Ok(Some(
+                written.cloned().unwrap_or_else(|| symbol(&mut self.state)),
+            ));
+        }
+
+        let values = enabled
+            .iter()
+            .map(|transition| match (written, transition.value) {
+                (Some(written), _) => written.clone(),
+                (None, Some(value)) => ctx.from_u64(value, bits),
+                (None, None) => symbol(&mut self.state),
+            })
+            .collect::<Vec<_>>();
+        let targets = enabled
+            .iter()
+            .map(|transition| transition.to)
+            .collect::<Vec<_>>();
+        if let [transition] = enabled.as_slice() {
+            self.state.peripherals.states.insert(index, transition.to);
+            return Ok(Some(values[0].clone()));
+        }
+
+        // Selects the transition, resolved by forking after the instruction.
+        let name = format!(
+            "{}.choice{}",
+            model.name,
+            self.state.get_instruction_count()
+        );
+        let choice = ctx.unconstrained(32, &name);
+        let mut selected = vec![];
+        let mut value = values[0].clone();
+        for (k, transition) in enabled.iter().enumerate() {
+            let chosen = choice.eq(&ctx.from_u64(k as u64, 32));
+            let condition = match (written, transition.value) {
+                (Some(written), Some(guard)) => {
+                    chosen.and(&written.eq(&ctx.from_u64(guard, written.len())))
+                }
+                _ => chosen.clone(),
+            };
+            selected.push(condition);
+            if k > 0 {
+                value = chosen.ite(&values[k], &value);
+            }
+        }
+        let selected = selected
+            .into_iter()
+            .reduce(|any, condition| any.or(&condition))
+            .expect("more than one transition is enabled");
+        self.state.constraints.assert(&selected);
+        self.state.peripherals.pending.push(PendingChoice {
+            peripheral: index,
+            choice,
+            targets,
+        });
+        Ok(Some(value))
+    }
+
+    /// Forks the path for each nondeterministic transition of the instruction
+    /// and returns the reason the path fails if an access was not allowed.
+    pub(super) fn resolve_peripherals(&mut self) -> Result<Option<&'static str>> {
+        if let Some(violation) = self.state.peripherals.violation {
+            return Ok(Some(violation));
+        }
+        while let Some(pending) = self.state.peripherals.pending.pop() {
+            let mut feasible = vec![];
+            for (k, target) in pending.targets.iter().enumerate() {
+                let chosen = pending.choice.eq(&self.state.ctx.from_u64(k as u64, 32));
+                if self.state.constraints.is_sat_with_constraint(&chosen)? {
+                    feasible.push((chosen, *target));
+                }
+            }
+            let Some(((chosen, target), rest)) = feasible.split_first() else {
+                continue;
+            };
+            for (other, target) in rest {
+                let mut forked_state = self.state.clone();
+                forked_state
+                    .peripherals
+                    .states
+                    .insert(pending.peripheral, *target);
+                forked_state.trace_position = None;
+                self.vm
+                    .paths
+                    .save_path(Path::new(forked_state, Some(other.clone())));
+            }
+            self.state.constraints.assert(chosen);
+            self.state
+                .peripherals
+                .states
+                .insert(pending.peripheral, *target);
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{parse_peripheral_models, PeripheralModelError, Transition, PERIPHERAL_VIOLATION};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        mmio::AccessKind,
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    const UART: &str = "peripheral uart 0x40004000 0x400
+state idle
+state busy
+write idle 0x4 busy
+read idle 0x0 idle 0x1
+read busy 0x0 busy 0x0
+read busy 0x0 idle 0x1
+";
+
+    #[test]
+    fn test_parse_peripheral_models() {
+        let models = parse_peripheral_models(
+            "# uart\nperipheral uart 0x40004000 0x400\nstate idle\nstate busy\nwrite idle 0x4 \
+             busy\nread busy 0x0 idle 0x1\n",
+        )
+        .unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].states, vec!["idle", "busy"]);
+        assert_eq!(models[0].transitions, vec![
+            Transition {
+                from: 0,
+                kind: AccessKind::Write,
+                offset: 4,
+                to: 1,
+                value: None
+            },
+            Transition {
+                from: 1,
+                kind: AccessKind::Read,
+                offset: 0,
+                to: 0,
+                value: Some(1)
+            },
+        ]);
+        assert!(models[0].contains(0x4000_43FF));
+        assert_eq!(models[0].enabled(1, AccessKind::Read, 0x4000_4000).len(), 1);
+        assert!(models[0]
+            .enabled(0, AccessKind::Read, 0x4000_4000)
+            .is_empty());
+
+        assert_eq!(
+            parse_peripheral_models("state idle\n"),
+            Err(PeripheralModelError::MissingPeripheral(1))
+        );
+        assert_eq!(
+            parse_peripheral_models("peripheral a 0x0 0x4\nstate s\nread s 0x0 t\n"),
+            Err(PeripheralModelError::UnknownState {
+                line: 3,
+                state: "t".to_owned()
+            })
+        );
+        assert_eq!(
+            parse_peripheral_models("peripheral a 0x0 0x4\n"),
+            Err(PeripheralModelError::NoStates("a".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_peripheral_transitions() {
+        let mut project = Project::<ArmV6M>::test_project();
+        for model in parse_peripheral_models(UART).unwrap() {
+            project.add_peripheral_model(model);
+        }
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+
+        let status = executor
+            .access_peripheral(AccessKind::Read, 0x4000_4000, 32, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.get_constant(), Some(1));
+        let byte = context.from_u64(0x41, 8);
+        executor
+            .access_peripheral(AccessKind::Write, 0x4000_4004, 8, Some(&byte))
+            .unwrap();
+        assert_eq!(executor.resolve_peripherals().unwrap(), None);
+        assert_eq!(executor.state.peripherals.state(0), 1);
+
+        // Busy either stays busy or becomes idle.
+        executor
+            .access_peripheral(AccessKind::Read, 0x4000_4000, 32, None)
+            .unwrap();
+        assert_eq!(executor.resolve_peripherals().unwrap(), None);
+        assert_eq!(executor.state.peripherals.state(0), 1);
+
+        // The data register can not be written while busy.
+        executor
+            .access_peripheral(AccessKind::Write, 0x4000_4004, 8, Some(&byte))
+            .unwrap();
+        assert_eq!(
+            executor.resolve_peripherals().unwrap(),
+            Some(PERIPHERAL_VIOLATION)
+        );
+
+        let forked = vm.paths.get_path().unwrap().state;
+        assert_eq!(forked.peripherals.state(0), 0);
+        assert!(vm.paths.get_path().is_none());
+    }
+}
@@ -0,0 +1,80 @@
+//! Generic model of the stalls between dependent instructions.
+//!
+//! The cycle counts of the instructions assume that their operands are ready.
+//! With a [`pipeline`](super::RunConfig::pipeline) model the
+//! [`Hazards`] of consecutive instructions are compared, and an instruction
+//! that reads a register or flag written by the previous load, multiplication
+//! or division waits for the result. As the hazards are derived from the
+//! operations this works for every architecture.
+
+use general_assembly::hazard::{HazardClass, Hazards};
+
+/// Cycles an instruction waits for the result of the previous instruction,
+/// by the class of the previous instruction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineModel {
+    /// Stall after a load.
+    pub load_use: usize,
+
+    /// Stall after a multiplication.
+    pub multiply_use: usize,
+
+    /// Stall after a division.
+    pub divide_use: usize,
+}
+
+impl PipelineModel {
+    /// Get the cycles `later` stalls when it is executed right after
+    /// `earlier`.
+    pub fn stall(&self, earlier: &Hazards, later: &Hazards) -> usize {
+        if !later.depends_on(earlier) {
+            return 0;
+        }
+        match earlier.class {
+            HazardClass::Load => self.load_use,
+            HazardClass::Multiply => self.multiply_use,
+            HazardClass::Divide => self.divide_use,
+            HazardClass::Branch | HazardClass::Store | HazardClass::Alu => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use general_assembly::{
+        hazard::Hazards,
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    use super::PipelineModel;
+
+    #[test]
+    fn test_load_use_stall() {
+        let model = PipelineModel {
+            load_use: 1,
+            multiply_use: 2,
+            divide_use: 0,
+        };
+        let r = |name: &str| Operand::Register(name.to_owned());
+        let load = Hazards::of(&[Operation::Move {
+            destination: r("R0"),
+            source: Operand::Address(DataWord::Word32(0x2000_0000), 32),
+        }]);
+        let multiply = Hazards::of(&[Operation::Mul {
+            destination: r("R1"),
+            operand1: r("R0"),
+            operand2: r("R0"),
+        }]);
+        let add = Hazards::of(&[Operation::Add {
+            destination: r("R2"),
+            operand1: r("R1"),
+            operand2: r("R3"),
+        }]);
+
+        assert_eq!(model.stall(&load, &multiply), 1);
+        assert_eq!(model.stall(&multiply, &add), 2);
+        assert_eq!(model.stall(&load, &add), 0);
+        assert_eq!(model.stall(&add, &load), 0);
+    }
+}
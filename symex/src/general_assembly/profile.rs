@@ -0,0 +1,320 @@
+//! Execution profiling.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use super::project::RegionMetaData;
+
+/// Execution statistics for a single program counter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PcStats {
+    /// Number of times the instruction has been executed.
+    pub executions: usize,
+
+    /// Cumulative number of cycles spent in the instruction.
+    pub cycles: usize,
+}
+
+/// Histogram over all executed instructions, accumulated across all paths.
+///
+/// Instructions that are shared between paths are only counted once per time
+/// they are actually executed, i.e. a fork does not duplicate the counts.
+#[derive(Debug, Clone, Default)]
+pub struct PcHistogram {
+    stats: HashMap<u64, PcStats>,
+}
+
+impl PcHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an execution of the instruction at `pc` that took `cycles`.
+    pub fn record(&mut self, pc: u64, cycles: usize) {
+        let stats = self.stats.entry(pc).or_default();
+        stats.executions += 1;
+        stats.cycles += cycles;
+    }
+
+    /// Returns the `n` instructions with the most cumulative cycles, ties are
+    /// broken by the number of executions and then by address.
+    pub fn hotspots(&self, n: usize) -> Vec<(u64, PcStats)> {
+        let mut stats: Vec<(u64, PcStats)> = self.stats.iter().map(|(pc, s)| (*pc, *s)).collect();
+        stats.sort_by(|(a_pc, a), (b_pc, b)| {
+            b.cycles
+                .cmp(&a.cycles)
+                .then(b.executions.cmp(&a.executions))
+                .then(a_pc.cmp(b_pc))
+        });
+        stats.truncate(n);
+        stats
+    }
+
+    /// Returns how much of each of the `regions` was executed, in the order of
+    /// the regions.
+    pub fn block_coverage<'a>(&self, regions: &'a [RegionMetaData]) -> Vec<BlockCoverage<'a>> {
+        regions
+            .iter()
+            .map(|region| {
+                let mut coverage = BlockCoverage {
+                    region,
+                    instructions: 0,
+                    executions: 0,
+                };
+                for (_, stats) in self
+                    .stats
+                    .iter()
+                    .filter(|(pc, _)| (region.start..region.end).contains(*pc))
+                {
+                    coverage.instructions += 1;
+                    coverage.executions += stats.executions;
+                }
+                coverage
+            })
+            .collect()
+    }
+}
+
+/// How much of a block was executed, accumulated across all paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockCoverage<'a> {
+    /// The block, such as a lexical block or an inlined subroutine.
+    pub region: &'a RegionMetaData,
+
+    /// Number of distinct instructions in the block that were executed.
+    pub instructions: usize,
+
+    /// Number of times any instruction in the block was executed.
+    pub executions: usize,
+}
+
+impl BlockCoverage<'_> {
+    /// Returns true if any instruction in the block was executed.
+    pub fn is_reached(&self) -> bool {
+        self.instructions > 0
+    }
+}
+
+/// The outcomes that were feasible for a conditional branch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BranchOutcome {
+    /// The branch was taken on at least one path.
+    pub taken: bool,
+
+    /// The branch was not taken on at least one path.
+    pub not_taken: bool,
+}
+
+impl BranchOutcome {
+    /// Returns true if only one of the outcomes was ever feasible.
+    pub fn is_one_sided(&self) -> bool {
+        self.taken != self.not_taken
+    }
+}
+
+/// Feasible outcomes of all conditional branches encountered during the run.
+#[derive(Debug, Clone, Default)]
+pub struct BranchFeasibility {
+    branches: BTreeMap<u64, BranchOutcome>,
+}
+
+impl BranchFeasibility {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record which outcomes were feasible for the branch at `pc`.
+    pub fn record(&mut self, pc: u64, taken: bool, not_taken: bool) {
+        let outcome = self.branches.entry(pc).or_default();
+        outcome.taken |= taken;
+        outcome.not_taken |= not_taken;
+    }
+
+    /// Returns all branches where only one outcome was feasible, ordered by
+    /// address.
+    pub fn one_sided(&self) -> impl Iterator<Item = (u64, BranchOutcome)> + '_ {
+        self.branches
+            .iter()
+            .filter(|(_, outcome)| outcome.is_one_sided())
+            .map(|(pc, outcome)| (*pc, *outcome))
+    }
+}
+
+/// How the semantics of an instruction were handled.
+///
+/// Ordered from the most to the least faithful handling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Semantics {
+    /// The instruction was translated to operations that model it.
+    Modeled,
+
+    /// The instruction was translated to no operations, such as hints and
+    /// barriers, or an unpredictable instruction was treated as a no-op.
+    Stubbed,
+
+    /// The instruction was unpredictable and all registers were made
+    /// unconstrained instead of executing it.
+    Havoced,
+
+    /// The instruction could not be translated, the path failed or the run was
+    /// aborted.
+    Unsupported,
+}
+
+impl Display for Semantics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Modeled => write!(f, "modeled"),
+            Self::Stubbed => write!(f, "stubbed"),
+            Self::Havoced => write!(f, "havoced"),
+            Self::Unsupported => write!(f, "unsupported"),
+        }
+    }
+}
+
+/// How the instructions with one opcode were handled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpcodeCoverage {
+    /// Number of distinct addresses with the opcode for each handling.
+    pub addresses: BTreeMap<Semantics, usize>,
+}
+
+impl OpcodeCoverage {
+    /// Returns true if every instruction with the opcode was modeled.
+    pub fn is_fully_modeled(&self) -> bool {
+        self.addresses
+            .keys()
+            .all(|semantics| *semantics == Semantics::Modeled)
+    }
+}
+
+/// How the semantics of every executed instruction were handled, accumulated
+/// across all paths.
+#[derive(Debug, Clone, Default)]
+pub struct SemanticsCoverage {
+    instructions: HashMap<u64, (String, Semantics)>,
+}
+
+impl SemanticsCoverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record how the instruction at `pc` was handled, `mnemonic` is only
+    /// called the first time the address is recorded.
+    ///
+    /// The least faithful handling of an address is kept.
+    pub fn record(
+        &mut self,
+        pc: u64,
+        semantics: Semantics,
+        mnemonic: impl FnOnce() -> Option<String>,
+    ) {
+        let entry = self.instructions.entry(pc).or_insert_with(|| {
+            let mnemonic = mnemonic().unwrap_or_else(|| "<unknown>".to_owned());
+            (mnemonic, semantics)
+        });
+        entry.1 = entry.1.max(semantics);
+    }
+
+    /// Returns how each opcode was handled, ordered by opcode.
+    pub fn opcodes(&self) -> BTreeMap<&str, OpcodeCoverage> {
+        let mut opcodes: BTreeMap<&str, OpcodeCoverage> = BTreeMap::new();
+        for (mnemonic, semantics) in self.instructions.values() {
+            *opcodes
+                .entry(mnemonic)
+                .or_default()
+                .addresses
+                .entry(*semantics)
+                .or_default() += 1;
+        }
+        opcodes
+    }
+
+    /// Returns the number of distinct addresses that were handled as
+    /// `semantics`.
+    pub fn count(&self, semantics: Semantics) -> usize {
+        self.instructions
+            .values()
+            .filter(|(_, handled)| *handled == semantics)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BranchFeasibility, PcHistogram, Semantics, SemanticsCoverage};
+    use crate::general_assembly::project::RegionMetaData;
+
+    #[test]
+    fn hotspots_are_sorted_by_cycles() {
+        let mut histogram = PcHistogram::new();
+        histogram.record(0x100, 1);
+        histogram.record(0x102, 3);
+        histogram.record(0x100, 1);
+        histogram.record(0x104, 1);
+
+        let hotspots = histogram.hotspots(2);
+        assert_eq!(hotspots.len(), 2);
+        assert_eq!(hotspots[0].0, 0x102);
+        assert_eq!(hotspots[0].1.cycles, 3);
+        assert_eq!(hotspots[1].0, 0x100);
+        assert_eq!(hotspots[1].1.executions, 2);
+    }
+
+    #[test]
+    fn block_coverage_counts_executed_instructions() {
+        let mut histogram = PcHistogram::new();
+        histogram.record(0x100, 1);
+        histogram.record(0x102, 1);
+        histogram.record(0x102, 1);
+        // the end of a region is not part of it
+        histogram.record(0x104, 1);
+
+        let region = |name: &str, start, end| RegionMetaData {
+            name: name.to_owned(),
+            start,
+            end,
+        };
+        let regions = [region("entry", 0x100, 0x104), region("exit", 0x110, 0x114)];
+        let coverage = histogram.block_coverage(&regions);
+        assert_eq!(coverage.len(), 2);
+        assert_eq!(coverage[0].region.name, "entry");
+        assert_eq!(coverage[0].instructions, 2);
+        assert_eq!(coverage[0].executions, 3);
+        assert!(!coverage[1].is_reached());
+    }
+
+    #[test]
+    fn branch_outcomes_are_merged() {
+        let mut branches = BranchFeasibility::new();
+        branches.record(0x100, true, false);
+        branches.record(0x100, false, true);
+        branches.record(0x104, true, false);
+        branches.record(0x108, false, true);
+
+        let one_sided: Vec<u64> = branches.one_sided().map(|(pc, _)| pc).collect();
+        assert_eq!(one_sided, vec![0x104, 0x108]);
+    }
+
+    #[test]
+    fn semantics_are_grouped_by_opcode() {
+        let mut coverage = SemanticsCoverage::new();
+        coverage.record(0x100, Semantics::Modeled, || Some("Add".to_owned()));
+        coverage.record(0x102, Semantics::Modeled, || Some("Add".to_owned()));
+        coverage.record(0x104, Semantics::Stubbed, || Some("Dsb".to_owned()));
+        coverage.record(0x106, Semantics::Havoced, || None);
+        // the mnemonic is only decoded once and the worst handling is kept
+        coverage.record(0x102, Semantics::Unsupported, || unreachable!());
+
+        let opcodes = coverage.opcodes();
+        assert_eq!(opcodes.len(), 3);
+        assert!(!opcodes["Add"].is_fully_modeled());
+        assert_eq!(opcodes["Add"].addresses[&Semantics::Modeled], 1);
+        assert_eq!(opcodes["Add"].addresses[&Semantics::Unsupported], 1);
+        assert_eq!(opcodes["<unknown>"].addresses[&Semantics::Havoced], 1);
+        assert_eq!(coverage.count(Semantics::Stubbed), 1);
+    }
+}
@@ -1,29 +1,75 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use general_assembly::operand::{DataHalfWord, DataWord, RawDataWord};
-use gimli::{DebugAbbrev, DebugInfo, DebugStr};
-use object::{File, Object, ObjectSection, ObjectSymbol};
+use object::{File, Object, ObjectSymbol};
+use regex::Regex;
 use tracing::{debug, trace};
 
 use self::segments::Segments;
 use super::{
     arch::ArchError,
+    branch_predictor::BranchPredictor,
+    budget::ExecutionBudget,
+    cache::CacheConfig,
+    cosim::CoSimulator,
+    energy::EnergyModel,
+    hardware::{self, HardwareModel, MappedPeripheral},
     instruction::Instruction,
+    interrupts::Interrupt,
+    peripheral_model::PeripheralModel,
+    pipeline::PipelineModel,
+    resources::Resource,
     state::GAState,
+    temporal::TemporalProperty,
+    watchdog::Watchdog,
     Endianness,
+    Entry,
+    GAError,
+    MemoryMapRegion,
+    MemoryModel,
     Result as SuperResult,
     RunConfig,
+    TimeSource,
+    UnknownMemoryPolicy,
+    UnknownMemoryRegion,
+    UnknownRegisterPolicy,
+    UnpredictablePolicy,
     WordSize,
 };
 use crate::{general_assembly::arch::Arch, memory::MemoryError, smt::DExpr};
 
+mod debug_index;
 mod dwarf_helper;
-use dwarf_helper::*;
+mod symbol_table;
+use debug_index::DebugIndex;
+pub use symbol_table::SymbolBinding;
+use symbol_table::SymbolTable;
 
+pub mod debug_type;
+pub mod dwarf_expression;
 pub mod segments;
+pub mod stack_frame;
+use stack_frame::FunctionFrame;
 
 pub type Result<T> = std::result::Result<T, ProjectError>;
 
+/// Symbols of the vector table given by `cortex-m-rt`, CMSIS and the startup
+/// code of some vendors, see [`Entry::Reset`].
+const VECTOR_TABLE_SYMBOLS: &[&str] = &[
+    "__vector_table",
+    "__Vectors",
+    "__isr_vector",
+    "g_pfnVectors",
+];
+
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
 pub enum ProjectError {
     #[error("Unable to parse elf file: {0}")]
@@ -37,6 +83,12 @@ pub enum ProjectError {
 
     #[error("Architecture specific error")]
     ArchError(#[from] ArchError),
+
+    #[error("No function named {0}")]
+    FunctionNotFound(String),
+
+    #[error("{1} functions are named {0}")]
+    AmbiguousFunction(String, usize),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -46,10 +98,117 @@ pub enum PCHook<A: Arch> {
     EndFailure(&'static str),
     Intrinsic(fn(state: &mut GAState<A>) -> SuperResult<()>),
     Suppress,
+
+    /// Assume that the boolean argument of the hooked function is true, paths
+    /// where it can not be true end as
+    /// [`PathResult::AssumptionUnsat`](super::executor::PathResult::AssumptionUnsat).
+    Assume,
+
+    /// Assert that the boolean argument of the hooked function is true, the
+    /// paths where it can be false end as a failure with the reason.
+    Assert(&'static str),
+
+    /// Filter the hooked function out of the analysis, see [`FunctionFilter`].
+    Filter(FilterAction),
+}
+
+/// How a function matched by a [`FunctionFilter`] is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Return from the function immediately with an unconstrained return
+    /// value, the side effects of the function are ignored.
+    Skip,
+
+    /// End the path successfully when the function is called.
+    Succeed,
+}
+
+impl std::fmt::Display for FilterAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skip => write!(f, "skipped"),
+            Self::Succeed => write!(f, "assumed to succeed"),
+        }
+    }
+}
+
+/// Filters all functions with a name that matches an expression out of the
+/// analysis, such as logging or formatting functions.
+///
+/// Filtered calls are modeled assumptions and are reported for each path,
+/// see [`FilteredCall`](super::state::FilteredCall). A filter replaces any
+/// other pc hook on the same function.
+#[derive(Debug, Clone)]
+pub struct FunctionFilter {
+    /// Expression matching the debug information name of the functions.
+    pub function: Regex,
+
+    /// How calls to the functions are handled.
+    pub action: FilterAction,
 }
 
 pub type PCHooks<A> = HashMap<u64, PCHook<A>>;
 
+/// The PC hooks added at each address with their ids, the last hook is run and
+/// the hooks it replaced are run again when it is removed.
+type PCHookStacks<A> = HashMap<u64, Vec<(u64, PCHook<A>)>>;
+
+/// Gives each hook of `hooks` the next id.
+fn stack_pc_hooks<A: Arch>(hooks: PCHooks<A>, next_hook_id: &mut u64) -> PCHookStacks<A> {
+    let mut hooks: Vec<(u64, PCHook<A>)> = hooks.into_iter().collect();
+    hooks.sort_unstable_by_key(|(pc, _)| *pc);
+    hooks
+        .into_iter()
+        .map(|(pc, hook)| {
+            let id = *next_hook_id;
+            *next_hook_id += 1;
+            (pc, vec![(id, hook)])
+        })
+        .collect()
+}
+
+/// Call site a [`CallSiteHook`] applies to, identified by the return address
+/// in `LR` when the hooked function is entered.
+#[derive(Debug, Clone)]
+pub enum CallSite {
+    /// Calls that return to the address.
+    ReturnAddress(u64),
+
+    /// Calls from a function with a symbol that matches the expression.
+    Caller(Regex),
+}
+
+impl CallSite {
+    /// Checks if a call that returns to `return_address` is from this call
+    /// site.
+    pub fn matches<A: Arch>(&self, project: &Project<A>, return_address: u64) -> bool {
+        let return_address = return_address & !0b1;
+        match self {
+            Self::ReturnAddress(address) => *address & !0b1 == return_address,
+            Self::Caller(caller) => project
+                .get_symbol_name(return_address)
+                .is_some_and(|(name, _)| caller.is_match(name)),
+        }
+    }
+}
+
+/// A PC hook that only applies to calls from a specific call site, such as a
+/// HAL function that is modeled differently during initialization.
+///
+/// Call site hooks take precedence over PC hooks on the same function, calls
+/// from other call sites use the PC hook if there is one.
+#[derive(Debug, Clone)]
+pub struct CallSiteHook<A: Arch> {
+    /// Expression matching the name of the hooked function.
+    pub function: Regex,
+
+    /// Call site the hook applies to.
+    pub call_site: CallSite,
+
+    /// Hook that is run instead of the function.
+    pub hook: PCHook<A>,
+}
+
 /// Hook for a register read.
 pub type RegisterReadHook<A> = fn(state: &mut GAState<A>) -> SuperResult<DExpr>;
 pub type RegisterReadHooks<A> = HashMap<String, RegisterReadHook<A>>;
@@ -58,12 +217,223 @@ pub type RegisterReadHooks<A> = HashMap<String, RegisterReadHook<A>>;
 pub type RegisterWriteHook<A> = fn(state: &mut GAState<A>, value: DExpr) -> SuperResult<()>;
 pub type RegisterWriteHooks<A> = HashMap<String, RegisterWriteHook<A>>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MemoryHookAddress {
     Single(u64),
     Range(u64, u64),
 }
 
+impl Display for MemoryHookAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Single(address) => write!(f, "{address:#x}"),
+            Self::Range(start, end) => write!(f, "{start:#x}..{end:#x}"),
+        }
+    }
+}
+
+/// Handle to a hook added to a [`Project`], used to remove the hook again with
+/// [`Project::remove_hook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookHandle {
+    /// A PC hook at `pc`.
+    Pc { pc: u64, id: u64 },
+
+    /// A call site hook on the function at `pc`.
+    CallSite { pc: u64, id: u64 },
+
+    /// The read hook on the register.
+    RegisterRead(String),
+
+    /// The write hook on the register.
+    RegisterWrite(String),
+
+    /// The memory read hook at the address.
+    MemoryRead(MemoryHookAddress),
+
+    /// The memory write hook at the address.
+    MemoryWrite(MemoryHookAddress),
+}
+
+impl Display for HookHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pc { pc, .. } => write!(f, "pc hook at {pc:#x}"),
+            Self::CallSite { pc, .. } => write!(f, "call site hook at {pc:#x}"),
+            Self::RegisterRead(register) => write!(f, "read hook on {register}"),
+            Self::RegisterWrite(register) => write!(f, "write hook on {register}"),
+            Self::MemoryRead(address) => write!(f, "memory read hook at {address}"),
+            Self::MemoryWrite(address) => write!(f, "memory write hook at {address}"),
+        }
+    }
+}
+
+/// Where a hook was added to a [`Project`], reported when the hook panics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookRegistration {
+    /// Added by a call of a method of [`Project`] at the location.
+    Call(&'static std::panic::Location<'static>),
+
+    /// Configured in a field of [`RunConfig`], for the function, register or
+    /// address.
+    Config {
+        /// Name of the field.
+        field: &'static str,
+
+        /// What the entry of the field applies to.
+        target: String,
+    },
+}
+
+impl HookRegistration {
+    /// Creates the registration of an entry of the [`RunConfig`] field `field`
+    /// for `target`.
+    fn config(field: &'static str, target: impl Display) -> Self {
+        Self::Config {
+            field,
+            target: target.to_string(),
+        }
+    }
+}
+
+impl Display for HookRegistration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Call(location) => write!(f, "added at {location}"),
+            Self::Config { field, target } => {
+                write!(f, "configured in RunConfig::{field} for {target}")
+            }
+        }
+    }
+}
+
+/// A region of code delimited by a start and an end address.
+///
+/// A cycle lap is recorded each time execution reaches `start` and each time
+/// it reaches `end`. The end address is exclusive, i.e. the instruction at
+/// `end` is not counted as part of the region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionMetaData {
+    /// Name used to label the cycle laps for this region.
+    pub name: String,
+
+    /// Address of the first instruction in the region.
+    pub start: u64,
+
+    /// Address of the first instruction after the region.
+    pub end: u64,
+}
+
+/// Invariant of a loop, returns a boolean expression over the state at the
+/// loop header.
+pub type LoopInvariantFn<A> = fn(state: &mut GAState<A>) -> SuperResult<DExpr>;
+
+/// An invariant attached to the header of a loop.
+///
+/// When a path reaches `header` the invariant is checked, the registers and
+/// memory the loop modifies and the flags are replaced by fresh symbols and
+/// the invariant is assumed. This represents an arbitrary iteration of the
+/// loop. When the path reaches `header` again the invariant is checked once
+/// more, which completes the induction, and the path ends. Paths that leave the
+/// loop continue after it without unrolling it.
+///
+/// The analysis is only sound if all registers and memory the loop writes are
+/// listed, the path fails if the loop modifies a register or writes memory that
+/// is not listed.
+#[derive(Debug, Clone)]
+pub struct LoopInvariant<A: Arch> {
+    /// Address of the first instruction of the loop, the target of the back
+    /// edge.
+    pub header: u64,
+
+    /// The invariant.
+    pub invariant: LoopInvariantFn<A>,
+
+    /// Registers the loop modifies.
+    pub registers: Vec<String>,
+
+    /// Memory the loop modifies, as ranges of start address and length in
+    /// bytes.
+    pub memory: Vec<(u64, u64)>,
+}
+
+/// Assumption on the contents of a havoced memory region, returns a boolean
+/// expression.
+pub type MemoryHavocAssumption<A> = fn(state: &mut GAState<A>) -> SuperResult<DExpr>;
+
+/// A memory region that becomes arbitrary at a program point, such as a DMA
+/// buffer after the transfer completes.
+///
+/// Each time a path reaches `pc` the contents of the region are replaced by
+/// fresh symbols and `assumption` is added as a constraint, before the
+/// instruction at `pc` is executed.
+#[derive(Debug, Clone)]
+pub struct MemoryHavoc<A: Arch> {
+    /// Address of the instruction the region is havoced before.
+    pub pc: u64,
+
+    /// Address of the first byte in the region.
+    pub start: u64,
+
+    /// Size of the region in bytes.
+    pub size: u64,
+
+    /// Assumption on the new contents, `None` if the contents are
+    /// unconstrained.
+    pub assumption: Option<MemoryHavocAssumption<A>>,
+}
+
+/// A region of memory backed by a concrete byte buffer, such as an external
+/// flash or file system image.
+///
+/// Reads return the bytes of the buffer until the path writes to them, writes
+/// go to a symbolic copy-on-write overlay that is local to the path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackedRegion {
+    /// Address of the first byte in the region.
+    pub start: u64,
+
+    /// Contents of the region.
+    pub data: Arc<[u8]>,
+}
+
+impl BackedRegion {
+    /// Creates a region at `start` backed by `data`.
+    pub fn new(start: u64, data: Vec<u8>) -> Self {
+        Self {
+            start,
+            data: data.into(),
+        }
+    }
+
+    /// Creates a region at `start` backed by the contents of the file at
+    /// `path`.
+    pub fn from_file<P: AsRef<Path>>(start: u64, path: P) -> std::io::Result<Self> {
+        Ok(Self::new(start, fs::read(path)?))
+    }
+
+    /// Checks if `address` is inside of the region.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.start && address - self.start < self.data.len() as u64
+    }
+}
+
+/// A location in the source code of the analyzed program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Name of the source file.
+    pub file: String,
+
+    /// Line in the source file.
+    pub line: u64,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
 /// Hook for a memory write.
 pub type MemoryWriteHook<A> =
     fn(state: &mut GAState<A>, address: u64, value: DExpr, bits: u32) -> SuperResult<()>;
@@ -81,14 +451,82 @@ pub struct Project<A: Arch> {
     segments: Segments,
     word_size: WordSize,
     endianness: Endianness,
-    symtab: HashMap<String, u64>,
-    pc_hooks: PCHooks<A>,
+    symtab: SymbolTable,
+    pc_hooks: PCHookStacks<A>,
+    call_site_hooks: HashMap<u64, Vec<(u64, CallSite, PCHook<A>)>>,
+    next_hook_id: u64,
+    hook_registrations: Vec<(HookHandle, HookRegistration)>,
     reg_read_hooks: RegisterReadHooks<A>,
     reg_write_hooks: RegisterWriteHooks<A>,
     single_memory_read_hooks: SingleMemoryReadHooks<A>,
     range_memory_read_hooks: RangeMemoryReadHooks<A>,
     single_memory_write_hooks: SingleMemoryWriteHooks<A>,
     range_memory_write_hooks: RangeMemoryWriteHooks<A>,
+    regions: Vec<RegionMetaData>,
+    debug_index: DebugIndex,
+    max_call_depth: Option<usize>,
+    entry: Option<Entry>,
+    max_forks: Option<usize>,
+    deduplicate_states: bool,
+    subsumption_budget: Option<usize>,
+    time_source: Option<TimeSource>,
+    unpredictable: UnpredictablePolicy,
+    unknown_registers: UnknownRegisterPolicy,
+    co_simulator: Option<Arc<dyn CoSimulator>>,
+    energy_model: Option<Arc<dyn EnergyModel>>,
+    instruction_cache: Option<CacheConfig>,
+    data_cache: Option<CacheConfig>,
+    branch_predictor: Option<BranchPredictor>,
+    pipeline: Option<PipelineModel>,
+    trace: Vec<u64>,
+    backed_regions: Vec<BackedRegion>,
+    unknown_memory: Vec<UnknownMemoryRegion>,
+    loop_invariants: HashMap<u64, LoopInvariant<A>>,
+    accelerate_loops: bool,
+    accelerate_copies: Option<u64>,
+    range_analysis: bool,
+    memory_havocs: HashMap<u64, Vec<MemoryHavoc<A>>>,
+    side_effects_file: Option<PathBuf>,
+    recorded_registers: Vec<String>,
+    register_history_limit: usize,
+    mmio_regions: Vec<Range<u64>>,
+    properties: Vec<TemporalProperty>,
+    watchdog: Option<Watchdog>,
+    peripheral_models: Vec<PeripheralModel>,
+    hardware: Vec<MappedPeripheral>,
+    interrupts: Vec<Interrupt>,
+    partial_order_reduction: bool,
+    atomicity_violations: bool,
+    resources: Vec<Resource>,
+    non_reentrant: Vec<String>,
+    summarized: Vec<String>,
+    nvic: bool,
+    slice_failures: bool,
+    solver_timeout: Option<Duration>,
+    time_travel: bool,
+    snapshot_interval: Option<usize>,
+    max_symbol_size: Option<u64>,
+    memory_model: MemoryModel,
+    memory_map: Vec<MemoryMapRegion>,
+    stack_locals: bool,
+    infer_preconditions: bool,
+    budget: ExecutionBudget,
+    function_frames: HashMap<u64, FunctionFrame>,
+}
+
+/// Removes the most recently added hook on the range, returns false if there is
+/// none.
+fn remove_last_range_hook<H>(hooks: &mut Vec<((u64, u64), H)>, range: (u64, u64)) -> bool {
+    match hooks
+        .iter()
+        .rposition(|(hook_range, _)| *hook_range == range)
+    {
+        Some(index) => {
+            hooks.remove(index);
+            true
+        }
+        None => false,
+    }
 }
 
 fn construct_register_read_hooks<A: Arch>(
@@ -169,31 +607,113 @@ impl<A: Arch> Project<A> {
         single_memory_write_hooks: SingleMemoryWriteHooks<A>,
         range_memory_write_hooks: RangeMemoryWriteHooks<A>,
     ) -> Project<A> {
+        let mut next_hook_id = 0;
         Project {
             segments: Segments::from_single_segment(program_memory, start_addr, end_addr),
             word_size,
             endianness,
-            symtab,
-            pc_hooks,
+            symtab: symtab.into(),
+            pc_hooks: stack_pc_hooks(pc_hooks, &mut next_hook_id),
+            call_site_hooks: HashMap::new(),
+            next_hook_id,
+            hook_registrations: vec![],
             reg_read_hooks,
             reg_write_hooks,
             single_memory_read_hooks,
             range_memory_read_hooks,
             single_memory_write_hooks,
             range_memory_write_hooks,
+            regions: vec![],
+            debug_index: DebugIndex::empty(),
+            max_call_depth: None,
+            entry: None,
+            max_forks: None,
+            deduplicate_states: false,
+            subsumption_budget: None,
+            time_source: None,
+            unpredictable: UnpredictablePolicy::FailPath,
+            unknown_registers: UnknownRegisterPolicy::Create,
+            co_simulator: None,
+            energy_model: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictor: None,
+            pipeline: None,
+            trace: vec![],
+            backed_regions: vec![],
+            unknown_memory: vec![],
+            loop_invariants: HashMap::new(),
+            accelerate_loops: false,
+            accelerate_copies: None,
+            range_analysis: false,
+            memory_havocs: HashMap::new(),
+            side_effects_file: None,
+            recorded_registers: vec![],
+            register_history_limit: 256,
+            mmio_regions: vec![],
+            properties: vec![],
+            watchdog: None,
+            peripheral_models: vec![],
+            hardware: vec![],
+            interrupts: vec![],
+            partial_order_reduction: true,
+            atomicity_violations: false,
+            resources: vec![],
+            non_reentrant: vec![],
+            summarized: vec![],
+            nvic: false,
+            slice_failures: false,
+            solver_timeout: None,
+            time_travel: false,
+            snapshot_interval: Some(1000),
+            max_symbol_size: None,
+            memory_model: MemoryModel::Array,
+            memory_map: vec![],
+            stack_locals: false,
+            infer_preconditions: false,
+            budget: ExecutionBudget::unlimited(),
+            function_frames: HashMap::new(),
         }
     }
 
+    /// Creates a little endian 32-bit project without code, symbols or hooks.
+    #[cfg(test)]
+    pub(crate) fn test_project() -> Project<A> {
+        Self::manual_project(
+            vec![],
+            0,
+            0,
+            WordSize::Bit32,
+            Endianness::Little,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            vec![],
+            HashMap::new(),
+            vec![],
+        )
+    }
+
+    /// Loads `program` in to a project created by [`Self::test_project`].
+    #[cfg(test)]
+    pub(crate) fn with_program(mut self, program: Vec<u8>, start_addr: u64, end_addr: u64) -> Self {
+        self.segments = Segments::from_single_segment(program, start_addr, end_addr);
+        self
+    }
+
+    /// Replaces the symbols of a project created by [`Self::test_project`].
+    #[cfg(test)]
+    pub(crate) fn with_symbols(mut self, symtab: HashMap<String, u64>) -> Self {
+        self.symtab = symtab.into();
+        self
+    }
+
     #[cfg(test)]
     pub fn add_hooks(&mut self, arch: &A) {
-        let mut cfg = RunConfig {
-            memory_read_hooks: Vec::new(),
-            memory_write_hooks: Vec::new(),
-            pc_hooks: Vec::new(),
-            register_read_hooks: Vec::new(),
-            register_write_hooks: Vec::new(),
-            show_path_results: false,
-        };
+        let mut cfg = RunConfig::new(false);
+        cfg.time_source = self.time_source;
         arch.add_hooks(&mut cfg);
 
         let reg_read_hooks = construct_register_read_hooks(cfg.register_read_hooks);
@@ -227,14 +747,27 @@ impl<A: Arch> Project<A> {
             WordSize::Bit32
         };
 
-        let mut symtab = HashMap::new();
+        let mut symtab = SymbolTable::new();
         for symbol in obj_file.symbols() {
+            // Undefined weak references have the address zero.
+            if symbol.is_undefined() {
+                continue;
+            }
+            let binding = if symbol.is_weak() {
+                SymbolBinding::Weak
+            } else if symbol.is_global() {
+                SymbolBinding::Global
+            } else {
+                SymbolBinding::Local
+            };
             symtab.insert(
                 match symbol.name() {
                     Ok(name) => name.to_owned(),
                     Err(_) => continue, // ignore entry if name can not be read
                 },
                 symbol.address(),
+                symbol.size(),
+                binding,
             );
         }
 
@@ -243,23 +776,79 @@ impl<A: Arch> Project<A> {
             Endianness::Big => gimli::RunTimeEndian::Big,
         };
 
-        let debug_info = obj_file.section_by_name(".debug_info").unwrap();
-        let debug_info = DebugInfo::new(debug_info.data().unwrap(), gimli_endian);
-
-        let debug_abbrev = obj_file.section_by_name(".debug_abbrev").unwrap();
-        let debug_abbrev = DebugAbbrev::new(debug_abbrev.data().unwrap(), gimli_endian);
-
-        let debug_str = obj_file.section_by_name(".debug_str").unwrap();
-        let debug_str = DebugStr::new(debug_str.data().unwrap(), gimli_endian);
+        let debug_index = DebugIndex::from_file(&obj_file, gimli_endian);
 
         trace!("Running for Architecture {}", architecture);
         architecture.add_hooks(cfg);
-        let pc_hooks = &cfg.pc_hooks;
-
-        let pc_hooks =
-            construct_pc_hooks_no_index(pc_hooks, &debug_info, &debug_abbrev, &debug_str);
+        let mut pc_hooks = debug_index.pc_hooks(&cfg.pc_hooks);
+        for filter in &cfg.function_filters {
+            let filter = vec![(filter.function.clone(), PCHook::Filter(filter.action))];
+            pc_hooks.extend(debug_index.pc_hooks(&filter));
+        }
 
         debug!("Created pc hooks: {:?}", pc_hooks);
+        let mut next_hook_id = 0;
+        let pc_hooks = stack_pc_hooks(pc_hooks, &mut next_hook_id);
+
+        let mut hook_registrations = vec![];
+        for (function, hook) in &cfg.pc_hooks {
+            // Only intrinsics run code of the user.
+            if let PCHook::Intrinsic(_) = hook {
+                for pc in debug_index
+                    .pc_hooks(&vec![(function.clone(), hook.clone())])
+                    .keys()
+                {
+                    let Some((id, _)) = pc_hooks.get(pc).and_then(|hooks| hooks.last()) else {
+                        continue;
+                    };
+                    let registration = HookRegistration::config("pc_hooks", function);
+                    hook_registrations.push((HookHandle::Pc { pc: *pc, id: *id }, registration));
+                }
+            }
+        }
+
+        let mut call_site_hooks: HashMap<u64, Vec<(u64, CallSite, PCHook<A>)>> = HashMap::new();
+        for hook in &cfg.call_site_hooks {
+            let functions = debug_index.pc_hooks(&vec![(hook.function.clone(), hook.hook.clone())]);
+            for pc in functions.keys() {
+                hook_registrations.push((
+                    HookHandle::CallSite {
+                        pc: *pc & !0b1,
+                        id: next_hook_id,
+                    },
+                    HookRegistration::config("call_site_hooks", &hook.function),
+                ));
+                call_site_hooks.entry(*pc & !0b1).or_default().push((
+                    next_hook_id,
+                    hook.call_site.clone(),
+                    hook.hook.clone(),
+                ));
+                next_hook_id += 1;
+            }
+        }
+        debug!("Created call site hooks: {:?}", call_site_hooks);
+
+        let mut regions = cfg.regions.clone();
+        if cfg.regions_from_debug_info {
+            regions.extend(debug_index.regions());
+        }
+
+        for (register, _) in &cfg.register_read_hooks {
+            let registration = HookRegistration::config("register_read_hooks", register);
+            hook_registrations.push((HookHandle::RegisterRead(register.clone()), registration));
+        }
+        for (register, _) in &cfg.register_write_hooks {
+            let registration = HookRegistration::config("register_write_hooks", register);
+            hook_registrations.push((HookHandle::RegisterWrite(register.clone()), registration));
+        }
+        for (address, _) in &cfg.memory_read_hooks {
+            let registration = HookRegistration::config("memory_read_hooks", address);
+            hook_registrations.push((HookHandle::MemoryRead(address.clone()), registration));
+        }
+        for (address, _) in &cfg.memory_write_hooks {
+            let registration = HookRegistration::config("memory_write_hooks", address);
+            hook_registrations.push((HookHandle::MemoryWrite(address.clone()), registration));
+        }
 
         let reg_read_hooks = construct_register_read_hooks(cfg.register_read_hooks.clone());
         let reg_write_hooks = construct_register_write_hooks(cfg.register_write_hooks.clone());
@@ -269,27 +858,449 @@ impl<A: Arch> Project<A> {
         let (single_memory_read_hooks, range_memory_read_hooks) =
             construct_memory_read_hooks(cfg.memory_read_hooks.clone());
 
+        let mut memory_havocs: HashMap<u64, Vec<MemoryHavoc<A>>> = HashMap::new();
+        for havoc in &cfg.memory_havocs {
+            memory_havocs
+                .entry(havoc.pc & !0b1)
+                .or_default()
+                .push(havoc.clone());
+        }
+
         Ok(Project {
             segments,
             word_size,
             endianness,
             symtab,
             pc_hooks,
+            call_site_hooks,
+            next_hook_id,
+            hook_registrations,
             reg_read_hooks,
             reg_write_hooks,
             single_memory_read_hooks,
             range_memory_read_hooks,
             single_memory_write_hooks,
             range_memory_write_hooks,
+            regions,
+            debug_index,
+            max_call_depth: cfg.max_call_depth,
+            entry: cfg.entry,
+            max_forks: cfg.max_forks,
+            deduplicate_states: cfg.deduplicate_states,
+            subsumption_budget: cfg.subsumption_budget,
+            time_source: cfg.time_source,
+            unpredictable: cfg.unpredictable,
+            unknown_registers: cfg.unknown_registers,
+            co_simulator: cfg.co_simulator.clone(),
+            energy_model: cfg.energy_model.clone(),
+            instruction_cache: cfg.instruction_cache,
+            data_cache: cfg.data_cache,
+            branch_predictor: cfg.branch_predictor,
+            pipeline: cfg.pipeline,
+            trace: cfg.trace.clone(),
+            backed_regions: cfg.backed_regions.clone(),
+            unknown_memory: cfg.unknown_memory.clone(),
+            loop_invariants: cfg
+                .loop_invariants
+                .iter()
+                .map(|invariant| (invariant.header & !0b1, invariant.clone()))
+                .collect(),
+            accelerate_loops: cfg.accelerate_loops,
+            accelerate_copies: cfg.accelerate_copies,
+            range_analysis: cfg.range_analysis,
+            memory_havocs,
+            side_effects_file: cfg.side_effects_file.clone(),
+            recorded_registers: cfg.recorded_registers.clone(),
+            register_history_limit: cfg.register_history_limit,
+            mmio_regions: cfg.mmio_regions.clone(),
+            properties: cfg.properties.clone(),
+            watchdog: cfg.watchdog,
+            peripheral_models: cfg.peripheral_models.clone(),
+            hardware: cfg
+                .hardware_models
+                .iter()
+                .flat_map(|model| model.peripherals.iter().cloned())
+                .collect(),
+            interrupts: cfg.interrupts.clone(),
+            partial_order_reduction: cfg.partial_order_reduction,
+            atomicity_violations: cfg.atomicity_violations,
+            resources: cfg.resources.clone(),
+            non_reentrant: cfg.non_reentrant.clone(),
+            summarized: cfg.summarized.clone(),
+            nvic: cfg.nvic,
+            slice_failures: cfg.slice_failures,
+            solver_timeout: cfg.solver_timeout,
+            time_travel: cfg.time_travel,
+            snapshot_interval: cfg.snapshot_interval,
+            max_symbol_size: cfg.max_symbol_size,
+            memory_model: cfg.memory_model,
+            memory_map: cfg.memory_map.clone(),
+            stack_locals: cfg.stack_locals,
+            infer_preconditions: cfg.infer_preconditions,
+            budget: cfg.budget.clone(),
+            function_frames: HashMap::new(),
         })
     }
 
     pub fn get_pc_hook(&self, pc: u64) -> Option<&PCHook<A>> {
-        self.pc_hooks.get(&pc)
+        self.pc_hooks.get(&pc)?.last().map(|(_, hook)| hook)
+    }
+
+    /// Adds `hook` at `pc`. A previous hook at `pc` is not run until `hook` is
+    /// removed.
+    #[track_caller]
+    pub fn add_pc_hook(&mut self, pc: u64, hook: PCHook<A>) -> HookHandle {
+        let id = self.next_hook_id;
+        self.next_hook_id += 1;
+        self.pc_hooks.entry(pc).or_default().push((id, hook));
+        self.register_hook(HookHandle::Pc { pc, id })
+    }
+
+    /// Adds `hook` to every function in the debug data whose name matches
+    /// `function`, such as every monomorphization of a generic function.
+    ///
+    /// Returns the number of functions that were hooked.
+    #[track_caller]
+    pub fn add_pc_hook_regex(&mut self, function: &Regex, hook: PCHook<A>) -> usize {
+        let hooks = self.debug_index.pc_hooks(&vec![(function.clone(), hook)]);
+        let count = hooks.len();
+        for (pc, hook) in hooks {
+            self.add_pc_hook(pc, hook);
+        }
+        count
+    }
+
+    /// Adds `hook` to the function named exactly `function`, for when a single
+    /// target is intended.
+    ///
+    /// The function is looked up in the debug data, or in the symbol table if
+    /// the debug data does not name it. Fails if no function or more than one
+    /// function has the name.
+    #[track_caller]
+    pub fn add_pc_hook_exact(&mut self, function: &str, hook: PCHook<A>) -> Result<HookHandle> {
+        let mut addresses: Vec<u64> = self
+            .debug_index
+            .subprograms()
+            .iter()
+            .filter(|(name, _)| name == function)
+            .map(|(_, address)| *address)
+            .collect();
+        addresses.sort_unstable();
+        addresses.dedup();
+        if addresses.is_empty() {
+            addresses.extend(self.symtab.address(function).map(|address| address & !0b1));
+        }
+        match addresses[..] {
+            [address] => Ok(self.add_pc_hook(address, hook)),
+            [] => Err(ProjectError::FunctionNotFound(function.to_owned())),
+            _ => Err(ProjectError::AmbiguousFunction(
+                function.to_owned(),
+                addresses.len(),
+            )),
+        }
+    }
+
+    /// Get the hook for a call to the function at `pc` that returns to
+    /// `return_address`, see [`CallSiteHook`].
+    pub fn get_call_site_hook(&self, pc: u64, return_address: u64) -> Option<&PCHook<A>> {
+        self.call_site_hooks
+            .get(&pc)?
+            .iter()
+            .find(|(_, call_site, _)| call_site.matches(self, return_address))
+            .map(|(_, _, hook)| hook)
+    }
+
+    /// Adds a hook for calls to the function at `pc` from `call_site`.
+    #[track_caller]
+    pub fn add_call_site_hook(
+        &mut self,
+        pc: u64,
+        call_site: CallSite,
+        hook: PCHook<A>,
+    ) -> HookHandle {
+        let pc = pc & !0b1;
+        let id = self.next_hook_id;
+        self.next_hook_id += 1;
+        self.call_site_hooks
+            .entry(pc)
+            .or_default()
+            .push((id, call_site, hook));
+        self.register_hook(HookHandle::CallSite { pc, id })
+    }
+
+    /// Adds a hook that is run instead of reading from `register`, replacing
+    /// any previous hook on the register.
+    #[track_caller]
+    pub fn add_register_read_hook(
+        &mut self,
+        register: &str,
+        hook: RegisterReadHook<A>,
+    ) -> HookHandle {
+        self.reg_read_hooks.insert(register.to_owned(), hook);
+        self.register_hook(HookHandle::RegisterRead(register.to_owned()))
+    }
+
+    /// Adds a hook that is run instead of writing to `register`, replacing any
+    /// previous hook on the register.
+    #[track_caller]
+    pub fn add_register_write_hook(
+        &mut self,
+        register: &str,
+        hook: RegisterWriteHook<A>,
+    ) -> HookHandle {
+        self.reg_write_hooks.insert(register.to_owned(), hook);
+        self.register_hook(HookHandle::RegisterWrite(register.to_owned()))
+    }
+
+    /// Adds a hook that is run instead of reading from memory at `address`.
+    #[track_caller]
+    pub fn add_memory_read_hook(
+        &mut self,
+        address: MemoryHookAddress,
+        hook: MemoryReadHook<A>,
+    ) -> HookHandle {
+        match address {
+            MemoryHookAddress::Single(address) => {
+                self.single_memory_read_hooks.insert(address, hook);
+            }
+            MemoryHookAddress::Range(start, end) => {
+                self.range_memory_read_hooks.push(((start, end), hook));
+            }
+        }
+        self.register_hook(HookHandle::MemoryRead(address))
+    }
+
+    /// Adds a hook that is run instead of writing to memory at `address`.
+    #[track_caller]
+    pub fn add_memory_write_hook(
+        &mut self,
+        address: MemoryHookAddress,
+        hook: MemoryWriteHook<A>,
+    ) -> HookHandle {
+        match address {
+            MemoryHookAddress::Single(address) => {
+                self.single_memory_write_hooks.insert(address, hook);
+            }
+            MemoryHookAddress::Range(start, end) => {
+                self.range_memory_write_hooks.push(((start, end), hook));
+            }
+        }
+        self.register_hook(HookHandle::MemoryWrite(address))
+    }
+
+    /// Records that the hook identified by `handle` was added by the caller.
+    #[track_caller]
+    fn register_hook(&mut self, handle: HookHandle) -> HookHandle {
+        let registration = HookRegistration::Call(std::panic::Location::caller());
+        self.hook_registrations.push((handle.clone(), registration));
+        handle
+    }
+
+    /// Get where the hook identified by `handle` was added, the latest
+    /// registration if a hook was added more than once.
+    pub fn get_hook_registration(&self, handle: &HookHandle) -> Option<&HookRegistration> {
+        self.hook_registrations
+            .iter()
+            .rev()
+            .find(|(registered, _)| registered == handle)
+            .map(|(_, registration)| registration)
+    }
+
+    /// Get the handle of the hook that is run at `pc` when the function there
+    /// is called with the return address `return_address`, `None` if no hook
+    /// is run.
+    pub fn get_pc_hook_handle(&self, pc: u64, return_address: Option<u64>) -> Option<HookHandle> {
+        let call_site = return_address.and_then(|return_address| {
+            self.call_site_hooks
+                .get(&pc)?
+                .iter()
+                .find(|(_, call_site, _)| call_site.matches(self, return_address))
+        });
+        match call_site {
+            Some((id, _, _)) => Some(HookHandle::CallSite { pc, id: *id }),
+            None => {
+                let (id, _) = self.pc_hooks.get(&pc)?.last()?;
+                Some(HookHandle::Pc { pc, id: *id })
+            }
+        }
+    }
+
+    /// Get the handle of the hook that is run when reading from `address`.
+    pub fn get_memory_read_hook_handle(&self, address: u64) -> HookHandle {
+        if self.single_memory_read_hooks.contains_key(&address) {
+            return HookHandle::MemoryRead(MemoryHookAddress::Single(address));
+        }
+        let range = self
+            .range_memory_read_hooks
+            .iter()
+            .find(|((start, end), _)| address >= *start && address < *end);
+        match range {
+            Some(((start, end), _)) => {
+                HookHandle::MemoryRead(MemoryHookAddress::Range(*start, *end))
+            }
+            None => HookHandle::MemoryRead(MemoryHookAddress::Single(address)),
+        }
+    }
+
+    /// Get the handle of the hook that is run when writing to `address`.
+    pub fn get_memory_write_hook_handle(&self, address: u64) -> HookHandle {
+        if self.single_memory_write_hooks.contains_key(&address) {
+            return HookHandle::MemoryWrite(MemoryHookAddress::Single(address));
+        }
+        let range = self
+            .range_memory_write_hooks
+            .iter()
+            .find(|((start, end), _)| address >= *start && address < *end);
+        match range {
+            Some(((start, end), _)) => {
+                HookHandle::MemoryWrite(MemoryHookAddress::Range(*start, *end))
+            }
+            None => HookHandle::MemoryWrite(MemoryHookAddress::Single(address)),
+        }
+    }
+
+    /// Removes the hook identified by `handle`.
+    ///
+    /// Returns false if there is no such hook, for example if it was already
+    /// removed.
+    pub fn remove_hook(&mut self, handle: &HookHandle) -> bool {
+        let registration = self
+            .hook_registrations
+            .iter()
+            .rposition(|(registered, _)| registered == handle);
+        if let Some(index) = registration {
+            self.hook_registrations.remove(index);
+        }
+        match handle {
+            HookHandle::Pc { pc, id } => match self.pc_hooks.get_mut(pc) {
+                Some(hooks) => {
+                    let before = hooks.len();
+                    hooks.retain(|(hook_id, _)| hook_id != id);
+                    let removed = hooks.len() < before;
+                    if hooks.is_empty() {
+                        self.pc_hooks.remove(pc);
+                    }
+                    removed
+                }
+                None => false,
+            },
+            HookHandle::CallSite { pc, id } => match self.call_site_hooks.get_mut(pc) {
+                Some(hooks) => {
+                    let before = hooks.len();
+                    hooks.retain(|(hook_id, _, _)| hook_id != id);
+                    hooks.len() < before
+                }
+                None => false,
+            },
+            HookHandle::RegisterRead(register) => self.reg_read_hooks.remove(register).is_some(),
+            HookHandle::RegisterWrite(register) => self.reg_write_hooks.remove(register).is_some(),
+            HookHandle::MemoryRead(MemoryHookAddress::Single(address)) => {
+                self.single_memory_read_hooks.remove(address).is_some()
+            }
+            HookHandle::MemoryRead(MemoryHookAddress::Range(start, end)) => {
+                remove_last_range_hook(&mut self.range_memory_read_hooks, (*start, *end))
+            }
+            HookHandle::MemoryWrite(MemoryHookAddress::Single(address)) => {
+                self.single_memory_write_hooks.remove(address).is_some()
+            }
+            HookHandle::MemoryWrite(MemoryHookAddress::Range(start, end)) => {
+                remove_last_range_hook(&mut self.range_memory_write_hooks, (*start, *end))
+            }
+        }
+    }
+
+    /// Runs `f` with a temporary hook scope. All hooks are restored to the
+    /// hooks before the call when `f` returns, so hooks added or removed by `f`
+    /// do not outlive it.
+    pub fn with_hooks<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let pc_hooks = self.pc_hooks.clone();
+        let call_site_hooks = self.call_site_hooks.clone();
+        let reg_read_hooks = self.reg_read_hooks.clone();
+        let reg_write_hooks = self.reg_write_hooks.clone();
+        let single_memory_read_hooks = self.single_memory_read_hooks.clone();
+        let range_memory_read_hooks = self.range_memory_read_hooks.clone();
+        let single_memory_write_hooks = self.single_memory_write_hooks.clone();
+        let range_memory_write_hooks = self.range_memory_write_hooks.clone();
+        let hook_registrations = self.hook_registrations.clone();
+
+        let result = f(self);
+
+        self.pc_hooks = pc_hooks;
+        self.call_site_hooks = call_site_hooks;
+        self.reg_read_hooks = reg_read_hooks;
+        self.reg_write_hooks = reg_write_hooks;
+        self.single_memory_read_hooks = single_memory_read_hooks;
+        self.range_memory_read_hooks = range_memory_read_hooks;
+        self.single_memory_write_hooks = single_memory_write_hooks;
+        self.range_memory_write_hooks = range_memory_write_hooks;
+        self.hook_registrations = hook_registrations;
+        result
+    }
+
+    /// Adds an invariant for the loop with the header at `invariant.header`.
+    pub fn add_loop_invariant(&mut self, invariant: LoopInvariant<A>) {
+        self.loop_invariants
+            .insert(invariant.header & !0b1, invariant);
     }
 
-    pub fn add_pc_hook(&mut self, pc: u64, hook: PCHook<A>) {
-        self.pc_hooks.insert(pc, hook);
+    /// Get the invariant of the loop with the header at `pc`.
+    pub fn get_loop_invariant(&self, pc: u64) -> Option<&LoopInvariant<A>> {
+        self.loop_invariants.get(&pc)
+    }
+
+    /// Adds a memory region that is havoced at `havoc.pc`.
+    pub fn add_memory_havoc(&mut self, havoc: MemoryHavoc<A>) {
+        self.memory_havocs
+            .entry(havoc.pc & !0b1)
+            .or_default()
+            .push(havoc);
+    }
+
+    /// Get the memory regions that are havoced at `pc`.
+    pub fn get_memory_havocs(&self, pc: u64) -> &[MemoryHavoc<A>] {
+        self.memory_havocs
+            .get(&pc)
+            .map(|havocs| havocs.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Checks if counting loops are accelerated.
+    pub fn get_accelerate_loops(&self) -> bool {
+        self.accelerate_loops
+    }
+
+    /// Get the largest number of bytes an accelerated copy loop copies at
+    /// once, `None` if copy loops are not accelerated.
+    pub fn get_accelerate_copies(&self) -> Option<u64> {
+        self.accelerate_copies
+    }
+
+    /// Sets the largest number of bytes an accelerated copy loop copies at
+    /// once, `None` disables the acceleration of copy loops.
+    pub fn set_accelerate_copies(&mut self, max_bytes: Option<u64>) {
+        self.accelerate_copies = max_bytes;
+    }
+
+    /// Checks if the ranges inferred by the range analysis are assumed.
+    pub fn get_range_analysis(&self) -> bool {
+        self.range_analysis
+    }
+
+    /// Adds a region that cycle laps should be recorded for.
+    pub fn add_region(&mut self, region: RegionMetaData) {
+        self.regions.push(region);
+    }
+
+    /// Get the regions that cycle laps are recorded for.
+    pub fn get_regions(&self) -> &[RegionMetaData] {
+        &self.regions
+    }
+
+    /// Returns all regions that start or end at `pc`.
+    pub fn get_region_delimiters(&self, pc: u64) -> impl Iterator<Item = &RegionMetaData> {
+        self.regions
+            .iter()
+            .filter(move |region| region.start == pc || region.end == pc)
     }
 
     pub fn get_register_read_hook(&self, register: &str) -> Option<RegisterReadHook<A>> {
@@ -332,6 +1343,13 @@ impl<A: Arch> Project<A> {
         self.segments.read_raw_bytes(address, 1).is_some()
     }
 
+    /// Returns true if the `bytes` bytes at `address` are loaded from the elf
+    /// file and can not be written by the program, so they always hold their
+    /// initial value.
+    pub fn address_is_read_only(&self, address: u64, bytes: u64) -> bool {
+        self.segments.is_read_only(address, bytes)
+    }
+
     pub fn get_word_size(&self) -> u32 {
         self.get_ptr_size() // same for now
     }
@@ -353,7 +1371,561 @@ impl<A: Arch> Project<A> {
 
     /// Get the address of a symbol from the ELF symbol table
     pub fn get_symbol_address(&self, symbol: &str) -> Option<u64> {
-        self.symtab.get(symbol).copied()
+        self.symtab.address(symbol)
+    }
+
+    /// Get the size of a symbol from the ELF symbol table, zero if it is not
+    /// known.
+    pub fn get_symbol_size(&self, symbol: &str) -> Option<u64> {
+        self.symtab.size(symbol)
+    }
+
+    /// Get the names of all symbols at `address`, such as the weak aliases of
+    /// a default handler. The name preferred by
+    /// [`get_symbol_name`](Self::get_symbol_name) is first.
+    pub fn get_symbol_aliases(&self, address: u64) -> Vec<&str> {
+        self.symtab.aliases(address)
+    }
+
+    /// Get the name of the symbol that contains `address` and the offset of
+    /// `address` into that symbol.
+    ///
+    /// Falls back to the closest named symbol at or below `address` if no
+    /// symbol near it contains it.
+    pub fn get_symbol_name(&self, address: u64) -> Option<(&str, u64)> {
+        self.symtab.lookup(address)
+    }
+
+    /// Adds a global symbol of `size` bytes to the symbol table, a size of
+    /// zero means the size is not known.
+    pub fn add_symbol(&mut self, name: &str, address: u64, size: u64) {
+        self.symtab
+            .insert(name.to_owned(), address, size, SymbolBinding::Global);
+    }
+
+    /// Get the name of the symbol whose size covers `address` and the offset
+    /// of `address` into that symbol.
+    pub fn get_symbol_containing(&self, address: u64) -> Option<(&str, u64)> {
+        self.symtab.containing(address)
+    }
+
+    /// Get the maximum call depth to explore, `None` if unbounded.
+    pub fn get_max_call_depth(&self) -> Option<usize> {
+        self.max_call_depth
+    }
+
+    /// Sets the maximum call depth to explore.
+    pub fn set_max_call_depth(&mut self, max_call_depth: Option<usize>) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Sets where the exploration starts, see [`Entry`].
+    pub fn set_entry(&mut self, entry: Option<Entry>) {
+        self.entry = entry;
+    }
+
+    /// Get the address the exploration starts at, `function` unless another
+    /// [`entry`](super::RunConfig::entry) is configured.
+    pub fn get_entry_address(&self, function: &str) -> SuperResult<u64> {
+        let function = match self.entry {
+            None => function,
+            Some(Entry::Main) => "main",
+            Some(Entry::Reset) => {
+                let table = VECTOR_TABLE_SYMBOLS
+                    .iter()
+                    .find_map(|symbol| self.get_symbol_address(symbol))
+                    .ok_or_else(|| GAError::EntryFunctionNotFound("reset handler".to_owned()))?;
+                let vector = table + (self.get_ptr_size() / 8) as u64;
+                return Ok(match self.get_word(vector)? {
+                    DataWord::Word64(handler) => handler,
+                    DataWord::Word32(handler) => handler as u64,
+                    DataWord::Word16(handler) => handler as u64,
+                    DataWord::Word8(handler) => handler as u64,
+                });
+            }
+        };
+        self.get_symbol_address(function)
+            .ok_or_else(|| GAError::EntryFunctionNotFound(function.to_owned()))
+    }
+
+    /// Get the maximum number of paths forked for the values of a symbolic
+    /// address, `None` if all values up to a fixed bound are followed.
+    pub fn get_max_forks(&self) -> Option<usize> {
+        self.max_forks
+    }
+
+    /// Sets the maximum number of paths forked for the values of a symbolic
+    /// address.
+    pub fn set_max_forks(&mut self, max_forks: Option<usize>) {
+        self.max_forks = max_forks;
+    }
+
+    /// Get whether paths that reach an explored state are pruned.
+    pub fn get_deduplicate_states(&self) -> bool {
+        self.deduplicate_states
+    }
+
+    /// Sets whether paths that reach an explored state are pruned.
+    pub fn set_deduplicate_states(&mut self, deduplicate: bool) {
+        self.deduplicate_states = deduplicate;
+    }
+
+    /// Get the maximum number of solver queries per subsumption check.
+    pub fn get_subsumption_budget(&self) -> Option<usize> {
+        self.subsumption_budget
+    }
+
+    /// Sets the maximum number of solver queries per subsumption check,
+    /// `None` disables the check.
+    pub fn set_subsumption_budget(&mut self, budget: Option<usize>) {
+        self.subsumption_budget = budget;
+    }
+
+    /// Get the model used for reads of hardware time sources.
+    pub fn get_time_source(&self) -> Option<TimeSource> {
+        self.time_source
+    }
+
+    /// Sets the model used for reads of hardware time sources, it takes
+    /// effect when the architecture hooks are added.
+    pub fn set_time_source(&mut self, time_source: Option<TimeSource>) {
+        self.time_source = time_source;
+    }
+
+    /// Get the co-simulator that is notified of retired instructions.
+    pub fn get_co_simulator(&self) -> Option<&dyn CoSimulator> {
+        self.co_simulator.as_deref()
+    }
+
+    /// Get the model of the energy used by the execution.
+    pub fn get_energy_model(&self) -> Option<&dyn EnergyModel> {
+        self.energy_model.as_deref()
+    }
+
+    /// Sets the model of the energy used by the execution.
+    pub fn set_energy_model(&mut self, model: Option<Arc<dyn EnergyModel>>) {
+        self.energy_model = model;
+    }
+
+    /// Get the instruction cache that instruction fetches are looked up in.
+    pub fn get_instruction_cache(&self) -> Option<&CacheConfig> {
+        self.instruction_cache.as_ref()
+    }
+
+    /// Sets the instruction cache that instruction fetches are looked up in.
+    pub fn set_instruction_cache(&mut self, cache: Option<CacheConfig>) {
+        self.instruction_cache = cache;
+    }
+
+    /// Get the data cache that data memory accesses are looked up in.
+    pub fn get_data_cache(&self) -> Option<&CacheConfig> {
+        self.data_cache.as_ref()
+    }
+
+    /// Sets the data cache that data memory accesses are looked up in.
+    pub fn set_data_cache(&mut self, cache: Option<CacheConfig>) {
+        self.data_cache = cache;
+    }
+
+    /// Get the branch predictor of the core.
+    pub fn get_branch_predictor(&self) -> Option<&BranchPredictor> {
+        self.branch_predictor.as_ref()
+    }
+
+    /// Sets the branch predictor of the core.
+    pub fn set_branch_predictor(&mut self, predictor: Option<BranchPredictor>) {
+        self.branch_predictor = predictor;
+    }
+
+    /// Get the model of the stalls between dependent instructions.
+    pub fn get_pipeline(&self) -> Option<&PipelineModel> {
+        self.pipeline.as_ref()
+    }
+
+    /// Sets the model of the stalls between dependent instructions.
+    pub fn set_pipeline(&mut self, pipeline: Option<PipelineModel>) {
+        self.pipeline = pipeline;
+    }
+
+    /// Add a memory region backed by a byte buffer.
+    pub fn add_backed_region(&mut self, region: BackedRegion) {
+        self.backed_regions.push(region);
+    }
+
+    /// Checks if `address` is inside of a region backed by a byte buffer.
+    pub fn address_in_backed_region(&self, address: u64) -> bool {
+        self.backed_regions
+            .iter()
+            .any(|region| region.contains(address))
+    }
+
+    /// Get the byte at `address` from the buffer of a backed region.
+    pub fn get_backed_byte(&self, address: u64) -> Option<u8> {
+        self.backed_regions
+            .iter()
+            .find(|region| region.contains(address))
+            .map(|region| region.data[(address - region.start) as usize])
+    }
+
+    /// Add a region with its own policy for bytes that are read before they
+    /// are written, earlier regions take precedence where regions overlap.
+    pub fn add_unknown_memory_region(&mut self, region: UnknownMemoryRegion) {
+        self.unknown_memory.push(region);
+    }
+
+    /// Get the policy for bytes at `address` that are read before they are
+    /// written.
+    pub fn get_unknown_memory_policy(&self, address: u64) -> UnknownMemoryPolicy {
+        self.unknown_memory
+            .iter()
+            .find(|region| region.contains(address))
+            .map_or(UnknownMemoryPolicy::Unconstrained, |region| region.policy)
+    }
+
+    /// Get the file the knowledge base of side effects is stored in, `None`
+    /// if side effects are not recorded.
+    pub fn get_side_effects_file(&self) -> Option<&Path> {
+        self.side_effects_file.as_deref()
+    }
+
+    /// Get the registers whose writes are recorded.
+    pub fn get_recorded_registers(&self) -> &[String] {
+        &self.recorded_registers
+    }
+
+    /// Get the maximum number of register writes kept per path.
+    pub fn get_register_history_limit(&self) -> usize {
+        self.register_history_limit
+    }
+
+    /// Add an address range of a memory mapped peripheral whose accesses are
+    /// recorded.
+    pub fn add_mmio_region(&mut self, region: Range<u64>) {
+        self.mmio_regions.push(region);
+    }
+
+    /// Checks if `address` is inside of a memory mapped peripheral whose
+    /// accesses are recorded.
+    pub fn address_in_mmio_region(&self, address: u64) -> bool {
+        self.mmio_regions
+            .iter()
+            .any(|region| region.contains(&address))
+    }
+
+    /// Add a temporal property that every path has to satisfy.
+    pub fn add_property(&mut self, property: TemporalProperty) {
+        self.properties.push(property);
+    }
+
+    /// Get the temporal properties that every path has to satisfy.
+    pub fn get_properties(&self) -> &[TemporalProperty] {
+        &self.properties
+    }
+
+    /// Add an automaton that models the allowed behavior of a peripheral.
+    pub fn add_peripheral_model(&mut self, model: PeripheralModel) {
+        self.peripheral_models.push(model);
+    }
+
+    /// Get the index and model of the peripheral `address` belongs to, if it
+    /// is modeled.
+    pub fn get_peripheral_model(&self, address: u64) -> Option<(usize, &PeripheralModel)> {
+        self.peripheral_models
+            .iter()
+            .enumerate()
+            .find(|(_, model)| model.contains(address))
+    }
+
+    /// Get the automata that model the allowed behavior of peripherals.
+    pub fn get_peripheral_models(&self) -> &[PeripheralModel] {
+        &self.peripheral_models
+    }
+
+    /// Adds the peripherals of `model` and a memory read and a memory write
+    /// hook for the address range of each peripheral.
+    ///
+    /// Returns the handles of the added hooks.
+    #[track_caller]
+    pub fn add_hardware_model(&mut self, model: HardwareModel) -> Vec<HookHandle> {
+        let mut handles = vec![];
+        for peripheral in model.peripherals {
+            let range =
+                MemoryHookAddress::Range(peripheral.base, peripheral.base + peripheral.size);
+            handles.push(
+                self.add_memory_read_hook(range.clone(), hardware::read_mapped_register::<A>),
+            );
+            handles.push(self.add_memory_write_hook(range, hardware::write_mapped_register::<A>));
+            self.hardware.push(peripheral);
+        }
+        handles
+    }
+
+    /// Get the peripheral of a [`HardwareModel`] that `address` belongs to, if
+    /// any.
+    pub fn get_mapped_peripheral(&self, address: u64) -> Option<&MappedPeripheral> {
+        self.hardware
+            .iter()
+            .find(|peripheral| peripheral.contains(address))
+    }
+
+    /// Get the watchdog timer that has to be refreshed before it expires.
+    pub fn get_watchdog(&self) -> Option<&Watchdog> {
+        self.watchdog.as_ref()
+    }
+
+    /// Sets the watchdog timer that has to be refreshed before it expires.
+    pub fn set_watchdog(&mut self, watchdog: Option<Watchdog>) {
+        self.watchdog = watchdog;
+    }
+
+    /// Add an interrupt that may preempt the explored function.
+    pub fn add_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupts.push(interrupt);
+    }
+
+    /// Get the interrupts that may preempt the explored function.
+    pub fn get_interrupts(&self) -> &[Interrupt] {
+        &self.interrupts
+    }
+
+    /// Checks if interleavings of the interrupts that commute are pruned.
+    pub fn get_partial_order_reduction(&self) -> bool {
+        self.partial_order_reduction
+    }
+
+    /// Sets whether interleavings of the interrupts that commute are pruned.
+    pub fn set_partial_order_reduction(&mut self, reduce: bool) {
+        self.partial_order_reduction = reduce;
+    }
+
+    /// Checks if paths where a handler writes into a read-modify-write
+    /// sequence of the thread code fail.
+    pub fn get_atomicity_violations(&self) -> bool {
+        self.atomicity_violations
+    }
+
+    /// Sets whether paths where a handler writes into a read-modify-write
+    /// sequence of the thread code fail.
+    pub fn set_atomicity_violations(&mut self, detect: bool) {
+        self.atomicity_violations = detect;
+    }
+
+    /// Adds a resource whose priority ceiling is verified.
+    pub fn add_resource(&mut self, resource: Resource) {
+        self.resources.push(resource);
+    }
+
+    /// Get the resources whose priority ceilings are verified.
+    pub fn get_resources(&self) -> &[Resource] {
+        &self.resources
+    }
+
+    /// Marks the function `function` as non-reentrant.
+    pub fn add_non_reentrant(&mut self, function: &str) {
+        self.non_reentrant.push(function.to_owned());
+    }
+
+    /// Get the names of the functions that fail a path when they are
+    /// reentered.
+    pub fn get_non_reentrant(&self) -> &[String] {
+        &self.non_reentrant
+    }
+
+    /// Summarizes the calls of the function `function`.
+    pub fn add_summarized(&mut self, function: &str) {
+        self.summarized.push(function.to_owned());
+    }
+
+    /// Get the names of the functions whose calls are summarized.
+    pub fn get_summarized(&self) -> &[String] {
+        &self.summarized
+    }
+
+    /// Checks if the enable, pending and active registers of the NVIC are
+    /// modeled.
+    pub fn get_nvic(&self) -> bool {
+        self.nvic
+    }
+
+    /// Sets whether the enable, pending and active registers of the NVIC are
+    /// modeled.
+    pub fn set_nvic(&mut self, nvic: bool) {
+        self.nvic = nvic;
+    }
+
+    /// Checks if failing paths are sliced.
+    pub fn get_slice_failures(&self) -> bool {
+        self.slice_failures
+    }
+
+    /// Get the time limit of each solver query.
+    pub fn get_solver_timeout(&self) -> Option<Duration> {
+        self.solver_timeout
+    }
+
+    /// Checks if paths are recorded for time travel.
+    pub fn get_time_travel(&self) -> bool {
+        self.time_travel
+    }
+
+    /// Sets if paths are recorded for time travel.
+    pub fn set_time_travel(&mut self, time_travel: bool) {
+        self.time_travel = time_travel;
+    }
+
+    /// Get the number of instructions between the snapshots of a recorded
+    /// path.
+    pub fn get_snapshot_interval(&self) -> Option<usize> {
+        self.snapshot_interval
+    }
+
+    /// Sets the number of instructions between the snapshots of a recorded
+    /// path.
+    pub fn set_snapshot_interval(&mut self, snapshot_interval: Option<usize>) {
+        self.snapshot_interval = snapshot_interval;
+    }
+
+    /// Get the maximum size in bytes of a symbol, `None` if symbols are not
+    /// bounded.
+    pub fn get_max_symbol_size(&self) -> Option<u64> {
+        self.max_symbol_size
+    }
+
+    /// Sets the maximum size in bytes of a symbol.
+    pub fn set_max_symbol_size(&mut self, max_symbol_size: Option<u64>) {
+        self.max_symbol_size = max_symbol_size;
+    }
+
+    /// Get the model of the symbolic memory.
+    pub fn get_memory_model(&self) -> MemoryModel {
+        self.memory_model
+    }
+
+    /// Sets the model of the symbolic memory.
+    pub fn set_memory_model(&mut self, memory_model: MemoryModel) {
+        self.memory_model = memory_model;
+    }
+
+    /// Get the regions of memory with their own model.
+    pub fn get_memory_map(&self) -> &[MemoryMapRegion] {
+        &self.memory_map
+    }
+
+    /// Add a region of memory with its own model, earlier regions take
+    /// precedence where regions overlap.
+    pub fn add_memory_map_region(&mut self, region: MemoryMapRegion) {
+        self.memory_map.push(region);
+    }
+
+    /// Checks if locals on the stack are named.
+    pub fn get_stack_locals(&self) -> bool {
+        self.stack_locals
+    }
+
+    /// Sets if locals on the stack are named.
+    pub fn set_stack_locals(&mut self, stack_locals: bool) {
+        self.stack_locals = stack_locals;
+    }
+
+    /// Checks if the ranges of the symbolic inputs are solved for at the end of
+    /// each path.
+    pub fn get_infer_preconditions(&self) -> bool {
+        self.infer_preconditions
+    }
+
+    /// Sets if the ranges of the symbolic inputs are solved for at the end of
+    /// each path.
+    pub fn set_infer_preconditions(&mut self, infer_preconditions: bool) {
+        self.infer_preconditions = infer_preconditions;
+    }
+
+    /// Get the budget of the exploration.
+    pub fn get_budget(&self) -> &ExecutionBudget {
+        &self.budget
+    }
+
+    /// Sets the budget of the exploration.
+    pub fn set_budget(&mut self, budget: ExecutionBudget) {
+        self.budget = budget;
+    }
+
+    /// Get the stack frame layout of the function that starts at `address`,
+    /// if it was added or the program contains debug information for it.
+    ///
+    /// The stack frames in the debug data are indexed on first use.
+    pub fn get_function_frame(&self, address: u64) -> Option<&FunctionFrame> {
+        let address = address & !0b1;
+        self.function_frames
+            .get(&address)
+            .or_else(|| self.debug_index.frames().get(&address))
+    }
+
+    /// Adds the stack frame layout of the function that starts at `address`,
+    /// it replaces the layout from the debug data.
+    pub fn add_function_frame(&mut self, address: u64, frame: FunctionFrame) {
+        self.function_frames.insert(address & !0b1, frame);
+    }
+
+    /// Sets the registers whose writes are recorded and the maximum number of
+    /// writes kept per path.
+    pub fn record_registers(&mut self, registers: Vec<String>, limit: usize) {
+        self.recorded_registers = registers;
+        self.register_history_limit = limit;
+    }
+
+    /// Get the recorded trace the first path follows.
+    pub fn get_trace(&self) -> &[u64] {
+        &self.trace
+    }
+
+    /// Sets the recorded trace the first path follows.
+    pub fn set_trace(&mut self, trace: Vec<u64>) {
+        self.trace = trace;
+    }
+
+    /// Get the policy for instructions defined as unpredictable.
+    pub fn get_unpredictable_policy(&self) -> UnpredictablePolicy {
+        self.unpredictable
+    }
+
+    /// Sets the policy for instructions defined as unpredictable.
+    pub fn set_unpredictable_policy(&mut self, policy: UnpredictablePolicy) {
+        self.unpredictable = policy;
+    }
+
+    /// Get the policy for reads of registers the architecture does not define.
+    pub fn get_unknown_register_policy(&self) -> UnknownRegisterPolicy {
+        self.unknown_registers
+    }
+
+    /// Sets the policy for reads of registers the architecture does not
+    /// define.
+    pub fn set_unknown_register_policy(&mut self, policy: UnknownRegisterPolicy) {
+        self.unknown_registers = policy;
+    }
+
+    /// Builds all indices of the debug data up front instead of on first use,
+    /// for tools that inspect all of it.
+    pub fn preload_all(&self) {
+        self.debug_index.preload_all();
+    }
+
+    /// Get the source location of the instruction at `address`, if the program
+    /// contains line debug information.
+    ///
+    /// The line table is indexed on first use.
+    pub fn get_source_location(&self, address: u64) -> Option<&SourceLocation> {
+        self.debug_index
+            .line_table()
+            .range(..=address)
+            .next_back()
+            .map(|(_, location)| location)
+    }
+
+    /// Get the declared return type of the function that starts at `address`,
+    /// if the program contains debug information for it.
+    pub fn get_return_type(&self, address: u64) -> Option<debug_type::ReturnType> {
+        self.debug_index.return_type(address)
     }
 
     /// Get the instruction att a address
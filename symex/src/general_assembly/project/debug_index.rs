@@ -0,0 +1,217 @@
+//! Lazily built indices of the dwarf debug data.
+//!
+//! Indexing the debug data of a large binary takes seconds, most runs only
+//! need a small part of it. The sections are kept and each index is built the
+//! first time it is used, [`DebugIndex::preload_all`] builds all of them up
+//! front for tools that need everything. The compilation units are parsed in
+//! parallel.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use gimli::{
+    DebugAbbrev,
+    DebugAddr,
+    DebugInfo,
+    DebugLine,
+    DebugLoc,
+    DebugLocLists,
+    DebugRanges,
+    DebugRngLists,
+    DebugStr,
+    Dwarf,
+    EndianSlice,
+    LocationLists,
+    RangeLists,
+    RunTimeEndian,
+};
+use object::{File, Object, ObjectSection};
+use regex::Regex;
+use tracing::debug;
+
+use super::{
+    debug_type::ReturnType,
+    dwarf_helper::{
+        construct_frames,
+        construct_line_table,
+        construct_pc_hooks_from_subprograms,
+        construct_regions,
+        construct_return_type,
+        construct_subprograms,
+    },
+    stack_frame::FunctionFrame,
+    PCHook,
+    PCHooks,
+    RegionMetaData,
+    SourceLocation,
+};
+use crate::general_assembly::arch::Arch;
+
+type Sections<'a> = (
+    DebugInfo<EndianSlice<'a, RunTimeEndian>>,
+    DebugAbbrev<EndianSlice<'a, RunTimeEndian>>,
+    DebugStr<EndianSlice<'a, RunTimeEndian>>,
+);
+
+/// The debug sections of a binary and the indices built from them.
+#[derive(Debug)]
+pub struct DebugIndex {
+    endianness: RunTimeEndian,
+    debug_info: Arc<[u8]>,
+    debug_abbrev: Arc<[u8]>,
+    debug_str: Arc<[u8]>,
+    debug_line: Arc<[u8]>,
+    debug_addr: Arc<[u8]>,
+    debug_loc: Arc<[u8]>,
+    debug_loclists: Arc<[u8]>,
+    debug_ranges: Arc<[u8]>,
+    debug_rnglists: Arc<[u8]>,
+
+    /// Name and start address of every function.
+    subprograms: OnceLock<Vec<(String, u64)>>,
+
+    /// Source location of the start address of each line table row.
+    line_table: OnceLock<BTreeMap<u64, SourceLocation>>,
+
+    /// Stack frame layout of every function by start address.
+    frames: OnceLock<HashMap<u64, FunctionFrame>>,
+
+    /// Return types of the functions looked up so far by start address.
+    return_types: Mutex<HashMap<u64, Option<ReturnType>>>,
+}
+
+impl DebugIndex {
+    /// Creates an index without any debug data.
+    pub fn empty() -> Self {
+        Self::new(RunTimeEndian::Little, |_| &[])
+    }
+
+    /// Copies the debug sections of `file`, no index is built until it is
+    /// used. Missing sections are treated as empty.
+    pub fn from_file(file: &File<'_>, endianness: RunTimeEndian) -> Self {
+        Self::new(endianness, |name| {
+            file.section_by_name(name)
+                .and_then(|section| section.data().ok())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Copies the debug sections returned by `section` for each section name.
+    fn new<'a>(endianness: RunTimeEndian, section: impl Fn(&str) -> &'a [u8]) -> Self {
+        Self {
+            endianness,
+            debug_info: section(".debug_info").into(),
+            debug_abbrev: section(".debug_abbrev").into(),
+            debug_str: section(".debug_str").into(),
+            debug_line: section(".debug_line").into(),
+            debug_addr: section(".debug_addr").into(),
+            debug_loc: section(".debug_loc").into(),
+            debug_loclists: section(".debug_loclists").into(),
+            debug_ranges: section(".debug_ranges").into(),
+            debug_rnglists: section(".debug_rnglists").into(),
+            subprograms: OnceLock::new(),
+            line_table: OnceLock::new(),
+            frames: OnceLock::new(),
+            return_types: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn sections(&self) -> Sections<'_> {
+        (
+            DebugInfo::new(&self.debug_info, self.endianness),
+            DebugAbbrev::new(&self.debug_abbrev, self.endianness),
+            DebugStr::new(&self.debug_str, self.endianness),
+        )
+    }
+
+    /// The sections needed to read locations and address ranges, including
+    /// location lists, range lists and the address table.
+    fn dwarf(&self) -> Dwarf<EndianSlice<'_, RunTimeEndian>> {
+        let section = |data| EndianSlice::new(data, self.endianness);
+        let (debug_info, debug_abbrev, debug_str) = self.sections();
+        Dwarf {
+            debug_info,
+            debug_abbrev,
+            debug_str,
+            debug_addr: DebugAddr::from(section(&self.debug_addr)),
+            locations: LocationLists::new(
+                DebugLoc::from(section(&self.debug_loc)),
+                DebugLocLists::from(section(&self.debug_loclists)),
+            ),
+            ranges: RangeLists::new(
+                DebugRanges::from(section(&self.debug_ranges)),
+                DebugRngLists::from(section(&self.debug_rnglists)),
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// Builds all indices, in parallel.
+    pub fn preload_all(&self) {
+        rayon::join(
+            || self.subprograms(),
+            || rayon::join(|| self.line_table(), || self.frames()),
+        );
+    }
+
+    /// Get the name and start address of every function.
+    pub fn subprograms(&self) -> &[(String, u64)] {
+        self.subprograms.get_or_init(|| {
+            debug!("Indexing the functions in the debug data");
+            let (debug_info, debug_abbrev, debug_str) = self.sections();
+            construct_subprograms(&debug_info, &debug_abbrev, &debug_str)
+        })
+    }
+
+    /// Resolves hooks on the functions whose names match the expressions to
+    /// hooks on their start addresses.
+    ///
+    /// The function index is not built if there are no hooks.
+    pub fn pc_hooks<A: Arch>(&self, hooks: &Vec<(Regex, PCHook<A>)>) -> PCHooks<A> {
+        if hooks.is_empty() {
+            return PCHooks::new();
+        }
+        construct_pc_hooks_from_subprograms(hooks, self.subprograms())
+    }
+
+    /// Constructs regions for all lexical blocks and inlined subroutines.
+    pub fn regions(&self) -> Vec<RegionMetaData> {
+        construct_regions(&self.dwarf())
+    }
+
+    /// Get the table that maps the start address of each line table row to its
+    /// source location.
+    pub fn line_table(&self) -> &BTreeMap<u64, SourceLocation> {
+        self.line_table.get_or_init(|| {
+            debug!("Indexing the line table in the debug data");
+            let (debug_info, debug_abbrev, debug_str) = self.sections();
+            let debug_line = DebugLine::new(&self.debug_line, self.endianness);
+            construct_line_table(&debug_info, &debug_abbrev, &debug_line, &debug_str)
+        })
+    }
+
+    /// Get the stack frame layout of every function by start address.
+    pub fn frames(&self) -> &HashMap<u64, FunctionFrame> {
+        self.frames.get_or_init(|| {
+            debug!("Indexing the stack frames in the debug data");
+            construct_frames(&self.dwarf())
+        })
+    }
+
+    /// Get the return type of the function that starts at `address`, `None`
+    /// if the debug data does not describe the function.
+    ///
+    /// The debug data is searched the first time each function is looked up.
+    pub fn return_type(&self, address: u64) -> Option<ReturnType> {
+        let mut return_types = self.return_types.lock().unwrap_or_else(|e| e.into_inner());
+        return_types
+            .entry(address)
+            .or_insert_with(|| {
+                let (debug_info, debug_abbrev, debug_str) = self.sections();
+                construct_return_type(&debug_info, &debug_abbrev, &debug_str, address)
+            })
+            .clone()
+    }
+}
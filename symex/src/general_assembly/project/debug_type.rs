@@ -0,0 +1,982 @@
+//! Types of the values described by the dwarf debug data.
+//!
+//! Only what is needed to show a value the way the source declares it is kept,
+//! such as the members of a structure and the variants of an enum. A Rust enum
+//! is a structure with a variant part, the variant is selected by the value of
+//! its discriminant member, and a variant without a discriminant value is
+//! selected by every value the other variants do not use.
+//!
+//! An enum with a niche optimized layout, such as `Option<&T>` or
+//! `Option<bool>`, has no discriminant of its own. The discriminant member
+//! overlaps a field of the variant without a discriminant value, and the other
+//! variants are stored as values the field can not hold, such as the null
+//! pointer or `2` for a `bool`. The discriminant values in the debug data may
+//! be sign extended, so only as many bytes as the discriminant member holds
+//! are compared.
+//!
+//! A slice or string slice, such as `&[u16]` or `&str`, is a pointer to the
+//! first element and the number of elements. Its contents are shown from the
+//! memory at the end of the path when memory is available, up to
+//! [`MAX_SLICE_BYTES`] bytes.
+//!
+//! A collection with a fixed capacity, such as `heapless::Vec`,
+//! `arrayvec::ArrayVec`, `heapless::Deque` or the ring buffer of
+//! `heapless::spsc::Queue`, is recognized by the layout of its members: an
+//! array of elements and the length or the ends of the ring. Only the elements
+//! that are in the collection are shown, in order, and the collection is shown
+//! as a structure if its length is not valid.
+//!
+//! A value returned in registers is laid out in them as if it was loaded from
+//! memory a word at a time, except for a Rust function that returns a pair of
+//! scalars, such as an `Option<u8>`, which returns each scalar in a register of
+//! its own.
+
+use crate::general_assembly::Endianness;
+
+/// Largest number of bytes of the contents of a slice that are shown.
+pub const MAX_SLICE_BYTES: u64 = 256;
+
+/// Reads the bytes of the memory at the end of a path, as the number of bytes
+/// at an address. Returns `None` if the memory can not be read.
+pub type ReadMemory<'a> = &'a dyn Fn(u64, u64) -> Option<Vec<u8>>;
+
+/// The encoding of a primitive type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseEncoding {
+    Signed,
+    Unsigned,
+    Boolean,
+    Char,
+    Float,
+}
+
+/// A member of a structure or the variant of an enum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    /// Name of the member, tuple members are named `__0`, `__1` and so on.
+    pub name: String,
+
+    /// Offset of the member from the start of the enclosing value in bytes.
+    pub offset: u64,
+
+    /// Type of the member.
+    pub ty: DebugType,
+}
+
+/// A variant of a Rust enum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    /// Value of the discriminant that selects the variant, `None` for the
+    /// variant selected by the values no other variant uses.
+    pub discriminant: Option<u64>,
+
+    /// The variant, a structure named after the variant that holds its
+    /// fields.
+    pub member: Member,
+}
+
+/// Which elements of the array of a [`DebugType::Collection`] are in the
+/// collection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Collection {
+    /// The first `len` elements, as `heapless::Vec` and `arrayvec::ArrayVec`.
+    Prefix { len: Member },
+
+    /// A ring from the index `front` to before the index `back`, all elements
+    /// if `full` is set, as `heapless::Deque`.
+    Deque {
+        front: Member,
+        back: Member,
+        full: Member,
+    },
+
+    /// A ring from the index `head` to before the index `tail`, as
+    /// `heapless::spsc::Queue`.
+    Queue { head: Member, tail: Member },
+}
+
+/// The type of a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugType {
+    /// A type without values, such as the return type of a function that does
+    /// not return anything.
+    Unit,
+
+    /// A primitive type of `size` bytes.
+    Base {
+        name: String,
+        size: u64,
+        encoding: BaseEncoding,
+    },
+
+    /// A pointer or reference of `size` bytes.
+    Pointer { name: String, size: u64 },
+
+    /// A C-like enumeration of `size` bytes and the values of its
+    /// enumerators.
+    Enumeration {
+        name: String,
+        size: u64,
+        enumerators: Vec<(String, u64)>,
+    },
+
+    /// An array of `count` elements of `size` bytes in total.
+    Array {
+        name: String,
+        size: u64,
+        element: Box<DebugType>,
+        count: u64,
+    },
+
+    /// A slice of `size` bytes, a pointer to the first element and the
+    /// number of elements. `utf8` is set for string slices.
+    Slice {
+        name: String,
+        size: u64,
+        pointer: Box<Member>,
+        length: Box<Member>,
+        element: Box<DebugType>,
+        utf8: bool,
+    },
+
+    /// A collection of `size` bytes whose elements are stored in the array
+    /// `buffer`. `utf8` is set for strings, the structure `members` are shown
+    /// if the length of the collection is not valid.
+    Collection {
+        name: String,
+        size: u64,
+        buffer: Box<Member>,
+        layout: Box<Collection>,
+        members: Vec<Member>,
+        utf8: bool,
+    },
+
+    /// A structure or tuple of `size` bytes.
+    Struct {
+        name: String,
+        size: u64,
+        members: Vec<Member>,
+    },
+
+    /// A Rust enum of `size` bytes.
+    Variants {
+        name: String,
+        size: u64,
+        discriminant: Option<Box<Member>>,
+        variants: Vec<Variant>,
+    },
+
+    /// A type that can not be shown, such as a union.
+    Unknown { name: String, size: Option<u64> },
+}
+
+/// The declared return type of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnType {
+    /// Type of the returned value.
+    pub ty: DebugType,
+
+    /// True if the function is written in Rust.
+    pub rust: bool,
+}
+
+impl ReturnType {
+    /// Lays out the value returned in the words `registers` of `word` bytes
+    /// the way it is stored in memory, `None` if the value does not fit in
+    /// them and is returned in memory.
+    pub fn from_registers(
+        &self,
+        registers: &[u64],
+        word: u64,
+        endianness: &Endianness,
+    ) -> Option<Vec<u8>> {
+        let size = self.ty.size()?;
+        if size > registers.len() as u64 * word {
+            return None;
+        }
+        let mut bytes = vec![0; size as usize];
+        let mut scalars = vec![];
+        let pair = self.rust
+            && self.ty.scalars(0, &mut scalars).is_some()
+            && scalars.len() == 2
+            && scalars.iter().all(|(_, size)| *size <= word);
+        let slots = match pair {
+            true => scalars,
+            false => (0..size.div_ceil(word))
+                .map(|n| (n * word, word.min(size - n * word)))
+                .collect(),
+        };
+        for ((offset, size), value) in slots.into_iter().zip(registers) {
+            let size = size as usize;
+            let value = match endianness {
+                Endianness::Little => value.to_le_bytes()[..size].to_vec(),
+                Endianness::Big => value.to_be_bytes()[8 - size..].to_vec(),
+            };
+            bytes[offset as usize..offset as usize + size].copy_from_slice(&value);
+        }
+        Some(bytes)
+    }
+}
+
+impl DebugType {
+    /// Makes a structure of `members`, or a collection if the members have the
+    /// layout of one.
+    pub fn structure(name: String, size: u64, members: Vec<Member>) -> Self {
+        let find = |wanted: &str| members.iter().find(|member| member.name == wanted).cloned();
+        let buffer = find("buffer")
+            .or_else(|| find("xs"))
+            .filter(|buffer| matches!(buffer.ty, DebugType::Array { .. }));
+        let layout = match (find("front"), find("back"), find("full")) {
+            (Some(front), Some(back), Some(full)) => Some(Collection::Deque { front, back, full }),
+            _ => match (find("head"), find("tail"), find("len")) {
+                (Some(head), Some(tail), _) => Some(Collection::Queue { head, tail }),
+                (_, _, Some(len)) => Some(Collection::Prefix { len }),
+                _ => None,
+            },
+        };
+        if let (Some(buffer), Some(layout)) = (buffer, layout) {
+            let utf8 = name.starts_with("ArrayString<") && buffer.ty.is_byte_array();
+            return DebugType::Collection {
+                name,
+                size,
+                buffer: Box::new(buffer),
+                layout: Box::new(layout),
+                members,
+                utf8,
+            };
+        }
+
+        // A `heapless::String` holds a vector of its bytes.
+        if let [vec] = members.as_slice() {
+            if let DebugType::Collection { buffer, layout, .. } = &vec.ty {
+                match layout.as_ref() {
+                    Collection::Prefix { len }
+                        if buffer.ty.is_byte_array() && name.starts_with("String<") =>
+                    {
+                        let shifted = |member: &Member| Member {
+                            offset: vec.offset + member.offset,
+                            ..member.clone()
+                        };
+                        return DebugType::Collection {
+                            name,
+                            size,
+                            buffer: Box::new(shifted(buffer)),
+                            layout: Box::new(Collection::Prefix { len: shifted(len) }),
+                            members,
+                            utf8: true,
+                        };
+                    }
+                    _ => {}
+                }
+            }
+        }
+        DebugType::Struct {
+            name,
+            size,
+            members,
+        }
+    }
+
+    /// Checks if the type is an array of elements of a single byte.
+    fn is_byte_array(&self) -> bool {
+        match self {
+            DebugType::Array { element, .. } => element.size() == Some(1),
+            _ => false,
+        }
+    }
+
+    /// Get the size of a value of the type in bytes, if it is known.
+    pub fn size(&self) -> Option<u64> {
+        match self {
+            DebugType::Unit => Some(0),
+            DebugType::Base { size, .. }
+            | DebugType::Pointer { size, .. }
+            | DebugType::Enumeration { size, .. }
+            | DebugType::Array { size, .. }
+            | DebugType::Slice { size, .. }
+            | DebugType::Collection { size, .. }
+            | DebugType::Struct { size, .. }
+            | DebugType::Variants { size, .. } => Some(*size),
+            DebugType::Unknown { size, .. } => *size,
+        }
+    }
+
+    /// Shows the value stored in `bytes` the way the source declares it, such
+    /// as `Err(3)`. Returns `None` if the type can not be shown or `bytes` is
+    /// too short.
+    pub fn render(&self, bytes: &[u8], endianness: &Endianness) -> Option<String> {
+        self.render_with_memory(bytes, endianness, &|_, _| None)
+    }
+
+    /// Shows the value stored in `bytes` like [`render`](Self::render), with
+    /// the contents of slices read from `memory`.
+    pub fn render_with_memory(
+        &self,
+        bytes: &[u8],
+        endianness: &Endianness,
+        memory: ReadMemory<'_>,
+    ) -> Option<String> {
+        match self {
+            DebugType::Unit => Some("()".to_owned()),
+            DebugType::Base { size, encoding, .. } => {
+                let value = read(bytes, 0, *size, endianness)?;
+                let bits = *size as u32 * 8;
+                Some(match encoding {
+                    BaseEncoding::Signed => {
+                        let shift = 64 - bits;
+                        (((value << shift) as i64) >> shift).to_string()
+                    }
+                    BaseEncoding::Unsigned => value.to_string(),
+                    BaseEncoding::Boolean => (value != 0).to_string(),
+                    BaseEncoding::Char => match char::from_u32(value as u32) {
+                        Some(c) => format!("{c:?}"),
+                        None => format!("{value:#x}"),
+                    },
+                    BaseEncoding::Float => match size {
+                        4 => f32::from_bits(value as u32).to_string(),
+                        8 => f64::from_bits(value).to_string(),
+                        _ => return None,
+                    },
+                })
+            }
+            DebugType::Pointer { size, .. } => {
+                Some(format!("{:#x}", read(bytes, 0, *size, endianness)?))
+            }
+            DebugType::Enumeration {
+                size, enumerators, ..
+            } => {
+                let value = read(bytes, 0, *size, endianness)?;
+                Some(
+                    match enumerators
+                        .iter()
+                        .find(|(_, known)| truncate(*known, *size) == value)
+                    {
+                        Some((name, _)) => name.clone(),
+                        None => value.to_string(),
+                    },
+                )
+            }
+            DebugType::Struct { name, members, .. } => {
+                render_struct(name, members, bytes, endianness, memory)
+            }
+            DebugType::Collection {
+                name,
+                buffer,
+                layout,
+                members,
+                utf8,
+                ..
+            } => {
+                let (element, count) = match &buffer.ty {
+                    DebugType::Array { element, count, .. } => (element, *count),
+                    _ => return None,
+                };
+                let indices = match layout.indices(bytes, count, endianness) {
+                    Some(indices) => indices,
+                    None => return render_struct(name, members, bytes, endianness, memory),
+                };
+                let element_size = element.size()?;
+                let element_bytes =
+                    |index: u64| bytes.get((buffer.offset + index * element_size) as usize..);
+                if *utf8 {
+                    let contents: Option<Vec<u8>> = indices
+                        .into_iter()
+                        .map(|index| element_bytes(index)?.first().copied())
+                        .collect();
+                    return Some(format!("{:?}", String::from_utf8_lossy(&contents?)));
+                }
+                let mut elements = vec![];
+                for index in indices {
+                    elements.push(element.render_with_memory(
+                        element_bytes(index)?,
+                        endianness,
+                        memory,
+                    )?);
+                }
+                Some(format!("[{}]", elements.join(", ")))
+            }
+            DebugType::Variants {
+                discriminant,
+                variants,
+                ..
+            } => {
+                let value = match discriminant {
+                    Some(member) => {
+                        let size = member.ty.size()?;
+                        Some((read(bytes, member.offset, size, endianness)?, size))
+                    }
+                    None => None,
+                };
+                let variant = variants
+                    .iter()
+                    .find(|variant| match (variant.discriminant, value) {
+                        (Some(discriminant), Some((value, size))) => {
+                            truncate(discriminant, size) == value
+                        }
+                        _ => false,
+                    })
+                    .or_else(|| {
+                        variants
+                            .iter()
+                            .find(|variant| variant.discriminant.is_none())
+                    })?;
+                variant
+                    .member
+                    .ty
+                    .render_with_memory(bytes, endianness, memory)
+            }
+            DebugType::Array { element, count, .. } => {
+                let element_size = element.size()?;
+                let mut elements = vec![];
+                for n in 0..*count {
+                    let bytes = bytes.get((n * element_size) as usize..)?;
+                    elements.push(element.render_with_memory(bytes, endianness, memory)?);
+                }
+                Some(format!("[{}]", elements.join(", ")))
+            }
+            DebugType::Slice {
+                pointer,
+                length,
+                element,
+                utf8,
+                ..
+            } => {
+                let address = read(bytes, pointer.offset, pointer.ty.size()?, endianness)?;
+                let length = read(bytes, length.offset, length.ty.size()?, endianness)?;
+                let raw = || format!("{{ data_ptr: {address:#x}, length: {length} }}");
+                let element_size = match element.size()? {
+                    0 => return Some(raw()),
+                    size => size,
+                };
+                let shown = length.min(MAX_SLICE_BYTES / element_size);
+                let contents = match memory(address, shown * element_size) {
+                    Some(contents) => contents,
+                    None => return Some(raw()),
+                };
+                let more = match shown < length {
+                    true => "..",
+                    false => "",
+                };
+                if *utf8 {
+                    return Some(format!("{:?}{more}", String::from_utf8_lossy(&contents)));
+                }
+                let mut elements = vec![];
+                for n in 0..shown {
+                    let bytes = contents.get((n * element_size) as usize..)?;
+                    elements.push(element.render_with_memory(bytes, endianness, memory)?);
+                }
+                if shown < length {
+                    elements.push(more.to_owned());
+                }
+                Some(format!("[{}]", elements.join(", ")))
+            }
+            DebugType::Unknown { .. } => None,
+        }
+    }
+
+    /// Adds the offset and size of every scalar of every variant of the type
+    /// to `scalars`, the type starting `offset` bytes into the value. Returns
+    /// `None` if the type is not known.
+    fn scalars(&self, offset: u64, scalars: &mut Vec<(u64, u64)>) -> Option<()> {
+        match self {
+            DebugType::Unit => {}
+            DebugType::Base { size, .. }
+            | DebugType::Pointer { size, .. }
+            | DebugType::Enumeration { size, .. } => {
+                if !scalars.contains(&(offset, *size)) {
+                    scalars.push((offset, *size));
+                    scalars.sort();
+                }
+            }
+            DebugType::Array { element, count, .. } => {
+                let element_size = element.size()?;
+                for n in 0..*count {
+                    element.scalars(offset + n * element_size, scalars)?;
+                }
+            }
+            DebugType::Slice {
+                pointer, length, ..
+            } => {
+                for member in [pointer, length] {
+                    member.ty.scalars(offset + member.offset, scalars)?;
+                }
+            }
+            DebugType::Struct { members, .. } | DebugType::Collection { members, .. } => {
+                for member in members {
+                    member.ty.scalars(offset + member.offset, scalars)?;
+                }
+            }
+            DebugType::Variants {
+                discriminant,
+                variants,
+                ..
+            } => {
+                let members = variants.iter().map(|variant| &variant.member);
+                for member in discriminant.as_deref().into_iter().chain(members) {
+                    member.ty.scalars(offset + member.offset, scalars)?;
+                }
+            }
+            DebugType::Unknown { .. } => return None,
+        }
+        Some(())
+    }
+}
+
+impl Collection {
+    /// Lists the indices of the elements in the collection stored in `bytes`
+    /// in order, `None` if the length or the ends are not valid for an array
+    /// of `count` elements.
+    fn indices(&self, bytes: &[u8], count: u64, endianness: &Endianness) -> Option<Vec<u64>> {
+        let scalar = |member: &Member| scalar(member, bytes, endianness);
+        match self {
+            Collection::Prefix { len } => {
+                let len = scalar(len)?;
+                (len <= count).then(|| (0..len).collect())
+            }
+            Collection::Deque { front, back, full } => {
+                let (front, back, full) = (scalar(front)?, scalar(back)?, scalar(full)? != 0);
+                if front >= count || back >= count || (full && front != back) {
+                    return None;
+                }
+                let len = match full {
+                    true => count,
+                    false => (back + count - front) % count,
+                };
+                Some((0..len).map(|n| (front + n) % count).collect())
+            }
+            Collection::Queue { head, tail } => {
+                let (head, tail) = (scalar(head)?, scalar(tail)?);
+                if head >= count || tail >= count {
+                    return None;
+                }
+                let len = (tail + count - head) % count;
+                Some((0..len).map(|n| (head + n) % count).collect())
+            }
+        }
+    }
+}
+
+/// Reads the integer `member` of `bytes`, through structures of a single
+/// member such as atomics.
+fn scalar(member: &Member, bytes: &[u8], endianness: &Endianness) -> Option<u64> {
+    let mut offset = member.offset;
+    let mut ty = &member.ty;
+    while let DebugType::Struct { members, .. } = ty {
+        let [inner] = members.as_slice() else {
+            return None;
+        };
+        offset += inner.offset;
+        ty = &inner.ty;
+    }
+    read(bytes, offset, ty.size()?, endianness)
+}
+
+/// Shows a structure or tuple named `name` of `members`.
+fn render_struct(
+    name: &str,
+    members: &[Member],
+    bytes: &[u8],
+    endianness: &Endianness,
+    memory: ReadMemory<'_>,
+) -> Option<String> {
+    let mut fields = vec![];
+    for member in members {
+        fields.push(member.render(bytes, endianness, memory)?);
+    }
+    let tuple = members.iter().all(|member| member.name.starts_with("__"));
+    Some(match (fields.is_empty(), tuple) {
+        (true, _) => name.to_owned(),
+        // Tuples are named after their element types.
+        (false, true) if name.starts_with('(') => format!("({})", fields.join(", ")),
+        (false, true) => format!("{name}({})", fields.join(", ")),
+        (false, false) => {
+            let fields: Vec<String> = members
+                .iter()
+                .zip(fields)
+                .map(|(member, field)| format!("{}: {field}", member.name))
+                .collect();
+            format!("{name} {{ {} }}", fields.join(", "))
+        }
+    })
+}
+
+impl Member {
+    fn render(
+        &self,
+        bytes: &[u8],
+        endianness: &Endianness,
+        memory: ReadMemory<'_>,
+    ) -> Option<String> {
+        self.ty
+            .render_with_memory(bytes.get(self.offset as usize..)?, endianness, memory)
+    }
+}
+
+/// Keeps the low `size` bytes of `value`, such as of a sign extended constant.
+fn truncate(value: u64, size: u64) -> u64 {
+    match size {
+        0..8 => value & ((1 << (size * 8)) - 1),
+        _ => value,
+    }
+}
+
+/// Reads an unsigned integer of `size` bytes at `offset` in `bytes`.
+fn read(bytes: &[u8], offset: u64, size: u64, endianness: &Endianness) -> Option<u64> {
+    if size == 0 || size > 8 {
+        return None;
+    }
+    let bytes = bytes.get(offset as usize..(offset + size) as usize)?;
+    let fold = |value: u64, byte: &u8| value << 8 | *byte as u64;
+    Some(match endianness {
+        Endianness::Little => bytes.iter().rev().fold(0, fold),
+        Endianness::Big => bytes.iter().fold(0, fold),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BaseEncoding, DebugType, Member, ReturnType, Variant, MAX_SLICE_BYTES};
+    use crate::general_assembly::Endianness;
+
+    fn base(name: &str, size: u64, encoding: BaseEncoding) -> DebugType {
+        DebugType::Base {
+            name: name.to_owned(),
+            size,
+            encoding,
+        }
+    }
+
+    fn member(name: &str, offset: u64, ty: DebugType) -> Member {
+        Member {
+            name: name.to_owned(),
+            offset,
+            ty,
+        }
+    }
+
+    fn variant(discriminant: Option<u64>, name: &str, members: Vec<Member>) -> Variant {
+        let ty = DebugType::Struct {
+            name: name.to_owned(),
+            size: 8,
+            members,
+        };
+        Variant {
+            discriminant,
+            member: member(name, 0, ty),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let little = Endianness::Little;
+        let i16 = base("i16", 2, BaseEncoding::Signed);
+        assert_eq!(i16.render(&[0xfe, 0xff], &little).unwrap(), "-2");
+        assert_eq!(i16.render(&[0xfe], &little), None);
+        let char = base("char", 4, BaseEncoding::Char);
+        assert_eq!(char.render(&[0x61, 0, 0, 0], &little).unwrap(), "'a'");
+
+        // Result<u32, u8>
+        let result = DebugType::Variants {
+            name: "Result<u32, u8>".to_owned(),
+            size: 8,
+            discriminant: Some(Box::new(member(
+                "",
+                0,
+                base("u32", 4, BaseEncoding::Unsigned),
+            ))),
+            variants: vec![
+                variant(Some(0), "Ok", vec![member(
+                    "__0",
+                    4,
+                    base("u32", 4, BaseEncoding::Unsigned),
+                )]),
+                variant(Some(1), "Err", vec![member(
+                    "__0",
+                    4,
+                    base("u8", 1, BaseEncoding::Unsigned),
+                )]),
+            ],
+        };
+        let err = [1, 0, 0, 0, 3, 0, 0, 0];
+        assert_eq!(result.render(&err, &little).unwrap(), "Err(3)");
+
+        // Option<&u8> uses the null pointer as the discriminant of None.
+        let option = DebugType::Variants {
+            name: "Option<&u8>".to_owned(),
+            size: 4,
+            discriminant: Some(Box::new(member("", 0, DebugType::Pointer {
+                name: "&u8".to_owned(),
+                size: 4,
+            }))),
+            variants: vec![
+                variant(Some(0), "None", vec![]),
+                variant(None, "Some", vec![member("__0", 0, DebugType::Pointer {
+                    name: "&u8".to_owned(),
+                    size: 4,
+                })]),
+            ],
+        };
+        assert_eq!(option.render(&[0; 4], &little).unwrap(), "None");
+        let some = [0x00, 0x10, 0x00, 0x20];
+        assert_eq!(option.render(&some, &little).unwrap(), "Some(0x20001000)");
+
+        let point = DebugType::Struct {
+            name: "Point".to_owned(),
+            size: 4,
+            members: vec![member("x", 0, i16.clone()), member("y", 2, i16)],
+        };
+        let bytes = [1, 0, 0, 1];
+        assert_eq!(
+            point.render(&bytes, &little).unwrap(),
+            "Point { x: 1, y: 256 }"
+        );
+        assert_eq!(
+            point.render(&bytes, &Endianness::Big).unwrap(),
+            "Point { x: 256, y: 1 }"
+        );
+    }
+
+    #[test]
+    fn test_niche() {
+        let little = Endianness::Little;
+        let bool = base("bool", 1, BaseEncoding::Boolean);
+        let u32 = base("u32", 4, BaseEncoding::Unsigned);
+
+        // enum Small { A(u32, bool), B, C } stores B and C in the niche of the
+        // bool.
+        let small = DebugType::Variants {
+            name: "Small".to_owned(),
+            size: 8,
+            discriminant: Some(Box::new(member("", 4, bool.clone()))),
+            variants: vec![
+                variant(None, "A", vec![
+                    member("__0", 0, u32),
+                    member("__1", 4, bool),
+                ]),
+                variant(Some(2), "B", vec![]),
+                variant(Some(3), "C", vec![]),
+            ],
+        };
+        assert_eq!(
+            small.render(&[7, 0, 0, 0, 1, 0, 0, 0], &little).unwrap(),
+            "A(7, true)"
+        );
+        assert_eq!(
+            small.render(&[7, 0, 0, 0, 2, 0, 0, 0], &little).unwrap(),
+            "B"
+        );
+        assert_eq!(
+            small.render(&[0, 0, 0, 0, 3, 0, 0, 0], &little).unwrap(),
+            "C"
+        );
+
+        // Negative discriminants may be sign extended in the debug data.
+        let i8 = base("i8", 1, BaseEncoding::Signed);
+        let ordering = DebugType::Variants {
+            name: "Ordering".to_owned(),
+            size: 1,
+            discriminant: Some(Box::new(member("", 0, i8))),
+            variants: vec![
+                variant(Some(-1i64 as u64), "Less", vec![]),
+                variant(Some(0), "Equal", vec![]),
+                variant(Some(1), "Greater", vec![]),
+            ],
+        };
+        assert_eq!(ordering.render(&[0xff], &little).unwrap(), "Less");
+        assert_eq!(ordering.render(&[0x01], &little).unwrap(), "Greater");
+        let ordering = DebugType::Enumeration {
+            name: "Ordering".to_owned(),
+            size: 1,
+            enumerators: vec![("Less".to_owned(), -1i64 as u64)],
+        };
+        assert_eq!(ordering.render(&[0xff], &little).unwrap(), "Less");
+    }
+
+    fn slice(name: &str, element: DebugType, utf8: bool) -> DebugType {
+        let usize = base("usize", 4, BaseEncoding::Unsigned);
+        DebugType::Slice {
+            name: name.to_owned(),
+            size: 8,
+            pointer: Box::new(member("data_ptr", 0, DebugType::Pointer {
+                name: "*const u8".to_owned(),
+                size: 4,
+            })),
+            length: Box::new(member("length", 4, usize)),
+            element: Box::new(element),
+            utf8,
+        }
+    }
+
+    #[test]
+    fn test_render_contents() {
+        let little = Endianness::Little;
+        let u8 = base("u8", 1, BaseEncoding::Unsigned);
+        let u16 = base("u16", 2, BaseEncoding::Unsigned);
+        let memory = |address: u64, len: u64| match address {
+            0x2000_0000 => Some((0..len).map(|n| b'a' + n as u8 % 26).collect()),
+            _ => None,
+        };
+
+        let array = DebugType::Array {
+            name: "[u16; 3]".to_owned(),
+            size: 6,
+            element: Box::new(u16.clone()),
+            count: 3,
+        };
+        assert_eq!(
+            array.render(&[1, 0, 2, 0, 0, 1], &little).unwrap(),
+            "[1, 2, 256]"
+        );
+
+        let str = slice("&str", u8.clone(), true);
+        let bytes = [0x00, 0x00, 0x00, 0x20, 3, 0, 0, 0];
+        assert_eq!(
+            str.render_with_memory(&bytes, &little, &memory).unwrap(),
+            "\"abc\""
+        );
+        let long = [0x00, 0x00, 0x00, 0x20, 0, 2, 0, 0];
+        let rendered = str.render_with_memory(&long, &little, &memory).unwrap();
+        assert!(rendered.ends_with("\"..") && rendered.len() == MAX_SLICE_BYTES as usize + 4);
+
+        let slice = slice("&[u16]", u16, false);
+        assert_eq!(
+            slice.render_with_memory(&bytes, &little, &memory).unwrap(),
+            "[25185, 25699, 26213]"
+        );
+        assert_eq!(
+            slice.render_with_memory(&long, &little, &memory).unwrap(),
+            format!(
+                "[{}, ..]",
+                (0..MAX_SLICE_BYTES / 2)
+                    .map(|n| {
+                        let n = 2 * n as u8;
+                        u16::from_le_bytes([b'a' + n % 26, b'a' + (n + 1) % 26]).to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        );
+
+        // Without the memory only the fat pointer is shown.
+        let unknown = [0x00, 0x10, 0x00, 0x20, 3, 0, 0, 0];
+        assert_eq!(
+            slice
+                .render_with_memory(&unknown, &little, &memory)
+                .unwrap(),
+            "{ data_ptr: 0x20001000, length: 3 }"
+        );
+        assert_eq!(
+            str.render(&bytes, &little).unwrap(),
+            "{ data_ptr: 0x20000000, length: 3 }"
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        let little = Endianness::Little;
+        let u8 = base("u8", 1, BaseEncoding::Unsigned);
+        let usize = base("usize", 4, BaseEncoding::Unsigned);
+        let array = |element: &DebugType, count: u64| DebugType::Array {
+            name: String::new(),
+            size: count * element.size().unwrap(),
+            element: Box::new(element.clone()),
+            count,
+        };
+
+        // heapless::Vec<u16, 3>
+        let u16 = base("u16", 2, BaseEncoding::Unsigned);
+        let vec = DebugType::structure("Vec<u16, 3>".to_owned(), 12, vec![
+            member("buffer", 0, array(&u16, 3)),
+            member("len", 8, usize.clone()),
+        ]);
+        let bytes = [1, 0, 2, 0, 3, 0, 0, 0, 2, 0, 0, 0];
+        assert_eq!(vec.render(&bytes, &little).unwrap(), "[1, 2]");
+        // A length larger than the capacity shows the structure.
+        let bytes = [1, 0, 2, 0, 3, 0, 0, 0, 4, 0, 0, 0];
+        assert_eq!(
+            vec.render(&bytes, &little).unwrap(),
+            "Vec<u16, 3> { buffer: [1, 2, 3], len: 4 }"
+        );
+
+        // heapless::String<4> holds a heapless::Vec<u8, 4>.
+        let bytes_vec = DebugType::structure("Vec<u8, 4>".to_owned(), 8, vec![
+            member("len", 0, usize.clone()),
+            member("buffer", 4, array(&u8, 4)),
+        ]);
+        let string =
+            DebugType::structure("String<4>".to_owned(), 8, vec![member("vec", 0, bytes_vec)]);
+        let bytes = [2, 0, 0, 0, b'h', b'i', b'!', 0];
+        assert_eq!(string.render(&bytes, &little).unwrap(), "\"hi\"");
+
+        // heapless::Deque<u8, 4> wraps around the end of its buffer.
+        let bool = base("bool", 1, BaseEncoding::Boolean);
+        let deque = DebugType::structure("Deque<u8, 4>".to_owned(), 16, vec![
+            member("buffer", 0, array(&u8, 4)),
+            member("front", 4, usize.clone()),
+            member("back", 8, usize.clone()),
+            member("full", 12, bool),
+        ]);
+        let mut bytes = [10, 11, 12, 13, 3, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(deque.render(&bytes, &little).unwrap(), "[13, 10]");
+        bytes[8] = 3;
+        assert_eq!(deque.render(&bytes, &little).unwrap(), "[]");
+        bytes[12] = 1;
+        assert_eq!(deque.render(&bytes, &little).unwrap(), "[13, 10, 11, 12]");
+
+        // heapless::spsc::Queue<u8, 4> with atomic indices.
+        let atomic = DebugType::Struct {
+            name: "AtomicUsize".to_owned(),
+            size: 4,
+            members: vec![member("v", 0, usize)],
+        };
+        let queue = DebugType::structure("Queue<u8, 4>".to_owned(), 12, vec![
+            member("head", 0, atomic.clone()),
+            member("tail", 4, atomic),
+            member("buffer", 8, array(&u8, 4)),
+        ]);
+        let bytes = [2, 0, 0, 0, 1, 0, 0, 0, 10, 11, 12, 13];
+        assert_eq!(queue.render(&bytes, &little).unwrap(), "[12, 13, 10]");
+    }
+
+    #[test]
+    fn test_from_registers() {
+        let little = Endianness::Little;
+        let u8 = base("u8", 1, BaseEncoding::Unsigned);
+        let option = DebugType::Variants {
+            name: "Option<u8>".to_owned(),
+            size: 2,
+            discriminant: Some(Box::new(member("", 0, u8.clone()))),
+            variants: vec![
+                variant(Some(0), "None", vec![]),
+                variant(Some(1), "Some", vec![member("__0", 1, u8)]),
+            ],
+        };
+
+        // Rust returns the discriminant and the value in a register each.
+        let rust = ReturnType {
+            ty: option.clone(),
+            rust: true,
+        };
+        let bytes = rust.from_registers(&[1, 5], 4, &little).unwrap();
+        assert_eq!(bytes, vec![1, 5]);
+        assert_eq!(option.render(&bytes, &little).unwrap(), "Some(5)");
+
+        let c = ReturnType {
+            ty: option,
+            rust: false,
+        };
+        assert_eq!(c.from_registers(&[0x0501, 0], 4, &little).unwrap(), vec![
+            1, 5
+        ]);
+
+        let large = ReturnType {
+            ty: DebugType::Struct {
+                name: "Large".to_owned(),
+                size: 12,
+                members: vec![],
+            },
+            rust: true,
+        };
+        assert_eq!(large.from_registers(&[0, 0], 4, &little), None);
+    }
+}
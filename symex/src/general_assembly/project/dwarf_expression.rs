@@ -0,0 +1,464 @@
+//! Evaluation of dwarf location expressions and location lists.
+//!
+//! The debug data describes where a variable is stored with a location
+//! expression, a small stack machine program that may read registers, memory,
+//! the frame base of the function and the canonical frame address. A variable
+//! that moves during the function has a location list instead, with an
+//! expression for each range of program counters. The expressions are kept as
+//! bytecode when the debug data is read and are evaluated against a path
+//! through an [`ExpressionContext`].
+//!
+//! The result of a location expression is a list of pieces. A piece is in
+//! memory, in a register, or has no location and is an implicit value computed
+//! by the expression. An expression without pieces is a single piece that
+//! holds the entire variable.
+
+use std::{ops::Range, sync::Arc};
+
+use gimli::{
+    DebugAddrIndex,
+    Encoding,
+    EndianSlice,
+    Evaluation,
+    EvaluationResult,
+    Location,
+    RunTimeEndian,
+    Value,
+};
+use thiserror::Error;
+
+/// Maximum number of operations executed by an expression, bounds expressions
+/// that branch backwards.
+const MAX_ITERATIONS: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ExpressionError {
+    #[error("Malformed dwarf expression: {0}")]
+    Malformed(gimli::Error),
+
+    #[error("Dwarf expression requires {0}, which is not supported.")]
+    Unsupported(&'static str),
+
+    #[error("Value of dwarf register {0} is not known.")]
+    UnknownRegister(u16),
+
+    #[error("Memory at {0:#X} is not known.")]
+    UnknownMemory(u64),
+
+    #[error("Frame base is not known.")]
+    UnknownFrameBase,
+
+    #[error("Canonical frame address is not known.")]
+    UnknownCfa,
+
+    #[error("Value of the expression at the entry of the function is not known.")]
+    UnknownEntryValue,
+
+    #[error("Address {0} in the address table is not known.")]
+    UnknownAddressIndex(u64),
+
+    #[error("Dwarf expression does not describe an address.")]
+    NotAnAddress,
+}
+
+impl From<gimli::Error> for ExpressionError {
+    fn from(value: gimli::Error) -> Self {
+        Self::Malformed(value)
+    }
+}
+
+/// Values an expression reads from the program, `None` if a value is not
+/// known, such as a register that holds a symbolic value.
+pub trait ExpressionContext {
+    /// Get the value of the register with the dwarf register number
+    /// `register`.
+    fn register(&mut self, register: u16) -> Option<u64>;
+
+    /// Get the `size` bytes of memory at `address`.
+    fn memory(&mut self, address: u64, size: u8) -> Option<u64>;
+
+    /// Get the frame base of the current function.
+    fn frame_base(&mut self) -> Option<u64>;
+
+    /// Get the canonical frame address, the value of the stack pointer when
+    /// the current function was called.
+    fn cfa(&mut self) -> Option<u64>;
+
+    /// Get the value of the register with the dwarf register number `register`
+    /// when the current function was called.
+    fn entry_register(&mut self, _register: u16) -> Option<u64> {
+        None
+    }
+}
+
+/// Reads the registers of an expression from their values at the entry of the
+/// current function, used to evaluate `DW_OP_entry_value`.
+struct EntryContext<'a>(&'a mut dyn ExpressionContext);
+
+impl ExpressionContext for EntryContext<'_> {
+    fn register(&mut self, register: u16) -> Option<u64> {
+        self.0.entry_register(register)
+    }
+
+    fn memory(&mut self, _address: u64, _size: u8) -> Option<u64> {
+        None
+    }
+
+    fn frame_base(&mut self) -> Option<u64> {
+        None
+    }
+
+    fn cfa(&mut self) -> Option<u64> {
+        self.0.cfa()
+    }
+}
+
+/// Where a piece of a value is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PieceLocation {
+    /// The piece is optimized out.
+    Empty,
+
+    /// The piece is stored in the register with the dwarf register number.
+    Register(u16),
+
+    /// The piece is stored in memory at the address.
+    Address(u64),
+
+    /// The piece is not stored, the expression computes its value.
+    Value(u64),
+
+    /// The piece is not stored, the expression holds its bytes.
+    Bytes(Vec<u8>),
+
+    /// The piece is a pointer to a variable that is not stored, with the
+    /// offset of the debugging information entry of the variable.
+    ImplicitPointer { variable: u64, byte_offset: i64 },
+}
+
+/// A piece of a value, the result of a location expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    /// Size of the piece in bits, `None` if the piece is the entire value.
+    pub size_in_bits: Option<u64>,
+
+    /// Offset of the piece into its location in bits, `None` for no offset.
+    pub bit_offset: Option<u64>,
+
+    /// Where the piece is stored.
+    pub location: PieceLocation,
+}
+
+/// A dwarf expression, kept as bytecode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DwarfExpression {
+    bytecode: Arc<[u8]>,
+    encoding: Encoding,
+    endianness: RunTimeEndian,
+
+    /// Entries of the address table the expression refers to, resolved when
+    /// the debug data is read.
+    addresses: Vec<(u64, u64)>,
+}
+
+impl DwarfExpression {
+    /// Creates an expression from its bytecode.
+    pub fn new(bytecode: Vec<u8>, encoding: Encoding, endianness: RunTimeEndian) -> Self {
+        Self {
+            bytecode: bytecode.into(),
+            encoding,
+            endianness,
+            addresses: vec![],
+        }
+    }
+
+    /// Sets the addresses of the entries in the address table the expression
+    /// refers to, as pairs of index and address.
+    pub fn with_addresses(mut self, addresses: Vec<(u64, u64)>) -> Self {
+        self.addresses = addresses;
+        self
+    }
+
+    /// Evaluates the expression as a location description.
+    pub fn evaluate(
+        &self,
+        context: &mut dyn ExpressionContext,
+    ) -> Result<Vec<Piece>, ExpressionError> {
+        let bytecode = EndianSlice::new(&self.bytecode, self.endianness);
+        let mut evaluation = Evaluation::new(bytecode, self.encoding);
+        evaluation.set_max_iterations(MAX_ITERATIONS);
+
+        let mut result = evaluation.evaluate()?;
+        loop {
+            result = match result {
+                EvaluationResult::Complete => break,
+                EvaluationResult::RequiresMemory { address, size, .. } => {
+                    let value = context
+                        .memory(address, size)
+                        .ok_or(ExpressionError::UnknownMemory(address))?;
+                    evaluation.resume_with_memory(Value::Generic(value))?
+                }
+                EvaluationResult::RequiresRegister { register, .. } => {
+                    let value = context
+                        .register(register.0)
+                        .ok_or(ExpressionError::UnknownRegister(register.0))?;
+                    evaluation.resume_with_register(Value::Generic(value))?
+                }
+                EvaluationResult::RequiresFrameBase => {
+                    let frame_base = context
+                        .frame_base()
+                        .ok_or(ExpressionError::UnknownFrameBase)?;
+                    evaluation.resume_with_frame_base(frame_base)?
+                }
+                EvaluationResult::RequiresCallFrameCfa => {
+                    let cfa = context.cfa().ok_or(ExpressionError::UnknownCfa)?;
+                    evaluation.resume_with_call_frame_cfa(cfa)?
+                }
+                EvaluationResult::RequiresEntryValue(expression) => {
+                    let expression = Self {
+                        bytecode: expression.0.slice().into(),
+                        ..self.clone()
+                    };
+                    let value = expression
+                        .evaluate_value(&mut EntryContext(context))
+                        .map_err(|_| ExpressionError::UnknownEntryValue)?;
+                    evaluation.resume_with_entry_value(Value::Generic(value))?
+                }
+                EvaluationResult::RequiresRelocatedAddress(address) => {
+                    // Linked programs do not need relocations.
+                    evaluation.resume_with_relocated_address(address)?
+                }
+                EvaluationResult::RequiresIndexedAddress { index, .. } => {
+                    let DebugAddrIndex(index) = index;
+                    let index = index as u64;
+                    let address = self
+                        .addresses
+                        .iter()
+                        .find(|(entry, _)| *entry == index)
+                        .map(|(_, address)| *address)
+                        .ok_or(ExpressionError::UnknownAddressIndex(index))?;
+                    evaluation.resume_with_indexed_address(address)?
+                }
+                EvaluationResult::RequiresTls(_) => {
+                    return Err(ExpressionError::Unsupported("thread local storage"))
+                }
+                EvaluationResult::RequiresAtLocation(_) => {
+                    return Err(ExpressionError::Unsupported(
+                        "the location of another entry",
+                    ))
+                }
+                EvaluationResult::RequiresParameterRef(_) => {
+                    return Err(ExpressionError::Unsupported("the parameters of the caller"))
+                }
+                EvaluationResult::RequiresBaseType(_) => {
+                    return Err(ExpressionError::Unsupported("typed values"))
+                }
+            };
+        }
+
+        let address_mask = match self.encoding.address_size {
+            8 => u64::MAX,
+            size => (1 << (size as u64 * 8)) - 1,
+        };
+        evaluation
+            .result()
+            .into_iter()
+            .map(|piece| {
+                let location = match piece.location {
+                    Location::Empty => PieceLocation::Empty,
+                    Location::Register { register } => PieceLocation::Register(register.0),
+                    Location::Address { address } => PieceLocation::Address(address),
+                    Location::Value { value } => PieceLocation::Value(value.to_u64(address_mask)?),
+                    Location::Bytes { value } => PieceLocation::Bytes(value.slice().to_vec()),
+                    Location::ImplicitPointer { value, byte_offset } => {
+                        PieceLocation::ImplicitPointer {
+                            variable: value.0 as u64,
+                            byte_offset,
+                        }
+                    }
+                };
+                Ok(Piece {
+                    size_in_bits: piece.size_in_bits,
+                    bit_offset: piece.bit_offset,
+                    location,
+                })
+            })
+            .collect()
+    }
+
+    /// Evaluates the expression as a single value, such as a frame base. A
+    /// location in a register evaluates to the value of the register.
+    pub fn evaluate_value(
+        &self,
+        context: &mut dyn ExpressionContext,
+    ) -> Result<u64, ExpressionError> {
+        let pieces = self.evaluate(context)?;
+        match pieces.as_slice() {
+            [Piece {
+                location: PieceLocation::Address(value) | PieceLocation::Value(value),
+                ..
+            }] => Ok(*value),
+            [Piece {
+                location: PieceLocation::Register(register),
+                ..
+            }] => context
+                .register(*register)
+                .ok_or(ExpressionError::UnknownRegister(*register)),
+            _ => Err(ExpressionError::NotAnAddress),
+        }
+    }
+}
+
+/// The location of a variable, a list of expressions that are each valid for
+/// a range of program counters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationList {
+    entries: Vec<(Range<u64>, DwarfExpression)>,
+}
+
+impl LocationList {
+    /// Creates a location list from expressions and the range of program
+    /// counters each is valid for.
+    pub fn new(entries: Vec<(Range<u64>, DwarfExpression)>) -> Self {
+        Self { entries }
+    }
+
+    /// Creates a location list with an expression that is valid everywhere.
+    pub fn single(expression: DwarfExpression) -> Self {
+        Self::new(vec![(0..u64::MAX, expression)])
+    }
+
+    /// Get the expression that is valid at `pc`, `None` if the variable has
+    /// no location at `pc`.
+    pub fn at(&self, pc: u64) -> Option<&DwarfExpression> {
+        self.entries
+            .iter()
+            .find(|(range, _)| range.contains(&pc))
+            .map(|(_, expression)| expression)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use gimli::{Encoding, Format, RunTimeEndian};
+
+    use super::{
+        DwarfExpression,
+        ExpressionContext,
+        ExpressionError,
+        LocationList,
+        Piece,
+        PieceLocation,
+    };
+
+    /// A program with R7 = 0x2000_0ff0, a word of memory at 0x2000_0ff0 and a
+    /// frame base at R7 + 8.
+    struct TestContext;
+
+    impl ExpressionContext for TestContext {
+        fn register(&mut self, register: u16) -> Option<u64> {
+            (register == 7).then_some(0x2000_0ff0)
+        }
+
+        fn memory(&mut self, address: u64, size: u8) -> Option<u64> {
+            (address == 0x2000_0ff0 && size == 4).then_some(0x2000_0100)
+        }
+
+        fn frame_base(&mut self) -> Option<u64> {
+            Some(0x2000_0ff8)
+        }
+
+        fn cfa(&mut self) -> Option<u64> {
+            Some(0x2000_1000)
+        }
+
+        fn entry_register(&mut self, register: u16) -> Option<u64> {
+            (register == 0).then_some(42)
+        }
+    }
+
+    fn expression(bytecode: &[u8]) -> DwarfExpression {
+        let encoding = Encoding {
+            address_size: 4,
+            format: Format::Dwarf32,
+            version: 5,
+        };
+        DwarfExpression::new(bytecode.to_vec(), encoding, RunTimeEndian::Little)
+    }
+
+    fn evaluate(bytecode: &[u8]) -> Result<Vec<PieceLocation>, ExpressionError> {
+        let pieces = expression(bytecode).evaluate(&mut TestContext)?;
+        Ok(pieces.into_iter().map(|piece| piece.location).collect())
+    }
+
+    #[test]
+    fn test_locations() {
+        use PieceLocation::*;
+
+        // DW_OP_fbreg -4
+        assert_eq!(evaluate(&[0x91, 0x7c]), Ok(vec![Address(0x2000_0ff4)]));
+        // DW_OP_call_frame_cfa; DW_OP_consts -8; DW_OP_plus
+        assert_eq!(
+            evaluate(&[0x9c, 0x11, 0x78, 0x22]),
+            Ok(vec![Address(0x2000_0ff8)])
+        );
+        // DW_OP_breg7 0; DW_OP_deref; DW_OP_plus_uconst 4
+        assert_eq!(
+            evaluate(&[0x77, 0x00, 0x06, 0x23, 0x04]),
+            Ok(vec![Address(0x2000_0104)])
+        );
+        // DW_OP_reg7
+        assert_eq!(evaluate(&[0x57]), Ok(vec![Register(7)]));
+        // DW_OP_lit5; DW_OP_stack_value
+        assert_eq!(evaluate(&[0x35, 0x9f]), Ok(vec![Value(5)]));
+        // DW_OP_implicit_value 2 0xab 0xcd
+        assert_eq!(
+            evaluate(&[0x9e, 0x02, 0xab, 0xcd]),
+            Ok(vec![Bytes(vec![0xab, 0xcd])])
+        );
+        // DW_OP_entry_value (DW_OP_reg0); DW_OP_stack_value
+        assert_eq!(evaluate(&[0xa3, 0x01, 0x50, 0x9f]), Ok(vec![Value(42)]));
+        // DW_OP_reg1
+        assert_eq!(
+            expression(&[0x51]).evaluate_value(&mut TestContext),
+            Err(ExpressionError::UnknownRegister(1))
+        );
+        // DW_OP_form_tls_address
+        assert_eq!(
+            evaluate(&[0x30, 0x9b]),
+            Err(ExpressionError::Unsupported("thread local storage"))
+        );
+    }
+
+    #[test]
+    fn test_pieces() {
+        // DW_OP_reg0; DW_OP_piece 4; DW_OP_fbreg 0; DW_OP_piece 2; DW_OP_piece 2
+        let pieces = expression(&[0x50, 0x93, 0x04, 0x91, 0x00, 0x93, 0x02, 0x93, 0x02])
+            .evaluate(&mut TestContext)
+            .unwrap();
+        let piece = |size, location| Piece {
+            size_in_bits: Some(size),
+            bit_offset: None,
+            location,
+        };
+        assert_eq!(pieces, vec![
+            piece(32, PieceLocation::Register(0)),
+            piece(16, PieceLocation::Address(0x2000_0ff8)),
+            piece(16, PieceLocation::Empty),
+        ]);
+    }
+
+    #[test]
+    fn test_location_list() {
+        let list = LocationList::new(vec![
+            (0x100..0x108, expression(&[0x50])),
+            (0x108..0x120, expression(&[0x91, 0x00])),
+        ]);
+        assert_eq!(list.at(0x100), Some(&expression(&[0x50])));
+        assert_eq!(list.at(0x110), Some(&expression(&[0x91, 0x00])));
+        assert_eq!(list.at(0x120), None);
+        assert!(LocationList::single(expression(&[0x50]))
+            .at(0x120)
+            .is_some());
+    }
+}
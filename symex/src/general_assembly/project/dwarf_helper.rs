@@ -1,22 +1,65 @@
 //! Helper functions to read dwarf debug data.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use gimli::{
+    Abbreviations,
     AttributeValue,
+    DW_AT_abstract_origin,
+    DW_AT_byte_size,
+    DW_AT_const_value,
+    DW_AT_count,
+    DW_AT_data_member_location,
+    DW_AT_discr,
+    DW_AT_discr_value,
+    DW_AT_encoding,
+    DW_AT_frame_base,
+    DW_AT_language,
+    DW_AT_location,
     DW_AT_low_pc,
     DW_AT_name,
+    DW_AT_stmt_list,
+    DW_AT_type,
+    DW_AT_upper_bound,
+    DW_TAG_compile_unit,
+    DW_TAG_enumerator,
+    DW_TAG_inlined_subroutine,
+    DW_TAG_lexical_block,
+    DW_TAG_member,
     DW_TAG_subprogram,
+    DW_TAG_subrange_type,
+    DW_TAG_variant,
     DebugAbbrev,
     DebugInfo,
+    DebugLine,
     DebugPubNames,
     DebugStr,
+    DebuggingInformationEntry,
+    DwAt,
+    Dwarf,
+    Endianity,
+    Expression,
+    Operation,
     Reader,
+    ReaderOffset,
+    RunTimeEndian,
+    Unit,
+    UnitHeader,
+    UnitOffset,
 };
+use rayon::prelude::*;
 use regex::Regex;
 use tracing::{debug, trace};
 
-use super::{PCHook, PCHooks};
+use super::{
+    debug_type::{BaseEncoding, DebugType, Member, ReturnType, Variant},
+    dwarf_expression::{DwarfExpression, LocationList},
+    stack_frame::{FunctionFrame, StackVariable},
+    PCHook,
+    PCHooks,
+    RegionMetaData,
+    SourceLocation,
+};
 use crate::general_assembly::arch::Arch;
 
 /// Constructs a list of address hook pairs from a list of symbol name hook
@@ -69,59 +112,911 @@ pub fn construct_pc_hooks<R: Reader, A: Arch>(
     ret
 }
 
-pub fn construct_pc_hooks_no_index<R: Reader, A: Arch>(
-    hooks: &Vec<(Regex, PCHook<A>)>,
+/// Reads the headers of all compilation units.
+fn unit_headers<R: Reader>(debug_info: &DebugInfo<R>) -> Vec<UnitHeader<R>> {
+    let mut ret = vec![];
+    let mut units = debug_info.units();
+    while let Ok(Some(unit)) = units.next() {
+        ret.push(unit);
+    }
+    ret
+}
+
+/// Constructs a list of the name and start address of every function
+/// (subprogram) in the dwarf debug data.
+///
+/// The compilation units are parsed in parallel, the functions are listed in
+/// the order of the units.
+pub fn construct_subprograms<R: Reader + Send + Sync>(
     debug_info: &DebugInfo<R>,
     debug_abbrev: &DebugAbbrev<R>,
     debug_str: &DebugStr<R>,
+) -> Vec<(String, u64)>
+where
+    R::Offset: Send + Sync,
+{
+    trace!("Constructing subprogram index");
+    let units: Vec<Vec<(String, u64)>> = unit_headers(debug_info)
+        .par_iter()
+        .map(|unit| unit_subprograms(unit, debug_abbrev, debug_str))
+        .collect();
+    units.concat()
+}
+
+/// Lists the name and start address of every function in `unit`.
+fn unit_subprograms<R: Reader>(
+    unit: &UnitHeader<R>,
+    debug_abbrev: &DebugAbbrev<R>,
+    debug_str: &DebugStr<R>,
+) -> Vec<(String, u64)> {
+    let mut ret = vec![];
+    let abbrev = match unit.abbreviations(debug_abbrev) {
+        Ok(abbrev) => abbrev,
+        Err(_) => return ret,
+    };
+    let mut cursor = unit.entries(&abbrev);
+
+    while let Ok(Some((_dept, entry))) = cursor.next_dfs() {
+        let tag = entry.tag();
+        if tag != gimli::DW_TAG_subprogram {
+            // is not a function continue the search
+            continue;
+        }
+        let attr = match entry.attr_value(DW_AT_name) {
+            Ok(Some(a)) => a,
+            _ => continue,
+        };
+        let entry_name = match attr {
+            AttributeValue::DebugStrRef(s) => s,
+            _ => continue,
+        };
+        let name_str = match debug_str
+            .get_str(entry_name)
+            .and_then(|name| name.to_string().map(|name| name.to_string()))
+        {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+
+        if let Ok(Some(AttributeValue::Addr(addr_value))) = entry.attr_value(DW_AT_low_pc) {
+            ret.push((name_str, addr_value));
+        }
+    }
+
+    ret
+}
+
+/// Constructs a list of address hook pairs from a list of symbol name hook
+/// pairs by matching the names against the functions in `subprograms`, see
+/// [`construct_subprograms`].
+pub fn construct_pc_hooks_from_subprograms<A: Arch>(
+    hooks: &Vec<(Regex, PCHook<A>)>,
+    subprograms: &[(String, u64)],
 ) -> PCHooks<A> {
     trace!("Constructing PC hooks");
     let mut ret: PCHooks<A> = HashMap::new();
     let mut found_hooks = HashSet::new();
 
-    let mut units = debug_info.units();
-    while let Some(unit) = units.next().unwrap() {
-        let abbrev = unit.abbreviations(debug_abbrev).unwrap();
-        let mut cursor = unit.entries(&abbrev);
+    for (name_str, addr_value) in subprograms {
+        for (name, hook) in hooks {
+            if name.is_match(name_str) {
+                found_hooks.insert(name.as_str());
+                trace!("found hook for {} att addr: {:#X}", name, addr_value);
+                ret.insert(*addr_value, hook.clone());
+            }
+        }
+    }
+    if found_hooks.len() < hooks.len() {
+        debug!("Did not find addresses for all hooks.") // fix a proper error
+                                                        // here later
+    }
+
+    ret
+}
+
+/// Reads the name of a debugging information entry, following the abstract
+/// origin for inlined subroutines.
+fn entry_name<R: Reader>(
+    unit: &UnitHeader<R>,
+    abbrev: &Abbreviations,
+    entry: &DebuggingInformationEntry<'_, '_, R>,
+    debug_str: &DebugStr<R>,
+) -> Option<String> {
+    if let Some(name) = entry.attr_value(DW_AT_name).ok()? {
+        return attr_string(name, debug_str);
+    }
+    match entry.attr_value(DW_AT_abstract_origin).ok()?? {
+        AttributeValue::UnitRef(offset) => {
+            let origin = unit.entry(abbrev, offset).ok()?;
+            entry_name(unit, abbrev, &origin, debug_str)
+        }
+        _ => None,
+    }
+}
+
+/// Constructs regions for all lexical blocks and inlined subroutines in the
+/// dwarf debug data.
+///
+/// Lexical blocks are named after the enclosing function and their start
+/// address, inlined subroutines are named after the function that was
+/// inlined. Entries that describe non contiguous ranges with `DW_AT_ranges`
+/// get a region for each range.
+pub fn construct_regions<R: Reader>(dwarf: &Dwarf<R>) -> Vec<RegionMetaData> {
+    trace!("Constructing regions from debug info");
+    let mut ret = vec![];
+
+    for header in unit_headers(&dwarf.debug_info) {
+        let unit = match dwarf.unit(header) {
+            Ok(unit) => unit,
+            Err(_) => continue,
+        };
+        let mut cursor = unit.entries();
+
+        // Enclosing functions as (depth, name) pairs.
+        let mut functions: Vec<(isize, String)> = vec![];
+        let mut depth = 0;
+        while let Ok(Some((delta, entry))) = cursor.next_dfs() {
+            depth += delta;
+            while functions.last().is_some_and(|(d, _)| *d >= depth) {
+                functions.pop();
+            }
 
-        'inner: while let Some((_dept, entry)) = cursor.next_dfs().unwrap() {
             let tag = entry.tag();
-            if tag != gimli::DW_TAG_subprogram {
-                // is not a function continue the search
+            let name = || entry_name(&unit.header, &unit.abbreviations, entry, &dwarf.debug_str);
+            if tag == DW_TAG_subprogram {
+                if let Some(name) = name() {
+                    functions.push((depth, name));
+                }
                 continue;
             }
-            let attr = match entry.attr_value(DW_AT_name).unwrap() {
-                Some(a) => a,
-                None => continue,
+
+            if tag != DW_TAG_lexical_block && tag != DW_TAG_inlined_subroutine {
+                continue;
+            }
+
+            let mut ranges = match dwarf.die_ranges(&unit, entry) {
+                Ok(ranges) => ranges,
+                Err(_) => continue,
             };
-            let entry_name = match attr {
-                AttributeValue::DebugStrRef(s) => s,
+            let function = functions
+                .last()
+                .map(|(_, name)| name.as_str())
+                .unwrap_or("<unknown>");
+            let inlined = (tag == DW_TAG_inlined_subroutine)
+                .then(|| name().unwrap_or_else(|| "<unknown>".to_owned()));
+            while let Ok(Some(range)) = ranges.next() {
+                if range.begin >= range.end {
+                    continue;
+                }
+                let (start, end) = (range.begin, range.end);
+                let name = match &inlined {
+                    Some(inlined) => format!("{function}::{inlined}@{start:#x}"),
+                    None => format!("{function}::block@{start:#x}"),
+                };
+                trace!("found region {} [{:#X}, {:#X})", name, start, end);
+                ret.push(RegionMetaData { name, start, end });
+            }
+        }
+    }
+
+    ret
+}
+
+/// Constructs the stack frame layout of every function in the dwarf debug data,
+/// indexed by the start address of the function.
+///
+/// Only functions with a frame base are included. Bit 0 of the addresses is
+/// cleared, as it selects the Thumb state on ARM.
+pub fn construct_frames<R: Reader + Send + Sync>(dwarf: &Dwarf<R>) -> HashMap<u64, FunctionFrame>
+where
+    R::Offset: Send + Sync,
+{
+    trace!("Constructing stack frame index");
+    let units: Vec<Vec<(u64, FunctionFrame)>> = unit_headers(&dwarf.debug_info)
+        .into_par_iter()
+        .map(|header| match dwarf.unit(header) {
+            Ok(unit) => unit_frames(dwarf, &unit),
+            Err(_) => vec![],
+        })
+        .collect();
+    units.into_iter().flatten().collect()
+}
+
+/// Reads the stack frame layout of every function in `unit`.
+fn unit_frames<R: Reader>(dwarf: &Dwarf<R>, unit: &Unit<R>) -> Vec<(u64, FunctionFrame)> {
+    let mut ret = vec![];
+    let mut cursor = unit.entries();
+
+    // Enclosing functions as (depth, start address, frame) triples.
+    let mut functions: Vec<(isize, u64, FunctionFrame)> = vec![];
+    let mut depth = 0;
+    while let Ok(Some((delta, entry))) = cursor.next_dfs() {
+        depth += delta;
+        while functions.last().is_some_and(|(d, ..)| *d >= depth) {
+            let (_, start, frame) = functions.pop().expect("there is an enclosing function");
+            ret.push((start, frame));
+        }
+
+        match entry.tag() {
+            gimli::DW_TAG_subprogram => {
+                let start = match entry.attr_value(DW_AT_low_pc) {
+                    Ok(Some(AttributeValue::Addr(low))) => low & !0b1,
+                    _ => continue,
+                };
+                let frame_base = match entry.attr_value(DW_AT_frame_base) {
+                    Ok(Some(value)) => location_list(dwarf, unit, value),
+                    _ => None,
+                };
+                let name = entry_name(&unit.header, &unit.abbreviations, entry, &dwarf.debug_str);
+                let (Some(frame_base), Some(function)) = (frame_base, name) else {
+                    continue;
+                };
+                functions.push((depth, start, FunctionFrame {
+                    function,
+                    frame_base,
+                    variables: vec![],
+                }));
+            }
+            gimli::DW_TAG_variable | gimli::DW_TAG_formal_parameter => {
+                let Some((_, _, frame)) = functions.last_mut() else {
+                    continue;
+                };
+                let location = match entry.attr_value(DW_AT_location) {
+                    Ok(Some(value)) => location_list(dwarf, unit, value),
+                    _ => None,
+                };
+                let name = entry_name(&unit.header, &unit.abbreviations, entry, &dwarf.debug_str);
+                let (Some(location), Some(name)) = (location, name) else {
+                    continue;
+                };
+                let size =
+                    variable_size(&unit.header, &unit.abbreviations, entry, &dwarf.debug_str)
+                        .unwrap_or(0);
+                frame.variables.push(StackVariable {
+                    name,
+                    location,
+                    size,
+                });
+            }
+            _ => {}
+        }
+    }
+    ret.extend(
+        functions
+            .into_iter()
+            .map(|(_, start, frame)| (start, frame)),
+    );
+
+    ret
+}
+
+/// Reads a location attribute, either a single expression or a reference to
+/// a location list. Bit 0 of the addresses in the list is cleared.
+fn location_list<R: Reader>(
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
+    value: AttributeValue<R>,
+) -> Option<LocationList> {
+    if let AttributeValue::Exprloc(expression) = value {
+        return Some(LocationList::single(owned_expression(
+            dwarf, unit, expression,
+        )?));
+    }
+    let mut locations = dwarf.attr_locations(unit, value).ok()??;
+    let mut entries = vec![];
+    while let Some(entry) = locations.next().ok()? {
+        let range = (entry.range.begin & !0b1)..(entry.range.end & !0b1);
+        entries.push((range, owned_expression(dwarf, unit, entry.data)?));
+    }
+    Some(LocationList::new(entries))
+}
+
+/// Copies an expression out of the debug data, the entries of the address
+/// table it refers to are resolved as the table is not kept.
+fn owned_expression<R: Reader>(
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
+    expression: Expression<R>,
+) -> Option<DwarfExpression> {
+    let mut addresses = vec![];
+    let mut operations = expression.clone().operations(unit.encoding());
+    while let Some(operation) = operations.next().ok()? {
+        if let Operation::AddressIndex { index } | Operation::ConstantIndex { index } = operation {
+            let address = dwarf.address(unit, index).ok()?;
+            addresses.push((index.0.into_u64(), address));
+        }
+    }
+    let endianness = match expression.0.endian().is_big_endian() {
+        true => RunTimeEndian::Big,
+        false => RunTimeEndian::Little,
+    };
+    let bytecode = expression.0.to_slice().ok()?.into_owned();
+    Some(DwarfExpression::new(bytecode, unit.encoding(), endianness).with_addresses(addresses))
+}
+
+/// Reads the size of the type of a variable, following the abstract origin
+/// for the variables of inlined subroutines.
+fn variable_size<R: Reader>(
+    unit: &UnitHeader<R>,
+    abbrev: &Abbreviations,
+    entry: &DebuggingInformationEntry<'_, '_, R>,
+    debug_str: &DebugStr<R>,
+) -> Option<u64> {
+    let ty = match type_ref(entry) {
+        Some(ty) => ty,
+        None => match entry.attr_value(DW_AT_abstract_origin).ok()?? {
+            AttributeValue::UnitRef(offset) => type_ref(&unit.entry(abbrev, offset).ok()?)?,
+            _ => return None,
+        },
+    };
+    read_type(unit, abbrev, ty, debug_str, 1).size()
+}
+
+/// Reads a string attribute value, returns `None` for string forms that are
+/// not supported.
+fn attr_string<R: Reader>(value: AttributeValue<R>, debug_str: &DebugStr<R>) -> Option<String> {
+    match value {
+        AttributeValue::DebugStrRef(offset) => {
+            let name = debug_str.get_str(offset).ok()?;
+            Some(name.to_string_lossy().ok()?.into_owned())
+        }
+        AttributeValue::String(name) => Some(name.to_string_lossy().ok()?.into_owned()),
+        _ => None,
+    }
+}
+
+/// Constructs a table that maps the start address of each line table row to
+/// the source location of that row.
+///
+/// The source location of an address is found by looking up the closest row
+/// at or below the address.
+pub fn construct_line_table<R: Reader + Send + Sync>(
+    debug_info: &DebugInfo<R>,
+    debug_abbrev: &DebugAbbrev<R>,
+    debug_line: &DebugLine<R>,
+    debug_str: &DebugStr<R>,
+) -> BTreeMap<u64, SourceLocation>
+where
+    R::Offset: Send + Sync,
+{
+    trace!("Constructing line table");
+    let units: Vec<Vec<(u64, SourceLocation)>> = unit_headers(debug_info)
+        .par_iter()
+        .map(|unit| unit_line_table(unit, debug_abbrev, debug_line, debug_str))
+        .collect();
+
+    // Merge in unit order so that later rows replace earlier rows at the same
+    // address as when the units are read in sequence.
+    units.into_iter().flatten().collect()
+}
+
+/// Reads the rows of the line program of `unit`.
+fn unit_line_table<R: Reader>(
+    unit: &UnitHeader<R>,
+    debug_abbrev: &DebugAbbrev<R>,
+    debug_line: &DebugLine<R>,
+    debug_str: &DebugStr<R>,
+) -> Vec<(u64, SourceLocation)> {
+    let mut ret = vec![];
+    let abbrev = match unit.abbreviations(debug_abbrev) {
+        Ok(abbrev) => abbrev,
+        Err(_) => return ret,
+    };
+    let mut cursor = unit.entries(&abbrev);
+    let root = match cursor.next_dfs() {
+        Ok(Some((_, root))) => root,
+        _ => return ret,
+    };
+    let offset = match root.attr_value(DW_AT_stmt_list) {
+        Ok(Some(AttributeValue::DebugLineRef(offset))) => offset,
+        _ => return ret,
+    };
+    let program = match debug_line.program(offset, unit.address_size(), None, None) {
+        Ok(program) => program,
+        Err(_) => return ret,
+    };
+
+    let mut rows = program.rows();
+    while let Ok(Some((header, row))) = rows.next_row() {
+        if row.end_sequence() {
+            continue;
+        }
+        let line = match row.line() {
+            Some(line) => line.get(),
+            None => continue,
+        };
+        let file = header
+            .file(row.file_index())
+            .and_then(|file| attr_string(file.path_name(), debug_str))
+            .unwrap_or_else(|| "<unknown>".to_owned());
+        ret.push((row.address(), SourceLocation { file, line }));
+    }
+
+    ret
+}
+
+/// Types are read this many levels deep, deeper types are unknown.
+const MAX_TYPE_DEPTH: usize = 8;
+
+/// Reads the return type of the function that starts at `address`, the type is
+/// [`DebugType::Unit`] if the function does not return a value.
+///
+/// Returns `None` if the dwarf debug data does not describe the function. Bit
+/// 0 of the addresses is ignored, as it selects the Thumb state on ARM.
+pub fn construct_return_type<R: Reader>(
+    debug_info: &DebugInfo<R>,
+    debug_abbrev: &DebugAbbrev<R>,
+    debug_str: &DebugStr<R>,
+    address: u64,
+) -> Option<ReturnType> {
+    trace!("Reading the return type of the function at {:#X}", address);
+    for unit in unit_headers(debug_info) {
+        let abbrev = match unit.abbreviations(debug_abbrev) {
+            Ok(abbrev) => abbrev,
+            Err(_) => continue,
+        };
+        let mut cursor = unit.entries(&abbrev);
+        let mut rust = false;
+        while let Ok(Some((_, entry))) = cursor.next_dfs() {
+            if entry.tag() == DW_TAG_compile_unit {
+                rust = matches!(
+                    entry.attr_value(DW_AT_language),
+                    Ok(Some(AttributeValue::Language(gimli::DW_LANG_Rust)))
+                );
+            }
+            if entry.tag() != DW_TAG_subprogram {
+                continue;
+            }
+            match entry.attr_value(DW_AT_low_pc) {
+                Ok(Some(AttributeValue::Addr(low))) if low & !0b1 == address & !0b1 => {}
                 _ => continue,
+            }
+            let ty = match type_ref(entry) {
+                Some(ty) => read_type(&unit, &abbrev, ty, debug_str, MAX_TYPE_DEPTH),
+                None => DebugType::Unit,
             };
+            return Some(ReturnType { ty, rust });
+        }
+    }
+    None
+}
+
+/// Reads the type referenced by the `DW_AT_type` attribute of an entry.
+fn type_ref<R: Reader>(
+    entry: &DebuggingInformationEntry<'_, '_, R>,
+) -> Option<UnitOffset<R::Offset>> {
+    match entry.attr_value(DW_AT_type).ok()?? {
+        AttributeValue::UnitRef(offset) => Some(offset),
+        _ => None,
+    }
+}
 
-            let entry_name = debug_str.get_str(entry_name).unwrap();
-            let name_str = entry_name.to_string().unwrap();
+/// Reads a constant attribute value, signed values are wrapped.
+fn attr_u64<R: Reader>(entry: &DebuggingInformationEntry<'_, '_, R>, name: DwAt) -> Option<u64> {
+    match entry.attr_value(name).ok()?? {
+        AttributeValue::Sdata(value) => Some(value as u64),
+        value => value.udata_value(),
+    }
+}
 
-            for (name, hook) in hooks {
-                if name.is_match(name_str.as_ref()) {
-                    let addr = match entry.attr_value(DW_AT_low_pc).unwrap() {
-                        Some(v) => v,
-                        None => continue 'inner,
-                    };
-                    found_hooks.insert(name.as_str());
+/// Lists the offsets of the children of the entry at `offset`.
+fn children<R: Reader>(
+    unit: &UnitHeader<R>,
+    abbrev: &Abbreviations,
+    offset: UnitOffset<R::Offset>,
+) -> Vec<UnitOffset<R::Offset>> {
+    let mut ret = vec![];
+    let mut tree = match unit.entries_tree(abbrev, Some(offset)) {
+        Ok(tree) => tree,
+        Err(_) => return ret,
+    };
+    let mut children = match tree.root() {
+        Ok(root) => root.children(),
+        Err(_) => return ret,
+    };
+    while let Ok(Some(child)) = children.next() {
+        ret.push(child.entry().offset());
+    }
+    ret
+}
 
-                    if let AttributeValue::Addr(addr_value) = addr {
-                        trace!("found hook for {} att addr: {:#X}", name, addr_value);
-                        ret.insert(addr_value, hook.clone());
+/// Reads the type at `offset`, at most `depth` levels deep.
+fn read_type<R: Reader>(
+    unit: &UnitHeader<R>,
+    abbrev: &Abbreviations,
+    offset: UnitOffset<R::Offset>,
+    debug_str: &DebugStr<R>,
+    depth: usize,
+) -> DebugType {
+    let entry = match unit.entry(abbrev, offset) {
+        Ok(entry) => entry,
+        Err(_) => {
+            return DebugType::Unknown {
+                name: "<unknown>".to_owned(),
+                size: None,
+            }
+        }
+    };
+    let name = entry_name(unit, abbrev, &entry, debug_str).unwrap_or_default();
+    let size = attr_u64(&entry, DW_AT_byte_size);
+    if depth == 0 {
+        return DebugType::Unknown { name, size };
+    }
+
+    match (entry.tag(), size) {
+        (gimli::DW_TAG_typedef | gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type, _) => {
+            match type_ref(&entry) {
+                Some(ty) => read_type(unit, abbrev, ty, debug_str, depth - 1),
+                None => DebugType::Unit,
+            }
+        }
+        (gimli::DW_TAG_base_type, Some(size)) => {
+            let encoding = match entry.attr_value(DW_AT_encoding) {
+                Ok(Some(AttributeValue::Encoding(encoding))) => encoding,
+                _ => return DebugType::Unknown { name, size: None },
+            };
+            let encoding = match encoding {
+                gimli::DW_ATE_signed | gimli::DW_ATE_signed_char => BaseEncoding::Signed,
+                gimli::DW_ATE_unsigned | gimli::DW_ATE_unsigned_char => BaseEncoding::Unsigned,
+                gimli::DW_ATE_boolean => BaseEncoding::Boolean,
+                gimli::DW_ATE_UTF => BaseEncoding::Char,
+                gimli::DW_ATE_float => BaseEncoding::Float,
+                _ => return DebugType::Unknown { name, size: None },
+            };
+            DebugType::Base {
+                name,
+                size,
+                encoding,
+            }
+        }
+        (
+            gimli::DW_TAG_pointer_type
+            | gimli::DW_TAG_reference_type
+            | gimli::DW_TAG_rvalue_reference_type,
+            _,
+        ) => DebugType::Pointer {
+            name,
+            size: size.unwrap_or(unit.address_size() as u64),
+        },
+        (gimli::DW_TAG_array_type, _) => {
+            let element = match type_ref(&entry) {
+                Some(ty) => read_type(unit, abbrev, ty, debug_str, depth - 1),
+                None => return DebugType::Unknown { name, size },
+            };
+            let count = children(unit, abbrev, offset)
+                .into_iter()
+                .find_map(|child| {
+                    let child = unit.entry(abbrev, child).ok()?;
+                    if child.tag() != DW_TAG_subrange_type {
+                        return None;
+                    }
+                    attr_u64(&child, DW_AT_count)
+                        .or_else(|| attr_u64(&child, DW_AT_upper_bound).map(|upper| upper + 1))
+                });
+            let (count, size) = match (count, element.size()) {
+                (Some(count), Some(element_size)) => (count, size.unwrap_or(count * element_size)),
+                _ => return DebugType::Unknown { name, size },
+            };
+            DebugType::Array {
+                name,
+                size,
+                element: Box::new(element),
+                count,
+            }
+        }
+        // `MaybeUninit<T>` is shown as the `T` it holds.
+        (gimli::DW_TAG_union_type, _) if name.starts_with("MaybeUninit<") => {
+            children(unit, abbrev, offset)
+                .into_iter()
+                .filter_map(|child| unit.entry(abbrev, child).ok())
+                .find(|child| {
+                    entry_name(unit, abbrev, child, debug_str).as_deref() == Some("value")
+                })
+                .map(|value| read_member(unit, abbrev, &value, debug_str, depth - 1).ty)
+                .unwrap_or(DebugType::Unknown { name, size })
+        }
+        (gimli::DW_TAG_enumeration_type, Some(size)) => {
+            let enumerators = children(unit, abbrev, offset)
+                .into_iter()
+                .filter_map(|child| {
+                    let child = unit.entry(abbrev, child).ok()?;
+                    if child.tag() != DW_TAG_enumerator {
+                        return None;
+                    }
+                    let name = entry_name(unit, abbrev, &child, debug_str)?;
+                    Some((name, attr_u64(&child, DW_AT_const_value)?))
+                })
+                .collect();
+            DebugType::Enumeration {
+                name,
+                size,
+                enumerators,
+            }
+        }
+        (gimli::DW_TAG_structure_type, Some(size)) => {
+            let mut members = vec![];
+            let mut variant_part = None;
+            let mut pointee = None;
+            for child in children(unit, abbrev, offset) {
+                let entry = match unit.entry(abbrev, child) {
+                    Ok(entry) => entry,
+                    Err(_) => continue,
+                };
+                match entry.tag() {
+                    gimli::DW_TAG_member => {
+                        let member = read_member(unit, abbrev, &entry, debug_str, depth - 1);
+                        if member.name == "data_ptr" {
+                            pointee = read_pointee(unit, abbrev, &entry, debug_str, depth - 1);
+                        }
+                        members.push(member)
+                    }
+                    gimli::DW_TAG_variant_part => {
+                        variant_part = Some(read_variant_part(
+                            unit,
+                            abbrev,
+                            &entry,
+                            debug_str,
+                            depth - 1,
+                        ))
                     }
+                    _ => {}
                 }
             }
+            match (variant_part, pointee) {
+                (Some((discriminant, variants)), _) => DebugType::Variants {
+                    name,
+                    size,
+                    discriminant,
+                    variants,
+                },
+                (None, Some(pointee)) => slice(name, size, members, pointee),
+                (None, None) => match members.as_slice() {
+                    [value] if transparent(&name) && value.offset == 0 => value.ty.clone(),
+                    _ => DebugType::structure(name, size, members),
+                },
+            }
         }
+        _ => DebugType::Unknown { name, size },
     }
-    if found_hooks.len() < hooks.len() {
-        debug!("Did not find addresses for all hooks.") // fix a proper error
-                                                        // here later
+}
+
+/// Checks if the type named `name` is a wrapper that is shown as the value it
+/// holds, such as `ManuallyDrop<T>`.
+fn transparent(name: &str) -> bool {
+    ["ManuallyDrop<", "UnsafeCell<"]
+        .iter()
+        .any(|wrapper| name.starts_with(wrapper))
+}
+
+/// Reads the type the pointer member `entry` points to.
+fn read_pointee<R: Reader>(
+    unit: &UnitHeader<R>,
+    abbrev: &Abbreviations,
+    entry: &DebuggingInformationEntry<'_, '_, R>,
+    debug_str: &DebugStr<R>,
+    depth: usize,
+) -> Option<DebugType> {
+    let pointer = unit.entry(abbrev, type_ref(entry)?).ok()?;
+    if pointer.tag() != gimli::DW_TAG_pointer_type || depth == 0 {
+        return None;
     }
+    Some(read_type(
+        unit,
+        abbrev,
+        type_ref(&pointer)?,
+        debug_str,
+        depth - 1,
+    ))
+}
 
-    ret
+/// Makes a slice of a fat pointer structure, the pointer to the first
+/// element `data_ptr` and the number of elements `length`. Other structures
+/// with a `data_ptr` member are kept as structures.
+fn slice(name: String, size: u64, members: Vec<Member>, element: DebugType) -> DebugType {
+    let pointer = members.iter().find(|member| member.name == "data_ptr");
+    let length = members.iter().find(|member| member.name == "length");
+    let (pointer, length) = match (pointer, length) {
+        (Some(pointer), Some(length)) if members.len() == 2 => (pointer.clone(), length.clone()),
+        _ => return DebugType::structure(name, size, members),
+    };
+    let utf8 = matches!(element, DebugType::Base { size: 1, .. })
+        && name.trim_start_matches('&').trim_start_matches("mut ") == "str";
+    DebugType::Slice {
+        name,
+        size,
+        pointer: Box::new(pointer),
+        length: Box::new(length),
+        element: Box::new(element),
+        utf8,
+    }
+}
+
+/// Reads a member of a structure or variant.
+fn read_member<R: Reader>(
+    unit: &UnitHeader<R>,
+    abbrev: &Abbreviations,
+    entry: &DebuggingInformationEntry<'_, '_, R>,
+    debug_str: &DebugStr<R>,
+    depth: usize,
+) -> Member {
+    let ty = match type_ref(entry) {
+        Some(ty) => read_type(unit, abbrev, ty, debug_str, depth),
+        None => DebugType::Unit,
+    };
+    Member {
+        name: entry_name(unit, abbrev, entry, debug_str).unwrap_or_default(),
+        offset: attr_u64(entry, DW_AT_data_member_location).unwrap_or(0),
+        ty,
+    }
+}
+
+/// Reads the discriminant and the variants of the variant part of a Rust
+/// enum.
+fn read_variant_part<R: Reader>(
+    unit: &UnitHeader<R>,
+    abbrev: &Abbreviations,
+    entry: &DebuggingInformationEntry<'_, '_, R>,
+    debug_str: &DebugStr<R>,
+    depth: usize,
+) -> (Option<Box<Member>>, Vec<Variant>) {
+    let discriminant = match entry.attr_value(DW_AT_discr) {
+        Ok(Some(AttributeValue::UnitRef(offset))) => unit
+            .entry(abbrev, offset)
+            .ok()
+            .map(|member| Box::new(read_member(unit, abbrev, &member, debug_str, depth))),
+        _ => None,
+    };
+    let mut variants = vec![];
+    for child in children(unit, abbrev, entry.offset()) {
+        let variant = match unit.entry(abbrev, child) {
+            Ok(variant) if variant.tag() == DW_TAG_variant => variant,
+            _ => continue,
+        };
+        // The variant holds a single member of the type of the variant.
+        let member = children(unit, abbrev, child)
+            .into_iter()
+            .find_map(|member| {
+                let member = unit.entry(abbrev, member).ok()?;
+                (member.tag() == DW_TAG_member)
+                    .then(|| read_member(unit, abbrev, &member, debug_str, depth))
+            });
+        // A discriminant value that can not be read, such as one of more than
+        // 64 bits, must not select the variant for all other values.
+        let discriminant = match variant.attr_value(DW_AT_discr_value) {
+            Ok(None) => None,
+            _ => match attr_u64(&variant, DW_AT_discr_value) {
+                Some(discriminant) => Some(discriminant),
+                None => continue,
+            },
+        };
+        if let Some(member) = member {
+            variants.push(Variant {
+                discriminant,
+                member,
+            });
+        }
+    }
+    (discriminant, variants)
+}
+
+#[cfg(test)]
+mod test {
+    use gimli::{
+        write::{Address, AttributeValue, DwarfUnit, EndianVec, Range, RangeList, Sections},
+        DW_AT_abstract_origin,
+        DW_AT_high_pc,
+        DW_AT_low_pc,
+        DW_AT_name,
+        DW_AT_ranges,
+        DW_TAG_inlined_subroutine,
+        DW_TAG_lexical_block,
+        DW_TAG_subprogram,
+        DebugAbbrev,
+        DebugInfo,
+        DebugRanges,
+        DebugRngLists,
+        DebugStr,
+        Dwarf,
+        Encoding,
+        EndianSlice,
+        Format,
+        LittleEndian,
+        RangeLists,
+    };
+
+    use super::construct_regions;
+    use crate::general_assembly::project::RegionMetaData;
+
+    /// Builds the debug data of a function `main` with a lexical block, a
+    /// block with non contiguous ranges and an inlined call to `helper`.
+    fn sections(version: u16) -> Sections<EndianVec<LittleEndian>> {
+        let encoding = Encoding {
+            address_size: 4,
+            format: Format::Dwarf32,
+            version,
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+        let root = dwarf.unit.root();
+        let function = |dwarf: &mut DwarfUnit, name: &str| {
+            let id = dwarf.unit.add(root, DW_TAG_subprogram);
+            let entry = dwarf.unit.get_mut(id);
+            entry.set(DW_AT_name, AttributeValue::String(name.as_bytes().to_vec()));
+            id
+        };
+        let main = function(&mut dwarf, "main");
+        let helper = function(&mut dwarf, "helper");
+        let mut add_range = |tag, low: u64, size: u64| {
+            let id = dwarf.unit.add(main, tag);
+            let entry = dwarf.unit.get_mut(id);
+            entry.set(
+                DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(low)),
+            );
+            entry.set(DW_AT_high_pc, AttributeValue::Udata(size));
+            id
+        };
+        add_range(DW_TAG_lexical_block, 0x100, 0x10);
+        let inlined = add_range(DW_TAG_inlined_subroutine, 0x120, 0x8);
+        dwarf
+            .unit
+            .get_mut(inlined)
+            .set(DW_AT_abstract_origin, AttributeValue::UnitRef(helper));
+
+        let ranges = dwarf.unit.ranges.add(RangeList(vec![
+            Range::StartLength {
+                begin: Address::Constant(0x140),
+                length: 0x4,
+            },
+            Range::StartEnd {
+                begin: Address::Constant(0x150),
+                end: Address::Constant(0x158),
+            },
+        ]));
+        let block = dwarf.unit.add(main, DW_TAG_lexical_block);
+        dwarf
+            .unit
+            .get_mut(block)
+            .set(DW_AT_ranges, AttributeValue::RangeListRef(ranges));
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        sections
+    }
+
+    fn section(data: &EndianVec<LittleEndian>) -> EndianSlice<'_, LittleEndian> {
+        EndianSlice::new(data.slice(), LittleEndian)
+    }
+
+    #[test]
+    fn test_construct_regions() {
+        let region = |name: &str, start, end| RegionMetaData {
+            name: name.to_owned(),
+            start,
+            end,
+        };
+        for version in [4, 5] {
+            let sections = sections(version);
+            let dwarf = Dwarf {
+                debug_info: DebugInfo::from(section(&sections.debug_info)),
+                debug_abbrev: DebugAbbrev::from(section(&sections.debug_abbrev)),
+                debug_str: DebugStr::from(section(&sections.debug_str)),
+                ranges: RangeLists::new(
+                    DebugRanges::from(section(&sections.debug_ranges)),
+                    DebugRngLists::from(section(&sections.debug_rnglists)),
+                ),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                construct_regions(&dwarf),
+                vec![
+                    region("main::block@0x100", 0x100, 0x110),
+                    region("main::helper@0x120", 0x120, 0x128),
+                    region("main::block@0x140", 0x140, 0x144),
+                    region("main::block@0x150", 0x150, 0x158),
+                ],
+                "DWARF {version}"
+            );
+        }
+    }
 }
@@ -1,10 +1,11 @@
 //! A loader that can load all segments from a elf file properly.
 
-use object::{read::elf::ProgramHeader, File, Object};
+use object::{elf::PF_W, read::elf::ProgramHeader, File, Object};
 pub struct Segment {
     data: Vec<u8>,
     start_address: u64,
     end_address: u64,
+    writable: bool,
 }
 
 pub struct Segments(Vec<Segment>);
@@ -15,6 +16,7 @@ impl Segments {
             data,
             start_address: start_addr,
             end_address: end_addr,
+            writable: false,
         }])
     }
 
@@ -33,11 +35,13 @@ impl Segments {
                 let addr_start = segment.p_vaddr.get(file.endianness()) as u64;
                 //let size = segment.p_memsz.get(file.endianness());
                 let data = segment.data(file.endianness(), elf_file.data()).unwrap();
+                let flags = segment.p_flags.get(file.endianness());
 
                 ret.push(Segment {
                     data: data.to_owned(),
                     start_address: addr_start,
                     end_address: addr_start + data.len() as u64,
+                    writable: flags & PF_W != 0,
                 })
             }
         }
@@ -55,4 +59,16 @@ impl Segments {
 
         None
     }
+
+    /// Returns true if all of the `bytes` bytes at `address` are in segments
+    /// that the program can not write to.
+    pub fn is_read_only(&self, address: u64, bytes: u64) -> bool {
+        (address..address + bytes).all(|address| {
+            self.0.iter().any(|segment| {
+                !segment.writable
+                    && address >= segment.start_address
+                    && address < segment.end_address
+            })
+        })
+    }
 }
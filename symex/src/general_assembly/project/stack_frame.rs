@@ -0,0 +1,111 @@
+//! Layout of the stack frames of functions described by the dwarf debug data.
+//!
+//! A local variable that lives on the stack is located by a location
+//! expression, usually at an offset from the frame base of its function, which
+//! is itself described by an expression such as the canonical frame address
+//! (the value of the stack pointer at the call) or a frame pointer register.
+//! See [`dwarf_expression`](super::dwarf_expression) for how the expressions
+//! are evaluated.
+
+use super::dwarf_expression::{LocationList, Piece, PieceLocation};
+
+/// A local variable or parameter of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackVariable {
+    /// Name of the variable.
+    pub name: String,
+
+    /// Where the variable is stored.
+    pub location: LocationList,
+
+    /// Size of the variable in bytes, zero if the size is not known.
+    pub size: u64,
+}
+
+impl StackVariable {
+    /// Lists the parts of the variable that are stored in memory according to
+    /// the evaluated location `pieces`, as triples of address, offset into the
+    /// variable and size in bytes.
+    ///
+    /// A variable that is not split into pieces is a single part of the size
+    /// of the variable.
+    pub fn memory_parts(&self, pieces: &[Piece]) -> Vec<(u64, u64, u64)> {
+        let mut parts = vec![];
+        let mut offset = 0;
+        for piece in pieces {
+            let size = match piece.size_in_bits {
+                Some(bits) => bits.div_ceil(8),
+                None => self.size,
+            };
+            if let PieceLocation::Address(address) = piece.location {
+                let address = address + piece.bit_offset.unwrap_or(0) / 8;
+                parts.push((address, offset, size));
+            }
+            offset += size;
+        }
+        parts
+    }
+}
+
+/// The stack frame layout of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionFrame {
+    /// Name of the function.
+    pub function: String,
+
+    /// Frame base the locations of the locals may refer to.
+    pub frame_base: LocationList,
+
+    /// Locals of the function, including those of inlined functions. Outer
+    /// scopes are listed before the scopes nested in them.
+    pub variables: Vec<StackVariable>,
+}
+
+#[cfg(test)]
+mod test {
+    use gimli::{Encoding, Format, RunTimeEndian};
+
+    use super::StackVariable;
+    use crate::general_assembly::project::dwarf_expression::{
+        DwarfExpression,
+        LocationList,
+        Piece,
+        PieceLocation,
+    };
+
+    #[test]
+    fn test_memory_parts() {
+        let encoding = Encoding {
+            address_size: 4,
+            format: Format::Dwarf32,
+            version: 5,
+        };
+        let variable = StackVariable {
+            name: "pair".to_owned(),
+            location: LocationList::single(DwarfExpression::new(
+                vec![],
+                encoding,
+                RunTimeEndian::Little,
+            )),
+            size: 8,
+        };
+        let piece = |size_in_bits, location| Piece {
+            size_in_bits,
+            bit_offset: None,
+            location,
+        };
+
+        let whole = [piece(None, PieceLocation::Address(0x2000_0ff0))];
+        assert_eq!(variable.memory_parts(&whole), vec![(0x2000_0ff0, 0, 8)]);
+
+        let split = [
+            piece(Some(32), PieceLocation::Register(0)),
+            piece(Some(16), PieceLocation::Address(0x2000_0ff0)),
+            piece(Some(16), PieceLocation::Address(0x2000_0ff8)),
+        ];
+        assert_eq!(variable.memory_parts(&split), vec![
+            (0x2000_0ff0, 4, 2),
+            (0x2000_0ff8, 6, 2)
+        ]);
+    }
+}
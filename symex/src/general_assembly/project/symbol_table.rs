@@ -0,0 +1,197 @@
+//! The ELF symbol table, indexed by name and by address.
+//!
+//! Binaries often define several symbols at the same address, such as the weak
+//! aliases of a default interrupt handler, and the same name more than once,
+//! such as a weak default that is overridden by a strong implementation.
+//! Global symbols are preferred over weak symbols and weak symbols over local
+//! symbols, the other symbols at an address are kept as aliases.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Number of preceding addresses searched for a symbol that contains an
+/// address before falling back to the nearest preceding symbol.
+const LOOKBACK: usize = 32;
+
+/// Binding of a symbol, in order of preference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SymbolBinding {
+    /// A strong symbol visible to other objects.
+    Global,
+
+    /// A weak symbol, replaced by a global symbol of the same name.
+    Weak,
+
+    /// A symbol local to its object.
+    Local,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    size: u64,
+    binding: SymbolBinding,
+}
+
+/// Symbols of the analyzed binary.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    /// Address and binding of the preferred symbol of each name.
+    addresses: HashMap<String, (u64, SymbolBinding)>,
+
+    /// The symbols at each address without the thumb bit, the preferred
+    /// symbol first.
+    by_address: BTreeMap<u64, Vec<Entry>>,
+}
+
+impl SymbolTable {
+    /// Creates an empty symbol table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a symbol of `size` bytes, a size of zero means the size is not
+    /// known.
+    ///
+    /// A name that is already defined by a symbol with a stronger binding
+    /// keeps its address.
+    pub fn insert(&mut self, name: String, address: u64, size: u64, binding: SymbolBinding) {
+        // Mapping symbols such as $t and $d do not name code or data.
+        if !name.is_empty() && !name.starts_with('$') {
+            let symbols = self.by_address.entry(address & !0b1).or_default();
+            if !symbols.iter().any(|symbol| symbol.name == name) {
+                symbols.push(Entry {
+                    name: name.clone(),
+                    size,
+                    binding,
+                });
+                symbols.sort_by_key(|symbol| symbol.binding);
+            }
+        }
+        match self.addresses.get(&name) {
+            Some((_, existing)) if *existing < binding => {}
+            _ => {
+                self.addresses.insert(name, (address, binding));
+            }
+        }
+    }
+
+    /// Get the address of the symbol `name`.
+    pub fn address(&self, name: &str) -> Option<u64> {
+        self.addresses.get(name).map(|(address, _)| *address)
+    }
+
+    /// Get the size of the symbol `name`, zero if it is not known.
+    pub fn size(&self, name: &str) -> Option<u64> {
+        let address = self.address(name)?;
+        self.by_address
+            .get(&(address & !0b1))?
+            .iter()
+            .find(|symbol| symbol.name == name)
+            .map(|symbol| symbol.size)
+    }
+
+    /// Get the names of all symbols at `address`, the preferred name first.
+    pub fn aliases(&self, address: u64) -> Vec<&str> {
+        self.by_address
+            .get(&(address & !0b1))
+            .map(|symbols| symbols.iter().map(|symbol| symbol.name.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the name of the symbol that contains `address` and the offset of
+    /// `address` into that symbol.
+    ///
+    /// A symbol contains the addresses within its size. If no symbol near
+    /// `address` contains it, such as for symbols without a size, the nearest
+    /// symbol at or below `address` is used.
+    pub fn lookup(&self, address: u64) -> Option<(&str, u64)> {
+        let mut nearest = None;
+        for (start, symbols) in self.by_address.range(..=address).rev().take(LOOKBACK) {
+            let offset = address - start;
+            for symbol in symbols {
+                if offset < symbol.size {
+                    return Some((&symbol.name, offset));
+                }
+                nearest.get_or_insert((symbol.name.as_str(), offset));
+            }
+        }
+        nearest
+    }
+
+    /// Get the name of the symbol whose size covers `address` and the offset
+    /// of `address` into that symbol, without falling back to a nearby symbol.
+    pub fn containing(&self, address: u64) -> Option<(&str, u64)> {
+        self.by_address
+            .range(..=address)
+            .rev()
+            .take(LOOKBACK)
+            .find_map(|(start, symbols)| {
+                let offset = address - start;
+                symbols
+                    .iter()
+                    .find(|symbol| offset < symbol.size)
+                    .map(|symbol| (symbol.name.as_str(), offset))
+            })
+    }
+}
+
+impl From<HashMap<String, u64>> for SymbolTable {
+    fn from(symbols: HashMap<String, u64>) -> Self {
+        let mut table = Self::new();
+        for (name, address) in symbols {
+            table.insert(name, address, 0, SymbolBinding::Global);
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SymbolBinding, SymbolTable};
+
+    #[test]
+    fn test_lookup() {
+        let mut table = SymbolTable::new();
+        table.insert("main".to_owned(), 0x101, 0x40, SymbolBinding::Global);
+        table.insert("$t".to_owned(), 0x100, 0, SymbolBinding::Local);
+        table.insert("helper".to_owned(), 0x181, 0x10, SymbolBinding::Global);
+        table.insert("label".to_owned(), 0x120, 0, SymbolBinding::Local);
+
+        assert_eq!(table.address("main"), Some(0x101));
+        assert_eq!(table.lookup(0x100), Some(("main", 0)));
+        // Inside main although the unsized label is closer.
+        assert_eq!(table.lookup(0x130), Some(("main", 0x30)));
+        // Past the end of main, the nearest symbol is used.
+        assert_eq!(table.lookup(0x150), Some(("label", 0x30)));
+        assert_eq!(table.lookup(0x18a), Some(("helper", 0xa)));
+        assert_eq!(table.lookup(0xff), None);
+        assert_eq!(table.containing(0x130), Some(("main", 0x30)));
+        assert_eq!(table.containing(0x150), None);
+    }
+
+    #[test]
+    fn test_weak_symbols_and_aliases() {
+        let mut table = SymbolTable::new();
+        // Weak interrupt handlers that alias the default handler.
+        table.insert("SysTick".to_owned(), 0x201, 0x4, SymbolBinding::Weak);
+        table.insert(
+            "DefaultHandler".to_owned(),
+            0x201,
+            0x4,
+            SymbolBinding::Global,
+        );
+        table.insert("UART0".to_owned(), 0x201, 0x4, SymbolBinding::Weak);
+        // The strong implementation replaces the weak default.
+        table.insert("SysTick".to_owned(), 0x301, 0x8, SymbolBinding::Global);
+
+        assert_eq!(table.address("SysTick"), Some(0x301));
+        assert_eq!(table.address("UART0"), Some(0x201));
+        assert_eq!(table.lookup(0x202), Some(("DefaultHandler", 2)));
+        assert_eq!(table.aliases(0x200), vec![
+            "DefaultHandler",
+            "SysTick",
+            "UART0"
+        ]);
+        assert_eq!(table.lookup(0x304), Some(("SysTick", 4)));
+    }
+}
@@ -0,0 +1,190 @@
+//! Detection of data races between interrupt handlers and thread code.
+//!
+//! While [`interrupts`](super::RunConfig::interrupts) are injected, the memory
+//! accesses of the thread code and of the handlers are logged across all
+//! paths. A byte is raced on if the thread code accesses it while the
+//! interrupt is not masked by `PRIMASK` or `BASEPRI`, the handler of the
+//! interrupt accesses it as well and one of the two accesses writes. Accesses
+//! of the thread code to its own stack are not logged, as the handlers can not
+//! name them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::interrupts::{Interrupt, InterruptMask};
+
+/// An access of the thread code to shared memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ThreadAccess {
+    /// Address of the accessing instruction.
+    pub pc: u64,
+
+    /// True if the instruction writes.
+    pub write: bool,
+
+    /// Interrupt mask the instruction was executed with.
+    pub mask: InterruptMask,
+}
+
+/// An access of an interrupt handler to shared memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandlerAccess {
+    /// Address of the accessing instruction.
+    pub pc: u64,
+
+    /// True if the instruction writes.
+    pub write: bool,
+
+    /// Index of the interrupt the handler belongs to.
+    pub interrupt: usize,
+}
+
+/// A pair of accesses that may race.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Race {
+    /// Lowest address both accesses touch.
+    pub address: u64,
+
+    /// The access of the thread code.
+    pub thread: ThreadAccess,
+
+    /// The access of the handler.
+    pub handler: HandlerAccess,
+}
+
+/// Accesses to memory by context, byte by byte.
+#[derive(Debug, Clone, Default)]
+pub struct RaceLog {
+    thread: BTreeMap<u64, BTreeSet<ThreadAccess>>,
+    handlers: BTreeMap<u64, BTreeSet<HandlerAccess>>,
+}
+
+impl RaceLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an access of `bytes` bytes at `address` by the thread code.
+    pub fn record_thread(&mut self, address: u64, bytes: u64, access: ThreadAccess) {
+        for byte in address..address + bytes {
+            self.thread.entry(byte).or_default().insert(access);
+        }
+    }
+
+    /// Records an access of `bytes` bytes at `address` by a handler.
+    pub fn record_handler(&mut self, address: u64, bytes: u64, access: HandlerAccess) {
+        for byte in address..address + bytes {
+            self.handlers.entry(byte).or_default().insert(access);
+        }
+    }
+
+    /// Adds the accesses of `other`.
+    pub fn merge(&mut self, other: &RaceLog) {
+        for (byte, accesses) in &other.thread {
+            self.thread.entry(*byte).or_default().extend(accesses);
+        }
+        for (byte, accesses) in &other.handlers {
+            self.handlers.entry(*byte).or_default().extend(accesses);
+        }
+    }
+
+    /// Get the pairs of accesses that may race, once per pair of
+    /// instructions at the lowest address they race on.
+    pub fn races(&self, interrupts: &[Interrupt]) -> Vec<Race> {
+        let mut races = BTreeMap::new();
+        for (byte, handler_accesses) in &self.handlers {
+            let Some(thread_accesses) = self.thread.get(byte) else {
+                continue;
+            };
+            for handler in handler_accesses {
+                let priority = interrupts
+                    .get(handler.interrupt)
+                    .map_or(0, |interrupt| interrupt.priority);
+                for thread in thread_accesses {
+                    if !(thread.write || handler.write) || thread.mask.masks(priority) {
+                        continue;
+                    }
+                    races
+                        .entry((thread.pc, thread.write, *handler))
+                        .or_insert(Race {
+                            address: *byte,
+                            thread: *thread,
+                            handler: *handler,
+                        });
+                }
+            }
+        }
+        races.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HandlerAccess, RaceLog, ThreadAccess};
+    use crate::general_assembly::interrupts::{Interrupt, InterruptMask};
+
+    #[test]
+    fn test_races() {
+        let interrupts = [
+            Interrupt::new("high").with_priority(0x20),
+            Interrupt::new("low").with_priority(0x80),
+        ];
+        let unmasked = InterruptMask::default();
+        let mut log = RaceLog::new();
+        // The thread code reads a counter the handlers increment.
+        log.record_thread(0x2000_0000, 4, ThreadAccess {
+            pc: 0x100,
+            write: false,
+            mask: unmasked,
+        });
+        // Resets it with the low priority interrupt masked.
+        log.record_thread(0x2000_0000, 4, ThreadAccess {
+            pc: 0x108,
+            write: true,
+            mask: InterruptMask {
+                primask: false,
+                basepri: 0x40,
+            },
+        });
+        // And reads a flag the handlers only read.
+        log.record_thread(0x2000_0010, 1, ThreadAccess {
+            pc: 0x110,
+            write: false,
+            mask: unmasked,
+        });
+        for interrupt in 0..2 {
+            log.record_handler(0x2000_0002, 2, HandlerAccess {
+                pc: 0x200,
+                write: true,
+                interrupt,
+            });
+            log.record_handler(0x2000_0010, 4, HandlerAccess {
+                pc: 0x204,
+                write: false,
+                interrupt,
+            });
+        }
+
+        let races: Vec<_> = log
+            .races(&interrupts)
+            .iter()
+            .map(|race| (race.address, race.thread.pc, race.handler.interrupt))
+            .collect();
+        assert_eq!(races, vec![
+            (0x2000_0002, 0x100, 0),
+            (0x2000_0002, 0x100, 1),
+            (0x2000_0002, 0x108, 0),
+        ]);
+
+        let mut masked = RaceLog::new();
+        masked.record_thread(0x2000_0000, 4, ThreadAccess {
+            pc: 0x100,
+            write: true,
+            mask: InterruptMask {
+                primask: true,
+                basepri: 0,
+            },
+        });
+        masked.merge(&log);
+        assert_eq!(masked.races(&interrupts).len(), 3);
+    }
+}
@@ -0,0 +1,1072 @@
+//! Interval analysis of the program before it is explored.
+//!
+//! The general assembly of the entry function and the functions it calls
+//! directly is interpreted over unsigned intervals of the registers. Ranges
+//! are narrowed by unsigned comparisons followed by a conditional branch and
+//! widened at loop headers. Stores are not tracked, so only loads from read
+//! only segments of the elf file have known values. Calls are assumed to follow
+//! the calling convention of the architecture, so only the caller saved
+//! registers are changed by a call. Architectures that do not describe their
+//! caller saved registers are not analyzed. Indirect jumps other than table
+//! branches are not followed.
+//!
+//! The inferred ranges of the registers at loop headers and at instructions
+//! that access memory are assumed when a path reaches them, which bounds
+//! symbolic indices and loop counters before the solver has to enumerate
+//! them. The analysis is not sound for everything a path can do, such as
+//! writes through hooks or registers changed by an interrupt, so the values
+//! outside of a range are not pruned. If they are possible on a path, the path
+//! forks and the forked path continues without the range.
+//! The ranges and how often they were assumed are reported after the run so
+//! that they can be audited.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use general_assembly::{
+    condition::Condition,
+    operand::{Operand, RawDataWord},
+    operation::Operation,
+    shift::Shift,
+};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use super::{
+    arch::Arch,
+    executor::GAExecutor,
+    jump_table::{recover_targets, JumpTable},
+    project::Project,
+    Endianness,
+    Result,
+};
+
+/// Number of times the state at an address is joined before it is widened.
+const WIDENING_DELAY: usize = 3;
+
+/// Number of instructions interpreted before the analysis gives up.
+const MAX_STEPS: usize = 100_000;
+
+/// An unsigned range of values, both bounds are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Interval {
+    /// Smallest value.
+    pub min: u64,
+
+    /// Largest value.
+    pub max: u64,
+}
+
+impl Interval {
+    /// Creates the interval `[min, max]`.
+    pub const fn new(min: u64, max: u64) -> Self {
+        Self { min, max }
+    }
+
+    /// Creates the interval that only contains `value`.
+    pub const fn constant(value: u64) -> Self {
+        Self::new(value, value)
+    }
+
+    /// Creates the interval of all values of `bits` bits.
+    pub const fn top(bits: u32) -> Self {
+        Self::new(0, mask(bits))
+    }
+
+    /// Returns the value if the interval contains a single value.
+    pub const fn as_constant(&self) -> Option<u64> {
+        match self.min == self.max {
+            true => Some(self.min),
+            false => None,
+        }
+    }
+
+    /// Returns the smallest interval that contains both intervals.
+    pub fn join(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Widens the bounds of `self` that `other` exceeds to the bounds of
+    /// `bits` bits.
+    fn widen(&self, other: &Self, bits: u32) -> Self {
+        Self::new(
+            match other.min < self.min {
+                true => 0,
+                false => self.min,
+            },
+            match other.max > self.max {
+                true => mask(bits),
+                false => self.max,
+            },
+        )
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {}]", self.min, self.max)
+    }
+}
+
+const fn mask(bits: u32) -> u64 {
+    match bits {
+        64.. => u64::MAX,
+        bits => (1 << bits) - 1,
+    }
+}
+
+/// Ranges of the registers inferred by the analysis.
+#[derive(Debug, Clone, Default)]
+pub struct RangeAnalysis {
+    /// Ranges of the registers before the instructions where they are
+    /// assumed, registers without a range can hold any value.
+    ranges: BTreeMap<u64, BTreeMap<String, Interval>>,
+
+    /// Number of times each range was assumed, indexed by address and
+    /// register.
+    assumed: BTreeMap<(u64, String), usize>,
+}
+
+impl RangeAnalysis {
+    /// Analyzes the function at `entry` and the functions it calls.
+    pub fn analyze<A: Arch>(project: &Project<A>, architecture: &A, entry: u64) -> Self {
+        let Some(caller_saved) = architecture.caller_saved_registers() else {
+            warn!(
+                "{} does not describe the registers changed by a call, no ranges are assumed",
+                architecture
+            );
+            return Self::default();
+        };
+        let mut analyzer = Analyzer {
+            project,
+            architecture,
+            caller_saved,
+            bits: project.get_word_size(),
+            states: HashMap::new(),
+            visits: HashMap::new(),
+            worklist: BTreeSet::new(),
+            functions: BTreeSet::new(),
+            assume_at: BTreeSet::new(),
+        };
+        analyzer.enter_function(entry & !0b1);
+        let mut steps = 0;
+        while let Some(pc) = analyzer.worklist.pop_first() {
+            steps += 1;
+            if steps > MAX_STEPS {
+                warn!("Range analysis did not converge, no ranges are assumed");
+                return Self::default();
+            }
+            analyzer.step(pc);
+        }
+        debug!(
+            "Range analysis analyzed {} functions in {} steps",
+            analyzer.functions.len(),
+            steps
+        );
+
+        let mut ranges = BTreeMap::new();
+        for pc in &analyzer.assume_at {
+            let registers = match analyzer.states.get(pc) {
+                Some(state) if !state.registers.is_empty() => state.registers.clone(),
+                _ => continue,
+            };
+            ranges.insert(*pc, registers);
+        }
+        Self {
+            ranges,
+            assumed: BTreeMap::new(),
+        }
+    }
+
+    /// Get the ranges of the registers that are assumed before the
+    /// instruction at `pc`.
+    pub fn ranges_at(&self, pc: u64) -> Option<&BTreeMap<String, Interval>> {
+        self.ranges.get(&pc)
+    }
+
+    /// Get all inferred ranges, indexed by address.
+    pub fn ranges(&self) -> &BTreeMap<u64, BTreeMap<String, Interval>> {
+        &self.ranges
+    }
+
+    /// Get the number of times the range of `register` at `pc` was assumed.
+    pub fn assumed(&self, pc: u64, register: &str) -> usize {
+        self.assumed
+            .get(&(pc, register.to_owned()))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// Abstract state before an instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct AbstractState {
+    /// Ranges of the registers, registers that are not present can hold any
+    /// value.
+    registers: BTreeMap<String, Interval>,
+
+    /// Register and the range of the value it was last compared to, if the
+    /// flags are set by the comparison.
+    compare: Option<(String, Interval)>,
+
+    /// Number of instructions left in the current conditional block.
+    conditional: usize,
+}
+
+impl AbstractState {
+    fn join(&self, other: &Self) -> Self {
+        let registers = self
+            .registers
+            .iter()
+            .filter_map(|(register, range)| {
+                let other = other.registers.get(register)?;
+                Some((register.to_owned(), range.join(other)))
+            })
+            .collect();
+        Self {
+            registers,
+            compare: match self.compare == other.compare {
+                true => self.compare.clone(),
+                false => None,
+            },
+            conditional: self.conditional.max(other.conditional),
+        }
+    }
+
+    fn widen(&self, other: &Self, bits: u32) -> Self {
+        let mut widened = self.join(other);
+        for (register, range) in widened.registers.iter_mut() {
+            if let Some(before) = self.registers.get(register) {
+                *range = before.widen(range, bits);
+            }
+        }
+        widened
+    }
+
+    fn set_register(&mut self, register: &str, range: Interval, bits: u32) {
+        if self
+            .compare
+            .as_ref()
+            .is_some_and(|(compared, _)| compared == register)
+        {
+            self.compare = None;
+        }
+        match range == Interval::top(bits) {
+            true => self.registers.remove(register),
+            false => self.registers.insert(register.to_owned(), range),
+        };
+    }
+
+    /// Narrows the compared register to the values for which `condition`
+    /// holds, returns `None` if it can not hold.
+    fn refine(mut self, condition: Condition, bits: u32) -> Option<Self> {
+        let (register, rhs) = match &self.compare {
+            Some(compare) => compare.clone(),
+            None => return Some(self),
+        };
+        let lhs = self
+            .registers
+            .get(&register)
+            .copied()
+            .unwrap_or(Interval::top(bits));
+        // Signed comparisons behave as unsigned ones for non negative values.
+        let non_negative = lhs.max <= mask(bits) >> 1 && rhs.max <= mask(bits) >> 1;
+        let refined = match condition {
+            Condition::EQ => Interval::new(lhs.min.max(rhs.min), lhs.max.min(rhs.max)),
+            Condition::CS => Interval::new(lhs.min.max(rhs.min), lhs.max),
+            Condition::GE if non_negative => Interval::new(lhs.min.max(rhs.min), lhs.max),
+            Condition::HI => Interval::new(lhs.min.max(rhs.min.saturating_add(1)), lhs.max),
+            Condition::GT if non_negative => {
+                Interval::new(lhs.min.max(rhs.min.saturating_add(1)), lhs.max)
+            }
+            Condition::CC | Condition::LT if rhs.max == 0 => return None,
+            Condition::CC => Interval::new(lhs.min, lhs.max.min(rhs.max - 1)),
+            Condition::LT if non_negative => Interval::new(lhs.min, lhs.max.min(rhs.max - 1)),
+            Condition::LS => Interval::new(lhs.min, lhs.max.min(rhs.max)),
+            Condition::LE if non_negative => Interval::new(lhs.min, lhs.max.min(rhs.max)),
+            _ => lhs,
+        };
+        if refined.min > refined.max {
+            return None;
+        }
+        let compare = self.compare.take();
+        self.set_register(&register, refined, bits);
+        self.compare = compare;
+        Some(self)
+    }
+}
+
+/// Returns the condition that holds when `condition` does not.
+fn negate(condition: Condition) -> Condition {
+    match condition {
+        Condition::EQ => Condition::NE,
+        Condition::NE => Condition::EQ,
+        Condition::CS => Condition::CC,
+        Condition::CC => Condition::CS,
+        Condition::MI => Condition::PL,
+        Condition::PL => Condition::MI,
+        Condition::VS => Condition::VC,
+        Condition::VC => Condition::VS,
+        Condition::HI => Condition::LS,
+        Condition::LS => Condition::HI,
+        Condition::GE => Condition::LT,
+        Condition::LT => Condition::GE,
+        Condition::GT => Condition::LE,
+        Condition::LE => Condition::GT,
+        Condition::None => Condition::None,
+    }
+}
+
+struct Analyzer<'a, A: Arch> {
+    project: &'a Project<A>,
+    architecture: &'a A,
+    /// Registers that are not preserved by calls.
+    caller_saved: &'static [&'static str],
+    bits: u32,
+    /// Abstract state before each reached instruction.
+    states: HashMap<u64, AbstractState>,
+    /// Number of times the state before each instruction changed.
+    visits: HashMap<u64, usize>,
+    worklist: BTreeSet<u64>,
+    /// Entries of the analyzed functions.
+    functions: BTreeSet<u64>,
+    /// Addresses where the ranges are assumed.
+    assume_at: BTreeSet<u64>,
+}
+
+/// Effect of the operations of an instruction.
+struct Effect {
+    state: AbstractState,
+    /// Jumps taken by the instruction, with the destination if it is known.
+    jumps: Vec<(Condition, Option<u64>)>,
+    writes_lr: bool,
+}
+
+impl<A: Arch> Analyzer<'_, A> {
+    fn enter_function(&mut self, entry: u64) {
+        if self.functions.insert(entry) {
+            self.merge(entry, AbstractState::default());
+        }
+    }
+
+    /// Merges `state` into the state before the instruction at `pc`.
+    fn merge(&mut self, pc: u64, state: AbstractState) {
+        let merged = match self.states.get(&pc) {
+            None => state,
+            Some(before) => {
+                let visits = self.visits.entry(pc).or_default();
+                *visits += 1;
+                let merged = match *visits > WIDENING_DELAY {
+                    true => before.widen(&state, self.bits),
+                    false => before.join(&state),
+                };
+                if &merged == before {
+                    return;
+                }
+                merged
+            }
+        };
+        self.states.insert(pc, merged);
+        self.worklist.insert(pc);
+    }
+
+    fn step(&mut self, pc: u64) {
+        let state = match self.states.get(&pc) {
+            Some(state) => state.clone(),
+            None => return,
+        };
+        // Hooked functions are not analyzed.
+        if self.project.get_pc_hook(pc).is_some() {
+            return;
+        }
+        let architecture = self.architecture;
+        let in_conditional_block = state.conditional > 0;
+        let decode = |buff: &[u8]| {
+            (
+                architecture.translate(buff, in_conditional_block).ok(),
+                architecture.jump_table(buff),
+            )
+        };
+        let (instruction, jump_table) = match self.project.get_raw_word(pc) {
+            Ok(RawDataWord::Word64(d)) => decode(&d),
+            Ok(RawDataWord::Word32(d)) => decode(&d),
+            Ok(RawDataWord::Word16(d)) => decode(&d),
+            Ok(RawDataWord::Word8(d)) => decode(&d),
+            Err(_) => return,
+        };
+        let instruction = match instruction {
+            Some(instruction) => instruction,
+            None => return,
+        };
+        let next = pc + (instruction.instruction_size / 8) as u64;
+        if instruction.memory_access {
+            self.assume_at.insert(pc);
+        }
+
+        let mut input = state;
+        input.conditional = input.conditional.saturating_sub(1);
+        let effect = self.execute(pc, next, &instruction.operations, input.clone());
+        let mut successors = vec![];
+
+        // The effect of a conditional instruction is optional.
+        if in_conditional_block {
+            let state = input.join(&effect.state);
+            successors.push((next, state.clone()));
+            for (_, destination) in &effect.jumps {
+                if let Some(destination) = destination {
+                    successors.push((destination & !0b1, state.clone()));
+                }
+            }
+        } else {
+            let call = effect.writes_lr
+                && effect
+                    .jumps
+                    .iter()
+                    .any(|(condition, _)| *condition == Condition::None);
+            let mut falls_through = true;
+            for (condition, destination) in &effect.jumps {
+                if call {
+                    if let Some(destination) = destination {
+                        self.enter_function(destination & !0b1);
+                    }
+                    continue;
+                }
+                if *condition == Condition::None {
+                    falls_through = false;
+                }
+                match destination {
+                    Some(destination) => {
+                        if let Some(state) = effect.state.clone().refine(*condition, self.bits) {
+                            successors.push((destination & !0b1, state));
+                        }
+                    }
+                    None => {
+                        for target in self.table_targets(jump_table.as_ref(), next) {
+                            successors.push((target, effect.state.clone()));
+                        }
+                    }
+                }
+            }
+            if call {
+                let mut state = effect.state.clone();
+                for register in self.caller_saved {
+                    state.registers.remove(*register);
+                }
+                state.compare = None;
+                successors.push((next, state));
+            } else if falls_through {
+                let state = effect
+                    .jumps
+                    .iter()
+                    .try_fold(effect.state.clone(), |state, (condition, _)| {
+                        state.refine(negate(*condition), self.bits)
+                    });
+                if let Some(state) = state {
+                    successors.push((next, state));
+                }
+            }
+        }
+
+        for (target, state) in successors {
+            if target <= pc {
+                self.assume_at.insert(target);
+            }
+            self.merge(target, state);
+        }
+    }
+
+    /// Targets of the table branched through by the instruction before
+    /// `origin`, if the table follows the instruction.
+    fn table_targets(&self, jump_table: Option<&JumpTable>, origin: u64) -> Vec<u64> {
+        match jump_table {
+            Some(table) if table.base.is_none() => {
+                recover_targets(self.project, origin, table.entry, origin)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn execute(
+        &self,
+        pc: u64,
+        next: u64,
+        operations: &[Operation],
+        mut state: AbstractState,
+    ) -> Effect {
+        let bits = self.bits;
+        let mut effect = Effect {
+            state: AbstractState::default(),
+            jumps: vec![],
+            writes_lr: false,
+        };
+        let mut locals = HashMap::new();
+        let mut sets_flags = false;
+        let mut compare = None;
+        for operation in operations {
+            let read =
+                |operand: &Operand, state: &AbstractState, locals: &HashMap<String, Interval>| {
+                    self.read(pc, next, operand, state, locals)
+                };
+            let (destination, value) = match operation {
+                Operation::Nop => continue,
+                Operation::Move {
+                    destination,
+                    source,
+                } => (destination, read(source, &state, &locals)),
+                Operation::Add {
+                    destination,
+                    operand1,
+                    operand2,
+                } => (
+                    destination,
+                    add(
+                        read(operand1, &state, &locals),
+                        read(operand2, &state, &locals),
+                        bits,
+                    ),
+                ),
+                Operation::Adc {
+                    destination,
+                    operand1,
+                    operand2,
+                } => {
+                    let sum = add(
+                        read(operand1, &state, &locals),
+                        read(operand2, &state, &locals),
+                        bits,
+                    );
+                    (destination, add(sum, Interval::new(0, 1), bits))
+                }
+                Operation::Sub {
+                    destination,
+                    operand1,
+                    operand2,
+                } => (
+                    destination,
+                    sub(
+                        read(operand1, &state, &locals),
+                        read(operand2, &state, &locals),
+                        bits,
+                    ),
+                ),
+                Operation::Mul {
+                    destination,
+                    operand1,
+                    operand2,
+                } => (
+                    destination,
+                    mul(
+                        read(operand1, &state, &locals),
+                        read(operand2, &state, &locals),
+                        bits,
+                    ),
+                ),
+                Operation::UDiv {
+                    destination,
+                    operand1,
+                    operand2,
+                } => {
+                    let (lhs, rhs) = (
+                        read(operand1, &state, &locals),
+                        read(operand2, &state, &locals),
+                    );
+                    // Division by zero results in zero.
+                    let value = match rhs.min {
+                        0 => Interval::new(0, lhs.max),
+                        min => Interval::new(lhs.min / rhs.max, lhs.max / min),
+                    };
+                    (destination, value)
+                }
+                Operation::And {
+                    destination,
+                    operand1,
+                    operand2,
+                } => {
+                    let (lhs, rhs) = (
+                        read(operand1, &state, &locals),
+                        read(operand2, &state, &locals),
+                    );
+                    let value = match (lhs.as_constant(), rhs.as_constant()) {
+                        (Some(lhs), Some(rhs)) => Interval::constant(lhs & rhs),
+                        _ => Interval::new(0, lhs.max.min(rhs.max)),
+                    };
+                    (destination, value)
+                }
+                Operation::Or {
+                    destination,
+                    operand1,
+                    operand2,
+                }
+                | Operation::Xor {
+                    destination,
+                    operand1,
+                    operand2,
+                } => {
+                    let (lhs, rhs) = (
+                        read(operand1, &state, &locals),
+                        read(operand2, &state, &locals),
+                    );
+                    let value = match (lhs.as_constant(), rhs.as_constant(), operation) {
+                        (Some(lhs), Some(rhs), Operation::Or { .. }) => {
+                            Interval::constant(lhs | rhs)
+                        }
+                        (Some(lhs), Some(rhs), _) => Interval::constant(lhs ^ rhs),
+                        _ => Interval::new(0, covering_mask(lhs.max.max(rhs.max))),
+                    };
+                    (destination, value)
+                }
+                Operation::Not {
+                    destination,
+                    operand,
+                } => {
+                    let value = read(operand, &state, &locals);
+                    let value = match value.as_constant() {
+                        Some(value) => Interval::constant(!value & mask(bits)),
+                        None => Interval::top(bits),
+                    };
+                    (destination, value)
+                }
+                Operation::Shift {
+                    destination,
+                    operand,
+                    shift_n,
+                    shift_t,
+                } => {
+                    let (value, shift) = (
+                        read(operand, &state, &locals),
+                        read(shift_n, &state, &locals),
+                    );
+                    let value = match shift_t {
+                        Shift::Lsl => shift_left(value, shift, bits),
+                        Shift::Lsr => shift_right(value, shift),
+                        _ => Interval::top(bits),
+                    };
+                    (destination, value)
+                }
+                Operation::Sl {
+                    destination,
+                    operand,
+                    shift,
+                } => (
+                    destination,
+                    shift_left(
+                        read(operand, &state, &locals),
+                        read(shift, &state, &locals),
+                        bits,
+                    ),
+                ),
+                Operation::Srl {
+                    destination,
+                    operand,
+                    shift,
+                } => (
+                    destination,
+                    shift_right(read(operand, &state, &locals), read(shift, &state, &locals)),
+                ),
+                Operation::ZeroExtend {
+                    destination,
+                    operand,
+                    bits: valid_bits,
+                    ..
+                }
+                | Operation::Resize {
+                    destination,
+                    operand,
+                    bits: valid_bits,
+                } => (
+                    destination,
+                    truncate(read(operand, &state, &locals), *valid_bits),
+                ),
+                Operation::SignExtend {
+                    destination,
+                    operand,
+                    bits: valid_bits,
+                } => {
+                    let value = truncate(read(operand, &state, &locals), *valid_bits);
+                    let value = match value.max <= mask(valid_bits - 1) {
+                        true => value,
+                        false => Interval::top(bits),
+                    };
+                    (destination, value)
+                }
+                Operation::BitFieldExtract {
+                    destination,
+                    operand,
+                    start_bit,
+                    stop_bit,
+                } => {
+                    let value = match start_bit {
+                        0 => truncate(read(operand, &state, &locals), stop_bit + 1),
+                        _ => Interval::top(stop_bit - start_bit + 1),
+                    };
+                    (destination, value)
+                }
+                Operation::CountOnes { destination, .. }
+                | Operation::CountZeroes { destination, .. }
+                | Operation::CountLeadingOnes { destination, .. }
+                | Operation::CountLeadingZeroes { destination, .. } => {
+                    (destination, Interval::new(0, bits as u64))
+                }
+                Operation::SDiv { destination, .. }
+                | Operation::Sra { destination, .. }
+                | Operation::Sror { destination, .. }
+                | Operation::SignedBitFieldExtract { destination, .. }
+                | Operation::BitFieldInsert { destination, .. }
+                | Operation::ReverseBits { destination, .. }
+                | Operation::RotateRightExtend { destination, .. } => {
+                    (destination, Interval::top(bits))
+                }
+                Operation::ConditionalJump {
+                    destination,
+                    condition,
+                } => {
+                    let destination = read(destination, &state, &locals).as_constant();
+                    effect.jumps.push((*condition, destination));
+                    continue;
+                }
+                Operation::SetCFlag {
+                    operand1: Operand::Register(register),
+                    operand2,
+                    sub: true,
+                    carry: false,
+                } => {
+                    sets_flags = true;
+                    compare = Some((register.to_owned(), read(operand2, &state, &locals)));
+                    continue;
+                }
+                Operation::SetNFlag(_)
+                | Operation::SetZFlag(_)
+                | Operation::SetCFlag { .. }
+                | Operation::SetCFlagShiftLeft { .. }
+                | Operation::SetCFlagSrl { .. }
+                | Operation::SetCFlagSra { .. }
+                | Operation::SetCFlagRor(_)
+                | Operation::SetVFlag { .. } => {
+                    sets_flags = true;
+                    continue;
+                }
+                Operation::ConditionalExecution { conditions } => {
+                    state.conditional = conditions.len();
+                    continue;
+                }
+                Operation::ForEach { .. } => {
+                    // Not modeled, nothing is known after the instruction.
+                    state.registers.clear();
+                    continue;
+                }
+            };
+
+            match destination {
+                Operand::Register(register) if register.starts_with("PC") => {
+                    effect.jumps.push((Condition::None, value.as_constant()));
+                }
+                Operand::Register(register) => {
+                    effect.writes_lr |= register == "LR";
+                    if compare
+                        .as_ref()
+                        .is_some_and(|(compared, _): &(String, Interval)| compared == register)
+                    {
+                        compare = None;
+                    }
+                    state.set_register(register, value, bits);
+                }
+                Operand::Local(local) => {
+                    locals.insert(local.to_owned(), value);
+                }
+                Operand::Flag(_) => sets_flags = true,
+                _ => {}
+            }
+        }
+        if sets_flags {
+            state.compare = compare;
+        }
+        effect.state = state;
+        effect
+    }
+
+    /// Get the range of `operand` in the instruction at `pc`.
+    fn read(
+        &self,
+        pc: u64,
+        next: u64,
+        operand: &Operand,
+        state: &AbstractState,
+        locals: &HashMap<String, Interval>,
+    ) -> Interval {
+        let bits = self.bits;
+        match operand {
+            // The program counter is incremented before an instruction is
+            // executed, reads of the architectural program counter are four
+            // bytes after the instruction as in thumb.
+            Operand::Register(register) if register == "PC" => Interval::constant(next),
+            Operand::Register(register) if register.starts_with("PC") => Interval::constant(pc + 4),
+            Operand::Register(register) => state
+                .registers
+                .get(register)
+                .copied()
+                .unwrap_or(Interval::top(bits)),
+            Operand::Immediate(value) => Interval::constant((*value).into()),
+            Operand::Local(local) => locals.get(local).copied().unwrap_or(Interval::top(bits)),
+            Operand::Address(address, width) => self.read_memory((*address).into(), *width),
+            Operand::AddressInLocal(local, width) => {
+                match locals.get(local).and_then(Interval::as_constant) {
+                    Some(address) => self.read_memory(address, *width),
+                    None => Interval::top(*width),
+                }
+            }
+            Operand::Flag(_) => Interval::new(0, 1),
+            Operand::AddressWithOffset { width, .. } => Interval::top(*width),
+        }
+    }
+
+    /// Get the range of a read from memory, reads from read only program
+    /// memory are constant. Stores are not tracked, so writable memory such as
+    /// `.data` can hold any value.
+    fn read_memory(&self, address: u64, width: u32) -> Interval {
+        let project = self.project;
+        let bytes = (width as u64).div_ceil(8);
+        if !project.address_is_read_only(address, bytes)
+            || project.address_in_backed_region(address)
+            || project.get_memory_read_hook(address).is_some()
+        {
+            return Interval::top(width);
+        }
+        let mut value = 0;
+        for n in 0..bytes {
+            let byte = match project.get_byte(address + n) {
+                Ok(byte) => byte as u64,
+                Err(_) => return Interval::top(width),
+            };
+            value = match project.get_endianness() {
+                Endianness::Little => value | byte << (8 * n),
+                Endianness::Big => value << 8 | byte,
+            };
+        }
+        Interval::constant(value)
+    }
+}
+
+fn add(lhs: Interval, rhs: Interval, bits: u32) -> Interval {
+    match (
+        lhs.max.checked_add(rhs.max),
+        lhs.as_constant(),
+        rhs.as_constant(),
+    ) {
+        (_, Some(lhs), Some(rhs)) => Interval::constant(lhs.wrapping_add(rhs) & mask(bits)),
+        (Some(max), _, _) if max <= mask(bits) => Interval::new(lhs.min + rhs.min, max),
+        _ => Interval::top(bits),
+    }
+}
+
+fn sub(lhs: Interval, rhs: Interval, bits: u32) -> Interval {
+    match (lhs.as_constant(), rhs.as_constant()) {
+        (Some(lhs), Some(rhs)) => Interval::constant(lhs.wrapping_sub(rhs) & mask(bits)),
+        _ if lhs.min >= rhs.max => Interval::new(lhs.min - rhs.max, lhs.max - rhs.min),
+        _ => Interval::top(bits),
+    }
+}
+
+fn mul(lhs: Interval, rhs: Interval, bits: u32) -> Interval {
+    match (lhs.max as u128) * (rhs.max as u128) <= mask(bits) as u128 {
+        true => Interval::new(lhs.min * rhs.min, lhs.max * rhs.max),
+        false => Interval::top(bits),
+    }
+}
+
+fn shift_left(value: Interval, shift: Interval, bits: u32) -> Interval {
+    match shift.as_constant() {
+        Some(shift) if shift < bits as u64 && value.max <= mask(bits) >> shift => {
+            Interval::new(value.min << shift, value.max << shift)
+        }
+        _ => Interval::top(bits),
+    }
+}
+
+fn shift_right(value: Interval, shift: Interval) -> Interval {
+    match shift.max < 64 {
+        true => Interval::new(value.min >> shift.max, value.max >> shift.min.min(63)),
+        false => Interval::new(0, value.max),
+    }
+}
+
+/// Get the range of the `bits` least significant bits of `value`.
+fn truncate(value: Interval, bits: u32) -> Interval {
+    match value.max <= mask(bits) {
+        true => value,
+        false => Interval::top(bits),
+    }
+}
+
+/// Get the smallest mask of ones that covers `value`.
+fn covering_mask(value: u64) -> u64 {
+    match value {
+        0 => 0,
+        value => mask(64 - value.leading_zeros()),
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Assumes the inferred ranges of the symbolic registers before the
+    /// instruction at the program counter, if any.
+    ///
+    /// A register that can be outside of its range, which the analysis did
+    /// not account for, forks the path. The forked path holds the values
+    /// outside of the range.
+    pub(super) fn assume_ranges(&mut self) -> Result<()> {
+        let pc = match self.state.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Ok(()),
+        };
+        let ranges = match self
+            .vm
+            .ranges
+            .as_ref()
+            .and_then(|ranges| ranges.ranges_at(pc))
+        {
+            Some(ranges) => ranges.clone(),
+            None => return Ok(()),
+        };
+        let word_size = self.project.get_word_size();
+        for (register, range) in ranges {
+            let value = self.state.get_register(register.to_owned())?;
+            if value.get_constant().is_some() {
+                continue;
+            }
+            let min = self.state.ctx.from_u64(range.min, word_size);
+            let max = self.state.ctx.from_u64(range.max, word_size);
+            let in_range = value.ugte(&min).and(&value.ulte(&max));
+            if !self.state.constraints.is_sat_with_constraint(&in_range)? {
+                debug!(
+                    "Inferred range {} of {} at {:#X} is not satisfiable on this path, it is not \
+                     assumed",
+                    range, register, pc
+                );
+                continue;
+            }
+            let outside = in_range.not();
+            if self.state.constraints.is_sat_with_constraint(&outside)? {
+                warn!(
+                    "Inferred range {} of {} at {:#X} does not hold on this path, the values \
+                     outside of it are explored on a forked path",
+                    range, register, pc
+                );
+                self.fork(outside)?;
+            }
+            self.state.constraints.assert(&in_range);
+            if let Some(ranges) = self.vm.ranges.as_mut() {
+                *ranges.assumed.entry((pc, register)).or_default() += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use general_assembly::condition::Condition;
+
+    use super::{add, shift_left, sub, AbstractState, Interval, RangeAnalysis};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    #[test]
+    fn test_interval_arithmetic() {
+        let index = Interval::new(0, 255);
+        assert_eq!(add(index, Interval::constant(4), 32), Interval::new(4, 259));
+        assert_eq!(
+            shift_left(index, Interval::constant(2), 32),
+            Interval::new(0, 1020)
+        );
+        assert_eq!(sub(index, Interval::constant(1), 32), Interval::top(32));
+        assert_eq!(
+            sub(Interval::constant(0), Interval::constant(1), 32),
+            Interval::constant(u32::MAX as u64)
+        );
+        assert_eq!(
+            add(Interval::top(32), Interval::constant(1), 32),
+            Interval::top(32)
+        );
+    }
+
+    #[test]
+    fn test_refine_by_comparison() {
+        // cmp r0, #5
+        let state = AbstractState {
+            compare: Some(("R0".to_owned(), Interval::constant(5))),
+            ..AbstractState::default()
+        };
+        // bhi default, the fall through is the table branch
+        let taken = state.clone().refine(Condition::HI, 32).unwrap();
+        let fall_through = state.clone().refine(Condition::LS, 32).unwrap();
+        assert_eq!(taken.registers["R0"], Interval::new(6, u32::MAX as u64));
+        assert_eq!(fall_through.registers["R0"], Interval::new(0, 5));
+
+        // the register can not be below zero
+        let state = AbstractState {
+            compare: Some(("R0".to_owned(), Interval::constant(0))),
+            ..AbstractState::default()
+        };
+        assert!(state.refine(Condition::CC, 32).is_none());
+    }
+
+    #[test]
+    fn test_range_widened_by_hook() {
+        let project = Arc::new(Project::test_project());
+        let mut state = GAState::test_state(project.clone(), 0x100, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        // A hook wrote a value to R0 that the analysis inferred to be at most
+        // ten.
+        let r0 = context.unconstrained(32, "r0");
+        state.set_register("R0".to_owned(), r0.clone()).unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        vm.ranges = Some(RangeAnalysis {
+            ranges: BTreeMap::from([(
+                0x100,
+                BTreeMap::from([("R0".to_owned(), Interval::new(0, 10))]),
+            )]),
+            assumed: BTreeMap::new(),
+        });
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project.clone());
+        executor.assume_ranges().unwrap();
+
+        // The path assumes the range and the values above it are not pruned,
+        // they are explored on a forked path.
+        let ten = context.from_u64(10, 32);
+        assert!(!executor
+            .state
+            .constraints
+            .is_sat_with_constraint(&r0.ugt(&ten))
+            .unwrap());
+        assert_eq!(vm.ranges.as_ref().unwrap().assumed(0x100, "R0"), 1);
+        let path = vm.paths.get_path().unwrap();
+        let mut forked = GAExecutor::from_state(path.state, &mut vm, project);
+        for constraint in path.constraints {
+            forked.state.constraints.assert(&constraint);
+        }
+        assert!(forked
+            .state
+            .constraints
+            .is_sat_with_constraint(&r0.ugt(&ten))
+            .unwrap());
+        assert!(!forked
+            .state
+            .constraints
+            .is_sat_with_constraint(&r0.ulte(&ten))
+            .unwrap());
+
+        // The forked path does not assume the range again.
+        forked.assume_ranges().unwrap();
+        assert!(vm.paths.get_path().is_none());
+        assert_eq!(vm.ranges.as_ref().unwrap().assumed(0x100, "R0"), 1);
+    }
+}
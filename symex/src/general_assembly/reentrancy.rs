@@ -0,0 +1,175 @@
+//! Detection of reentered functions.
+//!
+//! A function that keeps state in static variables, such as a driver that
+//! sequences a peripheral, is often not reentrant. Each path keeps a shadow
+//! stack of the activations of the
+//! [`non_reentrant`](super::RunConfig::non_reentrant) functions, an activation
+//! starts when execution reaches the function and ends when it returns to the
+//! return address with the stack pointer it was entered with. A path fails if
+//! it reaches a function while an activation of it has not returned, such as
+//! when an interrupt handler calls a function the thread code was preempted in.
+
+use tracing::debug;
+
+use super::{
+    arch::Arch,
+    executor::{GAExecutor, PathResult},
+    Result,
+};
+
+/// Reported as the reason of the failure of a path where a non-reentrant
+/// function is reentered.
+pub const REENTERED: &str = "non-reentrant function reentered";
+
+/// An activation of a non-reentrant function.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Activation {
+    /// Index of the function.
+    function: usize,
+
+    /// Address the activation returns to, `None` if it is not known.
+    return_address: Option<u64>,
+
+    /// Stack pointer the function was entered with.
+    sp: Option<u64>,
+}
+
+/// A non-reentrant function that was reentered.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reentry {
+    /// Name of the function.
+    pub function: String,
+
+    /// Return address of the activation that had not returned.
+    pub first: Option<u64>,
+
+    /// Return address of the activation that reentered the function.
+    pub second: Option<u64>,
+}
+
+/// Activations of the non-reentrant functions on a path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ReentrancyState {
+    /// Activations that have not returned, innermost last.
+    active: Vec<Activation>,
+
+    /// The reentry that failed the path.
+    pub reentry: Option<Reentry>,
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Tracks the activations of the non-reentrant functions before the next
+    /// instruction, returns a failure if one is reentered.
+    pub(super) fn check_reentrancy(&mut self) -> Result<Option<PathResult>> {
+        let functions = self.project.get_non_reentrant();
+        if functions.is_empty() {
+            return Ok(None);
+        }
+        let Some(pc) = self.state.get_register("PC".to_owned())?.get_constant() else {
+            return Ok(None);
+        };
+        let pc = pc & !0b1;
+        let sp = self.state.get_register("SP".to_owned())?.get_constant();
+        let active = &mut self.state.reentrancy.active;
+        while let Some(activation) = active.last() {
+            let returned = activation.return_address == Some(pc)
+                && matches!((sp, activation.sp), (Some(sp), Some(entry)) if sp >= entry);
+            if !returned {
+                break;
+            }
+            active.pop();
+        }
+
+        let Some(function) = functions.iter().position(|function| {
+            self.project
+                .get_symbol_address(function)
+                .is_some_and(|address| address & !0b1 == pc)
+        }) else {
+            return Ok(None);
+        };
+        let return_address = self
+            .state
+            .get_register("LR".to_owned())?
+            .get_constant()
+            .map(|lr| lr & !0b1);
+        let reentrancy = &mut self.state.reentrancy;
+        let first = reentrancy
+            .active
+            .iter()
+            .find(|activation| activation.function == function)
+            .map(|activation| activation.return_address);
+        if let Some(first) = first {
+            debug!("Reentered {}", functions[function]);
+            reentrancy.reentry = Some(Reentry {
+                function: functions[function].clone(),
+                first,
+                second: return_address,
+            });
+            return Ok(Some(PathResult::Failure(REENTERED)));
+        }
+        reentrancy.active.push(Activation {
+            function,
+            return_address,
+            sp,
+        });
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::REENTERED;
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::{GAExecutor, PathResult},
+        interrupts::{enter_handler, Interrupt},
+        project::{PCHook, Project},
+        state::GAState,
+        vm::VM,
+    };
+
+    #[test]
+    fn test_reentered() {
+        let mut project = Project::<ArmV6M>::test_project().with_symbols(HashMap::from([
+            ("send".to_owned(), 0x101),
+            ("handler".to_owned(), 0x201),
+        ]));
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("handler"));
+        project.add_non_reentrant("send");
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let set = |executor: &mut GAExecutor<'_, ArmV6M>, register: &str, value| {
+            let value = context.from_u64(value, 32);
+            executor
+                .state
+                .set_register(register.to_owned(), value)
+                .unwrap();
+        };
+
+        // Called and returned.
+        set(&mut executor, "LR", 0x401);
+        assert!(executor.check_reentrancy().unwrap().is_none());
+        set(&mut executor, "PC", 0x400);
+        assert!(executor.check_reentrancy().unwrap().is_none());
+        // Called again and preempted by a handler that calls it.
+        set(&mut executor, "PC", 0x100);
+        set(&mut executor, "LR", 0x411);
+        assert!(executor.check_reentrancy().unwrap().is_none());
+        set(&mut executor, "PC", 0x104);
+        enter_handler(&mut executor.state, 0, true).unwrap();
+        set(&mut executor, "PC", 0x100);
+        set(&mut executor, "LR", 0x205);
+        let result = executor.check_reentrancy().unwrap();
+        assert!(matches!(result, Some(PathResult::Failure(REENTERED))));
+        let reentry = executor.state.reentrancy.reentry.clone().unwrap();
+        assert_eq!(reentry.function, "send");
+        assert_eq!((reentry.first, reentry.second), (Some(0x410), Some(0x204)));
+    }
+}
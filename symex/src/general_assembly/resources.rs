@@ -0,0 +1,185 @@
+//! Verification of the priority ceilings of shared resources.
+//!
+//! Frameworks such as RTIC protect a resource shared by tasks of different
+//! priorities with the immediate ceiling protocol. Every task that accesses the
+//! resource runs at a priority of at least the ceiling of the resource, or
+//! raises `BASEPRI` to the ceiling for the duration of the access. Each access
+//! to one of the [`resources`](super::RunConfig::resources) with a lower
+//! running priority is recorded as a violation of the protocol.
+//!
+//! Priorities are the values of the priority registers, lower values are more
+//! urgent. The logical priorities of RTIC are converted with
+//! [`logical_priority`].
+
+use std::collections::BTreeSet;
+
+use super::{arch::Arch, executor::GAExecutor, interrupts::InterruptMask};
+
+/// Converts the logical priority `priority` of RTIC, where higher values are
+/// more urgent and 1 is the lowest task priority, to the value of the priority
+/// registers of a device that implements `nvic_prio_bits` priority bits.
+pub fn logical_priority(priority: u8, nvic_prio_bits: u8) -> u8 {
+    let levels = 1u32 << nvic_prio_bits;
+    let value = levels.saturating_sub(priority as u32) << (8 - nvic_prio_bits as u32);
+    value.min(u8::MAX as u32) as u8
+}
+
+/// A variable shared by tasks of different priorities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    /// Name of the symbol of the variable.
+    pub symbol: String,
+
+    /// Priority ceiling of the resource, the most urgent priority of the tasks
+    /// that access it.
+    pub ceiling: u8,
+}
+
+impl Resource {
+    /// Creates a resource for the variable `symbol` with the priority ceiling
+    /// `ceiling`.
+    pub fn new(symbol: &str, ceiling: u8) -> Self {
+        Self {
+            symbol: symbol.to_owned(),
+            ceiling,
+        }
+    }
+}
+
+/// An access to a resource below its ceiling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CeilingViolation {
+    /// Index of the resource.
+    pub resource: usize,
+
+    /// Address of the accessing instruction.
+    pub pc: u64,
+
+    /// True if the instruction writes.
+    pub write: bool,
+
+    /// Index of the interrupt whose handler accessed the resource, `None` for
+    /// the thread code.
+    pub interrupt: Option<usize>,
+
+    /// Interrupt mask the instruction was executed with.
+    pub mask: InterruptMask,
+}
+
+/// Violations of the priority ceilings on any path.
+pub type CeilingViolations = BTreeSet<CeilingViolation>;
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Records an access of `bits` bits at `address` if it accesses a resource
+    /// below its ceiling.
+    pub(super) fn check_resource_access(&mut self, address: u64, bits: u32, write: bool) {
+        let resources = self.project.get_resources();
+        if resources.is_empty() {
+            return;
+        }
+        let bytes = (bits as u64).div_ceil(8);
+        let mask = InterruptMask::of(&self.state);
+        let interrupt = self.state.interrupts.handler_index();
+        let running = interrupt.map(|index| self.project.get_interrupts()[index].priority);
+        for (index, resource) in resources.iter().enumerate() {
+            let Some(start) = self.project.get_symbol_address(&resource.symbol) else {
+                continue;
+            };
+            let size = self
+                .project
+                .get_symbol_size(&resource.symbol)
+                .unwrap_or(0)
+                .max(1);
+            if address >= start + size || start >= address + bytes {
+                continue;
+            }
+            let protected = mask.primask
+                || (mask.basepri != 0 && mask.basepri <= resource.ceiling as u64)
+                || running.is_some_and(|running| running <= resource.ceiling);
+            if !protected {
+                self.vm.ceiling_violations.insert(CeilingViolation {
+                    resource: index,
+                    pc: self.state.last_pc,
+                    write,
+                    interrupt,
+                    mask,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::{logical_priority, Resource};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        interrupts::{enter_handler, Interrupt},
+        project::{PCHook, Project},
+        state::GAState,
+        vm::VM,
+    };
+
+    #[test]
+    fn test_logical_priority() {
+        assert_eq!(logical_priority(1, 4), 0xf0);
+        assert_eq!(logical_priority(3, 4), 0xd0);
+        assert_eq!(logical_priority(2, 3), 0xc0);
+    }
+
+    #[test]
+    fn test_ceiling_violations() {
+        let mut project = Project::<ArmV6M>::test_project().with_symbols(HashMap::from([
+            ("COUNTER".to_owned(), 0x2000_0000),
+            ("low".to_owned(), 0x200),
+            ("high".to_owned(), 0x300),
+        ]));
+        project.add_pc_hook(0x200, PCHook::Continue);
+        project.add_pc_hook(0x300, PCHook::Continue);
+        project.add_interrupt(Interrupt::new("low").with_priority(0xe0));
+        project.add_interrupt(Interrupt::new("high").with_priority(0xc0));
+        project.add_resource(Resource::new("COUNTER", 0xc0));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let basepri = |executor: &mut GAExecutor<'_, ArmV6M>, value| {
+            let value = context.from_u64(value, 32);
+            executor
+                .state
+                .set_register("BASEPRI".to_owned(), value)
+                .unwrap();
+        };
+
+        // The thread code locks the resource at its ceiling.
+        basepri(&mut executor, 0xc0);
+        executor.check_resource_access(0x2000_0000, 32, true);
+        // Only locks out the low priority task.
+        basepri(&mut executor, 0xe0);
+        executor.state.last_pc = 0x104;
+        executor.check_resource_access(0x2000_0000, 32, false);
+        basepri(&mut executor, 0);
+        // Does not touch the resource.
+        executor.check_resource_access(0x2000_0004, 32, true);
+
+        // The high priority task runs at the ceiling, the low one does not.
+        enter_handler(&mut executor.state, 1, true).unwrap();
+        executor.check_resource_access(0x2000_0000, 8, true);
+        enter_handler(&mut executor.state, 0, true).unwrap();
+        executor.state.last_pc = 0x200;
+        executor.check_resource_access(0x2000_0000, 8, true);
+
+        let violations: Vec<_> = executor
+            .vm
+            .ceiling_violations
+            .iter()
+            .map(|violation| (violation.pc, violation.interrupt))
+            .collect();
+        assert_eq!(violations, vec![(0x104, None), (0x200, Some(0))]);
+    }
+}
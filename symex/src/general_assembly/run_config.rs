@@ -8,18 +8,38 @@
 //! carried out. Therefore it is advised that one familiarizes oneself with the
 //! inner workings of Symex executor before writing a hook function.
 
+use std::{ops::Range, path::PathBuf, sync::Arc, time::Duration};
+
 use regex::Regex;
 
 use super::{
     arch::Arch,
+    branch_predictor::BranchPredictor,
+    budget::ExecutionBudget,
+    cache::CacheConfig,
+    cosim::CoSimulator,
+    energy::EnergyModel,
+    hardware::{self, HardwareModel},
+    interrupts::Interrupt,
+    peripheral_model::PeripheralModel,
+    pipeline::PipelineModel,
     project::{
+        BackedRegion,
+        CallSiteHook,
+        FunctionFilter,
+        LoopInvariant,
+        MemoryHavoc,
         MemoryHookAddress,
         MemoryReadHook,
         MemoryWriteHook,
         PCHook,
+        RegionMetaData,
         RegisterReadHook,
         RegisterWriteHook,
     },
+    resources::Resource,
+    temporal::TemporalProperty,
+    watchdog::Watchdog,
 };
 
 /// Configures a symbolic execution run.
@@ -33,6 +53,16 @@ pub struct RunConfig<A: Arch> {
     /// provided regular expression and taking the starting address from these.
     pub pc_hooks: Vec<(Regex, PCHook<A>)>,
 
+    /// Hooks that replace a function only when it is called from a specific
+    /// call site, see [`CallSiteHook`]. These take precedence over
+    /// `pc_hooks`.
+    pub call_site_hooks: Vec<CallSiteHook<A>>,
+
+    /// Functions that are skipped or assumed to succeed instead of being
+    /// explored, see [`FunctionFilter`]. These replace `pc_hooks` on the same
+    /// function.
+    pub function_filters: Vec<FunctionFilter>,
+
     /// A register read hook will run a function instead of reading from a
     /// specified register. There can only be one hook on a single register.
     pub register_read_hooks: Vec<(String, RegisterReadHook<A>)>,
@@ -60,6 +90,434 @@ pub struct RunConfig<A: Arch> {
     /// order is preserved it is recommended to ensure that there are no
     /// overlapping ranges.
     pub memory_read_hooks: Vec<(MemoryHookAddress, MemoryReadHook<A>)>,
+
+    /// Regions of code to measure the cycle count of. A cycle lap is recorded
+    /// each time execution reaches the start or the end of a region, this
+    /// allows measuring sub function regions without adding intrinsic calls
+    /// to the analyzed program.
+    pub regions: Vec<RegionMetaData>,
+
+    /// Automatically add regions for all lexical blocks and inlined
+    /// subroutines found in the dwarf debug data.
+    pub regions_from_debug_info: bool,
+
+    /// Number of instructions to report in the hotspot profile after all paths
+    /// have been explored, zero disables the report.
+    pub hotspots: usize,
+
+    /// Report conditional branches where only one outcome was feasible across
+    /// all paths after the run.
+    pub branch_report: bool,
+
+    /// Report the pairs of accesses of the thread code and the
+    /// [`interrupts`](Self::interrupts) to shared memory that may race after
+    /// the run, see [`races`](super::races).
+    pub race_report: bool,
+
+    /// Report every opcode executed on any path and whether it was modeled,
+    /// stubbed, havoced or unsupported after the run, see
+    /// [`Semantics`](super::profile::Semantics).
+    pub coverage_report: bool,
+
+    /// Report how many instructions of each of the regions, such as the
+    /// blocks added by
+    /// [`regions_from_debug_info`](Self::regions_from_debug_info),
+    /// were executed on any path after the run.
+    pub block_coverage_report: bool,
+
+    /// Maximum call depth to explore, calls beyond this depth are replaced by
+    /// a stub that returns an unconstrained value and changes the registers a
+    /// call may change. Combined with `main` or the reset handler as the
+    /// [`entry`](Self::entry) this explores the whole program.
+    pub max_call_depth: Option<usize>,
+
+    /// Where the exploration starts, instead of the function the run is
+    /// started with, `None` to start at that function.
+    pub entry: Option<Entry>,
+
+    /// Maximum number of paths forked for the values of a symbolic address or
+    /// branch target. Beyond it the values are prioritized and the rest are
+    /// dropped, which is reported with the path. When `None` a path with an
+    /// address of more than 255 values fails.
+    pub max_forks: Option<usize>,
+
+    /// Prune paths that reach a state at a branch target that another path has
+    /// already explored, compared by
+    /// [`GAState::key`](super::state::GAState::key).
+    pub deduplicate_states: bool,
+
+    /// Prune paths whose state at a branch target is implied by a state
+    /// another path explored, the same values under a path condition that
+    /// includes this one and no more cycles. The value is the maximum number
+    /// of solver queries per check, `None` disables the check.
+    pub subsumption_budget: Option<usize>,
+
+    /// Model used for reads of hardware time sources such as the DWT cycle
+    /// counter and SysTick. When `None` these reads return unconstrained
+    /// values.
+    pub time_source: Option<TimeSource>,
+
+    /// How to handle instructions that the decoder reports as unpredictable.
+    pub unpredictable: UnpredictablePolicy,
+
+    /// Behavior when a register that the architecture does not define is read
+    /// before it is written, such as a misspelled register in a hook.
+    pub unknown_registers: UnknownRegisterPolicy,
+
+    /// External model that is notified of every retired instruction, see
+    /// [`CoSimulator`].
+    pub co_simulator: Option<Arc<dyn CoSimulator>>,
+
+    /// Model of the energy used by the instructions and memory accesses, see
+    /// [`energy`](super::energy).
+    pub energy_model: Option<Arc<dyn EnergyModel>>,
+
+    /// Instruction cache that instruction fetches are looked up in, see
+    /// [`cache`](super::cache).
+    pub instruction_cache: Option<CacheConfig>,
+
+    /// Data cache that data memory accesses are looked up in, see
+    /// [`cache`](super::cache).
+    pub data_cache: Option<CacheConfig>,
+
+    /// Branch predictor of the core that decides the cycles of the
+    /// conditional branches, see [`branch_predictor`](super::branch_predictor).
+    pub branch_predictor: Option<BranchPredictor>,
+
+    /// Stalls between dependent instructions, see
+    /// [`pipeline`](super::pipeline).
+    pub pipeline: Option<PipelineModel>,
+
+    /// Addresses of a recorded trace, see
+    /// [`parse_trace`](super::trace_import::parse_trace). The first path
+    /// follows the trace and the branches that diverge from it are explored
+    /// afterwards.
+    pub trace: Vec<u64>,
+
+    /// Memory regions backed by concrete byte buffers, such as an external
+    /// flash image. Writes to these regions are only visible to the path that
+    /// made them.
+    pub backed_regions: Vec<BackedRegion>,
+
+    /// Value of the bytes in each region that are read before they are
+    /// written, memory outside of these regions is unconstrained.
+    pub unknown_memory: Vec<UnknownMemoryRegion>,
+
+    /// Invariants of loops, loops with an invariant are verified inductively
+    /// instead of being unrolled, see [`LoopInvariant`].
+    pub loop_invariants: Vec<LoopInvariant<A>>,
+
+    /// Accelerate counting loops instead of unrolling them, see
+    /// [`acceleration`](super::acceleration). The cycles of the accelerated
+    /// iterations are not counted.
+    pub accelerate_loops: bool,
+
+    /// Replace the remaining iterations of loops that copy memory one element
+    /// per iteration with a copy of at most this many bytes at once, see
+    /// [`copies`](super::copies). `None` executes copy loops as usual.
+    pub accelerate_copies: Option<u64>,
+
+    /// Infer the ranges of the registers before exploring the program and
+    /// assume them at loop headers and memory accesses, see
+    /// [`range_analysis`](super::range_analysis). The inferred ranges are
+    /// reported after the run.
+    pub range_analysis: bool,
+
+    /// Memory regions that become arbitrary at a program point, such as DMA
+    /// buffers, see [`MemoryHavoc`].
+    pub memory_havocs: Vec<MemoryHavoc<A>>,
+
+    /// File the knowledge base of function side effects is loaded from and
+    /// saved to, see [`SideEffects`](super::side_effects::SideEffects). When
+    /// set, the side effects of all functions that return are recorded and
+    /// stubs are checked against the side effects known for the function
+    /// they replace.
+    pub side_effects_file: Option<PathBuf>,
+
+    /// Registers whose writes are recorded, see
+    /// [`RegisterWrite`](super::state::RegisterWrite). The history is reported
+    /// for each path.
+    pub recorded_registers: Vec<String>,
+
+    /// Maximum number of register writes kept per path, older writes are
+    /// dropped.
+    pub register_history_limit: usize,
+
+    /// Address ranges of memory mapped peripherals, the accesses to them are
+    /// recorded in program order, see [`mmio`](super::mmio).
+    pub mmio_regions: Vec<Range<u64>>,
+
+    /// Automata that model the allowed behavior of peripherals, loaded with
+    /// [`parse_peripheral_models`](super::peripheral_model::parse_peripheral_models).
+    pub peripheral_models: Vec<PeripheralModel>,
+
+    /// Memory mapped peripherals described by their registers, added with
+    /// [`add_hardware_model`](Self::add_hardware_model).
+    pub hardware_models: Vec<HardwareModel>,
+
+    /// Temporal properties that every path has to satisfy, see
+    /// [`temporal`](super::temporal).
+    pub properties: Vec<TemporalProperty>,
+
+    /// Watchdog timer that has to be refreshed before it expires, see
+    /// [`watchdog`](super::watchdog).
+    pub watchdog: Option<Watchdog>,
+
+    /// Interrupts that may preempt the explored function, see
+    /// [`interrupts`](super::interrupts).
+    pub interrupts: Vec<Interrupt>,
+
+    /// Only inject an interrupt after the instructions whose memory accesses
+    /// conflict with its handler, which explores each interleaving that
+    /// reaches a different state once.
+    pub partial_order_reduction: bool,
+
+    /// Fail the paths where an interrupt handler writes an address between a
+    /// load and a store of the thread code to it, see
+    /// [`interrupts`](super::interrupts).
+    pub atomicity_violations: bool,
+
+    /// Shared variables whose priority ceilings are verified, accesses below
+    /// the ceiling are reported after the run, see
+    /// [`resources`](super::resources).
+    pub resources: Vec<Resource>,
+
+    /// Names of the functions that fail a path when they are entered before
+    /// an earlier activation of them returns, see
+    /// [`reentrancy`](super::reentrancy).
+    pub non_reentrant: Vec<String>,
+
+    /// Names of the functions whose calls are summarized the first time they
+    /// return, later calls that read the same values apply the summary
+    /// instead of executing the function, see [`summaries`](super::summaries).
+    pub summarized: Vec<String>,
+
+    /// Model the enable, pending and active registers of the NVIC so that
+    /// only enabled [`interrupts`](Self::interrupts) are injected, see
+    /// [`nvic`](super::nvic).
+    pub nvic: bool,
+
+    /// Record the data flow of each path and report a backward slice from the
+    /// failure for failing paths, see [`slice`](super::slice).
+    pub slice_failures: bool,
+
+    /// Time limit of each solver query. A path whose query is not decided in
+    /// time ends with
+    /// [`PathResult::Unknown`](super::executor::PathResult::Unknown) instead
+    /// of blocking the rest of the analysis.
+    pub solver_timeout: Option<Duration>,
+
+    /// Record the paths so that the state before any executed instruction can
+    /// be reconstructed after the path ends, see
+    /// [`time_travel`](super::time_travel).
+    pub time_travel: bool,
+
+    /// Number of instructions between the snapshots of a recorded path, fewer
+    /// instructions make the reconstruction of earlier states faster at the
+    /// cost of memory. `None` only takes a snapshot at the start of the path.
+    pub snapshot_interval: Option<usize>,
+
+    /// Maximum size in bytes of a symbol, such as a buffer made symbolic with
+    /// `symbolic_size` or a read from a peripheral. Larger symbols are only
+    /// symbolic in their low bytes and the other bytes are assumed to be zero,
+    /// which is logged. `None` does not bound the symbols.
+    pub max_symbol_size: Option<u64>,
+
+    /// Model of the symbolic memory of each path, see [`MemoryModel`].
+    pub memory_model: MemoryModel,
+
+    /// Model of the memory in each region of the memory map of the device,
+    /// memory outside of the regions uses
+    /// [`memory_model`](Self::memory_model). Earlier regions take precedence
+    /// where regions overlap.
+    pub memory_map: Vec<MemoryMapRegion>,
+
+    /// Track the frame base of each function activation so that locals on the
+    /// stack are named after their function and variable, as
+    /// `app::parse::len`, see [`stack_locals`](super::stack_locals).
+    pub stack_locals: bool,
+
+    /// Solve for the range of each variable marked as symbolic at the end of
+    /// each path, see [`preconditions`](crate::preconditions).
+    pub infer_preconditions: bool,
+
+    /// Limits of the exploration, a path that exceeds them ends with
+    /// [`PathResult::Truncated`](super::executor::PathResult::Truncated), see
+    /// [`ExecutionBudget`].
+    pub budget: ExecutionBudget,
+}
+
+/// Where the exploration starts, see [`RunConfig::entry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    /// The `main` function.
+    Main,
+
+    /// The reset handler, read from the second entry of the vector table.
+    /// The vector table is found by the symbol `cortex-m-rt`, CMSIS or the
+    /// vendor startup code gives it.
+    Reset,
+}
+
+/// Behavior when an instruction is defined as unpredictable.
+///
+/// Real firmware sometimes contains such encodings that the hardware
+/// tolerates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnpredictablePolicy {
+    /// Log a warning and treat the instruction as a no-op.
+    Nop,
+
+    /// Log a warning and make all registers except the program counter and
+    /// stack pointer and all flags unconstrained, as the destination is not
+    /// known.
+    Havoc,
+
+    /// End the path as a failure.
+    #[default]
+    FailPath,
+}
+
+/// Behavior when a register that is not defined by the architecture is read
+/// before it is written, see [`InstructionSet::registers`].
+///
+/// [`InstructionSet::registers`]: super::arch::InstructionSet::registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownRegisterPolicy {
+    /// Create an unconstrained value for the register.
+    #[default]
+    Create,
+
+    /// Create an unconstrained value and log a warning with the address of
+    /// the instruction that read the register.
+    Warn,
+
+    /// Fail with [`GAError::UnknownRegister`](super::GAError::UnknownRegister).
+    Error,
+}
+
+/// Value of a byte that is read before it is written, see
+/// [`UnknownMemoryRegion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownMemoryPolicy {
+    /// The unconstrained contents of the symbolic memory, reading the same
+    /// byte again gives the same value.
+    #[default]
+    Unconstrained,
+
+    /// Zero, such as for memory that is cleared at reset.
+    Zero,
+
+    /// The given byte, such as `0xFF` for erased flash.
+    Fill(u8),
+
+    /// A new symbol on every read, such as for peripheral registers that
+    /// change between reads.
+    Fresh,
+
+    /// Fail with
+    /// [`GAError::UninitializedMemory`](super::GAError::UninitializedMemory).
+    ///
+    /// Bytes that may have been written through a symbolic address are read
+    /// from memory instead.
+    Error,
+}
+
+/// A range of memory with its own [`UnknownMemoryPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownMemoryRegion {
+    /// Address of the first byte in the region.
+    pub start: u64,
+
+    /// Address of the first byte after the region.
+    pub end: u64,
+
+    /// Value of the bytes in the region that are read before they are
+    /// written.
+    pub policy: UnknownMemoryPolicy,
+}
+
+impl UnknownMemoryRegion {
+    /// Creates a region of the bytes from `start` up to `end`.
+    pub fn new(start: u64, end: u64, policy: UnknownMemoryPolicy) -> Self {
+        Self { start, end, policy }
+    }
+
+    /// Checks if `address` is inside of the region.
+    pub fn contains(&self, address: u64) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+}
+
+/// Model of the symbolic memory, see [`memory`](crate::memory).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryModel {
+    /// A single theories-of-array, see
+    /// [`ArrayMemory`](crate::memory::ArrayMemory).
+    #[default]
+    Array,
+
+    /// Separately allocated regions that are copied on write after a fork, see
+    /// [`RegionMemory`](crate::memory::RegionMemory). Faster for firmware that
+    /// mostly accesses memory through concrete addresses.
+    Region,
+}
+
+/// Model of the memory in a [`MemoryMapRegion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionModel {
+    /// See [`MemoryModel::Array`], such as for small peripheral regions that
+    /// are accessed through symbolic addresses.
+    Array,
+
+    /// See [`MemoryModel::Region`], such as for RAM.
+    Region,
+
+    /// A [`MemoryModel::Region`] where the bytes that have not been written
+    /// are read from the given contents of the region, such as for flash.
+    Backed(Arc<[u8]>),
+}
+
+/// A range of memory with its own [`RegionModel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMapRegion {
+    /// Address of the first byte in the region.
+    pub start: u64,
+
+    /// Address of the first byte after the region.
+    pub end: u64,
+
+    /// Model of the memory in the region.
+    pub model: RegionModel,
+}
+
+impl MemoryMapRegion {
+    /// Creates a region of the bytes from `start` up to `end`.
+    pub fn new(start: u64, end: u64, model: RegionModel) -> Self {
+        Self { start, end, model }
+    }
+
+    /// Creates a region backed by `data` from `start`.
+    pub fn backed(start: u64, data: Vec<u8>) -> Self {
+        let end = start + data.len() as u64;
+        Self::new(start, end, RegionModel::Backed(data.into()))
+    }
+
+    /// Checks if `address` is inside of the region.
+    pub fn contains(&self, address: u64) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+}
+
+/// Model for reads of hardware time sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSource {
+    /// Reads return the value consistent with the cycle count of the path.
+    CycleCount,
+
+    /// Reads return a fresh symbol that is at most the given number of cycles
+    /// later than the cycle count of the path.
+    Bounded(u32),
 }
 
 impl<A: Arch> RunConfig<A> {
@@ -68,11 +526,82 @@ impl<A: Arch> RunConfig<A> {
         Self {
             show_path_results,
             pc_hooks: vec![],
+            call_site_hooks: vec![],
+            function_filters: vec![],
             register_read_hooks: vec![],
             register_write_hooks: vec![],
             memory_write_hooks: vec![],
             memory_read_hooks: vec![],
+            regions: vec![],
+            regions_from_debug_info: false,
+            hotspots: 0,
+            branch_report: false,
+            race_report: false,
+            coverage_report: false,
+            block_coverage_report: false,
+            max_call_depth: None,
+            entry: None,
+            max_forks: None,
+            deduplicate_states: false,
+            subsumption_budget: None,
+            time_source: None,
+            unpredictable: UnpredictablePolicy::FailPath,
+            unknown_registers: UnknownRegisterPolicy::Create,
+            co_simulator: None,
+            energy_model: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictor: None,
+            pipeline: None,
+            trace: vec![],
+            backed_regions: vec![],
+            unknown_memory: vec![],
+            loop_invariants: vec![],
+            accelerate_loops: false,
+            accelerate_copies: None,
+            range_analysis: false,
+            memory_havocs: vec![],
+            side_effects_file: None,
+            recorded_registers: vec![],
+            register_history_limit: 256,
+            mmio_regions: vec![],
+            peripheral_models: vec![],
+            hardware_models: vec![],
+            properties: vec![],
+            watchdog: None,
+            interrupts: vec![],
+            partial_order_reduction: true,
+            atomicity_violations: false,
+            resources: vec![],
+            non_reentrant: vec![],
+            summarized: vec![],
+            nvic: false,
+            slice_failures: false,
+            solver_timeout: None,
+            time_travel: false,
+            snapshot_interval: Some(1000),
+            max_symbol_size: None,
+            memory_model: MemoryModel::Array,
+            memory_map: vec![],
+            stack_locals: false,
+            infer_preconditions: false,
+            budget: ExecutionBudget::unlimited(),
+        }
+    }
+
+    /// Adds the peripherals of `model`, with a memory read and a memory write
+    /// hook for the address range of each peripheral, see
+    /// [`hardware`](super::hardware).
+    pub fn add_hardware_model(&mut self, model: HardwareModel) {
+        for peripheral in &model.peripherals {
+            let range =
+                MemoryHookAddress::Range(peripheral.base, peripheral.base + peripheral.size);
+            self.memory_read_hooks
+                .push((range.clone(), hardware::read_mapped_register::<A>));
+            self.memory_write_hooks
+                .push((range, hardware::write_mapped_register::<A>));
         }
+        self.hardware_models.push(model);
     }
 }
 
@@ -81,10 +610,66 @@ impl<A: Arch> Default for RunConfig<A> {
         Self {
             show_path_results: true,
             pc_hooks: vec![],
+            call_site_hooks: vec![],
+            function_filters: vec![],
             register_read_hooks: vec![],
             register_write_hooks: vec![],
             memory_write_hooks: vec![],
             memory_read_hooks: vec![],
+            regions: vec![],
+            regions_from_debug_info: false,
+            hotspots: 0,
+            branch_report: false,
+            race_report: false,
+            coverage_report: false,
+            block_coverage_report: false,
+            max_call_depth: None,
+            entry: None,
+            max_forks: None,
+            deduplicate_states: false,
+            subsumption_budget: None,
+            time_source: None,
+            unpredictable: UnpredictablePolicy::FailPath,
+            unknown_registers: UnknownRegisterPolicy::Create,
+            co_simulator: None,
+            energy_model: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictor: None,
+            pipeline: None,
+            trace: vec![],
+            backed_regions: vec![],
+            unknown_memory: vec![],
+            loop_invariants: vec![],
+            accelerate_loops: false,
+            accelerate_copies: None,
+            range_analysis: false,
+            memory_havocs: vec![],
+            side_effects_file: None,
+            recorded_registers: vec![],
+            register_history_limit: 256,
+            mmio_regions: vec![],
+            peripheral_models: vec![],
+            hardware_models: vec![],
+            properties: vec![],
+            watchdog: None,
+            interrupts: vec![],
+            partial_order_reduction: true,
+            atomicity_violations: false,
+            resources: vec![],
+            non_reentrant: vec![],
+            summarized: vec![],
+            nvic: false,
+            slice_failures: false,
+            solver_timeout: None,
+            time_travel: false,
+            snapshot_interval: Some(1000),
+            max_symbol_size: None,
+            memory_model: MemoryModel::Array,
+            memory_map: vec![],
+            stack_locals: false,
+            infer_preconditions: false,
+            budget: ExecutionBudget::unlimited(),
         }
     }
 }
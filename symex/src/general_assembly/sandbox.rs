@@ -0,0 +1,179 @@
+//! Isolation of panics in hooks.
+//!
+//! Hooks are code of the user that runs inside of the executor, such as an
+//! intrinsic or a model of a peripheral register. A hook that panics, for
+//! example on an `unwrap` of a value it did not expect to be symbolic, only
+//! fails the path it ran on. The path ends as a failure with
+//! [`HOOK_PANICKED`] as the reason and the [`HookPanic`], naming the hook and
+//! where it was added, is attached to the
+//! [`path_data`](super::state::GAState::path_data) of the path. The other
+//! paths are explored as usual.
+//!
+//! The panic is still printed by the panic hook of the process.
+
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+};
+
+use tracing::warn;
+
+use super::{
+    arch::Arch,
+    executor::{GAExecutor, PathResult},
+    project::{HookHandle, HookRegistration},
+    state::GAState,
+    GAError,
+    Result,
+};
+use crate::smt::DExpr;
+
+/// Reported as the reason of the failure of a path where a hook panicked.
+pub const HOOK_PANICKED: &str = "hook panicked";
+
+/// A hook that panicked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookPanic {
+    /// The hook that panicked.
+    pub hook: HookHandle,
+
+    /// Where the hook was added, `None` if it is not known.
+    pub registration: Option<HookRegistration>,
+
+    /// Message of the panic.
+    pub message: String,
+
+    /// Address of the last executed instruction when the hook ran.
+    pub pc: u64,
+}
+
+impl fmt::Display for HookPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the {} panicked at {:#x}: {}",
+            self.hook, self.pc, self.message
+        )?;
+        match &self.registration {
+            Some(registration) => write!(f, " (hook {registration})"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<A: Arch> GAState<A> {
+    /// Runs the hook identified by `hook`, a panic in the hook is returned as
+    /// [`GAError::HookPanicked`].
+    pub(crate) fn run_hook<T>(
+        &mut self,
+        hook: impl FnOnce() -> HookHandle,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        match panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(result) => result,
+            Err(payload) => {
+                let message = match payload.downcast::<String>() {
+                    Ok(message) => *message,
+                    Err(payload) => match payload.downcast::<&'static str>() {
+                        Ok(message) => (*message).to_owned(),
+                        Err(_) => "panic with a payload that is not a string".to_owned(),
+                    },
+                };
+                let hook = hook();
+                Err(GAError::HookPanicked(Box::new(HookPanic {
+                    registration: self.project.get_hook_registration(&hook).cloned(),
+                    hook,
+                    message,
+                    pc: self.last_pc,
+                })))
+            }
+        }
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Runs the intrinsic `f` of the pc hook at the program counter.
+    pub(super) fn run_intrinsic(&mut self, f: fn(&mut GAState<A>) -> Result<()>) -> Result<()> {
+        let register = |name: &str| self.state.registers.get(name).and_then(DExpr::get_constant);
+        let pc = register("PC").unwrap_or(self.state.last_pc) & !0b1;
+        let lr = register("LR");
+        let project = self.project.clone();
+        let handle = move || {
+            project
+                .get_pc_hook_handle(pc, lr)
+                .expect("intrinsics are run by hooks")
+        };
+        self.state.run_hook(handle, f)
+    }
+
+    /// Ends the path as a failure if `result` is a panic in a hook, the panic
+    /// is attached to the path.
+    pub(super) fn fail_on_hook_panic(&mut self, result: Result<PathResult>) -> Result<PathResult> {
+        match result {
+            Err(GAError::HookPanicked(panic)) => {
+                warn!("Failing the path, {}", panic);
+                self.state.path_data.insert(*panic);
+                Ok(PathResult::Failure(HOOK_PANICKED))
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{HookPanic, HOOK_PANICKED};
+    use crate::{
+        general_assembly::{
+            arch::arm::v6::ArmV6M,
+            executor::{GAExecutor, PathResult},
+            project::{HookHandle, HookRegistration, MemoryHookAddress, Project},
+            state::GAState,
+            vm::VM,
+            GAError,
+            Result,
+        },
+        smt::DExpr,
+    };
+
+    #[test]
+    fn test_hook_panic() {
+        let mut project = Project::<ArmV6M>::test_project();
+        fn read(_state: &mut GAState<ArmV6M>, _address: u64) -> Result<DExpr> {
+            panic!("unexpected read");
+        }
+        let line = line!() + 1;
+        project.add_memory_read_hook(MemoryHookAddress::Range(0x4000_0000, 0x4000_0100), read);
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project.clone());
+
+        let handle = || project.get_memory_read_hook_handle(0x4000_0010);
+        let result = executor
+            .state
+            .run_hook(handle, |state| read(state, 0x4000_0010));
+        let Err(GAError::HookPanicked(panic)) = result else {
+            panic!("the panic is not caught");
+        };
+        assert_eq!(
+            panic.hook,
+            HookHandle::MemoryRead(MemoryHookAddress::Range(0x4000_0000, 0x4000_0100))
+        );
+        assert_eq!(panic.message, "unexpected read");
+        let Some(HookRegistration::Call(location)) = &panic.registration else {
+            panic!("the registration is not recorded");
+        };
+        assert_eq!((location.file(), location.line()), (file!(), line));
+        assert!(panic
+            .to_string()
+            .starts_with("the memory read hook at 0x40000000..0x40000100 panicked"));
+
+        let result = executor.fail_on_hook_panic(Err(GAError::HookPanicked(panic)));
+        assert!(matches!(result, Ok(PathResult::Failure(HOOK_PANICKED))));
+        assert!(executor.state.path_data.get::<HookPanic>().is_some());
+    }
+}
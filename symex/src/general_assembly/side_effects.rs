@@ -0,0 +1,235 @@
+//! Knowledge base of the side effects of functions.
+//!
+//! The side effects of a function are recorded from the call until it returns
+//! and accumulated over all explored paths. The knowledge base can be saved
+//! and loaded again, which allows checking that a stub replacing a function
+//! covers the side effects observed for the real function in an earlier run.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use thiserror::Error;
+
+use crate::smt::DExpr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SideEffectsError {
+    #[error("Malformed line {0} in side effects file.")]
+    MalformedLine(usize),
+
+    #[error("Side effect on line {0} does not belong to a function.")]
+    MissingFunction(usize),
+}
+
+/// Side effects of a single function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionEffects {
+    /// Registers that may hold a different value when the function returns.
+    pub registers: BTreeSet<String>,
+
+    /// Addresses of the bytes of memory the function may write.
+    pub memory: BTreeSet<u64>,
+}
+
+impl FunctionEffects {
+    /// Returns true if the function has no side effects.
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.memory.is_empty()
+    }
+
+    /// Adds all side effects of `other`.
+    pub fn merge(&mut self, other: &FunctionEffects) {
+        self.registers.extend(other.registers.iter().cloned());
+        self.memory.extend(other.memory.iter().copied());
+    }
+
+    /// Returns the side effects that are not covered by `other`.
+    pub fn uncovered_by(&self, other: &FunctionEffects) -> FunctionEffects {
+        FunctionEffects {
+            registers: self
+                .registers
+                .difference(&other.registers)
+                .cloned()
+                .collect(),
+            memory: self.memory.difference(&other.memory).copied().collect(),
+        }
+    }
+
+    /// Returns the written memory as ranges of start address and length.
+    pub fn memory_ranges(&self) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = vec![];
+        for address in &self.memory {
+            match ranges.last_mut() {
+                Some((start, len)) if *start + *len == *address => *len += 1,
+                _ => ranges.push((*address, 1)),
+            }
+        }
+        ranges
+    }
+}
+
+/// A call that has not returned yet on the current path.
+#[derive(Debug, Clone)]
+pub struct EffectFrame {
+    /// Name of the called function.
+    pub function: String,
+
+    /// Address execution continues at when the function returns.
+    pub return_address: u64,
+
+    /// Registers when the function was called.
+    pub registers: HashMap<String, DExpr>,
+
+    /// Addresses of the bytes written since the function was called.
+    pub memory: BTreeSet<u64>,
+}
+
+/// Side effects of all functions, accumulated over all explored paths.
+///
+/// The knowledge base is saved in a line based text format using [`Display`]
+/// and loaded with [`SideEffects::parse`]:
+///
+/// ```text
+/// fn foo
+/// reg R0
+/// mem 0x20000000 4
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SideEffects {
+    functions: BTreeMap<String, FunctionEffects>,
+}
+
+impl SideEffects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record side effects of `function`.
+    pub fn record(&mut self, function: &str, effects: &FunctionEffects) {
+        self.functions
+            .entry(function.to_owned())
+            .or_default()
+            .merge(effects);
+    }
+
+    /// Get the side effects recorded for `function`.
+    pub fn get(&self, function: &str) -> Option<&FunctionEffects> {
+        self.functions.get(function)
+    }
+
+    /// Returns all functions and their side effects, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FunctionEffects)> {
+        self.functions
+            .iter()
+            .map(|(name, effects)| (name.as_str(), effects))
+    }
+
+    /// Adds all side effects recorded in `other`.
+    pub fn merge(&mut self, other: &SideEffects) {
+        for (function, effects) in other.iter() {
+            self.record(function, effects);
+        }
+    }
+
+    /// Parses a knowledge base saved with [`Display`].
+    pub fn parse(input: &str) -> Result<Self, SideEffectsError> {
+        let mut ret = Self::new();
+        let mut current: Option<&mut FunctionEffects> = None;
+        for (idx, line) in input.lines().enumerate() {
+            let line_number = idx + 1;
+            let mut words = line.split_whitespace();
+            let (kind, args) = match words.next() {
+                Some(kind) => (kind, words.collect::<Vec<_>>()),
+                None => continue,
+            };
+
+            match (kind, args.as_slice()) {
+                ("fn", [name]) => {
+                    current = Some(ret.functions.entry((*name).to_owned()).or_default());
+                }
+                ("reg", [register]) => match current.as_mut() {
+                    Some(effects) => {
+                        effects.registers.insert((*register).to_owned());
+                    }
+                    None => return Err(SideEffectsError::MissingFunction(line_number)),
+                },
+                ("mem", [start, len]) => {
+                    let start = start
+                        .strip_prefix("0x")
+                        .and_then(|start| u64::from_str_radix(start, 16).ok());
+                    let len = len.parse::<u64>().ok();
+                    let (start, len) = match (start, len) {
+                        (Some(start), Some(len)) => (start, len),
+                        _ => return Err(SideEffectsError::MalformedLine(line_number)),
+                    };
+                    match current.as_mut() {
+                        Some(effects) => effects.memory.extend(start..start + len),
+                        None => return Err(SideEffectsError::MissingFunction(line_number)),
+                    }
+                }
+                _ => return Err(SideEffectsError::MalformedLine(line_number)),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+impl Display for SideEffects {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (function, effects) in self.iter() {
+            writeln!(f, "fn {function}")?;
+            for register in &effects.registers {
+                writeln!(f, "reg {register}")?;
+            }
+            for (start, len) in effects.memory_ranges() {
+                writeln!(f, "mem {start:#x} {len}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FunctionEffects, SideEffects};
+
+    #[test]
+    fn test_side_effects_round_trip() {
+        let mut effects = FunctionEffects::default();
+        effects.registers.insert("R0".to_owned());
+        effects
+            .memory
+            .extend([0x2000_0000, 0x2000_0001, 0x2000_0004]);
+        let mut side_effects = SideEffects::new();
+        side_effects.record("foo", &effects);
+        side_effects.record("bar", &FunctionEffects::default());
+
+        let text = side_effects.to_string();
+        assert_eq!(
+            text,
+            "fn bar\nfn foo\nreg R0\nmem 0x20000000 2\nmem 0x20000004 1\n"
+        );
+        assert_eq!(SideEffects::parse(&text).unwrap(), side_effects);
+    }
+
+    #[test]
+    fn test_uncovered_effects() {
+        let mut real = FunctionEffects::default();
+        real.registers.extend(["R0".to_owned(), "R1".to_owned()]);
+        real.memory.extend([0x100, 0x101]);
+        let mut stub = FunctionEffects::default();
+        stub.registers.insert("R0".to_owned());
+        stub.memory.insert(0x100);
+
+        let uncovered = real.uncovered_by(&stub);
+        assert_eq!(uncovered.registers.into_iter().collect::<Vec<_>>(), vec![
+            "R1"
+        ]);
+        assert_eq!(uncovered.memory.into_iter().collect::<Vec<_>>(), vec![
+            0x101
+        ]);
+        assert!(stub.uncovered_by(&real).is_empty());
+    }
+}
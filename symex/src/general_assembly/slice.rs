@@ -0,0 +1,181 @@
+//! Backward data-flow slices of failing paths.
+//!
+//! When enabled, the registers, flags and memory bytes each instruction reads
+//! and writes are recorded on the path. When the path fails, the slice starts
+//! at the last instruction that read a flag, which is the conditional branch
+//! that led to the failure, and follows the recorded writes backwards to the
+//! instructions that computed the values the branch depends on. Locations that
+//! are read in the slice but never written on the path are the inputs that
+//! influenced the failure.
+
+use std::{collections::HashSet, fmt::Display};
+
+/// A location an instruction can read or write.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Location {
+    /// A register.
+    Register(String),
+
+    /// A flag.
+    Flag(String),
+
+    /// A byte of memory.
+    Memory(u64),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Register(register) => write!(f, "{register}"),
+            Self::Flag(flag) => write!(f, "flag {flag}"),
+            Self::Memory(address) => write!(f, "mem {address:#X}"),
+        }
+    }
+}
+
+/// The locations a single executed instruction read and wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFlowStep {
+    /// Address of the instruction.
+    pub pc: u64,
+
+    /// Index of the instruction on the path.
+    pub instruction: usize,
+
+    /// Locations read by the instruction.
+    pub uses: HashSet<Location>,
+
+    /// Locations written by the instruction.
+    pub defs: HashSet<Location>,
+}
+
+/// Data flow of the instructions executed on a path.
+#[derive(Debug, Clone, Default)]
+pub struct DataFlowLog {
+    steps: Vec<DataFlowStep>,
+    current: Option<DataFlowStep>,
+}
+
+impl DataFlowLog {
+    /// Starts recording the instruction at `pc`.
+    pub fn begin(&mut self, pc: u64, instruction: usize) {
+        self.end();
+        self.current = Some(DataFlowStep {
+            pc,
+            instruction,
+            uses: HashSet::new(),
+            defs: HashSet::new(),
+        });
+    }
+
+    /// Stops recording the current instruction.
+    pub fn end(&mut self) {
+        if let Some(step) = self.current.take() {
+            self.steps.push(step);
+        }
+    }
+
+    /// Returns true if an instruction is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Records that the current instruction read `location`.
+    pub fn read(&mut self, location: Location) {
+        if let Some(step) = &mut self.current {
+            step.uses.insert(location);
+        }
+    }
+
+    /// Records that the current instruction wrote `location`.
+    pub fn write(&mut self, location: Location) {
+        if let Some(step) = &mut self.current {
+            step.defs.insert(location);
+        }
+    }
+
+    /// Get the recorded instructions in execution order.
+    pub fn steps(&self) -> impl Iterator<Item = &DataFlowStep> {
+        self.steps.iter().chain(self.current.iter())
+    }
+
+    /// Computes the backward slice from the last instruction that read a flag.
+    ///
+    /// Returns `None` if no instruction on the path read a flag.
+    pub fn slice(&self) -> Option<Slice> {
+        let steps: Vec<&DataFlowStep> = self.steps().collect();
+        let criterion = steps.iter().rposition(|step| {
+            step.uses
+                .iter()
+                .any(|location| matches!(location, Location::Flag(_)))
+        })?;
+
+        let mut live: HashSet<Location> = steps[criterion].uses.clone();
+        let mut instructions = vec![steps[criterion]];
+        for step in steps[..criterion].iter().rev() {
+            if step.defs.is_disjoint(&live) {
+                continue;
+            }
+            for location in &step.defs {
+                live.remove(location);
+            }
+            live.extend(step.uses.iter().cloned());
+            instructions.push(step);
+        }
+        instructions.reverse();
+
+        let mut inputs: Vec<Location> = live.into_iter().collect();
+        inputs.sort();
+        Some(Slice {
+            instructions: instructions
+                .into_iter()
+                .map(|step| (step.instruction, step.pc))
+                .collect(),
+            inputs,
+        })
+    }
+}
+
+/// Backward slice of a failing path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slice {
+    /// Index and address of the instructions in the slice, in execution order.
+    pub instructions: Vec<(usize, u64)>,
+
+    /// Locations that influenced the failure and were not written on the path.
+    pub inputs: Vec<Location>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DataFlowLog, Location};
+
+    #[test]
+    fn test_backward_slice() {
+        let register = |name: &str| Location::Register(name.to_owned());
+        let mut log = DataFlowLog::default();
+
+        // ldr r1, [r0]
+        log.begin(0x10, 1);
+        log.read(register("R0"));
+        log.read(Location::Memory(0x2000_0000));
+        log.write(register("R1"));
+        // movs r2, #1
+        log.begin(0x12, 2);
+        log.write(register("R2"));
+        // cmp r1, #3
+        log.begin(0x14, 3);
+        log.read(register("R1"));
+        log.write(Location::Flag("Z".to_owned()));
+        // beq panic
+        log.begin(0x16, 4);
+        log.read(Location::Flag("Z".to_owned()));
+
+        let slice = log.slice().unwrap();
+        assert_eq!(slice.instructions, vec![(1, 0x10), (3, 0x14), (4, 0x16)]);
+        assert_eq!(slice.inputs, vec![
+            register("R0"),
+            Location::Memory(0x2000_0000)
+        ]);
+    }
+}
@@ -0,0 +1,316 @@
+//! Names of the locals stored on the stack.
+//!
+//! Each followed call pushes an activation of the called function together
+//! with its canonical frame address, the value of the stack pointer at the
+//! call. The function the path starts in is the outermost activation, with the
+//! initial stack pointer as its canonical frame address. When a path writes to
+//! the stack the written bytes are looked up in the locals of the innermost
+//! activation, using the frame layout from the debug data (see
+//! [`stack_frame`](super::project::stack_frame)), and the written locals are
+//! remembered by address. Memory deltas and the symbols of reads from
+//! uninitialized memory are then named after the local, as `app::parse::len`,
+//! instead of after the address.
+//!
+//! The locations are evaluated with the registers and memory of the path when
+//! the local is accessed. Before the function has set up its frame a frame
+//! pointer may still hold the frame base of the caller, so only bytes between
+//! the stack pointer and the canonical frame address of the activation are
+//! named.
+
+use serde::Serialize;
+
+use super::{
+    arch::Arch,
+    project::dwarf_expression::{ExpressionContext, LocationList},
+    state::GAState,
+};
+use crate::memory::SmtMap;
+
+/// A call that has not returned yet on the current path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackFrame {
+    /// Start address of the called function.
+    pub function: u64,
+
+    /// Address execution continues at when the function returns.
+    pub return_address: u64,
+
+    /// The canonical frame address, the value of the stack pointer at the
+    /// call.
+    pub cfa: u64,
+}
+
+/// A local of a function activation that is stored on the stack, or the part
+/// of it that is stored at one address if the local is split into pieces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StackLocal {
+    /// Name of the local qualified by its function, as `app::parse::len`.
+    pub name: String,
+
+    /// Address of the first byte of the local.
+    pub address: u64,
+
+    /// Size of the local in bytes, zero if the size is not known.
+    pub size: u64,
+
+    /// Offset of the first byte into the local, non zero for the later parts
+    /// of a local that is split into pieces.
+    pub offset: u64,
+}
+
+impl StackLocal {
+    /// Checks if `address` is inside of the local, a local of unknown size
+    /// only contains its first byte.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.address && address - self.address < self.size.max(1)
+    }
+}
+
+/// The values of a path that the location expressions of an activation read.
+struct ActivationContext<'a, A: Arch> {
+    state: &'a GAState<A>,
+    frame: StackFrame,
+    pc: u64,
+
+    /// Location of the frame base, `None` while evaluating the frame base.
+    frame_base: Option<&'a LocationList>,
+
+    /// The frame base, evaluated on first use.
+    frame_base_value: Option<Option<u64>>,
+}
+
+impl<A: Arch> ExpressionContext for ActivationContext<'_, A> {
+    fn register(&mut self, register: u16) -> Option<u64> {
+        let register = self.state.architecture.dwarf_register(register)?;
+        self.state.registers.get(register)?.get_constant()
+    }
+
+    fn memory(&mut self, address: u64, size: u8) -> Option<u64> {
+        let address = self
+            .state
+            .ctx
+            .from_u64(address, self.state.project.get_ptr_size());
+        let value = self.state.memory.read(&address, size as u32 * 8).ok()?;
+        value.simplify().get_constant()
+    }
+
+    fn frame_base(&mut self) -> Option<u64> {
+        if self.frame_base_value.is_none() {
+            let expression = self.frame_base?.at(self.pc)?;
+            let mut context = ActivationContext {
+                frame_base: None,
+                frame_base_value: None,
+                ..*self
+            };
+            self.frame_base_value = Some(expression.evaluate_value(&mut context).ok());
+        }
+        self.frame_base_value?
+    }
+
+    fn cfa(&mut self) -> Option<u64> {
+        Some(self.frame.cfa)
+    }
+}
+
+impl<A: Arch> GAState<A> {
+    /// Get the innermost activation, which is the function the path started
+    /// in if no call is active.
+    pub fn current_stack_frame(&self) -> StackFrame {
+        self.stack_frames.last().copied().unwrap_or(StackFrame {
+            function: self.entry & !0b1,
+            return_address: 0,
+            cfa: self.inital_sp,
+        })
+    }
+
+    /// Get the local of the innermost activation that contains `address`, and
+    /// the offset of `address` into it.
+    pub fn stack_local_at(&self, address: u64) -> Option<(StackLocal, u64)> {
+        let frame = self.current_stack_frame();
+        let sp = self.registers.get("SP")?.get_constant()?;
+        if !(sp..frame.cfa).contains(&address) {
+            return None;
+        }
+
+        let layout = self.project.get_function_frame(frame.function)?;
+        let pc = self.last_pc & !0b1;
+        let mut context = ActivationContext {
+            state: self,
+            frame,
+            pc,
+            frame_base: Some(&layout.frame_base),
+            frame_base_value: None,
+        };
+        // Scopes are listed before the scopes nested in them, the innermost
+        // local that contains the address is used.
+        let (variable, (start, offset, size)) =
+            layout.variables.iter().rev().find_map(|variable| {
+                let pieces = variable.location.at(pc)?.evaluate(&mut context).ok()?;
+                let part = variable
+                    .memory_parts(&pieces)
+                    .into_iter()
+                    .find(|(start, _, size)| address.wrapping_sub(*start) < (*size).max(1))?;
+                Some((variable, part))
+            })?;
+        let function = self
+            .function_name(frame.function)
+            .unwrap_or_else(|| layout.function.clone());
+        let local = StackLocal {
+            name: format!("{function}::{}", variable.name),
+            address: start,
+            size,
+            offset,
+        };
+        Some((local, address - start))
+    }
+
+    /// Remembers the locals of the innermost activation that are written by a
+    /// write of `bytes` bytes to `address`.
+    pub fn name_stack_locals(&mut self, address: u64, bytes: u64) {
+        let mut byte = address;
+        while byte < address + bytes {
+            match self.stack_local_at(byte) {
+                Some((local, offset)) => {
+                    byte += local.size.max(1) - offset;
+                    self.stack_locals.insert(local.address, local);
+                }
+                None => byte += 1,
+            }
+        }
+    }
+
+    /// Get the name of the local that contains `address`, either a local that
+    /// was written or a local of the innermost activation.
+    pub fn stack_local_name(&self, address: u64) -> Option<String> {
+        let written = self
+            .stack_locals
+            .range(..=address)
+            .next_back()
+            .map(|(_, local)| local)
+            .filter(|local| local.contains(address))
+            .map(|local| (local.clone(), address - local.address));
+        let (local, offset) = written.or_else(|| self.stack_local_at(address))?;
+        Some(match local.offset + offset {
+            0 => local.name,
+            offset => format!("{}+{offset:#X}", local.name),
+        })
+    }
+
+    /// Get the written locals that start in the `len` bytes from `address`.
+    pub fn stack_locals_in(&self, address: u64, len: u64) -> Vec<StackLocal> {
+        self.stack_locals
+            .range(address..address + len)
+            .map(|(_, local)| local.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use gimli::{Encoding, Format, RunTimeEndian};
+
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        project::{
+            dwarf_expression::{DwarfExpression, LocationList},
+            stack_frame::{FunctionFrame, StackVariable},
+            Project,
+        },
+        state::GAState,
+    };
+
+    fn location(bytecode: &[u8]) -> LocationList {
+        let encoding = Encoding {
+            address_size: 4,
+            format: Format::Dwarf32,
+            version: 5,
+        };
+        LocationList::single(DwarfExpression::new(
+            bytecode.to_vec(),
+            encoding,
+            RunTimeEndian::Little,
+        ))
+    }
+
+    #[test]
+    fn test_stack_locals() {
+        let mut project = Project::<ArmV6M>::test_project();
+        project.add_symbol("_ZN3app4main17h0123456789abcdefE", 0x100, 0x40);
+        project.add_symbol("_ZN3app5parse17h0123456789abcdefE", 0x200, 0x40);
+        project.add_function_frame(0x100, FunctionFrame {
+            function: "main".to_owned(),
+            // DW_OP_call_frame_cfa
+            frame_base: location(&[0x9c]),
+            variables: vec![StackVariable {
+                name: "buffer".to_owned(),
+                // DW_OP_fbreg -8
+                location: location(&[0x91, 0x78]),
+                size: 8,
+            }],
+        });
+        project.add_function_frame(0x200, FunctionFrame {
+            function: "parse".to_owned(),
+            // DW_OP_breg7 4
+            frame_base: location(&[0x77, 0x04]),
+            variables: vec![StackVariable {
+                name: "len".to_owned(),
+                // DW_OP_fbreg 0
+                location: location(&[0x91, 0x00]),
+                size: 4,
+            }],
+        });
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+
+        // The locals of the function the path started in.
+        state
+            .set_register("SP".to_owned(), context.from_u64(0x2000_0ff0, 32))
+            .unwrap();
+        state.name_stack_locals(0x2000_0ff8, 4);
+        assert_eq!(
+            state.stack_local_name(0x2000_0ffa).as_deref(),
+            Some("app::main::buffer+0x2")
+        );
+
+        // A call to `parse`, whose frame base is R7 + 4.
+        state
+            .set_register("PC".to_owned(), context.from_u64(0x200, 32))
+            .unwrap();
+        state.push_stack_frame(0x110);
+        state
+            .set_register("SP".to_owned(), context.from_u64(0x2000_0fe0, 32))
+            .unwrap();
+        state
+            .set_register("R7".to_owned(), context.from_u64(0x2000_0fe4, 32))
+            .unwrap();
+        assert_eq!(
+            state.stack_local_name(0x2000_0fe8).as_deref(),
+            Some("app::parse::len")
+        );
+        assert_eq!(state.stack_local_name(0x2000_0fe0), None);
+        state.name_stack_locals(0x2000_0fe6, 4);
+
+        // Written locals keep their names after the call returns.
+        state
+            .set_register("PC".to_owned(), context.from_u64(0x110, 32))
+            .unwrap();
+        state.pop_returned_stack_frame();
+        assert!(state.stack_frames.is_empty());
+        assert_eq!(
+            state.stack_local_name(0x2000_0fe9).as_deref(),
+            Some("app::parse::len+0x1")
+        );
+        let locals: Vec<_> = state
+            .stack_locals_in(0x2000_0fe0, 0x20)
+            .into_iter()
+            .map(|local| (local.name, local.address, local.size))
+            .collect();
+        assert_eq!(locals, vec![
+            ("app::parse::len".to_owned(), 0x2000_0fe8, 4),
+            ("app::main::buffer".to_owned(), 0x2000_0ff8, 8),
+        ]);
+    }
+}
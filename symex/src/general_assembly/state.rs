@@ -1,22 +1,132 @@
 //! Holds the state in general assembly execution.
 
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
 
 use general_assembly::{condition::Condition, operand::DataWord};
-use tracing::{debug, trace};
+use serde::Serialize;
+use tracing::{debug, trace, warn};
 
-use super::{arch::Arch, instruction::Instruction, project::Project};
+use super::{
+    acceleration::{LoopAcceleration, LoopFill},
+    arch::{Arch, ExecutionState},
+    branch_predictor::PredictorState,
+    cache::Cache,
+    copies::CopyDetector,
+    instruction::Instruction,
+    interrupts::InterruptState,
+    mmio::MmioAccess,
+    path_data::PathData,
+    peripheral_model::PeripheralStates,
+    project::Project,
+    reentrancy::ReentrancyState,
+    side_effects::EffectFrame,
+    slice::{DataFlowLog, Location},
+    stack_locals::{StackFrame, StackLocal},
+    summaries::SummaryRecorder,
+    temporal::PropertyMonitor,
+    time_travel::Timeline,
+};
 use crate::{
-    elf_util::{ExpressionType, Variable},
+    elf_util::{ConcreteInput, ExpressionType, MemoryDelta, Variable},
     general_assembly::{
-        project::{PCHook, ProjectError},
+        project::{FilterAction, HookHandle, PCHook, ProjectError},
         GAError,
         Result,
+        UnknownRegisterPolicy,
     },
-    memory::ArrayMemory,
+    memory::{Memory, SmtMap},
     smt::{DContext, DExpr, DSolver},
 };
 
+/// A recorded write to a register, see
+/// [`RunConfig::recorded_registers`](super::RunConfig::recorded_registers).
+#[derive(Debug, Clone)]
+pub struct RegisterWrite {
+    /// Name of the register.
+    pub register: String,
+
+    /// The written value.
+    pub value: DExpr,
+
+    /// Address of the instruction that wrote the register.
+    pub pc: u64,
+
+    /// Index of the instruction that wrote the register on the path.
+    pub instruction: usize,
+}
+
+/// A call to a function that was filtered out of the analysis, see
+/// [`FunctionFilter`](super::project::FunctionFilter).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilteredCall {
+    /// Name of the function, if known.
+    pub function: Option<String>,
+
+    /// How the call was handled.
+    pub action: FilterAction,
+
+    /// Address the call returns to.
+    pub return_address: Option<u64>,
+}
+
+/// Where a symbol entered the program.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum SymbolOrigin {
+    /// Created by a call to `symbolic` in the analyzed program.
+    Intrinsic,
+
+    /// Initial value of a register that was read before it was written.
+    RegisterInit,
+
+    /// Value of a read from a memory mapped peripheral at the address.
+    MemoryRead(u64),
+
+    /// Input declared in a [`Harness`](crate::harness::Harness).
+    Input,
+
+    /// Return value or other changed register or flag of a call that was
+    /// stubbed.
+    StubReturn,
+
+    /// Havoced value of a loop, a memory region or an unpredictable
+    /// instruction.
+    Havoc,
+
+    /// Value created by a hook that models a function.
+    Hook,
+}
+
+impl std::fmt::Display for SymbolOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Intrinsic => write!(f, "symbolic intrinsic"),
+            Self::RegisterInit => write!(f, "uninitialized register"),
+            Self::MemoryRead(address) => write!(f, "read of {address:#X}"),
+            Self::Input => write!(f, "harness input"),
+            Self::StubReturn => write!(f, "result of stubbed call"),
+            Self::Havoc => write!(f, "havoc"),
+            Self::Hook => write!(f, "hook"),
+        }
+    }
+}
+
+/// The origin of a symbol created on a path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SymbolProvenance {
+    /// Name of the symbol.
+    pub name: String,
+
+    /// Where the symbol entered the program.
+    pub origin: SymbolOrigin,
+
+    /// Address of the last instruction executed before the symbol was
+    /// created.
+    pub pc: u64,
+}
+
 pub enum HookOrInstruction<'a, A: Arch> {
     PcHook(&'a PCHook<A>),
     Instruction(Instruction<A>),
@@ -29,13 +139,64 @@ pub struct ContinueInsideInstruction<A: Arch> {
     pub local: HashMap<String, DExpr>,
 }
 
+/// A loop with an invariant in its inductive step on a path, see
+/// [`LoopInvariant`](super::project::LoopInvariant).
+#[derive(Clone, Debug)]
+pub struct LoopHavoc {
+    /// Registers after the havoc.
+    pub registers: HashMap<String, DExpr>,
+    /// Set if memory outside of the havoced ranges was written since the
+    /// havoc.
+    pub wrote_other_memory: bool,
+}
+
+/// The function, return address, registers and written bytes of an
+/// [`EffectFrame`].
+type EffectFrameValues = (String, u64, BTreeMap<String, DExpr>, BTreeSet<u64>);
+
+/// The values of a state that decide how its path continues, see
+/// [`GAState::values`]. Expressions are compared structurally, so distinct
+/// symbols are never equal even if they have the same name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StateValues {
+    pc: u64,
+    activations: Vec<usize>,
+    in_handler: bool,
+    peripherals: BTreeMap<usize, usize>,
+    registers: BTreeMap<String, DExpr>,
+    flags: BTreeMap<String, DExpr>,
+    memory: Vec<(u64, DExpr)>,
+    symbolic_writes: Vec<(DExpr, u32)>,
+    fills: Vec<LoopFill>,
+    execution_state: ExecutionState,
+    heap_break: Option<u64>,
+    call_stack: Vec<u64>,
+    trace_position: Option<usize>,
+    properties: PropertyMonitor,
+    watchdog_refresh: usize,
+    reentrancy: ReentrancyState,
+    loop_havocs: BTreeMap<u64, (BTreeMap<String, DExpr>, bool)>,
+    loop_iterations: BTreeMap<u64, usize>,
+    effect_frames: Vec<EffectFrameValues>,
+    summaries: SummaryRecorder,
+}
+
+/// The values of a state together with its path constraints and cycle count,
+/// see [`GAState::key`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StateKey {
+    values: StateValues,
+    constraints: Vec<DExpr>,
+    cycle_count: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct GAState<A: Arch> {
-    pub project: &'static Project<A>,
-    pub ctx: &'static DContext,
+    pub project: Arc<Project<A>>,
+    pub ctx: DContext,
     pub constraints: DSolver,
     pub marked_symbolic: Vec<Variable>,
-    pub memory: ArrayMemory,
+    pub memory: Memory,
     pub count_cycles: bool,
     pub cycle_count: usize,
     pub cycle_laps: Vec<(usize, String)>,
@@ -46,7 +207,100 @@ pub struct GAState<A: Arch> {
     pub current_instruction: Option<Instruction<A>>,
     pub architecture: A,
     pub inital_sp: u64,
+    /// Address of the function the path started in.
+    pub entry: u64,
+    pub reached_must_reach: bool,
+    /// Return addresses of the calls made on this path, only tracked when the
+    /// call depth is bounded.
+    pub call_stack: Vec<u64>,
+    /// Current program break used by the `_sbrk` model, `None` until the first
+    /// allocation.
+    pub heap_break: Option<u64>,
+    /// Position of the next address in the recorded trace, `None` if the
+    /// path does not follow the trace.
+    pub trace_position: Option<usize>,
+    /// Addresses of the bytes in backed regions that have been written on this
+    /// path and are read from the symbolic memory instead of the buffer.
+    pub backed_overlay: HashSet<u64>,
+    /// Calls that have not returned yet, only tracked when side effects are
+    /// recorded.
+    pub effect_frames: Vec<EffectFrame>,
+    /// Calls that have not returned yet, only tracked when locals on the stack
+    /// are named.
+    pub stack_frames: Vec<StackFrame>,
+    /// Locals on the stack written on this path, indexed by address.
+    pub stack_locals: BTreeMap<u64, StackLocal>,
+    /// Havocs of the loops with an invariant that are in their inductive step
+    /// on this path, indexed by loop header.
+    pub loop_havocs: HashMap<u64, LoopHavoc>,
+    /// Acceleration of the loops on this path, indexed by loop header.
+    pub loop_accelerations: HashMap<u64, LoopAcceleration<A>>,
+    /// Stores of the iterations skipped by the acceleration of loops on this
+    /// path, oldest first.
+    pub loop_fills: Vec<LoopFill>,
+    /// Number of jumps back to the header of each bounded loop on this path,
+    /// see [`ExecutionBudget`](super::budget::ExecutionBudget).
+    pub loop_iterations: HashMap<u64, usize>,
+    /// The latest writes to the recorded registers, oldest first.
+    pub register_history: VecDeque<RegisterWrite>,
+    /// Accesses to memory mapped peripherals on this path in program order,
+    /// see [`mmio`](super::mmio).
+    pub mmio_log: Vec<MmioAccess>,
+    /// States of the modeled peripherals on this path, see
+    /// [`peripheral_model`](super::peripheral_model).
+    pub peripherals: PeripheralStates,
+    /// Progress of the temporal properties on this path, see
+    /// [`temporal`](super::temporal).
+    pub properties: PropertyMonitor,
+    /// Cycle count at the latest refresh of the watchdog, see
+    /// [`watchdog`](super::watchdog).
+    pub watchdog_refresh: usize,
+    /// Energy used on this path in nJ, see [`energy`](super::energy).
+    pub energy: f64,
+    /// Contents of the instruction cache on this path, see
+    /// [`cache`](super::cache).
+    pub instruction_cache: Cache,
+    /// Contents of the data cache on this path, see [`cache`](super::cache).
+    pub data_cache: Cache,
+    /// History of the branch predictor on this path, see
+    /// [`branch_predictor`](super::branch_predictor).
+    pub branch_predictions: PredictorState,
+    /// Data flow of the executed instructions, only recorded when failing
+    /// paths are sliced.
+    pub data_flow: DataFlowLog,
+    /// Origins of the symbols created on this path, in creation order.
+    pub provenance: Vec<SymbolProvenance>,
+    /// Calls to filtered functions on this path, in call order.
+    pub filtered_calls: Vec<FilteredCall>,
+    /// Instructions on this path where not every value of a symbolic address
+    /// or branch target was followed, see
+    /// [`max_forks`](super::RunConfig::max_forks).
+    pub under_approximated: Vec<u64>,
+    /// Addresses this path transferred control to other than by falling
+    /// through to the next instruction, used to report the functions it
+    /// reached.
+    pub jump_targets: BTreeSet<u64>,
+    /// Interrupts that preempted this path, see
+    /// [`interrupts`](super::interrupts).
+    pub interrupts: InterruptState,
+    /// Activations of the non-reentrant functions on this path, see
+    /// [`reentrancy`](super::reentrancy).
+    pub reentrancy: ReentrancyState,
+    /// Calls of the summarized functions being recorded on this path, see
+    /// [`summaries`](super::summaries).
+    pub summaries: SummaryRecorder,
+    /// Iteration of a loop observed to detect copy loops on this path, see
+    /// [`copies`](super::copies).
+    pub copy_loops: CopyDetector,
+    /// Data attached to this path by hooks, reported with the result.
+    pub path_data: PathData,
+    /// Recording of this path for time travel, if enabled.
+    pub timeline: Option<Timeline<A>>,
     pc_register: u64, // this register is special
+    /// Symbolic value written to PC that has not been resolved yet, see
+    /// [`take_symbolic_pc`](Self::take_symbolic_pc).
+    symbolic_pc: Option<DExpr>,
+    execution_state: ExecutionState,
     flags: HashMap<String, DExpr>,
     instruction_counter: usize,
     has_jumped: bool,
@@ -56,17 +310,14 @@ pub struct GAState<A: Arch> {
 impl<A: Arch> GAState<A> {
     /// Create a new state.
     pub fn new(
-        ctx: &'static DContext,
-        project: &'static Project<A>,
+        ctx: &DContext,
+        project: Arc<Project<A>>,
         constraints: DSolver,
         function: &str,
         end_address: u64,
         architecture: A,
     ) -> Result<Self> {
-        let pc_reg = match project.get_symbol_address(function) {
-            Some(a) => a,
-            None => return Err(GAError::EntryFunctionNotFound(function.to_owned())),
-        };
+        let pc_reg = project.get_entry_address(function)?;
         debug!("Found function at addr: {:#X}.", pc_reg);
         let ptr_size = project.get_ptr_size();
 
@@ -78,7 +329,13 @@ impl<A: Arch> GAState<A> {
         }?;
         debug!("Found stack start at addr: {:#X}.", sp_reg);
 
-        let memory = ArrayMemory::new(ctx, ptr_size, project.get_endianness());
+        let memory = Memory::new(
+            project.get_memory_model(),
+            project.get_memory_map(),
+            ctx,
+            ptr_size,
+            project.get_endianness(),
+        );
         let mut registers = HashMap::new();
         let pc_expr = ctx.from_u64(pc_reg, ptr_size);
         registers.insert("PC".to_owned(), pc_expr);
@@ -97,8 +354,8 @@ impl<A: Arch> GAState<A> {
         flags.insert("V".to_owned(), ctx.unconstrained(1, "flags.V"));
 
         Ok(GAState {
-            project,
-            ctx,
+            project: project.clone(),
+            ctx: ctx.clone(),
             constraints,
             marked_symbolic: Vec::new(),
             memory,
@@ -106,6 +363,8 @@ impl<A: Arch> GAState<A> {
             cycle_laps: vec![],
             registers,
             pc_register: pc_reg,
+            symbolic_pc: None,
+            execution_state: ExecutionState::Thumb,
             flags,
             instruction_counter: 0,
             has_jumped: false,
@@ -117,9 +376,55 @@ impl<A: Arch> GAState<A> {
             instruction_conditions: VecDeque::new(),
             architecture,
             inital_sp: sp_reg,
+            entry: pc_reg,
+            reached_must_reach: false,
+            call_stack: vec![],
+            heap_break: None,
+            trace_position: match project.get_trace().is_empty() {
+                true => None,
+                false => Some(0),
+            },
+            backed_overlay: HashSet::new(),
+            effect_frames: vec![],
+            stack_frames: vec![],
+            stack_locals: BTreeMap::new(),
+            loop_havocs: HashMap::new(),
+            loop_accelerations: HashMap::new(),
+            loop_fills: vec![],
+            loop_iterations: HashMap::new(),
+            register_history: VecDeque::new(),
+            mmio_log: vec![],
+            peripherals: PeripheralStates::default(),
+            properties: PropertyMonitor::default(),
+            watchdog_refresh: 0,
+            energy: 0.0,
+            instruction_cache: Cache::default(),
+            data_cache: Cache::default(),
+            branch_predictions: PredictorState::default(),
+            data_flow: DataFlowLog::default(),
+            provenance: Vec::new(),
+            filtered_calls: Vec::new(),
+            under_approximated: Vec::new(),
+            jump_targets: BTreeSet::new(),
+            interrupts: InterruptState::default(),
+            reentrancy: ReentrancyState::default(),
+            summaries: SummaryRecorder::default(),
+            copy_loops: CopyDetector::default(),
+            path_data: PathData::new(),
+            timeline: project.get_time_travel().then(Timeline::new),
         })
     }
 
+    /// Get the instruction set state that the next instruction is executed in.
+    pub fn get_execution_state(&self) -> ExecutionState {
+        self.execution_state
+    }
+
+    /// Sets the instruction set state, written by interworking branches.
+    pub fn set_execution_state(&mut self, execution_state: ExecutionState) {
+        self.execution_state = execution_state;
+    }
+
     pub fn reset_has_jumped(&mut self) {
         self.has_jumped = false;
     }
@@ -139,6 +444,82 @@ impl<A: Arch> GAState<A> {
         self.instruction_counter += 1;
     }
 
+    /// Adds `count` instructions that were not executed one by one, such as
+    /// the instructions of a summarized call.
+    pub(crate) fn skip_instructions(&mut self, count: usize) {
+        self.instruction_counter += count;
+    }
+
+    /// The parts of the state that decide how the path continues, its values,
+    /// path constraints and cycle count. Paths with equal keys explore the
+    /// same states from here on.
+    pub fn key(&self) -> StateKey {
+        StateKey {
+            values: self.values(),
+            constraints: self.constraints.asserted(),
+            cycle_count: self.cycle_count,
+        }
+    }
+
+    /// The program counter, the interrupts, the states of the peripherals, the
+    /// registers and flags, the written memory and the per path state of the
+    /// models and monitors, the state without its path constraints and timing.
+    pub fn values(&self) -> StateValues {
+        let ptr_size = self.project.get_ptr_size();
+        let memory = self
+            .memory
+            .written_addresses()
+            .iter()
+            .filter_map(|address| {
+                let value = self
+                    .memory
+                    .read(&self.ctx.from_u64(*address, ptr_size), 8)
+                    .ok()?;
+                Some((*address, value))
+            })
+            .collect();
+        StateValues {
+            pc: self.pc_register,
+            activations: self.interrupts.activations().to_vec(),
+            in_handler: self.interrupts.in_handler(),
+            peripherals: self.peripherals.states(),
+            registers: self.registers.clone().into_iter().collect(),
+            flags: self.flags.clone().into_iter().collect(),
+            memory,
+            symbolic_writes: self.memory.symbolic_writes().to_vec(),
+            fills: self.loop_fills.clone(),
+            execution_state: self.execution_state,
+            heap_break: self.heap_break,
+            call_stack: self.call_stack.clone(),
+            trace_position: self.trace_position,
+            properties: self.properties.clone(),
+            watchdog_refresh: self.watchdog_refresh,
+            reentrancy: self.reentrancy.clone(),
+            loop_havocs: self
+                .loop_havocs
+                .iter()
+                .map(|(header, havoc)| {
+                    let registers = havoc.registers.clone().into_iter().collect();
+                    (*header, (registers, havoc.wrote_other_memory))
+                })
+                .collect(),
+            loop_iterations: self.loop_iterations.clone().into_iter().collect(),
+            effect_frames: self
+                .effect_frames
+                .iter()
+                .map(|frame| {
+                    (
+                        frame.function.clone(),
+                        frame.return_address,
+                        frame.registers.clone().into_iter().collect(),
+                        frame.memory.clone(),
+                    )
+                })
+                .collect(),
+            summaries: self.summaries.clone(),
+        }
+    }
+
     /// Gets the current instruction count
     pub fn get_instruction_count(&self) -> usize {
         self.instruction_counter
@@ -177,6 +558,122 @@ impl<A: Arch> GAState<A> {
         self.cycle_count += cycles;
     }
 
+    /// Pop the innermost call if execution has returned from it.
+    pub fn pop_returned_call(&mut self) {
+        let pc = self.pc_register & !0b1;
+        if self.call_stack.last() == Some(&pc) {
+            self.call_stack.pop();
+        }
+    }
+
+    /// Get the name of the function at the program counter, the address is
+    /// used for functions without a symbol.
+    pub fn get_current_function_name(&mut self) -> Result<Option<String>> {
+        let pc = match self.get_register("PC".to_owned())?.get_constant() {
+            Some(pc) => pc & !0b1,
+            None => return Ok(None),
+        };
+        Ok(Some(match self.project.get_symbol_name(pc) {
+            Some((name, 0)) => name.to_owned(),
+            _ => format!("{pc:#x}"),
+        }))
+    }
+
+    /// Pop the innermost call frame if execution has returned from it.
+    pub fn pop_returned_effect_frame(&mut self) -> Option<EffectFrame> {
+        let pc = self.pc_register & !0b1;
+        match self.effect_frames.last() {
+            Some(frame) if frame.return_address == pc => self.effect_frames.pop(),
+            _ => None,
+        }
+    }
+
+    /// Starts an activation of the function that was just called, if the
+    /// stack pointer is concrete.
+    pub fn push_stack_frame(&mut self, return_address: u64) {
+        let function = self.pc_register & !0b1;
+        let cfa = match self.registers.get("SP").and_then(|sp| sp.get_constant()) {
+            Some(sp) => sp,
+            None => return,
+        };
+        self.stack_frames.push(StackFrame {
+            function,
+            return_address,
+            cfa,
+        });
+    }
+
+    /// Pop the innermost activation if execution has returned from it.
+    pub fn pop_returned_stack_frame(&mut self) {
+        let pc = self.pc_register & !0b1;
+        if self
+            .stack_frames
+            .last()
+            .is_some_and(|frame| frame.return_address == pc)
+        {
+            self.stack_frames.pop();
+        }
+    }
+
+    /// Advance the position in the recorded trace if the current program
+    /// counter is the next address in it.
+    ///
+    /// The trace may only hold the targets of taken branches, so a program
+    /// counter that is not in the trace is only an error if the path jumped
+    /// to it, in which case [`GAError::TraceDiverged`] is returned.
+    pub fn advance_trace(&mut self) -> Result<()> {
+        let Some(position) = self.trace_position else {
+            return Ok(());
+        };
+
+        let trace = self.project.get_trace();
+        let pc = self.pc_register & !0b1;
+        match trace.get(position) {
+            Some(&expected) if expected == pc => {
+                if position + 1 == trace.len() {
+                    debug!("Reached the end of the trace at {:#X}", pc);
+                    self.trace_position = None;
+                } else {
+                    self.trace_position = Some(position + 1);
+                }
+            }
+            Some(&expected) if self.has_jumped => {
+                return Err(GAError::TraceDiverged {
+                    expected,
+                    found: pc,
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Get the next address in the recorded trace if the path follows it.
+    pub fn get_next_trace_address(&self) -> Option<u64> {
+        self.trace_position
+            .and_then(|position| self.project.get_trace().get(position).copied())
+    }
+
+    /// Record a cycle lap for every configured region that starts or ends at
+    /// the current program counter.
+    pub fn record_region_laps(&mut self) {
+        if !self.count_cycles {
+            return;
+        }
+
+        let pc = self.pc_register & !0b1;
+        for region in self.project.get_region_delimiters(pc) {
+            trace!(
+                "Region {} lap at {:#x}: {}",
+                region.name,
+                pc,
+                self.cycle_count
+            );
+            self.cycle_laps
+                .push((self.cycle_count, region.name.clone()));
+        }
+    }
+
     /// Update the last instruction that was executed.
     pub fn set_last_instruction(&mut self, instruction: Instruction<A>) {
         self.last_instruction = Some(instruction);
@@ -197,8 +694,8 @@ impl<A: Arch> GAState<A> {
 
     /// Create a state used for testing.
     pub fn create_test_state(
-        project: &'static Project<A>,
-        ctx: &'static DContext,
+        project: Arc<Project<A>>,
+        ctx: &DContext,
         constraints: DSolver,
         start_pc: u64,
         start_stack: u64,
@@ -210,7 +707,13 @@ impl<A: Arch> GAState<A> {
         let sp_reg = start_stack;
         debug!("Found stack start at addr: {:#X}.", sp_reg);
 
-        let memory = ArrayMemory::new(ctx, ptr_size, project.get_endianness());
+        let memory = Memory::new(
+            project.get_memory_model(),
+            project.get_memory_map(),
+            ctx,
+            ptr_size,
+            project.get_endianness(),
+        );
         let mut registers = HashMap::new();
         let pc_expr = ctx.from_u64(pc_reg, ptr_size);
         registers.insert("PC".to_owned(), pc_expr);
@@ -225,8 +728,8 @@ impl<A: Arch> GAState<A> {
         flags.insert("V".to_owned(), ctx.unconstrained(1, "flags.V"));
 
         GAState {
-            project,
-            ctx,
+            project: project.clone(),
+            ctx: ctx.clone(),
             constraints,
             marked_symbolic: Vec::new(),
             memory,
@@ -234,8 +737,43 @@ impl<A: Arch> GAState<A> {
             cycle_laps: vec![],
             registers,
             pc_register: pc_reg,
+            symbolic_pc: None,
+            execution_state: ExecutionState::Thumb,
             flags,
-            inital_sp: start_pc,
+            inital_sp: sp_reg,
+            entry: start_pc,
+            reached_must_reach: false,
+            call_stack: vec![],
+            heap_break: None,
+            trace_position: None,
+            backed_overlay: HashSet::new(),
+            effect_frames: vec![],
+            stack_frames: vec![],
+            stack_locals: BTreeMap::new(),
+            loop_havocs: HashMap::new(),
+            loop_accelerations: HashMap::new(),
+            loop_fills: vec![],
+            loop_iterations: HashMap::new(),
+            register_history: VecDeque::new(),
+            mmio_log: vec![],
+            peripherals: PeripheralStates::default(),
+            properties: PropertyMonitor::default(),
+            watchdog_refresh: 0,
+            energy: 0.0,
+            instruction_cache: Cache::default(),
+            data_cache: Cache::default(),
+            branch_predictions: PredictorState::default(),
+            data_flow: DataFlowLog::default(),
+            provenance: Vec::new(),
+            filtered_calls: Vec::new(),
+            under_approximated: Vec::new(),
+            jump_targets: BTreeSet::new(),
+            interrupts: InterruptState::default(),
+            reentrancy: ReentrancyState::default(),
+            summaries: SummaryRecorder::default(),
+            copy_loops: CopyDetector::default(),
+            path_data: PathData::new(),
+            timeline: project.get_time_travel().then(Timeline::new),
             instruction_counter: 0,
             has_jumped: false,
             last_instruction: None,
@@ -248,40 +786,64 @@ impl<A: Arch> GAState<A> {
         }
     }
 
+    /// Create a state used for testing with a solver context of its own.
+    #[cfg(test)]
+    pub(crate) fn test_state(
+        project: Arc<Project<A>>,
+        start_pc: u64,
+        start_stack: u64,
+        architecture: A,
+    ) -> Self {
+        let ctx = DContext::new();
+        let constraints = DSolver::new(&ctx);
+        Self::create_test_state(
+            project,
+            &ctx,
+            constraints,
+            start_pc,
+            start_stack,
+            architecture,
+        )
+    }
+
     /// Set a value to a register.
     pub fn set_register(&mut self, register: String, expr: DExpr) -> Result<()> {
-        // crude solution should prbobly change
+        let architecture = self.architecture.clone();
+        let register = architecture.register_alias(self, register);
+        let expr = architecture.write_register(self, &register, expr)?;
         if register == "PC" {
-            let value = match expr.get_constant() {
-                Some(v) => {
-                    // assert!(v % 4 == 0);
-                    v
+            match expr.get_constant() {
+                Some(value) => {
+                    self.pc_register = value;
+                    self.symbolic_pc = None;
                 }
                 None => {
-                    trace!("not a concrete pc try to generate possible values");
-                    let values: Vec<u64> = match self.constraints.get_values(&expr, 500).unwrap() {
-                        crate::smt::Solutions::Exactly(v) => v
-                            .iter()
-                            .map(|n| match n.get_constant() {
-                                Some(v) => v,
-                                None => todo!("e"),
-                            })
-                            .collect(),
-                        crate::smt::Solutions::AtLeast(_v) => todo!("Handle with lower bound, this should likely be done using a sub sample of the signal"),
-                    };
-                    trace!("{} possible PC values", values.len());
-                    for v in values {
-                        trace!("Possible PC: {:#X}", v);
-                    }
-
-                    todo!("handle symbolic branch")
+                    trace!("PC set to the symbolic value {:?}", expr);
+                    self.symbolic_pc = Some(expr.clone());
                 }
-            };
-            self.pc_register = value;
+            }
+        }
+
+        if self.project.get_recorded_registers().contains(&register) {
+            self.record_register_write(&register, &expr);
+        }
+        if self.data_flow.is_recording() && register != "PC" {
+            self.data_flow.write(Location::Register(register.clone()));
+        }
+        if self.summaries.is_recording() {
+            self.summaries.write(Location::Register(register.clone()));
+        }
+        if self.copy_loops.is_observing() && register != "PC" {
+            self.copy_loops
+                .write(Location::Register(register.clone()), &expr);
         }
 
         match self.project.get_register_write_hook(&register) {
-            Some(hook) => hook(self, expr),
+            Some(hook) => {
+                self.summaries.invalidate();
+                let handle = || HookHandle::RegisterWrite(register.clone());
+                self.run_hook(handle, |state| hook(state, expr))
+            }
             None => {
                 self.registers.insert(register, expr);
                 Ok(())
@@ -289,88 +851,293 @@ impl<A: Arch> GAState<A> {
         }
     }
 
+    /// Creates a fresh symbol of `bits` bits and records its origin.
+    ///
+    /// A symbol larger than the
+    /// [`max_symbol_size`](super::RunConfig::max_symbol_size) is only symbolic
+    /// in its low bytes, the other bits are zero.
+    pub fn new_symbol(&mut self, bits: u32, name: &str, origin: SymbolOrigin) -> DExpr {
+        self.provenance.push(SymbolProvenance {
+            name: name.to_owned(),
+            origin,
+            pc: self.last_pc,
+        });
+        let symbol_bits = match self.project.get_max_symbol_size() {
+            Some(bytes) if bits as u64 > bytes.max(1) * 8 => {
+                let bytes = bytes.max(1);
+                warn!(
+                    "Bounding the symbol {} of {} bytes at {:#X} to {} bytes, assuming the other bytes are zero",
+                    name,
+                    bits.div_ceil(8),
+                    self.last_pc,
+                    bytes
+                );
+                bytes as u32 * 8
+            }
+            _ => bits,
+        };
+        let ctx = &self.ctx;
+        let symbol = match &mut self.timeline {
+            Some(timeline) => timeline.symbol(|| ctx.unconstrained(symbol_bits, name)),
+            None => ctx.unconstrained(symbol_bits, name),
+        };
+        match symbol_bits == bits {
+            true => symbol,
+            false => symbol.resize_unsigned(bits),
+        }
+    }
+
+    /// Adds a write to the register history, dropping the oldest write if the
+    /// history is full.
+    fn record_register_write(&mut self, register: &str, value: &DExpr) {
+        let limit = self.project.get_register_history_limit();
+        if limit == 0 {
+            return;
+        }
+        if self.register_history.len() >= limit {
+            self.register_history.pop_front();
+        }
+        self.register_history.push_back(RegisterWrite {
+            register: register.to_owned(),
+            value: value.clone(),
+            pc: self.last_pc,
+            instruction: self.instruction_counter,
+        });
+    }
+
+    /// Name of the register argument `n`, counted from zero, is passed in.
+    ///
+    /// Falls back to the AAPCS registers for architectures that do not describe
+    /// their calling convention.
+    pub fn argument_register(&self, n: usize) -> String {
+        match self
+            .architecture
+            .argument_registers()
+            .and_then(|registers| registers.get(n))
+        {
+            Some(register) => (*register).to_owned(),
+            None => format!("R{n}"),
+        }
+    }
+
+    /// Name of the register a function returns a word sized value in, see
+    /// [`argument_register`](Self::argument_register).
+    pub fn return_register(&self) -> String {
+        match self
+            .architecture
+            .return_registers()
+            .and_then(|registers| registers.first())
+        {
+            Some(register) => (*register).to_owned(),
+            None => "R0".to_owned(),
+        }
+    }
+
+    /// Takes the symbolic value written to PC since it was last concrete, such
+    /// as a function pointer returned by a hook. The executor forks a path for
+    /// every possible value before the next instruction is fetched.
+    pub fn take_symbolic_pc(&mut self) -> Option<DExpr> {
+        self.symbolic_pc.take()
+    }
+
     /// Get the value stored at a register.
     pub fn get_register(&mut self, register: String) -> Result<DExpr> {
+        let register = self.architecture.clone().register_alias(self, register);
+        if self.data_flow.is_recording() && register != "PC" {
+            self.data_flow.read(Location::Register(register.clone()));
+        }
+        if self.copy_loops.is_observing() && register != "PC" {
+            self.copy_loops.read(Location::Register(register.clone()));
+        }
         // check register hooks
-        match self.project.get_register_read_hook(&register) {
+        let value = match self.project.get_register_read_hook(&register) {
             // run hook if found
-            Some(hook) => Ok(hook(self)?),
+            Some(hook) => {
+                self.summaries.invalidate();
+                self.run_hook(|| HookHandle::RegisterRead(register.clone()), hook)?
+            }
             // if no hook found read like normal
             None => match self.registers.get(&register) {
-                Some(v) => Ok(v.to_owned()),
+                Some(v) => v.to_owned(),
                 None => {
+                    let known = self
+                        .architecture
+                        .registers()
+                        .is_some_and(|registers| registers.contains(&register.as_str()));
+                    if !known {
+                        match self.project.get_unknown_register_policy() {
+                            UnknownRegisterPolicy::Create => {}
+                            UnknownRegisterPolicy::Warn => warn!(
+                                "Creating an unconstrained value for unknown register {} at {:#X}",
+                                register, self.last_pc
+                            ),
+                            UnknownRegisterPolicy::Error => {
+                                return Err(GAError::UnknownRegister(register))
+                            }
+                        }
+                    }
+
                     // If register do not exist yet create it with unconstrained value.
-                    let value = self
-                        .ctx
-                        .unconstrained(self.project.get_word_size(), &register);
+                    let name = self.register_symbol_name(&register);
+                    let value = self.new_symbol(
+                        self.project.get_word_size(),
+                        &name,
+                        SymbolOrigin::RegisterInit,
+                    );
                     self.marked_symbolic.push(Variable {
-                        name: Some(register.to_owned()),
+                        name: Some(name),
                         value: value.clone(),
                         ty: ExpressionType::Integer(self.project.get_word_size() as usize),
                     });
                     self.registers.insert(register.to_owned(), value.to_owned());
-                    Ok(value)
+                    value
                 }
             },
+        };
+        if self.summaries.is_recording() && register != "PC" {
+            self.summaries
+                .read(&Location::Register(register), Some(value.clone()));
         }
+        Ok(value)
+    }
+
+    /// Get the value of the symbol marked as symbolic with the name `name`.
+    pub fn get_symbol(&self, name: &str) -> Option<DExpr> {
+        self.marked_symbolic
+            .iter()
+            .find(|variable| variable.name.as_deref() == Some(name))
+            .map(|variable| variable.value.clone())
+    }
+
+    /// Solves for concrete values of the variables marked as symbolic that
+    /// satisfy the current path condition, in the order they were marked.
+    pub fn concrete_inputs(&self) -> Result<Vec<ConcreteInput>> {
+        let values: Vec<DExpr> = self
+            .marked_symbolic
+            .iter()
+            .map(|variable| variable.value.clone())
+            .collect();
+        let model = self.constraints.get_model(&values)?;
+        Ok(self
+            .marked_symbolic
+            .iter()
+            .zip(model)
+            .map(|(variable, value)| {
+                ConcreteInput::new(variable.name.clone(), variable.ty.clone(), &value)
+            })
+            .collect())
     }
 
     /// Set the value of a flag.
-    pub fn set_flag(&mut self, flag: String, expr: DExpr) {
+    pub fn set_flag(&mut self, flag: String, expr: DExpr) -> Result<()> {
+        self.check_flag(&flag)?;
         let expr = expr.simplify().simplify();
         trace!("flag {} set to {:?}", flag, expr);
+        if self.data_flow.is_recording() {
+            self.data_flow.write(Location::Flag(flag.clone()));
+        }
+        if self.summaries.is_recording() {
+            self.summaries.write(Location::Flag(flag.clone()));
+        }
+        if self.copy_loops.is_observing() {
+            self.copy_loops.write(Location::Flag(flag.clone()), &expr);
+        }
         self.flags.insert(flag, expr);
+        Ok(())
     }
 
     /// Get the value of a flag.
-    pub fn get_flag(&mut self, flag: String) -> Option<DExpr> {
-        match self.flags.get(&flag) {
-            Some(v) => Some(v.to_owned()),
-            None => todo!(),
+    ///
+    /// A flag that has not been set yet is unconstrained.
+    pub fn get_flag(&mut self, flag: String) -> Result<DExpr> {
+        self.check_flag(&flag)?;
+        if self.data_flow.is_recording() {
+            self.data_flow.read(Location::Flag(flag.clone()));
+        }
+        if self.copy_loops.is_observing() {
+            self.copy_loops.read(Location::Flag(flag.clone()));
+        }
+        let value = match self.flags.get(&flag) {
+            Some(v) => v.to_owned(),
+            None => {
+                let value =
+                    self.new_symbol(1, &format!("flags.{flag}"), SymbolOrigin::RegisterInit);
+                self.flags.insert(flag.clone(), value.clone());
+                value
+            }
+        };
+        if self.summaries.is_recording() {
+            self.summaries
+                .read(&Location::Flag(flag), Some(value.clone()));
+        }
+        Ok(value)
+    }
+
+    /// Get the values of the flags that have been set.
+    pub(crate) fn get_flags(&self) -> &HashMap<String, DExpr> {
+        &self.flags
+    }
+
+    /// Takes all flags out of the state, used to store snapshots compactly.
+    pub(crate) fn take_flags(&mut self) -> HashMap<String, DExpr> {
+        std::mem::take(&mut self.flags)
+    }
+
+    /// Replaces all flags of the state.
+    pub(crate) fn set_flags(&mut self, flags: HashMap<String, DExpr>) {
+        self.flags = flags;
+    }
+
+    /// Checks that the architecture defines `flag`, architectures that do not
+    /// declare their flags accept any flag.
+    fn check_flag(&self, flag: &str) -> Result<()> {
+        match self.architecture.flags() {
+            Some(flags) if !flags.contains(&flag) => Err(GAError::UnknownFlag(flag.to_owned())),
+            _ => Ok(()),
         }
     }
 
     /// Get the expression for a condition based on the current flag values.
     pub fn get_expr(&mut self, condition: &Condition) -> Result<DExpr> {
         Ok(match condition {
-            Condition::EQ => self.get_flag("Z".to_owned()).unwrap(),
-            Condition::NE => self.get_flag("Z".to_owned()).unwrap().not(),
-            Condition::CS => self.get_flag("C".to_owned()).unwrap(),
-            Condition::CC => self.get_flag("C".to_owned()).unwrap().not(),
-            Condition::MI => self.get_flag("N".to_owned()).unwrap(),
-            Condition::PL => self.get_flag("N".to_owned()).unwrap().not(),
-            Condition::VS => self.get_flag("V".to_owned()).unwrap(),
-            Condition::VC => self.get_flag("V".to_owned()).unwrap().not(),
+            Condition::EQ => self.get_flag("Z".to_owned())?,
+            Condition::NE => self.get_flag("Z".to_owned())?.not(),
+            Condition::CS => self.get_flag("C".to_owned())?,
+            Condition::CC => self.get_flag("C".to_owned())?.not(),
+            Condition::MI => self.get_flag("N".to_owned())?,
+            Condition::PL => self.get_flag("N".to_owned())?.not(),
+            Condition::VS => self.get_flag("V".to_owned())?,
+            Condition::VC => self.get_flag("V".to_owned())?.not(),
             Condition::HI => {
-                let c = self.get_flag("C".to_owned()).unwrap();
-                let z = self.get_flag("Z".to_owned()).unwrap().not();
+                let c = self.get_flag("C".to_owned())?;
+                let z = self.get_flag("Z".to_owned())?.not();
                 c.and(&z)
             }
             Condition::LS => {
-                let c = self.get_flag("C".to_owned()).unwrap().not();
-                let z = self.get_flag("Z".to_owned()).unwrap();
+                let c = self.get_flag("C".to_owned())?.not();
+                let z = self.get_flag("Z".to_owned())?;
                 c.or(&z)
             }
             Condition::GE => {
-                let n = self.get_flag("N".to_owned()).unwrap();
-                let v = self.get_flag("V".to_owned()).unwrap();
+                let n = self.get_flag("N".to_owned())?;
+                let v = self.get_flag("V".to_owned())?;
                 n.xor(&v).not()
             }
             Condition::LT => {
-                let n = self.get_flag("N".to_owned()).unwrap();
-                let v = self.get_flag("V".to_owned()).unwrap();
+                let n = self.get_flag("N".to_owned())?;
+                let v = self.get_flag("V".to_owned())?;
                 n.ne(&v)
             }
             Condition::GT => {
-                let z = self.get_flag("Z".to_owned()).unwrap();
-                let n = self.get_flag("N".to_owned()).unwrap();
-                let v = self.get_flag("V".to_owned()).unwrap();
+                let z = self.get_flag("Z".to_owned())?;
+                let n = self.get_flag("N".to_owned())?;
+                let v = self.get_flag("V".to_owned())?;
                 z.not().and(&n.eq(&v))
             }
             Condition::LE => {
-                let z = self.get_flag("Z".to_owned()).unwrap();
-                let n = self.get_flag("N".to_owned()).unwrap();
-                let v = self.get_flag("V".to_owned()).unwrap();
+                let z = self.get_flag("Z".to_owned())?;
+                let n = self.get_flag("N".to_owned())?;
+                let v = self.get_flag("V".to_owned())?;
                 z.and(&n.ne(&v))
             }
             Condition::None => self.ctx.from_bool(true),
@@ -380,8 +1147,19 @@ impl<A: Arch> GAState<A> {
     /// Get the next instruction based on the address in the PC register.
     pub fn get_next_instruction(&self) -> Result<HookOrInstruction<'_, A>> {
         let pc = self.pc_register & !(0b1); // Not applicable for all architectures TODO: Fix this.;
-        match self.project.get_pc_hook(pc) {
+        let call_site_hook = self
+            .registers
+            .get("LR")
+            .and_then(|lr| lr.get_constant())
+            .and_then(|lr| self.project.get_call_site_hook(pc, lr));
+        match call_site_hook.or_else(|| self.project.get_pc_hook(pc)) {
             Some(hook) => Ok(HookOrInstruction::PcHook(hook)),
+            None if !self
+                .architecture
+                .supports_execution_state(self.execution_state) =>
+            {
+                Err(GAError::UnsupportedExecutionState(self.execution_state, pc))
+            }
             None => Ok(HookOrInstruction::Instruction(
                 self.project.get_instruction(pc, self)?,
             )),
@@ -435,12 +1213,91 @@ impl<A: Arch> GAState<A> {
         }
     }
 
+    /// Extracts all memory written during execution as contiguous regions with
+    /// concrete values from the current model.
+    ///
+    /// Writes through symbolic addresses are resolved using the same model.
+    pub fn memory_delta(&self) -> Result<Vec<MemoryDelta>> {
+        let (regions, resolved) = self.written_regions()?;
+        let (addresses, values): (Vec<u64>, Vec<DExpr>) = regions.into_iter().unzip();
+        self.constraints.push();
+        self.constraints.assert(&resolved);
+        let values = self.constraints.get_model(&values);
+        self.constraints.pop();
+        let values = values?;
+        Ok(addresses
+            .into_iter()
+            .zip(values.iter())
+            .map(|(address, value)| MemoryDelta::from_value(address, value).with_locals(self))
+            .collect())
+    }
+
+    /// Returns the start address and the symbolic value of every contiguous
+    /// region of memory written during execution.
+    ///
+    /// The value holds the byte at the lowest address as its most significant
+    /// byte so that a region can be solved for as a single value.
+    ///
+    /// Writes through symbolic addresses are placed at addresses from one
+    /// model. The returned condition holds if the writes were to those
+    /// addresses, the values must be solved for under it to be from a model
+    /// where the regions were written.
+    pub fn written_regions(&self) -> Result<(Vec<(u64, DExpr)>, DExpr)> {
+        let mut touched = self.memory.written_addresses().clone();
+        let (symbolic, sizes): (Vec<DExpr>, Vec<u32>) =
+            self.memory.symbolic_writes().iter().cloned().unzip();
+        let addresses = self.constraints.get_model(&symbolic)?;
+        let mut resolved = self.ctx.from_bool(true);
+        for ((address, symbolic), bytes) in addresses.iter().zip(&symbolic).zip(sizes) {
+            resolved = resolved.and(&symbolic.eq(address));
+            let address = match address.get_constant() {
+                Some(address) => address,
+                None => continue,
+            };
+            touched.extend((0..bytes as u64).map(|n| address + n));
+        }
+
+        let ptr_size = self.project.get_ptr_size();
+        let mut ret = vec![];
+        let mut touched = touched.into_iter().peekable();
+        while let Some(start) = touched.next() {
+            // Collect the full run of consecutive bytes.
+            let mut end = start;
+            while touched.next_if_eq(&(end + 1)).is_some() {
+                end += 1;
+            }
+
+            // Concatenate the run with the lowest address as the most significant byte so
+            // that the entire region can be solved for in a single query.
+            let mut value: Option<DExpr> = None;
+            for address in start..=end {
+                let byte = self.memory.read(&self.ctx.from_u64(address, ptr_size), 8)?;
+                value = Some(match value {
+                    Some(value) => value.concat(&byte),
+                    None => byte,
+                });
+            }
+            if let Some(value) = value {
+                ret.push((start, value));
+            }
+        }
+
+        Ok((ret, resolved))
+    }
+
     pub fn instruction_from_array_ptr(
         &self,
         data: &[u8],
     ) -> crate::general_assembly::project::Result<Instruction<A>> {
-        self.architecture
-            .translate(data, self)
-            .map_err(|el| el.into())
+        let instruction = self
+            .architecture
+            .translate(data, self.get_in_conditional_block())?;
+
+        // Catch translator bugs before they reach the solver.
+        #[cfg(debug_assertions)]
+        general_assembly::verify::verify(&instruction.operations)
+            .map_err(super::arch::ArchError::InvalidTranslation)?;
+
+        Ok(instruction)
     }
 }
@@ -0,0 +1,600 @@
+//! Summaries of the effect of called functions.
+//!
+//! Small helpers that are called in hot paths, such as a checksum step or a
+//! ring buffer index wrap, are explored again at every call on every path.
+//! The first time a call of a [`summarized`](super::RunConfig::summarized)
+//! function returns, the locations the call read before writing them and the
+//! values it left in the locations it wrote are stored as a summary, together
+//! with the cycles and instructions the call took. A later call that reads the
+//! same values, on the same path or any other path, applies the writes of the
+//! summary and returns immediately instead of executing the function again.
+//!
+//! The values a call leaves are kept as expressions over the values it read,
+//! which may be symbolic. Summaries are not parameterized over their inputs,
+//! as the solver can not substitute the inputs of one call by those of
+//! another, so a summary only replaces a call that reads the same values,
+//! concrete values or the same symbolic expressions. Only calls that read no
+//! memory that has not been written, add no constraint or symbol and access
+//! no hook, peripheral or memory mapped register are summarized, so the values
+//! a call leaves only depend on the values it read and a summary reproduces
+//! the call exactly. The return address is
+//! not part of a summary, a summarized function must only use it to return.
+//! Bytes written below the stack pointer the call returns with are the dead
+//! frame of the call and are not restored. The instructions of a summarized
+//! call are not seen by the per instruction analyses, such as the hotspot
+//! profile or the data flow of a slice. Summaries are not used with modeled
+//! caches or branch predictors, whose state changes the cycles of a call, or
+//! with interrupts, as a summarized call can not be preempted.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use tracing::{debug, trace};
+
+use super::{arch::Arch, executor::GAExecutor, instruction::Instruction, slice::Location, Result};
+use crate::{memory::SmtMap, smt::DExpr};
+
+/// Maximum number of summaries kept of each function, calls that do not match
+/// any of them are executed.
+const MAX_SUMMARIES: usize = 64;
+
+/// A call of a summarized function that has not returned.
+#[derive(Debug, Clone)]
+struct SummaryFrame {
+    /// Index of the function.
+    function: usize,
+
+    /// Address the call returns to.
+    return_address: u64,
+
+    /// Value of the link register at the call.
+    lr: u64,
+
+    /// Stack pointer the function was entered with.
+    sp: u64,
+
+    /// Cycle count at the call.
+    cycles: usize,
+
+    /// Instruction count at the call.
+    instructions: usize,
+
+    /// Energy used at the call.
+    energy: f64,
+
+    /// Number of constraints at the call.
+    constraints: usize,
+
+    /// Number of symbols at the call.
+    symbols: usize,
+
+    /// Values of the locations read before they were written.
+    inputs: BTreeMap<Location, DExpr>,
+
+    /// Locations written by the call.
+    written: BTreeSet<Location>,
+
+    /// If the call can still be summarized.
+    valid: bool,
+}
+
+impl SummaryFrame {
+    /// The fields of the frame with the energy as bits, which unlike a float
+    /// can be hashed.
+    #[allow(clippy::type_complexity)]
+    fn fields(
+        &self,
+    ) -> (
+        usize,
+        u64,
+        u64,
+        u64,
+        usize,
+        usize,
+        u64,
+        usize,
+        usize,
+        &BTreeMap<Location, DExpr>,
+        &BTreeSet<Location>,
+        bool,
+    ) {
+        (
+            self.function,
+            self.return_address,
+            self.lr,
+            self.sp,
+            self.cycles,
+            self.instructions,
+            self.energy.to_bits(),
+            self.constraints,
+            self.symbols,
+            &self.inputs,
+            &self.written,
+            self.valid,
+        )
+    }
+}
+
+impl PartialEq for SummaryFrame {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields() == other.fields()
+    }
+}
+
+impl Eq for SummaryFrame {}
+
+impl Hash for SummaryFrame {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fields().hash(state);
+    }
+}
+
+/// Calls of summarized functions being recorded on a path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SummaryRecorder {
+    /// Calls that have not returned, innermost last.
+    frames: Vec<SummaryFrame>,
+}
+
+impl SummaryRecorder {
+    /// Checks if a call is being recorded.
+    pub fn is_recording(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    /// Records a read of `location`, `value` is `None` if it depends on memory
+    /// that has not been written.
+    pub(crate) fn read(&mut self, location: &Location, value: Option<DExpr>) {
+        for frame in &mut self.frames {
+            if frame.written.contains(location) {
+                continue;
+            }
+            // The link register holds the return address until it is written.
+            if *location == Location::Register("LR".to_owned()) {
+                continue;
+            }
+            match &value {
+                Some(value) => {
+                    frame
+                        .inputs
+                        .entry(location.clone())
+                        .or_insert_with(|| value.clone());
+                }
+                None => frame.valid = false,
+            }
+        }
+    }
+
+    /// Records a write of `location`.
+    pub(crate) fn write(&mut self, location: Location) {
+        for frame in &mut self.frames {
+            frame.written.insert(location.clone());
+        }
+    }
+
+    /// Marks the calls being recorded as not possible to summarize.
+    pub(crate) fn invalidate(&mut self) {
+        for frame in &mut self.frames {
+            frame.valid = false;
+        }
+    }
+}
+
+/// The effect of a call of a function.
+#[derive(Debug, Clone)]
+pub struct FunctionSummary<A: Arch> {
+    /// Values of the locations the call read before writing them.
+    inputs: Vec<(Location, DExpr)>,
+
+    /// Values the call left in the registers and flags it wrote.
+    registers: Vec<(Location, DExpr)>,
+
+    /// Values the call left in the bytes it wrote.
+    memory: Vec<(u64, DExpr)>,
+
+    /// Number of cycles of the call, without the call and return
+    /// instructions.
+    cycles: usize,
+
+    /// Number of instructions of the call.
+    instructions: usize,
+
+    /// Energy used by the call.
+    energy: f64,
+
+    /// The instruction that returned, its cycles are counted when execution
+    /// continues at the return address.
+    last_instruction: Option<Instruction<A>>,
+}
+
+/// Summaries of the calls of the summarized functions on any path.
+#[derive(Debug, Clone)]
+pub struct SummaryCache<A: Arch> {
+    /// Summaries by the index of the function.
+    summaries: HashMap<usize, Vec<FunctionSummary<A>>>,
+
+    /// Number of calls that were replaced by a summary.
+    pub hits: usize,
+}
+
+impl<A: Arch> SummaryCache<A> {
+    /// Creates a cache without any summaries.
+    pub fn new() -> Self {
+        Self {
+            summaries: HashMap::new(),
+            hits: 0,
+        }
+    }
+
+    /// Number of summaries of all functions.
+    pub fn len(&self) -> usize {
+        self.summaries.values().map(Vec::len).sum()
+    }
+
+    /// Checks if no call has been summarized.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<A: Arch> Default for SummaryCache<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Arch> GAExecutor<'_, A> {
+    /// Checks if calls may be summarized in this run.
+    fn summaries_enabled(&self) -> bool {
+        !self.project.get_summarized().is_empty()
+            && self.project.get_interrupts().is_empty()
+            && self.project.get_instruction_cache().is_none()
+            && self.project.get_data_cache().is_none()
+            && self.project.get_branch_predictor().is_none()
+    }
+
+    /// Records the calls of the summarized functions before the next
+    /// instruction. Returns true if the next instruction is the entry of a
+    /// call that was replaced by its summary, execution then continues at the
+    /// return address.
+    pub(super) fn apply_summaries(&mut self) -> Result<bool> {
+        if !self.summaries_enabled() {
+            return Ok(false);
+        }
+        let Some(pc) = self.state.get_register("PC".to_owned())?.get_constant() else {
+            return Ok(false);
+        };
+        let pc = pc & !0b1;
+        let sp = self.state.registers.get("SP").and_then(DExpr::get_constant);
+        while let Some(frame) = self.state.summaries.frames.last() {
+            let returned = frame.return_address == pc && sp.is_some_and(|sp| sp >= frame.sp);
+            if !returned {
+                break;
+            }
+            let frame = self.state.summaries.frames.pop().expect("the frame exists");
+            self.record_summary(frame)?;
+        }
+
+        // A hook may do anything, a call that runs one is not summarized.
+        let lr = self.state.registers.get("LR").and_then(DExpr::get_constant);
+        let hooked = self.project.get_pc_hook(pc).is_some()
+            || lr.is_some_and(|lr| self.project.get_call_site_hook(pc, lr).is_some());
+        if hooked {
+            self.state.summaries.invalidate();
+            return Ok(false);
+        }
+
+        let functions = self.project.get_summarized();
+        let Some(function) = functions.iter().position(|function| {
+            self.project
+                .get_symbol_address(function)
+                .is_some_and(|address| address & !0b1 == pc)
+        }) else {
+            return Ok(false);
+        };
+        let (Some(lr), Some(sp)) = (lr, sp) else {
+            return Ok(false);
+        };
+        // Count the call instruction now so that a summary does not depend on
+        // the call site.
+        self.state.increment_cycle_count();
+        self.state.last_instruction = None;
+
+        let summary =
+            self.vm
+                .summaries
+                .summaries
+                .get(&function)
+                .and_then(|summaries| {
+                    summaries
+                        .iter()
+                        .find(|summary| {
+                            summary.inputs.iter().all(|(location, value)| {
+                                self.peek(location).as_ref() == Some(value)
+                            })
+                        })
+                        .cloned()
+                });
+        if let Some(summary) = summary {
+            debug!("Applying a summary of {} at {:#X}", functions[function], pc);
+            self.apply_summary(summary, lr)?;
+            self.vm.summaries.hits += 1;
+            return Ok(true);
+        }
+
+        trace!("Recording a summary of {}", functions[function]);
+        let frame = SummaryFrame {
+            function,
+            return_address: lr & !0b1,
+            lr,
+            sp,
+            cycles: self.state.cycle_count,
+            instructions: self.state.get_instruction_count(),
+            energy: self.state.energy,
+            constraints: self.state.constraints.constraint_count(),
+            symbols: self.state.provenance.len(),
+            inputs: BTreeMap::new(),
+            written: BTreeSet::new(),
+            valid: true,
+        };
+        self.state.summaries.frames.push(frame);
+        Ok(false)
+    }
+
+    /// Stores the summary of a call that returned, if it reproduces the call.
+    fn record_summary(&mut self, frame: SummaryFrame) -> Result<()> {
+        let valid = frame.valid
+            && frame.constraints == self.state.constraints.constraint_count()
+            && frame.symbols == self.state.provenance.len();
+        if !valid {
+            return Ok(());
+        }
+        let sp = match self.state.registers.get("SP").and_then(DExpr::get_constant) {
+            Some(sp) => sp,
+            None => return Ok(()),
+        };
+
+        let mut registers = vec![];
+        let mut memory = vec![];
+        for location in &frame.written {
+            let value = match location {
+                Location::Register(register) if register == "PC" => continue,
+                Location::Register(register) => self.state.registers.get(register).cloned(),
+                Location::Flag(flag) => self.state.get_flags().get(flag).cloned(),
+                // The frame of the call is dead once it returns.
+                Location::Memory(address) if *address < sp => continue,
+                Location::Memory(_) => self.peek(location),
+            };
+            let Some(value) = value else {
+                return Ok(());
+            };
+            let constant = value.get_constant();
+            match location {
+                Location::Register(register) if register == "LR" && constant == Some(frame.lr) => {}
+                // The return address must not escape the call.
+                _ if constant.is_some_and(|constant| constant & !0b1 == frame.return_address) => {
+                    return Ok(())
+                }
+                Location::Memory(address) => memory.push((*address, value)),
+                _ => registers.push((location.clone(), value)),
+            }
+        }
+
+        let summaries = self
+            .vm
+            .summaries
+            .summaries
+            .entry(frame.function)
+            .or_default();
+        if summaries.len() >= MAX_SUMMARIES {
+            return Ok(());
+        }
+        trace!(
+            "Summarized {} with {} inputs",
+            self.project.get_summarized()[frame.function],
+            frame.inputs.len()
+        );
+        summaries.push(FunctionSummary {
+            inputs: frame.inputs.into_iter().collect(),
+            registers,
+            memory,
+            cycles: self.state.cycle_count - frame.cycles,
+            instructions: self.state.get_instruction_count() - frame.instructions,
+            energy: self.state.energy - frame.energy,
+            last_instruction: self.state.get_last_instruction(),
+        });
+        Ok(())
+    }
+
+    /// Applies the effect of `summary` to the state and returns to the link
+    /// register `lr`.
+    fn apply_summary(&mut self, summary: FunctionSummary<A>, lr: u64) -> Result<()> {
+        for (location, value) in &summary.inputs {
+            self.state.summaries.read(location, Some(value.clone()));
+        }
+        for (location, value) in summary.registers {
+            match location {
+                Location::Register(register) => self.state.set_register(register, value)?,
+                Location::Flag(flag) => self.state.set_flag(flag, value)?,
+                Location::Memory(_) => unreachable!("memory is summarized by byte"),
+            }
+        }
+        let ptr_size = self.project.get_ptr_size();
+        for (address, value) in summary.memory {
+            if self.project.address_in_backed_region(address) {
+                self.state.backed_overlay.insert(address);
+            }
+            if let Some(frame) = self.state.effect_frames.last_mut() {
+                frame.memory.insert(address);
+            }
+            self.state.summaries.write(Location::Memory(address));
+            let address = self.state.ctx.from_u64(address, ptr_size);
+            self.state.memory.write(&address, value)?;
+        }
+
+        self.state.cycle_count += summary.cycles;
+        self.state.skip_instructions(summary.instructions);
+        self.state.energy += summary.energy;
+        self.state.last_instruction = summary.last_instruction;
+        let lr = self.state.ctx.from_u64(lr, ptr_size);
+        self.state.set_register("PC".to_owned(), lr)
+    }
+
+    /// Records a read of `bits` bits at `address` by the calls being recorded.
+    /// Reads of the static memory of the program are not part of a summary.
+    pub(super) fn record_summary_read(&mut self, address: u64, bits: u32) {
+        for address in address..address + (bits as u64).div_ceil(8) {
+            if self.project.address_in_range(address)
+                && !self.project.address_in_backed_region(address)
+            {
+                continue;
+            }
+            let location = Location::Memory(address);
+            let value = self.peek(&location);
+            self.state.summaries.read(&location, value);
+        }
+    }
+
+    /// Get the value of `location` without reading it, `None` if it has no
+    /// value or depends on the memory not written on this path.
+    pub(super) fn peek(&self, location: &Location) -> Option<DExpr> {
+        match location {
+            Location::Register(register) => self.state.registers.get(register).cloned(),
+            Location::Flag(flag) => self.state.get_flags().get(flag).cloned(),
+            Location::Memory(address) => {
+                let address = *address;
+                let backed = self.project.address_in_backed_region(address);
+                let byte = |byte: u8| self.state.ctx.from_u64(byte as u64, 8);
+                if backed && !self.state.backed_overlay.contains(&address) {
+                    return self.project.get_backed_byte(address).map(byte);
+                }
+                if self.project.address_in_range(address) && !backed {
+                    return self.project.get_byte(address).ok().map(byte);
+                }
+                if !self.state.memory.written_addresses().contains(&address) {
+                    return None;
+                }
+                let address = self
+                    .state
+                    .ctx
+                    .from_u64(address, self.project.get_ptr_size());
+                self.state.memory.read(&address, 8).ok()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    #[test]
+    fn test_summary() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("wrap".to_owned(), 0x101)]));
+        project.add_summarized("wrap");
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let set = |executor: &mut GAExecutor<'_, ArmV6M>, register: &str, value| {
+            let value = context.from_u64(value, 32);
+            executor
+                .state
+                .set_register(register.to_owned(), value)
+                .unwrap();
+        };
+        let get = |executor: &mut GAExecutor<'_, ArmV6M>, register: &str| {
+            let value = executor.state.get_register(register.to_owned()).unwrap();
+            value.get_constant().unwrap()
+        };
+
+        // The first call is executed and recorded.
+        set(&mut executor, "SP", 0x2000_1000);
+        set(&mut executor, "R0", 5);
+        set(&mut executor, "LR", 0x401);
+        set(&mut executor, "PC", 0x100);
+        assert!(!executor.apply_summaries().unwrap());
+        let r0 = get(&mut executor, "R0");
+        set(&mut executor, "R0", r0 + 1);
+        executor.state.cycle_count += 10;
+        set(&mut executor, "PC", 0x400);
+        assert!(!executor.apply_summaries().unwrap());
+        assert_eq!(executor.vm.summaries.len(), 1);
+
+        // A call from another call site with the same argument is replaced.
+        set(&mut executor, "R0", 5);
+        set(&mut executor, "LR", 0x411);
+        set(&mut executor, "PC", 0x100);
+        assert!(executor.apply_summaries().unwrap());
+        assert_eq!(get(&mut executor, "R0"), 6);
+        assert_eq!(get(&mut executor, "PC") & !0b1, 0x410);
+        assert_eq!(executor.state.cycle_count, 20);
+        assert_eq!(executor.vm.summaries.hits, 1);
+
+        // A call with another argument is executed.
+        set(&mut executor, "R0", 7);
+        set(&mut executor, "PC", 0x100);
+        assert!(!executor.apply_summaries().unwrap());
+        assert!(executor.state.summaries.is_recording());
+    }
+
+    #[test]
+    fn test_symbolic_summary() {
+        let mut project = Project::<ArmV6M>::test_project()
+            .with_symbols(HashMap::from([("wrap".to_owned(), 0x101)]));
+        project.add_summarized("wrap");
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0x100, 0x2000_1000, ArmV6M {});
+        let context = state.ctx.clone();
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let state = vm.paths.get_path().unwrap().state;
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let set = |executor: &mut GAExecutor<'_, ArmV6M>, register: &str, value| {
+            executor
+                .state
+                .set_register(register.to_owned(), value)
+                .unwrap();
+        };
+        let word = |value| context.from_u64(value, 32);
+        let argument = context.unconstrained(32, "argument");
+
+        // The call leaves a value that depends on its symbolic argument.
+        set(&mut executor, "SP", word(0x2000_1000));
+        set(&mut executor, "R0", argument.clone());
+        set(&mut executor, "LR", word(0x401));
+        set(&mut executor, "PC", word(0x100));
+        assert!(!executor.apply_summaries().unwrap());
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        let result = r0.add(&word(1));
+        set(&mut executor, "R0", result.clone());
+        set(&mut executor, "PC", word(0x400));
+        assert!(!executor.apply_summaries().unwrap());
+        assert_eq!(executor.vm.summaries.len(), 1);
+
+        // A call with the same argument is replaced.
+        set(&mut executor, "R0", argument);
+        set(&mut executor, "LR", word(0x411));
+        set(&mut executor, "PC", word(0x100));
+        assert!(executor.apply_summaries().unwrap());
+        let r0 = executor.state.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0, result);
+
+        // A call with another symbolic argument is executed.
+        set(&mut executor, "R0", context.unconstrained(32, "other"));
+        set(&mut executor, "PC", word(0x100));
+        assert!(!executor.apply_summaries().unwrap());
+    }
+}
@@ -0,0 +1,212 @@
+//! Temporal properties checked while the paths are explored.
+//!
+//! A [`TemporalProperty`] is a formula over the events of a single path, such
+//! as that the watchdog is refreshed before the path ends or that a
+//! peripheral is not used until it has been initialized. The properties in
+//! [`properties`](super::RunConfig::properties) are checked after every
+//! instruction and when the path ends, a path that violates a property fails
+//! with the name of the property.
+
+use super::{arch::Arch, project::Project, state::GAState, Result};
+use crate::smt::{DContext, DExpr};
+
+/// Condition on the value of a register, returns a boolean expression.
+pub type RegisterPredicate = fn(ctx: &DContext, value: &DExpr) -> DExpr;
+
+/// Something that happens at an instruction.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The instruction at the address is executed.
+    PcReached(u64),
+
+    /// The instruction writes to the byte at the address.
+    MemoryWritten(u64),
+
+    /// The predicate must be true for the value of the register after the
+    /// instruction.
+    Register(String, RegisterPredicate),
+
+    /// The event does not happen.
+    Not(Box<Event>),
+}
+
+/// When the events have to happen on a path.
+#[derive(Debug, Clone)]
+pub enum Formula {
+    /// The event happens at every instruction.
+    Always(Event),
+
+    /// The event happens at some instruction before the path ends.
+    Eventually(Event),
+
+    /// The first event happens at every instruction until the second event
+    /// happens, which has to happen before the path ends.
+    Until(Event, Event),
+}
+
+/// A named formula that every path has to satisfy.
+#[derive(Debug, Clone)]
+pub struct TemporalProperty {
+    /// Reported as the reason of the failure of a path that violates the
+    /// property.
+    pub name: &'static str,
+
+    pub formula: Formula,
+}
+
+impl TemporalProperty {
+    /// Creates a property that is reported as `name`.
+    pub fn new(name: &'static str, formula: Formula) -> Self {
+        Self { name, formula }
+    }
+}
+
+/// Progress of the properties on a path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PropertyMonitor {
+    /// Properties that are satisfied on the path, by index.
+    satisfied: Vec<bool>,
+
+    /// Bytes written by the current instruction.
+    writes: Vec<u64>,
+}
+
+impl PropertyMonitor {
+    /// Records that the current instruction writes `bits` at `address`.
+    pub(crate) fn record_write(&mut self, address: u64, bits: u32) {
+        self.writes
+            .extend(address..address + (bits as u64).div_ceil(8));
+    }
+
+    /// Checks the properties after the instruction at `pc` was executed,
+    /// returns the name of a violated property.
+    pub(crate) fn step<A: Arch>(state: &mut GAState<A>, pc: u64) -> Result<Option<&'static str>> {
+        let mut monitor = std::mem::take(&mut state.properties);
+        let violated = monitor.check(state, pc);
+        monitor.writes.clear();
+        state.properties = monitor;
+        violated
+    }
+
+    fn check<A: Arch>(&mut self, state: &mut GAState<A>, pc: u64) -> Result<Option<&'static str>> {
+        let project = state.project.clone();
+        let properties = project.get_properties();
+        self.satisfied.resize(properties.len(), false);
+        for (property, satisfied) in properties.iter().zip(&mut self.satisfied) {
+            if *satisfied {
+                continue;
+            }
+            let violated = match &property.formula {
+                Formula::Always(event) => !happens(event, state, pc, &self.writes)?,
+                Formula::Eventually(event) => {
+                    *satisfied = happens(event, state, pc, &self.writes)?;
+                    false
+                }
+                Formula::Until(hold, release) => {
+                    *satisfied = happens(release, state, pc, &self.writes)?;
+                    !*satisfied && !happens(hold, state, pc, &self.writes)?
+                }
+            };
+            if violated {
+                return Ok(Some(property.name));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the name of a property that requires an event which did not happen
+    /// before the path ended.
+    pub(crate) fn unsatisfied<A: Arch>(&self, project: &Project<A>) -> Option<&'static str> {
+        project
+            .get_properties()
+            .iter()
+            .enumerate()
+            .filter(|(_, property)| !matches!(property.formula, Formula::Always(_)))
+            .find(|(index, _)| !self.satisfied.get(*index).copied().unwrap_or(false))
+            .map(|(_, property)| property.name)
+    }
+}
+
+/// Checks if `event` happens at the instruction at `pc` that wrote `writes`.
+fn happens<A: Arch>(
+    event: &Event,
+    state: &mut GAState<A>,
+    pc: u64,
+    writes: &[u64],
+) -> Result<bool> {
+    Ok(match event {
+        Event::PcReached(address) => pc & !0b1 == address & !0b1,
+        Event::MemoryWritten(address) => writes.contains(address),
+        Event::Register(register, predicate) => {
+            let value = state.get_register(register.to_owned())?;
+            let condition = predicate(&state.ctx, &value);
+            !state.constraints.is_sat_with_constraint(&condition.not())?
+        }
+        Event::Not(event) => !happens(event, state, pc, writes)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{Event, Formula, PropertyMonitor, TemporalProperty};
+    use crate::general_assembly::{arch::arm::v6::ArmV6M, project::Project, state::GAState};
+
+    const CR: u64 = 0x4000_0000;
+    const DR: u64 = 0x4000_0004;
+
+    #[test]
+    fn test_temporal_properties() {
+        let mut project = Project::test_project();
+        project.add_property(TemporalProperty::new(
+            "initialized before use",
+            Formula::Until(
+                Event::Not(Box::new(Event::MemoryWritten(DR))),
+                Event::MemoryWritten(CR),
+            ),
+        ));
+        project.add_property(TemporalProperty::new(
+            "reaches the end",
+            Formula::Eventually(Event::PcReached(0x101)),
+        ));
+        project.add_property(TemporalProperty::new(
+            "index in bounds",
+            Formula::Always(Event::Register("R0".to_owned(), |ctx, value| {
+                value.ult(&ctx.from_u64(10, 32))
+            })),
+        ));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let context = state.ctx.clone();
+        state
+            .set_register("R0".to_owned(), context.from_u64(5, 32))
+            .unwrap();
+
+        let mut initialized = state.clone();
+        initialized.properties.record_write(CR, 32);
+        assert_eq!(PropertyMonitor::step(&mut initialized, 0x80), Ok(None));
+        initialized.properties.record_write(DR, 32);
+        assert_eq!(PropertyMonitor::step(&mut initialized, 0x84), Ok(None));
+        assert_eq!(
+            initialized.properties.unsatisfied(&project),
+            Some("reaches the end")
+        );
+        assert_eq!(PropertyMonitor::step(&mut initialized, 0x100), Ok(None));
+        assert_eq!(initialized.properties.unsatisfied(&project), None);
+        initialized
+            .set_register("R0".to_owned(), context.from_u64(12, 32))
+            .unwrap();
+        assert_eq!(
+            PropertyMonitor::step(&mut initialized, 0x102),
+            Ok(Some("index in bounds"))
+        );
+
+        let mut uninitialized = state;
+        uninitialized.properties.record_write(DR, 8);
+        assert_eq!(
+            PropertyMonitor::step(&mut uninitialized, 0x80),
+            Ok(Some("initialized before use"))
+        );
+    }
+}
@@ -0,0 +1,493 @@
+//! Reconstruction of earlier states of a path.
+//!
+//! When [`time_travel`](super::RunConfig::time_travel) is enabled each path
+//! keeps a [`Timeline`] of snapshots of its state and of every symbol created
+//! on it. The state before any executed instruction is reconstructed by
+//! replaying the path from the nearest earlier snapshot, see [`state_at`].
+//!
+//! A snapshot is taken at the start of the path and every
+//! [`snapshot_interval`](super::RunConfig::snapshot_interval) instructions
+//! after that. The snapshots only store the registers and flags that changed
+//! since the previous snapshot, the memory and the expressions are shared
+//! between the snapshots and the path.
+//!
+//! The replay reuses the recorded symbols and runs under the constraints of
+//! the path, so every branch goes the same way as when the path was explored.
+//! A reconstructed state can also be explored as a new path with other
+//! register values or constraints, see [`fork_at`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use super::{arch::Arch, executor::GAExecutor, state::GAState, vm::VM, GAError, Result};
+use crate::smt::DExpr;
+
+/// The state of a path before an instruction was executed.
+#[derive(Debug, Clone)]
+struct Snapshot<A: Arch> {
+    /// Number of instructions executed before the snapshot.
+    index: usize,
+
+    /// Number of symbols created before the snapshot.
+    symbols: usize,
+
+    /// The state without its registers and flags.
+    state: Arc<GAState<A>>,
+
+    /// Registers that changed since the previous snapshot.
+    registers: Vec<(String, DExpr)>,
+
+    /// Flags that changed since the previous snapshot.
+    flags: Vec<(String, DExpr)>,
+}
+
+/// Recording of a path that earlier states are reconstructed from.
+#[derive(Debug, Clone)]
+pub struct Timeline<A: Arch> {
+    /// Snapshots in instruction order, the snapshots do not hold a timeline.
+    snapshots: Vec<Snapshot<A>>,
+
+    /// Every symbol created on the path, in creation order.
+    symbols: Vec<DExpr>,
+
+    /// Recorded symbols that are reused instead of creating new ones while
+    /// the path is replayed.
+    replayed: VecDeque<DExpr>,
+
+    /// Instruction index the replay stops at.
+    replay_until: Option<usize>,
+
+    /// Registers at the latest snapshot.
+    registers: HashMap<String, DExpr>,
+
+    /// Flags at the latest snapshot.
+    flags: HashMap<String, DExpr>,
+
+    /// Instruction index and number of path constraints before the
+    /// instruction, each time the number changed.
+    constraints: Vec<(usize, usize)>,
+}
+
+impl<A: Arch> Timeline<A> {
+    /// Creates an empty timeline.
+    pub fn new() -> Self {
+        Self {
+            snapshots: vec![],
+            symbols: vec![],
+            replayed: VecDeque::new(),
+            replay_until: None,
+            registers: HashMap::new(),
+            flags: HashMap::new(),
+            constraints: vec![],
+        }
+    }
+
+    /// Returns true if a snapshot should be taken before the instruction with
+    /// `index` is executed, `interval` is the number of instructions between
+    /// the snapshots.
+    pub(crate) fn needs_snapshot(&self, index: usize, interval: Option<usize>) -> bool {
+        match (self.snapshots.last(), interval) {
+            (None, _) => true,
+            (Some(last), Some(interval)) => index >= last.index + interval.max(1),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Adds a snapshot of `state`, which must not hold a timeline.
+    pub(crate) fn add_snapshot(&mut self, mut state: GAState<A>) {
+        let registers = std::mem::take(&mut state.registers);
+        let flags = state.take_flags();
+        self.snapshots.push(Snapshot {
+            index: state.get_instruction_count(),
+            symbols: self.symbols.len(),
+            state: Arc::new(state),
+            registers: changed(&self.registers, &registers),
+            flags: changed(&self.flags, &flags),
+        });
+        self.registers = registers;
+        self.flags = flags;
+    }
+
+    /// Restores the state of the snapshot at `position` by applying the
+    /// changes of all snapshots up to it.
+    fn restore(&self, position: usize) -> GAState<A> {
+        let mut registers = HashMap::new();
+        let mut flags = HashMap::new();
+        for snapshot in &self.snapshots[..=position] {
+            registers.extend(snapshot.registers.iter().cloned());
+            flags.extend(snapshot.flags.iter().cloned());
+        }
+        let mut state = (*self.snapshots[position].state).clone();
+        state.registers = registers;
+        state.set_flags(flags);
+        state
+    }
+
+    /// Records that the path has `count` constraints before the instruction
+    /// with `index` is executed. The constraints are not recorded while the
+    /// path is replayed, as the replay runs under all constraints of the path.
+    pub(crate) fn record_constraints(&mut self, index: usize, count: usize) {
+        if self.replay_until.is_some() {
+            return;
+        }
+        match self.constraints.last_mut() {
+            Some((_, last)) if *last == count => {}
+            Some((last_index, last)) if *last_index == index => *last = count,
+            _ => self.constraints.push((index, count)),
+        }
+    }
+
+    /// Number of path constraints before the instruction with `index` was
+    /// executed.
+    fn constraints_at(&self, index: usize) -> usize {
+        self.constraints
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= index)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Returns true if a replay should stop before the instruction with
+    /// `index` is executed.
+    pub(crate) fn stops_at(&self, index: usize) -> bool {
+        self.replay_until == Some(index)
+    }
+
+    /// Records a symbol created on the path. While replaying the recorded
+    /// symbol is returned instead of the one created by `create`.
+    pub(crate) fn symbol(&mut self, create: impl FnOnce() -> DExpr) -> DExpr {
+        let symbol = self.replayed.pop_front().unwrap_or_else(create);
+        self.symbols.push(symbol.clone());
+        symbol
+    }
+}
+
+/// Returns the entries of `current` that are not in `previous`.
+fn changed(
+    previous: &HashMap<String, DExpr>,
+    current: &HashMap<String, DExpr>,
+) -> Vec<(String, DExpr)> {
+    current
+        .iter()
+        .filter(|(name, value)| previous.get(*name) != Some(value))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+impl<A: Arch> Default for Timeline<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reconstructs the state of the path that ended in `state` before the
+/// instruction with `index` was executed, where `index` counts the executed
+/// instructions from the start of the path.
+///
+/// The path is replayed from the nearest snapshot at or before `index`. This
+/// must be done before the next path is explored, while the solver holds the
+/// constraints of the path.
+pub fn state_at<A: Arch>(state: &GAState<A>, index: usize) -> Result<GAState<A>> {
+    let timeline = state
+        .timeline
+        .as_ref()
+        .filter(|_| index <= state.get_instruction_count())
+        .ok_or(GAError::StateNotRecorded(index))?;
+    let position = timeline
+        .snapshots
+        .iter()
+        .rposition(|snapshot| snapshot.index <= index)
+        .ok_or(GAError::StateNotRecorded(index))?;
+    let snapshot = &timeline.snapshots[position];
+
+    let mut start = timeline.restore(position);
+    let flags = start.take_flags();
+    start.set_flags(flags.clone());
+    start.timeline = Some(Timeline {
+        snapshots: timeline.snapshots[..=position].to_vec(),
+        symbols: timeline.symbols[..snapshot.symbols].to_vec(),
+        replayed: timeline.symbols[snapshot.symbols..]
+            .iter()
+            .cloned()
+            .collect(),
+        replay_until: Some(index),
+        registers: start.registers.clone(),
+        flags,
+        constraints: timeline
+            .constraints
+            .iter()
+            .filter(|(start, _)| *start <= index)
+            .copied()
+            .collect(),
+    });
+    if snapshot.index == index {
+        return Ok(finish_replay(start));
+    }
+
+    let project = start.project.clone();
+    let mut vm = VM::new_with_state(project.clone(), start);
+    vm.ranges = None;
+    let path = vm.paths.get_path().expect("the state was just added");
+    path.state.constraints.push();
+    let mut executor = GAExecutor::from_state(path.state, &mut vm, project);
+    let result = executor.resume_execution();
+    let replayed = executor.state;
+    replayed.constraints.pop();
+    // The path constraints only allow one direction at every branch, this
+    // keeps the solver scopes balanced if a path was forked anyway.
+    while vm.paths.get_path().is_some() {}
+
+    result?;
+    match replayed.get_instruction_count() == index {
+        true => Ok(finish_replay(replayed)),
+        false => Err(GAError::StateNotRecorded(index)),
+    }
+}
+
+/// Reconstructs the state before the instruction with `index` was executed as
+/// the start of a new path, see [`state_at`].
+///
+/// The new path has a solver of its own that only holds the constraints of the
+/// path up to `index`. Registers can be modified and constraints added before
+/// it is explored with [`VM::new_with_state`], without affecting the paths that
+/// remain to be explored.
+pub fn fork_at<A: Arch>(state: &GAState<A>, index: usize) -> Result<GAState<A>> {
+    let count = state
+        .timeline
+        .as_ref()
+        .map_or(0, |timeline| timeline.constraints_at(index));
+    let mut forked = state_at(state, index)?;
+    forked.constraints = forked.constraints.detach(count);
+    Ok(forked)
+}
+
+/// Ends the replay, exploring from the reconstructed state creates new
+/// symbols.
+fn finish_replay<A: Arch>(mut state: GAState<A>) -> GAState<A> {
+    if let Some(timeline) = &mut state.timeline {
+        timeline.replayed.clear();
+        timeline.replay_until = None;
+    }
+    state
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fmt, sync::Arc};
+
+    use general_assembly::{
+        condition::Condition,
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+    use object::File;
+
+    use super::{fork_at, state_at};
+    use crate::general_assembly::{
+        arch::{Arch, ArchError, InstructionSet},
+        instruction::{CycleCount, Instruction},
+        project::{PCHook, Project},
+        state::GAState,
+        vm::VM,
+        GAError,
+        RunConfig,
+    };
+
+    /// Two byte instructions, `[0, imm]` adds `imm` to R0 and `[1, target]`
+    /// jumps to `target` if R1 is zero.
+    #[derive(Debug, Clone)]
+    struct TestArch;
+
+    impl fmt::Display for TestArch {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "test")
+        }
+    }
+
+    impl InstructionSet for TestArch {
+        fn instruction_size(&self, _buff: &[u8]) -> Option<u32> {
+            Some(16)
+        }
+    }
+
+    impl Arch for TestArch {
+        fn translate(&self, buff: &[u8], _: bool) -> Result<Instruction<Self>, ArchError> {
+            let operations = match buff[0] {
+                0 => vec![Operation::Add {
+                    destination: Operand::Register("R0".to_owned()),
+                    operand1: Operand::Register("R0".to_owned()),
+                    operand2: Operand::Immediate(DataWord::Word32(buff[1] as u32)),
+                }],
+                _ => vec![
+                    Operation::SetZFlag(Operand::Register("R1".to_owned())),
+                    Operation::ConditionalJump {
+                        destination: Operand::Immediate(DataWord::Word32(buff[1] as u32)),
+                        condition: Condition::EQ,
+                    },
+                ],
+            };
+            Ok(Instruction {
+                instruction_size: 16,
+                operations,
+                max_cycle: CycleCount::Value(1),
+                memory_access: false,
+            })
+        }
+
+        fn add_hooks(&self, _cfg: &mut RunConfig<Self>) {}
+
+        fn discover(_file: &File<'_>) -> Result<Option<Self>, ArchError> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn test_state_at() {
+        #[rustfmt::skip]
+        let program = vec![
+            0, 1, // R0 += 1
+            1, 8, // if R1 == 0 goto 8
+            0, 2, // R0 += 2
+            0, 0, // R0 += 0
+            0, 4, // R0 += 4
+            0, 0,
+        ];
+        let mut project = Project::<TestArch>::test_project().with_program(program, 0, 12);
+        project.add_pc_hook(10, PCHook::EndSuccess);
+        project.set_time_travel(true);
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, TestArch);
+        let context = state.ctx.clone();
+        state
+            .set_register("R0".to_owned(), context.from_u64(0, 32))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+
+        let mut paths = 0;
+        while let Some((_, mut state)) = vm.run().unwrap() {
+            paths += 1;
+            let end = state.get_instruction_count();
+            let r1 = state.get_register("R1".to_owned()).unwrap();
+            // Step back to before the last instruction.
+            let (pc, r0) = match end {
+                5 => (8, 3),
+                3 => (8, 1),
+                _ => panic!("unexpected path length {end}"),
+            };
+            let mut earlier = state_at(&state, end - 1).unwrap();
+            assert_eq!(earlier.get_instruction_count(), end - 1);
+            let earlier_pc = earlier.get_register("PC".to_owned()).unwrap();
+            assert_eq!(earlier_pc.get_constant(), Some(pc));
+            let earlier_r0 = earlier.get_register("R0".to_owned()).unwrap();
+            assert_eq!(earlier_r0.get_constant(), Some(r0));
+            // The replay reuses the symbol of the path.
+            let earlier_r1 = earlier.get_register("R1".to_owned()).unwrap();
+            assert_eq!(earlier_r1, r1);
+
+            let start = state_at(&state, 0).unwrap();
+            assert_eq!(start.get_instruction_count(), 0);
+            assert_eq!(
+                state_at(&state, end + 1).err(),
+                Some(GAError::StateNotRecorded(end + 1))
+            );
+        }
+        assert_eq!(paths, 2);
+    }
+
+    #[test]
+    fn test_snapshot_interval() {
+        #[rustfmt::skip]
+        let program = vec![
+            0, 1, // R0 += 1
+            0, 2, // R0 += 2
+            0, 4, // R0 += 4
+            0, 8, // R0 += 8
+            0, 0,
+        ];
+        let mut project = Project::<TestArch>::test_project().with_program(program, 0, 10);
+        project.add_pc_hook(8, PCHook::EndSuccess);
+        project.set_time_travel(true);
+        project.set_snapshot_interval(Some(2));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, TestArch);
+        let context = state.ctx.clone();
+        state
+            .set_register("R0".to_owned(), context.from_u64(0, 32))
+            .unwrap();
+        state
+            .set_register("R1".to_owned(), context.from_u64(0, 32))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+
+        let (_, mut state) = vm.run().unwrap().unwrap();
+        assert_eq!(state.get_instruction_count(), 4);
+        let timeline = state.timeline.as_ref().unwrap();
+        let indices: Vec<_> = timeline.snapshots.iter().map(|s| s.index).collect();
+        assert_eq!(indices, vec![0, 2, 4]);
+        // Later snapshots only hold the registers that changed.
+        let mut changed: Vec<_> = timeline.snapshots[1]
+            .registers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        changed.sort();
+        assert_eq!(changed, vec!["PC", "R0"]);
+        assert!(timeline.snapshots[1].state.registers.is_empty());
+
+        for (index, r0) in [(0, 0), (1, 1), (2, 3), (3, 7), (4, 15)] {
+            let mut earlier = state_at(&state, index).unwrap();
+            assert_eq!(earlier.get_instruction_count(), index);
+            let earlier_r0 = earlier.get_register("R0".to_owned()).unwrap();
+            assert_eq!(earlier_r0.get_constant(), Some(r0));
+            let earlier_r1 = earlier.get_register("R1".to_owned()).unwrap();
+            assert_eq!(earlier_r1.get_constant(), Some(0));
+        }
+        let r0 = state.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0.get_constant(), Some(15));
+    }
+
+    #[test]
+    fn test_fork_at() {
+        #[rustfmt::skip]
+        let program = vec![
+            0, 1, // R0 += 1
+            1, 8, // if R1 == 0 goto 8
+            0, 2, // R0 += 2
+            0, 0, // R0 += 0
+            0, 4, // R0 += 4
+            0, 0,
+        ];
+        let mut project = Project::<TestArch>::test_project().with_program(program, 0, 12);
+        project.add_pc_hook(10, PCHook::EndSuccess);
+        project.set_time_travel(true);
+        project.set_snapshot_interval(Some(1));
+        let project = Arc::new(project);
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, TestArch);
+        let context = state.ctx.clone();
+        state
+            .set_register("R0".to_owned(), context.from_u64(0, 32))
+            .unwrap();
+        let mut vm = VM::new_with_state(project.clone(), state);
+
+        let (_, state) = vm.run().unwrap().unwrap();
+        let constraints = state.constraints.constraint_count();
+        assert!(constraints > 0);
+
+        // What if R1 was known to be non zero before the branch.
+        let mut forked = fork_at(&state, 1).unwrap();
+        assert_eq!(forked.constraints.constraint_count(), 0);
+        forked
+            .set_register("R1".to_owned(), context.from_u64(1, 32))
+            .unwrap();
+        let mut what_if = VM::new_with_state(project.clone(), forked);
+        let (_, mut end) = what_if.run().unwrap().unwrap();
+        assert!(what_if.run().unwrap().is_none());
+        let r0 = end.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0.get_constant(), Some(7));
+
+        // The explored path keeps its constraints.
+        assert_eq!(state.constraints.constraint_count(), constraints);
+    }
+}
@@ -0,0 +1,74 @@
+//! Import of recorded instruction and branch traces.
+//!
+//! A trace recorded on hardware, for example an ETM trace decoded to
+//! addresses or PCs sampled over SWO, can be used to seed the exploration. The
+//! first path follows the trace and every branch where it could have gone the
+//! other way is saved as a divergence point that is explored afterwards.
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TraceError {
+    #[error("Invalid address {text:?} on line {line} of the trace.")]
+    InvalidAddress { line: usize, text: String },
+}
+
+/// Parses a textual trace.
+///
+/// Each non empty line starts with a hexadecimal address, with or without a
+/// `0x` prefix. Anything after the address is ignored, so that decoder output
+/// with additional columns can be used directly. Lines starting with `#` are
+/// comments.
+///
+/// The trace may contain every executed instruction or only the targets of
+/// taken branches.
+pub fn parse_trace(input: &str) -> Result<Vec<u64>, TraceError> {
+    let mut trace = vec![];
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let text = line
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+            .next()
+            .unwrap_or_default();
+        let digits = text
+            .strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(text);
+        match u64::from_str_radix(digits, 16) {
+            Ok(address) => trace.push(address),
+            Err(_) => {
+                return Err(TraceError::InvalidAddress {
+                    line: idx + 1,
+                    text: text.to_owned(),
+                })
+            }
+        }
+    }
+    Ok(trace)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_trace, TraceError};
+
+    #[test]
+    fn test_parse_trace() {
+        let trace = "# recorded on target\n0x08000100\n08000104: ldr r0, [r1]\n\n0X8000108 B\n";
+        assert_eq!(parse_trace(trace).unwrap(), vec![
+            0x0800_0100,
+            0x0800_0104,
+            0x0800_0108
+        ]);
+        assert_eq!(
+            parse_trace("0x100\nbranch\n"),
+            Err(TraceError::InvalidAddress {
+                line: 2,
+                text: "branch".to_owned()
+            })
+        );
+    }
+}
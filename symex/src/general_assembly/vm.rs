@@ -1,65 +1,262 @@
 //! Descrebes the VM for general assembly
 
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use tracing::{debug, warn};
+
 use super::{
     arch::Arch,
+    budget::Truncation,
     executor::{GAExecutor, PathResult},
+    interrupts::{enter_handler, Footprint},
     path_selection::DFSPathSelection,
+    profile::{BranchFeasibility, PcHistogram, SemanticsCoverage},
     project::Project,
+    races::RaceLog,
+    range_analysis::RangeAnalysis,
+    resources::CeilingViolations,
+    side_effects::SideEffects,
+    summaries::SummaryCache,
+    GAError,
     Result,
 };
 use crate::{
-    general_assembly::{path_selection::Path, state::GAState},
-    smt::{DContext, DSolver},
+    general_assembly::{
+        path_selection::Path,
+        state::{GAState, StateKey, StateValues},
+    },
+    memory::MemoryError,
+    smt::{DContext, DExpr, DSolver, SolverError},
 };
 
 #[derive(Debug)]
 pub struct VM<A: Arch> {
-    pub project: &'static Project<A>,
+    pub project: Arc<Project<A>>,
     pub paths: DFSPathSelection<A>,
+    pub histogram: PcHistogram,
+    pub branches: BranchFeasibility,
+    /// How the semantics of the executed instructions were handled.
+    pub coverage: SemanticsCoverage,
+    /// Side effects of the functions that returned on any path.
+    pub side_effects: SideEffects,
+    /// Side effects of the stubs that were run on any path.
+    pub stub_effects: SideEffects,
+    /// Ranges inferred for the explored function, if enabled.
+    pub ranges: Option<RangeAnalysis>,
+    /// Number of paths pruned as they reached a state that was already
+    /// explored, see
+    /// [`deduplicate_states`](super::RunConfig::deduplicate_states).
+    pub duplicates: usize,
+    /// Keys of the explored states.
+    seen_states: HashSet<StateKey>,
+    /// Number of paths pruned as their state was implied by an explored
+    /// state, see
+    /// [`subsumption_budget`](super::RunConfig::subsumption_budget).
+    pub subsumed: usize,
+    /// Path conditions and cycle counts of the explored states by their
+    /// [`values`](GAState::values).
+    explored: HashMap<StateValues, Vec<(DExpr, usize)>>,
+    /// Memory footprints of the interrupt handlers by index, only found when
+    /// [`partial_order_reduction`](super::RunConfig::partial_order_reduction)
+    /// is enabled.
+    pub interrupt_footprints: Vec<Footprint>,
+    /// Number of interrupt injections skipped as they commute with the
+    /// instructions before them.
+    pub pruned_interleavings: usize,
+    /// Accesses of the thread code and the interrupt handlers to shared
+    /// memory on any path.
+    pub races: RaceLog,
+    /// Accesses to resources below their priority ceilings on any path.
+    pub ceiling_violations: CeilingViolations,
+    /// Number of paths that have been resumed, see
+    /// [`max_paths`](super::budget::ExecutionBudget::max_paths).
+    pub explored_paths: usize,
+    /// Number of paths that exceeded the budget of the exploration.
+    pub truncated: usize,
+    /// Summaries of the calls of the summarized functions, see
+    /// [`summaries`](super::summaries).
+    pub summaries: SummaryCache<A>,
+    /// Number of bytes copied by accelerated copy loops on any path, see
+    /// [`copies`](super::copies).
+    pub copied_bytes: u64,
 }
 
 impl<A: Arch> VM<A> {
+    /// Creates a VM without any paths.
+    fn empty(project: Arc<Project<A>>) -> Self {
+        Self {
+            project,
+            paths: DFSPathSelection::new(),
+            histogram: PcHistogram::new(),
+            branches: BranchFeasibility::new(),
+            coverage: SemanticsCoverage::new(),
+            side_effects: SideEffects::new(),
+            stub_effects: SideEffects::new(),
+            ranges: None,
+            duplicates: 0,
+            seen_states: HashSet::new(),
+            subsumed: 0,
+            explored: HashMap::new(),
+            interrupt_footprints: vec![],
+            pruned_interleavings: 0,
+            races: RaceLog::new(),
+            ceiling_violations: CeilingViolations::new(),
+            explored_paths: 0,
+            truncated: 0,
+            summaries: SummaryCache::new(),
+            copied_bytes: 0,
+        }
+    }
+
     pub fn new(
-        project: &'static Project<A>,
-        ctx: &'static DContext,
+        project: Arc<Project<A>>,
+        ctx: &DContext,
         fn_name: &str,
         end_pc: u64,
         architecture: A,
     ) -> Result<Self> {
-        let mut vm = Self {
-            project,
-            paths: DFSPathSelection::new(),
-        };
+        let mut vm = Self::empty(project.clone());
 
+        ctx.set_timeout(project.get_solver_timeout());
         let solver = DSolver::new(ctx);
-        let state = GAState::<A>::new(ctx, project, solver, fn_name, end_pc, architecture)?;
+        let mut state = GAState::<A>::new(ctx, project, solver, fn_name, end_pc, architecture)?;
+        vm.analyze_ranges(&mut state)?;
+        vm.analyze_interrupts(&state)?;
 
         vm.paths.save_path(Path::new(state, None));
 
         Ok(vm)
     }
 
-    pub fn new_with_state(project: &'static Project<A>, state: GAState<A>) -> Self {
-        let mut vm = Self {
-            project,
-            paths: DFSPathSelection::new(),
-        };
+    /// Records the key of a state, returns false if a state with an equal key
+    /// was explored before.
+    pub(crate) fn visit_state(&mut self, key: StateKey) -> bool {
+        self.seen_states.insert(key)
+    }
+
+    /// Get the path conditions and cycle counts of the explored states with
+    /// values equal to `values`, oldest first.
+    pub(crate) fn explored_states(&self, values: &StateValues) -> &[(DExpr, usize)] {
+        self.explored.get(values).map_or(&[], Vec::as_slice)
+    }
+
+    /// Records an explored state with the values `values`.
+    pub(crate) fn record_explored(&mut self, values: StateValues, condition: DExpr, cycles: usize) {
+        self.explored
+            .entry(values)
+            .or_default()
+            .push((condition, cycles));
+    }
+
+    pub fn new_with_state(project: Arc<Project<A>>, mut state: GAState<A>) -> Self {
+        state.ctx.set_timeout(project.get_solver_timeout());
+        let mut vm = Self::empty(project);
+        if let Err(error) = vm.analyze_ranges(&mut state) {
+            warn!("Unable to analyze the ranges of the registers: {}", error);
+        }
+        if let Err(error) = vm.analyze_interrupts(&state) {
+            warn!("Unable to find the footprints of the interrupts: {}", error);
+        }
 
         vm.paths.save_path(Path::new(state, None));
 
         vm
     }
 
+    /// Infers the ranges of the registers of the function `state` starts in,
+    /// if enabled.
+    fn analyze_ranges(&mut self, state: &mut GAState<A>) -> Result<()> {
+        if !self.project.get_range_analysis() {
+            return Ok(());
+        }
+        if let Some(entry) = state.get_register("PC".to_owned())?.get_constant() {
+            self.ranges = Some(RangeAnalysis::analyze(
+                &self.project,
+                &state.architecture,
+                entry,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Explores the handler of each interrupt on its own from `state` to find
+    /// its memory footprint, if partial order reduction is enabled.
+    fn analyze_interrupts(&mut self, state: &GAState<A>) -> Result<()> {
+        if !self.project.get_partial_order_reduction() {
+            return Ok(());
+        }
+        for index in 0..self.project.get_interrupts().len() {
+            let mut handler = state.clone();
+            enter_handler(&mut handler, index, false)?;
+            let mut explorer = Self::empty(self.project.clone());
+            explorer.paths.save_path(Path::new(handler, None));
+            let mut footprint = Footprint::default();
+            while let Some((_, state)) = explorer.run()? {
+                if let Some(accesses) = state.interrupts.handler_footprint() {
+                    footprint.merge(accesses);
+                }
+            }
+            self.interrupt_footprints.push(footprint);
+            self.races.merge(&explorer.races);
+        }
+        Ok(())
+    }
+
+    /// Get the part of the budget of the exploration that is exhausted for
+    /// all remaining paths, if any.
+    fn exhausted_budget(&self, ctx: &DContext) -> Option<Truncation> {
+        let budget = self.project.get_budget();
+        if let Some(max) = budget.max_paths {
+            if self.explored_paths >= max {
+                return Some(Truncation::Paths(max));
+            }
+        }
+        match budget.max_solver_time {
+            Some(max) if ctx.solver_time() >= max => Some(Truncation::SolverTime(max)),
+            _ => None,
+        }
+    }
+
     pub fn run(&mut self) -> Result<Option<(PathResult, GAState<A>)>> {
         if let Some(path) = self.paths.get_path() {
+            let exhausted = self.exhausted_budget(&path.state.ctx);
+            self.explored_paths += 1;
             // try stuff
-            let mut executor = GAExecutor::from_state(path.state, self, self.project);
+            let project = self.project.clone();
+            let mut executor = GAExecutor::from_state(path.state, self, project);
 
             for constraint in path.constraints {
                 executor.state.constraints.assert(&constraint);
             }
 
-            let result = executor.resume_execution()?;
+            if let Some(truncation) = exhausted {
+                debug!("Truncating a remaining path, {}", truncation);
+                executor.vm.truncated += 1;
+                return Ok(Some((PathResult::Truncated(truncation), executor.state)));
+            }
+
+            let result = executor.resume_execution();
+            let result = match executor.fail_on_hook_panic(result) {
+                Ok(result) => result,
+                Err(
+                    GAError::SolverError(SolverError::Unknown(query))
+                    | GAError::MemoryError(MemoryError::Solver(SolverError::Unknown(query))),
+                ) => {
+                    warn!("Ending the path, the solver could not decide {}", query);
+                    PathResult::Unknown(query)
+                }
+                Err(error) => return Err(error),
+            };
+            let result = match (result, executor.unsatisfied_at_end()) {
+                (PathResult::Success(_), Some(reason)) => PathResult::Failure(reason),
+                (result, _) => result,
+            };
+            if matches!(result, PathResult::Truncated(_)) {
+                executor.vm.truncated += 1;
+            }
             return Ok(Some((result, executor.state)));
         }
         Ok(None)
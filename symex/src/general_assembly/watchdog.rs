@@ -0,0 +1,128 @@
+//! Model of a watchdog timer that has to be refreshed within a deadline.
+//!
+//! The [`watchdog`](super::RunConfig::watchdog) counts the cycles since the
+//! last refresh, a write of the refresh value to the refresh register. A path
+//! that runs for more cycles than the timeout without a refresh fails, as the
+//! watchdog would have reset the processor.
+
+use super::{arch::Arch, state::GAState, Result};
+use crate::smt::DExpr;
+
+/// Reported as the reason of the failure of a path where the watchdog
+/// expires.
+pub const WATCHDOG_EXPIRED: &str = "watchdog expired before it was refreshed";
+
+/// A watchdog timer that is refreshed by writes to a register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchdog {
+    /// Address of the register that refreshes the watchdog.
+    pub refresh_address: u64,
+
+    /// Value that has to be written to refresh the watchdog, `None` if any
+    /// write refreshes it.
+    pub refresh_value: Option<u64>,
+
+    /// Number of cycles after a refresh until the watchdog expires.
+    pub timeout: usize,
+}
+
+impl Watchdog {
+    /// Creates a watchdog that is refreshed by any write to
+    /// `refresh_address` and expires `timeout` cycles after a refresh.
+    pub fn new(refresh_address: u64, timeout: usize) -> Self {
+        Self {
+            refresh_address,
+            refresh_value: None,
+            timeout,
+        }
+    }
+
+    /// Only refreshes the watchdog when `value` is written, such as the key
+    /// of the refresh register.
+    pub fn with_refresh_value(self, value: u64) -> Self {
+        Self {
+            refresh_value: Some(value),
+            ..self
+        }
+    }
+
+    /// Checks if writing `value` to `address` refreshes the watchdog. A
+    /// symbolic value only refreshes the watchdog if it must equal the refresh
+    /// value.
+    pub(crate) fn is_refresh<A: Arch>(
+        &self,
+        state: &GAState<A>,
+        address: u64,
+        value: &DExpr,
+    ) -> Result<bool> {
+        if address != self.refresh_address {
+            return Ok(false);
+        }
+        match self.refresh_value {
+            Some(refresh_value) => {
+                let refresh_value = state.ctx.from_u64(refresh_value, value.len());
+                Ok(state.constraints.must_be_equal(value, &refresh_value)?)
+            }
+            None => Ok(true),
+        }
+    }
+
+    /// Checks if the watchdog has expired on the path of `state`.
+    pub(crate) fn expired<A: Arch>(&self, state: &GAState<A>) -> bool {
+        state.cycle_count.saturating_sub(state.watchdog_refresh) > self.timeout
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use general_assembly::{
+        operand::{DataWord, Operand},
+        operation::Operation,
+    };
+
+    use super::{Watchdog, WATCHDOG_EXPIRED};
+    use crate::general_assembly::{
+        arch::arm::v6::ArmV6M,
+        executor::GAExecutor,
+        project::Project,
+        state::GAState,
+        vm::VM,
+    };
+
+    const KR: u64 = 0x4000_3000;
+
+    #[test]
+    fn test_watchdog() {
+        let mut project = Project::test_project();
+        project.set_watchdog(Some(Watchdog::new(KR, 100).with_refresh_value(0xAAAA)));
+        let project = Arc::new(project);
+        let state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+        let mut vm = VM::new_with_state(project.clone(), state);
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+        let write = |value: u32| Operation::Move {
+            destination: Operand::Address(DataWord::Word32(KR as u32), 32),
+            source: Operand::Immediate(DataWord::Word32(value)),
+        };
+
+        executor.state.cycle_count = 80;
+        executor
+            .execute_operation(&write(0xAAAA), &mut local)
+            .unwrap();
+        assert_eq!(executor.state.watchdog_refresh, 80);
+
+        // Other values do not refresh the watchdog.
+        executor.state.cycle_count = 150;
+        executor
+            .execute_operation(&write(0x5555), &mut local)
+            .unwrap();
+        assert_eq!(executor.state.watchdog_refresh, 80);
+        assert_eq!(executor.unsatisfied_at_end(), None);
+
+        executor.state.cycle_count = 181;
+        assert_eq!(executor.unsatisfied_at_end(), Some(WATCHDOG_EXPIRED));
+    }
+}
@@ -0,0 +1,278 @@
+//! Declarative verification harnesses.
+//!
+//! A [`Harness`] describes the inputs of a function, assumptions on them and
+//! assertions that must hold when the function returns:
+//!
+//! ```no_run
+//! # use symex::{general_assembly::arch::arm::v6::ArmV6M, harness::Harness};
+//! let results = Harness::<ArmV6M>::for_function("foo")
+//!     .input_u32("x", 0..100)
+//!     .assume(|s| {
+//!         let x = s.get_symbol("x").unwrap();
+//!         Ok(x.ne(&s.ctx.from_u64(42, 32)))
+//!     })
+//!     .assert(|s| {
+//!         let result = s.get_register("R0".to_owned())?;
+//!         Ok(result.ult(&s.ctx.from_u64(200, 32)))
+//!     })
+//!     .run("target/thumbv6m-none-eabi/release/examples/foo", ArmV6M {})
+//!     .unwrap();
+//! ```
+//!
+//! Inputs are passed as arguments according to the AAPCS, the first four in
+//! `R0` to `R3` and the rest on the stack. Assumptions are added as
+//! constraints before execution starts. Each path that returns is checked
+//! against the assertions and reported as a failure if any assertion can be
+//! violated, the reported values of the inputs then violate the assertion.
+
+use std::ops::{Bound, RangeBounds};
+
+use crate::{
+    elf_util::{ExpressionType, Variable, VisualPathResult},
+    general_assembly::{
+        arch::Arch,
+        state::{GAState, SymbolOrigin},
+        Result,
+        RunConfig,
+    },
+    memory::SmtMap,
+    smt::DExpr,
+};
+
+/// Predicate over the state of a path, returns a boolean expression.
+pub type Predicate<A> = Box<dyn Fn(&mut GAState<A>) -> Result<DExpr>>;
+
+/// Symbolic input of the function under test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    /// Name of the symbol.
+    pub name: String,
+
+    /// Width of the input in bits.
+    pub bits: u32,
+
+    /// Lower bound of the input.
+    pub lower: Bound<u64>,
+
+    /// Upper bound of the input.
+    pub upper: Bound<u64>,
+}
+
+/// Declarative verification harness for a single function.
+pub struct Harness<A: Arch> {
+    function: String,
+    inputs: Vec<Input>,
+    assumptions: Vec<Predicate<A>>,
+    assertions: Vec<Predicate<A>>,
+    cfg: RunConfig<A>,
+}
+
+impl<A: Arch> Harness<A> {
+    /// Creates a harness for the function with the symbol `function`.
+    pub fn for_function(function: &str) -> Self {
+        Self {
+            function: function.to_owned(),
+            inputs: vec![],
+            assumptions: vec![],
+            assertions: vec![],
+            cfg: RunConfig::default(),
+        }
+    }
+
+    /// Sets the configuration used for the run.
+    pub fn config(mut self, cfg: RunConfig<A>) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    /// Adds an input of `bits` bits in `range` as the next argument.
+    pub fn input(mut self, name: &str, bits: u32, range: impl RangeBounds<u64>) -> Self {
+        self.inputs.push(Input {
+            name: name.to_owned(),
+            bits,
+            lower: range.start_bound().cloned(),
+            upper: range.end_bound().cloned(),
+        });
+        self
+    }
+
+    /// Adds an `u8` input in `range` as the next argument.
+    pub fn input_u8(self, name: &str, range: impl RangeBounds<u8>) -> Self {
+        let (lower, upper) = widen(range);
+        self.input(name, 8, (lower, upper))
+    }
+
+    /// Adds an `u16` input in `range` as the next argument.
+    pub fn input_u16(self, name: &str, range: impl RangeBounds<u16>) -> Self {
+        let (lower, upper) = widen(range);
+        self.input(name, 16, (lower, upper))
+    }
+
+    /// Adds an `u32` input in `range` as the next argument.
+    pub fn input_u32(self, name: &str, range: impl RangeBounds<u32>) -> Self {
+        let (lower, upper) = widen(range);
+        self.input(name, 32, (lower, upper))
+    }
+
+    /// Adds an assumption that holds when the function is called.
+    pub fn assume(
+        mut self,
+        assumption: impl Fn(&mut GAState<A>) -> Result<DExpr> + 'static,
+    ) -> Self {
+        self.assumptions.push(Box::new(assumption));
+        self
+    }
+
+    /// Adds an assertion that must hold when the function returns.
+    pub fn assert(
+        mut self,
+        assertion: impl Fn(&mut GAState<A>) -> Result<DExpr> + 'static,
+    ) -> Self {
+        self.assertions.push(Box::new(assertion));
+        self
+    }
+
+    /// Get the symbol of the function under test.
+    pub fn get_function(&self) -> &str {
+        &self.function
+    }
+
+    /// Get the declared inputs in argument order.
+    pub fn get_inputs(&self) -> &[Input] {
+        &self.inputs
+    }
+
+    /// Runs the harness on the ELF file at `path`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the specified file does not exist.
+    pub fn run(self, path: &str, architecture: A) -> Result<Vec<VisualPathResult>> {
+        crate::run_elf::run_elf_harness(path, architecture, self)
+    }
+
+    /// Takes the configuration used for the run.
+    pub(crate) fn take_config(&mut self) -> RunConfig<A> {
+        std::mem::take(&mut self.cfg)
+    }
+
+    /// Passes the inputs to the function and adds the assumptions.
+    ///
+    /// Returns false if the assumptions can not be satisfied.
+    pub(crate) fn prepare(&self, state: &mut GAState<A>) -> Result<bool> {
+        let word_size = state.project.get_word_size();
+        let ptr_size = state.project.get_ptr_size();
+        let sp = state.get_register("SP".to_owned())?;
+        for (index, input) in self.inputs.iter().enumerate() {
+            let value = state.new_symbol(input.bits, &input.name, SymbolOrigin::Input);
+            state.marked_symbolic.push(Variable {
+                name: Some(input.name.to_owned()),
+                value: value.clone(),
+                ty: ExpressionType::Integer(input.bits as usize),
+            });
+            match input.lower {
+                Bound::Included(lower) => state
+                    .constraints
+                    .assert(&value.ugte(&state.ctx.from_u64(lower, input.bits))),
+                Bound::Excluded(lower) => state
+                    .constraints
+                    .assert(&value.ugt(&state.ctx.from_u64(lower, input.bits))),
+                Bound::Unbounded => {}
+            }
+            match input.upper {
+                Bound::Included(upper) => state
+                    .constraints
+                    .assert(&value.ulte(&state.ctx.from_u64(upper, input.bits))),
+                Bound::Excluded(upper) => state
+                    .constraints
+                    .assert(&value.ult(&state.ctx.from_u64(upper, input.bits))),
+                Bound::Unbounded => {}
+            }
+
+            let argument = value.zero_ext(word_size);
+            if index < 4 {
+                state.set_register(format!("R{index}"), argument)?;
+            } else {
+                let offset = state
+                    .ctx
+                    .from_u64((index as u64 - 4) * (word_size / 8) as u64, ptr_size);
+                state.memory.write(&sp.add(&offset), argument)?;
+            }
+        }
+
+        for assumption in &self.assumptions {
+            let constraint = assumption(state)?;
+            state.constraints.assert(&constraint);
+        }
+        Ok(state.constraints.is_sat()?)
+    }
+
+    /// Checks the assertions on a path that returned.
+    ///
+    /// Returns a message describing the first assertion that can be violated,
+    /// the path is then constrained to violate it.
+    pub(crate) fn check(&self, state: &mut GAState<A>) -> Result<Option<String>> {
+        for (index, assertion) in self.assertions.iter().enumerate() {
+            let violated = assertion(state)?.not();
+            if state.constraints.is_sat_with_constraint(&violated)? {
+                state.constraints.assert(&violated);
+                return Ok(Some(format!(
+                    "Assertion {index} of the harness for {} can be violated.",
+                    self.function
+                )));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Converts a range of unsigned integers to bounds of `u64`.
+fn widen<T: Copy + Into<u64>>(range: impl RangeBounds<T>) -> (Bound<u64>, Bound<u64>) {
+    let widen_bound = |bound: Bound<&T>| match bound {
+        Bound::Included(value) => Bound::Included((*value).into()),
+        Bound::Excluded(value) => Bound::Excluded((*value).into()),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    (
+        widen_bound(range.start_bound()),
+        widen_bound(range.end_bound()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::Harness;
+    use crate::general_assembly::{arch::arm::v6::ArmV6M, project::Project, state::GAState};
+
+    #[test]
+    fn test_harness_inputs_and_assertions() {
+        let project = Arc::new(Project::test_project());
+        let mut state = GAState::test_state(project.clone(), 0, u32::MAX as u64, ArmV6M {});
+
+        let harness = Harness::<ArmV6M>::for_function("foo")
+            .input_u32("x", 0..10)
+            .assume(|s| {
+                let x = s.get_symbol("x").unwrap();
+                Ok(x.ne(&s.ctx.from_u64(3, 32)))
+            })
+            .assert(|s| {
+                let r0 = s.get_register("R0".to_owned())?;
+                Ok(r0.ult(&s.ctx.from_u64(10, 32)))
+            });
+        assert!(harness.prepare(&mut state).unwrap());
+        assert_eq!(harness.check(&mut state).unwrap(), None);
+
+        let harness = Harness::<ArmV6M>::for_function("foo").assert(|s| {
+            let r0 = s.get_register("R0".to_owned())?;
+            Ok(r0.ult(&s.ctx.from_u64(5, 32)))
+        });
+        assert!(harness.check(&mut state).unwrap().is_some());
+        let x = state.get_symbol("x").unwrap();
+        assert!(!state
+            .constraints
+            .can_equal(&x, &state.ctx.from_u64(4, 32))
+            .unwrap());
+    }
+}
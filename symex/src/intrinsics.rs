@@ -0,0 +1,205 @@
+//! Registry of the intrinsic functions provided by `symex_lib`.
+//!
+//! Intrinsics are functions in the analyzed binary that the engine replaces
+//! with hooks, such as `symbolic_size` or `suppress_path`. The hooks are bound
+//! by name, so a binary built against a `symex_lib` that renamed or added an
+//! intrinsic silently runs the real function instead. `symex_lib` exports its
+//! intrinsics version in [`VERSION_SYMBOL`], which is checked against
+//! [`INTRINSICS_VERSION`] before execution starts.
+
+use std::fmt::Display;
+
+use object::{Object, ObjectSection, ObjectSymbol};
+use regex::Regex;
+
+/// Version of the intrinsics supported by this engine.
+pub const INTRINSICS_VERSION: u32 = 2;
+
+/// Symbol `symex_lib` exports its intrinsics version in, as a `u32`.
+pub const VERSION_SYMBOL: &str = "SYMEX_INTRINSICS_VERSION";
+
+/// An intrinsic function of `symex_lib`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Intrinsic {
+    /// Name of the function, without the path and generic arguments.
+    pub name: &'static str,
+
+    /// Expression matching the debug information name of the function, used
+    /// to bind the hook.
+    pub pattern: &'static str,
+
+    /// First intrinsics version that contains the intrinsic.
+    pub since: u32,
+}
+
+/// All intrinsics supported by this engine.
+pub const INTRINSICS: &[Intrinsic] = &[
+    Intrinsic {
+        name: "symbolic_size",
+        pattern: r"^symbolic_size<.+>$",
+        since: 1,
+    },
+    Intrinsic {
+        name: "suppress_path",
+        pattern: r"^suppress_path$",
+        since: 1,
+    },
+    Intrinsic {
+        name: "symex_unreachable",
+        pattern: r"^symex_unreachable$",
+        since: 1,
+    },
+    Intrinsic {
+        name: "symex_must_reach",
+        pattern: r"^symex_must_reach$",
+        since: 1,
+    },
+    Intrinsic {
+        name: "start_cyclecount",
+        pattern: r"^start_cyclecount$",
+        since: 1,
+    },
+    Intrinsic {
+        name: "end_cyclecount",
+        pattern: r"^end_cyclecount$",
+        since: 1,
+    },
+    Intrinsic {
+        name: "symex_assume",
+        pattern: r"^symex_assume$",
+        since: 2,
+    },
+    Intrinsic {
+        name: "symex_assert",
+        pattern: r"^symex_assert$",
+        since: 2,
+    },
+];
+
+/// Get the intrinsic with the name `name`.
+pub fn intrinsic(name: &str) -> Option<&'static Intrinsic> {
+    INTRINSICS.iter().find(|intrinsic| intrinsic.name == name)
+}
+
+/// Get the expression used to bind the hook of the intrinsic `name`.
+///
+/// # Panics
+///
+/// This function panics if `name` is not in [`INTRINSICS`].
+pub fn hook_pattern(name: &str) -> Regex {
+    let intrinsic = intrinsic(name).expect("intrinsic is registered");
+    Regex::new(intrinsic.pattern).expect("intrinsic pattern is valid")
+}
+
+/// Intrinsics found in a binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntrinsicReport {
+    /// Intrinsics version exported by the binary, `None` if the binary does
+    /// not export one.
+    pub version: Option<u32>,
+
+    /// Intrinsics defined in the binary, these are bound to hooks.
+    pub found: Vec<&'static Intrinsic>,
+
+    /// Intrinsics not defined in the binary.
+    pub missing: Vec<&'static Intrinsic>,
+}
+
+impl IntrinsicReport {
+    /// Discovers the intrinsics defined in `file`.
+    pub fn discover(file: &object::File<'_>) -> Self {
+        let names: Vec<String> = file
+            .symbols()
+            .filter_map(|symbol| symbol.name().ok())
+            .map(|name| base_name(&format!("{:#}", rustc_demangle::demangle(name))).to_owned())
+            .collect();
+        let (found, missing) = INTRINSICS
+            .iter()
+            .partition(|intrinsic| names.iter().any(|name| name == intrinsic.name));
+        Self {
+            version: read_version(file),
+            found,
+            missing,
+        }
+    }
+
+    /// Describes the mismatches between the intrinsics of the binary and the
+    /// engine.
+    pub fn mismatches(&self) -> Vec<String> {
+        let mut mismatches = vec![];
+        match self.version {
+            Some(version) if version > INTRINSICS_VERSION => mismatches.push(format!(
+                "The binary uses intrinsics version {version} but only version {INTRINSICS_VERSION} is supported, newer intrinsics are not hooked."
+            )),
+            Some(version) => {
+                for intrinsic in &self.found {
+                    if intrinsic.since > version {
+                        mismatches.push(format!(
+                            "{} is an intrinsic since version {} but the binary uses version {version}.",
+                            intrinsic.name, intrinsic.since
+                        ));
+                    }
+                }
+            }
+            None if !self.found.is_empty() => mismatches.push(format!(
+                "The binary defines intrinsics but does not export {VERSION_SYMBOL}, it may be built with an incompatible symex_lib."
+            )),
+            None => {}
+        }
+        mismatches
+    }
+}
+
+impl Display for IntrinsicReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version {
+            Some(version) => writeln!(f, "Intrinsics version: {version}")?,
+            None => writeln!(f, "Intrinsics version: not exported")?,
+        }
+        for intrinsic in &self.found {
+            writeln!(f, "found: {}", intrinsic.name)?;
+        }
+        for intrinsic in &self.missing {
+            writeln!(f, "missing: {}", intrinsic.name)?;
+        }
+        for mismatch in self.mismatches() {
+            writeln!(f, "warning: {mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Strips the path and generic arguments from a demangled function name.
+fn base_name(name: &str) -> &str {
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Reads the intrinsics version exported by `file`.
+fn read_version(file: &object::File<'_>) -> Option<u32> {
+    let symbol = file
+        .symbols()
+        .find(|symbol| symbol.name() == Ok(VERSION_SYMBOL))?;
+    let section = file.section_by_index(symbol.section_index()?).ok()?;
+    let data = section.data().ok()?;
+    let offset = symbol.address().checked_sub(section.address())? as usize;
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(match file.is_little_endian() {
+        true => u32::from_le_bytes(bytes),
+        false => u32::from_be_bytes(bytes),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{base_name, hook_pattern};
+
+    #[test]
+    fn test_intrinsic_names() {
+        assert_eq!(base_name("symex_lib::suppress_path"), "suppress_path");
+        assert_eq!(base_name("symex_lib::symbolic_size<u32>"), "symbolic_size");
+        assert_eq!(base_name("symex_must_reach"), "symex_must_reach");
+        assert!(hook_pattern("symbolic_size").is_match("symbolic_size<u8>"));
+        assert!(!hook_pattern("suppress_path").is_match("suppress_path_now"));
+    }
+}
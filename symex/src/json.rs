@@ -0,0 +1,346 @@
+//! Machine readable output of the results of a run.
+//!
+//! A [`RunReport`] holds the results of all paths of a function in a form that
+//! serializes to JSON, so that CI pipelines can consume the analysis without
+//! parsing the printed path results:
+//!
+//! ```no_run
+//! # use symex::{json, run_elf::ElfOptions};
+//! let json = json::run_to_json(
+//!     "target/thumbv6m-none-eabi/release/examples/foo",
+//!     "foo",
+//!     &ElfOptions::default(),
+//! )
+//! .unwrap();
+//! std::fs::write("symex.json", json).unwrap();
+//! ```
+//!
+//! Each path is a serialized [`VisualPathResult`]. Symbolic values are
+//! reported by their concrete values in a model of the path constraints, as
+//! [`ConcreteInput`]s, and the constraints themselves as an SMT-LIB script
+//! that a solver can check or extend. The ranges of the inputs are only
+//! included as [`InputRange`](crate::preconditions::InputRange)s when
+//! [`RunConfig::infer_preconditions`](crate::general_assembly::RunConfig::infer_preconditions)
+//! is set.
+
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{
+    elf_util::{ConcreteInput, PathStatus, Variable, VisualPathResult},
+    general_assembly::GAError,
+    run_elf::{run_elf_with_options, ElfOptions},
+    watch::RunSummary,
+};
+
+/// Runs all paths of `function` in the elf file at `path` with `options` and
+/// returns the results as JSON.
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn run_to_json<P: AsRef<Path>>(
+    path: P,
+    function: &str,
+    options: &ElfOptions,
+) -> Result<String, GAError> {
+    let results = run_elf_with_options(path, function, options)?;
+    Ok(RunReport::new(function, &results).to_json())
+}
+
+/// Results of all paths of a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport<'a> {
+    /// Name of the analyzed function.
+    pub function: &'a str,
+
+    /// Number of paths, failures and cycles of the run.
+    pub summary: RunSummary,
+
+    /// Result of each path.
+    pub paths: &'a [VisualPathResult],
+}
+
+impl<'a> RunReport<'a> {
+    /// Creates the report of the paths `results` of `function`.
+    pub fn new(function: &'a str, results: &'a [VisualPathResult]) -> Self {
+        Self {
+            function,
+            summary: RunSummary::new(results),
+            paths: results,
+        }
+    }
+
+    /// Serializes the report to pretty printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("run reports serialize to JSON")
+    }
+}
+
+/// How a path ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The path returned.
+    Ok,
+
+    /// The path failed.
+    Failed,
+
+    /// The solver could not decide the path.
+    Unknown,
+
+    /// The path was truncated by the budget.
+    Truncated,
+}
+
+/// Cycle count when execution reached a marked event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CycleLap {
+    /// Number of cycles at the event.
+    pub cycles: usize,
+
+    /// Name of the event.
+    pub name: String,
+}
+
+/// Serializes as the `status` of the path and a `message` with why the path
+/// failed, the undecided query or the exceeded limit.
+impl Serialize for PathStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (status, message) = match self {
+            PathStatus::Ok(_) => (Status::Ok, None),
+            PathStatus::Failed(reason) => (Status::Failed, Some(reason.error_message.clone())),
+            PathStatus::Unknown(query) => (Status::Unknown, Some(query.clone())),
+            PathStatus::Truncated(truncation) => (Status::Truncated, Some(truncation.to_string())),
+        };
+        let mut path = serializer.serialize_struct("PathStatus", 2)?;
+        path.serialize_field("status", &status)?;
+        path.serialize_field("message", &message)?;
+        path.end()
+    }
+}
+
+/// Serializes the solved `variables` as [`ConcreteInput`]s.
+pub(crate) fn concrete<S: Serializer>(
+    variables: &[Variable],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        variables
+            .iter()
+            .map(|var| ConcreteInput::new(var.name.clone(), var.ty.clone(), &var.value)),
+    )
+}
+
+/// Serializes the cycle counts at marked events as [`CycleLap`]s.
+pub(crate) fn cycle_laps<S: Serializer>(
+    laps: &[(usize, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(laps.iter().map(|(cycles, name)| CycleLap {
+        cycles: *cycles,
+        name: name.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::RunReport;
+    use crate::{
+        elf_util::{ErrorReason, ExpressionType, PathStatus, Variable, VisualPathResult},
+        general_assembly::{
+            budget::Truncation,
+            path_data::PathData,
+            state::{SymbolOrigin, SymbolProvenance},
+        },
+        smt::{DContext, DSolver},
+    };
+
+    /// A failing path with the input `x` constrained to be below 0x200.
+    fn result(ctx: &DContext) -> VisualPathResult {
+        let solver = DSolver::new(ctx);
+        let x = ctx.unconstrained(16, "x");
+        solver.assert(&x.ult(&ctx.from_u64(0x200, 16)));
+        VisualPathResult {
+            path: 1,
+            result: PathStatus::Failed(ErrorReason {
+                error_message: "panic".to_owned(),
+            }),
+            symbolics: vec![Variable {
+                name: Some("x".to_owned()),
+                value: ctx.from_u64(0x12c, 16),
+                ty: ExpressionType::Integer(16),
+            }],
+            constrained: vec!["x".to_owned()],
+            input_ranges: vec![],
+            end_state: vec![Variable {
+                name: Some("R0".to_owned()),
+                value: ctx.from_u64(7, 32),
+                ty: ExpressionType::Integer(32),
+            }],
+            register_roles: HashMap::new(),
+            return_value: None,
+            instruction_count: 12,
+            max_cycles: 20,
+            energy: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictions: None,
+            cycle_laps: vec![(5, "isr".to_owned())],
+            reached: vec!["foo".to_owned()],
+            initial_sp: 0,
+            memory_delta: vec![],
+            register_history: vec![],
+            mmio_log: vec![],
+            slice: None,
+            provenance: vec![SymbolProvenance {
+                name: "x".to_owned(),
+                origin: SymbolOrigin::MemoryRead(0x4000_0000),
+                pc: 0x100,
+            }],
+            filtered_calls: vec![],
+            under_approximated: vec![],
+            interrupts: vec![],
+            atomicity_violation: None,
+            reentry: None,
+            nvic: vec![],
+            peripherals: vec![],
+            path_data: PathData::default(),
+            constraints: solver.to_smtlib(),
+        }
+    }
+
+    fn json(results: &[VisualPathResult]) -> serde_json::Value {
+        serde_json::from_str(&RunReport::new("foo", results).to_json()).unwrap()
+    }
+
+    #[test]
+    fn test_run_report() {
+        let ctx = DContext::new();
+        let results = [result(&ctx)];
+        let report = RunReport::new("foo", &results);
+        assert_eq!(report.summary.failures.get("panic"), Some(&1));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        let path = &json["paths"][0];
+        assert_eq!(json["function"], "foo");
+        assert_eq!(json["summary"]["max_cycles"], 20);
+        assert_eq!(path["status"], "failed");
+        assert_eq!(path["message"], "panic");
+        assert_eq!(path["inputs"][0]["bytes"], serde_json::json!([0x2c, 0x01]));
+        assert_eq!(path["inputs"][0]["ty"]["Integer"], 16);
+        assert_eq!(path["registers"][0]["name"], "R0");
+        assert_eq!(
+            path["registers"][0]["bytes"],
+            serde_json::json!([7, 0, 0, 0])
+        );
+        assert_eq!(path["cycle_laps"][0]["cycles"], 5);
+        assert_eq!(path["cycle_laps"][0]["name"], "isr");
+        assert_eq!(path["provenance"][0]["origin"]["MemoryRead"], 0x4000_0000);
+    }
+
+    #[test]
+    fn test_path_keys() {
+        let ctx = DContext::new();
+        let json = json(&[result(&ctx)]);
+        let keys: BTreeSet<&str> = json["paths"][0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let expected = BTreeSet::from([
+            "path",
+            "status",
+            "message",
+            "inputs",
+            "constrained",
+            "input_ranges",
+            "registers",
+            "return_value",
+            "instruction_count",
+            "max_cycles",
+            "energy",
+            "instruction_cache",
+            "data_cache",
+            "branch_predictions",
+            "cycle_laps",
+            "reached",
+            "memory_delta",
+            "provenance",
+            "under_approximated",
+            "constraints",
+        ]);
+        assert_eq!(keys, expected);
+        let keys: BTreeSet<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(keys, BTreeSet::from(["function", "summary", "paths"]));
+    }
+
+    #[test]
+    fn test_path_status() {
+        let ctx = DContext::new();
+        let statuses = [
+            (PathStatus::Ok(None), "ok", serde_json::Value::Null),
+            (
+                PathStatus::Failed(ErrorReason {
+                    error_message: "panic".to_owned(),
+                }),
+                "failed",
+                "panic".into(),
+            ),
+            (
+                PathStatus::Unknown("x < 2".to_owned()),
+                "unknown",
+                "x < 2".into(),
+            ),
+            (
+                PathStatus::Truncated(Truncation::Paths(4)),
+                "truncated",
+                "explored the maximum of 4 paths".into(),
+            ),
+        ];
+        for (status, name, message) in statuses {
+            let json = json(&[VisualPathResult {
+                result: status,
+                ..result(&ctx)
+            }]);
+            assert_eq!(json["paths"][0]["status"], name);
+            assert_eq!(json["paths"][0]["message"], message);
+        }
+    }
+
+    #[test]
+    fn test_unmodeled_stats() {
+        let ctx = DContext::new();
+        let json = json(&[result(&ctx)]);
+        let path = &json["paths"][0];
+        for key in [
+            "return_value",
+            "energy",
+            "instruction_cache",
+            "data_cache",
+            "branch_predictions",
+        ] {
+            assert!(path[key].is_null(), "{key} is not null");
+        }
+        assert_eq!(path["input_ranges"], serde_json::json!([]));
+        assert_eq!(path["under_approximated"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_constraints() {
+        let ctx = DContext::new();
+        let json = json(&[result(&ctx)]);
+        let constraints = json["paths"][0]["constraints"].as_str().unwrap();
+        assert!(constraints.contains("(assert"), "{constraints}");
+        assert!(constraints.contains("x"), "{constraints}");
+    }
+}
@@ -21,14 +21,25 @@
 )]
 
 pub mod elf_util;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
 pub mod general_assembly;
+pub mod harness;
+pub mod intrinsics;
+pub mod json;
 pub mod memory;
+pub mod preconditions;
+pub mod query;
 //#[cfg(not(feature = "llvm"))]
 pub mod run_elf;
 #[cfg(feature = "llvm")]
 pub mod run_llvm;
+pub mod schedulability;
 pub mod smt;
+pub mod test_gen;
 #[cfg(feature = "llvm")]
 pub mod util;
 #[cfg(feature = "llvm")]
 pub mod vm;
+pub mod watch;
+pub mod wcet;
@@ -10,9 +10,11 @@
 //! to other memory models, and in general this memory model is slower compared
 //! to e.g. object memory. However, it may provide better performance in certain
 //! situations.
+use std::collections::BTreeSet;
+
 use tracing::trace;
 
-use super::{MemoryError, BITS_IN_BYTE};
+use super::{concat_bytes, MemoryError, SmtMap, BITS_IN_BYTE};
 use crate::{
     general_assembly::Endianness,
     smt::{DArray, DContext, DExpr},
@@ -22,7 +24,7 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct ArrayMemory {
     /// Reference to the context so new symbols can be created.
-    ctx: &'static DContext,
+    ctx: DContext,
 
     /// Size of a pointer.
     ptr_size: u32,
@@ -32,6 +34,12 @@ pub struct ArrayMemory {
 
     /// Memory endianness
     endianness: Endianness,
+
+    /// All byte addresses written to through a concrete address.
+    written: BTreeSet<u64>,
+
+    /// Writes made through a symbolic address and the number of bytes written.
+    symbolic_writes: Vec<(DExpr, u32)>,
 }
 
 impl ArrayMemory {
@@ -44,30 +52,17 @@ impl ArrayMemory {
         Ok(vec![addr.clone()])
     }
 
-    #[tracing::instrument(skip(self))]
-    pub fn read(&self, addr: &DExpr, bits: u32) -> Result<DExpr, MemoryError> {
-        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
-
-        let value = self.internal_read(addr, bits, self.ptr_size)?;
-        trace!("Read value: {value:?}");
-        Ok(value)
-    }
-
-    #[tracing::instrument(skip(self))]
-    pub fn write(&mut self, addr: &DExpr, value: DExpr) -> Result<(), MemoryError> {
-        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
-        self.internal_write(addr, value, self.ptr_size)
-    }
-
     /// Creates a new memory containing only uninitialized memory.
-    pub fn new(ctx: &'static DContext, ptr_size: u32, endianness: Endianness) -> Self {
+    pub fn new(ctx: &DContext, ptr_size: u32, endianness: Endianness) -> Self {
         let memory = DArray::new(ctx, ptr_size as usize, BITS_IN_BYTE as usize, "memory");
 
         Self {
-            ctx,
+            ctx: ctx.clone(),
             ptr_size,
             memory,
             endianness,
+            written: BTreeSet::new(),
+            symbolic_writes: Vec::new(),
         }
     }
 
@@ -103,14 +98,7 @@ impl ArrayMemory {
                 bytes.push(value);
             }
 
-            match self.endianness {
-                Endianness::Little => bytes.into_iter().reduce(|acc, v| v.concat(&acc)).unwrap(),
-                Endianness::Big => bytes
-                    .into_iter()
-                    .rev()
-                    .reduce(|acc, v| v.concat(&acc))
-                    .unwrap(),
-            }
+            concat_bytes(&self.endianness, bytes)
         };
 
         Ok(value)
@@ -133,6 +121,11 @@ impl ArrayMemory {
         assert_eq!(value.len() % BITS_IN_BYTE, 0);
 
         let num_bytes = value.len() / BITS_IN_BYTE;
+        match addr.get_constant() {
+            Some(base) => self.written.extend((0..num_bytes as u64).map(|n| base + n)),
+            None => self.symbolic_writes.push((addr.clone(), num_bytes)),
+        }
+
         for n in 0..num_bytes {
             let low_bit = n * BITS_IN_BYTE;
             let high_bit = (n + 1) * BITS_IN_BYTE - 1;
@@ -150,10 +143,57 @@ impl ArrayMemory {
     }
 }
 
+impl SmtMap for ArrayMemory {
+    #[tracing::instrument(skip(self))]
+    fn read(&self, addr: &DExpr, bits: u32) -> Result<DExpr, MemoryError> {
+        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
+
+        let value = self.internal_read(addr, bits, self.ptr_size)?;
+        trace!("Read value: {value:?}");
+        Ok(value)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn write(&mut self, addr: &DExpr, value: DExpr) -> Result<(), MemoryError> {
+        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
+        self.internal_write(addr, value, self.ptr_size)
+    }
+
+    fn written_addresses(&self) -> &BTreeSet<u64> {
+        &self.written
+    }
+
+    fn symbolic_writes(&self) -> &[(DExpr, u32)] {
+        &self.symbolic_writes
+    }
+
+    fn read_or_initial(&self, addr: u64, initial: &[Option<DExpr>]) -> DExpr {
+        let bytes = initial.iter().enumerate().map(|(offset, initial)| {
+            let byte_addr = addr + offset as u64;
+            let symbolic_addr = self.ctx.from_u64(byte_addr, self.ptr_size);
+            let stored = self.read_u8(&symbolic_addr);
+            match initial {
+                Some(initial) if !self.written.contains(&byte_addr) => {
+                    // The stored value is only used if a write through a
+                    // symbolic address may have covered the byte.
+                    self.symbolic_writes
+                        .iter()
+                        .fold(initial.clone(), |value, (write_addr, len)| {
+                            let len = self.ctx.from_u64(*len as u64, self.ptr_size);
+                            symbolic_addr.sub(write_addr).ult(&len).ite(&stored, &value)
+                        })
+                }
+                _ => stored,
+            }
+        });
+        concat_bytes(&self.endianness, bytes.collect())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::ArrayMemory;
-    use crate::{general_assembly::Endianness, smt::DContext};
+    use crate::{general_assembly::Endianness, memory::SmtMap, smt::DContext};
 
     fn setup_test_memory(endianness: Endianness) -> ArrayMemory {
         let ctx = Box::new(DContext::new());
@@ -248,4 +288,46 @@ mod test {
         let result = memory.read(&addr, 32).ok().unwrap();
         assert_eq!(result.get_constant().unwrap(), 0x01020304);
     }
+
+    #[test]
+    fn test_written_addresses() {
+        let mut memory = setup_test_memory(Endianness::Little);
+        let indata = memory.ctx.from_u64(0x01020304, 32);
+        let addr = memory.ctx.from_u64(0x10, 32);
+        memory.write(&addr, indata).ok();
+        let indata = memory.ctx.from_u64(0xff, 8);
+        let addr = memory.ctx.from_u64(0x20, 32);
+        memory.write(&addr, indata).ok();
+
+        let written: Vec<u64> = memory.written_addresses().iter().copied().collect();
+        assert_eq!(written, vec![0x10, 0x11, 0x12, 0x13, 0x20]);
+
+        let indata = memory.ctx.from_u64(0xff, 16);
+        let addr = memory.ctx.unconstrained(32, "addr");
+        memory.write(&addr, indata).ok();
+        assert_eq!(memory.symbolic_writes().len(), 1);
+        assert_eq!(memory.symbolic_writes()[0].1, 2);
+    }
+
+    #[test]
+    fn test_read_or_initial() {
+        let mut memory = setup_test_memory(Endianness::Little);
+        let fill = Some(memory.ctx.from_u64(0xff, 8));
+        let indata = memory.ctx.from_u64(0x12, 8);
+        let addr = memory.ctx.from_u64(0x11, 32);
+        memory.write(&addr, indata).ok();
+
+        let value = memory.read_or_initial(0x10, &[fill.clone(), fill.clone()]);
+        assert_eq!(value.simplify().get_constant(), Some(0x12ff));
+        // Bytes without an initial value are unconstrained.
+        let value = memory.read_or_initial(0x12, &[None]);
+        assert_eq!(value.simplify().get_constant(), None);
+
+        // A symbolic write may cover the byte.
+        let indata = memory.ctx.from_u64(0, 8);
+        let addr = memory.ctx.unconstrained(32, "addr");
+        memory.write(&addr, indata).ok();
+        let value = memory.read_or_initial(0x10, &[fill]);
+        assert_eq!(value.simplify().get_constant(), None);
+    }
 }
@@ -1,11 +1,20 @@
 mod array_memory;
 mod linear_allocator;
 mod object_memory;
+mod region_memory;
+mod routed_memory;
+
+use std::collections::BTreeSet;
 
 pub use array_memory::ArrayMemory;
 pub use object_memory::ObjectMemory;
+pub use region_memory::RegionMemory;
+pub use routed_memory::RoutedMemory;
 
-use crate::smt::SolverError;
+use crate::{
+    general_assembly::{Endianness, MemoryMapRegion, MemoryModel, RegionModel},
+    smt::{DContext, DExpr, SolverError},
+};
 
 /// The number of bits per byte the memory system expects.
 pub const BITS_IN_BYTE: u32 = 8;
@@ -28,6 +37,144 @@ pub fn to_bytes_u32(size: u32) -> Result<u32, MemoryError> {
     }
 }
 
+/// Byte addressable symbolic memory of a state.
+pub trait SmtMap {
+    /// Reads `bits` from `addr`.
+    fn read(&self, addr: &DExpr, bits: u32) -> Result<DExpr, MemoryError>;
+
+    /// Writes `value` to `addr`.
+    fn write(&mut self, addr: &DExpr, value: DExpr) -> Result<(), MemoryError>;
+
+    /// Returns all byte addresses that have been written to through a concrete
+    /// address, in ascending order.
+    fn written_addresses(&self) -> &BTreeSet<u64>;
+
+    /// Returns all writes made through a symbolic address together with the
+    /// number of bytes written.
+    ///
+    /// These need to be resolved against a model to know which bytes were
+    /// touched.
+    fn symbolic_writes(&self) -> &[(DExpr, u32)];
+
+    /// Reads `initial.len()` bytes from the concrete address `addr`.
+    ///
+    /// A byte with an initial value that has never been written is read as its
+    /// initial value instead of the unconstrained contents of the memory.
+    /// Bytes without an initial value are read from memory.
+    fn read_or_initial(&self, addr: u64, initial: &[Option<DExpr>]) -> DExpr;
+}
+
+/// Memory of a state in the model selected by [`MemoryModel`] and the memory
+/// map.
+#[derive(Debug, Clone)]
+pub enum Memory {
+    Array(ArrayMemory),
+    Region(RegionMemory),
+    Routed(RoutedMemory),
+}
+
+impl Memory {
+    /// Creates a new memory containing only uninitialized memory, or the
+    /// contents of the backed regions in `map`.
+    ///
+    /// The regions in `map` use their own model and all other memory uses
+    /// `model`.
+    pub fn new(
+        model: MemoryModel,
+        map: &[MemoryMapRegion],
+        ctx: &DContext,
+        ptr_size: u32,
+        endianness: Endianness,
+    ) -> Self {
+        let other = match model {
+            MemoryModel::Array => Self::Array(ArrayMemory::new(ctx, ptr_size, endianness.clone())),
+            MemoryModel::Region => {
+                Self::Region(RegionMemory::new(ctx, ptr_size, endianness.clone()))
+            }
+        };
+        if map.is_empty() {
+            return other;
+        }
+
+        let regions = map
+            .iter()
+            .map(|region| {
+                let memory = match &region.model {
+                    RegionModel::Array => {
+                        Self::Array(ArrayMemory::new(ctx, ptr_size, endianness.clone()))
+                    }
+                    RegionModel::Region => {
+                        Self::Region(RegionMemory::new(ctx, ptr_size, endianness.clone()))
+                    }
+                    RegionModel::Backed(data) => Self::Region(RegionMemory::backed(
+                        ctx,
+                        ptr_size,
+                        endianness.clone(),
+                        region.start,
+                        data.clone(),
+                    )),
+                };
+                (region.start..region.end, memory)
+            })
+            .collect();
+        Self::Routed(RoutedMemory::new(ctx, ptr_size, endianness, regions, other))
+    }
+}
+
+impl SmtMap for Memory {
+    fn read(&self, addr: &DExpr, bits: u32) -> Result<DExpr, MemoryError> {
+        match self {
+            Self::Array(memory) => memory.read(addr, bits),
+            Self::Region(memory) => memory.read(addr, bits),
+            Self::Routed(memory) => memory.read(addr, bits),
+        }
+    }
+
+    fn write(&mut self, addr: &DExpr, value: DExpr) -> Result<(), MemoryError> {
+        match self {
+            Self::Array(memory) => memory.write(addr, value),
+            Self::Region(memory) => memory.write(addr, value),
+            Self::Routed(memory) => memory.write(addr, value),
+        }
+    }
+
+    fn written_addresses(&self) -> &BTreeSet<u64> {
+        match self {
+            Self::Array(memory) => memory.written_addresses(),
+            Self::Region(memory) => memory.written_addresses(),
+            Self::Routed(memory) => memory.written_addresses(),
+        }
+    }
+
+    fn symbolic_writes(&self) -> &[(DExpr, u32)] {
+        match self {
+            Self::Array(memory) => memory.symbolic_writes(),
+            Self::Region(memory) => memory.symbolic_writes(),
+            Self::Routed(memory) => memory.symbolic_writes(),
+        }
+    }
+
+    fn read_or_initial(&self, addr: u64, initial: &[Option<DExpr>]) -> DExpr {
+        match self {
+            Self::Array(memory) => memory.read_or_initial(addr, initial),
+            Self::Region(memory) => memory.read_or_initial(addr, initial),
+            Self::Routed(memory) => memory.read_or_initial(addr, initial),
+        }
+    }
+}
+
+/// Combines the bytes read from ascending addresses into a single value.
+fn concat_bytes(endianness: &Endianness, bytes: Vec<DExpr>) -> DExpr {
+    match endianness {
+        Endianness::Little => bytes.into_iter().reduce(|acc, v| v.concat(&acc)).unwrap(),
+        Endianness::Big => bytes
+            .into_iter()
+            .rev()
+            .reduce(|acc, v| v.concat(&acc))
+            .unwrap(),
+    }
+}
+
 /// Error representing an issue when performing memory operations.
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
 pub enum MemoryError {
@@ -26,7 +26,7 @@ impl MemoryObject {
 
 #[derive(Debug, Clone)]
 pub struct ObjectMemory {
-    ctx: &'static DContext,
+    ctx: DContext,
 
     /// Allocator is used to generate new addresses.
     allocator: LinearAllocator,
@@ -41,9 +41,9 @@ pub struct ObjectMemory {
 }
 
 impl ObjectMemory {
-    pub fn new(ctx: &'static DContext, ptr_size: u32, solver: DSolver) -> Self {
+    pub fn new(ctx: &DContext, ptr_size: u32, solver: DSolver) -> Self {
         Self {
-            ctx,
+            ctx: ctx.clone(),
             allocator: LinearAllocator::new(),
             objects: BTreeMap::new(),
             ptr_size,
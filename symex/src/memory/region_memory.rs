@@ -0,0 +1,378 @@
+//! Region memory.
+//!
+//! This memory model stores the bytes written through concrete addresses as
+//! separate values grouped in fixed size regions instead of in a single
+//! theories-of-array. A read of a written byte is the written value itself, so
+//! reads of memory written with constants stay constant without the solver
+//! having to simplify a chain of array stores.
+//!
+//! The regions are shared between the paths forked from a state and a region
+//! is only copied when a path first writes to it, which makes forking cheap
+//! for memory heavy firmware.
+//!
+//! Bytes that have not been written are read from an unconstrained array that
+//! is never written to, or from the concrete contents of a backed memory such
+//! as flash. Writes through symbolic addresses are kept in a log
+//! and a read selects the latest write in the log that may have written the
+//! read address, which makes symbolic writes more expensive than in
+//! [`ArrayMemory`](super::ArrayMemory).
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use tracing::trace;
+
+use super::{concat_bytes, MemoryError, SmtMap, BITS_IN_BYTE};
+use crate::{
+    general_assembly::Endianness,
+    smt::{DArray, DContext, DExpr},
+};
+
+/// Number of bytes in a region, a region is copied by the first write to it
+/// after a fork.
+const REGION_SIZE: u64 = 0x100;
+
+/// A byte written through a concrete address.
+#[derive(Debug, Clone)]
+struct Byte {
+    value: DExpr,
+
+    /// Number of writes through symbolic addresses made before this byte was
+    /// written, only later writes may have overwritten it.
+    generation: usize,
+}
+
+/// Bytes written in a region of [`REGION_SIZE`] bytes.
+#[derive(Debug, Clone, Default)]
+struct Region {
+    bytes: HashMap<u64, Byte>,
+}
+
+/// Memory store of separately allocated regions that are copied on write.
+#[derive(Debug, Clone)]
+pub struct RegionMemory {
+    /// Reference to the context so new symbols can be created.
+    ctx: DContext,
+
+    /// Size of a pointer.
+    ptr_size: u32,
+
+    /// Memory endianness
+    endianness: Endianness,
+
+    /// Contents of the bytes that have not been written.
+    initial: DArray,
+
+    /// Address and concrete contents of the bytes that have not been written
+    /// in a backed memory, these take precedence over `initial`.
+    backing: Option<(u64, Arc<[u8]>)>,
+
+    /// Regions with at least one written byte indexed by their number.
+    regions: BTreeMap<u64, Arc<Region>>,
+
+    /// All byte addresses written to through a concrete address.
+    written: BTreeSet<u64>,
+
+    /// Writes made through a symbolic address and the number of bytes written.
+    symbolic_writes: Vec<(DExpr, u32)>,
+
+    /// Bytes written through a symbolic address and their address, oldest
+    /// first.
+    symbolic_bytes: Vec<(DExpr, DExpr)>,
+}
+
+impl RegionMemory {
+    /// Creates a new memory containing only uninitialized memory.
+    pub fn new(ctx: &DContext, ptr_size: u32, endianness: Endianness) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            ptr_size,
+            endianness,
+            initial: DArray::new(ctx, ptr_size as usize, BITS_IN_BYTE as usize, "memory"),
+            backing: None,
+            regions: BTreeMap::new(),
+            written: BTreeSet::new(),
+            symbolic_writes: Vec::new(),
+            symbolic_bytes: Vec::new(),
+        }
+    }
+
+    /// Creates a new memory where the bytes from `start` that have not been
+    /// written are read from `data`.
+    ///
+    /// Reads through a symbolic address read the unconstrained contents of
+    /// memory instead of `data`.
+    pub fn backed(
+        ctx: &DContext,
+        ptr_size: u32,
+        endianness: Endianness,
+        start: u64,
+        data: Arc<[u8]>,
+    ) -> Self {
+        Self {
+            backing: Some((start, data)),
+            ..Self::new(ctx, ptr_size, endianness)
+        }
+    }
+
+    /// Returns the number of regions that have been written.
+    pub fn regions(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Applies the writes through symbolic addresses made after `generation`
+    /// writes to `value` stored at `addr`.
+    fn overlay(&self, addr: &DExpr, value: DExpr, generation: usize) -> DExpr {
+        self.symbolic_bytes[generation..]
+            .iter()
+            .fold(value, |value, (write_addr, byte)| {
+                addr.eq(write_addr).ite(byte, &value)
+            })
+    }
+
+    /// Reads the byte at the concrete address `addr`.
+    fn read_concrete_u8(&self, addr: u64) -> DExpr {
+        let symbolic_addr = self.ctx.from_u64(addr, self.ptr_size);
+        let byte = self
+            .regions
+            .get(&(addr / REGION_SIZE))
+            .and_then(|region| region.bytes.get(&addr));
+        match byte {
+            Some(byte) => self.overlay(&symbolic_addr, byte.value.clone(), byte.generation),
+            None => {
+                let backed = self.backing.as_ref().and_then(|(start, data)| {
+                    let offset = addr.checked_sub(*start)?;
+                    data.get(offset as usize)
+                });
+                let value = match backed {
+                    Some(byte) => self.ctx.from_u64(*byte as u64, BITS_IN_BYTE),
+                    None => self.initial.read(&symbolic_addr),
+                };
+                self.overlay(&symbolic_addr, value, 0)
+            }
+        }
+    }
+
+    /// Reads an u8 from the given address.
+    fn read_u8(&self, addr: &DExpr) -> DExpr {
+        if let Some(addr) = addr.get_constant() {
+            return self.read_concrete_u8(addr);
+        }
+
+        // The address may be any of the written bytes or an unwritten byte.
+        let unwritten = self.overlay(addr, self.initial.read(addr), 0);
+        self.regions
+            .values()
+            .flat_map(|region| region.bytes.iter())
+            .fold(unwritten, |value, (byte_addr, byte)| {
+                let byte_addr = self.ctx.from_u64(*byte_addr, self.ptr_size);
+                let written = self.overlay(addr, byte.value.clone(), byte.generation);
+                addr.eq(&byte_addr).ite(&written, &value)
+            })
+    }
+
+    /// Writes an u8 value to the given address.
+    fn write_u8(&mut self, addr: &DExpr, val: DExpr) {
+        match addr.get_constant() {
+            Some(addr) => {
+                let byte = Byte {
+                    value: val,
+                    generation: self.symbolic_bytes.len(),
+                };
+                let region = self.regions.entry(addr / REGION_SIZE).or_default();
+                Arc::make_mut(region).bytes.insert(addr, byte);
+                self.written.insert(addr);
+            }
+            None => self.symbolic_bytes.push((addr.clone(), val)),
+        }
+    }
+}
+
+impl SmtMap for RegionMemory {
+    #[tracing::instrument(skip(self))]
+    fn read(&self, addr: &DExpr, bits: u32) -> Result<DExpr, MemoryError> {
+        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
+
+        let value = if bits < BITS_IN_BYTE {
+            self.read_u8(addr).slice(bits - 1, 0)
+        } else {
+            // Ensure we only read full bytes now.
+            assert_eq!(bits % BITS_IN_BYTE, 0, "Must read bytes, if bits >= 8");
+            let bytes = (0..bits / BITS_IN_BYTE)
+                .map(|byte| {
+                    let offset = self.ctx.from_u64(byte as u64, self.ptr_size);
+                    self.read_u8(&addr.add(&offset))
+                })
+                .collect();
+            concat_bytes(&self.endianness, bytes)
+        };
+        trace!("Read value: {value:?}");
+        Ok(value)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn write(&mut self, addr: &DExpr, value: DExpr) -> Result<(), MemoryError> {
+        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
+
+        // Check if we should zero extend the value (if it less than 8-bits).
+        let value = if value.len() < BITS_IN_BYTE {
+            value.zero_ext(BITS_IN_BYTE)
+        } else {
+            value
+        };
+
+        // Ensure the value we write is a multiple of `BITS_IN_BYTE`.
+        assert_eq!(value.len() % BITS_IN_BYTE, 0);
+
+        let num_bytes = value.len() / BITS_IN_BYTE;
+        if addr.get_constant().is_none() {
+            self.symbolic_writes.push((addr.clone(), num_bytes));
+        }
+
+        for n in 0..num_bytes {
+            let byte = value.slice(n * BITS_IN_BYTE, (n + 1) * BITS_IN_BYTE - 1);
+            let offset = match self.endianness {
+                Endianness::Little => n,
+                Endianness::Big => num_bytes - 1 - n,
+            };
+            let offset = self.ctx.from_u64(offset as u64, self.ptr_size);
+            self.write_u8(&addr.add(&offset), byte);
+        }
+
+        Ok(())
+    }
+
+    fn written_addresses(&self) -> &BTreeSet<u64> {
+        &self.written
+    }
+
+    fn symbolic_writes(&self) -> &[(DExpr, u32)] {
+        &self.symbolic_writes
+    }
+
+    fn read_or_initial(&self, addr: u64, initial: &[Option<DExpr>]) -> DExpr {
+        let bytes = initial.iter().enumerate().map(|(offset, initial)| {
+            let byte_addr = addr + offset as u64;
+            match initial {
+                Some(initial) if !self.written.contains(&byte_addr) => {
+                    let symbolic_addr = self.ctx.from_u64(byte_addr, self.ptr_size);
+                    self.overlay(&symbolic_addr, initial.clone(), 0)
+                }
+                _ => self.read_concrete_u8(byte_addr),
+            }
+        });
+        concat_bytes(&self.endianness, bytes.collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RegionMemory;
+    use crate::{general_assembly::Endianness, memory::SmtMap, smt::DContext};
+
+    fn setup_test_memory(endianness: Endianness) -> RegionMemory {
+        let ctx = Box::new(DContext::new());
+        let ctx = Box::leak(ctx);
+        RegionMemory::new(ctx, 32, endianness)
+    }
+
+    #[test]
+    fn test_read_write() {
+        let mut memory = setup_test_memory(Endianness::Little);
+        let indata = memory.ctx.from_u64(0x01020304, 32);
+        let addr = memory.ctx.from_u64(0xfe, 32);
+        memory.write(&addr, indata).ok();
+
+        // The write crosses the boundary of two regions.
+        assert_eq!(memory.regions(), 2);
+        let result = memory.read(&addr, 32).unwrap();
+        assert_eq!(result.get_constant(), Some(0x01020304));
+        let addr = memory.ctx.from_u64(0xff, 32);
+        let result = memory.read(&addr, 16).unwrap();
+        assert_eq!(result.get_constant(), Some(0x0203));
+
+        let mut memory = setup_test_memory(Endianness::Big);
+        let indata = memory.ctx.from_u64(0x01020304, 32);
+        let addr = memory.ctx.from_u64(0x10, 32);
+        memory.write(&addr, indata).ok();
+        let result = memory.read(&addr, 8).unwrap();
+        assert_eq!(result.get_constant(), Some(0x01));
+        let result = memory.read(&addr, 32).unwrap();
+        assert_eq!(result.get_constant(), Some(0x01020304));
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let mut memory = setup_test_memory(Endianness::Little);
+        let addr = memory.ctx.from_u64(0x10, 32);
+        memory.write(&addr, memory.ctx.from_u64(1, 8)).ok();
+
+        let mut fork = memory.clone();
+        fork.write(&addr, memory.ctx.from_u64(2, 8)).ok();
+        assert_eq!(memory.read(&addr, 8).unwrap().get_constant(), Some(1));
+        assert_eq!(fork.read(&addr, 8).unwrap().get_constant(), Some(2));
+    }
+
+    #[test]
+    fn test_symbolic_address() {
+        let mut memory = setup_test_memory(Endianness::Little);
+        let addr = memory.ctx.from_u64(0x10, 32);
+        let symbolic_addr = memory.ctx.unconstrained(32, "addr");
+        memory.write(&addr, memory.ctx.from_u64(1, 8)).ok();
+        memory.write(&symbolic_addr, memory.ctx.from_u64(2, 8)).ok();
+
+        // The symbolic write may have overwritten the byte.
+        assert_eq!(
+            memory.read(&addr, 8).unwrap().simplify().get_constant(),
+            None
+        );
+        assert_eq!(memory.symbolic_writes().len(), 1);
+
+        // Until it is written again.
+        memory.write(&addr, memory.ctx.from_u64(3, 8)).ok();
+        assert_eq!(memory.read(&addr, 8).unwrap().get_constant(), Some(3));
+
+        // A read through the symbolic address depends on the address.
+        let value = memory.read(&symbolic_addr, 8).unwrap().simplify();
+        assert_eq!(value.get_constant(), None);
+    }
+
+    #[test]
+    fn test_backed() {
+        let ctx = Box::leak(Box::new(DContext::new()));
+        let data = vec![0x01, 0x02, 0x03, 0x04];
+        let mut memory = RegionMemory::backed(ctx, 32, Endianness::Little, 0x100, data.into());
+        let addr = ctx.from_u64(0x100, 32);
+        assert_eq!(
+            memory.read(&addr, 32).unwrap().get_constant(),
+            Some(0x04030201)
+        );
+
+        // Written bytes shadow the contents and bytes outside are unconstrained.
+        memory
+            .write(&ctx.from_u64(0x101, 32), ctx.from_u64(0xff, 8))
+            .ok();
+        assert_eq!(
+            memory.read(&addr, 32).unwrap().get_constant(),
+            Some(0x0403ff01)
+        );
+        let addr = ctx.from_u64(0x104, 32);
+        assert_eq!(memory.read(&addr, 8).unwrap().get_constant(), None);
+    }
+
+    #[test]
+    fn test_read_or_initial() {
+        let mut memory = setup_test_memory(Endianness::Little);
+        let fill = Some(memory.ctx.from_u64(0xff, 8));
+        let indata = memory.ctx.from_u64(0x12, 8);
+        let addr = memory.ctx.from_u64(0x11, 32);
+        memory.write(&addr, indata).ok();
+
+        let value = memory.read_or_initial(0x10, &[fill.clone(), fill.clone()]);
+        assert_eq!(value.simplify().get_constant(), Some(0x12ff));
+        // Bytes without an initial value are unconstrained.
+        let value = memory.read_or_initial(0x12, &[None]);
+        assert_eq!(value.simplify().get_constant(), None);
+    }
+}
@@ -0,0 +1,276 @@
+//! Routed memory.
+//!
+//! Composes the memories of the regions in the memory map of a device, such as
+//! an [`ArrayMemory`](super::ArrayMemory) for peripherals, a
+//! [`RegionMemory`](super::RegionMemory) for RAM and a backed
+//! [`RegionMemory`](super::RegionMemory) for flash. An access through a
+//! concrete address is routed to the memory of each accessed byte.
+//!
+//! A read through a symbolic address reads all memories and selects the value
+//! from the memory of the region that contains the address, a write through a
+//! symbolic address is written to all memories as each memory is only read for
+//! the addresses routed to it. An access through a symbolic address that
+//! crosses the border of two regions is routed to the region of its first
+//! byte.
+use std::{collections::BTreeSet, ops::Range};
+
+use super::{concat_bytes, Memory, MemoryError, SmtMap, BITS_IN_BYTE};
+use crate::{
+    general_assembly::Endianness,
+    smt::{DContext, DExpr},
+};
+
+/// Memory store that routes accesses to the memory of each region.
+#[derive(Debug, Clone)]
+pub struct RoutedMemory {
+    /// Reference to the context so new symbols can be created.
+    ctx: DContext,
+
+    /// Size of a pointer.
+    ptr_size: u32,
+
+    /// Memory endianness
+    endianness: Endianness,
+
+    /// Address ranges of the regions, the first range that contains an
+    /// address routes it to the memory with the same index.
+    ranges: Vec<Range<u64>>,
+
+    /// Memories of the regions followed by the memory of all other addresses.
+    memories: Vec<Memory>,
+
+    /// All byte addresses written to through a concrete address.
+    written: BTreeSet<u64>,
+
+    /// Writes made through a symbolic address and the number of bytes written.
+    symbolic_writes: Vec<(DExpr, u32)>,
+}
+
+impl RoutedMemory {
+    /// Creates a memory that routes the addresses in each region to its memory
+    /// and all other addresses to `other`.
+    pub fn new(
+        ctx: &DContext,
+        ptr_size: u32,
+        endianness: Endianness,
+        regions: Vec<(Range<u64>, Memory)>,
+        other: Memory,
+    ) -> Self {
+        let (ranges, mut memories): (Vec<_>, Vec<_>) = regions.into_iter().unzip();
+        memories.push(other);
+        Self {
+            ctx: ctx.clone(),
+            ptr_size,
+            endianness,
+            ranges,
+            memories,
+            written: BTreeSet::new(),
+            symbolic_writes: Vec::new(),
+        }
+    }
+
+    /// Returns the index of the memory `addr` is routed to.
+    fn route(&self, addr: u64) -> usize {
+        self.ranges
+            .iter()
+            .position(|range| range.contains(&addr))
+            .unwrap_or(self.ranges.len())
+    }
+
+    /// Returns the index of the memory all of the `bytes` bytes from `addr`
+    /// are routed to, `None` if they are routed to different memories.
+    fn route_all(&self, addr: u64, bytes: u32) -> Option<usize> {
+        let route = self.route(addr);
+        (1..bytes as u64)
+            .all(|offset| self.route(addr + offset) == route)
+            .then_some(route)
+    }
+
+    /// Returns a condition that is true if `addr` is in `range`.
+    fn contains(&self, range: &Range<u64>, addr: &DExpr) -> DExpr {
+        let start = self.ctx.from_u64(range.start, self.ptr_size);
+        let last = self.ctx.from_u64(range.end - 1, self.ptr_size);
+        addr.ugte(&start).and(&addr.ulte(&last))
+    }
+}
+
+impl SmtMap for RoutedMemory {
+    #[tracing::instrument(skip(self))]
+    fn read(&self, addr: &DExpr, bits: u32) -> Result<DExpr, MemoryError> {
+        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
+        let bytes = bits.div_ceil(BITS_IN_BYTE);
+
+        let Some(base) = addr.get_constant() else {
+            // Earlier regions take precedence, so they are selected last.
+            let other = self
+                .memories
+                .last()
+                .expect("there is a memory for all other addresses");
+            let mut value = other.read(addr, bits)?;
+            for (range, memory) in self.ranges.iter().zip(&self.memories).rev() {
+                if range.is_empty() {
+                    continue;
+                }
+                value = self
+                    .contains(range, addr)
+                    .ite(&memory.read(addr, bits)?, &value);
+            }
+            return Ok(value);
+        };
+
+        if let Some(route) = self.route_all(base, bytes) {
+            return self.memories[route].read(addr, bits);
+        }
+        let bytes = (0..bytes as u64)
+            .map(|offset| {
+                let byte_addr = self.ctx.from_u64(base + offset, self.ptr_size);
+                self.memories[self.route(base + offset)].read(&byte_addr, BITS_IN_BYTE)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(concat_bytes(&self.endianness, bytes))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn write(&mut self, addr: &DExpr, value: DExpr) -> Result<(), MemoryError> {
+        assert_eq!(addr.len(), self.ptr_size, "passed wrong sized address");
+
+        // Check if we should zero extend the value (if it less than 8-bits).
+        let value = if value.len() < BITS_IN_BYTE {
+            value.zero_ext(BITS_IN_BYTE)
+        } else {
+            value
+        };
+
+        // Ensure the value we write is a multiple of `BITS_IN_BYTE`.
+        assert_eq!(value.len() % BITS_IN_BYTE, 0);
+
+        let num_bytes = value.len() / BITS_IN_BYTE;
+        let Some(base) = addr.get_constant() else {
+            self.symbolic_writes.push((addr.clone(), num_bytes));
+            for memory in &mut self.memories {
+                memory.write(addr, value.clone())?;
+            }
+            return Ok(());
+        };
+
+        self.written
+            .extend((0..num_bytes as u64).map(|offset| base + offset));
+        if let Some(route) = self.route_all(base, num_bytes) {
+            return self.memories[route].write(addr, value);
+        }
+        for n in 0..num_bytes {
+            let byte = value.slice(n * BITS_IN_BYTE, (n + 1) * BITS_IN_BYTE - 1);
+            let offset = match self.endianness {
+                Endianness::Little => n,
+                Endianness::Big => num_bytes - 1 - n,
+            } as u64;
+            let byte_addr = self.ctx.from_u64(base + offset, self.ptr_size);
+            let route = self.route(base + offset);
+            self.memories[route].write(&byte_addr, byte)?;
+        }
+        Ok(())
+    }
+
+    fn written_addresses(&self) -> &BTreeSet<u64> {
+        &self.written
+    }
+
+    fn symbolic_writes(&self) -> &[(DExpr, u32)] {
+        &self.symbolic_writes
+    }
+
+    fn read_or_initial(&self, addr: u64, initial: &[Option<DExpr>]) -> DExpr {
+        let bytes = initial.iter().enumerate().map(|(offset, initial)| {
+            let byte_addr = addr + offset as u64;
+            self.memories[self.route(byte_addr)]
+                .read_or_initial(byte_addr, std::slice::from_ref(initial))
+        });
+        concat_bytes(&self.endianness, bytes.collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RoutedMemory;
+    use crate::{
+        general_assembly::{Endianness, MemoryMapRegion, MemoryModel, RegionModel},
+        memory::{Memory, SmtMap},
+        smt::{DContext, DSolver},
+    };
+
+    fn setup_test_memory() -> (&'static DContext, Memory) {
+        let ctx = Box::leak(Box::new(DContext::new()));
+        let map = [
+            MemoryMapRegion::backed(0x0, vec![0x01, 0x02, 0x03, 0x04]),
+            MemoryMapRegion::new(0x2000_0000, 0x2000_1000, RegionModel::Region),
+            MemoryMapRegion::new(0x4000_0000, 0x4000_0100, RegionModel::Array),
+        ];
+        let memory = Memory::new(MemoryModel::Array, &map, ctx, 32, Endianness::Little);
+        (ctx, memory)
+    }
+
+    #[test]
+    fn test_routing() {
+        let (ctx, mut memory) = setup_test_memory();
+        let Memory::Routed(RoutedMemory { memories, .. }) = &memory else {
+            panic!("memory with a memory map is not routed");
+        };
+        assert!(matches!(memories[..], [
+            Memory::Region(_),
+            Memory::Region(_),
+            Memory::Array(_),
+            Memory::Array(_)
+        ]));
+
+        // Flash is read from its contents.
+        let flash = ctx.from_u64(0x0, 32);
+        assert_eq!(
+            memory.read(&flash, 32).unwrap().get_constant(),
+            Some(0x04030201)
+        );
+
+        // A write that crosses the end of the flash is split.
+        let addr = ctx.from_u64(0x2, 32);
+        memory.write(&addr, ctx.from_u64(0xaabb_ccdd, 32)).ok();
+        assert_eq!(
+            memory.read(&flash, 32).unwrap().get_constant(),
+            Some(0xccdd0201)
+        );
+        assert_eq!(
+            memory.read(&addr, 32).unwrap().get_constant(),
+            Some(0xaabbccdd)
+        );
+        let written: Vec<u64> = memory.written_addresses().iter().copied().collect();
+        assert_eq!(written, vec![0x2, 0x3, 0x4, 0x5]);
+
+        let ram = ctx.from_u64(0x2000_0000, 32);
+        memory.write(&ram, ctx.from_u64(0x1234, 16)).ok();
+        assert_eq!(memory.read(&ram, 16).unwrap().get_constant(), Some(0x1234));
+
+        // Bytes with an initial value are read from the memory of their region.
+        let fill = Some(ctx.from_u64(0xff, 8));
+        let value = memory.read_or_initial(0x1fff_ffff, &[fill.clone(), fill]);
+        assert_eq!(value.simplify().get_constant(), Some(0x34ff));
+    }
+
+    #[test]
+    fn test_symbolic_address() {
+        let (ctx, mut memory) = setup_test_memory();
+        let addr = ctx.unconstrained(32, "addr");
+        memory.write(&addr, ctx.from_u64(0x12, 8)).ok();
+        assert_eq!(memory.symbolic_writes().len(), 1);
+
+        // The symbolic write may have written any region.
+        for region in [0x0, 0x2000_0000, 0x4000_0000, 0x6000_0000] {
+            let region = ctx.from_u64(region, 32);
+            let value = memory.read(&region, 8).unwrap().simplify();
+            assert_eq!(value.get_constant(), None);
+        }
+        // Reading the written address gives the written value whichever
+        // region it is in.
+        let value = memory.read(&addr, 8).unwrap().simplify();
+        let written = ctx.from_u64(0x12, 8);
+        let solver = DSolver::new(ctx);
+        assert!(solver.must_be_equal(&value, &written).unwrap());
+    }
+}
@@ -0,0 +1,498 @@
+//! Preconditions of a function inferred from the paths of a run.
+//!
+//! When [`RunConfig::infer_preconditions`](crate::general_assembly::RunConfig::infer_preconditions)
+//! is set, the range of unsigned values each variable marked as symbolic can
+//! take on a path is solved for at the end of the path, see
+//! [`InputRange`]. A [`PreconditionReport`] combines the ranges of all paths:
+//!
+//! - The values of an input that no path which did not fail accepts can never
+//!   lead to a successful return, so the ranges of the paths that did not fail
+//!   are a precondition the callers of the function implicitly rely on.
+//! - A failing path only fails for the inputs in its ranges, so the negation of
+//!   the ranges of each failing path is a candidate precondition.
+//!
+//! ```no_run
+//! # use symex::{run_elf::ElfOptions, preconditions};
+//! let report = preconditions::analyze(
+//!     "target/thumbv6m-none-eabi/release/examples/foo",
+//!     "foo",
+//!     &ElfOptions::default(),
+//! )
+//! .unwrap();
+//! for assumption in report.assumptions() {
+//!     println!("{assumption}");
+//! }
+//! ```
+//!
+//! The ranges bound each input separately, so they over approximate the
+//! inputs of a path that constrains several inputs together, such as
+//! `x + y < 10`. The candidate preconditions are a starting point for
+//! `assume`s or documentation and should be reviewed before they are used.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    path::Path,
+};
+
+use colored::Colorize;
+use indenter::indented;
+use serde::Serialize;
+
+use crate::{
+    elf_util::{PathStatus, VisualPathResult},
+    general_assembly::{arch::Arch, range_analysis::Interval, state::GAState, GAError},
+    run_elf::{run_elf_with_options, ElfOptions},
+    smt::DExpr,
+};
+
+/// Runs all paths of `function` in the elf file at `path` with `options`,
+/// inferring the ranges of the inputs of each path, and reports the inferred
+/// preconditions.
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn analyze<P: AsRef<Path>>(
+    path: P,
+    function: &str,
+    options: &ElfOptions,
+) -> Result<PreconditionReport, GAError> {
+    let options = ElfOptions {
+        infer_preconditions: true,
+        ..options.clone()
+    };
+    let results = run_elf_with_options(path, function, &options)?;
+    Ok(PreconditionReport::new(&results))
+}
+
+/// The values a variable marked as symbolic can take on a path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputRange {
+    /// Name of the variable.
+    pub name: String,
+
+    /// Size of the variable in bits.
+    pub bits: u32,
+
+    /// Smallest and largest unsigned value of the variable on the path.
+    pub range: Interval,
+}
+
+impl InputRange {
+    /// Checks if the range contains every value of the variable.
+    pub fn is_unconstrained(&self) -> bool {
+        self.range == Interval::top(self.bits)
+    }
+}
+
+/// Solves for the range of each variable marked as symbolic in `state`, only
+/// the variables the path condition depends on are searched.
+pub(crate) fn input_ranges<A: Arch>(state: &GAState<A>) -> Result<Vec<InputRange>, GAError> {
+    let path_condition = state.constraints.path_condition();
+    let mut ranges = vec![];
+    for var in &state.marked_symbolic {
+        let (Some(name), bits @ 1..=64) = (&var.name, var.value.len()) else {
+            continue;
+        };
+        let range = match var.value.shares_symbols(&path_condition) {
+            true => solve_range(state, &var.value)?,
+            false => Interval::top(bits),
+        };
+        ranges.push(InputRange {
+            name: name.clone(),
+            bits,
+            range,
+        });
+    }
+    Ok(ranges)
+}
+
+/// Searches for the smallest and largest unsigned value of `value` under the
+/// constraints of `state`.
+fn solve_range<A: Arch>(state: &GAState<A>, value: &DExpr) -> Result<Interval, GAError> {
+    let bits = value.len();
+    let top = Interval::top(bits);
+    let sat = |constraint: DExpr| state.constraints.is_sat_with_constraint(&constraint);
+    let constant = |value: u64| state.ctx.from_u64(value, bits);
+
+    let (mut low, mut high) = (top.min, top.max);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match sat(value.ulte(&constant(mid)))? {
+            true => high = mid,
+            false => low = mid + 1,
+        }
+    }
+    let min = low;
+
+    let mut high = top.max;
+    while low < high {
+        let mid = high - (high - low) / 2;
+        match sat(value.ugte(&constant(mid)))? {
+            true => low = mid,
+            false => high = mid - 1,
+        }
+    }
+    Ok(Interval::new(min, high))
+}
+
+/// Preconditions inferred from the input ranges of the paths of a run.
+#[derive(Debug, Clone)]
+pub struct PreconditionReport {
+    /// Number of paths in the run.
+    pub paths: usize,
+
+    /// Number of paths that failed.
+    pub failed: usize,
+
+    /// Number of paths that did not run to completion, as they were truncated
+    /// or the solver could not decide them. Their ranges are counted as the
+    /// ranges of paths that did not fail.
+    pub incomplete: usize,
+
+    /// Values of each input accepted by the paths that did not fail, for the
+    /// inputs that not every value is accepted for.
+    pub accepted: Vec<AcceptedValues>,
+
+    /// Inputs each failing path fails for.
+    pub failures: Vec<FailingInputs>,
+}
+
+/// Values of an input that some path which did not fail accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptedValues {
+    /// Name of the input.
+    pub name: String,
+
+    /// Size of the input in bits.
+    pub bits: u32,
+
+    /// Ranges of accepted unsigned values, sorted and not overlapping.
+    pub ranges: Vec<Interval>,
+}
+
+/// The inputs a failing path fails for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailingInputs {
+    /// Which path this is.
+    pub path: usize,
+
+    /// Why the path failed.
+    pub reason: String,
+
+    /// Ranges of the inputs that the path constrains.
+    pub inputs: Vec<InputRange>,
+}
+
+impl PreconditionReport {
+    /// Combines the input ranges of `results`.
+    pub fn new(results: &[VisualPathResult]) -> Self {
+        let mut names: BTreeMap<&str, u32> = BTreeMap::new();
+        for input in results.iter().flat_map(|result| &result.input_ranges) {
+            names.insert(&input.name, input.bits);
+        }
+
+        let passing: Vec<&VisualPathResult> = results
+            .iter()
+            .filter(|result| !matches!(result.result, PathStatus::Failed(_)))
+            .collect();
+        let accepted = names
+            .into_iter()
+            .filter_map(|(name, bits)| {
+                let mut ranges = vec![];
+                for result in &passing {
+                    // A path that does not mark the input accepts every value.
+                    let range = result
+                        .input_ranges
+                        .iter()
+                        .find(|input| input.name == name)
+                        .map_or(Interval::top(bits), |input| input.range);
+                    ranges.push(range);
+                }
+                // Without a path that did not fail there is nothing to accept.
+                let ranges = merge(ranges);
+                (!ranges.is_empty() && ranges != [Interval::top(bits)]).then(|| AcceptedValues {
+                    name: name.to_owned(),
+                    bits,
+                    ranges,
+                })
+            })
+            .collect();
+
+        let failures = results
+            .iter()
+            .filter_map(|result| match &result.result {
+                PathStatus::Failed(reason) => Some(FailingInputs {
+                    path: result.path,
+                    reason: reason.error_message.clone(),
+                    inputs: result
+                        .input_ranges
+                        .iter()
+                        .filter(|input| !input.is_unconstrained())
+                        .cloned()
+                        .collect(),
+                }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            paths: results.len(),
+            failed: failures.len(),
+            incomplete: results
+                .iter()
+                .filter(|result| {
+                    matches!(
+                        result.result,
+                        PathStatus::Truncated(_) | PathStatus::Unknown(_)
+                    )
+                })
+                .count(),
+            accepted,
+            failures,
+        }
+    }
+
+    /// Lists the candidate preconditions as boolean expressions over the
+    /// inputs, such as `x <= 99`. The ranges accepted by the paths that did not
+    /// fail come first, followed by the negated inputs of the failing paths
+    /// that the accepted ranges do not already exclude.
+    pub fn assumptions(&self) -> Vec<String> {
+        let mut assumptions: Vec<String> = self
+            .accepted
+            .iter()
+            .map(|accepted| {
+                let conditions: Vec<String> = accepted
+                    .ranges
+                    .iter()
+                    .map(|range| condition(&accepted.name, accepted.bits, range))
+                    .collect();
+                match conditions.as_slice() {
+                    [condition] => condition.clone(),
+                    _ => format!("({})", conditions.join(") || (")),
+                }
+            })
+            .collect();
+        for failure in &self.failures {
+            if failure.inputs.is_empty() || self.excludes(failure) {
+                continue;
+            }
+            let conditions: Vec<String> = failure
+                .inputs
+                .iter()
+                .map(|input| condition(&input.name, input.bits, &input.range))
+                .collect();
+            assumptions.push(format!("!({})", conditions.join(" && ")));
+        }
+        assumptions
+    }
+
+    /// Checks if the accepted ranges exclude every input `failure` fails
+    /// for.
+    fn excludes(&self, failure: &FailingInputs) -> bool {
+        failure.inputs.iter().any(|input| {
+            self.accepted
+                .iter()
+                .find(|accepted| accepted.name == input.name)
+                .is_some_and(|accepted| {
+                    accepted
+                        .ranges
+                        .iter()
+                        .all(|range| range.max < input.range.min || range.min > input.range.max)
+                })
+        })
+    }
+}
+
+/// Sorts `ranges` and merges the ranges that overlap or are adjacent.
+fn merge(mut ranges: Vec<Interval>) -> Vec<Interval> {
+    ranges.sort_by_key(|range| (range.min, range.max));
+    let mut merged: Vec<Interval> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.min <= last.max.saturating_add(1) => *last = last.join(&range),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Writes that the input `name` of `bits` bits is in `range`.
+fn condition(name: &str, bits: u32, range: &Interval) -> String {
+    let top = Interval::top(bits);
+    match (
+        range.as_constant(),
+        range.min == top.min,
+        range.max == top.max,
+    ) {
+        (Some(value), ..) => format!("{name} == {value}"),
+        (None, true, true) => "true".to_owned(),
+        (None, true, false) => format!("{name} <= {}", range.max),
+        (None, false, true) => format!("{name} >= {}", range.min),
+        (None, false, false) => format!("{name} >= {} && {name} <= {}", range.min, range.max),
+    }
+}
+
+impl fmt::Display for PreconditionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━ Preconditions ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━"
+        )?;
+        writeln!(f, "Paths: {}, failed: {}", self.paths, self.failed)?;
+        if self.incomplete > 0 {
+            writeln!(
+                f,
+                "{}: {} paths did not run to completion and are counted as not failing",
+                "Incomplete".yellow(),
+                self.incomplete
+            )?;
+        }
+
+        if !self.accepted.is_empty() {
+            writeln!(f, "\nAccepted by the paths that did not fail:")?;
+            for accepted in &self.accepted {
+                let ranges: Vec<String> = accepted.ranges.iter().map(|r| r.to_string()).collect();
+                writeln!(indented(f), "{}: {}", accepted.name, ranges.join(", "))?;
+            }
+        }
+
+        if !self.failures.is_empty() {
+            writeln!(f, "\nFailing paths:")?;
+            for failure in &self.failures {
+                let inputs: Vec<String> = failure
+                    .inputs
+                    .iter()
+                    .map(|input| format!("{}: {}", input.name, input.range))
+                    .collect();
+                let inputs = match inputs.is_empty() {
+                    true => "any input".to_owned(),
+                    false => inputs.join(", "),
+                };
+                writeln!(
+                    indented(f),
+                    "path {} ({}): {inputs}",
+                    failure.path,
+                    failure.reason
+                )?;
+            }
+        }
+
+        let assumptions = self.assumptions();
+        if !assumptions.is_empty() {
+            writeln!(f, "\nCandidate preconditions:")?;
+            for assumption in assumptions {
+                writeln!(indented(f), "{assumption}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{AcceptedValues, InputRange, PreconditionReport};
+    use crate::{
+        elf_util::{ErrorReason, PathStatus, VisualPathResult},
+        general_assembly::{budget::Truncation, path_data::PathData, range_analysis::Interval},
+    };
+
+    fn result(path: usize, result: PathStatus, ranges: &[(&str, u64, u64)]) -> VisualPathResult {
+        VisualPathResult {
+            path,
+            result,
+            symbolics: vec![],
+            constrained: vec![],
+            input_ranges: ranges
+                .iter()
+                .map(|(name, min, max)| InputRange {
+                    name: name.to_string(),
+                    bits: 8,
+                    range: Interval::new(*min, *max),
+                })
+                .collect(),
+            end_state: vec![],
+            register_roles: HashMap::new(),
+            return_value: None,
+            instruction_count: 0,
+            max_cycles: 0,
+            energy: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictions: None,
+            cycle_laps: vec![],
+            reached: vec![],
+            initial_sp: 0,
+            memory_delta: vec![],
+            register_history: vec![],
+            mmio_log: vec![],
+            slice: None,
+            provenance: vec![],
+            filtered_calls: vec![],
+            under_approximated: vec![],
+            interrupts: vec![],
+            atomicity_violation: None,
+            reentry: None,
+            nvic: vec![],
+            peripherals: vec![],
+            path_data: PathData::default(),
+            constraints: String::new(),
+        }
+    }
+
+    fn failed() -> PathStatus {
+        PathStatus::Failed(ErrorReason {
+            error_message: "panic".to_owned(),
+        })
+    }
+
+    #[test]
+    fn test_precondition_report() {
+        let results = vec![
+            result(1, PathStatus::Ok(None), &[("x", 0, 9), ("y", 0, 255)]),
+            result(2, PathStatus::Ok(None), &[("x", 20, 29)]),
+            result(3, failed(), &[("x", 10, 19), ("y", 0, 255)]),
+            result(4, failed(), &[("x", 0, 255), ("y", 0, 0)]),
+        ];
+        let report = PreconditionReport::new(&results);
+        assert_eq!((report.paths, report.failed, report.incomplete), (4, 2, 0));
+
+        // The second path does not mark `y`, so every value of it is accepted.
+        assert_eq!(report.accepted, vec![AcceptedValues {
+            name: "x".to_owned(),
+            bits: 8,
+            ranges: vec![Interval::new(0, 9), Interval::new(20, 29)],
+        }]);
+        assert_eq!(report.failures[0].inputs.len(), 1);
+        assert_eq!(report.failures[1].inputs[0].name, "y");
+
+        // The third path is already excluded by the accepted values of `x`.
+        assert_eq!(report.assumptions(), vec![
+            "(x <= 9) || (x >= 20 && x <= 29)".to_owned(),
+            "!(y == 0)".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn test_incomplete_report() {
+        let truncated = PathStatus::Truncated(Truncation::Instructions(1000));
+        let results = vec![
+            result(1, PathStatus::Ok(None), &[("x", 0, 9)]),
+            result(2, truncated, &[("x", 10, 99)]),
+            result(3, PathStatus::Unknown("x".to_owned()), &[("x", 100, 100)]),
+            result(4, failed(), &[("x", 101, 255)]),
+        ];
+        let report = PreconditionReport::new(&results);
+        assert_eq!(report.incomplete, 2);
+
+        // Adjacent ranges are merged.
+        assert_eq!(report.accepted[0].ranges, vec![Interval::new(0, 100)]);
+        assert_eq!(report.assumptions(), vec!["x <= 100".to_owned()]);
+
+        let report = PreconditionReport::new(&[]);
+        assert!(report.accepted.is_empty() && report.assumptions().is_empty());
+    }
+}
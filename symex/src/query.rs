@@ -0,0 +1,234 @@
+//! Queries over the results of a run.
+//!
+//! A [`PathQuery`] selects the paths of a run that match all of its filters,
+//! such as for a CI policy that fails on slow error paths:
+//!
+//! ```no_run
+//! # use symex::{query::{Outcome, PathQuery}, run_elf::run_elf};
+//! let results = run_elf("target/thumbv6m-none-eabi/release/examples/foo", "foo", false).unwrap();
+//! let slow_failures = PathQuery::new(&results)
+//!     .outcome(Outcome::Failed)
+//!     .reached("handle_error")
+//!     .cycles(1000..)
+//!     .paths();
+//! for path in slow_failures {
+//!     println!("{path}");
+//! }
+//! ```
+
+use std::ops::{Bound, RangeBounds};
+
+use crate::elf_util::{PathStatus, VisualPathResult};
+
+/// Outcome of a path, see [`PathStatus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The path finished successfully.
+    Succeeded,
+
+    /// The path failed.
+    Failed,
+
+    /// The solver could not decide a query of the path.
+    Unknown,
+
+    /// The path exceeded the budget of the exploration.
+    Truncated,
+}
+
+impl Outcome {
+    /// Returns the outcome of a path with the status `status`.
+    pub fn of(status: &PathStatus) -> Self {
+        match status {
+            PathStatus::Ok(_) => Outcome::Succeeded,
+            PathStatus::Failed(_) => Outcome::Failed,
+            PathStatus::Unknown(_) => Outcome::Unknown,
+            PathStatus::Truncated(_) => Outcome::Truncated,
+        }
+    }
+}
+
+/// Selects the paths of a run that match all of the filters.
+#[derive(Debug, Clone)]
+pub struct PathQuery<'a> {
+    results: &'a [VisualPathResult],
+    outcome: Option<Outcome>,
+    reached: Vec<String>,
+    constrained: Vec<String>,
+    cycles: (Bound<usize>, Bound<usize>),
+}
+
+impl<'a> PathQuery<'a> {
+    /// Creates a query that matches all paths in `results`.
+    pub fn new(results: &'a [VisualPathResult]) -> Self {
+        Self {
+            results,
+            outcome: None,
+            reached: vec![],
+            constrained: vec![],
+            cycles: (Bound::Unbounded, Bound::Unbounded),
+        }
+    }
+
+    /// Only match paths with the outcome `outcome`.
+    pub fn outcome(mut self, outcome: Outcome) -> Self {
+        self.outcome = Some(outcome);
+        self
+    }
+
+    /// Only match paths that reached the function `function`, see
+    /// [`VisualPathResult::reached`].
+    ///
+    /// Rust functions match both their full path and their name, so
+    /// `"handle_error"` matches `app::handle_error`.
+    pub fn reached(mut self, function: &str) -> Self {
+        self.reached.push(function.to_owned());
+        self
+    }
+
+    /// Only match paths with a path condition that depends on the variable
+    /// `variable`, see [`VisualPathResult::constrained`].
+    pub fn constrained_by(mut self, variable: &str) -> Self {
+        self.constrained.push(variable.to_owned());
+        self
+    }
+
+    /// Only match paths with a maximum cycle count in `range`.
+    pub fn cycles(mut self, range: impl RangeBounds<usize>) -> Self {
+        self.cycles = (range.start_bound().cloned(), range.end_bound().cloned());
+        self
+    }
+
+    /// Returns true if `result` matches all of the filters.
+    pub fn matches(&self, result: &VisualPathResult) -> bool {
+        let reached = |function: &String| {
+            result.reached.iter().any(|name| {
+                name == function
+                    || name
+                        .strip_suffix(function.as_str())
+                        .is_some_and(|path| path.ends_with("::"))
+            })
+        };
+        self.outcome
+            .is_none_or(|outcome| outcome == Outcome::of(&result.result))
+            && self.reached.iter().all(reached)
+            && self
+                .constrained
+                .iter()
+                .all(|variable| result.constrained.contains(variable))
+            && self.cycles.contains(&result.max_cycles)
+    }
+
+    /// Returns the matching paths in the order they were reported.
+    pub fn paths(&self) -> Vec<&'a VisualPathResult> {
+        self.results
+            .iter()
+            .filter(|result| self.matches(result))
+            .collect()
+    }
+
+    /// Returns the number of matching paths.
+    pub fn count(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| self.matches(result))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{Outcome, PathQuery};
+    use crate::{
+        elf_util::{ErrorReason, PathStatus, VisualPathResult},
+        general_assembly::path_data::PathData,
+    };
+
+    fn result(
+        path: usize,
+        result: PathStatus,
+        max_cycles: usize,
+        reached: &[&str],
+        constrained: &[&str],
+    ) -> VisualPathResult {
+        VisualPathResult {
+            path,
+            result,
+            symbolics: vec![],
+            constrained: constrained.iter().map(|name| name.to_string()).collect(),
+            input_ranges: vec![],
+            end_state: vec![],
+            register_roles: HashMap::new(),
+            return_value: None,
+            instruction_count: 0,
+            max_cycles,
+            energy: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictions: None,
+            cycle_laps: vec![],
+            reached: reached.iter().map(|name| name.to_string()).collect(),
+            initial_sp: 0,
+            memory_delta: vec![],
+            register_history: vec![],
+            mmio_log: vec![],
+            slice: None,
+            provenance: vec![],
+            filtered_calls: vec![],
+            under_approximated: vec![],
+            interrupts: vec![],
+            atomicity_violation: None,
+            reentry: None,
+            nvic: vec![],
+            peripherals: vec![],
+            path_data: PathData::default(),
+            constraints: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_path_query() {
+        let failed = || {
+            PathStatus::Failed(ErrorReason {
+                error_message: "panic".to_owned(),
+            })
+        };
+        let results = vec![
+            result(1, PathStatus::Ok(None), 100, &["app::run"], &["x"]),
+            result(2, failed(), 200, &["app::run", "app::fail"], &["x", "y"]),
+            result(3, failed(), 50, &["app::run", "app::fail"], &[]),
+            result(4, PathStatus::Unknown("x".to_owned()), 300, &[], &[]),
+        ];
+        let paths = |query: PathQuery<'_>| -> Vec<usize> {
+            query.paths().iter().map(|result| result.path).collect()
+        };
+
+        assert_eq!(PathQuery::new(&results).count(), 4);
+        assert_eq!(
+            paths(PathQuery::new(&results).outcome(Outcome::Failed)),
+            vec![2, 3]
+        );
+        assert_eq!(paths(PathQuery::new(&results).reached("fail")), vec![2, 3]);
+        assert_eq!(
+            paths(PathQuery::new(&results).reached("app::run").reached("fail")),
+            vec![2, 3]
+        );
+        assert!(PathQuery::new(&results).reached("ail").paths().is_empty());
+        assert_eq!(paths(PathQuery::new(&results).constrained_by("x")), vec![
+            1, 2
+        ]);
+        assert_eq!(paths(PathQuery::new(&results).cycles(100..=200)), vec![
+            1, 2
+        ]);
+        assert_eq!(
+            paths(
+                PathQuery::new(&results)
+                    .outcome(Outcome::Failed)
+                    .cycles(..100)
+            ),
+            vec![3]
+        );
+    }
+}
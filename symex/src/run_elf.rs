@@ -1,21 +1,31 @@
 //! Simple runner that starts symbolic execution on machine code.
-use std::{fs, path::Path, time::Instant};
+use std::{fs, path::Path, sync::Arc, time::Instant};
 
+use object::{Object, ObjectSymbol};
 use regex::Regex;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::{
     elf_util::{ErrorReason, PathStatus, VisualPathResult},
     general_assembly::{
         self,
         arch::{Arch, SupportedArchitechture},
+        budget::ExecutionBudget,
+        concrete::{ConcreteResult, ConcreteState},
         executor::PathResult,
+        profile::Semantics,
         project::{PCHook, ProjectError},
-        state::GAState,
+        range_analysis::RangeAnalysis,
+        side_effects::SideEffects,
+        state::{GAState, SymbolOrigin},
+        vm::VM,
         GAError,
+        MemoryModel,
         RunConfig,
     },
-    smt::DContext,
+    harness::Harness,
+    intrinsics::{self, IntrinsicReport},
+    smt::{DContext, DSolver},
 };
 
 fn add_architecture_independent_hooks<A: Arch>(cfg: &mut RunConfig<A>) {
@@ -29,6 +39,15 @@ fn add_architecture_independent_hooks<A: Arch>(cfg: &mut RunConfig<A>) {
         state.set_register("PC".to_owned(), lr)?;
         Ok(())
     };
+    let must_reach = |state: &mut GAState<A>| {
+        trace!("Reached symex_must_reach");
+        state.reached_must_reach = true;
+
+        // jump back to where the function was called from
+        let lr = state.get_register("LR".to_owned()).unwrap();
+        state.set_register("PC".to_owned(), lr)?;
+        Ok(())
+    };
     let end_cyclecount = |state: &mut GAState<A>| {
         // stop counting
         state.count_cycles = false;
@@ -57,17 +76,30 @@ fn add_architecture_independent_hooks<A: Arch>(cfg: &mut RunConfig<A>) {
             Regex::new(r"^panic_bounds_check$").unwrap(),
             PCHook::EndFailure("bounds check panic"),
         ),
-        (Regex::new(r"^suppress_path$").unwrap(), PCHook::Suppress),
+        (intrinsics::hook_pattern("suppress_path"), PCHook::Suppress),
+        (intrinsics::hook_pattern("symex_assume"), PCHook::Assume),
+        (
+            intrinsics::hook_pattern("symex_assert"),
+            PCHook::Assert("symex_assert failed"),
+        ),
         (
             Regex::new(r"^unreachable_unchecked$").unwrap(),
             PCHook::EndFailure("reach a unreachable unchecked call undefined behavior"),
         ),
         (
-            Regex::new(r"^start_cyclecount$").unwrap(),
+            intrinsics::hook_pattern("symex_unreachable"),
+            PCHook::EndFailure("reached symex_unreachable"),
+        ),
+        (
+            intrinsics::hook_pattern("symex_must_reach"),
+            PCHook::Intrinsic(must_reach),
+        ),
+        (
+            intrinsics::hook_pattern("start_cyclecount"),
             PCHook::Intrinsic(start_cyclecount),
         ),
         (
-            Regex::new(r"^end_cyclecount$").unwrap(),
+            intrinsics::hook_pattern("end_cyclecount"),
             PCHook::Intrinsic(end_cyclecount),
         ),
         (
@@ -75,6 +107,205 @@ fn add_architecture_independent_hooks<A: Arch>(cfg: &mut RunConfig<A>) {
             PCHook::EndFailure("panic"),
         ),
     ]);
+
+    add_newlib_hooks(cfg);
+}
+
+/// Adds models for the newlib system calls commonly used by C firmware.
+///
+/// Arguments and return values are passed in the registers of the calling
+/// convention of the architecture.
+fn add_newlib_hooks<A: Arch>(cfg: &mut RunConfig<A>) {
+    // write(fd, buf, len), all writes succeed.
+    let write = |state: &mut GAState<A>| {
+        let len = state.get_register(state.argument_register(2))?;
+        trace!("_write of {:?} bytes", len.get_constant());
+        state.set_register(state.return_register(), len)?;
+
+        let lr = state.get_register("LR".to_owned())?;
+        state.set_register("PC".to_owned(), lr)?;
+        Ok(())
+    };
+
+    // sbrk(increment), the heap starts at the `end` symbol from the linker
+    // script.
+    let sbrk = |state: &mut GAState<A>| {
+        let ptr_size = state.project.get_ptr_size();
+        let increment = state.get_register(state.argument_register(0))?;
+        let increment = match increment.get_constant() {
+            Some(increment) => increment,
+            None => {
+                // pick one possible increment and stick with it for this path
+                let value = state.constraints.get_value(&increment)?;
+                state.constraints.assert(&increment.eq(&value));
+                value.get_constant().unwrap()
+            }
+        };
+
+        let heap_start = state
+            .project
+            .get_symbol_address("end")
+            .or_else(|| state.project.get_symbol_address("_end"));
+        let result = match (state.heap_break, heap_start) {
+            (Some(heap_break), _) | (None, Some(heap_break)) => {
+                // the increment is a signed value
+                let mask = u64::MAX >> (64 - ptr_size);
+                state.heap_break = Some(heap_break.wrapping_add(increment) & mask);
+                heap_break
+            }
+            // no heap, return (void *)-1
+            (None, None) => u64::MAX,
+        };
+        trace!("_sbrk returned {:#X}", result);
+        let result = state.ctx.from_u64(result, ptr_size);
+        state.set_register(state.return_register(), result)?;
+
+        let lr = state.get_register("LR".to_owned())?;
+        state.set_register("PC".to_owned(), lr)?;
+        Ok(())
+    };
+
+    // gettimeofday(tv, tz), the time is unconstrained.
+    let gettimeofday = |state: &mut GAState<A>| {
+        let ptr_size = state.project.get_ptr_size();
+        let tv = state.get_register(state.argument_register(0))?;
+        if tv.get_constant() != Some(0) {
+            // struct timeval { time_t tv_sec; suseconds_t tv_usec; } with a 64 bit
+            // time_t.
+            let tv_sec = state.new_symbol(64, "gettimeofday.tv_sec", SymbolOrigin::Hook);
+            let tv_usec = state.new_symbol(32, "gettimeofday.tv_usec", SymbolOrigin::Hook);
+            let tv_usec_address = tv.add(&state.ctx.from_u64(8, ptr_size));
+            state.write_word_to_memory(&tv, tv_sec)?;
+            state.write_word_to_memory(&tv_usec_address, tv_usec)?;
+        }
+        let zero = state.ctx.zero(ptr_size);
+        state.set_register(state.return_register(), zero)?;
+
+        let lr = state.get_register("LR".to_owned())?;
+        state.set_register("PC".to_owned(), lr)?;
+        Ok(())
+    };
+
+    cfg.pc_hooks.extend([
+        (Regex::new(r"^_write$").unwrap(), PCHook::Intrinsic(write)),
+        (Regex::new(r"^_sbrk$").unwrap(), PCHook::Intrinsic(sbrk)),
+        (
+            Regex::new(r"^_gettimeofday$").unwrap(),
+            PCHook::Intrinsic(gettimeofday),
+        ),
+        (Regex::new(r"^_exit$").unwrap(), PCHook::EndSuccess),
+    ]);
+}
+
+/// Prefix of the symbols of the shims generated by `symex_lib::symex_shim!`.
+pub const SHIM_PREFIX: &str = "symex_shim_";
+
+/// Lists the names of the shims in a elf file, without [`SHIM_PREFIX`].
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn list_shims<P: AsRef<Path>>(path: P) -> Result<Vec<String>, GAError> {
+    let str_version = path.as_ref().display().to_string();
+    let file = fs::read(path).expect("Unable to open file.");
+    let data: &[u8] = file.as_ref();
+    let obj_file = match object::File::parse(data) {
+        Ok(x) => x,
+        Err(e) => {
+            debug!("Error: {}", e);
+            return Err(ProjectError::UnableToParseElf(str_version))?;
+        }
+    };
+
+    let mut shims: Vec<String> = obj_file
+        .symbols()
+        .filter_map(|symbol| {
+            let name = symbol.name().ok()?;
+            name.strip_prefix(SHIM_PREFIX).map(|name| name.to_owned())
+        })
+        .collect();
+    shims.sort();
+    shims.dedup();
+    Ok(shims)
+}
+
+/// Lists the intrinsics of `symex_lib` found in a elf file, see
+/// [`intrinsics`].
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn list_intrinsics<P: AsRef<Path>>(path: P) -> Result<IntrinsicReport, GAError> {
+    let str_version = path.as_ref().display().to_string();
+    let file = fs::read(path).expect("Unable to open file.");
+    let data: &[u8] = file.as_ref();
+    let obj_file = match object::File::parse(data) {
+        Ok(x) => x,
+        Err(e) => {
+            debug!("Error: {}", e);
+            return Err(ProjectError::UnableToParseElf(str_version))?;
+        }
+    };
+    Ok(IntrinsicReport::discover(&obj_file))
+}
+
+/// Warns about mismatches between the intrinsics of the binary and the
+/// engine.
+fn check_intrinsics(obj_file: &object::File<'_>) {
+    let report = IntrinsicReport::discover(obj_file);
+    debug!("Discovered intrinsics:\n{}", report);
+    for mismatch in report.mismatches() {
+        warn!("{}", mismatch);
+    }
+}
+
+/// Options of [`run_elf_with_options`] that do not depend on the
+/// architecture.
+#[derive(Debug, Clone, Default)]
+pub struct ElfOptions {
+    /// Print the result of each path as it ends.
+    pub show_path_results: bool,
+
+    /// Maximum size in bytes of a symbol, see
+    /// [`RunConfig::max_symbol_size`].
+    pub max_symbol_size: Option<u64>,
+
+    /// Model of the symbolic memory, see [`RunConfig::memory_model`].
+    pub memory_model: MemoryModel,
+
+    /// Name the locals on the stack, see [`RunConfig::stack_locals`].
+    pub stack_locals: bool,
+
+    /// Solve for the ranges of the symbolic inputs of each path, see
+    /// [`RunConfig::infer_preconditions`].
+    pub infer_preconditions: bool,
+
+    /// Names of the functions whose calls are summarized, see
+    /// [`RunConfig::summarized`].
+    pub summarized: Vec<String>,
+
+    /// Largest copy of an accelerated copy loop in bytes, see
+    /// [`RunConfig::accelerate_copies`].
+    pub accelerate_copies: Option<u64>,
+
+    /// Limits of the exploration, see [`RunConfig::budget`].
+    pub budget: ExecutionBudget,
+}
+
+impl ElfOptions {
+    /// Creates the run configuration of the options.
+    fn config<A: Arch>(&self) -> RunConfig<A> {
+        let mut cfg = RunConfig::new(self.show_path_results);
+        cfg.max_symbol_size = self.max_symbol_size;
+        cfg.memory_model = self.memory_model;
+        cfg.stack_locals = self.stack_locals;
+        cfg.infer_preconditions = self.infer_preconditions;
+        cfg.summarized = self.summarized.clone();
+        cfg.accelerate_copies = self.accelerate_copies;
+        cfg.budget = self.budget.clone();
+        add_architecture_independent_hooks(&mut cfg);
+        cfg
+    }
 }
 
 /// Run symbolic execution on a elf file.
@@ -92,22 +323,49 @@ pub fn run_elf<P: AsRef<Path>>(
     function: &str,
     show_path_results: bool,
 ) -> Result<Vec<VisualPathResult>, GAError> {
-    let context = Box::new(DContext::new());
-    let context = Box::leak(context);
-
-    let end_pc = 0xFFFFFFFE;
+    run_elf_with_options(path, function, &ElfOptions {
+        show_path_results,
+        ..ElfOptions::default()
+    })
+}
 
+/// Run symbolic execution on a elf file like [`run_elf`] with `options`.
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn run_elf_with_options<P: AsRef<Path>>(
+    path: P,
+    function: &str,
+    options: &ElfOptions,
+) -> Result<Vec<VisualPathResult>, GAError> {
     let str_version = path.as_ref().display().to_string();
-    debug!("Parsing elf file: {}", str_version);
     let file = fs::read(path).expect("Unable to open file.");
-    let data = file.as_ref();
+    run_elf_bytes_with_options(&file, &str_version, function, options)
+}
+
+/// Run symbolic execution on the contents of an elf file like
+/// [`run_elf_with_options`], for when the file has already been read. `name`
+/// identifies the file in errors.
+pub fn run_elf_bytes_with_options(
+    data: &[u8],
+    name: &str,
+    function: &str,
+    options: &ElfOptions,
+) -> Result<Vec<VisualPathResult>, GAError> {
+    let context = DContext::new();
+
+    let end_pc = 0xFFFFFFFE;
+
+    debug!("Parsing elf file: {}", name);
     let obj_file = match object::File::parse(data) {
         Ok(x) => x,
         Err(e) => {
             debug!("Error: {}", e);
-            return Err(ProjectError::UnableToParseElf(str_version))?;
+            return Err(ProjectError::UnableToParseElf(name.to_owned()))?;
         }
     };
+    check_intrinsics(&obj_file);
 
     let arch = SupportedArchitechture::discover(&obj_file)?;
 
@@ -116,30 +374,37 @@ pub fn run_elf<P: AsRef<Path>>(
     match arch {
         SupportedArchitechture::ArmV7EM(v7) => {
             // Run the paths with architecture specific data.
-            let mut cfg = RunConfig::new(show_path_results);
-            add_architecture_independent_hooks(&mut cfg);
-            let project = Box::new(general_assembly::project::Project::from_path(
-                &mut cfg, obj_file, &v7,
-            )?);
-            let project = Box::leak(project);
+            let mut cfg = options.config();
+            let mut project =
+                general_assembly::project::Project::from_path(&mut cfg, obj_file, &v7)?;
             project.add_pc_hook(end_pc, PCHook::EndSuccess);
             debug!("Created project: {:?}", project);
+            let project = Arc::new(project);
 
-            let mut vm = general_assembly::vm::VM::new(project, context, function, end_pc, v7)?;
+            let mut vm = general_assembly::vm::VM::new(project, &context, function, end_pc, v7)?;
 
             run_elf_paths(&mut vm, &cfg)
         }
         SupportedArchitechture::ArmV6M(v6) => {
-            let mut cfg = RunConfig::new(show_path_results);
-            add_architecture_independent_hooks(&mut cfg);
-            let project = Box::new(general_assembly::project::Project::from_path(
-                &mut cfg, obj_file, &v6,
-            )?);
-            let project = Box::leak(project);
+            let mut cfg = options.config();
+            let mut project =
+                general_assembly::project::Project::from_path(&mut cfg, obj_file, &v6)?;
+            project.add_pc_hook(end_pc, PCHook::EndSuccess);
+            debug!("Created project: {:?}", project);
+            let project = Arc::new(project);
+
+            let mut vm = general_assembly::vm::VM::new(project, &context, function, end_pc, v6)?;
+            run_elf_paths(&mut vm, &cfg)
+        }
+        SupportedArchitechture::RiscV32I(rv32i) => {
+            let mut cfg = options.config();
+            let mut project =
+                general_assembly::project::Project::from_path(&mut cfg, obj_file, &rv32i)?;
             project.add_pc_hook(end_pc, PCHook::EndSuccess);
             debug!("Created project: {:?}", project);
+            let project = Arc::new(project);
 
-            let mut vm = general_assembly::vm::VM::new(project, context, function, end_pc, v6)?;
+            let mut vm = general_assembly::vm::VM::new(project, &context, function, end_pc, rv32i)?;
             run_elf_paths(&mut vm, &cfg)
         }
     }
@@ -161,8 +426,7 @@ pub fn run_elf_configured<A: Arch>(
     architecture: A,
     mut cfg: RunConfig<A>,
 ) -> Result<Vec<VisualPathResult>, GAError> {
-    let context = Box::new(DContext::new());
-    let context = Box::leak(context);
+    let context = DContext::new();
 
     let end_pc = 0xFFFFFFFE;
 
@@ -176,30 +440,149 @@ pub fn run_elf_configured<A: Arch>(
             return Err(ProjectError::UnableToParseElf(path.to_owned()))?;
         }
     };
+    check_intrinsics(&obj_file);
 
     add_architecture_independent_hooks(&mut cfg);
-    let project = Box::new(general_assembly::project::Project::from_path(
-        &mut cfg,
-        obj_file,
-        &architecture,
-    )?);
-    let project = Box::leak(project);
+    let mut project =
+        general_assembly::project::Project::from_path(&mut cfg, obj_file, &architecture)?;
     project.add_pc_hook(end_pc, PCHook::EndSuccess);
     debug!("Created project: {:?}", project);
+    let project = Arc::new(project);
 
-    let mut vm = general_assembly::vm::VM::new(project, context, function, end_pc, architecture)?;
+    let mut vm = general_assembly::vm::VM::new(project, &context, function, end_pc, architecture)?;
     run_elf_paths(&mut vm, &cfg)
 }
 
+/// Run a [`Harness`] on a elf file with a known [`Arch`].
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn run_elf_harness<A: Arch>(
+    path: &str,
+    architecture: A,
+    mut harness: Harness<A>,
+) -> Result<Vec<VisualPathResult>, GAError> {
+    let context = DContext::new();
+
+    let end_pc = 0xFFFFFFFE;
+
+    debug!("Parsing elf file: {}", path);
+    let file = fs::read(path).expect("Unable to open file.");
+    let data = file.as_ref();
+    let obj_file = match object::File::parse(data) {
+        Ok(x) => x,
+        Err(e) => {
+            debug!("Error: {}", e);
+            return Err(ProjectError::UnableToParseElf(path.to_owned()))?;
+        }
+    };
+    check_intrinsics(&obj_file);
+
+    let mut cfg = harness.take_config();
+    add_architecture_independent_hooks(&mut cfg);
+    let mut project =
+        general_assembly::project::Project::from_path(&mut cfg, obj_file, &architecture)?;
+    project.add_pc_hook(end_pc, PCHook::EndSuccess);
+    debug!("Created project: {:?}", project);
+    let project = Arc::new(project);
+
+    let mut state = GAState::new(
+        &context,
+        project.clone(),
+        DSolver::new(&context),
+        harness.get_function(),
+        end_pc,
+        architecture,
+    )?;
+    if !harness.prepare(&mut state)? {
+        println!("The assumptions of the harness are unsatisfiable");
+        return Ok(vec![]);
+    }
+
+    let mut vm = VM::new_with_state(project.clone(), state);
+    run_elf_paths_checked(&mut vm, &cfg, |state| harness.check(state))
+}
+
+/// Run concrete execution on a elf file with a known [`Arch`].
+///
+/// Executes the same instructions as [`run_elf_configured`] without the SMT
+/// solver, see [`ConcreteState`] for the differences to symbolic execution.
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn run_elf_concrete<A: Arch>(
+    path: &str,
+    function: &str,
+    architecture: A,
+    mut cfg: RunConfig<A>,
+) -> Result<ConcreteResult, GAError> {
+    let end_pc = 0xFFFFFFFE;
+
+    debug!("Parsing elf file: {}", path);
+    let file = fs::read(path).expect("Unable to open file.");
+    let data = file.as_ref();
+    let obj_file = match object::File::parse(data) {
+        Ok(x) => x,
+        Err(e) => {
+            debug!("Error: {}", e);
+            return Err(ProjectError::UnableToParseElf(path.to_owned()))?;
+        }
+    };
+    check_intrinsics(&obj_file);
+
+    add_architecture_independent_hooks(&mut cfg);
+    let mut project =
+        general_assembly::project::Project::from_path(&mut cfg, obj_file, &architecture)?;
+    project.add_pc_hook(end_pc, PCHook::EndSuccess);
+    debug!("Created project: {:?}", project);
+    let project = Arc::new(project);
+
+    let mut state = ConcreteState::new(project, function, end_pc, architecture)?;
+    let result = state.run()?;
+    debug!(
+        "Concrete execution ended with {:?} after {} instructions",
+        result, state.instruction_count
+    );
+    Ok(result)
+}
+
 /// Runs all paths in the vm
 fn run_elf_paths<A: Arch>(
     vm: &mut general_assembly::vm::VM<A>,
     cfg: &RunConfig<A>,
+) -> Result<Vec<VisualPathResult>, GAError> {
+    run_elf_paths_checked(vm, cfg, |_| Ok(None))
+}
+
+/// Runs all paths in the vm, `check` is called on every successful path and
+/// returns a message if the path fails.
+fn run_elf_paths_checked<A: Arch>(
+    vm: &mut general_assembly::vm::VM<A>,
+    cfg: &RunConfig<A>,
+    mut check: impl FnMut(&mut GAState<A>) -> Result<Option<String>, GAError>,
 ) -> Result<Vec<VisualPathResult>, GAError> {
     let mut path_num = 0;
     let start = Instant::now();
     let mut path_results = vec![];
-    while let Some((path_result, state)) = vm.run()? {
+    let mut reached_must_reach = false;
+    loop {
+        let (path_result, mut state) = match vm.run() {
+            Ok(Some(path)) => path,
+            Ok(None) => break,
+            Err(error) => {
+                // Show which instructions the run got through before it was aborted.
+                if cfg.coverage_report {
+                    print_coverage_report(vm);
+                }
+                if cfg.block_coverage_report {
+                    print_block_coverage_report(vm);
+                }
+                return Err(error);
+            }
+        };
+        reached_must_reach |= state.reached_must_reach;
         if matches!(path_result, PathResult::Suppress) {
             debug!("Suppressing path");
             continue;
@@ -212,17 +595,35 @@ fn run_elf_paths<A: Arch>(
         path_num += 1;
 
         let v_path_result = match path_result {
-            general_assembly::executor::PathResult::Success(_) => PathStatus::Ok(None),
+            general_assembly::executor::PathResult::Success(_) => match check(&mut state)? {
+                Some(message) => PathStatus::Failed(ErrorReason {
+                    error_message: message,
+                }),
+                None => PathStatus::Ok(None),
+            },
             general_assembly::executor::PathResult::Failure(reason) => {
                 PathStatus::Failed(ErrorReason {
                     error_message: reason.to_owned(),
                 })
             }
+            general_assembly::executor::PathResult::Unknown(query) => PathStatus::Unknown(query),
+            general_assembly::executor::PathResult::Truncated(truncation) => {
+                PathStatus::Truncated(truncation)
+            }
             general_assembly::executor::PathResult::AssumptionUnsat => todo!(),
             general_assembly::executor::PathResult::Suppress => todo!(),
         };
 
-        let result = VisualPathResult::from_state(state, path_num, v_path_result)?;
+        let undecided = matches!(v_path_result, PathStatus::Unknown(_));
+        let result = match VisualPathResult::from_state(state, path_num, v_path_result) {
+            Ok(result) => result,
+            // The end state of an undecided path may be undecidable as well.
+            Err(error) if undecided => {
+                println!("Path {path_num} could not be decided by the solver: {error}");
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
 
         if cfg.show_path_results {
             println!("{}", result);
@@ -232,5 +633,245 @@ fn run_elf_paths<A: Arch>(
     if cfg.show_path_results {
         println!("time: {:?}", start.elapsed());
     }
+    if cfg.deduplicate_states {
+        println!("Duplicate paths pruned: {}", vm.duplicates);
+    }
+    if cfg.subsumption_budget.is_some() {
+        println!("Subsumed paths pruned: {}", vm.subsumed);
+    }
+    if !cfg.summarized.is_empty() {
+        println!(
+            "Calls replaced by a summary: {}, summaries: {}",
+            vm.summaries.hits,
+            vm.summaries.len()
+        );
+    }
+    if cfg.accelerate_copies.is_some() {
+        println!(
+            "Bytes copied by accelerated copy loops: {}",
+            vm.copied_bytes
+        );
+    }
+    if vm.truncated > 0 {
+        println!(
+            "Paths truncated by the budget: {}, the cycle counts are lower bounds",
+            vm.truncated
+        );
+    }
+    if !cfg.interrupts.is_empty() && cfg.partial_order_reduction {
+        println!("Interleavings pruned: {}", vm.pruned_interleavings);
+    }
+    if cfg.hotspots > 0 {
+        print_hotspots(vm, cfg.hotspots);
+    }
+    if cfg.branch_report {
+        print_branch_report(vm);
+    }
+    if cfg.race_report {
+        print_race_report(vm);
+    }
+    if !cfg.resources.is_empty() {
+        print_ceiling_violations(vm);
+    }
+    if cfg.coverage_report {
+        print_coverage_report(vm);
+    }
+    if cfg.block_coverage_report {
+        print_block_coverage_report(vm);
+    }
+    if let Some(ranges) = &vm.ranges {
+        print_range_report(&vm.project, ranges);
+    }
+    if let Some(side_effects_file) = vm.project.get_side_effects_file() {
+        update_side_effects(vm, side_effects_file);
+    }
+
+    // only check the marker if it is present in the program
+    if !reached_must_reach && vm.project.get_symbol_address("symex_must_reach").is_some() {
+        return Err(GAError::MustReachNotReached);
+    }
     Ok(path_results)
 }
+
+/// Checks the stubs run in `vm` against the side effects known for the
+/// functions they replace and adds the side effects observed in `vm` to the
+/// knowledge base in `file`.
+fn update_side_effects<A: Arch>(vm: &general_assembly::vm::VM<A>, file: &Path) {
+    let mut known = match fs::read_to_string(file) {
+        Ok(text) => SideEffects::parse(&text).unwrap_or_else(|e| {
+            warn!("Ignoring side effects in {}: {}", file.display(), e);
+            SideEffects::new()
+        }),
+        Err(_) => SideEffects::new(),
+    };
+
+    for (function, stub) in vm.stub_effects.iter() {
+        let uncovered = match known.get(function) {
+            Some(real) => real.uncovered_by(stub),
+            None => continue,
+        };
+        if !uncovered.is_empty() {
+            let memory: Vec<String> = uncovered
+                .memory_ranges()
+                .iter()
+                .map(|(start, len)| format!("{start:#x}+{len}"))
+                .collect();
+            warn!(
+                "Stub for {} does not cover the side effects of the function, registers: {:?}, memory: {:?}",
+                function, uncovered.registers, memory
+            );
+        }
+    }
+
+    known.merge(&vm.side_effects);
+    if let Err(e) = fs::write(file, known.to_string()) {
+        warn!("Unable to save side effects to {}: {}", file.display(), e);
+    }
+}
+
+/// Formats `pc` as an offset into the closest preceding symbol.
+fn symbolize<A: Arch>(project: &general_assembly::project::Project<A>, pc: u64) -> String {
+    match project.get_symbol_name(pc) {
+        Some((name, offset)) => format!("{name}+{offset:#x}"),
+        None => "<unknown>".to_owned(),
+    }
+}
+
+/// Prints the `n` instructions that the most cycles were spent in across all
+/// paths.
+fn print_hotspots<A: Arch>(vm: &general_assembly::vm::VM<A>, n: usize) {
+    println!("Hotspots:");
+    for (pc, stats) in vm.histogram.hotspots(n) {
+        let symbol = symbolize(&vm.project, pc);
+        println!(
+            "  {pc:#010x} {symbol}: executed {} times, {} cycles",
+            stats.executions, stats.cycles
+        );
+    }
+}
+
+/// Prints all conditional branches that were always or never taken.
+fn print_branch_report<A: Arch>(vm: &general_assembly::vm::VM<A>) {
+    println!("One sided branches:");
+    for (pc, outcome) in vm.branches.one_sided() {
+        let symbol = symbolize(&vm.project, pc);
+        let location = match vm.project.get_source_location(pc) {
+            Some(location) => location.to_string(),
+            None => "<unknown>".to_owned(),
+        };
+        let kind = if outcome.taken {
+            "always taken"
+        } else {
+            "never taken"
+        };
+        println!("  {pc:#010x} {symbol} ({location}): {kind}");
+    }
+}
+
+/// Prints the accesses of the thread code and the interrupt handlers to shared
+/// memory that may race.
+fn print_race_report<A: Arch>(vm: &general_assembly::vm::VM<A>) {
+    let locate = |pc| match vm.project.get_source_location(pc) {
+        Some(location) => location.to_string(),
+        None => "<unknown>".to_owned(),
+    };
+    let kind = |write| if write { "write" } else { "read" };
+    let interrupts = vm.project.get_interrupts();
+    println!("Potential data races:");
+    for race in vm.races.races(interrupts) {
+        let (thread, handler) = (race.thread, race.handler);
+        println!(
+            "  {:#010x} {}: {} at {:#010x} ({}) and {} at {:#010x} ({}) in {}",
+            race.address,
+            symbolize(&vm.project, race.address),
+            kind(thread.write),
+            thread.pc,
+            locate(thread.pc),
+            kind(handler.write),
+            handler.pc,
+            locate(handler.pc),
+            interrupts[handler.interrupt].handler
+        );
+    }
+}
+
+/// Prints the accesses to resources below their priority ceilings.
+fn print_ceiling_violations<A: Arch>(vm: &general_assembly::vm::VM<A>) {
+    println!("Priority ceiling violations:");
+    for violation in &vm.ceiling_violations {
+        let resource = &vm.project.get_resources()[violation.resource];
+        let location = match vm.project.get_source_location(violation.pc) {
+            Some(location) => location.to_string(),
+            None => "<unknown>".to_owned(),
+        };
+        let context = match violation.interrupt {
+            Some(index) => vm.project.get_interrupts()[index].handler.as_str(),
+            None => "thread code",
+        };
+        let kind = if violation.write { "write" } else { "read" };
+        println!(
+            "  {}: {kind} at {:#010x} ({location}) in {context} with BASEPRI {:#04x}, ceiling {:#04x}",
+            resource.symbol, violation.pc, violation.mask.basepri, resource.ceiling
+        );
+    }
+}
+
+/// Prints every executed opcode and how its semantics were handled.
+fn print_coverage_report<A: Arch>(vm: &general_assembly::vm::VM<A>) {
+    println!("Instruction coverage:");
+    for (mnemonic, coverage) in vm.coverage.opcodes() {
+        let handling: Vec<String> = coverage
+            .addresses
+            .iter()
+            .map(|(semantics, addresses)| format!("{semantics} at {addresses}"))
+            .collect();
+        println!("  {mnemonic}: {}", handling.join(", "));
+    }
+    let modeled = vm.coverage.count(Semantics::Modeled);
+    let stubbed = vm.coverage.count(Semantics::Stubbed);
+    let havoced = vm.coverage.count(Semantics::Havoced);
+    let unsupported = vm.coverage.count(Semantics::Unsupported);
+    println!(
+        "  {} addresses: {modeled} modeled, {stubbed} stubbed, {havoced} havoced, {unsupported} unsupported",
+        modeled + stubbed + havoced + unsupported
+    );
+}
+
+/// Prints how many instructions of each region were executed.
+fn print_block_coverage_report<A: Arch>(vm: &general_assembly::vm::VM<A>) {
+    println!("Block coverage:");
+    let coverage = vm.histogram.block_coverage(vm.project.get_regions());
+    for block in &coverage {
+        let region = block.region;
+        match block.is_reached() {
+            true => println!(
+                "  {} [{:#010x}, {:#010x}): {} instructions executed {} times",
+                region.name, region.start, region.end, block.instructions, block.executions
+            ),
+            false => println!(
+                "  {} [{:#010x}, {:#010x}): not reached",
+                region.name, region.start, region.end
+            ),
+        }
+    }
+    let reached = coverage.iter().filter(|block| block.is_reached()).count();
+    println!("  {reached} of {} blocks reached", coverage.len());
+}
+
+/// Prints the ranges inferred by the range analysis and how often they were
+/// assumed.
+fn print_range_report<A: Arch>(
+    project: &general_assembly::project::Project<A>,
+    ranges: &RangeAnalysis,
+) {
+    println!("Inferred ranges:");
+    for (pc, registers) in ranges.ranges() {
+        for (register, range) in registers {
+            println!(
+                "  {} ({pc:#010X}): {register} in {range}, assumed {} times",
+                symbolize(project, *pc),
+                ranges.assumed(*pc, register)
+            );
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Export of the worst case execution times of tasks to schedulability
+//! analyzers.
+//!
+//! A [`TaskSet`] holds the execution times of each task, taken from the
+//! [`WcetReport`] of the function that implements it, together with the
+//! period, deadline and priority of the task that the analysis can not know.
+//! The set is exported as CSV, as JSON or as a
+//! [MAST](https://mast.unican.es) model for response time analysis:
+//!
+//! ```no_run
+//! # use symex::{run_elf::ElfOptions, schedulability::{ExportFormat, Task, TaskSet}, wcet};
+//! let path = "target/thumbv6m-none-eabi/release/examples/tasks";
+//! let options = ElfOptions::default();
+//! let mut tasks = TaskSet::default();
+//! for (function, period, priority) in [("uart_rx", 1_000, 2), ("control", 10_000, 1)] {
+//!     let report = wcet::analyze(path, function, &options).unwrap();
+//!     tasks.push(Task::new(function, &report).period(period).priority(priority));
+//! }
+//! std::fs::write("tasks.csv", tasks.export(ExportFormat::Csv)).unwrap();
+//! ```
+//!
+//! All times are in clock cycles of the analyzed core. The execution time of a
+//! task whose report has paths that did not run to completion, such as paths
+//! cut by a loop bound, is only a lower bound of its worst case and is marked
+//! as such in every format.
+
+use std::{fmt::Write, path::Path};
+
+use crate::wcet::WcetReport;
+
+/// Format of an exported task set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One row per task, with a header row.
+    Csv,
+
+    /// An object with an array of tasks.
+    Json,
+
+    /// A MAST model with one periodic transaction per task on a fixed
+    /// priority processor.
+    Mast,
+}
+
+impl ExportFormat {
+    /// Picks the format from the extension of `path`, `.csv` or `.json`, and
+    /// MAST for any other extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Self::Csv,
+            Some("json") => Self::Json,
+            _ => Self::Mast,
+        }
+    }
+}
+
+/// Timing of a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    /// Name of the task.
+    pub name: String,
+
+    /// Worst case execution time in cycles.
+    pub wcet: usize,
+
+    /// Best case execution time in cycles.
+    pub bcet: usize,
+
+    /// If the worst case execution time is only a lower bound, as some paths
+    /// did not run to completion.
+    pub lower_bound: bool,
+
+    /// Minimum time between two releases of the task in cycles.
+    pub period: Option<u64>,
+
+    /// Time from the release of the task that it must complete within in
+    /// cycles, the period if not set.
+    pub deadline: Option<u64>,
+
+    /// Priority of the task, higher values preempt lower values.
+    pub priority: Option<u32>,
+}
+
+impl Task {
+    /// Creates the task `name` with the execution times of `report`.
+    pub fn new(name: &str, report: &WcetReport) -> Self {
+        Self {
+            name: name.to_owned(),
+            wcet: report.max_cycles,
+            bcet: report.min_cycles,
+            lower_bound: report.is_lower_bound(),
+            period: None,
+            deadline: None,
+            priority: None,
+        }
+    }
+
+    /// Sets the period of the task in cycles.
+    pub fn period(mut self, period: u64) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// Sets the deadline of the task in cycles.
+    pub fn deadline(mut self, deadline: u64) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the priority of the task.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Returns the deadline of the task, the period if no deadline is set.
+    pub fn effective_deadline(&self) -> Option<u64> {
+        self.deadline.or(self.period)
+    }
+}
+
+/// Tasks to export to a schedulability analyzer.
+#[derive(Debug, Clone, Default)]
+pub struct TaskSet {
+    /// The tasks in the set.
+    pub tasks: Vec<Task>,
+}
+
+impl TaskSet {
+    /// Adds `task` to the set.
+    pub fn push(&mut self, task: Task) {
+        self.tasks.push(task);
+    }
+
+    /// Writes the set in `format`.
+    pub fn export(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Json => self.to_json(),
+            ExportFormat::Mast => self.to_mast(),
+        }
+    }
+
+    /// Writes the set as CSV, missing values are empty fields.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "task,wcet,bcet,lower_bound,period,deadline,priority\n".to_owned();
+        for task in &self.tasks {
+            let optional = |value: Option<u64>| value.map_or(String::new(), |v| v.to_string());
+            let name = match task.name.contains([',', '"']) {
+                true => format!("\"{}\"", task.name.replace('"', "\"\"")),
+                false => task.name.clone(),
+            };
+            writeln!(
+                csv,
+                "{name},{},{},{},{},{},{}",
+                task.wcet,
+                task.bcet,
+                task.lower_bound,
+                optional(task.period),
+                optional(task.effective_deadline()),
+                optional(task.priority.map(u64::from)),
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    /// Writes the set as JSON, missing values are `null`.
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<u64>| value.map_or("null".to_owned(), |v| v.to_string());
+        let tasks: Vec<String> = self
+            .tasks
+            .iter()
+            .map(|task| {
+                format!(
+                    "    {{\"name\": \"{}\", \"wcet\": {}, \"bcet\": {}, \"lower_bound\": {}, \
+                     \"period\": {}, \"deadline\": {}, \"priority\": {}}}",
+                    task.name.replace('\\', "\\\\").replace('"', "\\\""),
+                    task.wcet,
+                    task.bcet,
+                    task.lower_bound,
+                    optional(task.period),
+                    optional(task.effective_deadline()),
+                    optional(task.priority.map(u64::from)),
+                )
+            })
+            .collect();
+        match tasks.is_empty() {
+            true => "{\"unit\": \"cycles\", \"tasks\": []}\n".to_owned(),
+            false => format!(
+                "{{\"unit\": \"cycles\", \"tasks\": [\n{}\n]}}\n",
+                tasks.join(",\n")
+            ),
+        }
+    }
+
+    /// Writes the set as a MAST model. Each task is a regular transaction
+    /// released by a periodic event with a hard deadline, executed by its own
+    /// scheduling server on a single fixed priority processor. Tasks without
+    /// a period are released by an unbounded event instead and have no
+    /// deadline.
+    pub fn to_mast(&self) -> String {
+        let mut mast = "-- Generated by symex, all times are in cycles.\n\n".to_owned();
+        let out = &mut mast;
+        writeln!(out, "Model (\n   Model_Name => symex);\n").unwrap();
+        writeln!(out, "Processing_Resource (").unwrap();
+        writeln!(out, "   Type => Fixed_Priority_Processor,").unwrap();
+        writeln!(out, "   Name => cpu);\n").unwrap();
+        for task in &self.tasks {
+            let name = mast_identifier(&task.name);
+            if task.lower_bound {
+                writeln!(out, "-- The execution times of {name} are lower bounds.").unwrap();
+            }
+
+            writeln!(out, "Scheduling_Server (").unwrap();
+            writeln!(out, "   Type => Regular,").unwrap();
+            writeln!(out, "   Name => {name},").unwrap();
+            writeln!(out, "   Server_Sched_Parameters => (").unwrap();
+            writeln!(out, "      Type => Fixed_Priority_Policy,").unwrap();
+            writeln!(
+                out,
+                "      The_Priority => {}),",
+                task.priority.unwrap_or(1)
+            )
+            .unwrap();
+            writeln!(out, "   Server_Processing_Resource => cpu);\n").unwrap();
+
+            writeln!(out, "Operation (").unwrap();
+            writeln!(out, "   Type => Simple,").unwrap();
+            writeln!(out, "   Name => {name},").unwrap();
+            writeln!(out, "   Worst_Case_Execution_Time => {},", task.wcet).unwrap();
+            writeln!(out, "   Best_Case_Execution_Time => {});\n", task.bcet).unwrap();
+
+            writeln!(out, "Transaction (").unwrap();
+            writeln!(out, "   Type => Regular,").unwrap();
+            writeln!(out, "   Name => {name},").unwrap();
+            writeln!(out, "   External_Events => (").unwrap();
+            match task.period {
+                Some(period) => {
+                    writeln!(out, "      (Type => Periodic,").unwrap();
+                    writeln!(out, "       Name => {name}_release,").unwrap();
+                    writeln!(out, "       Period => {period})),").unwrap();
+                }
+                None => {
+                    writeln!(out, "      (Type => Unbounded,").unwrap();
+                    writeln!(out, "       Name => {name}_release)),").unwrap();
+                }
+            }
+            writeln!(out, "   Internal_Events => (").unwrap();
+            writeln!(out, "      (Type => Regular,").unwrap();
+            match task.effective_deadline() {
+                Some(deadline) => {
+                    writeln!(out, "       Name => {name}_done,").unwrap();
+                    writeln!(out, "       Timing_Requirements => (").unwrap();
+                    writeln!(out, "         Type => Hard_Global_Deadline,").unwrap();
+                    writeln!(out, "         Deadline => {deadline},").unwrap();
+                    writeln!(out, "         Referenced_Event => {name}_release))),").unwrap();
+                }
+                None => writeln!(out, "       Name => {name}_done)),").unwrap(),
+            }
+            writeln!(out, "   Event_Handlers => (").unwrap();
+            writeln!(out, "      (Type => Activity,").unwrap();
+            writeln!(out, "       Input_Event => {name}_release,").unwrap();
+            writeln!(out, "       Output_Event => {name}_done,").unwrap();
+            writeln!(out, "       Activity_Operation => {name},").unwrap();
+            writeln!(out, "       Activity_Server => {name})));\n").unwrap();
+        }
+        mast
+    }
+}
+
+/// Turns `name` into a MAST identifier, which starts with a letter and only
+/// contains letters, digits and underscores.
+fn mast_identifier(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert_str(0, "task_");
+    }
+    ident
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ExportFormat, Task, TaskSet};
+
+    fn task(name: &str, wcet: usize, bcet: usize) -> Task {
+        Task {
+            name: name.to_owned(),
+            wcet,
+            bcet,
+            lower_bound: false,
+            period: None,
+            deadline: None,
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let mut tasks = TaskSet::default();
+        tasks.push(task("uart_rx", 120, 80).period(1000).priority(2));
+        tasks.push(Task {
+            lower_bound: true,
+            ..task("app::control", 900, 300).period(10_000).deadline(5000)
+        });
+        tasks.push(task("idle", 10, 10));
+
+        assert_eq!(
+            tasks.to_csv(),
+            "task,wcet,bcet,lower_bound,period,deadline,priority\nuart_rx,120,80,false,1000,1000,\
+             2\napp::control,900,300,true,10000,5000,\nidle,10,10,false,,,\n"
+        );
+        let json = tasks.export(ExportFormat::Json);
+        assert!(json.contains(
+            "{\"name\": \"uart_rx\", \"wcet\": 120, \"bcet\": 80, \"lower_bound\": false, \
+             \"period\": 1000, \"deadline\": 1000, \"priority\": 2},"
+        ));
+        assert!(json.contains("\"period\": null, \"deadline\": null, \"priority\": null}\n]}"));
+
+        let mast = tasks.to_mast();
+        assert!(mast.contains("Name => app__control,\n   Worst_Case_Execution_Time => 900"));
+        assert!(mast.contains("-- The execution times of app__control are lower bounds"));
+        assert!(
+            mast.contains("Deadline => 5000,\n         Referenced_Event => app__control_release")
+        );
+        assert!(mast.contains("(Type => Unbounded,\n       Name => idle_release)"));
+        assert!(mast.contains("Name => idle_done)),"));
+
+        assert_eq!(ExportFormat::from_path("tasks.csv"), ExportFormat::Csv);
+        assert_eq!(ExportFormat::from_path("tasks.json"), ExportFormat::Json);
+        assert_eq!(ExportFormat::from_path("tasks.txt"), ExportFormat::Mast);
+    }
+}
@@ -0,0 +1,201 @@
+//! Constraint independence partitioning.
+//!
+//! The constraints of a path are split into groups that do not share any
+//! symbols, and only the groups that share symbols with a query are sent to
+//! the solver. Long paths often accumulate constraints on many unrelated
+//! inputs, such as one constraint per loop iteration on different array
+//! elements, that cannot change the answer to a query on a single value.
+//!
+//! Each expression carries the set of [`Symbols`] it is built from. Symbolic
+//! arrays are a single symbol together with everything written to them, so
+//! all reads from the same memory end up in the same group.
+
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Counter used to give every unconstrained value a unique symbol.
+static NEXT_SYMBOL: AtomicU64 = AtomicU64::new(0);
+
+/// The set of symbols an expression depends on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbols(Option<Arc<BTreeSet<u64>>>);
+
+impl Symbols {
+    /// The symbols of a constant expression.
+    pub const fn none() -> Self {
+        Self(None)
+    }
+
+    /// Creates a new unique symbol.
+    pub fn fresh() -> Self {
+        let symbol = NEXT_SYMBOL.fetch_add(1, Ordering::Relaxed);
+        Self(Some(Arc::new(BTreeSet::from([symbol]))))
+    }
+
+    /// Returns true if the expression does not depend on any symbols.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns the symbols of an expression built from both `self` and
+    /// `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        match (&self.0, &other.0) {
+            (None, _) => other.clone(),
+            (_, None) => self.clone(),
+            (Some(lhs), Some(rhs)) if Arc::ptr_eq(lhs, rhs) || rhs.is_subset(lhs) => self.clone(),
+            (Some(lhs), Some(rhs)) if lhs.is_subset(rhs) => other.clone(),
+            (Some(lhs), Some(rhs)) => Self(Some(Arc::new(lhs.union(rhs).copied().collect()))),
+        }
+    }
+
+    /// Returns true if `self` and `other` share any symbol.
+    pub fn intersects(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(lhs), Some(rhs)) => !lhs.is_disjoint(rhs),
+            _ => false,
+        }
+    }
+}
+
+/// Constraints of a path in scopes that can be pushed and popped.
+#[derive(Debug, Clone)]
+pub struct ConstraintSet<E> {
+    scopes: Vec<Vec<(E, Symbols)>>,
+}
+
+impl<E: Clone> ConstraintSet<E> {
+    /// Creates an empty set with a single scope.
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![vec![]],
+        }
+    }
+
+    /// Opens a new scope.
+    pub fn push(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    /// Removes all constraints added since the matching [`push`](Self::push).
+    /// The outermost scope is never removed.
+    pub fn pop(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    /// Adds a constraint to the current scope.
+    pub fn add(&mut self, constraint: E, symbols: Symbols) {
+        self.scopes
+            .last_mut()
+            .expect("there is always a scope")
+            .push((constraint, symbols));
+    }
+
+    /// Number of constraints in all scopes.
+    pub fn len(&self) -> usize {
+        self.scopes.iter().map(Vec::len).sum()
+    }
+
+    /// Returns true if there are no constraints.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a set with a single scope that holds the first `len`
+    /// constraints.
+    pub fn prefix(&self, len: usize) -> Self {
+        Self {
+            scopes: vec![self.constraints().take(len).cloned().collect()],
+        }
+    }
+
+    /// Returns all constraints.
+    pub fn all(&self) -> Vec<E> {
+        self.constraints()
+            .map(|(constraint, _)| constraint.clone())
+            .collect()
+    }
+
+    /// Returns the constraints that `symbols` depend on, the groups of
+    /// constraints that transitively share a symbol with `symbols`.
+    ///
+    /// Constraints without symbols are always returned, as a constant false
+    /// constraint makes every query unsatisfiable.
+    pub fn relevant(&self, symbols: &Symbols) -> Vec<E> {
+        let mut selected: Vec<bool> = self
+            .constraints()
+            .map(|(_, symbols)| symbols.is_empty())
+            .collect();
+        let mut reached = symbols.clone();
+        loop {
+            let mut changed = false;
+            for ((_, symbols), selected) in self.constraints().zip(selected.iter_mut()) {
+                if !*selected && symbols.intersects(&reached) {
+                    *selected = true;
+                    reached = reached.union(symbols);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        self.constraints()
+            .zip(selected)
+            .filter(|(_, selected)| *selected)
+            .map(|((constraint, _), _)| constraint.clone())
+            .collect()
+    }
+
+    /// Returns the constraints of all scopes together with their symbols, in
+    /// the order they were added.
+    pub fn constraints(&self) -> impl Iterator<Item = &(E, Symbols)> {
+        self.scopes.iter().flatten()
+    }
+}
+
+impl<E: Clone> Default for ConstraintSet<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConstraintSet, Symbols};
+
+    #[test]
+    fn test_relevant_constraints() {
+        let a = Symbols::fresh();
+        let b = Symbols::fresh();
+        let c = Symbols::fresh();
+        let d = Symbols::fresh();
+
+        let mut constraints = ConstraintSet::new();
+        constraints.add("a", a.clone());
+        constraints.add("b == c", b.union(&c));
+        constraints.add("const", Symbols::none());
+        constraints.push();
+        constraints.add("c == d", c.union(&d));
+        constraints.add("d", d.clone());
+
+        assert_eq!(constraints.relevant(&a), vec!["a", "const"]);
+        assert_eq!(constraints.relevant(&d), vec![
+            "b == c", "const", "c == d", "d"
+        ]);
+        assert_eq!(constraints.relevant(&Symbols::none()), vec!["const"]);
+        assert_eq!(constraints.len(), 5);
+
+        constraints.pop();
+        assert_eq!(constraints.relevant(&b), vec!["b == c", "const"]);
+        assert_eq!(constraints.relevant(&d), vec!["const"]);
+    }
+}
@@ -1,21 +1,39 @@
 use std::fmt::Debug;
 
+pub mod independence;
+#[cfg(feature = "boolector")]
 pub mod smt_boolector;
+#[cfg(not(feature = "boolector"))]
+pub mod smt_sat;
 
+#[cfg(feature = "boolector")]
 pub type DExpr = smt_boolector::BoolectorExpr;
+#[cfg(feature = "boolector")]
 pub type DSolver = smt_boolector::BoolectorIncrementalSolver;
+#[cfg(feature = "boolector")]
 pub type DContext = smt_boolector::BoolectorSolverContext;
+#[cfg(feature = "boolector")]
 pub type DArray = smt_boolector::BoolectorArray;
 
+#[cfg(not(feature = "boolector"))]
+pub type DExpr = smt_sat::SatExpr;
+#[cfg(not(feature = "boolector"))]
+pub type DSolver = smt_sat::SatIncrementalSolver;
+#[cfg(not(feature = "boolector"))]
+pub type DContext = smt_sat::SatSolverContext;
+#[cfg(not(feature = "boolector"))]
+pub type DArray = smt_sat::SatArray;
+
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
 pub enum SolverError {
     /// The set of constraints added to the solution are unsatisfiable.
     #[error("Unsat")]
     Unsat,
 
-    /// Unknown error passed along from the SMT solver used.
-    #[error("Unknown")]
-    Unknown,
+    /// The SMT solver could not decide the query, such as when it timed out.
+    /// Holds a description of the query.
+    #[error("Unknown result for {0}")]
+    Unknown(String),
 
     /// Exceeded the passed maximum number of solutions.
     #[error("Exceeded number of solutions")]
@@ -1,12 +1,17 @@
 #![allow(clippy::len_without_is_empty)]
-use std::{cmp::Ordering, rc::Rc};
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
 use boolector::{Btor, BV};
 
 use super::BoolectorSolverContext;
+use crate::smt::independence::Symbols;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BoolectorExpr(pub(crate) BV<Rc<Btor>>);
+pub struct BoolectorExpr(pub(crate) BV<Arc<Btor>>, pub(crate) Symbols);
 
 impl BoolectorExpr {
     /// Returns the bit width of the [Expression].
@@ -19,7 +24,7 @@ impl BoolectorExpr {
     pub fn zero_ext(&self, width: u32) -> Self {
         assert!(self.len() <= width);
         match self.len().cmp(&width) {
-            Ordering::Less => BoolectorExpr(self.0.uext(width - self.len())),
+            Ordering::Less => BoolectorExpr(self.0.uext(width - self.len()), self.1.clone()),
             Ordering::Equal => self.clone(),
             Ordering::Greater => todo!(),
         }
@@ -30,7 +35,7 @@ impl BoolectorExpr {
     pub fn sign_ext(&self, width: u32) -> Self {
         assert!(self.len() <= width);
         match self.len().cmp(&width) {
-            Ordering::Less => BoolectorExpr(self.0.sext(width - self.len())),
+            Ordering::Less => BoolectorExpr(self.0.sext(width - self.len()), self.1.clone()),
             Ordering::Equal => self.clone(),
             Ordering::Greater => todo!(),
         }
@@ -48,14 +53,14 @@ impl BoolectorExpr {
     /// width, the result is returned as an [Expression] of width `1`.
     pub fn eq(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0._eq(&other.0))
+        Self(self.0._eq(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] inequality check. Both [Expression]s must have the same bit
     /// width, the result is returned as an [Expression] of width `1`.
     pub fn ne(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0._ne(&other.0))
+        Self(self.0._ne(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] unsigned greater than. Both [Expression]s must have the
@@ -63,7 +68,7 @@ impl BoolectorExpr {
     /// `1`.
     pub fn ugt(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.ugt(&other.0))
+        Self(self.0.ugt(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] unsigned greater than or equal. Both [Expression]s must
@@ -71,14 +76,14 @@ impl BoolectorExpr {
     /// of width `1`.
     pub fn ugte(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.ugte(&other.0))
+        Self(self.0.ugte(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] unsigned less than. Both [Expression]s must have the same
     /// bit width, the result is returned as an [Expression] of width `1`.
     pub fn ult(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.ult(&other.0))
+        Self(self.0.ult(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] unsigned less than or equal. Both [Expression]s must have
@@ -86,14 +91,14 @@ impl BoolectorExpr {
     /// width `1`.
     pub fn ulte(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.ulte(&other.0))
+        Self(self.0.ulte(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] signed greater than. Both [Expression]s must have the same
     /// bit width, the result is returned as an [Expression] of width `1`.
     pub fn sgt(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.sgt(&other.0))
+        Self(self.0.sgt(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] signed greater or equal than. Both [Expression]s must have
@@ -101,14 +106,14 @@ impl BoolectorExpr {
     /// width `1`.
     pub fn sgte(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.sgte(&other.0))
+        Self(self.0.sgte(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] signed less than. Both [Expression]s must have the same bit
     /// width, the result is returned as an [Expression] of width `1`.
     pub fn slt(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.slt(&other.0))
+        Self(self.0.slt(&other.0), self.1.union(&other.1))
     }
 
     /// [Expression] signed less than or equal. Both [Expression]s must have the
@@ -116,118 +121,121 @@ impl BoolectorExpr {
     /// `1`.
     pub fn slte(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.slte(&other.0))
+        Self(self.0.slte(&other.0), self.1.union(&other.1))
     }
 
     pub fn add(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.add(&other.0))
+        Self(self.0.add(&other.0), self.1.union(&other.1))
     }
 
     pub fn sub(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.sub(&other.0))
+        Self(self.0.sub(&other.0), self.1.union(&other.1))
     }
 
     pub fn mul(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.mul(&other.0))
+        Self(self.0.mul(&other.0), self.1.union(&other.1))
     }
 
     pub fn udiv(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.udiv(&other.0))
+        Self(self.0.udiv(&other.0), self.1.union(&other.1))
     }
 
     pub fn sdiv(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.sdiv(&other.0))
+        Self(self.0.sdiv(&other.0), self.1.union(&other.1))
     }
 
     pub fn urem(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.urem(&other.0))
+        Self(self.0.urem(&other.0), self.1.union(&other.1))
     }
 
     pub fn srem(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.srem(&other.0))
+        Self(self.0.srem(&other.0), self.1.union(&other.1))
     }
 
     pub fn not(&self) -> Self {
-        Self(self.0.not())
+        Self(self.0.not(), self.1.clone())
     }
 
     pub fn and(&self, other: &Self) -> Self {
-        Self(self.0.and(&other.0))
+        Self(self.0.and(&other.0), self.1.union(&other.1))
     }
 
     pub fn or(&self, other: &Self) -> Self {
-        Self(self.0.or(&other.0))
+        Self(self.0.or(&other.0), self.1.union(&other.1))
     }
 
     pub fn xor(&self, other: &Self) -> Self {
-        Self(self.0.xor(&other.0))
+        Self(self.0.xor(&other.0), self.1.union(&other.1))
     }
 
     /// Shift left logical
     pub fn sll(&self, other: &Self) -> Self {
-        Self(self.0.sll(&other.0))
+        Self(self.0.sll(&other.0), self.1.union(&other.1))
     }
 
     /// Shift right logical
     pub fn srl(&self, other: &Self) -> Self {
-        Self(self.0.srl(&other.0))
+        Self(self.0.srl(&other.0), self.1.union(&other.1))
     }
 
     /// Shift right arithmetic
     pub fn sra(&self, other: &Self) -> Self {
-        Self(self.0.sra(&other.0))
+        Self(self.0.sra(&other.0), self.1.union(&other.1))
     }
 
     pub fn ite(&self, then_bv: &Self, else_bv: &Self) -> Self {
         assert_eq!(self.len(), 1);
-        Self(self.0.cond_bv(&then_bv.0, &else_bv.0))
+        Self(
+            self.0.cond_bv(&then_bv.0, &else_bv.0),
+            self.1.union(&then_bv.1).union(&else_bv.1),
+        )
     }
 
     pub fn concat(&self, other: &Self) -> Self {
-        Self(self.0.concat(&other.0))
+        Self(self.0.concat(&other.0), self.1.union(&other.1))
     }
 
     pub fn slice(&self, low: u32, high: u32) -> Self {
         assert!(low <= high);
         assert!(high <= self.len());
-        Self(self.0.slice(high, low))
+        Self(self.0.slice(high, low), self.1.clone())
     }
 
     pub fn uaddo(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.uaddo(&other.0))
+        Self(self.0.uaddo(&other.0), self.1.union(&other.1))
     }
 
     pub fn saddo(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.saddo(&other.0))
+        Self(self.0.saddo(&other.0), self.1.union(&other.1))
     }
 
     pub fn usubo(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.usubo(&other.0))
+        Self(self.0.usubo(&other.0), self.1.union(&other.1))
     }
 
     pub fn ssubo(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.ssubo(&other.0))
+        Self(self.0.ssubo(&other.0), self.1.union(&other.1))
     }
 
     pub fn umulo(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.umulo(&other.0))
+        Self(self.0.umulo(&other.0), self.1.union(&other.1))
     }
 
     pub fn smulo(&self, other: &Self) -> Self {
         assert_eq!(self.len(), other.len());
-        Self(self.0.smulo(&other.0))
+        Self(self.0.smulo(&other.0), self.1.union(&other.1))
     }
 
     pub fn simplify(self) -> Self {
@@ -245,6 +253,12 @@ impl BoolectorExpr {
         self.0.as_binary_str().map(|value| value != "0")
     }
 
+    /// Returns true if `self` and `other` are built from a common symbol, see
+    /// [`independence`](crate::smt::independence).
+    pub fn shares_symbols(&self, other: &Self) -> bool {
+        self.1.intersects(&other.1)
+    }
+
     pub fn to_binary_string(&self) -> String {
         // TODO: Check if there's a better way to get the an underlying string.
         if self.len() <= 64 {
@@ -260,7 +274,11 @@ impl BoolectorExpr {
 
     fn get_ctx(&self) -> BoolectorSolverContext {
         let ctx = self.0.get_btor();
-        BoolectorSolverContext { ctx }
+        // Only used to create constants, no queries are solved.
+        BoolectorSolverContext {
+            ctx,
+            solver_time: Arc::default(),
+        }
     }
 
     pub fn replace_part(&self, start_idx: u32, replace_with: Self) -> Self {
@@ -359,3 +377,13 @@ impl BoolectorExpr {
             .simplify()
     }
 }
+
+/// Hashes the width and, for constants, the value. Expressions are equal if
+/// they are the same node of the solver, so expressions with equal hashes have
+/// to be told apart with `==`.
+impl Hash for BoolectorExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        self.0.as_binary_str().hash(state);
+    }
+}
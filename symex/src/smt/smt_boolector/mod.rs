@@ -1,4 +1,7 @@
-use std::rc::Rc;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use boolector::{
     option::{BtorOption, NumberFormat},
@@ -13,49 +16,69 @@ mod solver;
 pub(super) use expr::BoolectorExpr;
 pub(super) use solver::BoolectorIncrementalSolver;
 
+use super::independence::Symbols;
+
 /// `BoolectorSolverContext` handles the creation of expressions.
 ///
 /// Keeps track of all the created expressions and the internal SMT state.
 #[derive(Debug, Clone)]
 pub struct BoolectorSolverContext {
-    pub ctx: Rc<Btor>,
+    pub ctx: Arc<Btor>,
+
+    /// Total time spent solving queries of the solvers of this context.
+    solver_time: Arc<Mutex<Duration>>,
 }
 
 impl BoolectorSolverContext {
     #[must_use]
     /// Create a new uninitialized expression of size `bits`.
     pub fn unconstrained(&self, bits: u32, name: &str) -> BoolectorExpr {
-        BoolectorExpr(BV::new(self.ctx.clone(), bits, Some(name)))
+        BoolectorExpr(
+            BV::new(self.ctx.clone(), bits, Some(name)),
+            Symbols::fresh(),
+        )
     }
 
     #[must_use]
     /// Create a new expression set equal to `1` of size `bits`.
     pub fn one(&self, bits: u32) -> BoolectorExpr {
-        BoolectorExpr(boolector::BV::from_u64(self.ctx.clone(), 1, bits))
+        BoolectorExpr(
+            boolector::BV::from_u64(self.ctx.clone(), 1, bits),
+            Symbols::none(),
+        )
     }
 
     #[must_use]
     /// Create a new expression set to zero of size `bits`.
     pub fn zero(&self, bits: u32) -> BoolectorExpr {
-        BoolectorExpr(boolector::BV::zero(self.ctx.clone(), bits))
+        BoolectorExpr(boolector::BV::zero(self.ctx.clone(), bits), Symbols::none())
     }
 
     #[must_use]
     /// Create a new expression from a boolean value.
     pub fn from_bool(&self, value: bool) -> BoolectorExpr {
-        BoolectorExpr(boolector::BV::from_bool(self.ctx.clone(), value))
+        BoolectorExpr(
+            boolector::BV::from_bool(self.ctx.clone(), value),
+            Symbols::none(),
+        )
     }
 
     #[must_use]
     /// Create a new expression from an `u64` value of size `bits`.
     pub fn from_u64(&self, value: u64, bits: u32) -> BoolectorExpr {
-        BoolectorExpr(boolector::BV::from_u64(self.ctx.clone(), value, bits))
+        BoolectorExpr(
+            boolector::BV::from_u64(self.ctx.clone(), value, bits),
+            Symbols::none(),
+        )
     }
 
     #[must_use]
     /// Create an expression of size `bits` from a binary string.
     pub fn from_binary_string(&self, bits: &str) -> BoolectorExpr {
-        BoolectorExpr(boolector::BV::from_binary_str(self.ctx.clone(), bits))
+        BoolectorExpr(
+            boolector::BV::from_binary_str(self.ctx.clone(), bits),
+            Symbols::none(),
+        )
     }
 
     #[must_use]
@@ -111,18 +134,37 @@ impl BoolectorSolverContext {
     #[must_use]
     pub fn new() -> Self {
         let btor = Btor::new();
-        let ctx = Rc::new(btor);
+        let ctx = Arc::new(btor);
         ctx.set_opt(BtorOption::Incremental(true));
         ctx.set_opt(BtorOption::PrettyPrint(true));
         ctx.set_opt(BtorOption::OutputNumberFormat(NumberFormat::Hexadecimal));
 
-        Self { ctx }
+        Self {
+            ctx,
+            solver_time: Arc::default(),
+        }
+    }
+
+    /// Sets the time limit of each query, a query that does not finish in time
+    /// fails with [`SolverError::Unknown`](crate::smt::SolverError::Unknown).
+    /// `None` removes the limit.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        self.ctx.set_opt(BtorOption::SolverTimeout(timeout));
+    }
+
+    /// Get the total time spent solving queries in the solvers created from
+    /// this context.
+    pub fn solver_time(&self) -> Duration {
+        *self
+            .solver_time
+            .lock()
+            .expect("the solver time is never used while panicking")
     }
 }
 
 /// Symbolic array where both index and stored values are symbolic.
 #[derive(Debug, Clone)]
-pub struct BoolectorArray(pub(super) boolector::Array<Rc<Btor>>);
+pub struct BoolectorArray(pub(super) boolector::Array<Arc<Btor>>, Symbols);
 
 impl BoolectorArray {
     #[must_use]
@@ -142,17 +184,18 @@ impl BoolectorArray {
             Some(name),
         );
 
-        Self(memory)
+        Self(memory, Symbols::fresh())
     }
 
     #[must_use]
     /// Return value with specific index.
     pub fn read(&self, index: &BoolectorExpr) -> BoolectorExpr {
-        BoolectorExpr(self.0.read(&index.0))
+        BoolectorExpr(self.0.read(&index.0), self.1.union(&index.1))
     }
 
     /// Write value to index.
     pub fn write(&mut self, index: &BoolectorExpr, value: &BoolectorExpr) {
         self.0 = self.0.write(&index.0, &value.0);
+        self.1 = self.1.union(&index.1).union(&value.1);
     }
 }
@@ -1,4 +1,7 @@
-use std::rc::Rc;
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
 
 use boolector::{
     option::{BtorOption, ModelGen},
@@ -8,29 +11,74 @@ use boolector::{
 };
 
 use super::{BoolectorExpr, BoolectorSolverContext};
-use crate::smt::{Solutions, SolverError};
+use crate::smt::{
+    independence::{ConstraintSet, Symbols},
+    Solutions,
+    SolverError,
+};
 
+/// Incremental solver over the constraints of a path.
+///
+/// Constraints are not asserted in Boolector but kept in a
+/// [`ConstraintSet`], each query only assumes the constraints that share
+/// symbols with it, see [`independence`](crate::smt::independence).
 #[derive(Debug, Clone)]
 pub struct BoolectorIncrementalSolver {
-    ctx: Rc<Btor>,
+    ctx: Arc<Btor>,
+    constraints: Arc<Mutex<ConstraintSet<BV<Arc<Btor>>>>>,
+    solver_time: Arc<Mutex<Duration>>,
 }
 
 impl BoolectorIncrementalSolver {
     pub fn new(ctx: &BoolectorSolverContext) -> Self {
         Self {
             ctx: ctx.ctx.clone(),
+            constraints: Arc::new(Mutex::new(ConstraintSet::new())),
+            solver_time: ctx.solver_time.clone(),
         }
     }
 
-    #[allow(clippy::unused_self)]
-    fn check_sat_result(&self, sat_result: SolverResult) -> Result<bool, SolverError> {
-        match sat_result {
+    fn constraints(&self) -> MutexGuard<'_, ConstraintSet<BV<Arc<Btor>>>> {
+        self.constraints
+            .lock()
+            .expect("the constraints are never used while panicking")
+    }
+
+    /// Assumes `constraints` and solves, the assumptions are forgotten after
+    /// the call.
+    ///
+    /// `query` describes what was solved for if the solver cannot decide, such
+    /// as when it times out.
+    fn check_sat(
+        &self,
+        constraints: Vec<BV<Arc<Btor>>>,
+        query: impl FnOnce() -> String,
+    ) -> Result<bool, SolverError> {
+        for constraint in &constraints {
+            constraint.assume();
+        }
+        let start = Instant::now();
+        let result = self.ctx.sat();
+        *self
+            .solver_time
+            .lock()
+            .expect("the solver time is never used while panicking") += start.elapsed();
+        match result {
             SolverResult::Sat => Ok(true),
             SolverResult::Unsat => Ok(false),
-            SolverResult::Unknown => Err(SolverError::Unknown),
+            SolverResult::Unknown => Err(SolverError::Unknown(format!(
+                "{} under {} path constraints",
+                query(),
+                constraints.len()
+            ))),
         }
     }
 
+    /// Returns the constraints that `symbols` depend on.
+    fn relevant(&self, symbols: &Symbols) -> Vec<BV<Arc<Btor>>> {
+        self.constraints().relevant(symbols)
+    }
+
     pub fn get_value(&self, expr: &BoolectorExpr) -> Result<BoolectorExpr, SolverError> {
         let expr = expr.clone().simplify();
         if expr.get_constant().is_some() {
@@ -40,12 +88,14 @@ impl BoolectorIncrementalSolver {
         self.ctx.set_opt(BtorOption::ModelGen(ModelGen::All));
 
         let result = || {
-            if self.is_sat()? {
-                self.is_sat()?;
+            if self.check_sat(self.relevant(&expr.1), || format!("value of {:?}", expr.0))? {
                 let solution = expr.0.get_a_solution().disambiguate();
                 let solution = solution.as_01x_str();
 
-                let solution = BoolectorExpr(BV::from_binary_str(self.ctx.clone(), solution));
+                let solution = BoolectorExpr(
+                    BV::from_binary_str(self.ctx.clone(), solution),
+                    Symbols::none(),
+                );
                 Ok(solution)
             } else {
                 Err(SolverError::Unsat)
@@ -58,12 +108,97 @@ impl BoolectorIncrementalSolver {
         result
     }
 
-    pub fn push(&self) {
+    /// Finds a concrete value for each of `exprs` from a single model of the
+    /// constraints.
+    ///
+    /// The values are consistent with each other, unlike separate calls to
+    /// [`get_value`](Self::get_value), and only one query is sent to the
+    /// solver.
+    pub fn get_model(&self, exprs: &[BoolectorExpr]) -> Result<Vec<BoolectorExpr>, SolverError> {
+        let exprs: Vec<BoolectorExpr> = exprs.iter().map(|expr| expr.clone().simplify()).collect();
+        let mut symbols = Symbols::none();
+        for expr in &exprs {
+            symbols = symbols.union(&expr.1);
+        }
+        if symbols.is_empty() {
+            return Ok(exprs);
+        }
+
+        self.ctx.set_opt(BtorOption::ModelGen(ModelGen::All));
+
+        let result = || {
+            let query = || format!("a model of {} values", exprs.len());
+            if !self.check_sat(self.relevant(&symbols), query)? {
+                return Err(SolverError::Unsat);
+            }
+            Ok(exprs
+                .iter()
+                .map(|expr| {
+                    let solution = expr.0.get_a_solution().disambiguate();
+                    BoolectorExpr(
+                        BV::from_binary_str(self.ctx.clone(), solution.as_01x_str()),
+                        Symbols::none(),
+                    )
+                })
+                .collect())
+        };
+        let result = result();
+
+        self.ctx.set_opt(BtorOption::ModelGen(ModelGen::Disabled));
+
+        result
+    }
+
+    /// The asserted constraints, in the order they were asserted.
+    pub fn asserted(&self) -> Vec<BoolectorExpr> {
+        self.constraints()
+            .constraints()
+            .map(|(constraint, symbols)| BoolectorExpr(constraint.clone(), symbols.clone()))
+            .collect()
+    }
+
+    /// An SMT-LIB script that asserts the asserted constraints, they are
+    /// asserted in a context that is popped after printing.
+    pub fn to_smtlib(&self) -> String {
         self.ctx.push(1);
+        for (constraint, _) in self.constraints().constraints() {
+            constraint.assert();
+        }
+        let script = self.ctx.print_constraints();
+        self.ctx.pop(1);
+        script
+    }
+
+    /// Conjunction of the asserted constraints, true if there are none.
+    pub fn path_condition(&self) -> BoolectorExpr {
+        let mut condition = BoolectorExpr(BV::from_bool(self.ctx.clone(), true), Symbols::none());
+        for (constraint, symbols) in self.constraints().constraints() {
+            condition = condition.and(&BoolectorExpr(constraint.clone(), symbols.clone()));
+        }
+        condition
+    }
+
+    /// Number of asserted constraints in all scopes.
+    pub fn constraint_count(&self) -> usize {
+        self.constraints().len()
+    }
+
+    /// Creates a solver that does not share its constraints with this solver,
+    /// holding the first `count` constraints asserted in this solver.
+    pub fn detach(&self, count: usize) -> Self {
+        Self {
+            ctx: self.ctx.clone(),
+            constraints: Arc::new(Mutex::new(self.constraints().prefix(count))),
+            solver_time: self.solver_time.clone(),
+        }
+    }
+
+    pub fn push(&self) {
+        self.constraints().push();
     }
 
     pub fn pop(&self) {
-        self.ctx.pop(1);
+        self.constraints().pop();
     }
 
     /// Solve for the current solver state, and returns if the result is
@@ -71,17 +206,23 @@ impl BoolectorIncrementalSolver {
     ///
     /// All asserts and assumes are implicitly combined with a boolean and.
     /// Returns true or false, and [`SolverError::Unknown`] if the result
-    /// cannot be determined.
+    /// cannot be determined, such as when the query times out, see
+    /// [`BoolectorSolverContext::set_timeout`].
     pub fn is_sat(&self) -> Result<bool, SolverError> {
-        let sat_result = self.ctx.sat();
-        self.check_sat_result(sat_result)
+        let constraints = self.constraints().all();
+        self.check_sat(constraints, || "feasibility of the path".to_owned())
     }
 
     /// Solve for the solver state with the assumption of the passed constraint.
+    ///
+    /// Only the constraints that share symbols with `constraint` are sent to
+    /// the solver, the others cannot change the result.
     pub fn is_sat_with_constraint(&self, constraint: &BoolectorExpr) -> Result<bool, SolverError> {
-        // Assume the constraint, will be forgotten after the next call to `is_sat`.
+        // Assume the constraint, will be forgotten after the next call to `sat`.
         constraint.0.assume();
-        self.is_sat()
+        self.check_sat(self.relevant(&constraint.1), || {
+            format!("{:?}", constraint.0)
+        })
     }
 
     /// Solve for the solver state with the assumption of the passed
@@ -90,19 +231,25 @@ impl BoolectorIncrementalSolver {
         &self,
         constraints: &[BoolectorExpr],
     ) -> Result<bool, SolverError> {
+        let mut symbols = Symbols::none();
         for constraint in constraints {
             constraint.0.assume();
+            symbols = symbols.union(&constraint.1);
         }
-        self.is_sat()
+        self.check_sat(self.relevant(&symbols), || format!("{:?}", constraints))
     }
 
-    #[allow(clippy::unused_self)]
     /// Add the constraint to the solver.
     ///
     /// The passed constraint will be implicitly combined with the current state
-    /// in a boolean `and`. Asserted constraints cannot be removed.
+    /// in a boolean `and`. Asserted constraints are only removed by popping the
+    /// scope they were added in.
     pub fn assert(&self, constraint: &BoolectorExpr) {
-        constraint.0.assert();
+        if constraint.get_constant_bool() == Some(true) {
+            return;
+        }
+        self.constraints()
+            .add(constraint.0.clone(), constraint.1.clone());
     }
 
     /// Find solutions to `expr`.
@@ -180,10 +327,14 @@ impl BoolectorIncrementalSolver {
         self.ctx.set_opt(BtorOption::ModelGen(ModelGen::All));
 
         let result = || {
-            while solutions.len() < upper_bound && self.is_sat()? {
+            let query = || format!("solutions of {:?}", expr.0);
+            while solutions.len() < upper_bound && self.check_sat(self.relevant(&expr.1), query)? {
                 let solution = expr.0.get_a_solution().disambiguate();
                 let solution = solution.as_01x_str();
-                let solution = BoolectorExpr(BV::from_binary_str(self.ctx.clone(), solution));
+                let solution = BoolectorExpr(
+                    BV::from_binary_str(self.ctx.clone(), solution),
+                    Symbols::none(),
+                );
 
                 // Constrain the next value to not be an already found solution.
                 self.assert(&expr.ne(&solution));
@@ -191,7 +342,7 @@ impl BoolectorIncrementalSolver {
                 solutions.push(solution);
             }
 
-            let exists_more_solutions = self.is_sat()?;
+            let exists_more_solutions = self.check_sat(self.relevant(&expr.1), query)?;
             if exists_more_solutions {
                 return Ok(Solutions::AtLeast(solutions));
             }
@@ -0,0 +1,556 @@
+//! Constant bit-vectors of any width.
+//!
+//! Values of up to 64 bits are stored in a single word as most expressions
+//! are, wider values are stored one bit at a time. The operations follow the
+//! semantics of SMT-LIB, e.g. division by zero yields all ones.
+
+use std::sync::Arc;
+
+/// A constant bit-vector.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Bits {
+    /// A value of at most 64 bits, the bits above `width` are zero.
+    Word { width: u32, value: u64 },
+
+    /// A value of more than 64 bits, least significant bit first.
+    Wide(Arc<[bool]>),
+}
+
+/// Returns a mask of the `width` least significant bits.
+const fn mask(width: u32) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+/// Sign extends the `width` bit `value` to 64 bits.
+const fn signed(value: u64, width: u32) -> i64 {
+    ((value << (64 - width)) as i64) >> (64 - width)
+}
+
+impl Bits {
+    /// Creates a `width` bit value from the least significant bits of `value`.
+    pub(super) fn from_u64(value: u64, width: u32) -> Self {
+        assert!(width > 0, "bit-vectors have at least one bit");
+        if width <= 64 {
+            Self::Word {
+                width,
+                value: value & mask(width),
+            }
+        } else {
+            Self::from_bits((0..width).map(|bit| bit < 64 && value >> bit & 1 == 1))
+        }
+    }
+
+    /// Creates a value from its bits, least significant bit first.
+    pub(super) fn from_bits(bits: impl IntoIterator<Item = bool>) -> Self {
+        let bits: Vec<bool> = bits.into_iter().collect();
+        assert!(!bits.is_empty(), "bit-vectors have at least one bit");
+        if bits.len() <= 64 {
+            Self::Word {
+                width: bits.len() as u32,
+                value: bits
+                    .iter()
+                    .rev()
+                    .fold(0, |value, bit| value << 1 | *bit as u64),
+            }
+        } else {
+            Self::Wide(bits.into())
+        }
+    }
+
+    /// Parses a string of `0` and `1`, most significant bit first.
+    pub(super) fn from_binary_str(bits: &str) -> Self {
+        Self::from_bits(bits.chars().rev().map(|bit| match bit {
+            '0' => false,
+            '1' => true,
+            _ => panic!("invalid binary digit {bit:?}"),
+        }))
+    }
+
+    /// Number of bits in the value.
+    pub(super) fn width(&self) -> u32 {
+        match self {
+            Self::Word { width, .. } => *width,
+            Self::Wide(bits) => bits.len() as u32,
+        }
+    }
+
+    /// Returns the value if it fits in 64 bits.
+    pub(super) fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Word { value, .. } => Some(*value),
+            Self::Wide(_) => None,
+        }
+    }
+
+    /// Returns bit `index`, counted from the least significant bit.
+    pub(super) fn bit(&self, index: u32) -> bool {
+        match self {
+            Self::Word { value, .. } => value >> index & 1 == 1,
+            Self::Wide(bits) => bits[index as usize],
+        }
+    }
+
+    /// Returns the bits of the value, least significant bit first.
+    pub(super) fn bits(&self) -> Vec<bool> {
+        (0..self.width()).map(|index| self.bit(index)).collect()
+    }
+
+    /// Returns true if all bits are zero.
+    pub(super) fn is_zero(&self) -> bool {
+        match self {
+            Self::Word { value, .. } => *value == 0,
+            Self::Wide(bits) => bits.iter().all(|bit| !bit),
+        }
+    }
+
+    /// Returns true if all bits are one.
+    pub(super) fn is_ones(&self) -> bool {
+        match self {
+            Self::Word { width, value } => *value == mask(*width),
+            Self::Wide(bits) => bits.iter().all(|bit| *bit),
+        }
+    }
+
+    /// Returns true if the value is one.
+    pub(super) fn is_one(&self) -> bool {
+        match self {
+            Self::Word { value, .. } => *value == 1,
+            Self::Wide(bits) => bits[0] && bits[1..].iter().all(|bit| !bit),
+        }
+    }
+
+    /// Formats the value as `0` and `1`, most significant bit first.
+    pub(super) fn to_binary_string(&self) -> String {
+        (0..self.width())
+            .rev()
+            .map(|index| if self.bit(index) { '1' } else { '0' })
+            .collect()
+    }
+
+    fn bool(value: bool) -> Self {
+        Self::from_u64(value as u64, 1)
+    }
+
+    fn sign(&self) -> bool {
+        self.bit(self.width() - 1)
+    }
+
+    pub(super) fn not(&self) -> Self {
+        match self {
+            Self::Word { width, value } => Self::from_u64(!value, *width),
+            _ => Self::from_bits(self.bits().into_iter().map(|bit| !bit)),
+        }
+    }
+
+    /// Applies `op` to each pair of bits.
+    fn bitwise(&self, other: &Self, op: impl Fn(bool, bool) -> bool) -> Self {
+        Self::from_bits(
+            self.bits()
+                .into_iter()
+                .zip(other.bits())
+                .map(|(a, b)| op(a, b)),
+        )
+    }
+
+    pub(super) fn and(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a & b, *width)
+            }
+            _ => self.bitwise(other, |a, b| a && b),
+        }
+    }
+
+    pub(super) fn or(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a | b, *width)
+            }
+            _ => self.bitwise(other, |a, b| a || b),
+        }
+    }
+
+    pub(super) fn xor(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a ^ b, *width)
+            }
+            _ => self.bitwise(other, |a, b| a != b),
+        }
+    }
+
+    pub(super) fn neg(&self) -> Self {
+        Self::from_u64(0, self.width()).sub(self)
+    }
+
+    pub(super) fn add(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a.wrapping_add(*b), *width)
+            }
+            _ => Self::from_bits(add_bits(&self.bits(), &other.bits(), false).0),
+        }
+    }
+
+    pub(super) fn sub(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a.wrapping_sub(*b), *width)
+            }
+            _ => Self::from_bits(add_bits(&self.bits(), &other.not().bits(), true).0),
+        }
+    }
+
+    pub(super) fn mul(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a.wrapping_mul(*b), *width)
+            }
+            _ => Self::from_bits(mul_bits(&self.bits(), &other.bits())),
+        }
+    }
+
+    /// Unsigned division, division by zero yields all ones.
+    pub(super) fn udiv(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a.checked_div(*b).unwrap_or(u64::MAX), *width)
+            }
+            _ => Self::from_bits(div_bits(&self.bits(), &other.bits()).0),
+        }
+    }
+
+    /// Unsigned remainder, the remainder of a division by zero is the
+    /// dividend.
+    pub(super) fn urem(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::from_u64(a.checked_rem(*b).unwrap_or(*a), *width)
+            }
+            _ => Self::from_bits(div_bits(&self.bits(), &other.bits()).1),
+        }
+    }
+
+    fn abs(&self) -> Self {
+        if self.sign() {
+            self.neg()
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Signed division rounding towards zero, defined through unsigned
+    /// division of the magnitudes.
+    pub(super) fn sdiv(&self, other: &Self) -> Self {
+        let quotient = self.abs().udiv(&other.abs());
+        if self.sign() != other.sign() {
+            quotient.neg()
+        } else {
+            quotient
+        }
+    }
+
+    /// Signed remainder with the sign of the dividend.
+    pub(super) fn srem(&self, other: &Self) -> Self {
+        let remainder = self.abs().urem(&other.abs());
+        if self.sign() {
+            remainder.neg()
+        } else {
+            remainder
+        }
+    }
+
+    /// Returns the shift amount `self`, saturated to `width`.
+    fn shift_amount(&self, width: u32) -> u32 {
+        match self {
+            Self::Word { value, .. } => (*value).min(width as u64) as u32,
+            Self::Wide(bits) => match bits[64..].iter().any(|bit| *bit) {
+                true => width,
+                false => Self::from_bits(bits[..64].iter().copied())
+                    .as_u64()
+                    .map_or(width, |value| value.min(width as u64) as u32),
+            },
+        }
+    }
+
+    /// Shifts `self` towards the most significant bit by `amount` bits.
+    pub(super) fn sll(&self, amount: &Self) -> Self {
+        let width = self.width();
+        let amount = amount.shift_amount(width);
+        match self {
+            Self::Word { value, .. } if amount < width => Self::from_u64(value << amount, width),
+            _ => {
+                Self::from_bits((0..width).map(|index| index >= amount && self.bit(index - amount)))
+            }
+        }
+    }
+
+    /// Shifts `self` towards the least significant bit by `amount` bits,
+    /// filling with zeroes.
+    pub(super) fn srl(&self, amount: &Self) -> Self {
+        let width = self.width();
+        let amount = amount.shift_amount(width);
+        match self {
+            Self::Word { value, .. } if amount < width => Self::from_u64(value >> amount, width),
+            _ => Self::from_bits(
+                (0..width).map(|index| index + amount < width && self.bit(index + amount)),
+            ),
+        }
+    }
+
+    /// Shifts `self` towards the least significant bit by `amount` bits,
+    /// filling with the sign bit.
+    pub(super) fn sra(&self, amount: &Self) -> Self {
+        let width = self.width();
+        let amount = amount.shift_amount(width);
+        match self {
+            Self::Word { value, .. } if amount < width => {
+                Self::from_u64((signed(*value, width) >> amount) as u64, width)
+            }
+            _ => Self::from_bits((0..width).map(|index| match index + amount < width {
+                true => self.bit(index + amount),
+                false => self.sign(),
+            })),
+        }
+    }
+
+    pub(super) fn eq_bits(&self, other: &Self) -> Self {
+        Self::bool(self == other)
+    }
+
+    pub(super) fn ult(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { value: a, .. }, Self::Word { value: b, .. }) => Self::bool(a < b),
+            _ => Self::bool(self.bits().iter().rev().lt(other.bits().iter().rev())),
+        }
+    }
+
+    pub(super) fn slt(&self, other: &Self) -> Self {
+        match self.sign() == other.sign() {
+            true => self.ult(other),
+            false => Self::bool(self.sign()),
+        }
+    }
+
+    pub(super) fn uaddo(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Word { width, value: a }, Self::Word { value: b, .. }) => {
+                Self::bool((*a as u128 + *b as u128) > mask(*width) as u128)
+            }
+            _ => Self::bool(add_bits(&self.bits(), &other.bits(), false).1),
+        }
+    }
+
+    pub(super) fn saddo(&self, other: &Self) -> Self {
+        let sum = self.add(other);
+        Self::bool(self.sign() == other.sign() && sum.sign() != self.sign())
+    }
+
+    pub(super) fn usubo(&self, other: &Self) -> Self {
+        self.ult(other)
+    }
+
+    pub(super) fn ssubo(&self, other: &Self) -> Self {
+        let difference = self.sub(other);
+        Self::bool(self.sign() != other.sign() && difference.sign() != self.sign())
+    }
+
+    pub(super) fn umulo(&self, other: &Self) -> Self {
+        if let (Self::Word { width, value: a }, Self::Word { value: b, .. }) = (self, other) {
+            return Self::bool(*a as u128 * *b as u128 > mask(*width) as u128);
+        }
+        let width = self.width();
+        let product = self.uext(width).mul(&other.uext(width));
+        Self::bool(!product.slice(width, 2 * width - 1).is_zero())
+    }
+
+    pub(super) fn smulo(&self, other: &Self) -> Self {
+        if let (Self::Word { width, value: a }, Self::Word { value: b, .. }) = (self, other) {
+            let product = signed(*a, *width) as i128 * signed(*b, *width) as i128;
+            let limit = 1i128 << (width - 1);
+            return Self::bool(product < -limit || product >= limit);
+        }
+        let width = self.width();
+        let product = self.sext(width).mul(&other.sext(width));
+        let high = product.slice(width - 1, 2 * width - 1);
+        Self::bool(!high.is_zero() && !high.is_ones())
+    }
+
+    /// Returns bits `low` to `high`, both inclusive.
+    pub(super) fn slice(&self, low: u32, high: u32) -> Self {
+        match self {
+            Self::Word { value, .. } => Self::from_u64(value >> low, high - low + 1),
+            Self::Wide(bits) => Self::from_bits(bits[low as usize..=high as usize].iter().copied()),
+        }
+    }
+
+    /// Returns `self` followed by the less significant bits `low`.
+    pub(super) fn concat(&self, low: &Self) -> Self {
+        match (self, low) {
+            (
+                Self::Word { width, value: high },
+                Self::Word {
+                    width: low_width,
+                    value: low,
+                },
+            ) if width + low_width <= 64 => {
+                Self::from_u64(high << low_width | low, width + low_width)
+            }
+            _ => Self::from_bits(low.bits().into_iter().chain(self.bits())),
+        }
+    }
+
+    /// Extends the value with `bits` zeroes.
+    pub(super) fn uext(&self, bits: u32) -> Self {
+        match self {
+            Self::Word { width, value } if width + bits <= 64 => {
+                Self::from_u64(*value, width + bits)
+            }
+            _ => Self::from_bits(self.bits().into_iter().chain((0..bits).map(|_| false))),
+        }
+    }
+
+    /// Extends the value with `bits` copies of the sign bit.
+    pub(super) fn sext(&self, bits: u32) -> Self {
+        let sign = self.sign();
+        match self {
+            Self::Word { width, value } if width + bits <= 64 => {
+                Self::from_u64(signed(*value, *width) as u64, width + bits)
+            }
+            _ => Self::from_bits(self.bits().into_iter().chain((0..bits).map(|_| sign))),
+        }
+    }
+}
+
+/// Adds `a` and `b` with the carry in `carry`, returns the sum and the carry
+/// out.
+fn add_bits(a: &[bool], b: &[bool], mut carry: bool) -> (Vec<bool>, bool) {
+    let sum = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| {
+            let sum = a ^ b ^ carry;
+            carry = (a & b) | (carry & (a ^ b));
+            sum
+        })
+        .collect();
+    (sum, carry)
+}
+
+/// Multiplies `a` with `b`, truncated to the width of `a`.
+fn mul_bits(a: &[bool], b: &[bool]) -> Vec<bool> {
+    let mut product = vec![false; a.len()];
+    for (shift, bit) in b.iter().enumerate() {
+        if *bit {
+            let mut shifted = vec![false; shift];
+            shifted.extend_from_slice(&a[..a.len() - shift]);
+            product = add_bits(&product, &shifted, false).0;
+        }
+    }
+    product
+}
+
+/// Divides `a` with `b` by restoring division, returns the quotient and the
+/// remainder.
+fn div_bits(a: &[bool], b: &[bool]) -> (Vec<bool>, Vec<bool>) {
+    let width = a.len();
+    let mut quotient = vec![false; width];
+    let mut remainder = vec![false; width];
+    for index in (0..width).rev() {
+        // The shifted out bit makes the remainder larger than the divisor.
+        let overflow = remainder[width - 1];
+        remainder.rotate_right(1);
+        remainder[0] = a[index];
+        let smaller = remainder.iter().rev().lt(b.iter().rev());
+        if overflow || !smaller {
+            let negated: Vec<bool> = b.iter().map(|bit| !bit).collect();
+            remainder = add_bits(&remainder, &negated, true).0;
+            quotient[index] = true;
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Bits;
+
+    /// Returns `value` as a value wider than 64 bits with the same low bits.
+    fn wide(value: u64, width: u32) -> Bits {
+        Bits::from_bits((0..64).map(|bit| value >> bit & 1 == 1))
+            .uext(64)
+            .slice(0, width - 1)
+            .uext(100 - width)
+    }
+
+    #[test]
+    fn test_wide_operations_match_words() {
+        let values = [0, 1, 2, 3, 5, 0x7f, 0x80, 0xff, 0xfe];
+        for a in values {
+            for b in values {
+                let (x, y) = (Bits::from_u64(a, 8), Bits::from_u64(b, 8));
+                let (wx, wy) = (wide(a, 8), wide(b, 8));
+                let narrow = |value: Bits| value.slice(0, 7);
+
+                assert_eq!(narrow(wx.add(&wy)), x.add(&y));
+                assert_eq!(narrow(wx.sub(&wy)), x.sub(&y));
+                assert_eq!(narrow(wx.mul(&wy)), x.mul(&y));
+                assert_eq!(narrow(wx.and(&wy)), x.and(&y));
+                assert_eq!(narrow(wx.xor(&wy)), x.xor(&y));
+                assert_eq!(wx.ult(&wy), x.ult(&y));
+                if b != 0 {
+                    assert_eq!(narrow(wx.udiv(&wy)), x.udiv(&y));
+                }
+                assert_eq!(narrow(wx.urem(&wy)), x.urem(&y));
+                if b < 8 {
+                    assert_eq!(narrow(wx.sll(&wy)), x.sll(&y));
+                    assert_eq!(narrow(wx.srl(&wy)), x.srl(&y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let zero = Bits::from_u64(0, 32);
+        let value = Bits::from_u64(1234, 32);
+        assert_eq!(value.udiv(&zero), Bits::from_u64(u64::MAX, 32));
+        assert_eq!(value.urem(&zero), value);
+        assert_eq!(value.sdiv(&zero), Bits::from_u64(u64::MAX, 32));
+        assert_eq!(value.neg().sdiv(&zero), Bits::from_u64(1, 32));
+        assert_eq!(value.neg().srem(&zero), value.neg());
+    }
+
+    #[test]
+    fn test_signed_operations() {
+        let value = |value: i64| Bits::from_u64(value as u64, 8);
+        assert_eq!(value(-7).sdiv(&value(2)), value(-3));
+        assert_eq!(value(-7).srem(&value(2)), value(-1));
+        assert_eq!(value(7).srem(&value(-2)), value(1));
+        assert_eq!(value(-128).sdiv(&value(-1)), value(-128));
+        assert_eq!(value(-128).sra(&value(3)), value(-16));
+        assert_eq!(value(-128).sra(&value(200)), value(-1));
+        assert_eq!(value(-1).slt(&value(0)), Bits::from_u64(1, 1));
+        assert_eq!(value(100).saddo(&value(100)), Bits::from_u64(1, 1));
+        assert_eq!(value(-100).ssubo(&value(100)), Bits::from_u64(1, 1));
+        assert_eq!(value(-64).smulo(&value(2)), Bits::from_u64(0, 1));
+        assert_eq!(value(64).smulo(&value(2)), Bits::from_u64(1, 1));
+        assert_eq!(value(16).umulo(&value(16)), Bits::from_u64(1, 1));
+    }
+
+    #[test]
+    fn test_concat_and_slice() {
+        let high = Bits::from_u64(0xdead_beef, 32);
+        let low = Bits::from_u64(0x1234_5678_9abc_def0, 64);
+        let value = high.concat(&low);
+        assert_eq!(value.width(), 96);
+        assert_eq!(value.slice(64, 95), high);
+        assert_eq!(value.slice(0, 63), low);
+        assert_eq!(value.slice(60, 67), Bits::from_u64(0xf1, 8));
+        assert_eq!(Bits::from_binary_str(&value.to_binary_string()), value);
+    }
+}
@@ -0,0 +1,627 @@
+//! Translation of expressions to clauses.
+//!
+//! Every bit of an expression is a literal of the SAT solver, the operations
+//! are encoded as circuits of and, xor and multiplexer gates. Gates with
+//! constant inputs are folded and equal gates are shared. Reads from an array
+//! with unconstrained elements are fresh values, constrained to be equal for
+//! equal indices.
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use super::{
+    bits::Bits,
+    cdcl::{Cdcl, Lit, Outcome},
+    node::{ArrayKind, ArrayNode, BinaryOp, Op, Term},
+};
+
+/// The bits of a value, least significant bit first.
+type Bv = Vec<Lit>;
+
+/// Encodes expressions in to a single SAT problem.
+pub(super) struct Blaster {
+    sat: Cdcl,
+    true_lit: Lit,
+    /// Bits of the translated nodes, the nodes are kept so their addresses
+    /// are not reused.
+    terms: HashMap<usize, (Term, Bv)>,
+    ands: HashMap<(Lit, Lit), Lit>,
+    xors: HashMap<(Lit, Lit), Lit>,
+    muxes: HashMap<(Lit, Lit, Lit), Lit>,
+    vars: HashMap<u64, Bv>,
+    /// Index and value of each read from an array with unconstrained elements.
+    reads: HashMap<u64, Vec<(Bv, Bv)>>,
+}
+
+pub(super) fn key(term: &Term) -> usize {
+    Arc::as_ptr(term) as usize
+}
+
+/// Returns the operands of `term`, including the indices and values written
+/// to an array that is read.
+pub(super) fn operands(term: &Term) -> Vec<&Term> {
+    match &term.op {
+        Op::Const(_) | Op::Var { .. } => vec![],
+        Op::Not(term) | Op::Slice { term, .. } | Op::Uext(term, _) | Op::Sext(term, _) => {
+            vec![term]
+        }
+        Op::Binary(_, lhs, rhs) | Op::Concat(lhs, rhs) => vec![lhs, rhs],
+        Op::Ite(condition, then, otherwise) => vec![condition, then, otherwise],
+        Op::Read(array, index) => {
+            let (_, writes) = array.writes();
+            let mut operands = vec![index];
+            for (index, value) in writes {
+                operands.push(index);
+                operands.push(value);
+            }
+            operands
+        }
+    }
+}
+
+impl Blaster {
+    pub(super) fn new() -> Self {
+        let mut sat = Cdcl::new();
+        let true_lit = sat.new_var();
+        sat.add_clause(&[true_lit]);
+        Self {
+            sat,
+            true_lit,
+            terms: HashMap::new(),
+            ands: HashMap::new(),
+            xors: HashMap::new(),
+            muxes: HashMap::new(),
+            vars: HashMap::new(),
+            reads: HashMap::new(),
+        }
+    }
+
+    fn constant(&self, value: bool) -> Lit {
+        match value {
+            true => self.true_lit,
+            false => !self.true_lit,
+        }
+    }
+
+    fn fresh(&mut self, width: u32) -> Bv {
+        (0..width).map(|_| self.sat.new_var()).collect()
+    }
+
+    fn and(&mut self, a: Lit, b: Lit) -> Lit {
+        let (a, b) = (a.min(b), a.max(b));
+        let f = !self.true_lit;
+        if a == f || b == f || a == !b {
+            return f;
+        }
+        if a == self.true_lit || a == b {
+            return b;
+        }
+        if b == self.true_lit {
+            return a;
+        }
+        if let Some(gate) = self.ands.get(&(a, b)) {
+            return *gate;
+        }
+        let gate = self.sat.new_var();
+        self.sat.add_clause(&[!gate, a]);
+        self.sat.add_clause(&[!gate, b]);
+        self.sat.add_clause(&[gate, !a, !b]);
+        self.ands.insert((a, b), gate);
+        gate
+    }
+
+    fn or(&mut self, a: Lit, b: Lit) -> Lit {
+        !self.and(!a, !b)
+    }
+
+    fn xor(&mut self, a: Lit, b: Lit) -> Lit {
+        // Negations are moved to the output so equal gates are shared.
+        let negated = a.is_negative() != b.is_negative();
+        let (a, b) = match (a.is_negative(), b.is_negative()) {
+            (true, true) => (!a, !b),
+            (true, false) => (!a, b),
+            (false, true) => (a, !b),
+            (false, false) => (a, b),
+        };
+        let (a, b) = (a.min(b), a.max(b));
+        let gate = if a == b {
+            !self.true_lit
+        } else if a == self.true_lit {
+            !b
+        } else if let Some(gate) = self.xors.get(&(a, b)) {
+            *gate
+        } else {
+            let gate = self.sat.new_var();
+            self.sat.add_clause(&[!gate, a, b]);
+            self.sat.add_clause(&[!gate, !a, !b]);
+            self.sat.add_clause(&[gate, !a, b]);
+            self.sat.add_clause(&[gate, a, !b]);
+            self.xors.insert((a, b), gate);
+            gate
+        };
+        match negated {
+            true => !gate,
+            false => gate,
+        }
+    }
+
+    /// Returns `then` if `condition` is true and `otherwise` if it is false.
+    fn mux(&mut self, condition: Lit, then: Lit, otherwise: Lit) -> Lit {
+        if condition == self.true_lit || then == otherwise {
+            return then;
+        }
+        if condition == !self.true_lit {
+            return otherwise;
+        }
+        if let Some(gate) = self.muxes.get(&(condition, then, otherwise)) {
+            return *gate;
+        }
+        let gate = self.sat.new_var();
+        self.sat.add_clause(&[!condition, !then, gate]);
+        self.sat.add_clause(&[!condition, then, !gate]);
+        self.sat.add_clause(&[condition, !otherwise, gate]);
+        self.sat.add_clause(&[condition, otherwise, !gate]);
+        // Redundant, but helps propagation when both inputs are equal.
+        self.sat.add_clause(&[!then, !otherwise, gate]);
+        self.sat.add_clause(&[then, otherwise, !gate]);
+        self.muxes.insert((condition, then, otherwise), gate);
+        gate
+    }
+
+    fn mux_bv(&mut self, condition: Lit, then: &[Lit], otherwise: &[Lit]) -> Bv {
+        then.iter()
+            .zip(otherwise)
+            .map(|(then, otherwise)| self.mux(condition, *then, *otherwise))
+            .collect()
+    }
+
+    fn const_bv(&self, value: u64, width: u32) -> Bv {
+        (0..width)
+            .map(|bit| self.constant(bit < 64 && value >> bit & 1 == 1))
+            .collect()
+    }
+
+    /// Adds `a`, `b` and `carry`, returns the sum and the carry out.
+    fn add(&mut self, a: &[Lit], b: &[Lit], mut carry: Lit) -> (Bv, Lit) {
+        let sum = a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| {
+                let half = self.xor(*a, *b);
+                let sum = self.xor(half, carry);
+                let generated = self.and(*a, *b);
+                let propagated = self.and(half, carry);
+                carry = self.or(generated, propagated);
+                sum
+            })
+            .collect();
+        (sum, carry)
+    }
+
+    fn neg(&mut self, a: &[Lit]) -> Bv {
+        let inverted: Bv = a.iter().map(|bit| !*bit).collect();
+        let zero = self.const_bv(0, a.len() as u32);
+        self.add(&inverted, &zero, self.true_lit).0
+    }
+
+    /// Subtracts `b` from `a`, returns the difference and true if there was
+    /// no borrow.
+    fn sub(&mut self, a: &[Lit], b: &[Lit]) -> (Bv, Lit) {
+        let inverted: Bv = b.iter().map(|bit| !*bit).collect();
+        self.add(a, &inverted, self.true_lit)
+    }
+
+    fn eq(&mut self, a: &[Lit], b: &[Lit]) -> Lit {
+        let mut result = self.true_lit;
+        for (a, b) in a.iter().zip(b) {
+            let equal = !self.xor(*a, *b);
+            result = self.and(result, equal);
+        }
+        result
+    }
+
+    fn ult(&mut self, a: &[Lit], b: &[Lit]) -> Lit {
+        let mut less = !self.true_lit;
+        for (a, b) in a.iter().zip(b) {
+            // The most significant differing bit decides.
+            let differ = self.xor(*a, *b);
+            less = self.mux(differ, *b, less);
+        }
+        less
+    }
+
+    fn slt(&mut self, a: &[Lit], b: &[Lit]) -> Lit {
+        let flip = |bits: &[Lit]| {
+            let mut bits = bits.to_vec();
+            let sign = bits.len() - 1;
+            bits[sign] = !bits[sign];
+            bits
+        };
+        self.ult(&flip(a), &flip(b))
+    }
+
+    /// Multiplies `a` with `b`, truncated to the width of `a`.
+    fn mul(&mut self, a: &[Lit], b: &[Lit]) -> Bv {
+        let width = a.len();
+        let mut product = self.const_bv(0, width as u32);
+        for (shift, bit) in b.iter().enumerate() {
+            let partial: Bv = (0..width)
+                .map(|index| match index >= shift {
+                    true => self.and(a[index - shift], *bit),
+                    false => !self.true_lit,
+                })
+                .collect();
+            product = self.add(&product, &partial, !self.true_lit).0;
+        }
+        product
+    }
+
+    /// Restoring division, returns the quotient and the remainder. Division by
+    /// zero yields all ones and the dividend.
+    fn divide(&mut self, a: &[Lit], b: &[Lit]) -> (Bv, Bv) {
+        let width = a.len();
+        let mut quotient = vec![!self.true_lit; width];
+        let mut remainder = self.const_bv(0, width as u32);
+        for index in (0..width).rev() {
+            let overflow = remainder[width - 1];
+            remainder.rotate_right(1);
+            remainder[0] = a[index];
+            let (difference, no_borrow) = self.sub(&remainder, b);
+            let subtract = self.or(overflow, no_borrow);
+            remainder = self.mux_bv(subtract, &difference, &remainder);
+            quotient[index] = subtract;
+        }
+        (quotient, remainder)
+    }
+
+    fn abs(&mut self, a: &[Lit]) -> Bv {
+        let negated = self.neg(a);
+        self.mux_bv(a[a.len() - 1], &negated, a)
+    }
+
+    /// Shifts `a` by `amount` using `shift` to shift by a constant amount.
+    fn shift(&mut self, a: &[Lit], amount: &[Lit], fill: Lit, left: bool) -> Bv {
+        let width = a.len();
+        let shifted = |bits: &[Lit], by: usize| -> Bv {
+            (0..width)
+                .map(|index| match left {
+                    true if index >= by => bits[index - by],
+                    false if index + by < width => bits[index + by],
+                    _ => fill,
+                })
+                .collect()
+        };
+        let mut result = a.to_vec();
+        for (stage, bit) in amount.iter().enumerate() {
+            if stage >= 64 || 1usize << stage >= width {
+                break;
+            }
+            let by = shifted(&result, 1 << stage);
+            result = self.mux_bv(*bit, &by, &result);
+        }
+        // Amounts of at least the width shift out every bit.
+        let representable = amount.len() >= 64 || (width as u64) < 1 << amount.len();
+        if representable {
+            let limit = self.const_bv(width as u64, amount.len() as u32);
+            let within = self.ult(amount, &limit);
+            let filled = vec![fill; width];
+            result = self.mux_bv(within, &result, &filled);
+        }
+        result
+    }
+
+    fn is_nonzero(&mut self, bits: &[Lit]) -> Lit {
+        bits.iter()
+            .fold(!self.true_lit, |any, bit| self.or(any, *bit))
+    }
+
+    fn sign_extend(bits: &[Lit], width: usize) -> Bv {
+        let mut bits = bits.to_vec();
+        bits.resize(width, bits[bits.len() - 1]);
+        bits
+    }
+
+    fn binary(&mut self, op: BinaryOp, a: &[Lit], b: &[Lit]) -> Bv {
+        let width = a.len();
+        let sign = |bits: &[Lit]| bits[bits.len() - 1];
+        match op {
+            BinaryOp::And => a.iter().zip(b).map(|(a, b)| self.and(*a, *b)).collect(),
+            BinaryOp::Or => a.iter().zip(b).map(|(a, b)| self.or(*a, *b)).collect(),
+            BinaryOp::Xor => a.iter().zip(b).map(|(a, b)| self.xor(*a, *b)).collect(),
+            BinaryOp::Add => self.add(a, b, !self.true_lit).0,
+            BinaryOp::Sub => self.sub(a, b).0,
+            BinaryOp::Mul => self.mul(a, b),
+            BinaryOp::Udiv => self.divide(a, b).0,
+            BinaryOp::Urem => self.divide(a, b).1,
+            BinaryOp::Sdiv => {
+                let (abs_a, abs_b) = (self.abs(a), self.abs(b));
+                let quotient = self.divide(&abs_a, &abs_b).0;
+                let negated = self.neg(&quotient);
+                let differ = self.xor(sign(a), sign(b));
+                self.mux_bv(differ, &negated, &quotient)
+            }
+            BinaryOp::Srem => {
+                let (abs_a, abs_b) = (self.abs(a), self.abs(b));
+                let remainder = self.divide(&abs_a, &abs_b).1;
+                let negated = self.neg(&remainder);
+                self.mux_bv(sign(a), &negated, &remainder)
+            }
+            BinaryOp::Sll => self.shift(a, b, !self.true_lit, true),
+            BinaryOp::Srl => self.shift(a, b, !self.true_lit, false),
+            BinaryOp::Sra => self.shift(a, b, sign(a), false),
+            BinaryOp::Eq => vec![self.eq(a, b)],
+            BinaryOp::Ult => vec![self.ult(a, b)],
+            BinaryOp::Slt => vec![self.slt(a, b)],
+            BinaryOp::Uaddo => vec![self.add(a, b, !self.true_lit).1],
+            BinaryOp::Usubo => vec![self.ult(a, b)],
+            BinaryOp::Saddo | BinaryOp::Ssubo => {
+                let (result, operand_signs_equal) = match op {
+                    BinaryOp::Saddo => (
+                        self.add(a, b, !self.true_lit).0,
+                        !self.xor(sign(a), sign(b)),
+                    ),
+                    _ => (self.sub(a, b).0, self.xor(sign(a), sign(b))),
+                };
+                let changed = self.xor(sign(&result), sign(a));
+                vec![self.and(operand_signs_equal, changed)]
+            }
+            BinaryOp::Umulo => {
+                let mut wide_a = a.to_vec();
+                let mut wide_b = b.to_vec();
+                wide_a.resize(2 * width, !self.true_lit);
+                wide_b.resize(2 * width, !self.true_lit);
+                let product = self.mul(&wide_a, &wide_b);
+                vec![self.is_nonzero(&product[width..])]
+            }
+            BinaryOp::Smulo => {
+                let wide_a = Self::sign_extend(a, 2 * width);
+                let wide_b = Self::sign_extend(b, 2 * width);
+                let product = self.mul(&wide_a, &wide_b);
+                let sign = product[width - 1];
+                let differ: Bv = product[width..]
+                    .iter()
+                    .map(|bit| self.xor(*bit, sign))
+                    .collect();
+                vec![self.is_nonzero(&differ)]
+            }
+        }
+    }
+
+    /// Returns the element at `index` of an array with unconstrained elements.
+    fn read_base(&mut self, id: u64, width: u32, index: Bv) -> Bv {
+        let previous = self.reads.get(&id).cloned().unwrap_or_default();
+        if let Some((_, value)) = previous.iter().find(|(read, _)| *read == index) {
+            return value.clone();
+        }
+        let value = self.fresh(width);
+        for (read, read_value) in &previous {
+            // Equal indices read equal values.
+            let same_index = self.eq(&index, read);
+            if same_index == !self.true_lit {
+                continue;
+            }
+            for (bit, read_bit) in value.iter().zip(read_value) {
+                self.sat.add_clause(&[!same_index, !*bit, *read_bit]);
+                self.sat.add_clause(&[!same_index, *bit, !*read_bit]);
+            }
+        }
+        self.reads
+            .entry(id)
+            .or_default()
+            .push((index, value.clone()));
+        value
+    }
+
+    fn read(&mut self, array: &ArrayNode, index: Bv) -> Bv {
+        let (base, writes) = array.writes();
+        let ArrayKind::Base { id, .. } = base.kind else {
+            unreachable!("the writes are skipped");
+        };
+        let writes: Vec<(Bv, Bv)> = writes
+            .into_iter()
+            .map(|(index, value)| (self.bits(index).clone(), self.bits(value).clone()))
+            .collect();
+        let mut value = self.read_base(id, array.element_width, index.clone());
+        for (written, written_value) in writes.iter().rev() {
+            let hit = self.eq(written, &index);
+            value = self.mux_bv(hit, written_value, &value);
+        }
+        value
+    }
+
+    fn bits(&self, term: &Term) -> &Bv {
+        &self.terms[&key(term)].1
+    }
+
+    /// Translates a node whose operands have been translated.
+    fn translate(&mut self, term: &Term) -> Bv {
+        match &term.op {
+            Op::Const(bits) => bits
+                .bits()
+                .into_iter()
+                .map(|bit| self.constant(bit))
+                .collect(),
+            Op::Var { id, .. } => match self.vars.get(id) {
+                Some(bits) => bits.clone(),
+                None => {
+                    let bits = self.fresh(term.width);
+                    self.vars.insert(*id, bits.clone());
+                    bits
+                }
+            },
+            Op::Not(operand) => self.bits(operand).iter().map(|bit| !*bit).collect(),
+            Op::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (self.bits(lhs).clone(), self.bits(rhs).clone());
+                self.binary(*op, &lhs, &rhs)
+            }
+            Op::Ite(condition, then, otherwise) => {
+                let condition = self.bits(condition)[0];
+                let (then, otherwise) = (self.bits(then).clone(), self.bits(otherwise).clone());
+                self.mux_bv(condition, &then, &otherwise)
+            }
+            Op::Concat(high, low) => {
+                let mut bits = self.bits(low).clone();
+                bits.extend_from_slice(self.bits(high));
+                bits
+            }
+            Op::Slice { term, low, high } => {
+                self.bits(term)[*low as usize..=*high as usize].to_vec()
+            }
+            Op::Uext(operand, _) => {
+                let mut bits = self.bits(operand).clone();
+                bits.resize(term.width as usize, !self.true_lit);
+                bits
+            }
+            Op::Sext(operand, _) => Self::sign_extend(self.bits(operand), term.width as usize),
+            Op::Read(array, index) => {
+                let index = self.bits(index).clone();
+                self.read(array, index)
+            }
+        }
+    }
+
+    /// Returns the literals of the bits of `term`.
+    pub(super) fn blast(&mut self, term: &Term) -> Bv {
+        // Translate the operands before the nodes using them, without
+        // recursion as expressions can be deep.
+        let mut pending = vec![(term.clone(), false)];
+        while let Some((term, ready)) = pending.pop() {
+            if self.terms.contains_key(&key(&term)) {
+                continue;
+            }
+            if ready {
+                let bits = self.translate(&term);
+                debug_assert_eq!(bits.len(), term.width as usize);
+                self.terms.insert(key(&term), (term, bits));
+            } else {
+                pending.push((term.clone(), true));
+                for operand in operands(&term) {
+                    if !self.terms.contains_key(&key(operand)) {
+                        pending.push((operand.clone(), false));
+                    }
+                }
+            }
+        }
+        self.bits(term).clone()
+    }
+
+    /// Requires the single bit `term` to be one.
+    pub(super) fn assert(&mut self, term: &Term) {
+        let bit = self.blast(term)[0];
+        self.sat.add_clause(&[bit]);
+    }
+
+    pub(super) fn solve(&mut self, deadline: Option<Instant>) -> Outcome {
+        self.sat.solve(deadline)
+    }
+
+    /// Returns the value of `bits` in the model found by the last call to
+    /// [`solve`](Self::solve).
+    pub(super) fn value(&self, bits: &[Lit]) -> Bits {
+        Bits::from_bits(bits.iter().map(|bit| self.sat.model_value(*bit)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Blaster;
+    use crate::smt::smt_sat::{
+        bits::Bits,
+        cdcl::Outcome,
+        node::{self, test::SMTLIB, BinaryOp, Term},
+    };
+
+    fn value(value: u64, width: u32) -> Term {
+        node::constant(Bits::from_u64(value, width))
+    }
+
+    /// Returns an unconstrained value required to equal `value`, so that
+    /// operations on it are not folded.
+    fn equal_to(blaster: &mut Blaster, value: u64, width: u32) -> Term {
+        let var = node::var(width, "x");
+        blaster.assert(&node::binary(
+            BinaryOp::Eq,
+            &var,
+            &self::value(value, width),
+        ));
+        var
+    }
+
+    /// Solves for `terms` with the constraints of `blaster`, a blaster is
+    /// only solved once.
+    fn solve(mut blaster: Blaster, terms: &[Term]) -> Vec<u64> {
+        let bits: Vec<_> = terms.iter().map(|term| blaster.blast(term)).collect();
+        assert_eq!(blaster.solve(None), Outcome::Sat);
+        bits.iter()
+            .map(|bits| blaster.value(bits).as_u64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_circuits_match_smtlib() {
+        for (op, lhs, rhs, expected) in SMTLIB {
+            let mut blaster = Blaster::new();
+            let a = equal_to(&mut blaster, lhs, 8);
+            let b = equal_to(&mut blaster, rhs, 8);
+            let result = solve(blaster, &[node::binary(op, &a, &b)]);
+            assert_eq!(result, [expected], "{} {lhs:#x} {rhs:#x}", op.name());
+        }
+    }
+
+    #[test]
+    fn test_shift_amounts_of_other_widths() {
+        let cases = [
+            (BinaryOp::Sll, 1, 4, 0x02),
+            (BinaryOp::Sra, 0xf, 4, 0xff),
+            (BinaryOp::Srl, 0x100, 16, 0),
+            (BinaryOp::Sra, 0x100, 16, 0xff),
+            (BinaryOp::Srl, 0x7, 16, 0x01),
+        ];
+        for (op, amount, width, expected) in cases {
+            let mut blaster = Blaster::new();
+            let x = equal_to(&mut blaster, 0x81, 8);
+            let bits = equal_to(&mut blaster, amount, width);
+            let result = solve(blaster, &[node::binary(op, &x, &bits)]);
+            assert_eq!(result, [expected], "{} by {amount:#x}", op.name());
+        }
+    }
+
+    #[test]
+    fn test_extension_slice_and_concat() {
+        let mut blaster = Blaster::new();
+        let x = equal_to(&mut blaster, 0x80, 8);
+        let y = equal_to(&mut blaster, 0xabcd, 16);
+        let terms = [
+            node::uext(&x, 8),
+            node::sext(&x, 8),
+            node::slice(&y, 4, 11),
+            node::concat(&x, &y),
+        ];
+        assert_eq!(solve(blaster, &terms), [0x0080, 0xff80, 0xbc, 0x80_abcd]);
+    }
+
+    #[test]
+    fn test_read_after_write() {
+        let mut blaster = Blaster::new();
+        let memory = node::array(8, 8, "memory");
+        let i = equal_to(&mut blaster, 1, 8);
+        let j = equal_to(&mut blaster, 2, 8);
+        let other = equal_to(&mut blaster, 3, 8);
+        let written = node::write(&memory, &i, &value(0xaa, 8));
+        let written = node::write(&written, &j, &value(0xbb, 8));
+        let overwritten = node::write(&written, &i, &node::read(&memory, &j));
+
+        // The latest write to an index is read, other indices keep their
+        // unconstrained values.
+        let values = solve(blaster, &[
+            node::read(&written, &i),
+            node::read(&written, &j),
+            node::read(&written, &other),
+            node::read(&memory, &other),
+            node::read(&overwritten, &i),
+            node::read(&memory, &j),
+        ]);
+        assert_eq!(values[..2], [0xaa, 0xbb]);
+        assert_eq!(values[2], values[3]);
+        assert_eq!(values[4], values[5]);
+    }
+}
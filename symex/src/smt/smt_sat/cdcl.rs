@@ -0,0 +1,692 @@
+//! Conflict driven clause learning SAT solver.
+//!
+//! A small solver in the style of MiniSat: two watched literals, first unique
+//! implication point learning with clause minimization, VSIDS branching with
+//! phase saving and Luby restarts. Learnt clauses are reduced at restarts.
+
+use std::time::Instant;
+
+/// A literal, a variable or its negation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(super) struct Lit(u32);
+
+impl Lit {
+    /// The positive literal of `var`.
+    pub(super) const fn positive(var: u32) -> Self {
+        Self(var << 1)
+    }
+
+    pub(super) const fn var(self) -> u32 {
+        self.0 >> 1
+    }
+
+    pub(super) const fn is_negative(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl std::ops::Not for Lit {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0 ^ 1)
+    }
+}
+
+/// Result of [`Cdcl::solve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Outcome {
+    Sat,
+    Unsat,
+    /// The deadline passed before the problem was decided.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Unassigned,
+    True,
+    False,
+}
+
+#[derive(Debug)]
+struct Clause {
+    lits: Vec<Lit>,
+    learnt: bool,
+    activity: f64,
+    deleted: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Watch {
+    clause: u32,
+    /// A literal of the clause, the clause is satisfied if it is true.
+    blocker: Lit,
+}
+
+/// Binary heap of variables ordered by activity.
+#[derive(Debug, Default)]
+struct VarHeap {
+    heap: Vec<u32>,
+    /// Position of each variable in `heap`, `usize::MAX` if not in the heap.
+    position: Vec<usize>,
+}
+
+impl VarHeap {
+    fn contains(&self, var: u32) -> bool {
+        self.position[var as usize] != usize::MAX
+    }
+
+    fn insert(&mut self, var: u32, activity: &[f64]) {
+        if self.contains(var) {
+            return;
+        }
+        self.position[var as usize] = self.heap.len();
+        self.heap.push(var);
+        self.up(self.heap.len() - 1, activity);
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<u32> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().expect("the heap is not empty");
+        self.position[top as usize] = usize::MAX;
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.position[last as usize] = 0;
+            self.down(0, activity);
+        }
+        Some(top)
+    }
+
+    /// Restores the heap after the activity of `var` increased.
+    fn increased(&mut self, var: u32, activity: &[f64]) {
+        if self.contains(var) {
+            self.up(self.position[var as usize], activity);
+        }
+    }
+
+    fn up(&mut self, mut index: usize, activity: &[f64]) {
+        let var = self.heap[index];
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if activity[self.heap[parent] as usize] >= activity[var as usize] {
+                break;
+            }
+            self.heap[index] = self.heap[parent];
+            self.position[self.heap[index] as usize] = index;
+            index = parent;
+        }
+        self.heap[index] = var;
+        self.position[var as usize] = index;
+    }
+
+    fn down(&mut self, mut index: usize, activity: &[f64]) {
+        let var = self.heap[index];
+        loop {
+            let left = 2 * index + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = match right < self.heap.len()
+                && activity[self.heap[right] as usize] > activity[self.heap[left] as usize]
+            {
+                true => right,
+                false => left,
+            };
+            if activity[self.heap[child] as usize] <= activity[var as usize] {
+                break;
+            }
+            self.heap[index] = self.heap[child];
+            self.position[self.heap[index] as usize] = index;
+            index = child;
+        }
+        self.heap[index] = var;
+        self.position[var as usize] = index;
+    }
+}
+
+/// A SAT solver for a single problem, clauses are added before solving.
+#[derive(Debug, Default)]
+pub(super) struct Cdcl {
+    clauses: Vec<Clause>,
+    /// Clauses watching the negation of each literal, visited when the
+    /// literal becomes true.
+    watches: Vec<Vec<Watch>>,
+    values: Vec<Value>,
+    level: Vec<u32>,
+    reason: Vec<Option<u32>>,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    activity: Vec<f64>,
+    var_increment: f64,
+    clause_increment: f64,
+    order: VarHeap,
+    /// Last value of each variable, reused when branching on it.
+    phase: Vec<bool>,
+    seen: Vec<bool>,
+    learnts: usize,
+    /// False if the clauses are unsatisfiable at the top level.
+    consistent: bool,
+}
+
+/// Decay factor of the variable activities.
+const VAR_DECAY: f64 = 0.95;
+
+/// Decay factor of the learnt clause activities.
+const CLAUSE_DECAY: f64 = 0.999;
+
+/// Number of conflicts in the first restart interval.
+const RESTART_BASE: u64 = 100;
+
+/// Returns element `index` of the Luby sequence 1, 1, 2, 1, 1, 2, 4, ...
+fn luby(mut index: u64) -> u64 {
+    let mut size = 1;
+    let mut sequence = 0;
+    while size < index + 1 {
+        sequence += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != index {
+        size = (size - 1) >> 1;
+        sequence -= 1;
+        index %= size;
+    }
+    1 << sequence
+}
+
+impl Cdcl {
+    pub(super) fn new() -> Self {
+        Self {
+            var_increment: 1.0,
+            clause_increment: 1.0,
+            consistent: true,
+            ..Self::default()
+        }
+    }
+
+    /// Creates a new variable and returns its positive literal.
+    pub(super) fn new_var(&mut self) -> Lit {
+        let var = self.values.len() as u32;
+        self.values.push(Value::Unassigned);
+        self.level.push(0);
+        self.reason.push(None);
+        self.activity.push(0.0);
+        self.phase.push(false);
+        self.seen.push(false);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.order.position.push(usize::MAX);
+        self.order.insert(var, &self.activity);
+        Lit::positive(var)
+    }
+
+    fn value(&self, lit: Lit) -> Value {
+        match (self.values[lit.var() as usize], lit.is_negative()) {
+            (Value::Unassigned, _) => Value::Unassigned,
+            (Value::True, false) | (Value::False, true) => Value::True,
+            _ => Value::False,
+        }
+    }
+
+    /// Returns the value of `lit` in the model found by the last call to
+    /// [`solve`](Self::solve) that returned [`Outcome::Sat`].
+    pub(super) fn model_value(&self, lit: Lit) -> bool {
+        self.value(lit) == Value::True
+    }
+
+    fn decision_level(&self) -> u32 {
+        self.trail_limits.len() as u32
+    }
+
+    /// Adds a clause, must be called before solving.
+    pub(super) fn add_clause(&mut self, lits: &[Lit]) {
+        if !self.consistent {
+            return;
+        }
+        debug_assert_eq!(self.decision_level(), 0);
+        let mut lits = lits.to_vec();
+        lits.sort_unstable();
+        lits.dedup();
+        let mut clause = Vec::with_capacity(lits.len());
+        for (index, lit) in lits.iter().enumerate() {
+            // Sorting places a literal next to its negation.
+            let tautology = index + 1 < lits.len() && lits[index + 1] == !*lit;
+            match self.value(*lit) {
+                Value::True => return,
+                _ if tautology => return,
+                Value::False => {}
+                Value::Unassigned => clause.push(*lit),
+            }
+        }
+        match clause.len() {
+            0 => self.consistent = false,
+            1 => {
+                self.assign(clause[0], None);
+                self.consistent = self.propagate().is_none();
+            }
+            _ => {
+                self.attach(clause, false);
+            }
+        }
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>, learnt: bool) -> u32 {
+        let index = self.clauses.len() as u32;
+        self.watches[(!lits[0]).index()].push(Watch {
+            clause: index,
+            blocker: lits[1],
+        });
+        self.watches[(!lits[1]).index()].push(Watch {
+            clause: index,
+            blocker: lits[0],
+        });
+        self.clauses.push(Clause {
+            lits,
+            learnt,
+            activity: 0.0,
+            deleted: false,
+        });
+        if learnt {
+            self.learnts += 1;
+        }
+        index
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<u32>) {
+        let var = lit.var() as usize;
+        self.values[var] = match lit.is_negative() {
+            true => Value::False,
+            false => Value::True,
+        };
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Propagates the assignments on the trail, returns a conflicting clause.
+    fn propagate(&mut self) -> Option<u32> {
+        let mut conflict = None;
+        while self.propagated < self.trail.len() {
+            let lit = self.trail[self.propagated];
+            self.propagated += 1;
+            let false_lit = !lit;
+            let mut watches = std::mem::take(&mut self.watches[lit.index()]);
+            let mut kept = 0;
+            let mut index = 0;
+            while index < watches.len() {
+                let watch = watches[index];
+                index += 1;
+                if self.value(watch.blocker) == Value::True {
+                    watches[kept] = watch;
+                    kept += 1;
+                    continue;
+                }
+                let clause = &mut self.clauses[watch.clause as usize].lits;
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                let watch = Watch {
+                    clause: watch.clause,
+                    blocker: first,
+                };
+                if self.value(first) == Value::True {
+                    watches[kept] = watch;
+                    kept += 1;
+                    continue;
+                }
+                let clause = &self.clauses[watch.clause as usize].lits;
+                let replacement =
+                    (2..clause.len()).find(|index| self.value(clause[*index]) != Value::False);
+                if let Some(replacement) = replacement {
+                    let clause = &mut self.clauses[watch.clause as usize].lits;
+                    clause.swap(1, replacement);
+                    let watched = !clause[1];
+                    self.watches[watched.index()].push(watch);
+                    continue;
+                }
+                watches[kept] = watch;
+                kept += 1;
+                if self.value(first) == Value::False {
+                    conflict = Some(watch.clause);
+                    self.propagated = self.trail.len();
+                    while index < watches.len() {
+                        watches[kept] = watches[index];
+                        kept += 1;
+                        index += 1;
+                    }
+                } else {
+                    self.assign(first, Some(watch.clause));
+                }
+            }
+            watches.truncate(kept);
+            self.watches[lit.index()] = watches;
+            if conflict.is_some() {
+                break;
+            }
+        }
+        conflict
+    }
+
+    fn bump_var(&mut self, var: u32) {
+        self.activity[var as usize] += self.var_increment;
+        if self.activity[var as usize] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.var_increment *= 1e-100;
+        }
+        self.order.increased(var, &self.activity);
+    }
+
+    fn bump_clause(&mut self, clause: u32) {
+        let clause = &mut self.clauses[clause as usize];
+        if !clause.learnt {
+            return;
+        }
+        clause.activity += self.clause_increment;
+        if clause.activity > 1e20 {
+            for clause in self.clauses.iter_mut().filter(|clause| clause.learnt) {
+                clause.activity *= 1e-20;
+            }
+            self.clause_increment *= 1e-20;
+        }
+    }
+
+    /// Derives a clause from `conflict` with a single literal assigned at the
+    /// current decision level, returns the clause with that literal first and
+    /// the level to backtrack to.
+    fn analyze(&mut self, mut conflict: u32) -> (Vec<Lit>, u32) {
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut implied: Option<Lit> = None;
+        let mut index = self.trail.len();
+        let mut to_clear = Vec::new();
+
+        loop {
+            self.bump_clause(conflict);
+            let skip = implied.is_some() as usize;
+            for position in skip..self.clauses[conflict as usize].lits.len() {
+                let lit = self.clauses[conflict as usize].lits[position];
+                let var = lit.var();
+                if self.seen[var as usize] || self.level[var as usize] == 0 {
+                    continue;
+                }
+                self.bump_var(var);
+                self.seen[var as usize] = true;
+                to_clear.push(var);
+                if self.level[var as usize] >= self.decision_level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+
+            // The next literal of the current level on the trail.
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index].var() as usize] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            self.seen[lit.var() as usize] = false;
+            pending -= 1;
+            implied = Some(lit);
+            if pending == 0 {
+                break;
+            }
+            conflict = self.reason[lit.var() as usize].expect("implied literals have a reason");
+        }
+        learnt[0] = !implied.expect("the conflict has a literal at the current level");
+
+        // Remove literals implied by the other literals of the clause.
+        let mut minimized = vec![learnt[0]];
+        for lit in &learnt[1..] {
+            let redundant = self.reason[lit.var() as usize].is_some_and(|reason| {
+                self.clauses[reason as usize].lits[1..].iter().all(|other| {
+                    self.seen[other.var() as usize] || self.level[other.var() as usize] == 0
+                })
+            });
+            if !redundant {
+                minimized.push(*lit);
+            }
+        }
+        for var in to_clear {
+            self.seen[var as usize] = false;
+        }
+
+        let mut level = 0;
+        if minimized.len() > 1 {
+            let (position, _) = minimized
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by_key(|(_, lit)| self.level[lit.var() as usize])
+                .expect("the clause has more than one literal");
+            minimized.swap(1, position);
+            level = self.level[minimized[1].var() as usize];
+        }
+        (minimized, level)
+    }
+
+    fn backtrack(&mut self, level: u32) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let start = self.trail_limits[level as usize];
+        for lit in self.trail.drain(start..) {
+            let var = lit.var();
+            self.values[var as usize] = Value::Unassigned;
+            self.phase[var as usize] = !lit.is_negative();
+            self.order.insert(var, &self.activity);
+        }
+        self.trail_limits.truncate(level as usize);
+        self.propagated = self.trail.len();
+    }
+
+    /// Removes the less active half of the learnt clauses, only at the top
+    /// level where no learnt clause is the reason of an assignment.
+    fn reduce(&mut self) {
+        debug_assert_eq!(self.decision_level(), 0);
+        let mut learnt: Vec<(f64, usize)> = self
+            .clauses
+            .iter()
+            .enumerate()
+            .filter(|(_, clause)| clause.learnt && !clause.deleted && clause.lits.len() > 2)
+            .map(|(index, clause)| (clause.activity, index))
+            .collect();
+        learnt.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, index) in &learnt[..learnt.len() / 2] {
+            let clause = &mut self.clauses[*index];
+            clause.deleted = true;
+            clause.lits = Vec::new();
+            self.learnts -= 1;
+        }
+        for watches in &mut self.watches {
+            watches.retain(|watch| !self.clauses[watch.clause as usize].deleted);
+        }
+    }
+
+    fn decide(&mut self) -> Option<Lit> {
+        while let Some(var) = self.order.pop(&self.activity) {
+            if self.values[var as usize] == Value::Unassigned {
+                let lit = Lit::positive(var);
+                return Some(match self.phase[var as usize] {
+                    true => lit,
+                    false => !lit,
+                });
+            }
+        }
+        None
+    }
+
+    /// Searches for a model until `conflicts` conflicts occurred, returns
+    /// `None` if the search should be restarted.
+    fn search(&mut self, conflicts: u64, deadline: Option<Instant>) -> Option<Outcome> {
+        let mut count: u64 = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                count += 1;
+                if self.decision_level() == 0 {
+                    return Some(Outcome::Unsat);
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let clause = self.attach(learnt, true);
+                    self.bump_clause(clause);
+                    self.assign(asserting, Some(clause));
+                }
+                self.var_increment /= VAR_DECAY;
+                self.clause_increment /= CLAUSE_DECAY;
+                if count.is_multiple_of(64)
+                    && deadline.is_some_and(|deadline| Instant::now() > deadline)
+                {
+                    return Some(Outcome::Unknown);
+                }
+            } else {
+                if count >= conflicts {
+                    self.backtrack(0);
+                    return None;
+                }
+                match self.decide() {
+                    None => return Some(Outcome::Sat),
+                    Some(lit) => {
+                        self.trail_limits.push(self.trail.len());
+                        self.assign(lit, None);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Searches for an assignment that satisfies all clauses.
+    ///
+    /// Gives up with [`Outcome::Unknown`] if `deadline` passes first.
+    pub(super) fn solve(&mut self, deadline: Option<Instant>) -> Outcome {
+        if !self.consistent {
+            return Outcome::Unsat;
+        }
+        let mut restarts = 0;
+        loop {
+            if self.learnts > self.clauses.len() / 3 + 1000 {
+                self.reduce();
+            }
+            let conflicts = luby(restarts) * RESTART_BASE;
+            restarts += 1;
+            if let Some(outcome) = self.search(conflicts, deadline) {
+                if outcome == Outcome::Unsat {
+                    self.consistent = false;
+                }
+                return outcome;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return Outcome::Unknown;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{luby, Cdcl, Lit, Outcome};
+
+    /// Small deterministic random number generator.
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn satisfies(clauses: &[Vec<Lit>], assignment: impl Fn(Lit) -> bool) -> bool {
+        clauses
+            .iter()
+            .all(|clause| clause.iter().any(|lit| assignment(*lit)))
+    }
+
+    #[test]
+    fn test_luby() {
+        let sequence: Vec<u64> = (0..15).map(luby).collect();
+        assert_eq!(sequence, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_random_problems_match_brute_force() {
+        let mut random = Random(0x1234_5678);
+        for _ in 0..300 {
+            let vars = 3 + random.next() % 10;
+            let clause_count = vars * 4 + random.next() % 10;
+            let clauses: Vec<Vec<Lit>> = (0..clause_count)
+                .map(|_| {
+                    (0..1 + random.next() % 3)
+                        .map(|_| {
+                            let lit = Lit::positive((random.next() % vars) as u32);
+                            match random.next() % 2 {
+                                0 => lit,
+                                _ => !lit,
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+
+            let mut solver = Cdcl::new();
+            for _ in 0..vars {
+                solver.new_var();
+            }
+            for clause in &clauses {
+                solver.add_clause(clause);
+            }
+            let outcome = solver.solve(None);
+
+            let brute_force = (0..1u64 << vars).any(|assignment| {
+                satisfies(&clauses, |lit| {
+                    (assignment >> lit.var() & 1 == 1) != lit.is_negative()
+                })
+            });
+            match outcome {
+                Outcome::Sat => {
+                    assert!(satisfies(&clauses, |lit| solver.model_value(lit)));
+                }
+                Outcome::Unsat => assert!(!brute_force),
+                Outcome::Unknown => panic!("no deadline was given"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pigeonhole_is_unsat() {
+        // Five pigeons in four holes.
+        let (pigeons, holes) = (5, 4);
+        let mut solver = Cdcl::new();
+        let vars: Vec<Vec<Lit>> = (0..pigeons)
+            .map(|_| (0..holes).map(|_| solver.new_var()).collect())
+            .collect();
+        for pigeon in &vars {
+            solver.add_clause(pigeon);
+        }
+        for hole in 0..holes {
+            for (a, first) in vars.iter().enumerate() {
+                for second in &vars[a + 1..] {
+                    solver.add_clause(&[!first[hole], !second[hole]]);
+                }
+            }
+        }
+        assert_eq!(solver.solve(None), Outcome::Unsat);
+    }
+}
@@ -0,0 +1,425 @@
+#![allow(clippy::len_without_is_empty)]
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use super::{
+    bits::Bits,
+    node::{self, BinaryOp, Term},
+};
+use crate::smt::independence::Symbols;
+
+#[derive(Debug, Clone)]
+pub struct SatExpr(pub(super) Term, pub(super) Symbols);
+
+impl SatExpr {
+    fn binary(&self, op: BinaryOp, other: &Self) -> Self {
+        assert_eq!(self.len(), other.len());
+        Self(node::binary(op, &self.0, &other.0), self.1.union(&other.1))
+    }
+
+    /// Returns the bit width of the expression.
+    pub fn len(&self) -> u32 {
+        self.0.width
+    }
+
+    /// Zero-extend the current expression to the passed bit width and return
+    /// the resulting expression.
+    pub fn zero_ext(&self, width: u32) -> Self {
+        assert!(self.len() <= width);
+        match self.len().cmp(&width) {
+            Ordering::Less => SatExpr(node::uext(&self.0, width - self.len()), self.1.clone()),
+            Ordering::Equal => self.clone(),
+            Ordering::Greater => unreachable!("zero-extended to a width smaller than its own"),
+        }
+    }
+
+    /// Sign-extend the current expression to the passed bit width and return
+    /// the resulting expression.
+    pub fn sign_ext(&self, width: u32) -> Self {
+        assert!(self.len() <= width);
+        match self.len().cmp(&width) {
+            Ordering::Less => SatExpr(node::sext(&self.0, width - self.len()), self.1.clone()),
+            Ordering::Equal => self.clone(),
+            Ordering::Greater => unreachable!("sign-extended to a width smaller than its own"),
+        }
+    }
+
+    pub fn resize_unsigned(&self, width: u32) -> Self {
+        match self.len().cmp(&width) {
+            Ordering::Equal => self.clone(),
+            Ordering::Less => self.zero_ext(width),
+            Ordering::Greater => self.slice(0, width - 1),
+        }
+    }
+
+    /// expression equality check. Both expressions must have the same bit
+    /// width, the result is returned as an expression of width `1`.
+    pub fn eq(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Eq, other)
+    }
+
+    /// expression inequality check. Both expressions must have the same bit
+    /// width, the result is returned as an expression of width `1`.
+    pub fn ne(&self, other: &Self) -> Self {
+        self.eq(other).not()
+    }
+
+    /// expression unsigned greater than. Both expressions must have the
+    /// same bit width, the result is returned as an expression of width
+    /// `1`.
+    pub fn ugt(&self, other: &Self) -> Self {
+        other.ult(self)
+    }
+
+    /// expression unsigned greater than or equal. Both expressions must
+    /// have the same bit width, the result is returned as an expression
+    /// of width `1`.
+    pub fn ugte(&self, other: &Self) -> Self {
+        self.ult(other).not()
+    }
+
+    /// expression unsigned less than. Both expressions must have the same
+    /// bit width, the result is returned as an expression of width `1`.
+    pub fn ult(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Ult, other)
+    }
+
+    /// expression unsigned less than or equal. Both expressions must have
+    /// the same bit width, the result is returned as an expression of
+    /// width `1`.
+    pub fn ulte(&self, other: &Self) -> Self {
+        other.ult(self).not()
+    }
+
+    /// expression signed greater than. Both expressions must have the same
+    /// bit width, the result is returned as an expression of width `1`.
+    pub fn sgt(&self, other: &Self) -> Self {
+        other.slt(self)
+    }
+
+    /// expression signed greater or equal than. Both expressions must have
+    /// the same bit width, the result is returned as an expression of
+    /// width `1`.
+    pub fn sgte(&self, other: &Self) -> Self {
+        self.slt(other).not()
+    }
+
+    /// expression signed less than. Both expressions must have the same bit
+    /// width, the result is returned as an expression of width `1`.
+    pub fn slt(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Slt, other)
+    }
+
+    /// expression signed less than or equal. Both expressions must have the
+    /// same bit width, the result is returned as an expression of width
+    /// `1`.
+    pub fn slte(&self, other: &Self) -> Self {
+        other.slt(self).not()
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Add, other)
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Sub, other)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Mul, other)
+    }
+
+    pub fn udiv(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Udiv, other)
+    }
+
+    pub fn sdiv(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Sdiv, other)
+    }
+
+    pub fn urem(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Urem, other)
+    }
+
+    pub fn srem(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Srem, other)
+    }
+
+    pub fn not(&self) -> Self {
+        Self(node::not(&self.0), self.1.clone())
+    }
+
+    pub fn and(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::And, other)
+    }
+
+    pub fn or(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Or, other)
+    }
+
+    pub fn xor(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Xor, other)
+    }
+
+    /// Shift left logical
+    pub fn sll(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Sll, other)
+    }
+
+    /// Shift right logical
+    pub fn srl(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Srl, other)
+    }
+
+    /// Shift right arithmetic
+    pub fn sra(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Sra, other)
+    }
+
+    pub fn ite(&self, then_bv: &Self, else_bv: &Self) -> Self {
+        assert_eq!(self.len(), 1);
+        Self(
+            node::ite(&self.0, &then_bv.0, &else_bv.0),
+            self.1.union(&then_bv.1).union(&else_bv.1),
+        )
+    }
+
+    pub fn concat(&self, other: &Self) -> Self {
+        Self(node::concat(&self.0, &other.0), self.1.union(&other.1))
+    }
+
+    pub fn slice(&self, low: u32, high: u32) -> Self {
+        assert!(low <= high);
+        assert!(high < self.len());
+        Self(node::slice(&self.0, low, high), self.1.clone())
+    }
+
+    pub fn uaddo(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Uaddo, other)
+    }
+
+    pub fn saddo(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Saddo, other)
+    }
+
+    pub fn usubo(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Usubo, other)
+    }
+
+    pub fn ssubo(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Ssubo, other)
+    }
+
+    pub fn umulo(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Umulo, other)
+    }
+
+    pub fn smulo(&self, other: &Self) -> Self {
+        self.binary(BinaryOp::Smulo, other)
+    }
+
+    /// Constants are folded when the expression is built, there is nothing
+    /// left to simplify.
+    pub fn simplify(self) -> Self {
+        self
+    }
+
+    pub fn get_constant(&self) -> Option<u64> {
+        self.0.constant().and_then(Bits::as_u64)
+    }
+
+    pub fn get_constant_bool(&self) -> Option<bool> {
+        assert_eq!(self.len(), 1);
+        self.0.constant().map(|value| !value.is_zero())
+    }
+
+    /// Returns true if `self` and `other` are built from a common symbol, see
+    /// [`independence`](crate::smt::independence).
+    pub fn shares_symbols(&self, other: &Self) -> bool {
+        self.1.intersects(&other.1)
+    }
+
+    pub fn to_binary_string(&self) -> String {
+        self.0
+            .constant()
+            .expect("only constants have a binary representation")
+            .to_binary_string()
+    }
+
+    fn constant(&self, bits: Bits) -> Self {
+        Self(node::constant(bits), Symbols::none())
+    }
+
+    pub fn replace_part(&self, start_idx: u32, replace_with: Self) -> Self {
+        let end_idx = start_idx + replace_with.len();
+        assert!(end_idx <= self.len());
+
+        let value = if start_idx == 0 {
+            replace_with
+        } else {
+            let prefix = self.slice(0, start_idx - 1);
+            replace_with.concat(&prefix)
+        };
+
+        let value = if end_idx == self.len() {
+            value
+        } else {
+            let suffix = self.slice(end_idx, self.len() - 1);
+            suffix.concat(&value)
+        };
+        assert_eq!(value.len(), self.len());
+
+        value
+    }
+
+    /// Saturated unsigned addition. Adds `self` with `other` and if the result
+    /// overflows the maximum value is returned.
+    ///
+    /// Requires that `self` and `other` have the same width.
+    pub fn uadds(&self, other: &Self) -> Self {
+        let result = self.add(other);
+        let overflow = self.uaddo(other);
+        let saturated = self.constant(Bits::from_u64(0, self.len()).not());
+
+        overflow.ite(&saturated, &result)
+    }
+
+    /// Returns the smallest and the largest signed value of the width of
+    /// `self`.
+    fn signed_limits(&self) -> (Self, Self) {
+        let width = self.len();
+        assert!(width > 1);
+        let min = Bits::from_u64(1, width).sll(&Bits::from_u64(u64::from(width - 1), width));
+        (self.constant(min.clone()), self.constant(min.not()))
+    }
+
+    /// Saturated signed addition. Adds `self` with `other` and if the result
+    /// overflows either the maximum or minimum value is returned, depending
+    /// on the sign bit of `self`.
+    ///
+    /// Requires that `self` and `other` have the same width.
+    pub fn sadds(&self, other: &Self) -> Self {
+        let result = self.add(other);
+        let overflow = self.saddo(other);
+        let (min, max) = self.signed_limits();
+
+        // Check the sign bit if max or min should be given on overflow.
+        let is_negative = self.slice(self.len() - 1, self.len() - 1);
+
+        overflow.ite(&is_negative.ite(&min, &max), &result)
+    }
+
+    /// Saturated unsigned subtraction.
+    ///
+    /// Subtracts `self` with `other` and if the result overflows it is clamped
+    /// to zero, since the values are unsigned it can never go below the
+    /// minimum value.
+    pub fn usubs(&self, other: &Self) -> Self {
+        let result = self.sub(other);
+        let overflow = self.usubo(other);
+        let zero = self.constant(Bits::from_u64(0, self.len()));
+
+        overflow.ite(&zero, &result)
+    }
+
+    /// Saturated signed subtraction.
+    ///
+    /// Subtracts `self` with `other` with the result clamped between the
+    /// largest and smallest value allowed by the bit-width.
+    pub fn ssubs(&self, other: &Self) -> Self {
+        let result = self.sub(other);
+        let overflow = self.ssubo(other);
+        let (min, max) = self.signed_limits();
+
+        // Check the sign bit if max or min should be given on overflow.
+        let is_negative = self.slice(self.len() - 1, self.len() - 1);
+
+        overflow.ite(&is_negative.ite(&min, &max), &result)
+    }
+}
+
+/// Expressions are equal if they are structurally equal.
+impl PartialEq for SatExpr {
+    fn eq(&self, other: &Self) -> bool {
+        node::same(&self.0, &other.0)
+    }
+}
+
+impl Eq for SatExpr {}
+
+/// Hashes the width and, for constants, the value, consistent with the
+/// structural equality.
+impl Hash for SatExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        self.0.constant().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::smt::smt_sat::{SatIncrementalSolver, SatSolverContext};
+
+    #[test]
+    fn test_extension() {
+        let ctx = SatSolverContext::new();
+        let negative = ctx.from_u64(0x80, 8);
+        let positive = ctx.from_u64(0x7f, 8);
+        assert_eq!(negative.zero_ext(16).get_constant(), Some(0x0080));
+        assert_eq!(negative.sign_ext(16).get_constant(), Some(0xff80));
+        assert_eq!(positive.sign_ext(16).get_constant(), Some(0x007f));
+        assert_eq!(negative.sign_ext(8).get_constant(), Some(0x80));
+        assert_eq!(negative.resize_unsigned(4).get_constant(), Some(0));
+        assert_eq!(negative.resize_unsigned(12).get_constant(), Some(0x080));
+
+        // Extended unconstrained values keep their value.
+        let solver = SatIncrementalSolver::new(&ctx);
+        let x = ctx.unconstrained(8, "x");
+        solver.assert(&x.slt(&ctx.zero(8)));
+        let high = |value: &super::SatExpr| value.slice(8, 15);
+        assert!(solver
+            .must_be_equal(&high(&x.sign_ext(16)), &ctx.unsigned_max(8))
+            .unwrap());
+        assert!(solver
+            .must_be_equal(&high(&x.zero_ext(16)), &ctx.zero(8))
+            .unwrap());
+        assert!(solver
+            .must_be_equal(&x.sign_ext(16).slice(0, 7), &x)
+            .unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_extension_to_smaller_width() {
+        let ctx = SatSolverContext::new();
+        let _ = ctx.unconstrained(8, "x").sign_ext(4);
+    }
+
+    #[test]
+    fn test_slice_and_concat() {
+        let ctx = SatSolverContext::new();
+        let value = ctx.from_u64(0xabcd, 16);
+        assert_eq!(value.slice(4, 11).get_constant(), Some(0xbc));
+        assert_eq!(value.slice(0, 0).get_constant(), Some(1));
+        assert_eq!(value.slice(0, 15).get_constant(), Some(0xabcd));
+        let high = ctx.from_u64(0x12, 8);
+        assert_eq!(high.concat(&value).get_constant(), Some(0x12_abcd));
+        assert_eq!(high.concat(&value).len(), 24);
+
+        // Slicing a concatenation back gives the parts.
+        let solver = SatIncrementalSolver::new(&ctx);
+        let x = ctx.unconstrained(8, "x");
+        let y = ctx.unconstrained(16, "y");
+        let joined = x.concat(&y);
+        assert!(solver.must_be_equal(&joined.slice(16, 23), &x).unwrap());
+        assert!(solver.must_be_equal(&joined.slice(0, 15), &y).unwrap());
+        assert!(solver
+            .must_be_equal(
+                &joined.slice(12, 19),
+                &x.slice(0, 3).concat(&y.slice(12, 15))
+            )
+            .unwrap());
+    }
+}
@@ -0,0 +1,365 @@
+//! SMT backend written in Rust, used when the `boolector` feature is disabled.
+//!
+//! Expressions are built as a graph that folds constants. Queries are
+//! answered by encoding the relevant constraints as a boolean formula and
+//! solving it with a CDCL SAT solver. Every query is solved from scratch, so
+//! expressions and solvers have no shared mutable state.
+//!
+//! The backend is slower than Boolector on hard queries, such as those with
+//! symbolic multiplication or division, but needs no C toolchain.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+mod bits;
+mod blast;
+mod cdcl;
+mod expr;
+mod node;
+mod smtlib;
+mod solver;
+
+// Re-exports.
+pub(super) use expr::SatExpr;
+pub(super) use solver::SatIncrementalSolver;
+
+use self::{bits::Bits, node::ArrayNode};
+use super::independence::Symbols;
+
+/// Marks that queries have no time limit.
+const NO_TIMEOUT: u64 = u64::MAX;
+
+/// `SatSolverContext` handles the creation of expressions.
+///
+/// Expressions do not belong to a context, the context holds the options
+/// shared by the solvers created from it.
+#[derive(Debug, Clone)]
+pub struct SatSolverContext {
+    /// Time limit of each query in nanoseconds, [`NO_TIMEOUT`] if there is
+    /// none.
+    timeout: Arc<AtomicU64>,
+
+    /// Total time spent solving queries of the solvers of this context, in
+    /// nanoseconds.
+    solver_time: Arc<AtomicU64>,
+}
+
+impl SatSolverContext {
+    #[must_use]
+    /// Create a new uninitialized expression of size `bits`.
+    pub fn unconstrained(&self, bits: u32, name: &str) -> SatExpr {
+        SatExpr(node::var(bits, name), Symbols::fresh())
+    }
+
+    #[must_use]
+    /// Create a new expression set equal to `1` of size `bits`.
+    pub fn one(&self, bits: u32) -> SatExpr {
+        self.from_u64(1, bits)
+    }
+
+    #[must_use]
+    /// Create a new expression set to zero of size `bits`.
+    pub fn zero(&self, bits: u32) -> SatExpr {
+        self.from_u64(0, bits)
+    }
+
+    #[must_use]
+    /// Create a new expression from a boolean value.
+    pub fn from_bool(&self, value: bool) -> SatExpr {
+        self.from_u64(value as u64, 1)
+    }
+
+    #[must_use]
+    /// Create a new expression from an `u64` value of size `bits`.
+    pub fn from_u64(&self, value: u64, bits: u32) -> SatExpr {
+        SatExpr(node::constant(Bits::from_u64(value, bits)), Symbols::none())
+    }
+
+    #[must_use]
+    /// Create an expression of size `bits` from a binary string.
+    pub fn from_binary_string(&self, bits: &str) -> SatExpr {
+        SatExpr(node::constant(Bits::from_binary_str(bits)), Symbols::none())
+    }
+
+    #[must_use]
+    /// Creates an expression of size `bits` containing the maximum unsigned
+    /// value.
+    pub fn unsigned_max(&self, bits: u32) -> SatExpr {
+        self.from_binary_string(&"1".repeat(bits as usize))
+    }
+
+    #[must_use]
+    /// Create an expression of size `bits` containing the maximum signed value.
+    ///
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of bits is zero.
+    pub fn signed_max(&self, bits: u32) -> SatExpr {
+        // Maximum value: 0111...1
+        assert!(bits > 1);
+        self.from_binary_string(&format!("0{}", "1".repeat(bits as usize - 1)))
+    }
+
+    #[must_use]
+    /// Create an expression of size `bits` containing the minimum signed value.
+    ///
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of bits is zero.
+    pub fn signed_min(&self, bits: u32) -> SatExpr {
+        // Minimum value: 1000...0
+        assert!(bits > 1);
+        self.from_binary_string(&format!("1{}", "0".repeat(bits as usize - 1)))
+    }
+}
+
+impl SatSolverContext {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            timeout: Arc::new(AtomicU64::new(NO_TIMEOUT)),
+            solver_time: Arc::default(),
+        }
+    }
+
+    /// Sets the time limit of each query, a query that does not finish in time
+    /// fails with [`SolverError::Unknown`](crate::smt::SolverError::Unknown).
+    /// `None` removes the limit.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        let timeout = timeout.map_or(NO_TIMEOUT, |timeout| {
+            u64::try_from(timeout.as_nanos()).unwrap_or(NO_TIMEOUT - 1)
+        });
+        self.timeout.store(timeout, Ordering::Relaxed);
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        match self.timeout.load(Ordering::Relaxed) {
+            NO_TIMEOUT => None,
+            timeout => Some(Duration::from_nanos(timeout)),
+        }
+    }
+
+    /// Get the total time spent solving queries in the solvers created from
+    /// this context.
+    pub fn solver_time(&self) -> Duration {
+        Duration::from_nanos(self.solver_time.load(Ordering::Relaxed))
+    }
+
+    fn add_solver_time(&self, time: Duration) {
+        let time = u64::try_from(time.as_nanos()).unwrap_or(u64::MAX);
+        self.solver_time.fetch_add(time, Ordering::Relaxed);
+    }
+}
+
+impl Default for SatSolverContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Symbolic array where both index and stored values are symbolic.
+#[derive(Debug, Clone)]
+pub struct SatArray(Arc<ArrayNode>, Symbols);
+
+impl SatArray {
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    /// Create a new array where index has size `index_size` and each element
+    /// has size `element_size`.
+    pub fn new(
+        _ctx: &SatSolverContext,
+        index_size: usize,
+        element_size: usize,
+        name: &str,
+    ) -> Self {
+        let memory = node::array(index_size as u32, element_size as u32, name);
+        Self(memory, Symbols::fresh())
+    }
+
+    #[must_use]
+    /// Return value with specific index.
+    pub fn read(&self, index: &SatExpr) -> SatExpr {
+        SatExpr(node::read(&self.0, &index.0), self.1.union(&index.1))
+    }
+
+    /// Write value to index.
+    pub fn write(&mut self, index: &SatExpr, value: &SatExpr) {
+        self.0 = node::write(&self.0, &index.0, &value.0);
+        self.1 = self.1.union(&index.1).union(&value.1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SatArray, SatExpr, SatIncrementalSolver, SatSolverContext};
+    use crate::smt::Solutions;
+
+    /// Small deterministic generator, the tests do not depend on a random
+    /// number crate.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    type Operation = fn(&SatExpr, &SatExpr) -> SatExpr;
+
+    const OPERATIONS: [Operation; 23] = [
+        SatExpr::add,
+        SatExpr::sub,
+        SatExpr::mul,
+        SatExpr::udiv,
+        SatExpr::urem,
+        SatExpr::sdiv,
+        SatExpr::srem,
+        SatExpr::and,
+        SatExpr::or,
+        SatExpr::xor,
+        SatExpr::sll,
+        SatExpr::srl,
+        SatExpr::sra,
+        SatExpr::uaddo,
+        SatExpr::saddo,
+        SatExpr::usubo,
+        SatExpr::ssubo,
+        SatExpr::umulo,
+        SatExpr::smulo,
+        SatExpr::eq,
+        SatExpr::ult,
+        SatExpr::slt,
+        SatExpr::sgte,
+    ];
+
+    #[test]
+    fn test_circuits_match_folding() {
+        let ctx = SatSolverContext::new();
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for width in [1, 3, 8, 64, 70] {
+            for (index, operation) in OPERATIONS.iter().enumerate() {
+                for _ in 0..4 {
+                    let random = |rng: &mut Rng| match rng.next() % 4 {
+                        0 => ctx.zero(width),
+                        1 => ctx.unsigned_max(width),
+                        _ => ctx.from_u64(rng.next(), 64).resize_unsigned(width),
+                    };
+                    let (lhs, rhs) = (random(&mut rng), random(&mut rng));
+                    let expected = operation(&lhs, &rhs);
+                    assert!(expected.0.constant().is_some());
+
+                    // The same operation on unconstrained values equal to the
+                    // constants is solved by the circuit.
+                    let solver = SatIncrementalSolver::new(&ctx);
+                    let a = ctx.unconstrained(width, "a");
+                    let b = ctx.unconstrained(width, "b");
+                    solver.assert(&a.eq(&lhs));
+                    solver.assert(&b.eq(&rhs));
+                    let result = solver.get_value(&operation(&a, &b)).unwrap();
+                    assert_eq!(
+                        result, expected,
+                        "operation {index} on {lhs:?} and {rhs:?} of width {width}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_bits_are_folded() {
+        let ctx = SatSolverContext::new();
+        let x = ctx.unconstrained(32, "x");
+        let bit = ctx.from_u64(0b10, 32);
+
+        // Forcing a bit makes it concrete, the other bits stay symbolic.
+        let forced = x.and(&bit.not()).or(&bit);
+        assert_eq!(forced.and(&bit).get_constant(), Some(0b10));
+        assert_eq!(forced.get_constant(), None);
+        let byte = x.slice(0, 7).zero_ext(32).or(&ctx.from_u64(0xff, 32));
+        assert_eq!(byte.get_constant(), Some(0xff));
+        assert_eq!(x.and(&ctx.from_u64(0xff, 32)).get_constant(), None);
+    }
+
+    #[test]
+    fn test_solutions() {
+        let ctx = SatSolverContext::new();
+        let solver = SatIncrementalSolver::new(&ctx);
+        let x = ctx.unconstrained(8, "x");
+        solver.assert(&x.ult(&ctx.from_u64(3, 8)));
+
+        assert_eq!(solver.get_solutions2(&x, 5).unwrap().len(), 3);
+        assert!(matches!(
+            solver.get_values(&x, 2),
+            Ok(Solutions::AtLeast(_))
+        ));
+        assert!(solver
+            .must_be_equal(&x.udiv(&ctx.from_u64(4, 8)), &ctx.zero(8))
+            .unwrap());
+        assert!(!solver
+            .is_sat_with_constraint(&x.eq(&ctx.from_u64(3, 8)))
+            .unwrap());
+
+        // The solutions are not kept as constraints.
+        assert!(solver.can_equal(&x, &ctx.from_u64(2, 8)).unwrap());
+        assert_eq!(solver.constraint_count(), 1);
+    }
+
+    #[test]
+    fn test_symbolic_array() {
+        let ctx = SatSolverContext::new();
+        let solver = SatIncrementalSolver::new(&ctx);
+        let mut memory = SatArray::new(&ctx, 32, 8, "memory");
+        let a = ctx.unconstrained(32, "a");
+        let b = ctx.unconstrained(32, "b");
+        let value = ctx.from_u64(0xab, 8);
+
+        // Reads at equal indices are equal, and writes are seen through reads
+        // at possibly equal indices.
+        assert!(solver
+            .must_be_equal(&memory.read(&a), &memory.read(&a))
+            .unwrap());
+        memory.write(&a, &value);
+        assert_eq!(memory.read(&a), value);
+        assert!(solver.can_equal(&memory.read(&b), &value).unwrap());
+        assert!(!solver.must_be_equal(&memory.read(&b), &value).unwrap());
+
+        solver.assert(&a.eq(&b));
+        assert!(solver.must_be_equal(&memory.read(&b), &value).unwrap());
+
+        // Unwritten reads at different indices are independent.
+        let c = ctx.unconstrained(32, "c");
+        solver.assert(&c.ne(&a));
+        let before = memory.read(&c);
+        memory.write(&a, &ctx.from_u64(1, 8));
+        assert!(solver.must_be_equal(&memory.read(&c), &before).unwrap());
+    }
+
+    #[test]
+    fn test_get_model_is_consistent() {
+        let ctx = SatSolverContext::new();
+        let solver = SatIncrementalSolver::new(&ctx);
+        let x = ctx.unconstrained(32, "x");
+        let y = ctx.unconstrained(32, "y");
+        solver.assert(&x.add(&y).eq(&ctx.from_u64(100, 32)));
+        solver.assert(&x.ugt(&ctx.from_u64(10, 32)));
+        solver.assert(&y.ugt(&ctx.from_u64(10, 32)));
+
+        let model = solver.get_model(&[x, y]).unwrap();
+        let (x, y) = (
+            model[0].get_constant().unwrap(),
+            model[1].get_constant().unwrap(),
+        );
+        assert_eq!((x + y) as u32, 100);
+        assert!(x > 10 && y > 10);
+    }
+}
@@ -0,0 +1,924 @@
+//! Expression graph of the pure Rust backend.
+//!
+//! Expressions are immutable nodes shared through [`Arc`]. Constructors fold
+//! operations on constants and apply local simplifications, such as reading a
+//! byte from an array at the address it was written to, so that concrete
+//! execution never reaches the SAT solver. Two nodes are equal if they are
+//! structurally equal.
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Debug},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use super::bits::Bits;
+
+/// Counter used to tell apart unconstrained values and arrays with the same
+/// name.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+pub(super) type Term = Arc<Node>;
+
+/// A bit-vector expression.
+pub(super) struct Node {
+    pub(super) op: Op,
+    pub(super) width: u32,
+    /// Structural hash, equal nodes have equal hashes.
+    hash: u64,
+}
+
+/// Operations with two operands of the same width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum BinaryOp {
+    And,
+    Or,
+    Xor,
+    Add,
+    Sub,
+    Mul,
+    Udiv,
+    Sdiv,
+    Urem,
+    Srem,
+    Sll,
+    Srl,
+    Sra,
+    Eq,
+    Ult,
+    Slt,
+    Uaddo,
+    Saddo,
+    Usubo,
+    Ssubo,
+    Umulo,
+    Smulo,
+}
+
+impl BinaryOp {
+    /// Returns true if the result is a single bit.
+    pub(super) const fn is_predicate(self) -> bool {
+        matches!(
+            self,
+            Self::Eq
+                | Self::Ult
+                | Self::Slt
+                | Self::Uaddo
+                | Self::Saddo
+                | Self::Usubo
+                | Self::Ssubo
+                | Self::Umulo
+                | Self::Smulo
+        )
+    }
+
+    /// Returns true if the operand of a shift may have a different width than
+    /// the shifted value.
+    pub(super) const fn is_shift(self) -> bool {
+        matches!(self, Self::Sll | Self::Srl | Self::Sra)
+    }
+
+    fn eval(self, lhs: &Bits, rhs: &Bits) -> Bits {
+        match self {
+            Self::And => lhs.and(rhs),
+            Self::Or => lhs.or(rhs),
+            Self::Xor => lhs.xor(rhs),
+            Self::Add => lhs.add(rhs),
+            Self::Sub => lhs.sub(rhs),
+            Self::Mul => lhs.mul(rhs),
+            Self::Udiv => lhs.udiv(rhs),
+            Self::Sdiv => lhs.sdiv(rhs),
+            Self::Urem => lhs.urem(rhs),
+            Self::Srem => lhs.srem(rhs),
+            Self::Sll => lhs.sll(rhs),
+            Self::Srl => lhs.srl(rhs),
+            Self::Sra => lhs.sra(rhs),
+            Self::Eq => lhs.eq_bits(rhs),
+            Self::Ult => lhs.ult(rhs),
+            Self::Slt => lhs.slt(rhs),
+            Self::Uaddo => lhs.uaddo(rhs),
+            Self::Saddo => lhs.saddo(rhs),
+            Self::Usubo => lhs.usubo(rhs),
+            Self::Ssubo => lhs.ssubo(rhs),
+            Self::Umulo => lhs.umulo(rhs),
+            Self::Smulo => lhs.smulo(rhs),
+        }
+    }
+
+    pub(super) const fn name(self) -> &'static str {
+        match self {
+            Self::And => "bvand",
+            Self::Or => "bvor",
+            Self::Xor => "bvxor",
+            Self::Add => "bvadd",
+            Self::Sub => "bvsub",
+            Self::Mul => "bvmul",
+            Self::Udiv => "bvudiv",
+            Self::Sdiv => "bvsdiv",
+            Self::Urem => "bvurem",
+            Self::Srem => "bvsrem",
+            Self::Sll => "bvshl",
+            Self::Srl => "bvlshr",
+            Self::Sra => "bvashr",
+            Self::Eq => "=",
+            Self::Ult => "bvult",
+            Self::Slt => "bvslt",
+            Self::Uaddo => "bvuaddo",
+            Self::Saddo => "bvsaddo",
+            Self::Usubo => "bvusubo",
+            Self::Ssubo => "bvssubo",
+            Self::Umulo => "bvumulo",
+            Self::Smulo => "bvsmulo",
+        }
+    }
+}
+
+pub(super) enum Op {
+    Const(Bits),
+    Var {
+        id: u64,
+        name: Arc<str>,
+    },
+    Not(Term),
+    Binary(BinaryOp, Term, Term),
+    /// Condition, value if it is one and value if it is zero.
+    Ite(Term, Term, Term),
+    /// The more significant part followed by the less significant part.
+    Concat(Term, Term),
+    /// Bits `low` to `high` of the operand, both inclusive.
+    Slice {
+        term: Term,
+        low: u32,
+        high: u32,
+    },
+    /// Extension with zeroes by the given number of bits.
+    Uext(Term, u32),
+    /// Extension with the sign bit by the given number of bits.
+    Sext(Term, u32),
+    Read(Arc<ArrayNode>, Term),
+}
+
+/// An array of bit-vectors indexed by bit-vectors.
+pub(super) struct ArrayNode {
+    pub(super) kind: ArrayKind,
+    pub(super) index_width: u32,
+    pub(super) element_width: u32,
+    hash: u64,
+}
+
+pub(super) enum ArrayKind {
+    /// An array with unconstrained elements.
+    Base { id: u64, name: Arc<str> },
+    /// The array, the index and the value written to it.
+    Write(Arc<ArrayNode>, Term, Term),
+}
+
+/// Combines `value` in to `hash`.
+const fn mix(hash: u64, value: u64) -> u64 {
+    (hash.rotate_left(5) ^ value).wrapping_mul(0x517c_c1b7_2722_0a95)
+}
+
+fn hash_bits(bits: &Bits) -> u64 {
+    match bits {
+        Bits::Word { width, value } => mix(mix(1, *width as u64), *value),
+        Bits::Wide(bits) => bits.iter().fold(mix(2, bits.len() as u64), |hash, bit| {
+            mix(hash, *bit as u64)
+        }),
+    }
+}
+
+impl Op {
+    fn hash(&self) -> u64 {
+        match self {
+            Self::Const(bits) => hash_bits(bits),
+            Self::Var { id, .. } => mix(3, *id),
+            Self::Not(term) => mix(4, term.hash),
+            Self::Binary(op, lhs, rhs) => mix(mix(mix(5, *op as u64), lhs.hash), rhs.hash),
+            Self::Ite(condition, then, otherwise) => {
+                mix(mix(mix(6, condition.hash), then.hash), otherwise.hash)
+            }
+            Self::Concat(high, low) => mix(mix(7, high.hash), low.hash),
+            Self::Slice { term, low, high } => {
+                mix(mix(mix(8, term.hash), *low as u64), *high as u64)
+            }
+            Self::Uext(term, bits) => mix(mix(9, term.hash), *bits as u64),
+            Self::Sext(term, bits) => mix(mix(10, term.hash), *bits as u64),
+            Self::Read(array, index) => mix(mix(11, array.hash), index.hash),
+        }
+    }
+
+    /// Moves the operands out of the operation.
+    fn take_children(&mut self, children: &mut Vec<Child>) {
+        match std::mem::replace(self, Op::Const(Bits::from_u64(0, 1))) {
+            Self::Const(_) | Self::Var { .. } => {}
+            Self::Not(term)
+            | Self::Slice { term, .. }
+            | Self::Uext(term, _)
+            | Self::Sext(term, _) => {
+                children.push(Child::Term(term));
+            }
+            Self::Binary(_, lhs, rhs) | Self::Concat(lhs, rhs) => {
+                children.push(Child::Term(lhs));
+                children.push(Child::Term(rhs));
+            }
+            Self::Ite(condition, then, otherwise) => {
+                children.push(Child::Term(condition));
+                children.push(Child::Term(then));
+                children.push(Child::Term(otherwise));
+            }
+            Self::Read(array, index) => {
+                children.push(Child::Array(array));
+                children.push(Child::Term(index));
+            }
+        }
+    }
+}
+
+/// An operand of a node or an array that is being dropped.
+enum Child {
+    Term(Term),
+    Array(Arc<ArrayNode>),
+}
+
+/// Drops the nodes in `children` that are not shared without recursion, long
+/// chains of operations would otherwise overflow the stack.
+fn drop_children(mut children: Vec<Child>) {
+    while let Some(child) = children.pop() {
+        match child {
+            Child::Term(term) => {
+                if let Some(mut node) = Arc::into_inner(term) {
+                    node.op.take_children(&mut children);
+                }
+            }
+            Child::Array(array) => {
+                if let Some(mut array) = Arc::into_inner(array) {
+                    array.take_children(&mut children);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        if matches!(self.op, Op::Const(_) | Op::Var { .. }) {
+            return;
+        }
+        let mut children = Vec::new();
+        self.op.take_children(&mut children);
+        drop_children(children);
+    }
+}
+
+impl ArrayNode {
+    fn take_children(&mut self, children: &mut Vec<Child>) {
+        let placeholder = ArrayKind::Base {
+            id: 0,
+            name: Arc::from(""),
+        };
+        if let ArrayKind::Write(array, index, value) =
+            std::mem::replace(&mut self.kind, placeholder)
+        {
+            children.push(Child::Array(array));
+            children.push(Child::Term(index));
+            children.push(Child::Term(value));
+        }
+    }
+
+    /// Returns the array the writes were made to and the writes, most recent
+    /// first.
+    pub(super) fn writes(&self) -> (&ArrayNode, Vec<(&Term, &Term)>) {
+        let mut array = self;
+        let mut writes = Vec::new();
+        while let ArrayKind::Write(inner, index, value) = &array.kind {
+            writes.push((index, value));
+            array = inner;
+        }
+        (array, writes)
+    }
+}
+
+impl Drop for ArrayNode {
+    fn drop(&mut self) {
+        if matches!(self.kind, ArrayKind::Base { .. }) {
+            return;
+        }
+        let mut children = Vec::new();
+        self.take_children(&mut children);
+        drop_children(children);
+    }
+}
+
+/// Returns true if `lhs` and `rhs` are structurally equal.
+pub(super) fn same(lhs: &Term, rhs: &Term) -> bool {
+    enum Pair<'a> {
+        Term(&'a Node, &'a Node),
+        Array(&'a ArrayNode, &'a ArrayNode),
+    }
+
+    let mut visited = HashSet::new();
+    let mut pending = vec![Pair::Term(lhs, rhs)];
+    while let Some(pair) = pending.pop() {
+        match pair {
+            Pair::Term(lhs, rhs) => {
+                if std::ptr::eq(lhs, rhs) {
+                    continue;
+                }
+                if lhs.hash != rhs.hash || lhs.width != rhs.width {
+                    return false;
+                }
+                if !visited.insert((lhs as *const Node as usize, rhs as *const Node as usize)) {
+                    continue;
+                }
+                match (&lhs.op, &rhs.op) {
+                    (Op::Const(lhs), Op::Const(rhs)) if lhs == rhs => {}
+                    (Op::Var { id: lhs, .. }, Op::Var { id: rhs, .. }) if lhs == rhs => {}
+                    (Op::Not(lhs), Op::Not(rhs)) => pending.push(Pair::Term(lhs, rhs)),
+                    (Op::Binary(lhs_op, lhs_a, lhs_b), Op::Binary(rhs_op, rhs_a, rhs_b))
+                        if lhs_op == rhs_op =>
+                    {
+                        pending.push(Pair::Term(lhs_a, rhs_a));
+                        pending.push(Pair::Term(lhs_b, rhs_b));
+                    }
+                    (Op::Ite(lhs_c, lhs_t, lhs_e), Op::Ite(rhs_c, rhs_t, rhs_e)) => {
+                        pending.push(Pair::Term(lhs_c, rhs_c));
+                        pending.push(Pair::Term(lhs_t, rhs_t));
+                        pending.push(Pair::Term(lhs_e, rhs_e));
+                    }
+                    (Op::Concat(lhs_h, lhs_l), Op::Concat(rhs_h, rhs_l)) => {
+                        pending.push(Pair::Term(lhs_h, rhs_h));
+                        pending.push(Pair::Term(lhs_l, rhs_l));
+                    }
+                    (
+                        Op::Slice {
+                            term: lhs,
+                            low: lhs_low,
+                            ..
+                        },
+                        Op::Slice {
+                            term: rhs,
+                            low: rhs_low,
+                            ..
+                        },
+                    ) if lhs_low == rhs_low => pending.push(Pair::Term(lhs, rhs)),
+                    (Op::Uext(lhs, _), Op::Uext(rhs, _)) | (Op::Sext(lhs, _), Op::Sext(rhs, _)) => {
+                        pending.push(Pair::Term(lhs, rhs));
+                    }
+                    (Op::Read(lhs_array, lhs_index), Op::Read(rhs_array, rhs_index)) => {
+                        pending.push(Pair::Array(lhs_array, rhs_array));
+                        pending.push(Pair::Term(lhs_index, rhs_index));
+                    }
+                    _ => return false,
+                }
+            }
+            Pair::Array(lhs, rhs) => {
+                if std::ptr::eq(lhs, rhs) {
+                    continue;
+                }
+                if lhs.hash != rhs.hash || lhs.element_width != rhs.element_width {
+                    return false;
+                }
+                match (&lhs.kind, &rhs.kind) {
+                    (ArrayKind::Base { id: lhs, .. }, ArrayKind::Base { id: rhs, .. })
+                        if lhs == rhs => {}
+                    (
+                        ArrayKind::Write(lhs_array, lhs_index, lhs_value),
+                        ArrayKind::Write(rhs_array, rhs_index, rhs_value),
+                    ) => {
+                        pending.push(Pair::Array(lhs_array, rhs_array));
+                        pending.push(Pair::Term(lhs_index, rhs_index));
+                        pending.push(Pair::Term(lhs_value, rhs_value));
+                    }
+                    _ => return false,
+                }
+            }
+        }
+    }
+    true
+}
+
+fn node(op: Op, width: u32) -> Term {
+    Arc::new(Node {
+        hash: mix(op.hash(), width as u64),
+        op,
+        width,
+    })
+}
+
+impl Node {
+    pub(super) fn constant(&self) -> Option<&Bits> {
+        match &self.op {
+            Op::Const(bits) => Some(bits),
+            _ => None,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.constant().is_some_and(Bits::is_zero)
+    }
+
+    fn is_ones(&self) -> bool {
+        self.constant().is_some_and(Bits::is_ones)
+    }
+
+    fn is_one(&self) -> bool {
+        self.constant().is_some_and(Bits::is_one)
+    }
+}
+
+pub(super) fn constant(bits: Bits) -> Term {
+    let width = bits.width();
+    node(Op::Const(bits), width)
+}
+
+/// Creates an unconstrained value.
+pub(super) fn var(width: u32, name: &str) -> Term {
+    node(
+        Op::Var {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: Arc::from(name),
+        },
+        width,
+    )
+}
+
+fn boolean(value: bool) -> Term {
+    constant(Bits::from_u64(value as u64, 1))
+}
+
+pub(super) fn not(term: &Term) -> Term {
+    match &term.op {
+        Op::Const(bits) => constant(bits.not()),
+        Op::Not(inner) => inner.clone(),
+        _ => node(Op::Not(term.clone()), term.width),
+    }
+}
+
+pub(super) fn binary(op: BinaryOp, lhs: &Term, rhs: &Term) -> Term {
+    assert!(
+        op.is_shift() || lhs.width == rhs.width,
+        "operands of {} have different widths {} and {}",
+        op.name(),
+        lhs.width,
+        rhs.width
+    );
+    if let (Some(lhs), Some(rhs)) = (lhs.constant(), rhs.constant()) {
+        return constant(op.eval(lhs, rhs));
+    }
+    let zero = || constant(Bits::from_u64(0, lhs.width));
+    let simplified = match op {
+        BinaryOp::And if lhs.is_zero() || rhs.is_ones() => Some(lhs.clone()),
+        BinaryOp::And if rhs.is_zero() || lhs.is_ones() => Some(rhs.clone()),
+        BinaryOp::Or if lhs.is_zero() || rhs.is_ones() => Some(rhs.clone()),
+        BinaryOp::Or if rhs.is_zero() || lhs.is_ones() => Some(lhs.clone()),
+        BinaryOp::And | BinaryOp::Or if same(lhs, rhs) => Some(lhs.clone()),
+        BinaryOp::Xor | BinaryOp::Add if lhs.is_zero() => Some(rhs.clone()),
+        BinaryOp::Xor
+        | BinaryOp::Add
+        | BinaryOp::Sub
+        | BinaryOp::Sll
+        | BinaryOp::Srl
+        | BinaryOp::Sra
+            if rhs.is_zero() =>
+        {
+            Some(lhs.clone())
+        }
+        BinaryOp::Xor | BinaryOp::Sub if same(lhs, rhs) => Some(zero()),
+        BinaryOp::Mul if lhs.is_zero() || rhs.is_one() => Some(lhs.clone()),
+        BinaryOp::Mul if rhs.is_zero() || lhs.is_one() => Some(rhs.clone()),
+        BinaryOp::Udiv | BinaryOp::Sdiv if rhs.is_one() => Some(lhs.clone()),
+        BinaryOp::Urem | BinaryOp::Srem if rhs.is_one() => Some(zero()),
+        BinaryOp::Eq if same(lhs, rhs) => Some(boolean(true)),
+        BinaryOp::Eq if lhs.width == 1 && rhs.is_one() => Some(lhs.clone()),
+        BinaryOp::Eq if lhs.width == 1 && rhs.is_zero() => Some(not(lhs)),
+        BinaryOp::Ult | BinaryOp::Usubo if rhs.is_zero() => Some(boolean(false)),
+        BinaryOp::Uaddo | BinaryOp::Saddo | BinaryOp::Ssubo if rhs.is_zero() => {
+            Some(boolean(false))
+        }
+        BinaryOp::Ult | BinaryOp::Slt | BinaryOp::Usubo if same(lhs, rhs) => Some(boolean(false)),
+        _ => None,
+    };
+    if let Some(simplified) = simplified {
+        return simplified;
+    }
+    let width = match op.is_predicate() {
+        true => 1,
+        false => lhs.width,
+    };
+    let term = node(Op::Binary(op, lhs.clone(), rhs.clone()), width);
+    // Masking and setting bits can make a value with symbolic bits constant,
+    // such as when a single bit of a register is forced.
+    if matches!(op, BinaryOp::And | BinaryOp::Or | BinaryOp::Xor) {
+        if let Some((known, value)) = known_bits(&term, KNOWN_BITS_DEPTH) {
+            if known == mask(width) {
+                return constant(Bits::from_u64(value, width));
+            }
+        }
+    }
+    term
+}
+
+/// Number of operations [`known_bits`] looks through.
+const KNOWN_BITS_DEPTH: u32 = 6;
+
+/// Returns a value with the lowest `width` bits set.
+const fn mask(width: u32) -> u64 {
+    match width {
+        64 => u64::MAX,
+        width => (1 << width) - 1,
+    }
+}
+
+/// Returns the bits of `term` that are the same for all values of its symbols
+/// and their values, looking through at most `depth` operations. Only values
+/// of at most 64 bits are tracked.
+fn known_bits(term: &Node, depth: u32) -> Option<(u64, u64)> {
+    if term.width > 64 {
+        return None;
+    }
+    if let Some(value) = term.constant() {
+        return Some((mask(term.width), value.as_u64()?));
+    }
+    if depth == 0 {
+        return Some((0, 0));
+    }
+    let known = |term: &Node| known_bits(term, depth - 1).unwrap_or((0, 0));
+    let (known, value) = match &term.op {
+        Op::Not(operand) => {
+            let (known, value) = known(operand);
+            (known, !value)
+        }
+        Op::Binary(BinaryOp::And, lhs, rhs) => {
+            let ((lhs_known, lhs_value), (rhs_known, rhs_value)) = (known(lhs), known(rhs));
+            let zeroes = (lhs_known & !lhs_value) | (rhs_known & !rhs_value);
+            let ones = lhs_known & lhs_value & rhs_known & rhs_value;
+            (zeroes | ones, ones)
+        }
+        Op::Binary(BinaryOp::Or, lhs, rhs) => {
+            let ((lhs_known, lhs_value), (rhs_known, rhs_value)) = (known(lhs), known(rhs));
+            let ones = (lhs_known & lhs_value) | (rhs_known & rhs_value);
+            let zeroes = lhs_known & !lhs_value & rhs_known & !rhs_value;
+            (zeroes | ones, ones)
+        }
+        Op::Binary(BinaryOp::Xor, lhs, rhs) => {
+            let ((lhs_known, lhs_value), (rhs_known, rhs_value)) = (known(lhs), known(rhs));
+            (lhs_known & rhs_known, lhs_value ^ rhs_value)
+        }
+        Op::Concat(high, low) => {
+            let ((high_known, high_value), (low_known, low_value)) = (known(high), known(low));
+            (
+                high_known << low.width | low_known,
+                high_value << low.width | low_value,
+            )
+        }
+        Op::Slice { term, low, .. } => {
+            let (known, value) = known_bits(term, depth - 1).unwrap_or((0, 0));
+            (known >> low, value >> low)
+        }
+        Op::Uext(operand, _) => {
+            let (known, value) = known(operand);
+            (known | !mask(operand.width), value)
+        }
+        _ => (0, 0),
+    };
+    let width = mask(term.width);
+    Some((known & width, value & known & width))
+}
+
+pub(super) fn ite(condition: &Term, then: &Term, otherwise: &Term) -> Term {
+    assert_eq!(
+        condition.width, 1,
+        "the condition of an ite is a single bit"
+    );
+    assert_eq!(
+        then.width, otherwise.width,
+        "the branches of an ite have different widths"
+    );
+    if let Some(condition) = condition.constant() {
+        return match condition.is_zero() {
+            true => otherwise.clone(),
+            false => then.clone(),
+        };
+    }
+    if same(then, otherwise) {
+        return then.clone();
+    }
+    if then.width == 1 && then.is_one() && otherwise.is_zero() {
+        return condition.clone();
+    }
+    if then.width == 1 && then.is_zero() && otherwise.is_one() {
+        return not(condition);
+    }
+    node(
+        Op::Ite(condition.clone(), then.clone(), otherwise.clone()),
+        then.width,
+    )
+}
+
+pub(super) fn concat(high: &Term, low: &Term) -> Term {
+    if let (Some(high), Some(low)) = (high.constant(), low.constant()) {
+        return constant(high.concat(low));
+    }
+    // Adjacent slices of the same value, as when a value written to memory
+    // byte by byte is read back.
+    if let (
+        Op::Slice {
+            term: high_term,
+            low: high_low,
+            high: high_high,
+        },
+        Op::Slice {
+            term: low_term,
+            low: low_low,
+            high: low_high,
+        },
+    ) = (&high.op, &low.op)
+    {
+        if *high_low == low_high + 1 && same(high_term, low_term) {
+            return slice(high_term, *low_low, *high_high);
+        }
+    }
+    node(
+        Op::Concat(high.clone(), low.clone()),
+        high.width + low.width,
+    )
+}
+
+pub(super) fn slice(term: &Term, low: u32, high: u32) -> Term {
+    assert!(
+        low <= high && high < term.width,
+        "slice {low}..={high} of a {} bit value",
+        term.width
+    );
+    if low == 0 && high == term.width - 1 {
+        return term.clone();
+    }
+    match &term.op {
+        Op::Const(bits) => constant(bits.slice(low, high)),
+        Op::Slice {
+            term, low: offset, ..
+        } => slice(term, offset + low, offset + high),
+        Op::Concat(_, lower) if high < lower.width => slice(lower, low, high),
+        Op::Concat(upper, lower) if low >= lower.width => {
+            slice(upper, low - lower.width, high - lower.width)
+        }
+        Op::Uext(inner, _) | Op::Sext(inner, _) if high < inner.width => slice(inner, low, high),
+        Op::Uext(inner, _) if low >= inner.width => constant(Bits::from_u64(0, high - low + 1)),
+        _ => node(
+            Op::Slice {
+                term: term.clone(),
+                low,
+                high,
+            },
+            high - low + 1,
+        ),
+    }
+}
+
+pub(super) fn uext(term: &Term, bits: u32) -> Term {
+    match term.constant() {
+        _ if bits == 0 => term.clone(),
+        Some(value) => constant(value.uext(bits)),
+        None => node(Op::Uext(term.clone(), bits), term.width + bits),
+    }
+}
+
+pub(super) fn sext(term: &Term, bits: u32) -> Term {
+    match term.constant() {
+        _ if bits == 0 => term.clone(),
+        Some(value) => constant(value.sext(bits)),
+        None => node(Op::Sext(term.clone(), bits), term.width + bits),
+    }
+}
+
+/// Creates an array with unconstrained elements.
+pub(super) fn array(index_width: u32, element_width: u32, name: &str) -> Arc<ArrayNode> {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    Arc::new(ArrayNode {
+        kind: ArrayKind::Base {
+            id,
+            name: Arc::from(name),
+        },
+        index_width,
+        element_width,
+        hash: mix(mix(12, id), element_width as u64),
+    })
+}
+
+pub(super) fn write(array: &Arc<ArrayNode>, index: &Term, value: &Term) -> Arc<ArrayNode> {
+    assert_eq!(index.width, array.index_width, "wrong width of array index");
+    assert_eq!(
+        value.width, array.element_width,
+        "wrong width of array element"
+    );
+    // A write to the same index as the latest write replaces it.
+    let array = match &array.kind {
+        ArrayKind::Write(inner, latest, _) if same(latest, index) => inner,
+        _ => array,
+    };
+    Arc::new(ArrayNode {
+        hash: mix(mix(mix(13, array.hash), index.hash), value.hash),
+        kind: ArrayKind::Write(array.clone(), index.clone(), value.clone()),
+        index_width: array.index_width,
+        element_width: array.element_width,
+    })
+}
+
+pub(super) fn read(array: &Arc<ArrayNode>, index: &Term) -> Term {
+    assert_eq!(index.width, array.index_width, "wrong width of array index");
+    // Skip the writes that can not have written the index.
+    let mut array = array;
+    while let ArrayKind::Write(inner, written, value) = &array.kind {
+        if same(written, index) {
+            return value.clone();
+        }
+        match (written.constant(), index.constant()) {
+            (Some(_), Some(_)) => array = inner,
+            _ => break,
+        }
+    }
+    node(Op::Read(array.clone(), index.clone()), array.element_width)
+}
+
+/// Maximum number of nodes printed by [`Debug`], larger expressions are
+/// abbreviated.
+const PRINTED_NODES: usize = 64;
+
+fn print(term: &Node, budget: &mut usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if *budget == 0 {
+        return write!(f, "...");
+    }
+    *budget -= 1;
+    match &term.op {
+        Op::Const(bits) => write!(f, "#b{}", bits.to_binary_string()),
+        Op::Var { name, .. } => write!(f, "{name}"),
+        Op::Not(term) => {
+            write!(f, "(bvnot ")?;
+            print(term, budget, f)?;
+            write!(f, ")")
+        }
+        Op::Binary(op, lhs, rhs) => {
+            write!(f, "({} ", op.name())?;
+            print(lhs, budget, f)?;
+            write!(f, " ")?;
+            print(rhs, budget, f)?;
+            write!(f, ")")
+        }
+        Op::Ite(condition, then, otherwise) => {
+            write!(f, "(ite ")?;
+            print(condition, budget, f)?;
+            write!(f, " ")?;
+            print(then, budget, f)?;
+            write!(f, " ")?;
+            print(otherwise, budget, f)?;
+            write!(f, ")")
+        }
+        Op::Concat(high, low) => {
+            write!(f, "(concat ")?;
+            print(high, budget, f)?;
+            write!(f, " ")?;
+            print(low, budget, f)?;
+            write!(f, ")")
+        }
+        Op::Slice { term, low, high } => {
+            write!(f, "((_ extract {high} {low}) ")?;
+            print(term, budget, f)?;
+            write!(f, ")")
+        }
+        Op::Uext(term, bits) => {
+            write!(f, "((_ zero_extend {bits}) ")?;
+            print(term, budget, f)?;
+            write!(f, ")")
+        }
+        Op::Sext(term, bits) => {
+            write!(f, "((_ sign_extend {bits}) ")?;
+            print(term, budget, f)?;
+            write!(f, ")")
+        }
+        Op::Read(array, index) => {
+            write!(f, "(select {array:?} ")?;
+            print(index, budget, f)?;
+            write!(f, ")")
+        }
+    }
+}
+
+impl Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut budget = PRINTED_NODES;
+        print(self, &mut budget, f)
+    }
+}
+
+impl Debug for ArrayNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (array, writes) = self.writes();
+        match &array.kind {
+            ArrayKind::Base { name, .. } => write!(f, "{name}")?,
+            ArrayKind::Write(..) => unreachable!("the writes are skipped"),
+        }
+        if !writes.is_empty() {
+            write!(f, "[{} stores]", writes.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::{binary, concat, constant, sext, slice, uext, var, BinaryOp, Term};
+    use crate::smt::smt_sat::bits::Bits;
+
+    /// Results of the corner cases of the operations on 8 bit values, as
+    /// defined by the SMT-LIB theory of fixed size bit-vectors. Each case is
+    /// the operation, the operands and the result.
+    pub(in crate::smt::smt_sat) const SMTLIB: [(BinaryOp, u64, u64, u64); 25] = [
+        // Unsigned division by zero is all ones and the remainder is the
+        // dividend.
+        (BinaryOp::Udiv, 0x2a, 0, 0xff),
+        (BinaryOp::Udiv, 0, 0, 0xff),
+        (BinaryOp::Urem, 0x2a, 0, 0x2a),
+        (BinaryOp::Urem, 0, 0, 0),
+        // Signed division by zero is all ones for non-negative dividends and
+        // one for negative ones, the remainder is the dividend.
+        (BinaryOp::Sdiv, 0x2a, 0, 0xff),
+        (BinaryOp::Sdiv, 0, 0, 0xff),
+        (BinaryOp::Sdiv, 0xd6, 0, 0x01),
+        (BinaryOp::Srem, 0x2a, 0, 0x2a),
+        (BinaryOp::Srem, 0xd6, 0, 0xd6),
+        // The minimum divided by minus one overflows to the minimum.
+        (BinaryOp::Sdiv, 0x80, 0xff, 0x80),
+        (BinaryOp::Srem, 0x80, 0xff, 0),
+        // The quotient is truncated and the remainder has the sign of the
+        // dividend.
+        (BinaryOp::Sdiv, 0xf9, 0x02, 0xfd),
+        (BinaryOp::Srem, 0xf9, 0x02, 0xff),
+        (BinaryOp::Sdiv, 0x07, 0xfe, 0xfd),
+        (BinaryOp::Srem, 0x07, 0xfe, 0x01),
+        // Shifts by at least the width shift out every bit.
+        (BinaryOp::Sll, 0x81, 7, 0x80),
+        (BinaryOp::Sll, 0xff, 8, 0),
+        (BinaryOp::Sll, 0xff, 0xff, 0),
+        (BinaryOp::Srl, 0x81, 7, 0x01),
+        (BinaryOp::Srl, 0xff, 8, 0),
+        (BinaryOp::Srl, 0x80, 0xc8, 0),
+        (BinaryOp::Sra, 0x80, 7, 0xff),
+        (BinaryOp::Sra, 0x80, 8, 0xff),
+        (BinaryOp::Sra, 0x80, 0xff, 0xff),
+        (BinaryOp::Sra, 0x7f, 8, 0),
+    ];
+
+    fn value(value: u64, width: u32) -> Term {
+        constant(Bits::from_u64(value, width))
+    }
+
+    fn folded(term: &Term) -> u64 {
+        term.constant()
+            .and_then(Bits::as_u64)
+            .expect("operations on constants are folded")
+    }
+
+    #[test]
+    fn test_folding_matches_smtlib() {
+        for (op, lhs, rhs, expected) in SMTLIB {
+            let result = binary(op, &value(lhs, 8), &value(rhs, 8));
+            assert_eq!(folded(&result), expected, "{} {lhs:#x} {rhs:#x}", op.name());
+        }
+    }
+
+    #[test]
+    fn test_shift_amounts_of_other_widths() {
+        let x = value(0x81, 8);
+        assert_eq!(folded(&binary(BinaryOp::Sll, &x, &value(1, 4))), 0x02);
+        assert_eq!(folded(&binary(BinaryOp::Sra, &x, &value(0xf, 4))), 0xff);
+        assert_eq!(folded(&binary(BinaryOp::Srl, &x, &value(0x100, 16))), 0);
+        assert_eq!(folded(&binary(BinaryOp::Sra, &x, &value(0x100, 16))), 0xff);
+    }
+
+    #[test]
+    fn test_extension_slice_and_concat() {
+        assert_eq!(folded(&uext(&value(0x80, 8), 8)), 0x0080);
+        assert_eq!(folded(&sext(&value(0x80, 8), 8)), 0xff80);
+        assert_eq!(folded(&sext(&value(0x7f, 8), 8)), 0x007f);
+        assert_eq!(folded(&sext(&value(1, 1), 63)), u64::MAX);
+        assert_eq!(folded(&slice(&value(0xabcd, 16), 4, 11)), 0xbc);
+        assert_eq!(folded(&slice(&value(0xabcd, 16), 15, 15)), 1);
+        assert_eq!(folded(&concat(&value(0xab, 8), &value(0xcd, 8))), 0xabcd);
+        assert_eq!(folded(&concat(&value(1, 1), &value(0, 3))), 0b1000);
+
+        // Slices of extensions and concatenations are taken from the operand
+        // they select.
+        let x = var(8, "x");
+        assert_eq!(folded(&slice(&uext(&x, 8), 8, 15)), 0);
+        assert!(super::same(&slice(&sext(&x, 8), 0, 7), &x));
+        let joined = concat(&x, &var(8, "y"));
+        assert!(super::same(&slice(&joined, 8, 15), &x));
+        assert!(super::same(
+            &concat(&slice(&joined, 12, 15), &slice(&joined, 8, 11)),
+            &x
+        ));
+    }
+}
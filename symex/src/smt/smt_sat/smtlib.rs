@@ -0,0 +1,319 @@
+//! SMT-LIB scripts of expressions.
+//!
+//! Every operation and every write to an array is defined once with
+//! `define-fun`, so subterms shared in the expression graph are printed once.
+//! Predicates are bit-vectors of a single bit in the graph and are converted
+//! from the `Bool` of SMT-LIB with `ite`. Distinct symbols with the same name
+//! are told apart by a suffix.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use super::{
+    blast::{key, operands},
+    node::{ArrayKind, ArrayNode, Op, Term},
+};
+
+/// Builds the declarations and definitions of a script.
+#[derive(Default)]
+struct Script {
+    declarations: Vec<String>,
+    definitions: Vec<String>,
+    /// Names of the printed nodes, the nodes are kept so their addresses are
+    /// not reused.
+    terms: HashMap<usize, (Term, String)>,
+    arrays: HashMap<usize, (Arc<ArrayNode>, String)>,
+    /// Names of the declared symbols by id.
+    symbols: HashMap<u64, String>,
+    used: HashSet<String>,
+}
+
+/// Returns an SMT-LIB script that asserts that each of the single bits
+/// `constraints` is one.
+pub(super) fn script(constraints: &[Term]) -> String {
+    let mut script = Script::default();
+    let asserts: Vec<String> = constraints
+        .iter()
+        .map(|constraint| format!("(assert (= {} #b1))", script.term(constraint)))
+        .collect();
+    let mut lines = vec!["(set-logic QF_ABV)".to_owned()];
+    lines.extend(script.declarations);
+    lines.extend(script.definitions);
+    lines.extend(asserts);
+    lines.push("(check-sat)".to_owned());
+    lines.join("\n")
+}
+
+fn bit_vector(width: u32) -> String {
+    format!("(_ BitVec {width})")
+}
+
+impl Script {
+    /// Returns a name based on `name` that is not used yet.
+    fn fresh(&mut self, name: &str) -> String {
+        let name = name.replace(['|', '\\'], "_");
+        let mut candidate = format!("|{name}|");
+        let mut suffix = 0;
+        while !self.used.insert(candidate.clone()) {
+            suffix += 1;
+            candidate = format!("|{name}!{suffix}|");
+        }
+        candidate
+    }
+
+    /// Returns the name of the symbol `id`, declaring it the first time.
+    fn symbol(&mut self, id: u64, name: &str, sort: String) -> String {
+        if let Some(name) = self.symbols.get(&id) {
+            return name.clone();
+        }
+        let name = self.fresh(name);
+        self.declarations
+            .push(format!("(declare-const {name} {sort})"));
+        self.symbols.insert(id, name.clone());
+        name
+    }
+
+    /// Defines a constant of `sort` with the value `body` and returns its
+    /// name.
+    fn define(&mut self, sort: String, body: String) -> String {
+        let name = self.fresh(&format!("t{}", self.definitions.len()));
+        self.definitions
+            .push(format!("(define-fun {name} () {sort} {body})"));
+        name
+    }
+
+    fn name(&self, term: &Term) -> &str {
+        &self.terms[&key(term)].1
+    }
+
+    /// Returns the name or value of `term`, defining it and its operands the
+    /// first time.
+    fn term(&mut self, term: &Term) -> String {
+        // Print the operands before the nodes using them, without recursion as
+        // expressions can be deep.
+        let mut pending = vec![(term.clone(), false)];
+        while let Some((term, ready)) = pending.pop() {
+            if self.terms.contains_key(&key(&term)) {
+                continue;
+            }
+            if ready {
+                let name = self.translate(&term);
+                self.terms.insert(key(&term), (term, name));
+            } else {
+                pending.push((term.clone(), true));
+                // Reversed so the operands are printed from left to right.
+                for operand in operands(&term).into_iter().rev() {
+                    if !self.terms.contains_key(&key(operand)) {
+                        pending.push((operand.clone(), false));
+                    }
+                }
+            }
+        }
+        self.name(term).to_owned()
+    }
+
+    /// Returns the name of `array`, defining the writes to it the first time.
+    /// The indices and values written must be printed.
+    fn array(&mut self, array: &Arc<ArrayNode>) -> String {
+        let sort = format!(
+            "(Array {} {})",
+            bit_vector(array.index_width),
+            bit_vector(array.element_width)
+        );
+        let mut writes = vec![];
+        let mut current = array;
+        let mut name = loop {
+            if let Some((_, name)) = self.arrays.get(&(Arc::as_ptr(current) as usize)) {
+                break name.clone();
+            }
+            match &current.kind {
+                ArrayKind::Base { id, name } => {
+                    let name = self.symbol(*id, name, sort.clone());
+                    self.arrays.insert(
+                        Arc::as_ptr(current) as usize,
+                        (current.clone(), name.clone()),
+                    );
+                    break name;
+                }
+                ArrayKind::Write(inner, ..) => {
+                    writes.push(current);
+                    current = inner;
+                }
+            }
+        };
+        for array in writes.into_iter().rev() {
+            let ArrayKind::Write(_, index, value) = &array.kind else {
+                unreachable!("only writes are defined");
+            };
+            let body = format!("(store {name} {} {})", self.name(index), self.name(value));
+            name = self.define(sort.clone(), body);
+            self.arrays
+                .insert(Arc::as_ptr(array) as usize, (array.clone(), name.clone()));
+        }
+        name
+    }
+
+    /// Prints a node whose operands have been printed.
+    fn translate(&mut self, term: &Term) -> String {
+        let body = match &term.op {
+            Op::Const(bits) => return format!("#b{}", bits.to_binary_string()),
+            Op::Var { id, name } => return self.symbol(*id, name, bit_vector(term.width)),
+            Op::Not(operand) => format!("(bvnot {})", self.name(operand)),
+            Op::Binary(op, lhs, rhs) => {
+                let amount = match op.is_shift() {
+                    true => self.shift_amount(rhs, lhs.width),
+                    false => self.name(rhs).to_owned(),
+                };
+                match op.is_predicate() {
+                    true => format!("(ite ({} {} {amount}) #b1 #b0)", op.name(), self.name(lhs)),
+                    false => format!("({} {} {amount})", op.name(), self.name(lhs)),
+                }
+            }
+            Op::Ite(condition, then, otherwise) => format!(
+                "(ite (= {} #b1) {} {})",
+                self.name(condition),
+                self.name(then),
+                self.name(otherwise)
+            ),
+            Op::Concat(high, low) => format!("(concat {} {})", self.name(high), self.name(low)),
+            Op::Slice { term, low, high } => {
+                format!("((_ extract {high} {low}) {})", self.name(term))
+            }
+            Op::Uext(operand, bits) => {
+                format!("((_ zero_extend {bits}) {})", self.name(operand))
+            }
+            Op::Sext(operand, bits) => {
+                format!("((_ sign_extend {bits}) {})", self.name(operand))
+            }
+            Op::Read(array, index) => {
+                let array = self.array(array);
+                format!("(select {array} {})", self.name(index))
+            }
+        };
+        self.define(bit_vector(term.width), body)
+    }
+
+    /// Converts the unsigned shift amount `amount` to `width` bits, as SMT-LIB
+    /// shifts require operands of the same width. Amounts of at least `width`
+    /// are saturated to `width` first, which shifts out every bit as well.
+    fn shift_amount(&self, amount: &Term, width: u32) -> String {
+        let name = self.name(amount);
+        match amount.width.cmp(&width) {
+            std::cmp::Ordering::Equal => name.to_owned(),
+            std::cmp::Ordering::Less => {
+                format!("((_ zero_extend {}) {name})", width - amount.width)
+            }
+            std::cmp::Ordering::Greater => {
+                let limit = format!("(_ bv{width} {})", amount.width);
+                format!(
+                    "((_ extract {} 0) (ite (bvult {name} {limit}) {name} {limit}))",
+                    width - 1
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::script;
+    use crate::smt::smt_sat::node::{self, BinaryOp};
+
+    fn lines(lines: &[&str]) -> String {
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_shared_subterms() {
+        let x = node::var(8, "x");
+        let y = node::var(8, "y");
+        let sum = node::binary(BinaryOp::Add, &x, &y);
+        let shifted = node::binary(BinaryOp::Sll, &sum, &y);
+        let constraint = node::binary(BinaryOp::Ult, &sum, &shifted);
+        assert_eq!(
+            script(&[constraint]),
+            lines(&[
+                "(set-logic QF_ABV)",
+                "(declare-const |x| (_ BitVec 8))",
+                "(declare-const |y| (_ BitVec 8))",
+                "(define-fun |t0| () (_ BitVec 8) (bvadd |x| |y|))",
+                "(define-fun |t1| () (_ BitVec 8) (bvshl |t0| |y|))",
+                "(define-fun |t2| () (_ BitVec 1) (ite (bvult |t0| |t1|) #b1 #b0))",
+                "(assert (= |t2| #b1))",
+                "(check-sat)",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_same_name() {
+        let first = node::var(8, "x");
+        let second = node::var(8, "x");
+        let constraint = node::binary(BinaryOp::Eq, &first, &second);
+        assert_eq!(
+            script(&[constraint.clone(), constraint]),
+            lines(&[
+                "(set-logic QF_ABV)",
+                "(declare-const |x| (_ BitVec 8))",
+                "(declare-const |x!1| (_ BitVec 8))",
+                "(define-fun |t0| () (_ BitVec 1) (ite (= |x| |x!1|) #b1 #b0))",
+                "(assert (= |t0| #b1))",
+                "(assert (= |t0| #b1))",
+                "(check-sat)",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_array_store() {
+        let memory = node::array(8, 8, "memory");
+        let index = node::var(8, "i");
+        let value = node::var(8, "v");
+        let written = node::write(&memory, &index, &value);
+        let written = node::write(&written, &value, &index);
+        let read = node::read(&written, &node::var(8, "j"));
+        let constraint = node::binary(BinaryOp::Eq, &read, &value);
+        let array = "(Array (_ BitVec 8) (_ BitVec 8))";
+        assert_eq!(
+            script(&[constraint]),
+            lines(&[
+                "(set-logic QF_ABV)",
+                "(declare-const |j| (_ BitVec 8))",
+                "(declare-const |v| (_ BitVec 8))",
+                "(declare-const |i| (_ BitVec 8))",
+                &format!("(declare-const |memory| {array})"),
+                &format!("(define-fun |t0| () {array} (store |memory| |i| |v|))"),
+                &format!("(define-fun |t1| () {array} (store |t0| |v| |i|))"),
+                "(define-fun |t2| () (_ BitVec 8) (select |t1| |j|))",
+                "(define-fun |t3| () (_ BitVec 1) (ite (= |t2| |v|) #b1 #b0))",
+                "(assert (= |t3| #b1))",
+                "(check-sat)",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_shift_widths() {
+        let x = node::var(8, "x");
+        let narrow = node::binary(BinaryOp::Srl, &x, &node::var(4, "a"));
+        let wide = node::binary(BinaryOp::Sra, &x, &node::var(16, "b"));
+        let constraint = node::binary(BinaryOp::Eq, &narrow, &wide);
+        assert_eq!(
+            script(&[constraint]),
+            lines(&[
+                "(set-logic QF_ABV)",
+                "(declare-const |x| (_ BitVec 8))",
+                "(declare-const |a| (_ BitVec 4))",
+                "(declare-const |b| (_ BitVec 16))",
+                "(define-fun |t0| () (_ BitVec 8) (bvlshr |x| ((_ zero_extend 4) |a|)))",
+                "(define-fun |t1| () (_ BitVec 8) (bvashr |x| ((_ extract 7 0) (ite (bvult |b| \
+                 (_ bv8 16)) |b| (_ bv8 16)))))",
+                "(define-fun |t2| () (_ BitVec 1) (ite (= |t0| |t1|) #b1 #b0))",
+                "(assert (= |t2| #b1))",
+                "(check-sat)",
+            ])
+        );
+    }
+}
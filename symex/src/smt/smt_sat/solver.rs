@@ -0,0 +1,409 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use super::{
+    blast::Blaster,
+    cdcl::Outcome,
+    node::{self, Term},
+    smtlib,
+    SatExpr,
+    SatSolverContext,
+};
+use crate::smt::{
+    independence::{ConstraintSet, Symbols},
+    Solutions,
+    SolverError,
+};
+
+/// Incremental solver over the constraints of a path.
+///
+/// Constraints are kept in a [`ConstraintSet`], each query encodes the
+/// constraints that share symbols with it in a new SAT problem, see
+/// [`independence`](crate::smt::independence).
+#[derive(Debug, Clone)]
+pub struct SatIncrementalSolver {
+    ctx: SatSolverContext,
+    constraints: Arc<Mutex<ConstraintSet<Term>>>,
+}
+
+impl SatIncrementalSolver {
+    pub fn new(ctx: &SatSolverContext) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            constraints: Arc::new(Mutex::new(ConstraintSet::new())),
+        }
+    }
+
+    fn constraints(&self) -> std::sync::MutexGuard<'_, ConstraintSet<Term>> {
+        self.constraints
+            .lock()
+            .expect("the constraints are never used while panicking")
+    }
+
+    /// Solves `constraints` together with `assumptions`, and returns the
+    /// values of `exprs` in the model if it is satisfiable.
+    ///
+    /// `query` describes what was solved for if the solver cannot decide, such
+    /// as when it times out.
+    fn check_sat(
+        &self,
+        constraints: Vec<Term>,
+        assumptions: &[&SatExpr],
+        exprs: &[&SatExpr],
+        query: impl FnOnce() -> String,
+    ) -> Result<Option<Vec<SatExpr>>, SolverError> {
+        let start = Instant::now();
+        let mut blaster = Blaster::new();
+        for constraint in constraints
+            .iter()
+            .chain(assumptions.iter().map(|expr| &expr.0))
+        {
+            blaster.assert(constraint);
+        }
+        let bits: Vec<_> = exprs.iter().map(|expr| blaster.blast(&expr.0)).collect();
+        let deadline = self.ctx.timeout().map(|timeout| start + timeout);
+        let result = blaster.solve(deadline);
+        self.ctx.add_solver_time(start.elapsed());
+        match result {
+            Outcome::Sat => Ok(Some(
+                bits.iter()
+                    .map(|bits| SatExpr(node::constant(blaster.value(bits)), Symbols::none()))
+                    .collect(),
+            )),
+            Outcome::Unsat => Ok(None),
+            Outcome::Unknown => Err(SolverError::Unknown(format!(
+                "{} under {} path constraints",
+                query(),
+                constraints.len()
+            ))),
+        }
+    }
+
+    /// Returns the constraints that `symbols` depend on.
+    fn relevant(&self, symbols: &Symbols) -> Vec<Term> {
+        self.constraints().relevant(symbols)
+    }
+
+    pub fn get_value(&self, expr: &SatExpr) -> Result<SatExpr, SolverError> {
+        if expr.get_constant().is_some() {
+            return Ok(expr.clone());
+        }
+
+        let query = || format!("value of {:?}", expr.0);
+        match self.check_sat(self.relevant(&expr.1), &[], &[expr], query)? {
+            Some(mut values) => Ok(values.remove(0)),
+            None => Err(SolverError::Unsat),
+        }
+    }
+
+    /// Finds a concrete value for each of `exprs` from a single model of the
+    /// constraints.
+    ///
+    /// The values are consistent with each other, unlike separate calls to
+    /// [`get_value`](Self::get_value), and only one query is sent to the
+    /// solver.
+    pub fn get_model(&self, exprs: &[SatExpr]) -> Result<Vec<SatExpr>, SolverError> {
+        let mut symbols = Symbols::none();
+        for expr in exprs {
+            symbols = symbols.union(&expr.1);
+        }
+        if symbols.is_empty() {
+            return Ok(exprs.to_vec());
+        }
+
+        let query = || format!("a model of {} values", exprs.len());
+        let exprs: Vec<&SatExpr> = exprs.iter().collect();
+        self.check_sat(self.relevant(&symbols), &[], &exprs, query)?
+            .ok_or(SolverError::Unsat)
+    }
+
+    /// The asserted constraints, in the order they were asserted.
+    pub fn asserted(&self) -> Vec<SatExpr> {
+        self.constraints()
+            .constraints()
+            .map(|(constraint, symbols)| SatExpr(constraint.clone(), symbols.clone()))
+            .collect()
+    }
+
+    /// An SMT-LIB script that asserts the asserted constraints.
+    pub fn to_smtlib(&self) -> String {
+        let constraints: Vec<Term> = self
+            .constraints()
+            .constraints()
+            .map(|(constraint, _)| constraint.clone())
+            .collect();
+        smtlib::script(&constraints)
+    }
+
+    /// Conjunction of the asserted constraints, true if there are none.
+    pub fn path_condition(&self) -> SatExpr {
+        let mut condition = self.ctx.from_bool(true);
+        for (constraint, symbols) in self.constraints().constraints() {
+            condition = condition.and(&SatExpr(constraint.clone(), symbols.clone()));
+        }
+        condition
+    }
+
+    /// Number of asserted constraints in all scopes.
+    pub fn constraint_count(&self) -> usize {
+        self.constraints().len()
+    }
+
+    /// Creates a solver that does not share its constraints with this solver,
+    /// holding the first `count` constraints asserted in this solver.
+    pub fn detach(&self, count: usize) -> Self {
+        Self {
+            ctx: self.ctx.clone(),
+            constraints: Arc::new(Mutex::new(self.constraints().prefix(count))),
+        }
+    }
+
+    pub fn push(&self) {
+        self.constraints().push();
+    }
+
+    pub fn pop(&self) {
+        self.constraints().pop();
+    }
+
+    /// Solve for the current solver state, and returns if the result is
+    /// satisfiable.
+    ///
+    /// All asserts and assumes are implicitly combined with a boolean and.
+    /// Returns true or false, and [`SolverError::Unknown`] if the result
+    /// cannot be determined, such as when the query times out, see
+    /// [`SatSolverContext::set_timeout`].
+    pub fn is_sat(&self) -> Result<bool, SolverError> {
+        let constraints = self.constraints().all();
+        self.check_sat(constraints, &[], &[], || {
+            "feasibility of the path".to_owned()
+        })
+        .map(|model| model.is_some())
+    }
+
+    /// Solve for the solver state with the assumption of the passed constraint.
+    ///
+    /// Only the constraints that share symbols with `constraint` are sent to
+    /// the solver, the others cannot change the result.
+    pub fn is_sat_with_constraint(&self, constraint: &SatExpr) -> Result<bool, SolverError> {
+        self.is_sat_with_constraints(std::slice::from_ref(constraint))
+    }
+
+    /// Solve for the solver state with the assumption of the passed
+    /// constraints.
+    pub fn is_sat_with_constraints(&self, constraints: &[SatExpr]) -> Result<bool, SolverError> {
+        let mut symbols = Symbols::none();
+        for constraint in constraints {
+            if constraint.get_constant_bool() == Some(false) {
+                return Ok(false);
+            }
+            symbols = symbols.union(&constraint.1);
+        }
+        let assumptions: Vec<&SatExpr> = constraints.iter().collect();
+        self.check_sat(self.relevant(&symbols), &assumptions, &[], || {
+            format!("{:?}", constraints)
+        })
+        .map(|model| model.is_some())
+    }
+
+    /// Add the constraint to the solver.
+    ///
+    /// The passed constraint will be implicitly combined with the current state
+    /// in a boolean `and`. Asserted constraints are only removed by popping the
+    /// scope they were added in.
+    pub fn assert(&self, constraint: &SatExpr) {
+        if constraint.get_constant_bool() == Some(true) {
+            return;
+        }
+        self.constraints()
+            .add(constraint.0.clone(), constraint.1.clone());
+    }
+
+    /// Find solutions to `expr`.
+    ///
+    /// Returns concrete solutions up to `upper_bound`, the returned
+    /// [`Solutions`] has variants for if the number of solution exceeds the
+    /// upper bound.
+    pub fn get_values(
+        &self,
+        expr: &SatExpr,
+        upper_bound: usize,
+    ) -> Result<Solutions<SatExpr>, SolverError> {
+        if expr.get_constant().is_some() {
+            return Ok(Solutions::Exactly(vec![expr.clone()]));
+        }
+
+        // Setup before checking for solutions.
+        self.push();
+        let result = self.get_solutions(expr, upper_bound);
+        // Restore solver to initial state.
+        self.pop();
+
+        result
+    }
+
+    /// Returns `true` if `lhs` and `rhs` must be equal under the current
+    /// constraints.
+    pub fn must_be_equal(&self, lhs: &SatExpr, rhs: &SatExpr) -> Result<bool, SolverError> {
+        // Add the constraint lhs != rhs and invert the results. The only way
+        // for `lhs != rhs` to be `false` is that if they are equal.
+        let constraint = lhs.ne(rhs);
+        let result = self.is_sat_with_constraint(&constraint)?;
+        Ok(!result)
+    }
+
+    /// Check if `lhs` and `rhs` can be equal under the current constraints.
+    pub fn can_equal(&self, lhs: &SatExpr, rhs: &SatExpr) -> Result<bool, SolverError> {
+        self.is_sat_with_constraint(&lhs.eq(rhs))
+    }
+
+    /// Find solutions to `expr`.
+    ///
+    /// Returns concrete solutions up to a maximum of `upper_bound`. If more
+    /// solutions are available the error [`SolverError::TooManySolutions`]
+    /// is returned.
+    pub fn get_solutions2(
+        &self,
+        expr: &SatExpr,
+        upper_bound: usize,
+    ) -> Result<Vec<SatExpr>, SolverError> {
+        let result = self.get_values(expr, upper_bound)?;
+        match result {
+            Solutions::Exactly(solutions) => Ok(solutions),
+            Solutions::AtLeast(_) => Err(SolverError::TooManySolutions),
+        }
+    }
+
+    fn get_solutions(
+        &self,
+        expr: &SatExpr,
+        upper_bound: usize,
+    ) -> Result<Solutions<SatExpr>, SolverError> {
+        let mut solutions = Vec::new();
+        let query = || format!("solutions of {:?}", expr.0);
+
+        while solutions.len() < upper_bound {
+            let Some(mut values) = self.check_sat(self.relevant(&expr.1), &[], &[expr], query)?
+            else {
+                return Ok(Solutions::Exactly(solutions));
+            };
+            let solution = values.remove(0);
+
+            // Constrain the next value to not be an already found solution.
+            self.assert(&expr.ne(&solution));
+
+            solutions.push(solution);
+        }
+
+        let exists_more_solutions = self
+            .check_sat(self.relevant(&expr.1), &[], &[], query)?
+            .is_some();
+        if exists_more_solutions {
+            return Ok(Solutions::AtLeast(solutions));
+        }
+        Ok(Solutions::Exactly(solutions))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SatIncrementalSolver;
+    use crate::smt::smt_sat::{SatArray, SatSolverContext};
+
+    #[test]
+    fn test_division_by_zero() {
+        let ctx = SatSolverContext::new();
+        let solver = SatIncrementalSolver::new(&ctx);
+        let x = ctx.unconstrained(8, "x");
+        let zero = ctx.zero(8);
+        let must_equal = |lhs, rhs| solver.must_be_equal(&lhs, &rhs).unwrap();
+
+        // As in SMT-LIB, for every dividend.
+        assert!(must_equal(x.udiv(&zero), ctx.unsigned_max(8)));
+        assert!(must_equal(x.urem(&zero), x.clone()));
+        assert!(must_equal(x.srem(&zero), x.clone()));
+
+        solver.push();
+        solver.assert(&x.slt(&zero));
+        assert!(must_equal(x.sdiv(&zero), ctx.one(8)));
+        solver.pop();
+        solver.push();
+        solver.assert(&x.sgte(&zero));
+        assert!(must_equal(x.sdiv(&zero), ctx.unsigned_max(8)));
+        solver.pop();
+
+        // The minimum divided by minus one overflows.
+        solver.assert(&x.eq(&ctx.signed_min(8)));
+        let minus_one = ctx.unsigned_max(8);
+        assert!(must_equal(x.sdiv(&minus_one), ctx.signed_min(8)));
+        assert!(must_equal(x.srem(&minus_one), zero));
+    }
+
+    #[test]
+    fn test_shifts_by_width() {
+        let ctx = SatSolverContext::new();
+        let solver = SatIncrementalSolver::new(&ctx);
+        let x = ctx.unconstrained(8, "x");
+        let amount = ctx.unconstrained(8, "amount");
+        solver.assert(&amount.ugte(&ctx.from_u64(8, 8)));
+
+        assert!(solver.must_be_equal(&x.sll(&amount), &ctx.zero(8)).unwrap());
+        assert!(solver.must_be_equal(&x.srl(&amount), &ctx.zero(8)).unwrap());
+        solver.assert(&x.slt(&ctx.zero(8)));
+        assert!(solver
+            .must_be_equal(&x.sra(&amount), &ctx.unsigned_max(8))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let ctx = SatSolverContext::new();
+        let solver = SatIncrementalSolver::new(&ctx);
+        let x = ctx.unconstrained(8, "x");
+        let four = ctx.from_u64(4, 8);
+        solver.assert(&x.ult(&ctx.from_u64(5, 8)));
+
+        solver.push();
+        solver.assert(&x.ugt(&ctx.from_u64(3, 8)));
+        assert!(solver.must_be_equal(&x, &four).unwrap());
+        solver.push();
+        solver.assert(&x.ne(&four));
+        assert!(!solver.is_sat().unwrap());
+        assert_eq!(solver.constraint_count(), 3);
+
+        // Popping removes the constraints of the scope and only those.
+        solver.pop();
+        assert!(solver.is_sat().unwrap());
+        assert!(!solver.can_equal(&x, &ctx.zero(8)).unwrap());
+        solver.pop();
+        assert!(solver.can_equal(&x, &ctx.zero(8)).unwrap());
+        assert!(!solver.can_equal(&x, &ctx.from_u64(5, 8)).unwrap());
+        assert_eq!(solver.constraint_count(), 1);
+    }
+
+    #[test]
+    fn test_read_after_write() {
+        let ctx = SatSolverContext::new();
+        let solver = SatIncrementalSolver::new(&ctx);
+        let mut memory = SatArray::new(&ctx, 8, 8, "memory");
+        let a = ctx.unconstrained(8, "a");
+        let b = ctx.unconstrained(8, "b");
+        let (one, two) = (ctx.from_u64(1, 8), ctx.from_u64(2, 8));
+        memory.write(&a, &one);
+        memory.write(&b, &two);
+
+        // The read sees the latest write if the indices may be equal.
+        let read = memory.read(&a);
+        assert!(solver.can_equal(&read, &one).unwrap());
+        assert!(solver.can_equal(&read, &two).unwrap());
+        assert!(!solver.can_equal(&read, &ctx.zero(8)).unwrap());
+        solver.push();
+        solver.assert(&a.ne(&b));
+        assert!(solver.must_be_equal(&read, &one).unwrap());
+        solver.pop();
+        solver.assert(&a.eq(&b));
+        assert!(solver.must_be_equal(&read, &two).unwrap());
+    }
+}
@@ -0,0 +1,369 @@
+//! Regression tests generated from the paths of a run.
+//!
+//! A [`TestGenerator`] turns the solved values of the variables marked as
+//! symbolic on each path into a `#[test]` function that binds the values and
+//! calls the code under test, so that a counterexample found by symex can be
+//! committed as a regression test of the firmware crate:
+//!
+//! ```no_run
+//! # use symex::{run_elf::{run_elf_with_options, ElfOptions}, test_gen::TestGenerator};
+//! let results = run_elf_with_options(
+//!     "target/thumbv6m-none-eabi/release/examples/parse",
+//!     "parse",
+//!     &ElfOptions::default(),
+//! )
+//! .unwrap();
+//! let tests = TestGenerator::new("parse")
+//!     .call("app::parse_header({inputs})")
+//!     .generate(&results);
+//! std::fs::write("tests/parse_paths.rs", tests).unwrap();
+//! ```
+//!
+//! The call is a template where `{function}` is replaced by the name of the
+//! analyzed function and `{inputs}` by the bound inputs separated by commas,
+//! in the order they were marked as symbolic. The template can be any
+//! expression, such as a call of an embedded test harness. Paths that failed
+//! are expected to panic. Paths that did not run to completion are left out
+//! as their inputs do not reproduce a complete behavior.
+
+use std::fmt;
+
+use crate::elf_util::{ConcreteInput, ExpressionType, PathStatus, VisualPathResult};
+
+/// Keywords that can not be used as the name of a binding.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while",
+];
+
+/// Generates regression tests from the paths of an analyzed function.
+#[derive(Debug, Clone)]
+pub struct TestGenerator {
+    function: String,
+    call: String,
+}
+
+impl TestGenerator {
+    /// Creates a generator for the paths of `function` that calls
+    /// `function` with the inputs.
+    pub fn new(function: &str) -> Self {
+        Self {
+            function: function.to_owned(),
+            call: "{function}({inputs})".to_owned(),
+        }
+    }
+
+    /// Sets the template of the call of each test, see the
+    /// [module documentation](self).
+    pub fn call(mut self, call: &str) -> Self {
+        self.call = call.to_owned();
+        self
+    }
+
+    /// Creates the test case of `result`, if the path ran to completion.
+    pub fn test_case(&self, result: &VisualPathResult) -> Option<TestCase> {
+        let outcome = match &result.result {
+            PathStatus::Ok(_) => Outcome::Returns(result.return_value.clone()),
+            PathStatus::Failed(reason) => Outcome::Panics(reason.error_message.clone()),
+            PathStatus::Unknown(_) | PathStatus::Truncated(_) => return None,
+        };
+
+        let mut inputs: Vec<TestInput> = vec![];
+        for input in result.concrete_inputs() {
+            let name = input.name.clone().unwrap_or_else(|| "_".to_owned());
+            let mut ident = identifier(&name);
+            let taken = |ident: &str| inputs.iter().any(|input| input.ident == ident);
+            if taken(&ident) {
+                ident = (2..)
+                    .map(|n| format!("{ident}_{n}"))
+                    .find(|candidate| !taken(candidate))
+                    .unwrap();
+            }
+            let (ty, literal) = literal(&input);
+            inputs.push(TestInput {
+                constrained: result.constrained.contains(&name),
+                name,
+                ident,
+                ty,
+                literal,
+            });
+        }
+
+        let idents: Vec<&str> = inputs.iter().map(|input| input.ident.as_str()).collect();
+        let call = self
+            .call
+            .replace("{function}", &self.function)
+            .replace("{inputs}", &idents.join(", "));
+        Some(TestCase {
+            name: format!("{}_path_{}", identifier(&self.function), result.path),
+            function: self.function.clone(),
+            path: result.path,
+            outcome,
+            inputs,
+            call,
+        })
+    }
+
+    /// Generates the source of a test for every path in `results` that ran to
+    /// completion.
+    pub fn generate(&self, results: &[VisualPathResult]) -> String {
+        let cases: Vec<TestCase> = results
+            .iter()
+            .filter_map(|result| self.test_case(result))
+            .collect();
+        let mut source = format!(
+            "// Regression tests generated by symex from the paths of `{}`.\n",
+            self.function
+        );
+        let skipped = results.len() - cases.len();
+        if skipped > 0 {
+            source.push_str(&format!(
+                "// {skipped} paths did not run to completion and are not included.\n"
+            ));
+        }
+        for case in cases {
+            source.push_str(&format!("\n{case}"));
+        }
+        source
+    }
+}
+
+/// A generated test that reproduces a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// Name of the test function.
+    pub name: String,
+
+    /// Name of the analyzed function.
+    pub function: String,
+
+    /// Which path the test reproduces.
+    pub path: usize,
+
+    /// What the path did.
+    pub outcome: Outcome,
+
+    /// Values of the variables marked as symbolic on the path.
+    pub inputs: Vec<TestInput>,
+
+    /// The call of the code under test.
+    pub call: String,
+}
+
+/// What a reproduced path did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The path returned, with the decoded return value if the debug data
+    /// describes it.
+    Returns(Option<String>),
+
+    /// The path failed for the reason, the test expects a panic.
+    Panics(String),
+}
+
+/// The value of an input on a path, bound to a local in the test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestInput {
+    /// Name of the symbol.
+    pub name: String,
+
+    /// Name of the local the value is bound to.
+    pub ident: String,
+
+    /// Rust type of the local.
+    pub ty: String,
+
+    /// Rust expression of the value.
+    pub literal: String,
+
+    /// If the path condition depends on the input, otherwise any value takes
+    /// the path.
+    pub constrained: bool,
+}
+
+impl fmt::Display for TestCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            Outcome::Returns(Some(value)) => writeln!(
+                f,
+                "/// Path {} of `{}`, returns `{value}`.",
+                self.path, self.function
+            )?,
+            Outcome::Returns(None) => {
+                writeln!(f, "/// Path {} of `{}`, returns.", self.path, self.function)?
+            }
+            Outcome::Panics(reason) => writeln!(
+                f,
+                "/// Path {} of `{}`, fails with: {reason}",
+                self.path, self.function
+            )?,
+        }
+        writeln!(f, "#[test]")?;
+        if let Outcome::Panics(_) = self.outcome {
+            writeln!(f, "#[should_panic]")?;
+        }
+        writeln!(f, "fn {}() {{", self.name)?;
+        for input in &self.inputs {
+            write!(
+                f,
+                "    let {}: {} = {};",
+                input.ident, input.ty, input.literal
+            )?;
+            match (input.constrained, input.ident == input.name) {
+                (false, _) => writeln!(f, " // Any value takes the path.")?,
+                (true, false) => writeln!(f, " // {}", input.name)?,
+                (true, true) => writeln!(f)?,
+            }
+        }
+        writeln!(f, "    {};", self.call)?;
+        writeln!(f, "}}")
+    }
+}
+
+/// Turns the last segment of `name`, such as `len` of `app::parse::len`, into
+/// an identifier.
+fn identifier(name: &str) -> String {
+    let segment = name.rsplit("::").next().unwrap_or(name);
+    let mut ident: String = segment
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+/// Writes the value of `input` as a Rust type and expression. Integers and
+/// floats of a native size are written as such, other values as their bytes
+/// in little endian order.
+fn literal(input: &ConcreteInput) -> (String, String) {
+    let bits = input.bits;
+    match (&input.ty, input.as_u128()) {
+        (ExpressionType::Float(32 | 64), Some(value)) if matches!(bits, 32 | 64) => (
+            format!("f{bits}"),
+            format!("f{bits}::from_bits({value:#x})"),
+        ),
+        (ExpressionType::Integer(_) | ExpressionType::Unknown, Some(value))
+            if matches!(bits, 8 | 16 | 32 | 64 | 128) =>
+        {
+            (format!("u{bits}"), format!("{value:#x}"))
+        }
+        _ => {
+            let bytes: Vec<String> = input
+                .bytes
+                .iter()
+                .map(|byte| format!("{byte:#04x}"))
+                .collect();
+            (
+                format!("[u8; {}]", bytes.len()),
+                format!("[{}]", bytes.join(", ")),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{Outcome, TestGenerator};
+    use crate::{
+        elf_util::{ErrorReason, ExpressionType, PathStatus, Variable, VisualPathResult},
+        general_assembly::{budget::Truncation, path_data::PathData},
+        smt::DContext,
+    };
+
+    fn result(path: usize, result: PathStatus, symbolics: Vec<Variable>) -> VisualPathResult {
+        VisualPathResult {
+            path,
+            result,
+            symbolics,
+            constrained: vec!["app::parse::len".to_owned()],
+            input_ranges: vec![],
+            end_state: vec![],
+            register_roles: HashMap::new(),
+            return_value: Some("Ok(3)".to_owned()),
+            instruction_count: 0,
+            max_cycles: 0,
+            energy: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictions: None,
+            cycle_laps: vec![],
+            reached: vec![],
+            initial_sp: 0,
+            memory_delta: vec![],
+            register_history: vec![],
+            mmio_log: vec![],
+            slice: None,
+            provenance: vec![],
+            filtered_calls: vec![],
+            under_approximated: vec![],
+            interrupts: vec![],
+            atomicity_violation: None,
+            reentry: None,
+            nvic: vec![],
+            peripherals: vec![],
+            path_data: PathData::default(),
+            constraints: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        let ctx = DContext::new();
+        let var = |name: &str, value: u64, bits: u32, ty: ExpressionType| Variable {
+            name: Some(name.to_owned()),
+            value: ctx.from_u64(value, bits),
+            ty,
+        };
+        let symbolics = vec![
+            var("app::parse::len", 300, 32, ExpressionType::Integer(32)),
+            var("any1", 0x03_0201, 24, ExpressionType::Integer(24)),
+            var("len", 0x3f80_0000, 32, ExpressionType::Float(32)),
+        ];
+        let failed = PathStatus::Failed(ErrorReason {
+            error_message: "panic".to_owned(),
+        });
+        let results = vec![
+            result(1, PathStatus::Ok(None), symbolics.clone()),
+            result(2, failed, symbolics),
+            result(3, PathStatus::Truncated(Truncation::Paths(3)), vec![]),
+        ];
+
+        let generator = TestGenerator::new("app::parse").call("parse_header({inputs})");
+        let case = generator.test_case(&results[0]).unwrap();
+        assert_eq!(case.name, "parse_path_1");
+        assert_eq!(case.outcome, Outcome::Returns(Some("Ok(3)".to_owned())));
+        assert_eq!(case.call, "parse_header(len, any1, len_2)");
+        let inputs: Vec<(&str, &str)> = case
+            .inputs
+            .iter()
+            .map(|input| (input.ty.as_str(), input.literal.as_str()))
+            .collect();
+        assert_eq!(inputs, vec![
+            ("u32", "0x12c"),
+            ("[u8; 3]", "[0x01, 0x02, 0x03]"),
+            ("f32", "f32::from_bits(0x3f800000)"),
+        ]);
+        assert!(generator.test_case(&results[2]).is_none());
+
+        let source = generator.generate(&results);
+        assert!(source.contains("// 1 paths did not run to completion"));
+        assert!(source.contains(
+            "#[test]\n#[should_panic]\nfn parse_path_2() {\n    let len: u32 = 0x12c; // \
+             app::parse::len\n"
+        ));
+        assert!(source.contains("    let any1: [u8; 3] = [0x01, 0x02, 0x03]; // Any value"));
+        assert!(source.contains("    parse_header(len, any1, len_2);\n}\n"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Continuous analysis of an elf file that is rebuilt during development.
+//!
+//! A [`Watcher`] polls the modification time of the elf file and runs the
+//! analysis of a function again when the file changes, reporting how the
+//! results changed since the previous run as a [`RunDelta`]:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use symex::{run_elf::ElfOptions, watch::Watcher};
+//! Watcher::new(
+//!     "target/thumbv6m-none-eabi/release/examples/foo",
+//!     "foo",
+//!     ElfOptions::default(),
+//! )
+//! .watch(Duration::from_millis(500), |delta| match delta {
+//!     Ok(delta) => println!("{delta}"),
+//!     Err(error) => eprintln!("{error}"),
+//! });
+//! ```
+//!
+//! A change is only analyzed once the modification time has been the same for
+//! two polls, so that a file that is still being written by the linker is not
+//! read. The summaries of earlier runs are kept by the contents of the file, a
+//! rebuild that produces an elf file that was already analyzed, such as after
+//! touching a source file or switching back to an earlier revision, reuses
+//! the summary instead of running the analysis again.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    fmt,
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+
+use crate::{
+    elf_util::{PathStatus, VisualPathResult},
+    general_assembly::GAError,
+    run_elf::{run_elf_bytes_with_options, ElfOptions},
+};
+
+/// The parts of the results of a run that are compared between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunSummary {
+    /// Number of paths in the run.
+    pub paths: usize,
+
+    /// Number of paths that failed for each reason.
+    pub failures: BTreeMap<String, usize>,
+
+    /// Number of paths that did not run to completion.
+    pub incomplete: usize,
+
+    /// Largest number of cycles of any path.
+    pub max_cycles: usize,
+
+    /// Smallest number of cycles of any path.
+    pub min_cycles: usize,
+}
+
+impl RunSummary {
+    /// Summarizes `results`.
+    pub fn new(results: &[VisualPathResult]) -> Self {
+        let mut failures = BTreeMap::new();
+        let mut incomplete = 0;
+        for result in results {
+            match &result.result {
+                PathStatus::Failed(reason) => {
+                    *failures.entry(reason.error_message.clone()).or_default() += 1
+                }
+                PathStatus::Unknown(_) | PathStatus::Truncated(_) => incomplete += 1,
+                PathStatus::Ok(_) => {}
+            }
+        }
+        let cycles = results.iter().map(|result| result.max_cycles);
+        Self {
+            paths: results.len(),
+            failures,
+            incomplete,
+            max_cycles: cycles.clone().max().unwrap_or(0),
+            min_cycles: cycles.min().unwrap_or(0),
+        }
+    }
+}
+
+/// How the results of a run changed since the previous run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunDelta {
+    /// Summary of the previous run, `None` for the first run.
+    pub previous: Option<RunSummary>,
+
+    /// Summary of this run.
+    pub current: RunSummary,
+
+    /// If the summary was reused from an earlier run of the same file.
+    pub cached: bool,
+}
+
+impl RunDelta {
+    /// Lists the changes since the previous run, or the whole summary of the
+    /// first run.
+    pub fn changes(&self) -> Vec<String> {
+        let current = &self.current;
+        let previous = match &self.previous {
+            Some(previous) => previous,
+            None => {
+                let mut changes = vec![
+                    format!("paths: {}", current.paths),
+                    format!("cycles: {} to {}", current.min_cycles, current.max_cycles),
+                ];
+                if current.incomplete > 0 {
+                    changes.push(format!("incomplete paths: {}", current.incomplete));
+                }
+                for (reason, count) in &current.failures {
+                    changes.push(format!("failure: {reason} ({count} paths)"));
+                }
+                return changes;
+            }
+        };
+
+        let mut changes = vec![];
+        let mut compare = |what: &str, before: usize, after: usize| {
+            if before != after {
+                let diff = after as i64 - before as i64;
+                changes.push(format!("{what}: {before} -> {after} ({diff:+})"));
+            }
+        };
+        compare("paths", previous.paths, current.paths);
+        compare("incomplete paths", previous.incomplete, current.incomplete);
+        compare("max cycles", previous.max_cycles, current.max_cycles);
+        compare("min cycles", previous.min_cycles, current.min_cycles);
+        for (reason, count) in &current.failures {
+            match previous.failures.get(reason) {
+                None => changes.push(format!("new failure: {reason} ({count} paths)")),
+                Some(before) if before != count => {
+                    changes.push(format!("failure: {reason} ({before} -> {count} paths)"))
+                }
+                Some(_) => {}
+            }
+        }
+        for reason in previous.failures.keys() {
+            if !current.failures.contains_key(reason) {
+                changes.push(format!("fixed failure: {reason}"));
+            }
+        }
+        changes
+    }
+}
+
+impl fmt::Display for RunDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let changes = self.changes();
+        match (changes.is_empty(), self.cached) {
+            (true, _) => return writeln!(f, "No changes in the results."),
+            (false, true) => writeln!(f, "Results of an earlier run of the same file:")?,
+            (false, false) => {}
+        }
+        for change in changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the analysis of a function again each time the elf file changes.
+#[derive(Debug)]
+pub struct Watcher {
+    path: PathBuf,
+    function: String,
+    options: ElfOptions,
+    analyzed: Option<SystemTime>,
+    pending: Option<SystemTime>,
+    last: Option<RunSummary>,
+    summaries: HashMap<u64, RunSummary>,
+}
+
+impl Watcher {
+    /// Creates a watcher of `function` in the elf file at `path`, analyzed
+    /// with `options`.
+    pub fn new<P: Into<PathBuf>>(path: P, function: &str, options: ElfOptions) -> Self {
+        Self {
+            path: path.into(),
+            function: function.to_owned(),
+            options,
+            analyzed: None,
+            pending: None,
+            last: None,
+            summaries: HashMap::new(),
+        }
+    }
+
+    /// Checks the elf file, and analyzes it if it changed since the last
+    /// analysis. Returns `None` if there is nothing new to report, such as
+    /// when the file has not changed or does not exist while it is rebuilt.
+    pub fn poll(&mut self) -> Result<Option<RunDelta>, GAError> {
+        let Ok(modified) = fs::metadata(&self.path).and_then(|meta| meta.modified()) else {
+            return Ok(None);
+        };
+        if self.analyzed == Some(modified) {
+            return Ok(None);
+        }
+        // Wait for the file to settle, except for the first run.
+        if self.analyzed.is_some() && self.pending != Some(modified) {
+            self.pending = Some(modified);
+            return Ok(None);
+        }
+        let Ok(contents) = fs::read(&self.path) else {
+            return Ok(None);
+        };
+        self.analyzed = Some(modified);
+        self.pending = None;
+
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        let digest = hasher.finish();
+        let (current, cached) = match self.summaries.get(&digest) {
+            Some(summary) => (summary.clone(), true),
+            None => {
+                let results = run_elf_bytes_with_options(
+                    &contents,
+                    &self.path.display().to_string(),
+                    &self.function,
+                    &self.options,
+                )?;
+                let summary = RunSummary::new(&results);
+                self.summaries.insert(digest, summary.clone());
+                (summary, false)
+            }
+        };
+        let previous = self.last.replace(current.clone());
+        Ok(Some(RunDelta {
+            previous,
+            current,
+            cached,
+        }))
+    }
+
+    /// Polls the elf file every `interval` and passes the result of every
+    /// analysis to `report`, forever.
+    pub fn watch(
+        mut self,
+        interval: Duration,
+        mut report: impl FnMut(Result<RunDelta, GAError>),
+    ) -> ! {
+        loop {
+            match self.poll() {
+                Ok(Some(delta)) => report(Ok(delta)),
+                Ok(None) => {}
+                Err(error) => report(Err(error)),
+            }
+            thread::sleep(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    use super::{RunDelta, RunSummary, Watcher};
+    use crate::run_elf::ElfOptions;
+
+    fn summary(paths: usize, max_cycles: usize, failures: &[(&str, usize)]) -> RunSummary {
+        RunSummary {
+            paths,
+            failures: failures
+                .iter()
+                .map(|(reason, count)| (reason.to_string(), *count))
+                .collect(),
+            incomplete: 0,
+            max_cycles,
+            min_cycles: 10,
+        }
+    }
+
+    #[test]
+    fn test_run_delta() {
+        let first = RunDelta {
+            previous: None,
+            current: summary(3, 100, &[("panic", 1)]),
+            cached: false,
+        };
+        assert_eq!(first.changes(), vec![
+            "paths: 3".to_owned(),
+            "cycles: 10 to 100".to_owned(),
+            "failure: panic (1 paths)".to_owned(),
+        ]);
+
+        let delta = RunDelta {
+            previous: Some(summary(3, 100, &[("panic", 1), ("overflow", 2)])),
+            current: summary(4, 90, &[("panic", 2), ("unreachable", 1)]),
+            cached: false,
+        };
+        assert_eq!(delta.changes(), vec![
+            "paths: 3 -> 4 (+1)".to_owned(),
+            "max cycles: 100 -> 90 (-10)".to_owned(),
+            "failure: panic (1 -> 2 paths)".to_owned(),
+            "new failure: unreachable (1 paths)".to_owned(),
+            "fixed failure: overflow".to_owned(),
+        ]);
+
+        let unchanged = RunDelta {
+            previous: Some(summary(3, 100, &[])),
+            current: summary(3, 100, &[]),
+            cached: true,
+        };
+        assert_eq!(format!("{unchanged}"), "No changes in the results.\n");
+        assert_eq!(RunSummary::new(&[]).failures, BTreeMap::new());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let path = PathBuf::from("target/does-not-exist.elf");
+        let mut watcher = Watcher::new(path, "foo", ElfOptions::default());
+        assert_eq!(watcher.poll().unwrap(), None);
+    }
+}
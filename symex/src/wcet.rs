@@ -0,0 +1,365 @@
+//! Worst case execution time report of a run.
+//!
+//! A [`WcetReport`] aggregates the cycle counts of all paths of a function:
+//! the maximum, minimum and mean number of cycles, the worst path with the
+//! symbolic inputs that lead to it, and the cycles spent in each region marked
+//! in [`RunConfig::regions`](crate::general_assembly::RunConfig::regions).
+//!
+//! ```no_run
+//! # use symex::{run_elf::ElfOptions, wcet};
+//! let report = wcet::analyze(
+//!     "target/thumbv6m-none-eabi/release/examples/foo",
+//!     "foo",
+//!     &ElfOptions::default(),
+//! )
+//! .unwrap();
+//! println!("{report}");
+//! ```
+//!
+//! Paths that were truncated by the
+//! [`budget`](crate::general_assembly::RunConfig::budget) or that the solver
+//! could not decide did not run to completion, a report with such paths only
+//! bounds the worst case from below.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    path::Path,
+};
+
+use colored::Colorize;
+use indenter::indented;
+
+use crate::{
+    elf_util::{PathStatus, Variable, VisualPathResult},
+    general_assembly::GAError,
+    run_elf::{run_elf_with_options, ElfOptions},
+};
+
+/// Runs all paths of `function` in the elf file at `path` with `options` and
+/// reports their cycle counts.
+///
+/// # Panics
+///
+/// This function panics if the specified file does not exist.
+pub fn analyze<P: AsRef<Path>>(
+    path: P,
+    function: &str,
+    options: &ElfOptions,
+) -> Result<WcetReport, GAError> {
+    let results = run_elf_with_options(path, function, options)?;
+    Ok(WcetReport::new(&results))
+}
+
+/// Cycle counts of the paths of a run.
+#[derive(Debug, Clone)]
+pub struct WcetReport {
+    /// Number of paths in the run.
+    pub paths: usize,
+
+    /// Number of paths that did not run to completion, as they were truncated
+    /// or the solver could not decide them.
+    pub incomplete: usize,
+
+    /// Largest number of cycles of any path.
+    pub max_cycles: usize,
+
+    /// Smallest number of cycles of any path.
+    pub min_cycles: usize,
+
+    /// Mean number of cycles of the paths.
+    pub mean_cycles: f64,
+
+    /// The path with the most cycles, the first one if several paths have
+    /// the same number of cycles.
+    pub worst_path: Option<WorstPath>,
+
+    /// Cycles spent in each region, by name.
+    pub regions: Vec<RegionCycles>,
+}
+
+/// The path with the most cycles of a run.
+#[derive(Debug, Clone)]
+pub struct WorstPath {
+    /// Which path this is.
+    pub path: usize,
+
+    /// Number of cycles of the path.
+    pub cycles: usize,
+
+    /// Number of instructions executed on the path.
+    pub instruction_count: usize,
+
+    /// Names of the variables marked as symbolic that the path condition
+    /// depends on.
+    pub constrained: Vec<String>,
+
+    /// Values of the variables marked as symbolic that take the path.
+    pub inputs: Vec<Variable>,
+}
+
+/// Cycles spent in a region on all paths, counted from each time execution
+/// reaches the start of the region to the next time it reaches the end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionCycles {
+    /// Name of the region.
+    pub name: String,
+
+    /// Number of times a path passed through the region.
+    pub passes: usize,
+
+    /// Largest number of cycles of a pass.
+    pub max_cycles: usize,
+
+    /// Smallest number of cycles of a pass.
+    pub min_cycles: usize,
+
+    /// Mean number of cycles of the passes.
+    pub mean_cycles: f64,
+}
+
+impl WcetReport {
+    /// Aggregates the cycle counts of `results`.
+    pub fn new(results: &[VisualPathResult]) -> Self {
+        let cycles = results.iter().map(|result| result.max_cycles);
+        let worst_path = results
+            .iter()
+            .rev()
+            .max_by_key(|result| result.max_cycles)
+            .map(|result| WorstPath {
+                path: result.path,
+                cycles: result.max_cycles,
+                instruction_count: result.instruction_count,
+                constrained: result.constrained.clone(),
+                inputs: result.symbolics.clone(),
+            });
+
+        Self {
+            paths: results.len(),
+            incomplete: results
+                .iter()
+                .filter(|result| {
+                    matches!(
+                        result.result,
+                        PathStatus::Truncated(_) | PathStatus::Unknown(_)
+                    )
+                })
+                .count(),
+            max_cycles: cycles.clone().max().unwrap_or(0),
+            min_cycles: cycles.clone().min().unwrap_or(0),
+            mean_cycles: mean(cycles),
+            worst_path,
+            regions: region_cycles(results),
+        }
+    }
+
+    /// Checks if the maximum number of cycles is only a lower bound of the
+    /// worst case, as some paths did not run to completion.
+    pub fn is_lower_bound(&self) -> bool {
+        self.incomplete > 0
+    }
+}
+
+/// Mean of `values`, zero if there are none.
+fn mean(values: impl Iterator<Item = usize>) -> f64 {
+    let (count, sum) = values.fold((0, 0), |(count, sum), value| (count + 1, sum + value));
+    match count {
+        0 => 0.0,
+        count => sum as f64 / count as f64,
+    }
+}
+
+/// Collects the cycles of each pass through a region on the paths of
+/// `results`, the laps of a region alternate between its start and its end.
+fn region_cycles(results: &[VisualPathResult]) -> Vec<RegionCycles> {
+    let mut passes: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for result in results {
+        let mut started: BTreeMap<&str, usize> = BTreeMap::new();
+        for (cycles, name) in &result.cycle_laps {
+            match started.remove(name.as_str()) {
+                Some(start) => passes
+                    .entry(name)
+                    .or_default()
+                    .push(cycles.saturating_sub(start)),
+                None => {
+                    started.insert(name, *cycles);
+                }
+            }
+        }
+    }
+
+    passes
+        .into_iter()
+        .map(|(name, passes)| RegionCycles {
+            name: name.to_owned(),
+            passes: passes.len(),
+            max_cycles: passes.iter().copied().max().unwrap_or(0),
+            min_cycles: passes.iter().copied().min().unwrap_or(0),
+            mean_cycles: mean(passes.into_iter()),
+        })
+        .collect()
+}
+
+impl fmt::Display for WcetReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━ WCET ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━"
+        )?;
+        writeln!(f, "Paths: {}", self.paths)?;
+        if self.is_lower_bound() {
+            writeln!(
+                f,
+                "{}: {} paths did not run to completion, the worst case may be larger",
+                "Incomplete".yellow(),
+                self.incomplete
+            )?;
+        }
+        writeln!(f, "Max cycles: {}", self.max_cycles)?;
+        writeln!(f, "Min cycles: {}", self.min_cycles)?;
+        writeln!(f, "Mean cycles: {:.1}", self.mean_cycles)?;
+
+        if let Some(worst) = &self.worst_path {
+            writeln!(
+                f,
+                "\nWorst path: {} ({} cycles, {} instructions)",
+                worst.path, worst.cycles, worst.instruction_count
+            )?;
+            if !worst.constrained.is_empty() {
+                writeln!(
+                    indented(f),
+                    "constrained by: {}",
+                    worst.constrained.join(", ")
+                )?;
+            }
+            for input in &worst.inputs {
+                let name = input.name.as_deref().unwrap_or("_");
+                writeln!(indented(f), "{name}: {input}")?;
+            }
+        }
+
+        if !self.regions.is_empty() {
+            writeln!(f, "\nRegions:")?;
+            for region in &self.regions {
+                writeln!(
+                    indented(f),
+                    "{}: {} passes, max {}, min {}, mean {:.1} cycles",
+                    region.name,
+                    region.passes,
+                    region.max_cycles,
+                    region.min_cycles,
+                    region.mean_cycles
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{RegionCycles, WcetReport};
+    use crate::{
+        elf_util::{PathStatus, VisualPathResult},
+        general_assembly::{budget::Truncation, path_data::PathData},
+    };
+
+    fn result(
+        path: usize,
+        result: PathStatus,
+        max_cycles: usize,
+        cycle_laps: &[(usize, &str)],
+    ) -> VisualPathResult {
+        VisualPathResult {
+            path,
+            result,
+            symbolics: vec![],
+            constrained: vec![format!("x{path}")],
+            input_ranges: vec![],
+            end_state: vec![],
+            register_roles: HashMap::new(),
+            return_value: None,
+            instruction_count: max_cycles / 2,
+            max_cycles,
+            energy: None,
+            instruction_cache: None,
+            data_cache: None,
+            branch_predictions: None,
+            cycle_laps: cycle_laps
+                .iter()
+                .map(|(cycles, name)| (*cycles, name.to_string()))
+                .collect(),
+            reached: vec![],
+            initial_sp: 0,
+            memory_delta: vec![],
+            register_history: vec![],
+            mmio_log: vec![],
+            slice: None,
+            provenance: vec![],
+            filtered_calls: vec![],
+            under_approximated: vec![],
+            interrupts: vec![],
+            atomicity_violation: None,
+            reentry: None,
+            nvic: vec![],
+            peripherals: vec![],
+            path_data: PathData::default(),
+            constraints: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_wcet_report() {
+        let results = vec![
+            result(1, PathStatus::Ok(None), 100, &[(10, "isr"), (40, "isr")]),
+            result(2, PathStatus::Ok(None), 300, &[
+                (10, "isr"),
+                (60, "isr"),
+                (100, "isr"),
+                (110, "isr"),
+            ]),
+            result(3, PathStatus::Ok(None), 300, &[(0, "init")]),
+            result(4, PathStatus::Ok(None), 50, &[]),
+        ];
+        let report = WcetReport::new(&results);
+        assert_eq!(report.paths, 4);
+        assert!(!report.is_lower_bound());
+        assert_eq!(report.max_cycles, 300);
+        assert_eq!(report.min_cycles, 50);
+        assert_eq!(report.mean_cycles, 187.5);
+
+        let worst = report.worst_path.unwrap();
+        assert_eq!(worst.path, 2);
+        assert_eq!(worst.instruction_count, 150);
+        assert_eq!(worst.constrained, vec!["x2".to_owned()]);
+
+        // A region that is never left has no passes.
+        assert_eq!(report.regions, vec![RegionCycles {
+            name: "isr".to_owned(),
+            passes: 3,
+            max_cycles: 50,
+            min_cycles: 10,
+            mean_cycles: 30.0,
+        }]);
+    }
+
+    #[test]
+    fn test_incomplete_report() {
+        let truncated = PathStatus::Truncated(Truncation::Instructions(1000));
+        let results = vec![
+            result(1, PathStatus::Ok(None), 100, &[]),
+            result(2, truncated, 2000, &[]),
+            result(3, PathStatus::Unknown("x".to_owned()), 10, &[]),
+        ];
+        let report = WcetReport::new(&results);
+        assert_eq!(report.incomplete, 2);
+        assert!(report.is_lower_bound());
+        assert_eq!(report.max_cycles, 2000);
+
+        let report = WcetReport::new(&[]);
+        assert_eq!((report.max_cycles, report.mean_cycles), (0, 0.0));
+        assert!(report.worst_path.is_none());
+    }
+}
@@ -99,15 +99,11 @@ fn main() {
 
     // create a run configuration with the hooks associated with the correct addresses.
     let config = RunConfig {
-        pc_hooks: vec![],
-        register_read_hooks: vec![],
-        register_write_hooks: vec![],
         memory_write_hooks: vec![
             (MemoryHookAddress::Single(0xe000e100), unlock_hook),
             (MemoryHookAddress::Single(0xe000e180), lock_hook),
         ],
-        memory_read_hooks: vec![],
-        show_path_results: false,
+        ..RunConfig::default()
     };
 
     // run the symbolic execution